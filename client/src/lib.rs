@@ -0,0 +1,292 @@
+//! Async client for the Isaac Four Souls server. Sends `ClientMessage`s and hands back
+//! `ServerResponse`s, both the server's own types, so a protocol change that breaks the
+//! client breaks its build. Every message is sent in the reliable envelope: the server acks
+//! it, and anything not acked in time is sent again. Reliable messages from the server are
+//! acked and handed out in sequence order.
+//!
+//! ```no_run
+//! # async fn run() -> isaac_four_souls_client::ClientResult<()> {
+//! use isaac_four_souls_client::Client;
+//!
+//! let mut client = Client::connect("ws://127.0.0.1:8080").await?;
+//! let seat = client.create_room("Basement", "Isaac", Default::default()).await?;
+//! client.ready().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use futures_util::{SinkExt, StreamExt};
+use isaac_four_souls::actors::connection_actor::{ReliableClientMessage, ReliableServerResponse};
+use isaac_four_souls::network::compression::gunzip;
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls::network::reliable_messaging::{
+    create_reliable_message, MessageReceiver, ReliableMessage, ResendQueue,
+};
+use isaac_four_souls::network::room::RoomOptions;
+use tokio::net::TcpStream;
+use tokio::time::{sleep_until, Instant};
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("WebSocket error: {0}")]
+    WebSocket(#[from] tungstenite::Error),
+
+    #[error("Unreadable server message: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
+
+    #[error("Server refused the request ({error_type}): {message}")]
+    Server { error_type: String, message: String },
+
+    #[error("Message {sequence} was never acked by the server")]
+    DeliveryFailed { sequence: u64 },
+
+    #[error("Undecodable compressed frame: {0}")]
+    Compression(String),
+
+    #[error("The server closed the connection")]
+    Closed,
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+/// Where the server seated this client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seat {
+    pub room_id: String,
+    pub player_id: String,
+}
+
+pub struct Client {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    connection_id: String,
+    next_sequence: u64,
+    resend_queue: ResendQueue,
+    receiver: MessageReceiver,
+    inbox: VecDeque<ServerResponse>, // received but not handed out yet
+}
+
+impl Client {
+    /// Connect and wait for the server to assign a connection id. Auth tokens, the locale
+    /// and compression go in the query string, e.g. `ws://host:8080/?token=...&lang=fr` or
+    /// `?compress=gzip`. Plain `ws://` only, the client is built without TLS.
+    pub async fn connect(url: &str) -> ClientResult<Self> {
+        let (socket, _) = connect_async(url).await?;
+        let mut client = Self {
+            socket,
+            connection_id: String::new(),
+            next_sequence: 1,
+            resend_queue: ResendQueue::new(),
+            receiver: MessageReceiver::new(),
+            inbox: VecDeque::new(),
+        };
+        let response = client
+            .wait_for(|response| matches!(response, ServerResponse::ConnectionId { .. }))
+            .await?;
+        if let ServerResponse::ConnectionId { connection_id } = response {
+            client.connection_id = connection_id;
+        }
+        Ok(client)
+    }
+
+    pub fn connection_id(&self) -> &str {
+        &self.connection_id
+    }
+
+    /// Send a message without waiting for anything back
+    pub async fn send(&mut self, message: ClientMessage) -> ClientResult<()> {
+        let payload = serde_json::to_string(&message)?;
+        let message = create_reliable_message(self.next_sequence, payload);
+        self.next_sequence += 1;
+        self.send_reliable(message.clone()).await?;
+        self.resend_queue.track(message);
+        Ok(())
+    }
+
+    /// The next response from the server, in the order it was sent
+    pub async fn recv(&mut self) -> ClientResult<ServerResponse> {
+        loop {
+            if let Some(response) = self.inbox.pop_front() {
+                return Ok(response);
+            }
+            self.read_frame().await?;
+        }
+    }
+
+    /// Wait for the first response `pick` accepts and take it out of the queue; everything
+    /// else stays queued for `recv`. An error response from the server ends the wait.
+    pub async fn wait_for(
+        &mut self,
+        mut pick: impl FnMut(&ServerResponse) -> bool,
+    ) -> ClientResult<ServerResponse> {
+        // Errors queued before the wait began answered something else
+        let queued_before = self.inbox.len();
+        let mut checked = 0;
+        loop {
+            // One frame can release several reliable messages at once
+            while checked < self.inbox.len() {
+                let response = &self.inbox[checked];
+                if pick(response) {
+                    return Ok(self.inbox.remove(checked).expect("Index is in bounds"));
+                }
+                if checked >= queued_before {
+                    if let ServerResponse::Error {
+                        error_type,
+                        message,
+                        ..
+                    } = response
+                    {
+                        let error = ClientError::Server {
+                            error_type: error_type.clone(),
+                            message: message.clone(),
+                        };
+                        self.inbox.remove(checked);
+                        return Err(error);
+                    }
+                }
+                checked += 1;
+            }
+            self.read_frame().await?;
+        }
+    }
+
+    pub async fn create_room(
+        &mut self,
+        room_name: &str,
+        player_name: &str,
+        options: RoomOptions,
+    ) -> ClientResult<Seat> {
+        self.send(ClientMessage::CreateRoom {
+            room_name: room_name.to_string(),
+            first_player_name: player_name.to_string(),
+            options,
+        })
+        .await?;
+        match self
+            .wait_for(|response| matches!(response, ServerResponse::RoomCreated { .. }))
+            .await?
+        {
+            ServerResponse::RoomCreated { room_id, player_id } => Ok(Seat { room_id, player_id }),
+            _ => unreachable!("wait_for only returns RoomCreated"),
+        }
+    }
+
+    pub async fn join(&mut self, room_id: &str, player_name: &str) -> ClientResult<Seat> {
+        self.send(ClientMessage::JoinRoom {
+            player_name: player_name.to_string(),
+            room_id: room_id.to_string(),
+        })
+        .await?;
+        match self
+            .wait_for(|response| matches!(response, ServerResponse::SelfJoined { .. }))
+            .await?
+        {
+            ServerResponse::SelfJoined { player_id, .. } => Ok(Seat {
+                room_id: room_id.to_string(),
+                player_id,
+            }),
+            _ => unreachable!("wait_for only returns SelfJoined"),
+        }
+    }
+
+    /// Mark this player ready. The room always gets the new `PlayersReady`; once everyone
+    /// seated is ready and the room has enough players, a countdown or the game start follows.
+    pub async fn ready(&mut self) -> ClientResult<()> {
+        self.send(ClientMessage::PlayerReady).await
+    }
+
+    /// Play a loot card from hand and wait for the server to put it on the stack
+    pub async fn play_card(&mut self, card_id: &str) -> ClientResult<ServerResponse> {
+        self.send(ClientMessage::PlayLootCard {
+            card_id: card_id.to_string(),
+            tap_character: false,
+        })
+        .await?;
+        self.wait_for(|response| matches!(response, ServerResponse::LootPlayed { .. }))
+            .await
+    }
+
+    pub async fn close(mut self) -> ClientResult<()> {
+        self.socket.close(None).await?;
+        Ok(())
+    }
+
+    async fn send_reliable(&mut self, message: ReliableMessage) -> ClientResult<()> {
+        self.send_frame(&ReliableClientMessage::Reliable(message))
+            .await
+    }
+
+    async fn send_frame(&mut self, frame: &ReliableClientMessage) -> ClientResult<()> {
+        let text = serde_json::to_string(frame)?;
+        self.socket.send(Message::Text(text)).await?;
+        Ok(())
+    }
+
+    /// Read one frame into the inbox, resending unacked messages while waiting for it
+    async fn read_frame(&mut self) -> ClientResult<()> {
+        let frame = loop {
+            let next_retry = self.resend_queue.next_retry();
+            tokio::select! {
+                frame = self.socket.next() => break frame,
+                _ = sleep_until(next_retry.unwrap_or_else(Instant::now)), if next_retry.is_some() => {
+                    self.retransmit_due().await?;
+                }
+            }
+        };
+        let text = match frame {
+            Some(Ok(Message::Text(text))) => text,
+            // Only sent to clients that connected with compress=gzip
+            Some(Ok(Message::Binary(bytes))) => {
+                gunzip(&bytes).map_err(|e| ClientError::Compression(e.to_string()))?
+            }
+            Some(Ok(Message::Close(_))) | None => return Err(ClientError::Closed),
+            Some(Ok(_)) => return Ok(()), // pings are answered by tungstenite
+            Some(Err(e)) => return Err(e.into()),
+        };
+
+        // Reliable frames wrap a ServerResponse, anything else is a bare one
+        match serde_json::from_str::<ReliableServerResponse>(&text) {
+            Ok(ReliableServerResponse::Reliable(message)) => {
+                // Only a receiver with a window refuses messages, and this one has none
+                let Ok((ack, ordered_messages)) = self.receiver.receive_message(message) else {
+                    return Ok(());
+                };
+                self.send_frame(&ReliableClientMessage::Ack(ack)).await?;
+                self.queue(ordered_messages)?;
+            }
+            Ok(ReliableServerResponse::Ack(ack)) => {
+                self.resend_queue.ack(&ack.message_id);
+            }
+            Ok(ReliableServerResponse::DeliveryFailed(failed)) => {
+                let released = self.receiver.skip(failed.sequence);
+                self.queue(released)?;
+            }
+            Err(_) => self.inbox.push_back(serde_json::from_str(&text)?),
+        }
+        Ok(())
+    }
+
+    fn queue(&mut self, messages: Vec<ReliableMessage>) -> serde_json::Result<()> {
+        for message in messages {
+            self.inbox
+                .push_back(serde_json::from_str(&message.payload)?);
+        }
+        Ok(())
+    }
+
+    async fn retransmit_due(&mut self) -> ClientResult<()> {
+        let (resend, failed) = self.resend_queue.take_due(Instant::now());
+        if let Some(pending) = failed.into_iter().next() {
+            return Err(ClientError::DeliveryFailed {
+                sequence: pending.message.sequence,
+            });
+        }
+        for message in resend {
+            self.send_reliable(message).await?;
+        }
+        Ok(())
+    }
+}
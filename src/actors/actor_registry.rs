@@ -1,43 +1,186 @@
 use dashmap::DashMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
 
 use crate::actors::connection_actor::ConnectionMessage;
-use crate::actors::game_actor::{GameActor, GameMessage};
+use crate::actors::game_actor::{GameActor, GameMessage, GameServices};
 use crate::actors::lobby_actor::LobbyMessage;
-use crate::{AppError, AppResult, ConnectionCommand, TurnOrder};
+use crate::actors::lobby_shards::LobbyShards;
+use crate::actors::mailbox::Mailbox;
+use crate::actors::tournament_actor::TournamentMessage;
+use crate::config::MailboxConfig;
+use crate::game::ratings::Ratings;
+use crate::game::stats::GameSummaries;
+use crate::game::turn_order::GameOpening;
+use crate::network::locale::Locale;
+use crate::network::messages::{KickReason, ServerResponse};
+use crate::network::messenger::Messenger;
+use crate::network::metrics::{LiveGauges, ServerMetrics};
+use crate::network::permissions::Role;
+use crate::network::profiles::PlayerProfile;
+use crate::network::room::RoomOptions;
+use crate::network::webhooks::Webhooks;
+use crate::{AppError, AppResult};
+
+// A game whose actor panics this many times is abandoned and its room sent back to the lobby
+const MAX_GAME_RESTARTS: u32 = 3;
 
 pub struct ActorRegistry {
-    lobby_sender: mpsc::UnboundedSender<LobbyMessage>,
-    game_actors: DashMap<String, mpsc::UnboundedSender<GameMessage>>, // game_id -> sender
-    connection_actors: DashMap<String, mpsc::UnboundedSender<ConnectionMessage>>, // connection_id -> sender
-    connection_to_game_mapping: DashMap<String, String>,
+    lobby: LobbyShards,
+    tournament_sender: Mailbox<TournamentMessage>,
+    game_actors: DashMap<String, Mailbox<GameMessage>>, // game_id -> mailbox
+    game_tasks: DashMap<String, JoinHandle<()>>,        // game_id -> running actor task
+    connection_actors: DashMap<String, Mailbox<ConnectionMessage>>, // connection_id -> mailbox
+    connection_to_game_mapping: DashMap<String, HashSet<String>>, // connection_id -> game ids
+    game_sessions: DashMap<String, (String, String)>,   // session token -> (game_id, player_id)
+    services: GameServices,
+    mailboxes: MailboxConfig,
+    messenger: Messenger,
 }
 
 impl ActorRegistry {
-    pub fn new(lobby_sender: mpsc::UnboundedSender<LobbyMessage>) -> Self {
+    pub fn new(
+        lobby: LobbyShards,
+        tournament_sender: mpsc::Sender<TournamentMessage>,
+        services: GameServices,
+        mailboxes: MailboxConfig,
+        messenger: Messenger,
+    ) -> Self {
         Self {
-            lobby_sender,
+            lobby,
+            tournament_sender: Mailbox::new(tournament_sender),
             game_actors: DashMap::new(),
+            game_tasks: DashMap::new(),
             connection_to_game_mapping: DashMap::new(),
             connection_actors: DashMap::new(),
+            game_sessions: DashMap::new(),
+            services,
+            mailboxes,
+            messenger,
         }
     }
 
+    pub fn mailboxes(&self) -> &MailboxConfig {
+        &self.mailboxes
+    }
+
+    pub fn messenger(&self) -> &Messenger {
+        &self.messenger
+    }
+
+    pub fn broadcast_to_room(&self, room_id: &str, response: ServerResponse) -> AppResult<()> {
+        self.messenger.broadcast_to_room(room_id, response)
+    }
+
+    pub fn send_to_player_id(&self, player_id: &str, response: ServerResponse) -> AppResult<()> {
+        self.messenger.send_to_player_id(player_id, response)
+    }
+
+    pub fn metrics(&self) -> &ServerMetrics {
+        &self.services.metrics
+    }
+
+    /// The server metrics plus what only the registry can count, for `/metrics`
+    pub fn render_metrics(&self) -> String {
+        self.services.metrics.render_prometheus(LiveGauges {
+            games_running: self.game_actors.len(),
+            connections: self.connection_actors.len(),
+        })
+    }
+
+    /// Games with a running actor, including ones restored on start
+    pub fn game_count(&self) -> usize {
+        self.game_actors.len()
+    }
+
+    pub fn has_game(&self, game_id: &str) -> bool {
+        self.game_actors.contains_key(game_id)
+    }
+
+    pub fn ratings(&self) -> &Arc<Ratings> {
+        &self.services.ratings
+    }
+
+    pub fn game_summaries(&self) -> &Arc<GameSummaries> {
+        &self.services.summaries
+    }
+
+    pub fn webhooks(&self) -> &Arc<Webhooks> {
+        &self.services.webhooks
+    }
+
+    pub fn lobby(&self) -> &LobbyShards {
+        &self.lobby
+    }
+
+    /// Shed with ServerBusy when the lobby can't keep up; for requests coming from clients
     pub fn send_lobby_message(&self, message: LobbyMessage) -> AppResult<()> {
-        self.lobby_sender
-            .send(message)
-            .map_err(|_| AppError::Internal {
-                message: "Failed to send message to lobby actor".to_string(),
+        // A resumed seat is in the game's room, wherever that lives
+        if let LobbyMessage::ResumeSession {
+            connection_id,
+            session_token,
+        } = message.inner()
+        {
+            if let Some(session) = self.game_sessions.get(session_token.expose()) {
+                self.lobby
+                    .directory()
+                    .expect_seat(connection_id, &session.0);
+            }
+        }
+        self.lobby.send(message)
+    }
+
+    /// For messages the lobby must see even under load, like connections opening and closing
+    pub fn deliver_lobby_message(&self, message: LobbyMessage) -> AppResult<()> {
+        self.lobby.deliver(message)
+    }
+
+    /// Shed with ServerBusy like lobby requests
+    pub fn send_tournament_message(&self, message: TournamentMessage) -> AppResult<()> {
+        self.tournament_sender
+            .try_send(message)
+            .map_err(|e| match e {
+                TrySendError::Full(_) => AppError::ServerBusy {
+                    mailbox: "tournament".to_string(),
+                },
+                TrySendError::Closed(_) => AppError::Internal {
+                    message: "Failed to send message to tournament actor".to_string(),
+                },
+            })
+    }
+
+    /// For match results, which a bracket can't do without
+    pub fn deliver_tournament_message(&self, message: TournamentMessage) -> AppResult<()> {
+        if self.tournament_sender.deliver(message) {
+            Ok(())
+        } else {
+            Err(AppError::Internal {
+                message: "Failed to send message to tournament actor".to_string(),
             })
+        }
     }
 
     pub fn register_connection_actor(
         &self,
         connection_id: String,
-        sender: mpsc::UnboundedSender<ConnectionMessage>,
+        sender: mpsc::Sender<ConnectionMessage>,
     ) {
-        self.connection_actors.insert(connection_id, sender);
+        self.connection_actors
+            .insert(connection_id, Mailbox::new(sender));
+    }
+
+    pub fn set_locale(&self, connection_id: &str, locale: Locale) {
+        self.services.locales.set(connection_id, locale);
+    }
+
+    /// Language to answer a connection in, negotiated in its handshake
+    pub fn locale(&self, connection_id: &str) -> Locale {
+        self.services.locales.get(connection_id)
     }
 
     pub fn send_to_connection_actor(
@@ -52,11 +195,13 @@ impl ActorRegistry {
                     connection_id: connection_id.to_string(),
                 })?;
 
-        sender
-            .send(message)
-            .map_err(|_| AppError::MessageSendFailed {
+        if sender.deliver(message) {
+            Ok(())
+        } else {
+            Err(AppError::MessageSendFailed {
                 connection_id: connection_id.to_string(),
             })
+        }
     }
 
     pub fn disconnect_connection_actor(&self, connection_id: &str) -> AppResult<()> {
@@ -64,43 +209,215 @@ impl ActorRegistry {
     }
 
     pub fn start_game_actor(
-        &self,
+        self: &Arc<Self>,
         game_id: String,
         players_id_to_connection_id: HashMap<String, String>,
-        cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
-    ) -> AppResult<TurnOrder> {
-        let turn_order = TurnOrder::new(players_id_to_connection_id.keys().cloned().collect());
-
-        let (game_sender, game_receiver) = mpsc::unbounded_channel::<GameMessage>();
+        rated_players: HashMap<String, PlayerProfile>,
+        options: RoomOptions,
+        player_names: HashMap<String, String>,
+    ) -> AppResult<GameOpening> {
+        let mut opening = GameOpening::new(
+            players_id_to_connection_id.keys().cloned().collect(),
+            rand::random(),
+            self.services.game_config.roll_for_first_player,
+        );
+        opening.options = options;
+        opening.player_names = player_names;
 
         // Store connection -> game mapping
-        for (_, connection_id) in &players_id_to_connection_id {
-            self.connection_to_game_mapping
-                .insert(connection_id.clone(), game_id.clone());
-
-            if let Some(_) = self.connection_actors.get(connection_id) {
-                // We can't directly call methods on the connection actor, but we could
-                // send a state transition message if we had that message type
-                // For now, we'll handle this through the connection actor's internal logic
-            }
+        for connection_id in players_id_to_connection_id.values() {
+            self.subscribe(connection_id, &game_id);
         }
 
-        let mut game_actor = GameActor::new(
+        // Each seat gets a token the player can use to take it back from a new connection
+        let sessions: HashMap<String, String> = players_id_to_connection_id
+            .keys()
+            .map(|player_id| (Uuid::new_v4().to_string(), player_id.clone()))
+            .collect();
+        for (session_token, player_id) in &sessions {
+            self.game_sessions
+                .insert(session_token.clone(), (game_id.clone(), player_id.clone()));
+        }
+
+        let game_actor = GameActor::new(
             game_id.clone(),
             players_id_to_connection_id,
-            turn_order.clone(),
-            cmd_sender.clone(),
+            opening.clone(),
+            sessions,
+            rated_players,
+            self.services.clone(),
+            self.messenger.for_game(&game_id),
         );
+        opening.card_set_version = game_actor.card_set_version().to_string();
+        self.spawn_game_actor(game_id, game_actor);
+
+        Ok(opening)
+    }
+
+    /// Bring back every game left in the store by a previous run
+    pub fn restore_games(self: &Arc<Self>) {
+        let Some(game_store) = &self.services.game_store else {
+            return;
+        };
+        let saved_games = match game_store.load_all() {
+            Ok(saved_games) => saved_games,
+            Err(e) => {
+                eprintln!("❌ Failed to load saved games: {:?}", e);
+                return;
+            }
+        };
+
+        for saved_game in saved_games {
+            let game_id = saved_game.game_id.clone();
+            let sessions = saved_game.sessions.clone();
+            // Left in the store for whoever looks into it
+            let game_actor = match GameActor::restore(
+                saved_game,
+                self.services.clone(),
+                self.messenger.for_game(&game_id),
+            ) {
+                Ok(game_actor) => game_actor,
+                Err(e) => {
+                    eprintln!("❌ Not restoring game {}: {}", game_id, e);
+                    continue;
+                }
+            };
+            for (session_token, player_id) in sessions {
+                self.game_sessions
+                    .insert(session_token, (game_id.clone(), player_id));
+            }
+            println!("♻️ Restored game {}", game_id);
+            self.spawn_game_actor(game_id, game_actor);
+        }
+    }
+
+    /// Run a game actor under a supervisor that restarts it from its last snapshot if it panics
+    fn spawn_game_actor(self: &Arc<Self>, game_id: String, game_actor: GameActor) {
+        let registry = Arc::clone(self);
+        let supervised_game_id = game_id.clone();
+        let mut game_actor = game_actor;
+        let mut game_receiver = self.register_game_sender(&game_id);
 
-        // Store the sender for routing messages
-        self.game_actors.insert(game_id.clone(), game_sender);
+        let game_task = tokio::spawn(async move {
+            let game_id = supervised_game_id;
+            let mut crashes = 0;
+            loop {
+                let actor_task = tokio::spawn(async move {
+                    game_actor.run(game_receiver).await;
+                });
+                match actor_task.await {
+                    Err(e) if e.is_panic() => crashes += 1,
+                    // Finished, or aborted on purpose
+                    _ => break,
+                }
 
-        // Spawn the game actor task
-        tokio::spawn(async move {
-            game_actor.run(game_receiver).await;
+                let snapshot = registry.services.snapshots.get(&game_id);
+                let restarting = crashes <= MAX_GAME_RESTARTS && snapshot.is_some();
+                eprintln!(
+                    "💥 Game actor {} crashed {} time(s), {}",
+                    game_id,
+                    crashes,
+                    if restarting {
+                        "restarting from its last snapshot"
+                    } else {
+                        "giving up"
+                    }
+                );
+                let _ = registry.broadcast_to_room(
+                    &game_id,
+                    ServerResponse::GameCrashed {
+                        game_id: game_id.clone(),
+                        restarting,
+                    },
+                );
+
+                let Some(snapshot) = snapshot.filter(|_| restarting) else {
+                    registry.abandon_game(&game_id);
+                    break;
+                };
+                game_actor = match GameActor::restore(
+                    snapshot.saved_game,
+                    registry.services.clone(),
+                    registry.messenger.for_game(&game_id),
+                ) {
+                    Ok(game_actor) => game_actor,
+                    Err(e) => {
+                        eprintln!("❌ Can't restart game {}: {}", game_id, e);
+                        registry.abandon_game(&game_id);
+                        break;
+                    }
+                };
+                game_receiver = registry.register_game_sender(&game_id);
+                // Put everyone back in their seat, as if they had resumed their session
+                if let Some(game_sender) = registry.game_actors.get(&game_id) {
+                    for (player_id, connection_id) in snapshot.players_id_to_connection_id {
+                        game_sender.deliver(GameMessage::Reconnect {
+                            connection_id,
+                            player_id,
+                        });
+                    }
+                }
+            }
         });
+        self.game_tasks.insert(game_id, game_task);
+    }
 
-        Ok(turn_order)
+    /// Open a fresh channel for a game's actor and route its messages there
+    fn register_game_sender(&self, game_id: &str) -> mpsc::Receiver<GameMessage> {
+        let (game_sender, game_receiver) = mpsc::channel::<GameMessage>(self.mailboxes.game);
+        self.game_actors
+            .insert(game_id.to_string(), Mailbox::new(game_sender));
+        game_receiver
+    }
+
+    /// Drop a game that keeps crashing, including its saves so it isn't restored on the next start
+    fn abandon_game(&self, game_id: &str) {
+        self.services.snapshots.remove(game_id);
+        if let Some(game_store) = &self.services.game_store {
+            if let Err(e) = game_store.remove(game_id) {
+                eprintln!("Failed to remove saved game {}: {:?}", game_id, e);
+            }
+        }
+        // The lobby cleans up the actor and returns the room's players to it
+        let _ = self.deliver_lobby_message(LobbyMessage::GameEnded {
+            game_id: game_id.to_string(),
+            winner_id: None,
+        });
+    }
+
+    /// Hand a player's seat to `connection_id`. Returns the game and player it resumed.
+    pub fn resume_session(
+        &self,
+        session_token: &str,
+        connection_id: &str,
+    ) -> AppResult<(String, String)> {
+        let (game_id, player_id) = self
+            .game_sessions
+            .get(session_token)
+            .map(|entry| entry.value().clone())
+            .ok_or(AppError::SessionNotFound)?;
+
+        let game_sender = self
+            .game_actors
+            .get(&game_id)
+            .ok_or(AppError::SessionNotFound)?
+            .clone();
+
+        self.subscribe(connection_id, &game_id);
+        self.notify_connection_game_start(connection_id, game_id.clone(), player_id.clone())?;
+        self.notify_connection_role(connection_id, Role::Player)?;
+
+        let reconnect = GameMessage::Reconnect {
+            connection_id: connection_id.to_string(),
+            player_id: player_id.clone(),
+        };
+        if !game_sender.deliver(reconnect) {
+            return Err(AppError::GameEventSendFailed {
+                reason: "Game actor receiver closed".to_string(),
+            });
+        }
+
+        Ok((game_id, player_id))
     }
 
     pub fn notify_connection_game_start(
@@ -115,19 +432,58 @@ impl ActorRegistry {
         self.send_to_connection_actor(connection_id, message)
     }
 
-    pub fn notify_connection_lobby_return(&self, connection_id: &str) -> AppResult<()> {
+    pub fn notify_connection_role(&self, connection_id: &str, role: Role) -> AppResult<()> {
+        self.send_to_connection_actor(connection_id, ConnectionMessage::SetRole { role })
+    }
+
+    pub fn notify_connection_lobby_return(
+        &self,
+        connection_id: &str,
+        game_id: String,
+    ) -> AppResult<()> {
         use crate::actors::connection_actor::ConnectionMessage;
 
-        let message = ConnectionMessage::TransitionToLobby;
+        let message = ConnectionMessage::TransitionToLobby { game_id };
         self.send_to_connection_actor(connection_id, message)
     }
 
-    pub fn send_game_message(&self, connection_id: &str, message: GameMessage) -> AppResult<()> {
-        let game_id = self
+    pub fn notify_connection_kicked(
+        &self,
+        connection_id: &str,
+        reason: KickReason,
+    ) -> AppResult<()> {
+        self.send_to_connection_actor(connection_id, ConnectionMessage::Kicked { reason })
+    }
+
+    fn subscribe(&self, connection_id: &str, game_id: &str) {
+        self.connection_to_game_mapping
+            .entry(connection_id.to_string())
+            .or_default()
+            .insert(game_id.to_string());
+    }
+
+    /// Send to one of the connection's games: `game_id`, or without it the only one it is in
+    pub fn send_game_message(
+        &self,
+        connection_id: &str,
+        game_id: Option<&str>,
+        message: GameMessage,
+    ) -> AppResult<()> {
+        let games = self
             .connection_to_game_mapping
             .get(connection_id)
-            .ok_or_else(|| AppError::ConnectionNotInRoom)?
-            .clone();
+            .ok_or(AppError::ConnectionNotInRoom)?;
+        let game_id = match game_id {
+            Some(game_id) if games.contains(game_id) => game_id.to_string(),
+            Some(game_id) => {
+                return Err(AppError::GameNotFound {
+                    game_id: game_id.to_string(),
+                })
+            }
+            None if games.len() == 1 => games.iter().next().cloned().unwrap_or_default(),
+            None => return Err(AppError::GameIdRequired { games: games.len() }),
+        };
+        drop(games);
 
         println!(
             "🎯 Routing game message from connection {} to game {}: {:?}",
@@ -141,11 +497,33 @@ impl ActorRegistry {
                     room_id: game_id.clone(),
                 })?;
 
-        game_sender
-            .send(message)
-            .map_err(|_| AppError::GameEventSendFailed {
+        game_sender.try_send(message).map_err(|e| match e {
+            TrySendError::Full(_) => AppError::ServerBusy {
+                mailbox: "game".to_string(),
+            },
+            TrySendError::Closed(_) => AppError::GameEventSendFailed {
                 reason: "Game actor receiver closed".to_string(),
-            })
+            },
+        })
+    }
+
+    /// Reach a game by id, for requests that don't come from one of its players
+    pub fn send_to_game(&self, game_id: &str, message: GameMessage) -> AppResult<()> {
+        let game_sender = self
+            .game_actors
+            .get(game_id)
+            .ok_or_else(|| AppError::GameNotFound {
+                game_id: game_id.to_string(),
+            })?;
+
+        game_sender.try_send(message).map_err(|e| match e {
+            TrySendError::Full(_) => AppError::ServerBusy {
+                mailbox: "game".to_string(),
+            },
+            TrySendError::Closed(_) => AppError::GameEventSendFailed {
+                reason: "Game actor receiver closed".to_string(),
+            },
+        })
     }
 
     pub fn cleanup_game_actor(&self, game_id: &str) -> AppResult<()> {
@@ -159,42 +537,58 @@ impl ActorRegistry {
         if let Some((_, sender)) = self.game_actors.remove(game_id) {
             drop(sender); // This will close the channel and stop the actor
         }
+        self.game_tasks.remove(game_id);
+        self.game_sessions
+            .retain(|_, (session_game_id, _)| session_game_id != game_id);
 
         // Remove connection mappings for this game
-        self.connection_to_game_mapping
-            .retain(|_, mapped_game_id| mapped_game_id != game_id);
+        self.connection_to_game_mapping.retain(|_, game_ids| {
+            game_ids.remove(game_id);
+            !game_ids.is_empty()
+        });
         Ok(())
     }
 
-    // Remove player connection mapping
-    pub fn remove_player_connection(&self, connection_id: &str) -> Option<String> {
+    /// Ask every running game to stop once its queued events are handled.
+    /// Returns the actor tasks so the caller can wait for them to finish.
+    pub fn shutdown_game_actors(&self, snapshot_dir: Option<PathBuf>) -> Vec<JoinHandle<()>> {
+        for sender in self.game_actors.iter() {
+            sender.deliver(GameMessage::Shutdown {
+                snapshot_dir: snapshot_dir.clone(),
+            });
+        }
+        self.game_actors.clear();
+        self.connection_to_game_mapping.clear();
+
+        let game_ids: Vec<String> = self.game_tasks.iter().map(|e| e.key().clone()).collect();
+        game_ids
+            .iter()
+            .filter_map(|game_id| self.game_tasks.remove(game_id))
+            .map(|(_, task)| task)
+            .collect()
+    }
+
+    // Remove player connection mapping; returns the games it was in
+    pub fn remove_player_connection(&self, connection_id: &str) -> HashSet<String> {
         // Remove connection actor
         self.connection_actors.remove(connection_id);
+        self.services.locales.remove(connection_id);
 
-        // Remove game mapping if exists
+        // Remove game mappings if any
         self.connection_to_game_mapping
             .remove(connection_id)
-            .map(|(_, game_id)| game_id)
+            .map(|(_, game_ids)| game_ids)
+            .unwrap_or_default()
     }
-    pub fn get_connection_game(&self, connection_id: &str) -> Option<String> {
+
+    pub fn get_connection_games(&self, connection_id: &str) -> HashSet<String> {
         self.connection_to_game_mapping
             .get(connection_id)
             .map(|entry| entry.value().clone())
+            .unwrap_or_default()
     }
 
     pub fn is_connection_in_game(&self, connection_id: &str) -> bool {
         self.connection_to_game_mapping.contains_key(connection_id)
     }
-
-    pub fn cleanup_game(&self, connection_id: &str) -> AppResult<()> {
-        let game_id = self.connection_to_game_mapping.get(connection_id);
-        match game_id {
-            None => Err(AppError::ConnectionNotInRoom),
-            Some(game_id) => {
-                self.cleanup_game_actor(&game_id);
-                self.disconnect_connection_actor(&connection_id)?;
-                Ok(())
-            }
-        }
-    }
 }
@@ -0,0 +1,299 @@
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::actors::actor_registry::ActorRegistry;
+use crate::actors::connection_actor::ConnectionMessage;
+use crate::actors::game_actor::GameMessage;
+use crate::actors::lobby_actor::LobbyMessage;
+use crate::game::bot_strategy::{BotAction, BotStrategy, GameStateView};
+use crate::network::connection_manager::ConnectionSender;
+use crate::network::messages::SharedMessage;
+use crate::{AppResult, ConnectionCommand};
+
+/// A seat filled by the server. The bot goes through the same lobby and game messages
+/// a client would, and reads the same serialized responses from a local connection.
+pub struct BotActor {
+    connection_id: String,
+    player_name: String,
+    room_id: String,
+    seated: bool, // placed in the room by the lobby, so it doesn't send JoinRoom itself
+    view: GameStateView,
+    strategy: Box<dyn BotStrategy>,
+    think_time: Duration, // pause before acting so humans can follow what the bot did
+    actor_registry: Arc<ActorRegistry>,
+    cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+}
+
+impl BotActor {
+    /// Spawn a bot that joins `room_id` and readies up. Returns its connection id.
+    pub fn spawn(
+        room_id: String,
+        strategy: Box<dyn BotStrategy>,
+        think_time: Duration,
+        actor_registry: Arc<ActorRegistry>,
+        cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+    ) -> AppResult<String> {
+        let (connection_id, _) = Self::start(
+            room_id,
+            false,
+            strategy,
+            think_time,
+            actor_registry,
+            cmd_sender,
+        )?;
+        Ok(connection_id)
+    }
+
+    /// Spawn a bot for the lobby to seat in `room_id` itself, for games that start without
+    /// ready-up. Returns its connection id and player name.
+    pub fn spawn_seated(
+        room_id: String,
+        strategy: Box<dyn BotStrategy>,
+        think_time: Duration,
+        actor_registry: Arc<ActorRegistry>,
+        cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+    ) -> AppResult<(String, String)> {
+        Self::start(
+            room_id,
+            true,
+            strategy,
+            think_time,
+            actor_registry,
+            cmd_sender,
+        )
+    }
+
+    fn start(
+        room_id: String,
+        seated: bool,
+        strategy: Box<dyn BotStrategy>,
+        think_time: Duration,
+        actor_registry: Arc<ActorRegistry>,
+        cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+    ) -> AppResult<(String, String)> {
+        let bot_id = Uuid::new_v4().simple().to_string();
+        let connection_id = format!("bot-{}", bot_id);
+        let player_name = format!("Bot_{}", &bot_id[..6]);
+
+        let (outbound_sender, outbound_receiver) = mpsc::unbounded_channel::<SharedMessage>();
+        let (conn_sender, conn_receiver) =
+            mpsc::channel::<ConnectionMessage>(actor_registry.mailboxes().connection);
+
+        cmd_sender.send(ConnectionCommand::AddConnection {
+            id: connection_id.clone(),
+            sender: ConnectionSender::Local(outbound_sender),
+        })?;
+        actor_registry.register_connection_actor(connection_id.clone(), conn_sender);
+
+        let mut bot = BotActor {
+            connection_id: connection_id.clone(),
+            player_name: player_name.clone(),
+            room_id,
+            seated,
+            view: GameStateView::default(),
+            strategy,
+            think_time,
+            actor_registry,
+            cmd_sender,
+        };
+        tokio::spawn(async move {
+            bot.run(conn_receiver, outbound_receiver).await;
+        });
+
+        Ok((connection_id, player_name))
+    }
+
+    async fn run(
+        &mut self,
+        mut conn_receiver: mpsc::Receiver<ConnectionMessage>,
+        mut outbound_receiver: mpsc::UnboundedReceiver<SharedMessage>,
+    ) {
+        println!(
+            "🤖 Bot {} started with strategy {}",
+            self.connection_id,
+            self.strategy.name()
+        );
+
+        let joined = if self.seated {
+            Ok(())
+        } else {
+            self.actor_registry
+                .send_lobby_message(LobbyMessage::JoinRoom {
+                    connection_id: self.connection_id.clone(),
+                    player_name: self.player_name.clone(),
+                    room_id: self.room_id.clone(),
+                })
+        };
+        if let Err(e) = joined {
+            eprintln!("Bot {} failed to join room: {:?}", self.connection_id, e);
+        } else {
+            loop {
+                tokio::select! {
+                    message = conn_receiver.recv() => match message {
+                        Some(ConnectionMessage::TransitionToGame { player_id, .. }) => {
+                            self.view.player_id = player_id;
+                        }
+                        Some(ConnectionMessage::TransitionToLobby { .. }) => {
+                            // Bots are always ready, including for the next game in the room
+                            let _ = self.actor_registry.send_lobby_message(LobbyMessage::PlayerReady {
+                                connection_id: self.connection_id.clone(),
+                            });
+                        }
+                        // Nothing left to do once the host removes it
+                        Some(ConnectionMessage::Kicked { .. })
+                        | Some(ConnectionMessage::Disconnect)
+                        | None => break,
+                        Some(_) => {}
+                    },
+                    outbound = outbound_receiver.recv() => match outbound {
+                        Some(payload) => {
+                            if let Err(e) = self.handle_server_message(&payload).await {
+                                eprintln!("Bot {} error: {:?}", self.connection_id, e);
+                            }
+                        }
+                        None => break,
+                    },
+                }
+            }
+        }
+
+        self.actor_registry
+            .remove_player_connection(&self.connection_id);
+        let _ = self.cmd_sender.send(ConnectionCommand::RemoveConnection {
+            id: self.connection_id.clone(),
+        });
+        println!("🤖 Bot {} stopped", self.connection_id);
+    }
+
+    async fn handle_server_message(&mut self, payload: &str) -> AppResult<()> {
+        let Ok(response) = serde_json::from_str::<Value>(payload) else {
+            return Ok(());
+        };
+
+        if let Some(joined) = response.get("SelfJoined") {
+            if let Some(player_id) = joined["player_id"].as_str() {
+                self.view.player_id = player_id.to_string();
+            }
+            // Bots are always ready
+            self.actor_registry
+                .send_lobby_message(LobbyMessage::PlayerReady {
+                    connection_id: self.connection_id.clone(),
+                })?;
+        } else if response.get("MulliganStart").is_some() {
+            // Bots always keep their opening hand
+            self.actor_registry.send_game_message(
+                &self.connection_id,
+                None,
+                GameMessage::MulliganDecision {
+                    connection_id: self.connection_id.clone(),
+                    keep: true,
+                },
+            )?;
+        } else if let Some(choice) = response.get("ChoiceRequired") {
+            // Bots take the first option, same as a timed out player
+            if let (Some(choice_id), Some(option_id)) = (
+                choice["choice_id"].as_str(),
+                choice["options"][0]["option_id"].as_str(),
+            ) {
+                self.actor_registry.send_game_message(
+                    &self.connection_id,
+                    None,
+                    GameMessage::ChoiceResponse {
+                        connection_id: self.connection_id.clone(),
+                        choice_id: choice_id.to_string(),
+                        option_id: option_id.to_string(),
+                    },
+                )?;
+            }
+        } else if let Some(undo) = response.get("UndoRequested") {
+            // Bots never stand in the way of an undo
+            if undo["player_id"].as_str() != Some(self.view.player_id.as_str()) {
+                self.actor_registry.send_game_message(
+                    &self.connection_id,
+                    None,
+                    GameMessage::RespondToUndo {
+                        connection_id: self.connection_id.clone(),
+                        accept: true,
+                    },
+                )?;
+            }
+        } else if let Some(offer) = response.get("DrawOffered") {
+            if offer["player_id"].as_str() != Some(self.view.player_id.as_str()) {
+                let accept = self.strategy.accept_draw(&self.view);
+                self.actor_registry.send_game_message(
+                    &self.connection_id,
+                    None,
+                    GameMessage::RespondToDraw {
+                        connection_id: self.connection_id.clone(),
+                        accept,
+                    },
+                )?;
+            }
+        } else if let Some(board) = response.get("PublicBoardState") {
+            self.view.active_player = board["active_player"].as_str().map(str::to_string);
+            self.view.phase = serde_json::from_value(board["current_phase"].clone()).ok();
+            self.view.loot_deck_size = board["loot_deck_size"].as_u64().unwrap_or(0) as usize;
+            self.view.coins = board["players"][&self.view.player_id]["coins"]
+                .as_u64()
+                .unwrap_or(0) as u32;
+            self.view.shop = serde_json::from_value(board["shop"].clone()).unwrap_or_default();
+            self.view.shop_price = board["shop_price"].as_u64().unwrap_or(0) as u32;
+        } else if let Some(private) = response.get("PrivateBoardState") {
+            self.view.hand = serde_json::from_value(private["hand"].clone()).unwrap_or_default();
+        } else if let Some(phase_change) = response.get("TurnPhaseChange") {
+            // Phase changes and priority passes are the bot's cue to act. The board arrives
+            // after them, so the active player is taken from here too.
+            self.view.priority_player = phase_change["player_id"].as_str().map(str::to_string);
+            self.view.active_player = phase_change["active_player"].as_str().map(str::to_string);
+            self.view.phase = serde_json::from_value(phase_change["phase"].clone()).ok();
+            self.act().await?;
+        } else if let Some(bought) = response.get("ItemBought") {
+            // Buying keeps priority, so the bot goes on before its new board arrives
+            if bought["player_id"].as_str() == Some(self.view.player_id.as_str()) {
+                self.view.coins = self.view.coins.saturating_sub(self.view.shop_price);
+                self.act().await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn act(&mut self) -> AppResult<()> {
+        if !self.view.has_priority() {
+            return Ok(());
+        }
+        tokio::time::sleep(self.think_time).await;
+        let action = self.strategy.decide_action(&self.view);
+        self.perform(action)
+    }
+
+    fn perform(&self, action: BotAction) -> AppResult<()> {
+        match action {
+            BotAction::PassTurn => self.actor_registry.send_game_message(
+                &self.connection_id,
+                None,
+                GameMessage::TurnPass {
+                    connection_id: self.connection_id.clone(),
+                },
+            ),
+            BotAction::PassPriority => self.actor_registry.send_game_message(
+                &self.connection_id,
+                None,
+                GameMessage::PriorityPass {
+                    connection_id: self.connection_id.clone(),
+                },
+            ),
+            BotAction::BuyItem { shop_index } => self.actor_registry.send_game_message(
+                &self.connection_id,
+                None,
+                GameMessage::BuyItem {
+                    connection_id: self.connection_id.clone(),
+                    shop_index,
+                },
+            ),
+            BotAction::Wait => Ok(()),
+        }
+    }
+}
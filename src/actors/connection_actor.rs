@@ -1,15 +1,26 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Instant};
+use tokio_tungstenite::tungstenite::protocol::{frame::coding::CloseCode, CloseFrame};
 
 use crate::actors::actor_registry::ActorRegistry;
 use crate::actors::game_actor::GameMessage;
 use crate::actors::lobby_actor::LobbyMessage;
-use crate::network::messages::{ClientMessage, ClientMessageCategory, ServerResponse};
+use crate::actors::tournament_actor::TournamentMessage;
+use crate::config::InboundConfig;
+use crate::network::auth::AuthContext;
+use crate::network::frame_guard::{FrameGuard, MalformedFrames};
+use crate::network::locale::Locale;
+use crate::network::messages::{
+    deserialize_message, serialize_response, BatchedClientMessage, BatchedError, BatchedResult,
+    ClientMessage, ClientMessageCategory, KickReason, ServerResponse,
+};
+use crate::network::permissions::Role;
 use crate::network::reliable_messaging::{
-    create_reliable_message, MessageAck, MessageReceiver, PendingMessage, ReliableMessage,
+    create_reliable_message, DeliveryFailed, MessageAck, MessageReceiver, ReliableMessage,
+    ResendQueue,
 };
 use crate::{AppError, AppResult, ConnectionCommand};
 
@@ -17,54 +28,134 @@ use crate::{AppError, AppResult, ConnectionCommand};
 pub enum ConnectionMessage {
     ClientMessage { message: ClientMessage },
     TransitionToGame { game_id: String, player_id: String },
-    TransitionToLobby,
+    TransitionToLobby { game_id: String }, // that game is over for this connection
+    Kicked { reason: KickReason },         // out of the room, back in the lobby
+    SetRole { role: Role },
     Disconnect,
     ReliableMessage { message: ReliableMessage },
     MessageAck { ack: MessageAck },
 }
 
+/// Reliable-layer frames a client can send instead of a bare ClientMessage
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ReliableClientMessage {
+    Reliable(ReliableMessage), // payload is a serialized ClientMessage
+    Ack(MessageAck),           // for a ReliableServerResponse::Reliable
+}
+
+impl From<ReliableClientMessage> for ConnectionMessage {
+    fn from(frame: ReliableClientMessage) -> Self {
+        match frame {
+            ReliableClientMessage::Reliable(message) => {
+                ConnectionMessage::ReliableMessage { message }
+            }
+            ReliableClientMessage::Ack(ack) => ConnectionMessage::MessageAck { ack },
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum ReliableServerResponse {
     Reliable(ReliableMessage),
     Ack(MessageAck),
+    DeliveryFailed(DeliveryFailed),
 }
 
+/// One message of a batch on its way to the lobby or a game, which answer it here instead of
+/// with an Error of its own
 #[derive(Debug, Clone)]
-pub enum ConnectionState {
-    InLobby,
-    InGame { game_id: String, player_id: String },
+pub struct BatchRequest {
+    request_id: String,
+    index: usize, // in the batch
+    results: mpsc::UnboundedSender<(usize, BatchedResult)>,
+}
+
+impl BatchRequest {
+    pub fn answer(self, result: &AppResult<()>, locale: Locale) {
+        let _ = self.results.send((
+            self.index,
+            BatchedResult {
+                request_id: self.request_id,
+                error: result
+                    .as_ref()
+                    .err()
+                    .map(|error| BatchedError::localized(error, locale)),
+            },
+        ));
+    }
 }
 
 pub struct ConnectionActor {
     connection_id: String,
-    state: ConnectionState,
+    games: HashMap<String, String>, // game_id -> player_id, every game this connection plays in
+    role: Role,
+    auth: AuthContext,
+    locale: Locale, // negotiated in the handshake
+    max_batched_messages: usize,
+    frame_guard: FrameGuard, // for reliable payloads, counting with the socket loop's guard
     actor_registry: Arc<ActorRegistry>,
     cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
 
     message_receiver: MessageReceiver,
-    pending_messages: HashMap<String, PendingMessage>,
+    resend_queue: ResendQueue,
+    next_sequence: u64, // of the next reliable message sent to this client
 }
 
 impl ConnectionActor {
     pub fn new(
         connection_id: String,
+        auth: AuthContext,
+        locale: Locale,
+        inbound: &InboundConfig,
+        malformed_frames: MalformedFrames,
         actor_registry: Arc<ActorRegistry>,
         cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
     ) -> Self {
         Self {
             connection_id,
-            state: ConnectionState::InLobby,
+            games: HashMap::new(),
+            role: Role::from_auth(&auth),
+            auth,
+            locale,
+            max_batched_messages: inbound.max_batched_messages,
+            frame_guard: FrameGuard::sharing(inbound.clone(), malformed_frames),
             actor_registry,
             cmd_sender,
-            message_receiver: MessageReceiver::new(),
-            pending_messages: HashMap::new(),
+            message_receiver: MessageReceiver::with_window(inbound.max_reliable_window as u64),
+            resend_queue: ResendQueue::new(),
+            next_sequence: 1,
         }
     }
 
-    pub async fn run(&mut self, mut receiver: mpsc::UnboundedReceiver<ConnectionMessage>) {
+    pub fn auth_context(&self) -> &AuthContext {
+        &self.auth
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub async fn run(&mut self, mut receiver: mpsc::Receiver<ConnectionMessage>) {
         println!("🔌 Connection actor started for {}", self.connection_id);
+        self.send_response(ServerResponse::LobbyEntered);
+
+        loop {
+            let next_retry = self.resend_queue.next_retry();
+            let message = tokio::select! {
+                message = receiver.recv() => match message {
+                    Some(message) => message,
+                    None => break,
+                },
+                _ = sleep_until(next_retry.unwrap_or_else(Instant::now)), if next_retry.is_some() => {
+                    self.retransmit_due().await;
+                    continue;
+                }
+            };
 
-        while let Some(message) = receiver.recv().await {
             match message {
                 ConnectionMessage::ClientMessage { message } => {
                     if let Err(error) = self.handle_client_message(message).await {
@@ -80,14 +171,31 @@ impl ConnectionActor {
                         "🔌 Connection {} transitioning to game {} as player {}",
                         self.connection_id, game_id, player_id
                     );
-                    self.state = ConnectionState::InGame { game_id, player_id };
+                    self.send_response(ServerResponse::GameJoined {
+                        game_id: game_id.clone(),
+                        seat: player_id.clone(),
+                    });
+                    self.games.insert(game_id, player_id);
                 }
-                ConnectionMessage::TransitionToLobby => {
+                ConnectionMessage::TransitionToLobby { game_id } => {
                     println!(
-                        "🔌 Connection {} transitioning to lobby",
-                        self.connection_id
+                        "🔌 Connection {} leaving game {}",
+                        self.connection_id, game_id
+                    );
+                    // Back in the lobby once the last of its games is over
+                    if self.games.remove(&game_id).is_some() && self.games.is_empty() {
+                        self.send_response(ServerResponse::ReturnedToLobby);
+                    }
+                }
+                ConnectionMessage::Kicked { reason } => {
+                    println!(
+                        "🔌 Connection {} removed from its room: {:?}",
+                        self.connection_id, reason
                     );
-                    self.state = ConnectionState::InLobby;
+                    self.send_response(ServerResponse::Kicked { reason });
+                }
+                ConnectionMessage::SetRole { role } => {
+                    self.role = self.role.assign(role);
                 }
                 ConnectionMessage::ReliableMessage { message } => {
                     if let Err(error) = self.handle_reliable_message(message).await {
@@ -116,13 +224,95 @@ impl ConnectionActor {
     }
 
     async fn handle_client_message(&mut self, message: ClientMessage) -> AppResult<()> {
+        if let ClientMessage::BatchedClientMessages { messages } = message {
+            return self.handle_batch(messages).await;
+        }
+        self.route_client_message(message, None).await
+    }
+
+    /// Every message of a batch goes through in the order listed, as if it had come on its
+    /// own. One being refused doesn't hold back the rest; the results go back together.
+    async fn handle_batch(&mut self, messages: Vec<BatchedClientMessage>) -> AppResult<()> {
+        if messages.len() > self.max_batched_messages {
+            self.refuse_frame(AppError::MalformedFrame {
+                reason: format!(
+                    "batch of {} messages, the limit is {}",
+                    messages.len(),
+                    self.max_batched_messages
+                ),
+            })
+            .await;
+            return Ok(());
+        }
+
+        let (results_sender, mut answers) = mpsc::unbounded_channel();
+        let mut results = Vec::with_capacity(messages.len());
+        for (
+            index,
+            BatchedClientMessage {
+                request_id,
+                message,
+            },
+        ) in messages.into_iter().enumerate()
+        {
+            let request = BatchRequest {
+                request_id: request_id.clone(),
+                index,
+                results: results_sender.clone(),
+            };
+            let result = match message {
+                ClientMessage::BatchedClientMessages { .. } => Err(AppError::UnknownMessage {
+                    message: "Batches can't be nested".to_string(),
+                }),
+                message => self.route_client_message(message, Some(request)).await,
+            };
+            // Until the lobby or game answers, a message that got past the connection is fine
+            results.push(BatchedResult {
+                request_id,
+                error: result
+                    .err()
+                    .map(|error| BatchedError::localized(&error, self.locale)),
+            });
+        }
+        drop(results_sender);
+
+        // The answers come in as the lobby and games get to the messages, and go out together
+        let cmd_sender = self.cmd_sender.clone();
+        let connection_id = self.connection_id.clone();
+        tokio::spawn(async move {
+            while let Some((index, result)) = answers.recv().await {
+                results[index] = result;
+            }
+            let _ = cmd_sender.send(ConnectionCommand::SendToPlayer {
+                connection_id,
+                message: serialize_response(ServerResponse::BatchResults { results }),
+            });
+        });
+        Ok(())
+    }
+
+    /// Hand a message on to whoever handles it. A `request` is answered by the lobby or game
+    /// the message goes to; anything else leaves it unanswered.
+    async fn route_client_message(
+        &mut self,
+        message: ClientMessage,
+        request: Option<BatchRequest>,
+    ) -> AppResult<()> {
         println!(
-            "🔌 Connection {} (state: {:?}) handling message: {:?}",
-            self.connection_id, self.state, message
+            "🔌 Connection {} (games: {:?}) handling message: {:?}",
+            self.connection_id, self.games, message
         );
+        self.role.check(&message)?;
         match message.category() {
-            ClientMessageCategory::LobbyMessage => self.handle_lobby_message(message).await,
-            ClientMessageCategory::GameMessage => self.handle_game_message(message).await,
+            ClientMessageCategory::LobbyMessage => {
+                self.handle_lobby_message(message, request).await
+            }
+            ClientMessageCategory::GameMessage => self.handle_game_message(message, request).await,
+            ClientMessageCategory::TournamentMessage => {
+                let tournament_message = self.convert_to_tournament_message(message)?;
+                self.actor_registry
+                    .send_tournament_message(tournament_message)
+            }
         }
     }
 
@@ -132,37 +322,77 @@ impl ConnectionActor {
 
         let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayer {
             connection_id: self.connection_id.clone(),
-            message: serialized,
+            message: serialized.into(),
         });
     }
 
+    /// Send now and keep resending from the actor loop until the client acks it
     pub async fn send_reliable(&mut self, payload: String) {
-        let message = create_reliable_message(payload);
-
-        // Try to send, retry up to 3 times immediately
-        for _ in 1..=3 {
-            self.send_message_now(message.clone()).await;
-
-            // Wait a bit and see if we get an ack
-            tokio::time::sleep(Duration::from_millis(500)).await;
+        let message = create_reliable_message(self.next_sequence, payload);
+        self.next_sequence += 1;
+        self.send_message_now(message.clone()).await;
+        self.resend_queue.track(message);
+    }
 
-            // If message was acked, we're done
-            if !self.pending_messages.contains_key(&message.id) {
-                return;
-            }
+    async fn retransmit_due(&mut self) {
+        let (resend, failed) = self.resend_queue.take_due(Instant::now());
+        for message in resend {
+            println!(
+                "🔁 Resending message {} to {}",
+                message.id, self.connection_id
+            );
+            self.send_message_now(message).await;
+        }
+        for pending in failed {
+            eprintln!(
+                "❌ Message {} to {} not acked after {} attempts, giving up",
+                pending.message.id, self.connection_id, pending.retry_count
+            );
+            let wrapper = ReliableServerResponse::DeliveryFailed(DeliveryFailed {
+                message_id: pending.message.id,
+                sequence: pending.message.sequence,
+                attempts: pending.retry_count,
+            });
+            let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayer {
+                connection_id: self.connection_id.clone(),
+                message: serde_json::to_string(&wrapper).unwrap().into(),
+            });
         }
     }
 
     pub async fn handle_reliable_message(&mut self, message: ReliableMessage) -> AppResult<()> {
-        let (ack, ordered_messages) = self.message_receiver.receive_message(message);
+        let (ack, ordered_messages) = match self.message_receiver.receive_message(message) {
+            Ok(received) => received,
+            Err(error) => {
+                self.refuse_frame(error).await;
+                return Ok(());
+            }
+        };
 
         // Send ack
         self.send_ack(ack).await;
 
-        // Process ordered messages
+        // Process in sequence order; one bad command must not drop the ones queued behind it.
+        // The payload is a frame of its own and gets the same checks as one off the socket.
         for msg in ordered_messages {
-            if let Ok(client_message) = serde_json::from_str(&msg.payload) {
-                self.handle_client_message(client_message).await?;
+            let parsed = self.frame_guard.check_text(&msg.payload).and_then(|()| {
+                deserialize_message(&msg.payload).map_err(|e| AppError::UnknownMessage {
+                    message: format!("Parse error in reliable message {}: {}", msg.id, e),
+                })
+            });
+            let client_message = match parsed {
+                Ok(client_message) => client_message,
+                Err(error) => {
+                    self.refuse_frame(error).await;
+                    continue;
+                }
+            };
+            if let Err(error) = self.handle_client_message(client_message).await {
+                eprintln!(
+                    "Reliable message error for {}: {:?}",
+                    self.connection_id, error
+                );
+                self.send_error_to_client(error).await;
             }
         }
 
@@ -175,54 +405,65 @@ impl ConnectionActor {
 
         let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayer {
             connection_id: self.connection_id.clone(),
-            message: serialized,
+            message: serialized.into(),
         });
     }
 
     pub fn handle_ack(&mut self, ack: MessageAck) {
-        if self.pending_messages.remove(&ack.message_id).is_some() {
+        if self.resend_queue.ack(&ack.message_id) {
             println!("✅ Message {} acknowledged", ack.message_id);
         }
     }
 
-    async fn handle_lobby_message(&mut self, message: ClientMessage) -> AppResult<()> {
+    async fn handle_lobby_message(
+        &mut self,
+        message: ClientMessage,
+        request: Option<BatchRequest>,
+    ) -> AppResult<()> {
         let lobby_message = self.convert_to_lobby_message(message)?;
-
-        // Special handling for state transitions
-        match &lobby_message {
-            LobbyMessage::LeaveRoom { .. } => {
-                self.state = ConnectionState::InLobby;
-            }
-            _ => {}
-        }
-
-        self.actor_registry.send_lobby_message(lobby_message)?;
+        self.actor_registry
+            .send_lobby_message(LobbyMessage::batched(lobby_message, request))?;
         Ok(())
     }
 
-    async fn handle_game_message(&mut self, message: ClientMessage) -> AppResult<()> {
-        // Validate we're in a game
-        match &self.state {
-            ConnectionState::InGame { game_id, player_id } => {
-                println!(
-                    "🔌 Connection {} in game {} as player {}, processing game message",
-                    self.connection_id, game_id, player_id
-                );
+    async fn handle_game_message(
+        &mut self,
+        message: ClientMessage,
+        request: Option<BatchRequest>,
+    ) -> AppResult<()> {
+        let game_id = match &message {
+            ClientMessage::Game { game_id, .. } => Some(game_id.clone()),
+            _ => None,
+        };
+        let (game_id, player_id) = self.target_game(game_id)?;
+        println!(
+            "🔌 Connection {} in game {} as player {}, processing game message",
+            self.connection_id, game_id, player_id
+        );
 
-                // Use connection-based game messages instead of player-based
-                let game_message = self.convert_to_game_message_with_connection(message)?;
-                self.actor_registry
-                    .send_game_message(&self.connection_id, game_message)?;
-                Ok(())
-            }
-            ConnectionState::InLobby => {
-                println!(
-                    "🔌 Connection {} is in lobby, cannot process game message",
-                    self.connection_id
-                );
-                Err(AppError::ConnectionNotInRoom)
-            }
-        }
+        // Use connection-based game messages instead of player-based
+        let game_message = GameMessage::from_client_message(self.connection_id.clone(), message)?;
+        self.actor_registry.send_game_message(
+            &self.connection_id,
+            Some(game_id),
+            GameMessage::batched(game_message, request),
+        )
+    }
+
+    /// The game a game message is for: the one it names, or the only one this connection is in
+    fn target_game(&self, game_id: Option<String>) -> AppResult<(&str, &str)> {
+        let target = match game_id {
+            Some(game_id) => self
+                .games
+                .get_key_value(&game_id)
+                .ok_or(AppError::GameNotFound { game_id })?,
+            None => match self.games.len() {
+                0 => return Err(AppError::ConnectionNotInRoom),
+                1 => self.games.iter().next().expect("One game"),
+                games => return Err(AppError::GameIdRequired { games }),
+            },
+        };
+        Ok((target.0.as_str(), target.1.as_str()))
     }
 
     fn convert_to_lobby_message(&self, message: ClientMessage) -> AppResult<LobbyMessage> {
@@ -237,10 +478,12 @@ impl ConnectionActor {
             ClientMessage::CreateRoom {
                 room_name,
                 first_player_name,
+                options,
             } => Ok(LobbyMessage::CreateRoom {
                 connection_id,
                 room_name,
                 first_player_name,
+                options,
             }),
             ClientMessage::DestroyRoom { room_id } => Ok(LobbyMessage::DestroyRoom {
                 connection_id,
@@ -256,67 +499,236 @@ impl ConnectionActor {
             }),
             ClientMessage::LeaveRoom => Ok(LobbyMessage::LeaveRoom { connection_id }),
             ClientMessage::PlayerReady => Ok(LobbyMessage::PlayerReady { connection_id }),
+            ClientMessage::PlayerUnready => Ok(LobbyMessage::PlayerUnready { connection_id }),
+            ClientMessage::ReloadCards { admin_key } => Ok(LobbyMessage::ReloadCards {
+                connection_id,
+                admin_key,
+            }),
+            ClientMessage::GetProtocolInfo => Ok(LobbyMessage::GetProtocolInfo { connection_id }),
+            ClientMessage::GetGlossary => Ok(LobbyMessage::GetGlossary { connection_id }),
+            ClientMessage::GetCardCatalog { known_version } => Ok(LobbyMessage::GetCardCatalog {
+                connection_id,
+                known_version,
+            }),
+            ClientMessage::AddBot { strategy } => Ok(LobbyMessage::AddBot {
+                connection_id,
+                strategy,
+            }),
+            ClientMessage::StartSoloGame {
+                player_name,
+                bots,
+                strategy,
+                options,
+            } => Ok(LobbyMessage::StartSoloGame {
+                connection_id,
+                player_name,
+                bots,
+                strategy,
+                options,
+            }),
+            ClientMessage::LobbyChat { message } => Ok(LobbyMessage::LobbyChat {
+                connection_id,
+                message,
+            }),
+            ClientMessage::SetChatMuted { channel, muted } => Ok(LobbyMessage::SetChatMuted {
+                connection_id,
+                channel,
+                muted,
+            }),
+            ClientMessage::MutePlayer { player_id } => Ok(LobbyMessage::SetPlayerMuted {
+                connection_id,
+                player_id,
+                muted: true,
+            }),
+            ClientMessage::UnmutePlayer { player_id } => Ok(LobbyMessage::SetPlayerMuted {
+                connection_id,
+                player_id,
+                muted: false,
+            }),
+            ClientMessage::KickPlayer { player_id } => Ok(LobbyMessage::KickPlayer {
+                connection_id,
+                player_id,
+                ban: false,
+            }),
+            ClientMessage::BanPlayer { player_id } => Ok(LobbyMessage::KickPlayer {
+                connection_id,
+                player_id,
+                ban: true,
+            }),
+            ClientMessage::SetRoomWebhook { url } => {
+                Ok(LobbyMessage::SetRoomWebhook { connection_id, url })
+            }
+            ClientMessage::RegisterProfile {
+                display_name,
+                avatar_id,
+            } => Ok(LobbyMessage::RegisterProfile {
+                connection_id,
+                display_name,
+                avatar_id,
+            }),
+            ClientMessage::RestoreProfile { token } => Ok(LobbyMessage::RestoreProfile {
+                connection_id,
+                token,
+            }),
+            ClientMessage::AddFriend { profile_id } => Ok(LobbyMessage::AddFriend {
+                connection_id,
+                profile_id,
+            }),
+            ClientMessage::RemoveFriend { profile_id } => Ok(LobbyMessage::RemoveFriend {
+                connection_id,
+                profile_id,
+            }),
+            ClientMessage::GetFriends => Ok(LobbyMessage::GetFriends { connection_id }),
+            ClientMessage::InviteToRoom { profile_id } => Ok(LobbyMessage::InviteToRoom {
+                connection_id,
+                profile_id,
+            }),
+            ClientMessage::ResumeSession { session_token } => Ok(LobbyMessage::ResumeSession {
+                connection_id,
+                session_token,
+            }),
+            ClientMessage::GetLeaderboard { limit } => Ok(LobbyMessage::GetLeaderboard {
+                connection_id,
+                limit,
+            }),
+            ClientMessage::GetGameSummary { game_id } => Ok(LobbyMessage::GetGameSummary {
+                connection_id,
+                game_id,
+            }),
+            ClientMessage::DumpGameState { game_id, admin_key } => {
+                Ok(LobbyMessage::DumpGameState {
+                    connection_id,
+                    game_id,
+                    admin_key,
+                })
+            }
+            ClientMessage::Announce {
+                admin_key,
+                text,
+                severity,
+                send_at,
+            } => Ok(LobbyMessage::Announce {
+                connection_id,
+                admin_key,
+                text,
+                severity,
+                send_at,
+            }),
+            ClientMessage::CancelAnnouncement {
+                admin_key,
+                announcement_id,
+            } => Ok(LobbyMessage::CancelAnnouncement {
+                connection_id,
+                admin_key,
+                announcement_id,
+            }),
+            ClientMessage::PauseGame => Ok(LobbyMessage::SetGamePaused {
+                connection_id,
+                paused: true,
+            }),
+            ClientMessage::ResumeGame => Ok(LobbyMessage::SetGamePaused {
+                connection_id,
+                paused: false,
+            }),
+            ClientMessage::SpectateGame { room_id } => Ok(LobbyMessage::SetSpectating {
+                connection_id,
+                room_id,
+                spectating: true,
+            }),
+            ClientMessage::StopSpectating { room_id } => Ok(LobbyMessage::SetSpectating {
+                connection_id,
+                room_id,
+                spectating: false,
+            }),
             _ => Err(AppError::Internal {
                 message: "Invalid lobby message conversion".to_string(),
             }),
         }
     }
 
-    fn convert_to_game_message_with_connection(
+    fn convert_to_tournament_message(
         &self,
         message: ClientMessage,
-    ) -> AppResult<GameMessage> {
+    ) -> AppResult<TournamentMessage> {
+        let connection_id = self.connection_id.clone();
+
         match message {
-            ClientMessage::TurnPass => Ok(GameMessage::TurnPass {
-                connection_id: self.connection_id.clone(),
+            ClientMessage::CreateTournament { name, options } => {
+                Ok(TournamentMessage::CreateTournament {
+                    connection_id,
+                    name,
+                    options,
+                })
+            }
+            ClientMessage::RegisterForTournament {
+                tournament_id,
+                player_name,
+            } => Ok(TournamentMessage::Register {
+                connection_id,
+                tournament_id,
+                player_name,
             }),
-            // ClientMessage::PriorityPass => Ok(GameMessage::PriorityPass {
-            //     connection_id: self.connection_id.clone(),
-            // }),
+            ClientMessage::WithdrawFromTournament { tournament_id } => {
+                Ok(TournamentMessage::Withdraw {
+                    connection_id,
+                    tournament_id,
+                })
+            }
+            ClientMessage::StartTournament { tournament_id } => Ok(TournamentMessage::Start {
+                connection_id,
+                tournament_id,
+            }),
+            ClientMessage::GetTournament { tournament_id } => {
+                Ok(TournamentMessage::GetTournament {
+                    connection_id,
+                    tournament_id,
+                })
+            }
             _ => Err(AppError::Internal {
-                message: "Invalid game message conversion".to_string(),
+                message: "Invalid tournament message conversion".to_string(),
             }),
         }
     }
 
+    /// Refuse a frame that got past the FrameGuard; it counts towards the same disconnect
+    async fn refuse_frame(&self, error: AppError) {
+        eprintln!("Bad frame from {}: {}", self.connection_id, error);
+        self.send_error_to_client(error).await;
+        if self.frame_guard.record_malformed() {
+            println!(
+                "🚫 Disconnecting {}: too many malformed frames",
+                self.connection_id
+            );
+            let _ = self.cmd_sender.send(ConnectionCommand::Close {
+                id: self.connection_id.clone(),
+                close_frame: CloseFrame {
+                    code: CloseCode::Policy,
+                    reason: "Too many malformed frames".into(),
+                },
+            });
+        }
+    }
+
     async fn send_error_to_client(&self, error: AppError) {
-        use crate::network::messages::{serialize_response, ServerResponse};
+        self.send_response(ServerResponse::localized_error(&error, self.locale));
+    }
 
+    fn send_response(&self, response: ServerResponse) {
         let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayer {
             connection_id: self.connection_id.clone(),
-            message: serialize_response(ServerResponse::from_app_error(&error)),
+            message: serialize_response(response),
         });
     }
 
     async fn cleanup(&mut self) {
+        // Games hold the seat until the player resumes it or the game ends
         self.actor_registry
             .remove_player_connection(&self.connection_id);
-
-        // If we're in a game, we might want to notify other players
-        if let ConnectionState::InGame { .. } = self.state {
-            // In a full implementation, we might send a "player disconnected" message
-            // to the game actor, but for now the game will handle it via normal cleanup
-        }
-    }
-
-    pub fn transition_to_game(&mut self, game_id: String, player_id: String) {
-        println!(
-            "🔌 Connection {} transitioning to game {}",
-            self.connection_id, game_id
-        );
-        self.state = ConnectionState::InGame { game_id, player_id };
-    }
-
-    pub fn transition_to_lobby(&mut self) {
-        println!(
-            "🔌 Connection {} transitioning to lobby",
-            self.connection_id
-        );
-        self.state = ConnectionState::InLobby;
     }
 
-    pub fn get_state(&self) -> &ConnectionState {
-        &self.state
+    /// game_id -> player_id for every game this connection plays in
+    pub fn games(&self) -> &HashMap<String, String> {
+        &self.games
     }
 
     pub async fn send_reliable_game_state(
@@ -335,7 +747,7 @@ impl ConnectionActor {
         let serialized = serde_json::to_string(message).unwrap();
         let _ = cmd_sender.send(ConnectionCommand::SendToPlayer {
             connection_id: connection_id.to_string(),
-            message: serialized,
+            message: serialized.into(),
         });
     }
 }
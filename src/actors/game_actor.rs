@@ -1,30 +1,489 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Duration, Instant};
 
+use crate::actors::connection_actor::BatchRequest;
+use crate::actors::lobby_actor::LobbyMessage;
+use crate::actors::lobby_shards::LobbyShards;
+use crate::config::GameConfig;
+use crate::game::board::DeckKind;
+use crate::game::deadlines::{self, unix_millis, PriorityWindow, WindowTimer};
+use crate::game::event_log::LogMark;
 use crate::game::game_coordinator::{GameCoordinator, GameEvent};
-use crate::network::messages::{serialize_response, ServerResponse};
-use crate::{AppError, AppResult, ConnectionCommand, TurnOrder};
+use crate::game::persistence::{GameProgress, GameSnapshot, GameSnapshots, GameStore, SavedGame};
+use crate::game::ratings::Ratings;
+use crate::game::replay::ReplayKey;
+use crate::game::rules::{GameSpeed, SpeedTimers};
+use crate::game::stats::{GameSummaries, GameSummary};
+use crate::game::trades::TradeOffer;
+use crate::game::turn_order::GameOpening;
+use crate::network::locale::ConnectionLocales;
+use crate::network::messages::{ClientMessage, ServerResponse};
+use crate::network::messenger::Messenger;
+use crate::network::metrics::ServerMetrics;
+use crate::network::profiles::PlayerProfile;
+use crate::network::webhooks::{WebhookEvent, Webhooks};
+use crate::{AppError, AppResult};
+
+// Accepted events included in a debug dump, the full history is in the replay
+const DUMP_RECENT_EVENTS: usize = 50;
+
+// Saves that only append progress before the whole game is written out again
+const APPENDS_PER_CHECKPOINT: usize = 64;
 
 #[derive(Debug, Clone)]
 pub enum GameMessage {
-    TurnPass { connection_id: String },
-    // PriorityPass { connection_id: String },
+    TurnPass {
+        connection_id: String,
+    },
+    PriorityPass {
+        connection_id: String,
+    },
+    GetSimpleState {
+        connection_id: String,
+    },
+    GetLegalActions {
+        connection_id: String,
+    },
+    GetTimeRemaining {
+        connection_id: String,
+    },
+    ViewDiscardPile {
+        connection_id: String,
+        deck_type: DeckKind,
+    },
+    GetEventLog {
+        connection_id: String,
+        since: Option<u64>,
+    },
+    GetPrivateLog {
+        connection_id: String,
+        since: Option<u64>,
+    },
+    MulliganDecision {
+        connection_id: String,
+        keep: bool,
+    },
+    ActivateItem {
+        connection_id: String,
+        item_id: String,
+    },
+    BuyItem {
+        connection_id: String,
+        shop_index: usize,
+    },
+    PlayLootCard {
+        connection_id: String,
+        card_id: String,
+        tap_character: bool,
+    },
+    ChoiceResponse {
+        connection_id: String,
+        choice_id: String,
+        option_id: String,
+    },
+    ProposeTrade {
+        connection_id: String,
+        to_player: String,
+        offer: TradeOffer,
+        request: TradeOffer,
+    },
+    RespondToTrade {
+        connection_id: String,
+        trade_id: String,
+        accept: bool,
+    },
+    RequestUndo {
+        connection_id: String,
+    },
+    RespondToUndo {
+        connection_id: String,
+        accept: bool,
+    },
+    Concede {
+        connection_id: String,
+    },
+    OfferDraw {
+        connection_id: String,
+    },
+    RespondToDraw {
+        connection_id: String,
+        accept: bool,
+    },
+    WithdrawDraw {
+        connection_id: String,
+    },
+    ImBack {
+        connection_id: String,
+    },
+    // A new connection took over `player_id` through its session token
+    Reconnect {
+        connection_id: String,
+        player_id: String,
+    },
+    // Queued behind any in-flight events so they are processed before the actor stops
+    Shutdown {
+        snapshot_dir: Option<PathBuf>,
+    },
+    // Sent by the lobby once the admin key is checked, the connection needn't be a player
+    DumpState {
+        connection_id: String,
+    },
+    // Sent by the lobby once it checked the connection is the room's host
+    SetPaused {
+        connection_id: String,
+        paused: bool,
+    },
+    // Sent by the lobby for a connection watching the game rather than playing in it
+    AddSpectator {
+        connection_id: String,
+    },
+    RemoveSpectator {
+        connection_id: String,
+    },
+    // One message of a client's batch, answered through `request` rather than with an Error
+    Batched {
+        request: BatchRequest,
+        message: Box<GameMessage>,
+    },
+}
+
+impl GameMessage {
+    /// `message` as part of a batch when there is a request to answer
+    pub fn batched(message: GameMessage, request: Option<BatchRequest>) -> Self {
+        match request {
+            Some(request) => GameMessage::Batched {
+                request,
+                message: Box::new(message),
+            },
+            None => message,
+        }
+    }
+
+    fn unbatched(self) -> (GameMessage, Option<BatchRequest>) {
+        match self {
+            GameMessage::Batched { request, message } => (*message, Some(request)),
+            message => (message, None),
+        }
+    }
+
+    pub fn connection_id(&self) -> Option<&str> {
+        match self {
+            GameMessage::Batched { message, .. } => message.connection_id(),
+            GameMessage::TurnPass { connection_id }
+            | GameMessage::PriorityPass { connection_id }
+            | GameMessage::GetSimpleState { connection_id }
+            | GameMessage::GetLegalActions { connection_id }
+            | GameMessage::GetTimeRemaining { connection_id }
+            | GameMessage::ViewDiscardPile { connection_id, .. }
+            | GameMessage::GetEventLog { connection_id, .. }
+            | GameMessage::GetPrivateLog { connection_id, .. }
+            | GameMessage::MulliganDecision { connection_id, .. }
+            | GameMessage::ActivateItem { connection_id, .. }
+            | GameMessage::BuyItem { connection_id, .. }
+            | GameMessage::PlayLootCard { connection_id, .. }
+            | GameMessage::ChoiceResponse { connection_id, .. }
+            | GameMessage::ProposeTrade { connection_id, .. }
+            | GameMessage::RespondToTrade { connection_id, .. }
+            | GameMessage::RequestUndo { connection_id }
+            | GameMessage::RespondToUndo { connection_id, .. }
+            | GameMessage::Concede { connection_id }
+            | GameMessage::OfferDraw { connection_id }
+            | GameMessage::RespondToDraw { connection_id, .. }
+            | GameMessage::WithdrawDraw { connection_id }
+            | GameMessage::ImBack { connection_id }
+            | GameMessage::Reconnect { connection_id, .. }
+            | GameMessage::DumpState { connection_id }
+            | GameMessage::SetPaused { connection_id, .. }
+            | GameMessage::AddSpectator { connection_id }
+            | GameMessage::RemoveSpectator { connection_id } => Some(connection_id),
+            GameMessage::Shutdown { .. } => None,
+        }
+    }
+
+    /// The game's form of a `GameMessage` category client message, sent on `connection_id`
+    /// The `Game` wrapper is looked through, its `game_id` is for routing to the right actor.
+    pub fn from_client_message(connection_id: String, message: ClientMessage) -> AppResult<Self> {
+        match message {
+            ClientMessage::Game { message, .. } => {
+                Self::from_client_message(connection_id, *message)
+            }
+            ClientMessage::TurnPass => Ok(GameMessage::TurnPass { connection_id }),
+            ClientMessage::PriorityPass => Ok(GameMessage::PriorityPass { connection_id }),
+            ClientMessage::GetSimpleState => Ok(GameMessage::GetSimpleState { connection_id }),
+            ClientMessage::GetLegalActions => Ok(GameMessage::GetLegalActions { connection_id }),
+            ClientMessage::GetTimeRemaining => Ok(GameMessage::GetTimeRemaining { connection_id }),
+            ClientMessage::ViewDiscardPile { deck_type } => Ok(GameMessage::ViewDiscardPile {
+                connection_id,
+                deck_type,
+            }),
+            ClientMessage::GetEventLog { since } => Ok(GameMessage::GetEventLog {
+                connection_id,
+                since,
+            }),
+            ClientMessage::GetPrivateLog { since } => Ok(GameMessage::GetPrivateLog {
+                connection_id,
+                since,
+            }),
+            ClientMessage::MulliganDecision { keep } => Ok(GameMessage::MulliganDecision {
+                connection_id,
+                keep,
+            }),
+            ClientMessage::ActivateItem { item_id } => Ok(GameMessage::ActivateItem {
+                connection_id,
+                item_id,
+            }),
+            ClientMessage::BuyItem { shop_index } => Ok(GameMessage::BuyItem {
+                connection_id,
+                shop_index,
+            }),
+            ClientMessage::PlayLootCard {
+                card_id,
+                tap_character,
+            } => Ok(GameMessage::PlayLootCard {
+                connection_id,
+                card_id,
+                tap_character,
+            }),
+            ClientMessage::ChoiceResponse {
+                choice_id,
+                option_id,
+            } => Ok(GameMessage::ChoiceResponse {
+                connection_id,
+                choice_id,
+                option_id,
+            }),
+            ClientMessage::ProposeTrade {
+                to_player,
+                offer,
+                request,
+            } => Ok(GameMessage::ProposeTrade {
+                connection_id,
+                to_player,
+                offer,
+                request,
+            }),
+            ClientMessage::AcceptTrade { trade_id } => Ok(GameMessage::RespondToTrade {
+                connection_id,
+                trade_id,
+                accept: true,
+            }),
+            ClientMessage::DeclineTrade { trade_id } => Ok(GameMessage::RespondToTrade {
+                connection_id,
+                trade_id,
+                accept: false,
+            }),
+            ClientMessage::RequestUndo => Ok(GameMessage::RequestUndo { connection_id }),
+            ClientMessage::AcceptUndo => Ok(GameMessage::RespondToUndo {
+                connection_id,
+                accept: true,
+            }),
+            ClientMessage::DeclineUndo => Ok(GameMessage::RespondToUndo {
+                connection_id,
+                accept: false,
+            }),
+            ClientMessage::Concede => Ok(GameMessage::Concede { connection_id }),
+            ClientMessage::OfferDraw => Ok(GameMessage::OfferDraw { connection_id }),
+            ClientMessage::AcceptDraw => Ok(GameMessage::RespondToDraw {
+                connection_id,
+                accept: true,
+            }),
+            ClientMessage::DeclineDraw => Ok(GameMessage::RespondToDraw {
+                connection_id,
+                accept: false,
+            }),
+            ClientMessage::WithdrawDraw => Ok(GameMessage::WithdrawDraw { connection_id }),
+            ClientMessage::ImBack => Ok(GameMessage::ImBack { connection_id }),
+            _ => Err(AppError::Internal {
+                message: "Invalid game message conversion".to_string(),
+            }),
+        }
+    }
+}
+
+/// Server-wide facilities shared by every game actor
+#[derive(Clone)]
+pub struct GameServices {
+    pub game_store: Option<Arc<dyn GameStore>>,
+    pub snapshots: Arc<GameSnapshots>,
+    pub ratings: Arc<Ratings>,
+    pub summaries: Arc<GameSummaries>,
+    pub lobby: LobbyShards,
+    pub game_config: GameConfig,
+    pub locales: Arc<ConnectionLocales>,
+    pub metrics: Arc<ServerMetrics>,
+    pub webhooks: Arc<Webhooks>,
+    pub replay_dir: Option<PathBuf>, // finished games write their replay here
+    pub dump_dir: Option<PathBuf>,   // DumpGameState also writes each dump here
+    pub replay_key: Option<ReplayKey>, // replays are recorded unsigned without one
+}
+
+/// Deadlines the actor holds players to, standing still while the game is paused
+struct TurnTimers {
+    mulligan: Instant,
+    choice: Option<(String, Instant)>, // (choice_id, deadline) of the choice currently waited on
+    turn: Option<(u32, Instant)>,      // (turn counter, deadline) of the running turn
+    priority: Option<(PriorityWindow, Instant)>,
+    paused: Option<(String, Instant)>, // who paused and when
+}
+
+impl TurnTimers {
+    fn new(mulligan: Instant) -> Self {
+        Self {
+            mulligan,
+            choice: None,
+            turn: None,
+            priority: None,
+            paused: None,
+        }
+    }
+
+    fn pause(&mut self, player_id: String) -> AppResult<()> {
+        if self.paused.is_some() {
+            return Err(AppError::GamePaused);
+        }
+        self.paused = Some((player_id, Instant::now()));
+        Ok(())
+    }
+
+    /// Push every deadline back by the time spent paused, which is returned
+    fn resume(&mut self) -> AppResult<Duration> {
+        let (_, paused_at) = self.paused.take().ok_or(AppError::GameNotPaused)?;
+        let paused_for = paused_at.elapsed();
+        self.mulligan += paused_for;
+        if let Some((_, deadline)) = &mut self.choice {
+            *deadline += paused_for;
+        }
+        if let Some((_, deadline)) = &mut self.turn {
+            *deadline += paused_for;
+        }
+        if let Some((_, deadline)) = &mut self.priority {
+            *deadline += paused_for;
+        }
+        Ok(paused_for)
+    }
+
+    fn paused_by(&self) -> Option<&str> {
+        self.paused
+            .as_ref()
+            .map(|(player_id, _)| player_id.as_str())
+    }
+
+    /// What's left of every running window; the clock stands still while the game is paused
+    fn time_remaining(&self) -> ServerResponse {
+        let now = unix_millis();
+        let counted_from = self
+            .paused
+            .as_ref()
+            .map_or_else(Instant::now, |(_, at)| *at);
+        let timer = |deadline: Instant| {
+            let remaining = deadline.saturating_duration_since(counted_from).as_millis() as u64;
+            WindowTimer::at(now + remaining, now)
+        };
+        ServerResponse::TimeRemaining {
+            turn: self.turn.map(|(_, deadline)| timer(deadline)),
+            priority: self.priority.as_ref().map(|(_, deadline)| timer(*deadline)),
+            choice: self.choice.as_ref().map(|(_, deadline)| timer(*deadline)),
+            paused: self.paused.is_some(),
+        }
+    }
+}
+
+/// A write to the game store
+enum StoreWrite {
+    Save(Box<SavedGame>),
+    Append(Box<GameProgress>),
+    Remove,
+}
+
+/// Writes a game out on the blocking pool, one write after the other in the order they were
+/// made, so file IO never holds up the game actor
+struct StoreWriter {
+    sender: mpsc::UnboundedSender<StoreWrite>,
+    task: JoinHandle<()>,
+}
+
+impl StoreWriter {
+    fn spawn(game_store: Arc<dyn GameStore>, game_id: String) -> Self {
+        let (sender, mut writes) = mpsc::unbounded_channel();
+        let task = tokio::spawn(async move {
+            while let Some(write) = writes.recv().await {
+                let game_store = game_store.clone();
+                let id = game_id.clone();
+                let written = tokio::task::spawn_blocking(move || match write {
+                    StoreWrite::Save(saved_game) => game_store.save(&saved_game),
+                    StoreWrite::Append(progress) => game_store.append(&id, &progress),
+                    StoreWrite::Remove => game_store.remove(&id),
+                })
+                .await;
+                match written {
+                    Ok(Err(e)) => eprintln!("Failed to save game {}: {:?}", game_id, e),
+                    Err(e) => eprintln!("Saving game {} panicked: {:?}", game_id, e),
+                    Ok(Ok(())) => {}
+                }
+            }
+        });
+        Self { sender, task }
+    }
+
+    fn write(&self, write: StoreWrite) {
+        let _ = self.sender.send(write);
+    }
+
+    /// Wait for every write made so far
+    async fn finish(self) {
+        drop(self.sender);
+        let _ = self.task.await;
+    }
+}
+
+/// How much of the game its last save holds, so the next one only appends what's new
+#[derive(Default)]
+struct SavedUpTo {
+    checkpoint: u64,
+    events: usize,
+    replay_events: usize,
+    signatures: usize,
+    log: LogMark,
+    appends: usize, // since the checkpoint
+}
+
+// The moment a deadline announced to clients in unix millis falls on
+fn instant_at(deadline: u64) -> Instant {
+    Instant::now() + Duration::from_millis(deadline.saturating_sub(unix_millis()))
 }
 
 pub struct GameActor {
     game_id: String,
     coordinator: GameCoordinator,
+    speed: GameSpeed,
+    timers: TurnTimers,
     connection_to_player_mapping: HashMap<String, String>, // connection_id -> player_id
     player_to_connection_mapping: HashMap<String, String>, // player_id -> connection_id
-    cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+    sessions: HashMap<String, String>,                     // session token -> player_id
+    timed_out_turns: HashMap<String, u32>, // player_id -> turns in a row they let run out
+    afk_players: HashSet<String>,
+    rated_players: HashMap<String, PlayerProfile>, // player_id -> profile
+    services: GameServices,
+    restored: bool,
+    notified_turn: Option<u32>, // the last turn the room's webhook heard about
+    saved: SavedUpTo,
+    store_writer: Option<StoreWriter>, // started with the game, when there's a store
+    messenger: Messenger,
 }
 
 impl GameActor {
     pub fn new(
         game_id: String,
         players_id_to_connection_id: HashMap<String, String>,
-        turn_order: TurnOrder,
-        cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+        opening: GameOpening,
+        sessions: HashMap<String, String>,
+        rated_players: HashMap<String, PlayerProfile>,
+        services: GameServices,
+        messenger: Messenger,
     ) -> Self {
         // Reverse the mapping for quick lookup
         let connection_to_player_mapping: HashMap<String, String> = players_id_to_connection_id
@@ -34,41 +493,214 @@ impl GameActor {
 
         let player_to_connection_mapping = players_id_to_connection_id.clone();
 
-        let coordinator =
-            GameCoordinator::new(players_id_to_connection_id, turn_order, cmd_sender.clone());
+        let mut coordinator = GameCoordinator::with_seed(
+            players_id_to_connection_id,
+            opening.turn_order,
+            messenger.clone(),
+            opening.seed,
+            &opening.options.card_sets(),
+            &opening.options.rules,
+        );
+        if services.game_config.mulligan {
+            coordinator.enable_mulligan(services.game_config.mulligan_timeout_secs);
+        }
+        let speed = opening.options.speed;
+        coordinator.set_timers(services.game_config.speed_timers(speed));
+        coordinator.set_player_names(opening.player_names);
+        if let Some(coin_cap) = opening.options.coin_cap() {
+            coordinator.enable_coin_cap(coin_cap);
+        }
+        if opening.options.undo {
+            coordinator.enable_undo();
+        }
+        if let Some(key) = services.replay_key.clone() {
+            coordinator.enable_replay_signing(key);
+        }
+        coordinator.set_spectator_delay(opening.options.spectator_delay());
 
         Self {
             game_id,
             coordinator,
+            speed,
+            timers: TurnTimers::new(Instant::now()),
             connection_to_player_mapping,
             player_to_connection_mapping,
-            cmd_sender,
+            sessions,
+            timed_out_turns: HashMap::new(),
+            afk_players: HashSet::new(),
+            rated_players,
+            services,
+            restored: false,
+            notified_turn: None,
+            saved: SavedUpTo::default(),
+            store_writer: None,
+            messenger,
         }
     }
 
-    pub async fn run(&mut self, mut receiver: mpsc::UnboundedReceiver<GameMessage>) {
+    /// The card database its decks were built from
+    pub fn card_set_version(&self) -> &str {
+        &self.coordinator.game_state().board.card_set_version
+    }
+
+    /// Rebuild a saved game. Players come back one by one through `Reconnect`.
+    pub fn restore(
+        saved_game: SavedGame,
+        services: GameServices,
+        messenger: Messenger,
+    ) -> AppResult<Self> {
+        let mut coordinator = GameCoordinator::restore(
+            saved_game.game_state,
+            saved_game.replay,
+            saved_game.stats,
+            saved_game.event_log,
+            saved_game.events,
+            messenger.clone(),
+            services.replay_key.clone(),
+        )?;
+        let speed = saved_game.speed;
+        coordinator.set_timers(services.game_config.speed_timers(speed));
+        coordinator.set_spectator_delay(Duration::from_secs(saved_game.spectator_delay_secs));
+
+        Ok(Self {
+            game_id: saved_game.game_id,
+            coordinator,
+            speed,
+            timers: TurnTimers::new(Instant::now()),
+            connection_to_player_mapping: HashMap::new(),
+            player_to_connection_mapping: HashMap::new(),
+            sessions: saved_game.sessions,
+            timed_out_turns: HashMap::new(),
+            afk_players: HashSet::new(),
+            rated_players: saved_game.rated_players,
+            services,
+            restored: true,
+            notified_turn: None,
+            saved: SavedUpTo {
+                checkpoint: saved_game.checkpoint,
+                ..SavedUpTo::default()
+            },
+            store_writer: None,
+            messenger,
+        })
+    }
+
+    pub async fn run(&mut self, mut receiver: mpsc::Receiver<GameMessage>) {
         println!("🎮 Game actor started for game {}", self.game_id);
 
-        self.coordinator.initialize_game().await;
+        self.store_writer = self
+            .services
+            .game_store
+            .clone()
+            .map(|game_store| StoreWriter::spawn(game_store, self.game_id.clone()));
+        if !self.restored {
+            self.coordinator.initialize_game().await;
+            self.send_session_tokens();
+        }
+        // A restored game folds what it caught up on into a fresh checkpoint
+        self.checkpoint();
+        let mut shutting_down = false;
+        // Restored games give players a fresh window since they have to reconnect first
+        self.timers.mulligan = Instant::now() + self.services.game_config.mulligan_timeout();
+        // Phase the game is in and when it got there, for the phase duration metrics
+        let mut phase = (
+            self.coordinator.game_state().current_phase.clone(),
+            Instant::now(),
+        );
 
         // Main message loop
         while self.coordinator.is_running() {
+            let current_phase = &self.coordinator.game_state().current_phase;
+            if *current_phase != phase.0 {
+                let (previous, since) =
+                    std::mem::replace(&mut phase, (current_phase.clone(), Instant::now()));
+                self.services
+                    .metrics
+                    .record_phase(previous, since.elapsed());
+            }
+            // Windows are timed from the deadlines players were told about
+            self.coordinator.refresh_windows();
+            let pending_choice = self
+                .coordinator
+                .pending_choice()
+                .map(|choice| (choice.choice_id.clone(), choice.player_id.clone()));
+            let pending_choice_id = pending_choice.as_ref().map(|(id, _)| id.clone());
+            if pending_choice_id != self.timers.choice.as_ref().map(|(id, _)| id.clone()) {
+                self.timers.choice = pending_choice.map(|(choice_id, player_id)| {
+                    // AFK players' choices take their default right away
+                    let deadline = if self.afk_players.contains(&player_id) {
+                        Instant::now()
+                    } else {
+                        self.coordinator.windows().choice_deadline().map_or_else(
+                            || Instant::now() + self.speed_timers().choice_timeout(),
+                            instant_at,
+                        )
+                    };
+                    (choice_id, deadline)
+                });
+            }
+            let turn = self.coordinator.game_state().turn_order.get_turn_counter();
+            if self.timers.turn.map(|(timed_turn, _)| timed_turn) != Some(turn) {
+                self.timers.turn = self.turn_deadline(turn);
+            }
+            if self.notified_turn != Some(turn) {
+                self.notified_turn = Some(turn);
+                self.notify_turn(turn);
+            }
+            let window = self.priority_window();
+            if window.as_ref() != self.timers.priority.as_ref().map(|(window, _)| window) {
+                self.timers.priority = window.and_then(|window| self.priority_deadline(window));
+            }
+            let mulligan_expires_at = self.timers.mulligan;
+            let choice_expires_at = self
+                .timers
+                .choice
+                .as_ref()
+                .map_or_else(Instant::now, |(_, deadline)| *deadline);
+            let turn_expires_at = self
+                .timers
+                .turn
+                .map_or_else(Instant::now, |(_, deadline)| deadline);
+            let priority_expires_at = self
+                .timers
+                .priority
+                .as_ref()
+                .map_or_else(Instant::now, |(_, deadline)| *deadline);
+            let paused = self.timers.paused.is_some();
+
             tokio::select! {
                 // Handle incoming game messages
                 message = receiver.recv() => {
                     match message {
+                        Some(GameMessage::Shutdown { snapshot_dir }) => {
+                            println!("🎮 Game actor {} shutting down", self.game_id);
+                            if let Some(snapshot_dir) = snapshot_dir {
+                                self.save_replay_to(&snapshot_dir);
+                            }
+                            shutting_down = true;
+                            break;
+                        }
                         Some(game_message) => {
-                            if let Err(error) = self.handle_message(game_message.clone()).await {
+                            let (game_message, request) = game_message.unbatched();
+                            let result = self.handle_message(game_message.clone()).await;
+                            if let Err(error) = &result {
                                 eprintln!("Game actor error in {}: {:?}", self.game_id, error);
-                                // TODO: Need more friendly syntax
-                                let connection_id = match &game_message {
-                                    GameMessage::TurnPass { connection_id } => connection_id,
-                                    // GameMessage::PriorityPass { connection_id } => connection_id,
-                                };
-                                let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayer {
-                                    connection_id: connection_id.to_string(),
-                                    message: serialize_response(ServerResponse::from_app_error(&error)),
-                                });
+                            }
+                            let locale = game_message
+                                .connection_id()
+                                .map(|connection_id| self.services.locales.get(connection_id))
+                                .unwrap_or_default();
+                            match (result, request) {
+                                (result, Some(request)) => request.answer(&result, locale),
+                                (Err(error), None) => {
+                                    if let Some(connection_id) = game_message.connection_id() {
+                                        let _ = self.messenger.send_to_connection(
+                                            connection_id,
+                                            ServerResponse::localized_error(&error, locale),
+                                        );
+                                    }
+                                }
+                                (Ok(()), None) => {}
                             }
                         }
                         None => {
@@ -78,54 +710,687 @@ impl GameActor {
                     }
                 }
 
+                _ = sleep_until(mulligan_expires_at), if !paused && self.coordinator.game_state().is_mulligan_pending() => {
+                    self.expire_mulligans().await;
+                }
+
+                _ = sleep_until(choice_expires_at), if !paused && self.timers.choice.is_some() => {
+                    self.expire_choice().await;
+                }
+
+                _ = sleep_until(turn_expires_at), if !paused && self.timers.turn.is_some() => {
+                    self.expire_turn().await;
+                }
+
+                _ = sleep_until(priority_expires_at), if !paused && self.timers.priority.is_some() => {
+                    self.expire_priority().await;
+                }
+
                 // Future enhancements:
                 // - Game tick timer
             }
         }
 
+        // Keep the save across a shutdown so the game can be restored on the next start
+        if !shutting_down {
+            self.forget_saved_game();
+        }
+        // Whatever is still being written is on disk before the game is reported gone
+        if let Some(store_writer) = self.store_writer.take() {
+            store_writer.finish().await;
+        }
+        self.services
+            .metrics
+            .record_phase(phase.0, phase.1.elapsed());
+        if !self.coordinator.is_running() {
+            self.services.metrics.game_finished();
+        }
+        self.record_ratings();
+        self.record_summary();
+        // Hand the room back to the lobby once the game is over
+        if !self.coordinator.is_running() {
+            let _ = self.services.lobby.deliver(LobbyMessage::GameEnded {
+                game_id: self.game_id.clone(),
+                winner_id: self.coordinator.winner().map(str::to_string),
+            });
+            let winner_name = self
+                .coordinator
+                .winner()
+                .map(|winner_id| self.coordinator.event_log().name(winner_id).to_string());
+            self.services
+                .webhooks
+                .notify(&self.game_id, WebhookEvent::GameEnded { winner_name });
+        }
+        self.save_replay();
         println!("🎮 Game actor ended for game {}", self.game_id);
     }
 
+    fn notify_turn(&self, turn: u32) {
+        let player_id = &self.coordinator.game_state().turn_order.active_player_id;
+        self.services.webhooks.notify(
+            &self.game_id,
+            WebhookEvent::YourTurn {
+                player_id: player_id.clone(),
+                player_name: self.coordinator.event_log().name(player_id).to_string(),
+                turn,
+            },
+        );
+    }
+
+    async fn expire_mulligans(&mut self) {
+        for player_id in self.coordinator.pending_mulligans() {
+            let event = GameEvent::MulliganDecision {
+                player_id,
+                keep: true,
+            };
+            if let Err(e) = self.coordinator.handle_event(event).await {
+                eprintln!("Failed to expire mulligan in {}: {:?}", self.game_id, e);
+            }
+        }
+        self.persist();
+    }
+
+    async fn expire_choice(&mut self) {
+        let Some(choice) = self.coordinator.pending_choice() else {
+            return;
+        };
+        let Some(option) = choice.default_option() else {
+            return;
+        };
+        let event = GameEvent::ChoiceResponse {
+            player_id: choice.player_id.clone(),
+            choice_id: choice.choice_id.clone(),
+            option_id: option.option_id.clone(),
+        };
+        if let Err(e) = self.coordinator.handle_event(event).await {
+            eprintln!("Failed to expire choice in {}: {:?}", self.game_id, e);
+        }
+        self.persist();
+    }
+
+    /// None without a turn limit. An AFK player's turn is up as soon as it starts, unless
+    /// everyone is AFK: the game then waits out each turn instead of racing to its end.
+    fn turn_deadline(&self, turn: u32) -> Option<(u32, Instant)> {
+        let announced = self.coordinator.windows().turn_deadline()?;
+        let turn_order = &self.coordinator.game_state().turn_order;
+        let someone_present = turn_order
+            .order
+            .iter()
+            .any(|player_id| !self.afk_players.contains(player_id));
+        let deadline = if someone_present && self.afk_players.contains(&turn_order.active_player_id)
+        {
+            Instant::now()
+        } else {
+            instant_at(announced)
+        };
+        Some((turn, deadline))
+    }
+
+    async fn expire_turn(&mut self) {
+        let Some((turn, _)) = self.timers.turn else {
+            return;
+        };
+        let player_id = self
+            .coordinator
+            .game_state()
+            .turn_order
+            .active_player_id
+            .clone();
+        let event = GameEvent::TurnPass {
+            player_id: player_id.clone(),
+        };
+        if let Err(e) = self.coordinator.handle_event(event).await {
+            // Usually a choice or the mulligan is still open, give the turn another full timeout
+            eprintln!("Failed to expire turn in {}: {:?}", self.game_id, e);
+            self.timers.turn = self
+                .speed_timers()
+                .turn_timeout()
+                .map(|timeout| (turn, Instant::now() + timeout));
+            return;
+        }
+        self.persist();
+
+        if !self.afk_players.contains(&player_id) {
+            let timed_out = self.timed_out_turns.entry(player_id.clone()).or_default();
+            *timed_out += 1;
+            if *timed_out >= self.services.game_config.afk_after_timeouts {
+                self.set_afk(player_id, true);
+            }
+        }
+    }
+
+    fn speed_timers(&self) -> SpeedTimers {
+        self.services.game_config.speed_timers(self.speed)
+    }
+
+    fn priority_window(&self) -> Option<PriorityWindow> {
+        deadlines::priority_window(self.coordinator.game_state())
+    }
+
+    /// None without a priority limit; AFK holders pass right away
+    fn priority_deadline(&self, window: PriorityWindow) -> Option<(PriorityWindow, Instant)> {
+        let announced = self.coordinator.windows().priority_deadline()?;
+        let deadline = if self.afk_players.contains(&window.2) {
+            Instant::now()
+        } else {
+            instant_at(announced)
+        };
+        Some((window, deadline))
+    }
+
+    async fn expire_priority(&mut self) {
+        let Some(((_, _, player_id, _), _)) = self.timers.priority.clone() else {
+            return;
+        };
+        let event = GameEvent::PriorityPass { player_id };
+        if let Err(e) = self.coordinator.handle_event(event).await {
+            // Give the window another full timeout rather than retrying right away
+            eprintln!("Failed to expire priority in {}: {:?}", self.game_id, e);
+            let timeout = self.speed_timers().priority_timeout();
+            if let (Some((_, deadline)), Some(timeout)) = (&mut self.timers.priority, timeout) {
+                *deadline = Instant::now() + timeout;
+            }
+            return;
+        }
+        self.persist();
+    }
+
+    /// A conceded player's connection can't act in the game any more; the lobby sends it back
+    /// to the room, where it keeps watching until the game is over
+    fn unseat(&mut self, player_id: &str) {
+        self.sessions.retain(|_, seated| seated != player_id);
+        self.afk_players.remove(player_id);
+        self.timed_out_turns.remove(player_id);
+        if let Some(connection_id) = self.player_to_connection_mapping.remove(player_id) {
+            self.connection_to_player_mapping.remove(&connection_id);
+            let _ = self.services.lobby.deliver(LobbyMessage::PlayerConceded {
+                game_id: self.game_id.clone(),
+                connection_id,
+            });
+        }
+    }
+
+    fn set_afk(&mut self, player_id: String, afk: bool) {
+        self.timed_out_turns.remove(&player_id);
+        let changed = if afk {
+            self.afk_players.insert(player_id.clone())
+        } else {
+            self.afk_players.remove(&player_id)
+        };
+        if changed {
+            println!(
+                "💤 Player {} in game {} AFK: {}",
+                player_id, self.game_id, afk
+            );
+            let _ = self
+                .messenger
+                .broadcast_to_room(&self.game_id, ServerResponse::PlayerAfk { player_id, afk });
+        }
+    }
+
+    /// Only for games that ended, with a winner or drawn, not ones stopped by a shutdown
+    fn record_ratings(&self) {
+        if self.coordinator.is_running() {
+            return;
+        }
+        // Conceding counts as a loss
+        let state = self.coordinator.game_state();
+        if let Err(e) = self.services.ratings.record_game(
+            self.coordinator.winner(),
+            &state.turn_order.order,
+            &state.conceded,
+            &self.rated_players,
+        ) {
+            eprintln!("Failed to record ratings for {}: {:?}", self.game_id, e);
+        }
+    }
+
+    fn record_summary(&self) {
+        if self.coordinator.is_running() {
+            return;
+        }
+        self.services.summaries.insert(GameSummary {
+            game_id: self.game_id.clone(),
+            winner_id: self.coordinator.winner().map(str::to_string),
+            turn_order: self.coordinator.game_state().turn_order.order.clone(),
+            stats: self.coordinator.stats().clone(),
+        });
+    }
+
+    fn send_session_tokens(&self) {
+        for (session_token, player_id) in &self.sessions {
+            let _ = self.messenger.send_to_player_id(
+                player_id,
+                ServerResponse::GameSession {
+                    game_id: self.game_id.clone(),
+                    player_id: player_id.clone(),
+                    session_token: session_token.clone(),
+                },
+            );
+        }
+    }
+
+    /// Append what happened since the last save, or write the whole game out again every so
+    /// often and whenever an undo took back what was saved
+    fn persist(&mut self) {
+        let replay = self.coordinator.replay();
+        let events = self.coordinator.events();
+        let rewound = events.len() < self.saved.events
+            || replay.events.len() < self.saved.replay_events
+            || replay.signatures.len() < self.saved.signatures;
+        if rewound || self.saved.appends >= APPENDS_PER_CHECKPOINT {
+            self.checkpoint();
+            return;
+        }
+        let progress = GameProgress {
+            checkpoint: self.saved.checkpoint,
+            events: events.events()[self.saved.events..].to_vec(),
+            replay_from: self.saved.replay_events,
+            replay_events: replay.events[self.saved.replay_events..].to_vec(),
+            signatures_from: self.saved.signatures,
+            signatures: replay.signatures[self.saved.signatures..].to_vec(),
+            stats: self.coordinator.stats().clone(),
+            log: self.coordinator.event_log().progress_since(&self.saved.log),
+        };
+        if progress.is_empty() {
+            return;
+        }
+        self.saved = SavedUpTo {
+            checkpoint: self.saved.checkpoint,
+            events: events.len(),
+            replay_events: replay.events.len(),
+            signatures: replay.signatures.len(),
+            log: self.coordinator.event_log().mark(),
+            appends: self.saved.appends + 1,
+        };
+        if let Some(store_writer) = &self.store_writer {
+            store_writer.write(StoreWrite::Append(Box::new(progress.clone())));
+        }
+        self.services.snapshots.append(&self.game_id, progress);
+    }
+
+    /// Write the whole game out, the point later progress is appended to
+    fn checkpoint(&mut self) {
+        let replay = self.coordinator.replay();
+        self.saved = SavedUpTo {
+            checkpoint: self.saved.checkpoint + 1,
+            events: self.coordinator.events().len(),
+            replay_events: replay.events.len(),
+            signatures: replay.signatures.len(),
+            log: self.coordinator.event_log().mark(),
+            appends: 0,
+        };
+        let saved_game = SavedGame {
+            game_id: self.game_id.clone(),
+            game_state: self.coordinator.game_state().clone(),
+            replay: replay.clone(),
+            sessions: self.sessions.clone(),
+            rated_players: self.rated_players.clone(),
+            stats: self.coordinator.stats().clone(),
+            event_log: self.coordinator.event_log().clone(),
+            events: self.coordinator.events().clone(),
+            speed: self.speed,
+            spectator_delay_secs: self.coordinator.spectator_delay().as_secs(),
+            checkpoint: self.saved.checkpoint,
+        };
+        if let Some(store_writer) = &self.store_writer {
+            store_writer.write(StoreWrite::Save(Box::new(saved_game.clone())));
+        }
+        self.services.snapshots.save(GameSnapshot {
+            saved_game,
+            players_id_to_connection_id: self.player_to_connection_mapping.clone(),
+        });
+    }
+
+    fn forget_saved_game(&self) {
+        self.services.snapshots.remove(&self.game_id);
+        if let Some(store_writer) = &self.store_writer {
+            store_writer.write(StoreWrite::Remove);
+        }
+    }
+
+    async fn reconnect(&mut self, connection_id: String, player_id: String) {
+        if let Some(old_connection_id) = self
+            .player_to_connection_mapping
+            .insert(player_id.clone(), connection_id.clone())
+        {
+            self.connection_to_player_mapping.remove(&old_connection_id);
+        }
+        self.connection_to_player_mapping
+            .insert(connection_id.clone(), player_id.clone());
+        self.messenger
+            .join_room(&self.game_id, &player_id, &connection_id);
+
+        println!(
+            "🔁 Player {} resumed game {} on connection {}",
+            player_id, self.game_id, connection_id
+        );
+        self.coordinator
+            .reconnect_player(&player_id, connection_id.clone())
+            .await;
+        if let Some(paused_by) = self.timers.paused_by() {
+            let _ = self.messenger.send_to_connection(
+                &connection_id,
+                ServerResponse::GamePaused {
+                    player_id: paused_by.to_string(),
+                },
+            );
+        }
+        // Keep the crash snapshot pointing at the new connection
+        self.persist();
+        self.services
+            .snapshots
+            .reseat(&self.game_id, self.player_to_connection_mapping.clone());
+    }
+
+    // Replays are only written when replay_dir is set
+    fn save_replay(&self) {
+        if let Some(replay_dir) = &self.services.replay_dir {
+            self.save_replay_to(replay_dir);
+        }
+    }
+
+    fn save_replay_to(&self, dir: &Path) {
+        let path = dir.join(format!("{}.json", self.game_id));
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            eprintln!("Failed to create {}: {}", dir.display(), e);
+        }
+        match self.coordinator.replay().save(&path) {
+            Ok(()) => println!("💾 Replay saved to {}", path.display()),
+            Err(e) => eprintln!("Failed to save replay for {}: {:?}", self.game_id, e),
+        }
+    }
+
+    fn dump_state(&self, connection_id: String) -> AppResult<()> {
+        let dump =
+            serde_json::to_value(self.coordinator.debug_dump(DUMP_RECENT_EVENTS)).map_err(|e| {
+                AppError::SerializationError {
+                    message: e.to_string(),
+                }
+            })?;
+
+        let path = self.services.dump_dir.as_deref().and_then(|dir| {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis());
+            let path = dir.join(format!("{}-{}.json", self.game_id, timestamp));
+            let written = std::fs::create_dir_all(dir).and_then(|()| {
+                std::fs::write(
+                    &path,
+                    serde_json::to_string_pretty(&dump).unwrap_or_default(),
+                )
+            });
+            match written {
+                Ok(()) => {
+                    println!(
+                        "🩺 Debug dump of {} saved to {}",
+                        self.game_id,
+                        path.display()
+                    );
+                    Some(path.display().to_string())
+                }
+                Err(e) => {
+                    eprintln!("Failed to write debug dump to {}: {}", path.display(), e);
+                    None
+                }
+            }
+        });
+
+        self.messenger.send_to_connection(
+            &connection_id,
+            ServerResponse::GameStateDump {
+                game_id: self.game_id.clone(),
+                dump,
+                path,
+            },
+        )
+    }
+
     async fn handle_message(&mut self, message: GameMessage) -> AppResult<()> {
+        // The resuming connection isn't mapped to a player yet
+        if let GameMessage::Reconnect {
+            connection_id,
+            player_id,
+        } = message
+        {
+            self.reconnect(connection_id, player_id).await;
+            return Ok(());
+        }
+        if let GameMessage::DumpState { connection_id } = message {
+            return self.dump_state(connection_id);
+        }
+        if let GameMessage::AddSpectator { connection_id } = message {
+            return self.add_spectator(connection_id);
+        }
+        if let GameMessage::RemoveSpectator { connection_id } = message {
+            return self.remove_spectator(connection_id);
+        }
+
         println!("🎮 Game {} handling message: {:?}", self.game_id, message);
         println!(
             "🎮 Connection->Player mapping: {:?}",
             self.connection_to_player_mapping
         );
 
+        let player_id = self
+            .connection_to_player_mapping
+            .get(
+                message
+                    .connection_id()
+                    .ok_or(AppError::ConnectionNotInRoom)?,
+            )
+            .ok_or(AppError::ConnectionNotInRoom)?
+            .clone();
+
+        if let GameMessage::SetPaused { paused, .. } = message {
+            return self.set_paused(player_id, paused);
+        }
+        if let GameMessage::ImBack { .. } = message {
+            self.set_afk(player_id, false);
+            return Ok(());
+        }
+        // Only queries, conceding, resuming and ImBack get through a pause
+        let query = matches!(
+            message,
+            GameMessage::GetSimpleState { .. }
+                | GameMessage::GetLegalActions { .. }
+                | GameMessage::GetTimeRemaining { .. }
+                | GameMessage::ViewDiscardPile { .. }
+                | GameMessage::GetEventLog { .. }
+                | GameMessage::GetPrivateLog { .. }
+        );
+        if !query && !matches!(message, GameMessage::Concede { .. }) {
+            if self.timers.paused.is_some() {
+                return Err(AppError::GamePaused);
+            }
+            // Anything the player does shows they're still there
+            self.timed_out_turns.remove(&player_id);
+        }
+
         let game_event = match message {
-            GameMessage::TurnPass { connection_id } => {
-                let player_id = self
-                    .connection_to_player_mapping
-                    .get(&connection_id)
-                    .ok_or_else(|| AppError::ConnectionNotInRoom)?
-                    .clone();
-                GameEvent::TurnPass { player_id }
-            } // GameMessage::PriorityPass { connection_id } => {
-              //     let player_id = self
-              //         .connection_to_player_mapping
-              //         .get(&connection_id)
-              //         .ok_or_else(|| AppError::ConnectionNotInRoom)?
-              //         .clone();
-              //     GameEvent::PriorityPass { player_id }
-              // }
+            GameMessage::TurnPass { .. } => GameEvent::TurnPass { player_id },
+            GameMessage::PriorityPass { .. } => GameEvent::PriorityPass { player_id },
+            GameMessage::MulliganDecision { keep, .. } => {
+                GameEvent::MulliganDecision { player_id, keep }
+            }
+            GameMessage::ActivateItem { item_id, .. } => {
+                GameEvent::ActivateItem { player_id, item_id }
+            }
+            GameMessage::BuyItem { shop_index, .. } => GameEvent::BuyItem {
+                player_id,
+                shop_index,
+            },
+            GameMessage::PlayLootCard {
+                card_id,
+                tap_character,
+                ..
+            } => GameEvent::PlayLootCard {
+                player_id,
+                card_id,
+                tap_character,
+            },
+            GameMessage::ChoiceResponse {
+                choice_id,
+                option_id,
+                ..
+            } => GameEvent::ChoiceResponse {
+                player_id,
+                choice_id,
+                option_id,
+            },
+            GameMessage::ProposeTrade {
+                to_player,
+                offer,
+                request,
+                ..
+            } => GameEvent::ProposeTrade {
+                player_id,
+                to_player,
+                offer,
+                request,
+            },
+            GameMessage::RespondToTrade {
+                trade_id, accept, ..
+            } => GameEvent::RespondToTrade {
+                player_id,
+                trade_id,
+                accept,
+            },
+            // Queries answer the requesting player only and never touch game state
+            GameMessage::GetSimpleState { .. } => {
+                return self.coordinator.send_simple_state(&player_id).await;
+            }
+            GameMessage::GetLegalActions { .. } => {
+                return self.coordinator.send_legal_actions(&player_id).await;
+            }
+            GameMessage::GetTimeRemaining { connection_id } => {
+                return self
+                    .messenger
+                    .send_to_connection(&connection_id, self.timers.time_remaining());
+            }
+            GameMessage::ViewDiscardPile { deck_type, .. } => {
+                self.coordinator
+                    .send_discard_pile(&player_id, deck_type)
+                    .await;
+                return Ok(());
+            }
+            GameMessage::GetEventLog { since, .. } => {
+                self.coordinator.send_event_log(&player_id, since).await;
+                return Ok(());
+            }
+            GameMessage::GetPrivateLog { since, .. } => {
+                self.coordinator.send_private_log(&player_id, since).await;
+                return Ok(());
+            }
+            // Undo votes rewind the game rather than adding an event to it
+            GameMessage::RequestUndo { .. } => {
+                self.coordinator.request_undo(&player_id).await?;
+                self.persist();
+                return Ok(());
+            }
+            GameMessage::RespondToUndo { accept, .. } => {
+                self.coordinator.respond_to_undo(&player_id, accept).await?;
+                self.persist();
+                return Ok(());
+            }
+            // Same for draw votes, which end the game without an event
+            GameMessage::OfferDraw { .. } => {
+                self.coordinator.offer_draw(&player_id).await?;
+                self.persist();
+                return Ok(());
+            }
+            GameMessage::RespondToDraw { accept, .. } => {
+                self.coordinator.respond_to_draw(&player_id, accept).await?;
+                self.persist();
+                return Ok(());
+            }
+            GameMessage::WithdrawDraw { .. } => {
+                self.coordinator.withdraw_draw_offer(&player_id).await?;
+                self.persist();
+                return Ok(());
+            }
+            GameMessage::Concede { .. } => {
+                self.coordinator
+                    .handle_event(GameEvent::Concede {
+                        player_id: player_id.clone(),
+                    })
+                    .await?;
+                self.unseat(&player_id);
+                // Their session is gone, which only a checkpoint records
+                self.checkpoint();
+                return Ok(());
+            }
+            GameMessage::Shutdown { .. } => return Ok(()), // stops the loop in run()
+            GameMessage::Reconnect { .. }
+            | GameMessage::DumpState { .. }
+            | GameMessage::SetPaused { .. }
+            | GameMessage::AddSpectator { .. }
+            | GameMessage::RemoveSpectator { .. }
+            | GameMessage::ImBack { .. } => return Ok(()), // handled above
+            GameMessage::Batched { .. } => return Ok(()),  // unwrapped in run()
         };
 
         self.coordinator.handle_event(game_event).await?;
+        self.persist();
         Ok(())
     }
 
-    pub fn get_player_id_from_connection(&self, connection_id: &str) -> Option<String> {
-        self.connection_to_player_mapping
-            .get(connection_id)
-            .cloned()
+    // Players already see the game as it happens
+    fn add_spectator(&mut self, connection_id: String) -> AppResult<()> {
+        if self
+            .connection_to_player_mapping
+            .contains_key(&connection_id)
+        {
+            return Err(AppError::PermissionDenied {
+                action: "SpectateGame".to_string(),
+            });
+        }
+        self.messenger.send_to_connection(
+            &connection_id,
+            ServerResponse::SpectatingGame {
+                game_id: self.game_id.clone(),
+                delay_secs: self.coordinator.spectator_delay().as_secs(),
+            },
+        )?;
+        self.coordinator.add_spectator(connection_id);
+        Ok(())
+    }
+
+    fn remove_spectator(&mut self, connection_id: String) -> AppResult<()> {
+        if !self.coordinator.remove_spectator(&connection_id) {
+            return Err(AppError::ConnectionNotInRoom);
+        }
+        self.messenger.send_to_connection(
+            &connection_id,
+            ServerResponse::StoppedSpectating {
+                game_id: self.game_id.clone(),
+            },
+        )
     }
 
-    pub fn get_connection_from_player(&self, player_id: &str) -> Option<String> {
-        self.player_to_connection_mapping.get(player_id).cloned()
+    fn set_paused(&mut self, player_id: String, paused: bool) -> AppResult<()> {
+        if paused {
+            self.timers.pause(player_id.clone())?;
+            println!("⏸️ Game {} paused by {}", self.game_id, player_id);
+            return self
+                .messenger
+                .broadcast_to_room(&self.game_id, ServerResponse::GamePaused { player_id });
+        }
+        let paused_for = self.timers.resume()?;
+        // Windows announced later in the same turn carry the deadlines the actor now enforces
+        self.coordinator
+            .shift_windows(paused_for.as_millis() as u64);
+        println!("▶️ Game {} resumed by {}", self.game_id, player_id);
+        self.messenger
+            .broadcast_to_room(&self.game_id, ServerResponse::GameResumed { player_id })?;
+        // Every deadline moved back by the pause, clients restart their countdowns from here
+        self.messenger
+            .broadcast_to_room(&self.game_id, self.timers.time_remaining())
     }
 
-    pub fn get_all_connections(&self) -> Vec<String> {
-        self.connection_to_player_mapping.keys().cloned().collect()
+    pub fn get_player_id_from_connection(&self, connection_id: &str) -> Option<String> {
+        self.connection_to_player_mapping
+            .get(connection_id)
+            .cloned()
     }
 }
@@ -1,12 +1,39 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 use crate::actors::actor_registry::ActorRegistry;
-use crate::network::messages::{serialize_response, ServerResponse};
-use crate::{AppError, AppResult, ConnectionCommand, Room};
+use crate::actors::bot_actor::BotActor;
+use crate::actors::connection_actor::BatchRequest;
+use crate::actors::game_actor::GameMessage;
+use crate::actors::lobby_shards::{LobbyDirectory, HOME_SHARD};
+use crate::actors::tournament_actor::TournamentMessage;
+use crate::config::ServerConfig;
+use crate::game::{bot_strategy, card_loader};
+use crate::network::chat_history::{ChatEntry, ChatHistory};
+use crate::network::messages::{
+    AnnouncementSeverity, ChatChannel, KickReason, Secret, ServerResponse,
+};
+use crate::network::messenger::Messenger;
+use crate::network::metrics::Limit;
+use crate::network::permissions::{AdminKey, Role};
+use crate::network::profiles::{FriendPresence, PlayerProfile};
+use crate::network::protocol;
+use crate::network::room::{RoomOptions, RoomState};
+use crate::network::webhooks::WebhookEvent;
+use crate::{AppError, AppResult, Room};
+use uuid::Uuid;
+
+// How often rooms are checked for expiry, or every room_idle_ttl_secs when that is shorter
+const ROOM_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+const DEFAULT_LEADERBOARD_SIZE: usize = 10;
+const MAX_LEADERBOARD_SIZE: usize = 100;
+const MAX_ANNOUNCEMENT_LENGTH: usize = 500;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum LobbyMessage {
     Ping {
         connection_id: String,
@@ -19,6 +46,7 @@ pub enum LobbyMessage {
         connection_id: String,
         room_name: String,
         first_player_name: String,
+        options: RoomOptions,
     },
     DestroyRoom {
         connection_id: String,
@@ -35,6 +63,240 @@ pub enum LobbyMessage {
     PlayerReady {
         connection_id: String,
     },
+    PlayerUnready {
+        connection_id: String,
+    },
+    ReloadCards {
+        connection_id: String,
+        admin_key: Secret,
+    },
+    GetProtocolInfo {
+        connection_id: String,
+    },
+    GetCardCatalog {
+        connection_id: String,
+        known_version: Option<String>,
+    },
+    GetGlossary {
+        connection_id: String,
+    },
+    AddBot {
+        connection_id: String,
+        strategy: Option<String>,
+    },
+    StartSoloGame {
+        connection_id: String,
+        player_name: String,
+        bots: usize,
+        strategy: Option<String>,
+        options: RoomOptions,
+    },
+    LobbyChat {
+        connection_id: String,
+        message: String,
+    },
+    SetChatMuted {
+        connection_id: String,
+        channel: ChatChannel,
+        muted: bool,
+    },
+    SetPlayerMuted {
+        connection_id: String,
+        player_id: String,
+        muted: bool,
+    },
+    KickPlayer {
+        connection_id: String,
+        player_id: String,
+        ban: bool,
+    },
+    SetRoomWebhook {
+        connection_id: String,
+        url: Option<String>,
+    },
+    RegisterProfile {
+        connection_id: String,
+        display_name: String,
+        avatar_id: Option<String>,
+    },
+    RestoreProfile {
+        connection_id: String,
+        token: Secret,
+    },
+    AddFriend {
+        connection_id: String,
+        profile_id: String,
+    },
+    RemoveFriend {
+        connection_id: String,
+        profile_id: String,
+    },
+    GetFriends {
+        connection_id: String,
+    },
+    InviteToRoom {
+        connection_id: String,
+        profile_id: String,
+    },
+    ResumeSession {
+        connection_id: String,
+        session_token: Secret,
+    },
+    GetLeaderboard {
+        connection_id: String,
+        limit: Option<usize>,
+    },
+    GetGameSummary {
+        connection_id: String,
+        game_id: String,
+    },
+    DumpGameState {
+        connection_id: String,
+        game_id: String,
+        admin_key: Secret,
+    },
+    SetGamePaused {
+        connection_id: String,
+        paused: bool,
+    },
+    SetSpectating {
+        connection_id: String,
+        room_id: String,
+        spectating: bool,
+    },
+    Announce {
+        connection_id: String,
+        admin_key: Secret,
+        text: String,
+        severity: AnnouncementSeverity,
+        send_at: Option<u64>,
+    },
+    CancelAnnouncement {
+        connection_id: String,
+        admin_key: Secret,
+        announcement_id: String,
+    },
+    // Sent by an announcement's timer when its time has come
+    AnnouncementDue {
+        announcement_id: String,
+        text: String,
+        severity: AnnouncementSeverity,
+    },
+    ConnectionOpened {
+        connection_id: String,
+    },
+    // Sent by a game actor once its game is over
+    GameEnded {
+        game_id: String,
+        winner_id: Option<String>,
+    },
+    // Sent by the tournament actor for each pairing of a round; seats are
+    // (connection_id, player_name), higher seed first
+    StartTournamentMatch {
+        tournament_id: String,
+        match_id: String,
+        room_name: String,
+        seats: Vec<(String, String)>,
+        options: RoomOptions,
+    },
+    // Sent by a game actor when a player concedes; the rest of the room plays on
+    PlayerConceded {
+        game_id: String,
+        connection_id: String,
+    },
+    // Sent by a room's start countdown timer
+    CountdownTick {
+        room_id: String,
+        countdown_id: u64,
+        seconds_left: u64,
+    },
+    ConnectionClosed {
+        connection_id: String,
+    },
+    // Sent by the room sweep timer
+    SweepRooms,
+    Shutdown,
+    // One message of a client's batch, answered through `request` rather than with an Error
+    Batched {
+        request: BatchRequest,
+        message: Box<LobbyMessage>,
+    },
+}
+
+impl LobbyMessage {
+    /// `message` as part of a batch when there is a request to answer
+    pub fn batched(message: LobbyMessage, request: Option<BatchRequest>) -> Self {
+        match request {
+            Some(request) => LobbyMessage::Batched {
+                request,
+                message: Box::new(message),
+            },
+            None => message,
+        }
+    }
+
+    pub(crate) fn unbatched(self) -> (LobbyMessage, Option<BatchRequest>) {
+        match self {
+            LobbyMessage::Batched { request, message } => (*message, Some(request)),
+            message => (message, None),
+        }
+    }
+
+    /// The message itself, batched or not
+    pub(crate) fn inner(&self) -> &LobbyMessage {
+        match self {
+            LobbyMessage::Batched { message, .. } => message.inner(),
+            message => message,
+        }
+    }
+
+    pub fn connection_id(&self) -> Option<&str> {
+        match self {
+            LobbyMessage::Batched { message, .. } => message.connection_id(),
+            LobbyMessage::Ping { connection_id }
+            | LobbyMessage::Chat { connection_id, .. }
+            | LobbyMessage::CreateRoom { connection_id, .. }
+            | LobbyMessage::DestroyRoom { connection_id, .. }
+            | LobbyMessage::JoinRoom { connection_id, .. }
+            | LobbyMessage::LeaveRoom { connection_id }
+            | LobbyMessage::PlayerReady { connection_id }
+            | LobbyMessage::PlayerUnready { connection_id }
+            | LobbyMessage::ReloadCards { connection_id, .. }
+            | LobbyMessage::GetProtocolInfo { connection_id }
+            | LobbyMessage::GetCardCatalog { connection_id, .. }
+            | LobbyMessage::GetGlossary { connection_id }
+            | LobbyMessage::AddBot { connection_id, .. }
+            | LobbyMessage::StartSoloGame { connection_id, .. }
+            | LobbyMessage::LobbyChat { connection_id, .. }
+            | LobbyMessage::SetChatMuted { connection_id, .. }
+            | LobbyMessage::SetPlayerMuted { connection_id, .. }
+            | LobbyMessage::KickPlayer { connection_id, .. }
+            | LobbyMessage::SetRoomWebhook { connection_id, .. }
+            | LobbyMessage::RegisterProfile { connection_id, .. }
+            | LobbyMessage::RestoreProfile { connection_id, .. }
+            | LobbyMessage::AddFriend { connection_id, .. }
+            | LobbyMessage::RemoveFriend { connection_id, .. }
+            | LobbyMessage::GetFriends { connection_id }
+            | LobbyMessage::InviteToRoom { connection_id, .. }
+            | LobbyMessage::ResumeSession { connection_id, .. }
+            | LobbyMessage::GetLeaderboard { connection_id, .. }
+            | LobbyMessage::GetGameSummary { connection_id, .. }
+            | LobbyMessage::DumpGameState { connection_id, .. }
+            | LobbyMessage::SetGamePaused { connection_id, .. }
+            | LobbyMessage::SetSpectating { connection_id, .. }
+            | LobbyMessage::Announce { connection_id, .. }
+            | LobbyMessage::CancelAnnouncement { connection_id, .. }
+            | LobbyMessage::ConnectionOpened { connection_id }
+            | LobbyMessage::ConnectionClosed { connection_id } => Some(connection_id),
+            LobbyMessage::Shutdown
+            | LobbyMessage::GameEnded { .. }
+            | LobbyMessage::StartTournamentMatch { .. }
+            | LobbyMessage::PlayerConceded { .. }
+            | LobbyMessage::CountdownTick { .. }
+            | LobbyMessage::AnnouncementDue { .. }
+            | LobbyMessage::SweepRooms => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -44,48 +306,136 @@ struct PlayerRoomInfo {
     player_name: String,
 }
 
+/// One shard of the lobby, owning the rooms created on it. Who is online, profiles and chat
+/// settings are kept in the `LobbyDirectory` every shard shares.
 pub struct LobbyActor {
+    shard: usize,
     rooms: HashMap<String, Room>,
     connection_to_room_info: HashMap<String, PlayerRoomInfo>,
-    rooms_connections_map: HashMap<String, HashSet<String>>,
-
+    chat_history: ChatHistory,
+    countdowns: HashMap<String, (u64, JoinHandle<()>)>, // room_id -> (countdown id, timer)
+    next_countdown_id: u64,
+    announcements: HashMap<String, JoinHandle<()>>, // announcement_id -> timer, until it is sent
+    tournament_matches: HashMap<String, (String, String)>, // room_id -> (tournament_id, match_id)
+
+    config: Arc<ServerConfig>,
+    admin_key: AdminKey,
     actor_registry: Arc<ActorRegistry>,
-    cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+    directory: Arc<LobbyDirectory>,
+    messenger: Messenger, // room membership lives here, shared with the games
 }
 
 impl LobbyActor {
     pub fn new(
+        shard: usize,
+        config: Arc<ServerConfig>,
+        admin_key: AdminKey,
         actor_registry: Arc<ActorRegistry>,
-        cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
     ) -> Self {
+        let messenger = actor_registry.messenger().clone();
+        let directory = actor_registry.lobby().directory().clone();
         Self {
+            shard,
             rooms: HashMap::new(),
             connection_to_room_info: HashMap::new(),
-            rooms_connections_map: HashMap::new(),
+            chat_history: ChatHistory::default(),
+            countdowns: HashMap::new(),
+            next_countdown_id: 0,
+            announcements: HashMap::new(),
+            tournament_matches: HashMap::new(),
+            config,
+            admin_key,
             actor_registry,
-            cmd_sender,
+            directory,
+            messenger,
         }
     }
 
-    pub async fn run(&mut self, mut receiver: mpsc::UnboundedReceiver<LobbyMessage>) {
-        println!("🏛️ Lobby actor started");
+    pub async fn run(&mut self, mut receiver: mpsc::Receiver<LobbyMessage>) {
+        println!("🏛️ Lobby actor {} started", self.shard);
+        let sweeper = (self.shard == HOME_SHARD).then(|| self.start_room_sweeper());
 
         while let Some(message) = receiver.recv().await {
-            if let Err(error) = self.handle_message(message).await {
+            if let LobbyMessage::Shutdown = message {
+                self.announce_shutdown();
+                for (_, (_, timer)) in self.countdowns.drain() {
+                    timer.abort();
+                }
+                for (_, timer) in self.announcements.drain() {
+                    timer.abort();
+                }
+                break;
+            }
+
+            let (message, request) = message.unbatched();
+            let connection_id = message.connection_id().map(str::to_string);
+            if let Some(connection_id) = &connection_id {
+                self.touch_room(connection_id);
+            }
+            let claims_seat = matches!(
+                message,
+                LobbyMessage::JoinRoom { .. } | LobbyMessage::ResumeSession { .. }
+            );
+            let result = self.handle_message(message).await;
+            if let Some(connection_id) = connection_id.as_deref().filter(|_| claims_seat) {
+                // The seat claimed when the message was routed here, unless it was taken
+                if !self.connection_to_room_info.contains_key(connection_id) {
+                    self.directory.unseat(connection_id, self.shard);
+                }
+            }
+            self.actor_registry
+                .metrics()
+                .set_rooms(self.directory.room_count());
+            if let Err(error) = &result {
                 eprintln!("Lobby actor error: {:?}", error);
             }
+            let locale = connection_id
+                .as_deref()
+                .map(|connection_id| self.actor_registry.locale(connection_id))
+                .unwrap_or_default();
+            match (result, request) {
+                (result, Some(request)) => request.answer(&result, locale),
+                (Err(error), None) => {
+                    if let Some(connection_id) = connection_id {
+                        let _ = self.messenger.send_to_connection(
+                            &connection_id,
+                            ServerResponse::localized_error(&error, locale),
+                        );
+                    }
+                }
+                (Ok(()), None) => {}
+            }
+        }
+
+        if let Some(sweeper) = sweeper {
+            sweeper.abort();
+        }
+        println!("🏛️ Lobby actor {} stopped", self.shard);
+    }
+
+    fn announce_shutdown(&self) {
+        if self.shard != HOME_SHARD {
+            return;
         }
+        let _ = self.messenger.send_to_connections(
+            self.online_connections(),
+            ServerResponse::ServerShuttingDown,
+        );
+    }
 
-        println!("🏛️ Lobby actor stopped");
+    fn online_connections(&self) -> Vec<String> {
+        self.directory
+            .online_connections
+            .iter()
+            .map(|connection_id| connection_id.clone())
+            .collect()
     }
 
     async fn handle_message(&mut self, message: LobbyMessage) -> AppResult<()> {
         match message {
             LobbyMessage::Ping { connection_id } => {
-                self.cmd_sender.send(ConnectionCommand::SendToPlayer {
-                    connection_id,
-                    message: serialize_response(ServerResponse::Pong),
-                })?;
+                self.messenger
+                    .send_to_connection(&connection_id, ServerResponse::Pong)?;
             }
 
             LobbyMessage::Chat {
@@ -100,49 +450,78 @@ impl LobbyActor {
                     .get_player_name_from_connection_id(&connection_id)
                     .ok_or(AppError::ConnectionNotInRoom)?;
 
-                let connections_id = self.get_connections_id_from_room_id(&room_id)?;
-
-                self.cmd_sender.send(ConnectionCommand::SendToPlayers {
-                    connections_id,
-                    message: serialize_response(ServerResponse::ChatMessage {
-                        player_name,
-                        message,
-                    }),
+                let player_id = self.get_player_id_from_connection_id(&connection_id)?;
+                let room = self.rooms.get(&room_id).ok_or(AppError::RoomNotFound {
+                    room_id: room_id.clone(),
                 })?;
+                if room.is_player_muted(&player_id) {
+                    return Err(AppError::ChatRejected {
+                        reason: "You have been muted by the host".to_string(),
+                    });
+                }
+                self.directory
+                    .chat_filter()
+                    .check(&connection_id, &message)?;
+
+                let connections_id = self.without_muted(
+                    self.messenger.room_connections(&room_id)?,
+                    &ChatChannel::Room,
+                );
+
+                let entry = ChatEntry::now(player_id, player_name, message);
+                self.chat_history.record(&room_id, entry.clone());
+                self.messenger.send_to_connections(
+                    connections_id,
+                    ServerResponse::ChatMessage {
+                        player_id: entry.player_id,
+                        player_name: entry.player_name,
+                        message: entry.message,
+                        timestamp: entry.timestamp,
+                    },
+                )?;
             }
 
             LobbyMessage::CreateRoom {
                 connection_id,
                 room_name,
                 first_player_name,
+                options,
             } => {
+                let card_sets = options.card_sets();
+                let speed = options.speed;
                 let (room_id, new_player_id) =
-                    self.create_room(room_name, connection_id.clone(), first_player_name)?;
+                    self.create_room(room_name, connection_id.clone(), first_player_name, options)?;
 
-                self.cmd_sender.send(ConnectionCommand::SendToPlayer {
-                    connection_id,
-                    message: serialize_response(ServerResponse::RoomCreated {
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::RoomCreated {
                         room_id: room_id.clone(),
                         player_id: new_player_id,
-                    }),
-                })?;
-
-                self.cmd_sender.send(ConnectionCommand::SendToAll {
-                    message: serialize_response(ServerResponse::RoomCreatedBroadcast { room_id }),
-                })?;
+                    },
+                )?;
+
+                self.messenger
+                    .send_to_all(ServerResponse::RoomCreatedBroadcast {
+                        room_id,
+                        card_sets,
+                        speed,
+                        timers: self.config.game.speed_timers(speed),
+                    })?;
             }
 
             LobbyMessage::DestroyRoom {
                 connection_id,
                 room_id,
             } => {
-                let destroyed_room_id = self.destroy_room(&room_id, &connection_id)?;
-
-                self.cmd_sender.send(ConnectionCommand::SendToAll {
-                    message: serialize_response(ServerResponse::RoomDestroyed {
-                        room_id: destroyed_room_id,
-                    }),
-                })?;
+                let member_connections = self.destroy_room(&room_id, &connection_id)?;
+                self.send_members_to_lobby(
+                    &room_id,
+                    member_connections,
+                    KickReason::RoomClosed,
+                    Some(&connection_id),
+                )?;
+                self.messenger
+                    .send_to_all(ServerResponse::RoomDestroyed { room_id })?;
             }
 
             LobbyMessage::JoinRoom {
@@ -150,155 +529,744 @@ impl LobbyActor {
                 player_name,
                 room_id,
             } => {
-                let player_id =
-                    self.join_room(&room_id, connection_id.clone(), player_name.clone())?;
-
-                self.cmd_sender.send(ConnectionCommand::SendToPlayer {
-                    connection_id: connection_id.clone(),
-                    message: serialize_response(ServerResponse::SelfJoined {
+                let (player_id, player_name) =
+                    self.join_room(&room_id, connection_id.clone(), player_name)?;
+                // The newcomer isn't ready yet
+                self.cancel_countdown(&room_id)?;
+                let (card_sets, speed) = self
+                    .rooms
+                    .get(&room_id)
+                    .map(|room| (room.options().card_sets(), room.options().speed))
+                    .unwrap_or_default();
+
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::SelfJoined {
                         player_name: player_name.clone(),
                         player_id: player_id.clone(),
-                    }),
-                })?;
-
-                let connections_id = self.get_connections_id_from_room_id(&room_id)?;
-
-                self.cmd_sender.send(ConnectionCommand::SendToPlayers {
-                    connections_id,
-                    message: serialize_response(ServerResponse::PlayerJoined {
+                        profile: self.get_profile(&connection_id),
+                        card_sets,
+                        speed,
+                        timers: self.config.game.speed_timers(speed),
+                    },
+                )?;
+                self.send_chat_history(&room_id, &connection_id)?;
+
+                self.messenger.broadcast_to_room(
+                    &room_id,
+                    ServerResponse::PlayerJoined {
                         player_name,
                         player_id,
-                    }),
-                })?;
+                        profile: self.get_profile(&connection_id),
+                    },
+                )?;
+            }
 
-                // Temporary shortcircuit for testing purposes
-                //----------------------------------------------------------------------------------------
-                let players_mapping = self.get_players_mapping(&room_id)?;
-                if players_mapping.len() == 2 {
-                    let turn_order = self.actor_registry.start_game_actor(
-                        room_id.clone(),
-                        players_mapping.clone(),
-                        self.cmd_sender.clone(),
-                    )?;
+            LobbyMessage::LeaveRoom { connection_id } => {
+                self.leave_room_and_notify(&connection_id)?;
+            }
 
-                    for (player_id, connection_id) in &players_mapping {
-                        println!(
-                            "🏛️ Notifying connection {} that they are player {} in game {}",
-                            connection_id, player_id, room_id
-                        );
+            LobbyMessage::PlayerReady { connection_id } => {
+                let room_id = self
+                    .get_player_room_from_connection_id(&connection_id)
+                    .ok_or(AppError::ConnectionNotInRoom)?;
 
-                        if let Err(e) = self.actor_registry.notify_connection_game_start(
-                            connection_id,
-                            room_id.clone(),
-                            player_id.clone(),
-                        ) {
-                            eprintln!(
-                                "Failed to notify connection {} of game start: {:?}",
-                                connection_id, e
-                            );
-                        }
+                let player_id = self.get_player_id_from_connection_id(&connection_id)?;
+                let players_ready = self.ready_player(&player_id)?;
+                self.messenger
+                    .broadcast_to_room(&room_id, ServerResponse::PlayersReady { players_ready })?;
+
+                // A lone player readying up waits for company, however ready they are
+                let can_start = self
+                    .rooms
+                    .get(&room_id)
+                    .is_some_and(|room| room.can_start_game());
+                if can_start {
+                    self.set_room_state(&room_id, RoomState::Starting)?;
+                    let countdown_secs = self.config.timers.start_countdown_secs;
+                    if countdown_secs == 0 {
+                        self.start_game(&room_id)?;
+                    } else {
+                        self.start_countdown(&room_id, countdown_secs)?;
                     }
+                }
+            }
 
-                    let connections_id = self.get_connections_id_from_room_id(&room_id)?;
-
-                    self.cmd_sender.send(ConnectionCommand::SendToPlayers {
-                        connections_id: connections_id.clone(),
-                        message: serialize_response(ServerResponse::RoomGameStart {
-                            turn_order: turn_order.order,
-                        }),
+            LobbyMessage::GameEnded { game_id, winner_id } => {
+                let Some((tournament_id, match_id)) = self.tournament_matches.remove(&game_id)
+                else {
+                    return self.end_game(&game_id);
+                };
+                let winner = winner_id.and_then(|winner_id| {
+                    self.get_players_mapping(&game_id)
+                        .ok()
+                        .and_then(|players_mapping| players_mapping.get(&winner_id).cloned())
+                });
+                self.actor_registry
+                    .deliver_tournament_message(TournamentMessage::MatchEnded {
+                        tournament_id,
+                        match_id,
+                        winner,
                     })?;
+                self.end_game(&game_id)?;
+                // Nobody plays a second game in a tournament room, the seats are needed next round
+                let member_connections = self.close_room(&game_id);
+                self.messenger.send_to_connections(
+                    member_connections,
+                    ServerResponse::RoomClosed { room_id: game_id },
+                )?;
+            }
 
-                    self.cmd_sender.send(ConnectionCommand::SendToAll {
-                        message: serialize_response(ServerResponse::LobbyStartedGame {
-                            room_id: room_id.clone(),
-                        }),
-                    })?;
+            LobbyMessage::StartTournamentMatch {
+                tournament_id,
+                match_id,
+                room_name,
+                seats,
+                options,
+            } => {
+                self.start_tournament_match(tournament_id, match_id, room_name, seats, options)?;
+            }
+
+            LobbyMessage::PlayerConceded {
+                game_id,
+                connection_id,
+            } => {
+                if let Some(room) = self.rooms.get_mut(&game_id) {
+                    room.touch();
+                }
+                self.actor_registry
+                    .notify_connection_lobby_return(&connection_id, game_id)?;
+            }
+
+            LobbyMessage::SweepRooms => {
+                self.sweep_rooms()?;
+            }
 
+            LobbyMessage::CountdownTick {
+                room_id,
+                countdown_id,
+                seconds_left,
+            } => {
+                // Ticks from a cancelled countdown may still be queued
+                if self.countdowns.get(&room_id).map(|(id, _)| *id) != Some(countdown_id) {
+                    return Ok(());
+                }
+                if seconds_left > 0 {
+                    self.messenger.broadcast_to_room(
+                        &room_id,
+                        ServerResponse::GameStartCountdown { seconds_left },
+                    )?;
+                } else {
+                    // Anything that could have unreadied the room called the countdown off
+                    self.countdowns.remove(&room_id);
+                    self.start_game(&room_id)?;
+                }
+            }
+
+            LobbyMessage::PlayerUnready { connection_id } => {
+                let room_id = self
+                    .get_player_room_from_connection_id(&connection_id)
+                    .ok_or(AppError::ConnectionNotInRoom)?;
+                let player_id = self.get_player_id_from_connection_id(&connection_id)?;
+                // Refused once the room is starting, leaving is the way out of a countdown
+                let players_ready = self.unready_player(&room_id, &player_id)?;
+
+                self.messenger
+                    .broadcast_to_room(&room_id, ServerResponse::PlayersReady { players_ready })?;
+            }
+
+            LobbyMessage::ReloadCards {
+                connection_id,
+                admin_key,
+            } => {
+                self.admin_key.check(&admin_key, "ReloadCards")?;
+                let version = card_loader::reload_database()?;
+
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::CardsReloaded { version },
+                )?;
+            }
+
+            LobbyMessage::AddBot {
+                connection_id,
+                strategy,
+            } => {
+                let room_id = self
+                    .get_player_room_from_connection_id(&connection_id)
+                    .ok_or(AppError::ConnectionNotInRoom)?;
+                let player_id = self.get_player_id_from_connection_id(&connection_id)?;
+
+                let room = self.rooms.get(&room_id).ok_or(AppError::RoomNotFound {
+                    room_id: room_id.clone(),
+                })?;
+                if !room.is_host(&player_id) {
+                    return Err(AppError::PermissionDenied {
+                        action: "AddBot".to_string(),
+                    });
+                }
+                if room.is_full() {
+                    return Err(AppError::RoomFull {
+                        room_id,
+                        max_players: room.max_players(),
+                    });
+                }
+
+                let strategy = bot_strategy::create_strategy(
+                    strategy
+                        .as_deref()
+                        .unwrap_or(bot_strategy::DEFAULT_STRATEGY),
+                )?;
+
+                // The bot joins through the regular JoinRoom path once it is running
+                BotActor::spawn(
+                    room_id,
+                    strategy,
+                    self.config.timers.bot_think_time(),
+                    self.actor_registry.clone(),
+                    self.messenger.commands().clone(),
+                )?;
+            }
+
+            LobbyMessage::StartSoloGame {
+                connection_id,
+                player_name,
+                bots,
+                strategy,
+                options,
+            } => {
+                let room_id =
+                    self.start_solo_game(&connection_id, player_name, bots, strategy, options)?;
+                println!("🏛️ Started solo game {} with {} bots", room_id, bots);
+            }
+
+            LobbyMessage::LobbyChat {
+                connection_id,
+                message,
+            } => {
+                self.directory
+                    .chat_filter()
+                    .check(&connection_id, &message)?;
+                let sender_name = self.display_name(&connection_id);
+                let connections_id =
+                    self.without_muted(self.online_connections(), &ChatChannel::Lobby);
+
+                self.messenger.send_to_connections(
+                    connections_id,
+                    ServerResponse::LobbyChatMessage {
+                        sender_name,
+                        message,
+                    },
+                )?;
+            }
+
+            LobbyMessage::SetChatMuted {
+                connection_id,
+                channel,
+                muted,
+            } => {
+                let mut muted_channels = self
+                    .directory
+                    .muted_channels
+                    .entry(connection_id)
+                    .or_default();
+                if muted {
+                    muted_channels.insert(channel);
+                } else {
+                    muted_channels.remove(&channel);
+                }
+            }
+
+            LobbyMessage::SetPlayerMuted {
+                connection_id,
+                player_id,
+                muted,
+            } => {
+                let room_id = self
+                    .get_player_room_from_connection_id(&connection_id)
+                    .ok_or(AppError::ConnectionNotInRoom)?;
+                let host_id = self.get_player_id_from_connection_id(&connection_id)?;
+
+                let room = self.rooms.get_mut(&room_id).ok_or(AppError::RoomNotFound {
+                    room_id: room_id.clone(),
+                })?;
+                if !room.is_host(&host_id) {
+                    return Err(AppError::PermissionDenied {
+                        action: "MutePlayer".to_string(),
+                    });
+                }
+                room.set_player_muted(&player_id, muted)?;
+
+                self.messenger.broadcast_to_room(
+                    &room_id,
+                    ServerResponse::PlayerMuted { player_id, muted },
+                )?;
+            }
+
+            LobbyMessage::KickPlayer {
+                connection_id,
+                player_id,
+                ban,
+            } => {
+                let room_id = self
+                    .get_player_room_from_connection_id(&connection_id)
+                    .ok_or(AppError::ConnectionNotInRoom)?;
+                let host_id = self.get_player_id_from_connection_id(&connection_id)?;
+                let room = self.rooms.get(&room_id).ok_or(AppError::RoomNotFound {
+                    room_id: room_id.clone(),
+                })?;
+                if !room.is_host(&host_id) {
+                    return Err(AppError::PermissionDenied {
+                        action: if ban { "BanPlayer" } else { "KickPlayer" }.to_string(),
+                    });
+                }
+                if player_id == host_id {
+                    return Err(AppError::CannotKickSelf);
+                }
+                let target_connection_id = self
+                    .connection_to_room_info
+                    .iter()
+                    .find(|(_, info)| info.room_id == room_id && info.room_player_id == player_id)
+                    .map(|(target_connection_id, _)| target_connection_id.clone())
+                    .ok_or(AppError::PlayerNotFound)?;
+
+                if ban {
+                    let profile_id = self
+                        .get_profile(&target_connection_id)
+                        .map(|profile| profile.profile_id);
                     if let Some(room) = self.rooms.get_mut(&room_id) {
-                        room.set_state_in_game();
+                        room.ban(&target_connection_id, profile_id.as_deref());
                     }
                 }
-                //------------------------------------------------------------------------------------
+                let (player_name, _) = self.leave_room(&target_connection_id)?;
+                self.cancel_countdown(&room_id)?;
+                let reason = if ban {
+                    KickReason::Banned
+                } else {
+                    KickReason::RemovedByHost
+                };
+                if let Err(e) = self
+                    .actor_registry
+                    .notify_connection_kicked(&target_connection_id, reason)
+                {
+                    eprintln!(
+                        "Failed to tell connection {} it was kicked: {:?}",
+                        target_connection_id, e
+                    );
+                }
+
+                self.messenger.send_to_connection(
+                    &target_connection_id,
+                    ServerResponse::KickedFromRoom {
+                        room_id: room_id.clone(),
+                        banned: ban,
+                    },
+                )?;
+                self.messenger.broadcast_to_room(
+                    &room_id,
+                    ServerResponse::PlayerKicked {
+                        player_id,
+                        player_name,
+                        banned: ban,
+                    },
+                )?;
             }
 
-            LobbyMessage::LeaveRoom { connection_id } => {
+            LobbyMessage::SetRoomWebhook { connection_id, url } => {
+                let room_id = self
+                    .get_player_room_from_connection_id(&connection_id)
+                    .ok_or(AppError::ConnectionNotInRoom)?;
+                let host_id = self.get_player_id_from_connection_id(&connection_id)?;
+                let room = self.rooms.get(&room_id).ok_or(AppError::RoomNotFound {
+                    room_id: room_id.clone(),
+                })?;
+                if !room.is_host(&host_id) {
+                    return Err(AppError::PermissionDenied {
+                        action: "SetRoomWebhook".to_string(),
+                    });
+                }
+                let enabled = url.is_some();
+                self.actor_registry
+                    .webhooks()
+                    .set(&room_id, room.name(), url.as_deref())?;
+
+                self.messenger
+                    .broadcast_to_room(&room_id, ServerResponse::RoomWebhookSet { enabled })?;
+            }
+
+            LobbyMessage::RegisterProfile {
+                connection_id,
+                display_name,
+                avatar_id,
+            } => {
+                // Registering again while bound updates the existing profile in place
+                let bound = self
+                    .directory
+                    .connection_profiles
+                    .get(&connection_id)
+                    .map(|token| token.clone());
+                let (token, profile) = match bound {
+                    Some(token) => {
+                        let profile =
+                            self.directory
+                                .profiles()
+                                .update(&token, display_name, avatar_id)?;
+                        (token, profile)
+                    }
+                    None => self
+                        .directory
+                        .profiles()
+                        .register(display_name, avatar_id)?,
+                };
+                self.directory
+                    .connection_profiles
+                    .insert(connection_id.clone(), token.clone());
+
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::ProfileRegistered { token, profile },
+                )?;
+            }
+
+            LobbyMessage::RestoreProfile {
+                connection_id,
+                token,
+            } => {
+                let profile = self
+                    .directory
+                    .profiles()
+                    .get(token.expose())
+                    .cloned()
+                    .ok_or(AppError::ProfileNotFound)?;
+                self.directory
+                    .connection_profiles
+                    .insert(connection_id.clone(), token.expose().to_string());
+
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::ProfileRestored { profile },
+                )?;
+            }
+
+            LobbyMessage::AddFriend {
+                connection_id,
+                profile_id,
+            } => {
+                let token = self.profile_token(&connection_id)?;
+                self.directory.profiles().add_friend(&token, &profile_id)?;
+                self.send_friends(&connection_id, &token)?;
+            }
+
+            LobbyMessage::RemoveFriend {
+                connection_id,
+                profile_id,
+            } => {
+                let token = self.profile_token(&connection_id)?;
+                self.directory.profiles().remove_friend(&token, &profile_id);
+                self.send_friends(&connection_id, &token)?;
+            }
+
+            LobbyMessage::GetFriends { connection_id } => {
+                let token = self.profile_token(&connection_id)?;
+                self.send_friends(&connection_id, &token)?;
+            }
+
+            LobbyMessage::InviteToRoom {
+                connection_id,
+                profile_id,
+            } => {
                 let room_id = self
                     .get_player_room_from_connection_id(&connection_id)
                     .ok_or(AppError::ConnectionNotInRoom)?;
+                let from = self
+                    .get_profile(&connection_id)
+                    .ok_or(AppError::ProfileNotFound)?;
+                let invited_token = self
+                    .directory
+                    .profiles()
+                    .token_of(&profile_id)
+                    .map(str::to_string);
+                let invited = invited_token
+                    .map(|token| self.profile_connections(&token))
+                    .unwrap_or_default();
+                if invited.is_empty() {
+                    return Err(AppError::PlayerOffline { profile_id });
+                }
+                let room_name = self
+                    .rooms
+                    .get(&room_id)
+                    .map(|room| room.name().to_string())
+                    .unwrap_or_default();
+
+                self.messenger.send_to_connections(
+                    invited,
+                    ServerResponse::RoomInvite {
+                        room_id,
+                        room_name,
+                        from,
+                    },
+                )?;
+            }
 
-                let player_name = self.leave_room(&connection_id)?;
-                let connections_id = self.get_connections_id_from_room_id(&room_id)?;
+            LobbyMessage::ResumeSession {
+                connection_id,
+                session_token,
+            } => {
+                if self.is_seated(&connection_id) {
+                    return Err(AppError::PlayerAlreadyInRoom {
+                        player_name: self.display_name(&connection_id),
+                    });
+                }
+                let (game_id, player_id) = self
+                    .actor_registry
+                    .resume_session(session_token.expose(), &connection_id)?;
+                self.move_seat(&game_id, &player_id, &connection_id);
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::SessionResumed {
+                        game_id: game_id.clone(),
+                        player_id,
+                    },
+                )?;
+                self.send_chat_history(&game_id, &connection_id)?;
+            }
+
+            LobbyMessage::GetLeaderboard {
+                connection_id,
+                limit,
+            } => {
+                let limit = limit
+                    .unwrap_or(DEFAULT_LEADERBOARD_SIZE)
+                    .min(MAX_LEADERBOARD_SIZE);
+                let entries = self.actor_registry.ratings().leaderboard(limit)?;
+                self.messenger
+                    .send_to_connection(&connection_id, ServerResponse::Leaderboard { entries })?;
+            }
+
+            LobbyMessage::GetGameSummary {
+                connection_id,
+                game_id,
+            } => {
+                let summary = self
+                    .actor_registry
+                    .game_summaries()
+                    .get(&game_id)
+                    .ok_or(AppError::GameSummaryNotFound { game_id })?;
+                self.messenger
+                    .send_to_connection(&connection_id, ServerResponse::GameSummary { summary })?;
+            }
 
-                self.cmd_sender.send(ConnectionCommand::SendToPlayers {
-                    connections_id,
-                    message: serialize_response(ServerResponse::PlayerLeft { player_name }),
-                })?;
+            LobbyMessage::DumpGameState {
+                connection_id,
+                game_id,
+                admin_key,
+            } => {
+                self.admin_key.check(&admin_key, "DumpGameState")?;
+                // The game actor answers the admin directly
+                self.actor_registry
+                    .send_to_game(&game_id, GameMessage::DumpState { connection_id })?;
             }
 
-            LobbyMessage::PlayerReady { connection_id } => {
+            LobbyMessage::SetGamePaused {
+                connection_id,
+                paused,
+            } => {
                 let room_id = self
                     .get_player_room_from_connection_id(&connection_id)
                     .ok_or(AppError::ConnectionNotInRoom)?;
-
                 let player_id = self.get_player_id_from_connection_id(&connection_id)?;
-                let ready_result = self.ready_player(&player_id)?;
-                let players_mapping = self.get_players_mapping(&room_id)?;
+                let room = self.rooms.get(&room_id).ok_or(AppError::RoomNotFound {
+                    room_id: room_id.clone(),
+                })?;
+                if !room.is_host(&player_id) {
+                    return Err(AppError::PermissionDenied {
+                        action: if paused { "PauseGame" } else { "ResumeGame" }.to_string(),
+                    });
+                }
+                if !room.is_in_game() {
+                    return Err(AppError::GameNotFound { game_id: room_id });
+                }
+                // The game actor checks the pause state and tells the players
+                self.actor_registry.send_to_game(
+                    &room_id,
+                    GameMessage::SetPaused {
+                        connection_id,
+                        paused,
+                    },
+                )?;
+            }
 
-                if ready_result.len() == players_mapping.len() {
-                    println!(
-                        "🏛️ Starting game for room {} with players: {:?}",
-                        room_id, players_mapping
-                    );
+            LobbyMessage::SetSpectating {
+                connection_id,
+                room_id,
+                spectating,
+            } => {
+                let room = self.rooms.get(&room_id).ok_or(AppError::RoomNotFound {
+                    room_id: room_id.clone(),
+                })?;
+                if !room.is_in_game() {
+                    return Err(AppError::GameNotFound { game_id: room_id });
+                }
+                // The game actor keeps its spectators and answers them directly
+                let message = if spectating {
+                    GameMessage::AddSpectator { connection_id }
+                } else {
+                    GameMessage::RemoveSpectator { connection_id }
+                };
+                self.actor_registry.send_to_game(&room_id, message)?;
+            }
 
-                    let turn_order = self.actor_registry.start_game_actor(
-                        room_id.clone(),
-                        players_mapping.clone(),
-                        self.cmd_sender.clone(),
-                    )?;
+            LobbyMessage::Announce {
+                connection_id,
+                admin_key,
+                text,
+                severity,
+                send_at,
+            } => {
+                self.admin_key.check(&admin_key, "Announce")?;
+                let text = text.trim().to_string();
+                if text.is_empty() || text.chars().count() > MAX_ANNOUNCEMENT_LENGTH {
+                    return Err(AppError::InvalidAnnouncement {
+                        reason: format!(
+                            "the text needs 1 to {} characters",
+                            MAX_ANNOUNCEMENT_LENGTH
+                        ),
+                    });
+                }
+                let announcement_id = Uuid::new_v4().to_string();
+                let delay = send_at
+                    .map(|send_at| Duration::from_millis(send_at.saturating_sub(unix_millis())))
+                    .unwrap_or_default();
+                if delay.is_zero() {
+                    self.messenger.send_to_all(ServerResponse::Announcement {
+                        announcement_id,
+                        text,
+                        severity,
+                    })?;
+                    return Ok(());
+                }
 
-                    for (player_id, connection_id) in &players_mapping {
-                        println!(
-                            "🏛️ Notifying connection {} that they are player {} in game {}",
-                            connection_id, player_id, room_id
-                        );
+                let actor_registry = self.actor_registry.clone();
+                let due = LobbyMessage::AnnouncementDue {
+                    announcement_id: announcement_id.clone(),
+                    text,
+                    severity,
+                };
+                let timer = tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    let _ = actor_registry.deliver_lobby_message(due);
+                });
+                self.announcements.insert(announcement_id.clone(), timer);
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::AnnouncementScheduled {
+                        announcement_id,
+                        send_at: send_at.unwrap_or_default(),
+                    },
+                )?;
+            }
 
-                        if let Err(e) = self.actor_registry.notify_connection_game_start(
-                            connection_id,
-                            room_id.clone(),
-                            player_id.clone(),
-                        ) {
-                            eprintln!(
-                                "Failed to notify connection {} of game start: {:?}",
-                                connection_id, e
-                            );
-                        }
+            LobbyMessage::CancelAnnouncement {
+                connection_id,
+                admin_key,
+                announcement_id,
+            } => {
+                self.admin_key.check(&admin_key, "CancelAnnouncement")?;
+                let timer = self.announcements.remove(&announcement_id).ok_or_else(|| {
+                    AppError::AnnouncementNotFound {
+                        announcement_id: announcement_id.clone(),
                     }
+                })?;
+                timer.abort();
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::AnnouncementCancelled { announcement_id },
+                )?;
+            }
 
-                    let connections_id = self.get_connections_id_from_room_id(&room_id)?;
-
-                    self.cmd_sender.send(ConnectionCommand::SendToPlayers {
-                        connections_id: connections_id.clone(),
-                        message: serialize_response(ServerResponse::RoomGameStart {
-                            turn_order: turn_order.order,
-                        }),
+            LobbyMessage::AnnouncementDue {
+                announcement_id,
+                text,
+                severity,
+            } => {
+                // Unless it was cancelled after the timer fired
+                if self.announcements.remove(&announcement_id).is_some() {
+                    self.messenger.send_to_all(ServerResponse::Announcement {
+                        announcement_id,
+                        text,
+                        severity,
                     })?;
+                }
+            }
 
-                    self.cmd_sender.send(ConnectionCommand::SendToAll {
-                        message: serialize_response(ServerResponse::LobbyStartedGame {
-                            room_id: room_id.clone(),
-                        }),
-                    })?;
+            LobbyMessage::ConnectionOpened { connection_id } => {
+                self.directory.online_connections.insert(connection_id);
+            }
 
-                    if let Some(room) = self.rooms.get_mut(&room_id) {
-                        room.set_state_in_game();
+            LobbyMessage::Shutdown => {}       // stops the loop in run()
+            LobbyMessage::Batched { .. } => {} // unwrapped in run()
+
+            LobbyMessage::ConnectionClosed { connection_id } => {
+                // Every shard is told, only the one seating the connection has a room to leave
+                self.directory.online_connections.remove(&connection_id);
+                self.directory.muted_channels.remove(&connection_id);
+                self.directory
+                    .chat_filter()
+                    .forget_connection(&connection_id);
+                // A dropped connection gives up its seat, unless a game is running where
+                // the player can still come back with their session token
+                let in_lobby_room = self
+                    .get_player_room_from_connection_id(&connection_id)
+                    .and_then(|room_id| self.rooms.get(&room_id))
+                    .is_some_and(|room| !room.is_in_game());
+                if in_lobby_room {
+                    if let Err(e) = self.leave_room_and_notify(&connection_id) {
+                        eprintln!(
+                            "Failed to remove closed connection {} from its room: {:?}",
+                            connection_id, e
+                        );
                     }
-                } else {
-                    self.cmd_sender.send(ConnectionCommand::SendToAll {
-                        message: serialize_response(ServerResponse::PlayersReady {
-                            players_ready: ready_result,
-                        }),
-                    })?;
                 }
+                self.directory.connection_profiles.remove(&connection_id);
+            }
+
+            LobbyMessage::GetProtocolInfo { connection_id } => {
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::ProtocolInfo {
+                        info: protocol::protocol_info().clone(),
+                    },
+                )?;
+            }
+
+            LobbyMessage::GetCardCatalog {
+                connection_id,
+                known_version,
+            } => {
+                let database = card_loader::get_database();
+                let locale = self.actor_registry.locale(&connection_id);
+                let version = database.catalog_version(locale.code());
+                let cards = (known_version.as_ref() != Some(&version))
+                    .then(|| database.catalog_in(locale.code()));
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::CardCatalog {
+                        version,
+                        locale,
+                        cards,
+                    },
+                )?;
+            }
+
+            LobbyMessage::GetGlossary { connection_id } => {
+                let locale = self.actor_registry.locale(&connection_id);
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::Glossary {
+                        locale,
+                        keywords: card_loader::get_database().glossary_in(locale.code()),
+                    },
+                )?;
             }
         }
         Ok(())
@@ -309,38 +1277,505 @@ impl LobbyActor {
         room_name: String,
         first_player_connection_id: String,
         first_player_name: String,
+        options: RoomOptions,
     ) -> AppResult<(String, String)> {
         if room_name.trim().is_empty() {
             return Err(AppError::RoomNameEmpty);
         }
-        if self
-            .connection_to_room_info
-            .contains_key(&first_player_connection_id)
-        {
+        options.validate()?;
+        if self.is_seated(&first_player_connection_id) {
             return Err(AppError::PlayerAlreadyInRoom {
                 player_name: first_player_name,
             });
         }
+        if let Some(max_rooms) = self.config.max_rooms {
+            if self.directory.room_count() >= max_rooms {
+                return Err(self
+                    .actor_registry
+                    .metrics()
+                    .limit_hit(Limit::Rooms, max_rooms));
+            }
+        }
 
-        let mut room = Room::new(room_name);
-        let new_player_id = room.add_player(first_player_name.clone())?;
+        let mut room = Room::new(room_name, options);
+        let (new_player_id, first_player_name) = room.add_player(first_player_name)?;
         let room_id = room.get_id();
 
-        self.connection_to_room_info.insert(
-            first_player_connection_id.clone(),
+        self.take_seat(
+            &first_player_connection_id,
             PlayerRoomInfo {
                 room_id: room_id.clone(),
                 room_player_id: new_player_id.clone(),
                 player_name: first_player_name,
             },
         );
-        self.rooms_connections_map
-            .insert(room_id.clone(), HashSet::from([first_player_connection_id]));
+        self.set_role(&first_player_connection_id, Role::Host);
+        self.messenger
+            .join_room(&room_id, &new_player_id, &first_player_connection_id);
         self.rooms.insert(room_id.clone(), room);
+        self.directory.open_room(&room_id, self.shard);
 
         Ok((room_id, new_player_id))
     }
 
+    /// A room with one human and `bots` bots that starts right away, without ready-up or a
+    /// countdown. It isn't announced to the lobby; afterwards it behaves like any other room.
+    fn start_solo_game(
+        &mut self,
+        connection_id: &str,
+        player_name: String,
+        bots: usize,
+        strategy: Option<String>,
+        options: RoomOptions,
+    ) -> AppResult<String> {
+        let max_bots = Room::DEFAULT_MAX_PLAYERS - 1;
+        if bots == 0 || bots > max_bots {
+            return Err(AppError::InvalidBotCount {
+                requested: bots,
+                max: max_bots,
+            });
+        }
+        let strategy = strategy
+            .as_deref()
+            .unwrap_or(bot_strategy::DEFAULT_STRATEGY)
+            .to_string();
+        let strategies = (0..bots)
+            .map(|_| bot_strategy::create_strategy(&strategy))
+            .collect::<AppResult<Vec<_>>>()?;
+
+        let room_name = format!("{}'s practice", player_name);
+        let (room_id, player_id) =
+            self.create_room(room_name, connection_id.to_string(), player_name, options)?;
+        self.messenger.send_to_connection(
+            connection_id,
+            ServerResponse::RoomCreated {
+                room_id: room_id.clone(),
+                player_id,
+            },
+        )?;
+
+        for strategy in strategies {
+            let (bot_connection_id, bot_name) = BotActor::spawn_seated(
+                room_id.clone(),
+                strategy,
+                self.config.timers.bot_think_time(),
+                self.actor_registry.clone(),
+                self.messenger.commands().clone(),
+            )?;
+            let (bot_player_id, _) = self.join_room(&room_id, bot_connection_id, bot_name)?;
+            // Bots are always ready, should the room be held back by max_games
+            self.ready_player(&bot_player_id)?;
+        }
+
+        self.set_room_state(&room_id, RoomState::Starting)?;
+        self.start_game(&room_id)?;
+        Ok(room_id)
+    }
+
+    /// Seat a pairing in a room of its own and start its game right away. Entrants who are gone
+    /// or sitting in another room forfeit; with fewer than two left the match is decided here.
+    fn start_tournament_match(
+        &mut self,
+        tournament_id: String,
+        match_id: String,
+        room_name: String,
+        seats: Vec<(String, String)>,
+        options: RoomOptions,
+    ) -> AppResult<()> {
+        let pairing = (tournament_id.clone(), match_id.clone());
+        if self
+            .tournament_matches
+            .values()
+            .any(|seated| *seated == pairing)
+        {
+            return Ok(());
+        }
+        let mut seats: Vec<(String, String)> = seats
+            .into_iter()
+            .filter(|(connection_id, _)| {
+                self.directory.online_connections.contains(connection_id)
+                    && !self.is_seated(connection_id)
+            })
+            .collect();
+        if seats.len() < 2 {
+            return self
+                .actor_registry
+                .deliver_tournament_message(TournamentMessage::MatchEnded {
+                    tournament_id,
+                    match_id,
+                    winner: seats.pop().map(|(connection_id, _)| connection_id),
+                });
+        }
+
+        let (first_connection_id, first_player_name) = seats.remove(0);
+        let (room_id, _) =
+            self.create_room(room_name, first_connection_id, first_player_name, options)?;
+        let seated = seats
+            .into_iter()
+            .try_for_each(|(connection_id, player_name)| {
+                self.join_room(&room_id, connection_id, player_name)
+                    .map(|_| ())
+            });
+        let started = seated
+            .and_then(|()| self.set_room_state(&room_id, RoomState::Starting))
+            .and_then(|()| self.start_game(&room_id));
+        if let Err(e) = started {
+            self.close_room(&room_id);
+            return Err(e);
+        }
+        // Held back by max_games; the tournament's organizer can start it again later
+        if !self.rooms.get(&room_id).is_some_and(Room::is_in_game) {
+            let member_connections = self.close_room(&room_id);
+            return self
+                .messenger
+                .send_to_connections(member_connections, ServerResponse::RoomClosed { room_id });
+        }
+
+        self.tournament_matches.insert(room_id.clone(), pairing);
+        self.actor_registry
+            .deliver_tournament_message(TournamentMessage::MatchStarted {
+                tournament_id,
+                match_id,
+                room_id,
+            })
+    }
+
+    fn profile_token(&self, connection_id: &str) -> AppResult<String> {
+        self.directory
+            .connection_profiles
+            .get(connection_id)
+            .map(|token| token.clone())
+            .ok_or(AppError::ProfileNotFound)
+    }
+
+    /// Every open connection bound to a profile; the same profile can be online twice
+    fn profile_connections(&self, token: &str) -> Vec<String> {
+        self.directory
+            .connection_profiles
+            .iter()
+            .filter(|bound| bound.value() == token)
+            .map(|bound| bound.key().clone())
+            .collect()
+    }
+
+    fn send_friends(&self, connection_id: &str, token: &str) -> AppResult<()> {
+        let friends: Vec<(String, PlayerProfile)> = self
+            .directory
+            .profiles()
+            .friends(token)
+            .into_iter()
+            .map(|(friend_token, profile)| (friend_token.to_string(), profile.clone()))
+            .collect();
+        let friends = friends
+            .into_iter()
+            .map(|(friend_token, profile)| {
+                let connections = self.profile_connections(&friend_token);
+                // Friends may be seated on another shard
+                let room_id = connections
+                    .iter()
+                    .find_map(|connection_id| self.directory.seated_room(connection_id));
+                let in_game = room_id
+                    .as_ref()
+                    .is_some_and(|room_id| self.actor_registry.has_game(room_id));
+                FriendPresence {
+                    profile,
+                    online: !connections.is_empty(),
+                    room_id,
+                    in_game,
+                }
+            })
+            .collect();
+        self.messenger
+            .send_to_connection(connection_id, ServerResponse::FriendsPresence { friends })
+    }
+
+    fn get_profile(&self, connection_id: &str) -> Option<PlayerProfile> {
+        self.directory.profile(connection_id)
+    }
+
+    /// Start the game of a room that is `Starting`; a room that isn't can't start twice
+    fn start_game(&mut self, room_id: &str) -> AppResult<()> {
+        self.rooms
+            .get(room_id)
+            .ok_or(AppError::RoomNotFound {
+                room_id: room_id.to_string(),
+            })?
+            .check_transition(&RoomState::InGame)?;
+
+        // The whole room is told, the start may have been triggered by a countdown
+        if let Some(max_games) = self.config.max_games {
+            if self.actor_registry.game_count() >= max_games {
+                let error = self
+                    .actor_registry
+                    .metrics()
+                    .limit_hit(Limit::Games, max_games);
+                for connection_id in self.messenger.room_connections(room_id)? {
+                    let locale = self.actor_registry.locale(&connection_id);
+                    self.messenger.send_to_connection(
+                        &connection_id,
+                        ServerResponse::localized_error(&error, locale),
+                    )?;
+                }
+                return self.set_room_state(room_id, RoomState::Lobby);
+            }
+        }
+
+        let players_mapping = self.get_players_mapping(room_id)?;
+        println!(
+            "🏛️ Starting game for room {} with players: {:?}",
+            room_id, players_mapping
+        );
+
+        let player_names: HashMap<String, String> = players_mapping
+            .iter()
+            .map(|(player_id, connection_id)| (player_id.clone(), self.display_name(connection_id)))
+            .collect();
+        let players = player_names.values().cloned().collect();
+        let opening = self.actor_registry.start_game_actor(
+            room_id.to_string(),
+            players_mapping.clone(),
+            self.rated_players(&players_mapping),
+            self.room_options(room_id)?,
+            player_names,
+        )?;
+        self.actor_registry
+            .webhooks()
+            .notify(room_id, WebhookEvent::GameStarted { players });
+
+        for (player_id, connection_id) in &players_mapping {
+            println!(
+                "🏛️ Notifying connection {} that they are player {} in game {}",
+                connection_id, player_id, room_id
+            );
+
+            if let Err(e) = self.actor_registry.notify_connection_game_start(
+                connection_id,
+                room_id.to_string(),
+                player_id.clone(),
+            ) {
+                eprintln!(
+                    "Failed to notify connection {} of game start: {:?}",
+                    connection_id, e
+                );
+            }
+        }
+
+        if !opening.first_player_rolls.is_empty() {
+            self.messenger.broadcast_to_room(
+                room_id,
+                ServerResponse::FirstPlayerRolls {
+                    rounds: opening.first_player_rolls,
+                    first_player_id: opening.turn_order.active_player_id.clone(),
+                },
+            )?;
+        }
+        self.messenger.broadcast_to_room(
+            room_id,
+            ServerResponse::RoomGameStart {
+                turn_order: opening.turn_order.order,
+                card_set_version: opening.card_set_version,
+            },
+        )?;
+
+        self.messenger
+            .send_to_all(ServerResponse::LobbyStartedGame {
+                room_id: room_id.to_string(),
+            })?;
+
+        self.set_room_state(room_id, RoomState::InGame)
+    }
+
+    /// Move a room along its lifecycle and tell its members
+    fn set_room_state(&mut self, room_id: &str, state: RoomState) -> AppResult<()> {
+        self.rooms
+            .get_mut(room_id)
+            .ok_or(AppError::RoomNotFound {
+                room_id: room_id.to_string(),
+            })?
+            .transition(state.clone())?;
+        self.messenger.broadcast_to_room(
+            room_id,
+            ServerResponse::RoomStateChanged {
+                room_id: room_id.to_string(),
+                state,
+            },
+        )
+    }
+
+    /// Rooms are their game's id. Everyone still connected goes back to the room's lobby.
+    fn end_game(&mut self, room_id: &str) -> AppResult<()> {
+        if let Err(e) = self.actor_registry.cleanup_game_actor(room_id) {
+            eprintln!("Failed to clean up game actor {}: {:?}", room_id, e);
+        }
+        let Some(room) = self.rooms.get_mut(room_id) else {
+            // A game restored from a previous run, its seats were only known to the messenger
+            self.messenger.close_room(room_id);
+            return Ok(());
+        };
+        room.touch();
+        self.set_room_state(room_id, RoomState::Finished)?;
+        self.set_room_state(room_id, RoomState::Lobby)?;
+
+        let member_connections = self.messenger.room_connections(room_id)?;
+        for connection_id in &member_connections {
+            if !self.directory.online_connections.contains(connection_id) {
+                // Dropped mid-game; they kept their seat only while the game was running
+                self.leave_room_and_notify(connection_id)?;
+            } else if let Err(e) = self
+                .actor_registry
+                .notify_connection_lobby_return(connection_id, room_id.to_string())
+            {
+                eprintln!(
+                    "Failed to return connection {} to the lobby: {:?}",
+                    connection_id, e
+                );
+            }
+        }
+
+        self.messenger.send_to_all(ServerResponse::LobbyGameEnded {
+            room_id: room_id.to_string(),
+        })?;
+        Ok(())
+    }
+
+    /// Announce the first tick now and let a timer task feed the rest back as `CountdownTick`
+    fn start_countdown(&mut self, room_id: &str, seconds: u64) -> AppResult<()> {
+        self.messenger.broadcast_to_room(
+            room_id,
+            ServerResponse::GameStartCountdown {
+                seconds_left: seconds,
+            },
+        )?;
+
+        self.next_countdown_id += 1;
+        let countdown_id = self.next_countdown_id;
+        let actor_registry = self.actor_registry.clone();
+        let tick_room_id = room_id.to_string();
+        let timer = tokio::spawn(async move {
+            for seconds_left in (0..seconds).rev() {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                let tick = LobbyMessage::CountdownTick {
+                    room_id: tick_room_id.clone(),
+                    countdown_id,
+                    seconds_left,
+                };
+                if actor_registry.deliver_lobby_message(tick).is_err() {
+                    break;
+                }
+            }
+        });
+        self.countdowns
+            .insert(room_id.to_string(), (countdown_id, timer));
+        Ok(())
+    }
+
+    fn cancel_countdown(&mut self, room_id: &str) -> AppResult<()> {
+        if let Some((_, timer)) = self.countdowns.remove(room_id) {
+            timer.abort();
+            if let Ok(connections_id) = self.messenger.room_connections(room_id) {
+                self.messenger
+                    .send_to_connections(connections_id, ServerResponse::GameStartCancelled)?;
+            }
+        }
+        // The last member leaving removes the room along with its countdown
+        let starting = self
+            .rooms
+            .get(room_id)
+            .is_some_and(|room| *room.state() == RoomState::Starting);
+        if starting {
+            self.set_room_state(room_id, RoomState::Lobby)?;
+        }
+        Ok(())
+    }
+
+    /// Profiles of the seats that have one, keyed by player id, for ratings
+    fn rated_players(
+        &self,
+        players_mapping: &HashMap<String, String>,
+    ) -> HashMap<String, PlayerProfile> {
+        players_mapping
+            .iter()
+            .filter_map(|(player_id, connection_id)| {
+                self.get_profile(connection_id)
+                    .map(|profile| (player_id.clone(), profile))
+            })
+            .collect()
+    }
+
+    // Room members go by their player name, then profile name, then a short connection tag
+    fn display_name(&self, connection_id: &str) -> String {
+        self.get_player_name_from_connection_id(connection_id)
+            .or_else(|| {
+                self.get_profile(connection_id)
+                    .map(|profile| profile.display_name)
+            })
+            .unwrap_or_else(|| format!("Guest-{}", &connection_id[..connection_id.len().min(8)]))
+    }
+
+    /// Catch a joining or returning player up on the room's chat, unless they muted it
+    fn send_chat_history(&self, room_id: &str, connection_id: &str) -> AppResult<()> {
+        let messages = self.chat_history.recent(room_id);
+        let muted = self.directory.is_muted(connection_id, &ChatChannel::Room);
+        if messages.is_empty() || muted {
+            return Ok(());
+        }
+        self.messenger
+            .send_to_connection(connection_id, ServerResponse::ChatHistory { messages })
+    }
+
+    fn without_muted(&self, connections_id: Vec<String>, channel: &ChatChannel) -> Vec<String> {
+        connections_id
+            .into_iter()
+            .filter(|connection_id| !self.directory.is_muted(connection_id, channel))
+            .collect()
+    }
+
+    /// A resumed seat belongs to the new connection, so the player stays in the room when the
+    /// game ends. Games restored from a previous run have no room to move.
+    fn move_seat(&mut self, room_id: &str, player_id: &str, connection_id: &str) {
+        let old_connection_id = self
+            .connection_to_room_info
+            .iter()
+            .find(|(_, info)| info.room_id == room_id && info.room_player_id == player_id)
+            .map(|(old_connection_id, _)| old_connection_id.clone());
+        if let Some(info) =
+            old_connection_id.and_then(|old_connection_id| self.leave_seat(&old_connection_id))
+        {
+            self.take_seat(connection_id, info);
+            self.messenger.join_room(room_id, player_id, connection_id);
+        }
+    }
+
+    /// Seated in a room of this shard, or in one of another shard
+    fn is_seated(&self, connection_id: &str) -> bool {
+        self.connection_to_room_info.contains_key(connection_id)
+            || self
+                .directory
+                .seat_shard(connection_id)
+                .is_some_and(|shard| shard != self.shard)
+    }
+
+    fn take_seat(&mut self, connection_id: &str, info: PlayerRoomInfo) {
+        self.directory
+            .seat(connection_id, self.shard, &info.room_id);
+        self.connection_to_room_info
+            .insert(connection_id.to_string(), info);
+    }
+
+    fn leave_seat(&mut self, connection_id: &str) -> Option<PlayerRoomInfo> {
+        self.directory.unseat(connection_id, self.shard);
+        self.connection_to_room_info.remove(connection_id)
+    }
+
+    fn set_role(&self, connection_id: &str, role: Role) {
+        if let Err(e) = self
+            .actor_registry
+            .notify_connection_role(connection_id, role)
+        {
+            log::debug!("Could not update the role of {}: {:?}", connection_id, e);
+        }
+    }
+
     fn get_player_room_from_connection_id(&self, connection_id: &str) -> Option<String> {
         self.connection_to_room_info
             .get(connection_id)
@@ -360,95 +1795,249 @@ impl LobbyActor {
             .map(|info| info.room_player_id.clone())
     }
 
-    fn get_connections_id_from_room_id(&self, room_id: &str) -> AppResult<Vec<String>> {
-        self.rooms_connections_map
-            .get(room_id)
-            .ok_or(AppError::RoomNotFound {
-                room_id: room_id.to_string(),
-            })
-            .map(|connections| connections.iter().cloned().collect())
-    }
-
+    /// Returns the new player's id and the name they were seated under
     fn join_room(
         &mut self,
         room_id: &str,
         connection_id: String,
         player_name: String,
-    ) -> AppResult<String> {
-        if self.connection_to_room_info.contains_key(&connection_id) {
+    ) -> AppResult<(String, String)> {
+        if self.is_seated(&connection_id) {
             return Err(AppError::PlayerAlreadyInRoom { player_name });
         }
 
+        let profile_id = self
+            .get_profile(&connection_id)
+            .map(|profile| profile.profile_id);
         let room = self.rooms.get_mut(room_id).ok_or(AppError::RoomNotFound {
             room_id: room_id.to_string(),
         })?;
-        let new_player_id = room.add_player(player_name.clone())?;
+        if room.is_banned(&connection_id, profile_id.as_deref()) {
+            return Err(AppError::BannedFromRoom {
+                room_id: room_id.to_string(),
+            });
+        }
+        let (new_player_id, player_name) = room.add_player(player_name)?;
 
-        self.connection_to_room_info.insert(
-            connection_id.clone(),
+        self.take_seat(
+            &connection_id,
             PlayerRoomInfo {
                 room_id: room_id.to_string(),
                 room_player_id: new_player_id.clone(),
-                player_name,
+                player_name: player_name.clone(),
             },
         );
-        self.rooms_connections_map
-            .entry(room_id.to_string())
-            .or_insert_with(HashSet::new)
-            .insert(connection_id);
+        self.set_role(&connection_id, Role::Player);
+        self.messenger
+            .join_room(room_id, &new_player_id, &connection_id);
+
+        Ok((new_player_id, player_name))
+    }
 
-        Ok(new_player_id)
+    fn leave_room_and_notify(&mut self, connection_id: &str) -> AppResult<()> {
+        let room_id = self
+            .get_player_room_from_connection_id(connection_id)
+            .ok_or(AppError::ConnectionNotInRoom)?;
+
+        let (player_name, new_host_id) = self.leave_room(connection_id)?;
+        self.cancel_countdown(&room_id)?;
+
+        self.messenger
+            .broadcast_to_room(&room_id, ServerResponse::PlayerLeft { player_name })?;
+        if let Some(player_id) = new_host_id {
+            self.messenger
+                .broadcast_to_room(&room_id, ServerResponse::HostChanged { player_id })?;
+        }
+        Ok(())
     }
 
-    fn leave_room(&mut self, connection_id: &str) -> AppResult<String> {
+    /// Returns the leaving player's name and the new host if the host was the one leaving
+    fn leave_room(&mut self, connection_id: &str) -> AppResult<(String, Option<String>)> {
         let PlayerRoomInfo {
             room_id,
             room_player_id,
             player_name: _,
         } = self
             .connection_to_room_info
-            .remove(connection_id)
+            .get(connection_id)
+            .cloned()
             .ok_or(AppError::ConnectionNotInRoom)?;
 
         let room = self.rooms.get_mut(&room_id).ok_or(AppError::RoomNotFound {
             room_id: room_id.clone(),
         })?;
 
-        let connection_set =
-            self.rooms_connections_map
-                .get_mut(&room_id)
-                .ok_or(AppError::RoomNotFound {
-                    room_id: room_id.clone(),
-                })?;
-        connection_set.remove(connection_id);
-
+        // Remove from the room first so a refusal (e.g. game running) leaves everything as is
+        let previous_host_id = room.host_id().map(str::to_string);
         let removed_player_name = room.remove_player(&room_player_id)?;
+        let new_host_id = room
+            .host_id()
+            .filter(|host_id| previous_host_id.as_deref() != Some(*host_id))
+            .map(str::to_string);
 
         if room.player_count() == 0 {
             self.rooms.remove(&room_id);
+            self.directory.close_room(&room_id, self.shard);
+            self.chat_history.forget(&room_id);
+            self.actor_registry.webhooks().forget(&room_id);
         }
 
-        Ok(removed_player_name)
-    }
-
-    fn destroy_room(&mut self, room_id: &str, connection_id: &str) -> AppResult<String> {
-        self.connection_to_room_info
-            .remove(connection_id)
-            .ok_or(AppError::ConnectionNotInRoom)?;
+        self.leave_seat(connection_id);
+        self.messenger
+            .leave_room(&room_id, &room_player_id, connection_id);
+        self.set_role(connection_id, Role::Guest);
+        if let Some(new_host_id) = &new_host_id {
+            let new_host_connection_id = self
+                .connection_to_room_info
+                .iter()
+                .find(|(_, info)| info.room_id == room_id && &info.room_player_id == new_host_id)
+                .map(|(host_connection_id, _)| host_connection_id.clone());
+            if let Some(new_host_connection_id) = new_host_connection_id {
+                self.set_role(&new_host_connection_id, Role::Host);
+            }
+        }
 
-        let connection_set =
-            self.rooms_connections_map
-                .get_mut(room_id)
-                .ok_or(AppError::RoomNotFound {
-                    room_id: room_id.to_string(),
-                })?;
-        connection_set.remove(connection_id);
+        Ok((removed_player_name, new_host_id))
+    }
 
-        self.rooms.remove(room_id).ok_or(AppError::RoomNotFound {
+    /// Host only. Removes every member and returns their connections.
+    fn destroy_room(&mut self, room_id: &str, connection_id: &str) -> AppResult<Vec<String>> {
+        let room = self.rooms.get(room_id).ok_or(AppError::RoomNotFound {
             room_id: room_id.to_string(),
         })?;
+        let is_host = self
+            .connection_to_room_info
+            .get(connection_id)
+            .is_some_and(|info| info.room_id == room_id && room.is_host(&info.room_player_id));
+        if !is_host {
+            return Err(AppError::PermissionDenied {
+                action: "DestroyRoom".to_string(),
+            });
+        }
+        if room.is_in_game() {
+            return Err(AppError::RoomInGame {
+                room_id: room_id.to_string(),
+            });
+        }
+
+        Ok(self.close_room(room_id))
+    }
+
+    /// Remove a room and unseat every member; returns their connections
+    fn close_room(&mut self, room_id: &str) -> Vec<String> {
+        if let Some((_, timer)) = self.countdowns.remove(room_id) {
+            timer.abort();
+        }
+        self.rooms.remove(room_id);
+        self.directory.close_room(room_id, self.shard);
+        self.chat_history.forget(room_id);
+        self.actor_registry.webhooks().forget(room_id);
+        let member_connections = self.messenger.close_room(room_id);
+        for member_connection_id in &member_connections {
+            self.leave_seat(member_connection_id);
+            self.set_role(member_connection_id, Role::Guest);
+        }
+        member_connections
+    }
+
+    /// Tell the members of a room that was just closed that they are back in the lobby. All of
+    /// them but `closed_by` were kicked out of it.
+    fn send_members_to_lobby(
+        &self,
+        room_id: &str,
+        member_connections: Vec<String>,
+        reason: KickReason,
+        closed_by: Option<&str>,
+    ) -> AppResult<()> {
+        for member_connection_id in &member_connections {
+            if closed_by == Some(member_connection_id.as_str()) {
+                continue;
+            }
+            if let Err(e) = self
+                .actor_registry
+                .notify_connection_kicked(member_connection_id, reason)
+            {
+                eprintln!(
+                    "Failed to return connection {} to the lobby: {:?}",
+                    member_connection_id, e
+                );
+            }
+        }
+        self.messenger.send_to_connections(
+            member_connections,
+            ServerResponse::RoomClosed {
+                room_id: room_id.to_string(),
+            },
+        )
+    }
+
+    /// Anything a member sends keeps their room from expiring
+    fn touch_room(&mut self, connection_id: &str) {
+        if let Some(room) = self
+            .get_player_room_from_connection_id(connection_id)
+            .and_then(|room_id| self.rooms.get_mut(&room_id))
+        {
+            room.touch();
+        }
+    }
+
+    /// A timer task asking for `SweepRooms` every `ROOM_SWEEP_INTERVAL`
+    fn start_room_sweeper(&self) -> JoinHandle<()> {
+        let interval = self
+            .config
+            .timers
+            .room_idle_ttl()
+            .map_or(ROOM_SWEEP_INTERVAL, |ttl| ttl.min(ROOM_SWEEP_INTERVAL));
+        let actor_registry = self.actor_registry.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if actor_registry
+                    .deliver_lobby_message(LobbyMessage::SweepRooms)
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        })
+    }
 
-        Ok(room_id.to_string())
+    /// Expire rooms waiting in the lobby that every member dropped out of, or that nobody
+    /// sent anything to for `timers.room_idle_ttl_secs`. Rooms with a running game are left
+    /// to it.
+    fn sweep_rooms(&mut self) -> AppResult<()> {
+        let idle_ttl = self.config.timers.room_idle_ttl();
+        let expired: Vec<String> = self
+            .rooms
+            .iter()
+            .filter(|(room_id, room)| {
+                if room.is_in_game() {
+                    return false;
+                }
+                let abandoned = !self
+                    .messenger
+                    .room_connections(room_id)
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|connection_id| self.directory.online_connections.contains(connection_id));
+                abandoned || idle_ttl.is_some_and(|ttl| room.idle_for() >= ttl)
+            })
+            .map(|(room_id, _)| room_id.clone())
+            .collect();
+
+        for room_id in expired {
+            println!("🧹 Room {} expired", room_id);
+            let member_connections = self.close_room(&room_id);
+            self.send_members_to_lobby(
+                &room_id,
+                member_connections,
+                KickReason::RoomExpired,
+                None,
+            )?;
+            self.messenger
+                .send_to_all(ServerResponse::RoomExpired { room_id })?;
+        }
+        Ok(())
     }
 
     fn ready_player(&mut self, player_id: &str) -> AppResult<HashSet<String>> {
@@ -461,6 +2050,14 @@ impl LobbyActor {
         room.add_player_ready(player_id)
     }
 
+    fn unready_player(&mut self, room_id: &str, player_id: &str) -> AppResult<HashSet<String>> {
+        let room = self.rooms.get_mut(room_id).ok_or(AppError::RoomNotFound {
+            room_id: room_id.to_string(),
+        })?;
+
+        room.remove_player_ready(player_id)
+    }
+
     fn get_player_room_from_player_id(&self, player_id: &str) -> AppResult<String> {
         self.connection_to_room_info
             .values()
@@ -469,6 +2066,15 @@ impl LobbyActor {
             .ok_or(AppError::ConnectionNotInRoom)
     }
 
+    fn room_options(&self, room_id: &str) -> AppResult<RoomOptions> {
+        self.rooms
+            .get(room_id)
+            .map(|room| room.options().clone())
+            .ok_or(AppError::RoomNotFound {
+                room_id: room_id.to_string(),
+            })
+    }
+
     fn get_players_mapping(&self, room_id: &str) -> AppResult<HashMap<String, String>> {
         let mut players_mapping = HashMap::new();
 
@@ -488,10 +2094,8 @@ impl LobbyActor {
     }
 }
 
-impl From<mpsc::error::SendError<ConnectionCommand>> for AppError {
-    fn from(_: mpsc::error::SendError<ConnectionCommand>) -> Self {
-        AppError::Internal {
-            message: "Failed to send connection command".to_string(),
-        }
-    }
+fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
 }
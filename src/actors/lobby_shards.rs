@@ -0,0 +1,235 @@
+use dashmap::{DashMap, DashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::actors::lobby_actor::LobbyMessage;
+use crate::actors::mailbox::Mailbox;
+use crate::network::chat_filter::ChatFilter;
+use crate::network::messages::ChatChannel;
+use crate::network::profiles::{PlayerProfile, ProfileStore};
+use crate::{AppError, AppResult};
+
+/// Lobby-wide work that mustn't happen once per shard (announcements, tournament rooms, the
+/// shutdown notice, the room sweep timer) is done here
+pub const HOME_SHARD: usize = 0;
+
+/// What every lobby shard sees, whichever shard a message lands on: who is online, their
+/// profiles and chat settings, and which shard owns each room and seats each connection
+pub struct LobbyDirectory {
+    pub online_connections: DashSet<String>,
+    pub muted_channels: DashMap<String, HashSet<ChatChannel>>, // connection_id -> muted channels
+    pub connection_profiles: DashMap<String, String>,          // connection_id -> profile token
+    profiles: Mutex<ProfileStore>,
+    chat_filter: Mutex<ChatFilter>,
+    room_shards: DashMap<String, usize>, // room_id -> owning shard
+    seats: DashMap<String, (usize, String)>, // connection_id -> (shard, room_id)
+}
+
+impl LobbyDirectory {
+    pub fn new(chat_filter: ChatFilter) -> Self {
+        Self {
+            online_connections: DashSet::new(),
+            muted_channels: DashMap::new(),
+            connection_profiles: DashMap::new(),
+            profiles: Mutex::new(ProfileStore::new()),
+            chat_filter: Mutex::new(chat_filter),
+            room_shards: DashMap::new(),
+            seats: DashMap::new(),
+        }
+    }
+
+    pub fn profiles(&self) -> MutexGuard<'_, ProfileStore> {
+        self.profiles.lock().expect("Profile store lock poisoned")
+    }
+
+    pub fn chat_filter(&self) -> MutexGuard<'_, ChatFilter> {
+        self.chat_filter.lock().expect("Chat filter lock poisoned")
+    }
+
+    pub fn profile(&self, connection_id: &str) -> Option<PlayerProfile> {
+        let token = self.connection_profiles.get(connection_id)?.clone();
+        self.profiles().get(&token).cloned()
+    }
+
+    pub fn is_muted(&self, connection_id: &str, channel: &ChatChannel) -> bool {
+        self.muted_channels
+            .get(connection_id)
+            .is_some_and(|muted| muted.contains(channel))
+    }
+
+    pub fn open_room(&self, room_id: &str, shard: usize) {
+        self.room_shards.insert(room_id.to_string(), shard);
+    }
+
+    pub fn close_room(&self, room_id: &str, shard: usize) {
+        self.room_shards
+            .remove_if(room_id, |_, owner| *owner == shard);
+    }
+
+    pub fn room_shard(&self, room_id: &str) -> Option<usize> {
+        self.room_shards.get(room_id).map(|shard| *shard)
+    }
+
+    /// Rooms across every shard, for `max_rooms` and the metrics
+    pub fn room_count(&self) -> usize {
+        self.room_shards.len()
+    }
+
+    pub fn seat(&self, connection_id: &str, shard: usize, room_id: &str) {
+        self.seats
+            .insert(connection_id.to_string(), (shard, room_id.to_string()));
+    }
+
+    pub fn unseat(&self, connection_id: &str, shard: usize) {
+        self.seats
+            .remove_if(connection_id, |_, (owner, _)| *owner == shard);
+    }
+
+    pub fn seat_shard(&self, connection_id: &str) -> Option<usize> {
+        self.seats.get(connection_id).map(|seat| seat.0)
+    }
+
+    pub fn seated_room(&self, connection_id: &str) -> Option<String> {
+        self.seats.get(connection_id).map(|seat| seat.1.clone())
+    }
+
+    /// Claim the seat a connection is about to take in a room, so what it sends next follows
+    /// the join to the room's shard. The shard drops the claim if the join is refused.
+    pub fn expect_seat(&self, connection_id: &str, room_id: &str) {
+        let Some(shard) = self.room_shard(room_id) else {
+            return;
+        };
+        self.seats
+            .entry(connection_id.to_string())
+            .or_insert_with(|| (shard, room_id.to_string()));
+    }
+}
+
+/// The lobby's rooms spread over `lobby_shards` actors. A room lives on the shard it was
+/// created on and every message about it goes there; anything else is sent to the shard
+/// seating the connection, or picked by a hash of the connection id. Clones share the shards.
+#[derive(Clone)]
+pub struct LobbyShards {
+    senders: Arc<[Mailbox<LobbyMessage>]>,
+    directory: Arc<LobbyDirectory>,
+}
+
+impl LobbyShards {
+    pub fn new(senders: Vec<mpsc::Sender<LobbyMessage>>, directory: Arc<LobbyDirectory>) -> Self {
+        assert!(!senders.is_empty(), "The lobby needs at least one shard");
+        Self {
+            senders: senders.into_iter().map(Mailbox::new).collect(),
+            directory,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.senders.len()
+    }
+
+    pub fn directory(&self) -> &Arc<LobbyDirectory> {
+        &self.directory
+    }
+
+    /// Shed with ServerBusy when the shard can't keep up; for requests coming from clients
+    pub fn send(&self, message: LobbyMessage) -> AppResult<()> {
+        for (shard, message) in self.route(message) {
+            self.senders[shard].try_send(message).map_err(|e| match e {
+                TrySendError::Full(_) => AppError::ServerBusy {
+                    mailbox: "lobby".to_string(),
+                },
+                TrySendError::Closed(_) => lobby_gone(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// For messages the lobby must see even under load, like connections opening and closing
+    pub fn deliver(&self, message: LobbyMessage) -> AppResult<()> {
+        let mut delivered = true;
+        for (shard, message) in self.route(message) {
+            delivered &= self.senders[shard].deliver(message);
+        }
+        if delivered {
+            Ok(())
+        } else {
+            Err(lobby_gone())
+        }
+    }
+
+    fn route(&self, message: LobbyMessage) -> Vec<(usize, LobbyMessage)> {
+        // A batched message goes wherever the message itself would
+        let message = match message.unbatched() {
+            (message, Some(request)) => {
+                return self
+                    .route(message)
+                    .into_iter()
+                    .map(|(shard, message)| {
+                        (shard, LobbyMessage::batched(message, Some(request.clone())))
+                    })
+                    .collect();
+            }
+            (message, None) => message,
+        };
+        let shard = match &message {
+            LobbyMessage::JoinRoom {
+                connection_id,
+                room_id,
+                ..
+            } => {
+                self.directory.expect_seat(connection_id, room_id);
+                self.room_shard(room_id)
+            }
+            LobbyMessage::DestroyRoom { room_id, .. }
+            | LobbyMessage::SetSpectating { room_id, .. }
+            | LobbyMessage::CountdownTick { room_id, .. } => self.room_shard(room_id),
+            LobbyMessage::GameEnded { game_id, .. }
+            | LobbyMessage::PlayerConceded { game_id, .. } => self.room_shard(game_id),
+            LobbyMessage::Announce { .. }
+            | LobbyMessage::CancelAnnouncement { .. }
+            | LobbyMessage::AnnouncementDue { .. }
+            | LobbyMessage::StartTournamentMatch { .. }
+            | LobbyMessage::ConnectionOpened { .. } => HOME_SHARD,
+            // Every shard has rooms to sweep and seats a closed connection may have held
+            LobbyMessage::ConnectionClosed { .. }
+            | LobbyMessage::SweepRooms
+            | LobbyMessage::Shutdown => {
+                return (0..self.count())
+                    .map(|shard| (shard, message.clone()))
+                    .collect();
+            }
+            other => other.connection_id().map_or(HOME_SHARD, |connection_id| {
+                self.connection_shard(connection_id)
+            }),
+        };
+        vec![(shard, message)]
+    }
+
+    // Rooms nobody knows of are answered by the home shard
+    fn room_shard(&self, room_id: &str) -> usize {
+        self.directory.room_shard(room_id).unwrap_or(HOME_SHARD)
+    }
+
+    fn connection_shard(&self, connection_id: &str) -> usize {
+        self.directory
+            .seat_shard(connection_id)
+            .unwrap_or_else(|| hashed_shard(connection_id, self.count()))
+    }
+}
+
+/// The shard out of `shards` a connection with no seat is sent to, and creates its rooms on
+pub fn hashed_shard(connection_id: &str, shards: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    connection_id.hash(&mut hasher);
+    hasher.finish() as usize % shards
+}
+
+fn lobby_gone() -> AppError {
+    AppError::Internal {
+        message: "Failed to send message to lobby actor".to_string(),
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// An actor's bounded mailbox. Requests are shed when it's full, lifecycle messages
+/// (transitions, disconnects, a game ending) never are: they wait in an overflow queue that a
+/// single task feeds into the mailbox as room frees up, so they arrive in the order they were
+/// delivered and nothing sent later gets in ahead of them.
+pub struct Mailbox<T> {
+    sender: mpsc::Sender<T>,
+    overflow: Arc<Mutex<VecDeque<T>>>, // only ever non-empty while its task is feeding it in
+}
+
+impl<T> Clone for Mailbox<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            overflow: self.overflow.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Mailbox<T> {
+    pub fn new(sender: mpsc::Sender<T>) -> Self {
+        Self {
+            sender,
+            overflow: Arc::default(),
+        }
+    }
+
+    /// Full while delivered messages are still waiting, so requests don't overtake them
+    pub fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        let overflow = self
+            .overflow
+            .lock()
+            .expect("Mailbox overflow lock poisoned");
+        if !overflow.is_empty() && !self.sender.is_closed() {
+            return Err(TrySendError::Full(message));
+        }
+        self.sender.try_send(message)
+    }
+
+    /// Queue the message behind the mailbox when it's full. Returns false if the actor is gone.
+    pub fn deliver(&self, message: T) -> bool {
+        let mut overflow = self
+            .overflow
+            .lock()
+            .expect("Mailbox overflow lock poisoned");
+        if self.sender.is_closed() {
+            return false;
+        }
+        if !overflow.is_empty() {
+            overflow.push_back(message);
+            return true;
+        }
+        match self.sender.try_send(message) {
+            Ok(()) => true,
+            Err(TrySendError::Full(message)) => {
+                overflow.push_back(message);
+                self.feed_overflow();
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+
+    fn feed_overflow(&self) {
+        let sender = self.sender.clone();
+        let overflow = self.overflow.clone();
+        tokio::spawn(async move {
+            loop {
+                let permit = sender.reserve().await;
+                let mut overflow = overflow.lock().expect("Mailbox overflow lock poisoned");
+                let Ok(permit) = permit else {
+                    overflow.clear();
+                    return;
+                };
+                // Sent with the lock held, so a delivery can't slip in between
+                if let Some(message) = overflow.pop_front() {
+                    permit.send(message);
+                }
+                if overflow.is_empty() {
+                    return;
+                }
+            }
+        });
+    }
+}
@@ -1,4 +1,8 @@
 pub mod actor_registry;
+pub mod bot_actor;
 pub mod connection_actor;
 pub mod game_actor;
 pub mod lobby_actor;
+pub mod lobby_shards;
+pub mod mailbox;
+pub mod tournament_actor;
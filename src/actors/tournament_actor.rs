@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::actors::actor_registry::ActorRegistry;
+use crate::actors::lobby_actor::LobbyMessage;
+use crate::network::messages::ServerResponse;
+use crate::network::messenger::Messenger;
+use crate::network::room::RoomOptions;
+use crate::network::tournament::{MatchSeats, Tournament};
+use crate::{AppError, AppResult};
+
+#[derive(Debug)]
+pub enum TournamentMessage {
+    CreateTournament {
+        connection_id: String,
+        name: String,
+        options: RoomOptions,
+    },
+    Register {
+        connection_id: String,
+        tournament_id: String,
+        player_name: String,
+    },
+    Withdraw {
+        connection_id: String,
+        tournament_id: String,
+    },
+    Start {
+        connection_id: String,
+        tournament_id: String,
+    },
+    GetTournament {
+        connection_id: String,
+        tournament_id: String,
+    },
+    // Sent by the lobby once a pairing's game is running
+    MatchStarted {
+        tournament_id: String,
+        match_id: String,
+        room_id: String,
+    },
+    // Sent by the lobby when a pairing's game is over, or when it couldn't seat both sides;
+    // the winner is a connection id
+    MatchEnded {
+        tournament_id: String,
+        match_id: String,
+        winner: Option<String>,
+    },
+    Shutdown,
+}
+
+impl TournamentMessage {
+    pub fn connection_id(&self) -> Option<&str> {
+        match self {
+            TournamentMessage::CreateTournament { connection_id, .. }
+            | TournamentMessage::Register { connection_id, .. }
+            | TournamentMessage::Withdraw { connection_id, .. }
+            | TournamentMessage::Start { connection_id, .. }
+            | TournamentMessage::GetTournament { connection_id, .. } => Some(connection_id),
+            TournamentMessage::MatchStarted { .. }
+            | TournamentMessage::MatchEnded { .. }
+            | TournamentMessage::Shutdown => None,
+        }
+    }
+}
+
+/// Runs the brackets. Rooms stay the lobby's: this actor asks it to seat each pairing and
+/// hears back from it when the pairing's game is over.
+pub struct TournamentActor {
+    tournaments: HashMap<String, Tournament>,
+    actor_registry: Arc<ActorRegistry>,
+    messenger: Messenger,
+}
+
+impl TournamentActor {
+    pub fn new(actor_registry: Arc<ActorRegistry>) -> Self {
+        let messenger = actor_registry.messenger().clone();
+        Self {
+            tournaments: HashMap::new(),
+            actor_registry,
+            messenger,
+        }
+    }
+
+    pub async fn run(&mut self, mut receiver: mpsc::Receiver<TournamentMessage>) {
+        println!("🏆 Tournament actor started");
+        while let Some(message) = receiver.recv().await {
+            if let TournamentMessage::Shutdown = message {
+                break;
+            }
+            let connection_id = message.connection_id().map(str::to_string);
+            if let Err(error) = self.handle_message(message) {
+                eprintln!("Tournament actor error: {:?}", error);
+                if let Some(connection_id) = connection_id {
+                    let locale = self.actor_registry.locale(&connection_id);
+                    let _ = self.messenger.send_to_connection(
+                        &connection_id,
+                        ServerResponse::localized_error(&error, locale),
+                    );
+                }
+            }
+        }
+        println!("🏆 Tournament actor stopped");
+    }
+
+    fn handle_message(&mut self, message: TournamentMessage) -> AppResult<()> {
+        match message {
+            TournamentMessage::CreateTournament {
+                connection_id,
+                name,
+                options,
+            } => {
+                let tournament = Tournament::new(name, connection_id.clone(), options)?;
+                let tournament_id = tournament.id().to_string();
+                self.tournaments.insert(tournament_id.clone(), tournament);
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::TournamentCreated {
+                        tournament_id: tournament_id.clone(),
+                    },
+                )?;
+                self.broadcast(&tournament_id)?;
+            }
+
+            TournamentMessage::Register {
+                connection_id,
+                tournament_id,
+                player_name,
+            } => {
+                self.tournament_mut(&tournament_id)?
+                    .register(&connection_id, player_name)?;
+                self.broadcast(&tournament_id)?;
+            }
+
+            TournamentMessage::Withdraw {
+                connection_id,
+                tournament_id,
+            } => {
+                self.tournament_mut(&tournament_id)?
+                    .withdraw(&connection_id)?;
+                self.broadcast(&tournament_id)?;
+            }
+
+            TournamentMessage::Start {
+                connection_id,
+                tournament_id,
+            } => {
+                let pairings = self.tournament_mut(&tournament_id)?.start(&connection_id)?;
+                self.broadcast(&tournament_id)?;
+                self.seat(&tournament_id, pairings)?;
+            }
+
+            TournamentMessage::GetTournament {
+                connection_id,
+                tournament_id,
+            } => {
+                let bracket = self.tournament_mut(&tournament_id)?.bracket();
+                self.messenger.send_to_connection(
+                    &connection_id,
+                    ServerResponse::TournamentUpdate { bracket },
+                )?;
+            }
+
+            TournamentMessage::MatchStarted {
+                tournament_id,
+                match_id,
+                room_id,
+            } => {
+                self.tournament_mut(&tournament_id)?
+                    .match_started(&match_id, room_id);
+                self.broadcast(&tournament_id)?;
+            }
+
+            TournamentMessage::MatchEnded {
+                tournament_id,
+                match_id,
+                winner,
+            } => {
+                let pairings = self
+                    .tournament_mut(&tournament_id)?
+                    .record_result(&match_id, winner.as_deref());
+                self.broadcast(&tournament_id)?;
+                self.seat(&tournament_id, pairings)?;
+            }
+
+            TournamentMessage::Shutdown => {} // stops the loop in run()
+        }
+        Ok(())
+    }
+
+    fn tournament_mut(&mut self, tournament_id: &str) -> AppResult<&mut Tournament> {
+        self.tournaments
+            .get_mut(tournament_id)
+            .ok_or_else(|| AppError::TournamentNotFound {
+                tournament_id: tournament_id.to_string(),
+            })
+    }
+
+    /// Ask the lobby for a room per pairing; it answers with MatchStarted or MatchEnded
+    fn seat(&mut self, tournament_id: &str, pairings: Vec<MatchSeats>) -> AppResult<()> {
+        let options = self.tournament_mut(tournament_id)?.options().clone();
+        for pairing in pairings {
+            self.actor_registry
+                .deliver_lobby_message(LobbyMessage::StartTournamentMatch {
+                    tournament_id: tournament_id.to_string(),
+                    match_id: pairing.match_id,
+                    room_name: pairing.room_name,
+                    seats: pairing.seats,
+                    options: options.clone(),
+                })?;
+        }
+        Ok(())
+    }
+
+    fn broadcast(&self, tournament_id: &str) -> AppResult<()> {
+        if let Some(tournament) = self.tournaments.get(tournament_id) {
+            self.messenger
+                .send_to_all(ServerResponse::TournamentUpdate {
+                    bracket: tournament.bracket(),
+                })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+use isaac_four_souls::network::protocol::protocol_schema;
+
+// Usage: export-schema [output.json], prints to stdout without a path
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let schema = serde_json::to_string_pretty(&protocol_schema())?;
+    match std::env::args().nth(1) {
+        Some(path) => {
+            std::fs::write(&path, schema)?;
+            println!("📄 Protocol schema written to {}", path);
+        }
+        None => println!("{}", schema),
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use isaac_four_souls::game::replay::{Replay, ReplayKey};
+
+// Usage: IFS_REPLAY_KEY=<key> verify-replay <replay.json>, exits with 1 when the replay fails
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::args()
+        .nth(1)
+        .ok_or("usage: verify-replay <replay.json>")?;
+    let key = ReplayKey::from_env().ok_or("IFS_REPLAY_KEY is not set")?;
+    let replay = Replay::load(&path)?;
+    match replay.verify(&key) {
+        Ok(()) => {
+            println!("✅ {}: {} events, untampered", path, replay.events.len());
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("❌ {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,539 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::game::rules::{GameSpeed, SpeedTimers};
+use crate::{AppError, AppResult};
+
+const DEFAULT_CONFIG_PATH: &str = "server.toml";
+const ENV_PREFIX: &str = "IFS_";
+
+/// Every setting that can be overridden, by its name in server.toml. `game.turn_timeout_secs`
+/// comes from IFS_GAME_TURN_TIMEOUT_SECS or `--game.turn_timeout_secs`.
+pub const SETTINGS: &[&str] = &[
+    "bind_address",
+    "extra_bind_addresses",
+    "metrics_address",
+    "max_rooms",
+    "max_connections",
+    "max_games",
+    "lobby_shards",
+    "log_level",
+    "snapshot_dir",
+    "game_store_dir",
+    "ratings_path",
+    "replay_dir",
+    "dump_dir",
+    "timers.bot_think_ms",
+    "timers.chat_rate_window_secs",
+    "timers.shutdown_drain_secs",
+    "timers.start_countdown_secs",
+    "timers.room_idle_ttl_secs",
+    "game.roll_for_first_player",
+    "game.mulligan",
+    "game.mulligan_timeout_secs",
+    "game.choice_timeout_secs",
+    "game.turn_timeout_secs",
+    "game.priority_timeout_secs",
+    "game.afk_after_timeouts",
+    "outbound.queue_size",
+    "outbound.on_overflow",
+    "outbound.compress_above_bytes",
+    "inbound.max_frame_bytes",
+    "inbound.max_json_depth",
+    "inbound.max_malformed_frames",
+    "inbound.max_batched_messages",
+    "inbound.max_reliable_window",
+    "mailboxes.lobby",
+    "mailboxes.game",
+    "mailboxes.connection",
+    "webhooks.enabled",
+    "webhooks.allow_http",
+    "webhooks.allow_internal_addresses",
+    "webhooks.max_attempts",
+    "webhooks.timeout_secs",
+    "chat.banned_words",
+    "tls.cert_path",
+    "tls.key_path",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub bind_address: String,
+    pub extra_bind_addresses: Vec<String>, // more listeners, e.g. "[::]:8080" next to "0.0.0.0:8080"
+    pub metrics_address: Option<String>,   // plain HTTP, serves /metrics; None = not served
+    pub max_rooms: Option<usize>,          // None = unlimited
+    pub max_connections: Option<usize>,    // None = unlimited
+    pub max_games: Option<usize>,          // None = unlimited
+    pub lobby_shards: usize,               // lobby actors the rooms are spread over
+    pub log_level: LogLevel,
+    pub timers: TimerConfig,
+    pub game: GameConfig,
+    pub outbound: OutboundConfig,
+    pub inbound: InboundConfig,
+    pub mailboxes: MailboxConfig,
+    pub webhooks: WebhookConfig,
+    pub chat: ChatConfig,
+    pub tls: Option<TlsConfig>,
+    pub snapshot_dir: Option<PathBuf>, // where running games are written on shutdown
+    pub game_store_dir: Option<PathBuf>, // running games are saved here and restored on start
+    pub ratings_path: Option<PathBuf>, // None = ratings are kept in memory only
+    pub replay_dir: Option<PathBuf>,   // finished games write their replay here
+    pub dump_dir: Option<PathBuf>,     // DumpGameState also writes each dump here
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimerConfig {
+    pub bot_think_ms: u64,
+    pub chat_rate_window_secs: u64,
+    pub shutdown_drain_secs: u64,
+    pub start_countdown_secs: u64, // 0 = start as soon as everyone is ready
+    pub room_idle_ttl_secs: u64,   // rooms nobody sent anything to for this long expire; 0 = never
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub roll_for_first_player: bool, // otherwise the shuffled seating decides who starts
+    pub mulligan: bool,              // players may redraw their opening hand once
+    pub mulligan_timeout_secs: u64,  // undecided players keep their hand after this
+    pub choice_timeout_secs: u64,    // unanswered choices take their first option after this
+    pub turn_timeout_secs: u64,      // 0 = no limit; otherwise the turn is passed for the player
+    pub priority_timeout_secs: u64,  // 0 = no limit; otherwise priority is passed for the holder
+    pub afk_after_timeouts: u32,     // consecutive timed-out turns before a player is marked AFK
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutboundConfig {
+    pub queue_size: usize, // messages waiting to be written, per connection
+    pub on_overflow: OverflowPolicy, // what happens to a client that can't keep up
+    pub compress_above_bytes: usize, // gzip bigger messages for clients that asked; 0 = never
+}
+
+/// Limits on what a client may send. Frames over a limit are refused with MalformedFrame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InboundConfig {
+    pub max_frame_bytes: usize,
+    pub max_json_depth: usize,
+    pub max_malformed_frames: usize, // refused or unparseable frames before the client is disconnected
+    pub max_batched_messages: usize, // in one BatchedClientMessages
+    pub max_reliable_window: usize,  // reliable messages buffered past one that hasn't arrived
+}
+
+/// Capacity of each actor's inbox. Client messages that don't fit are answered with ServerBusy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MailboxConfig {
+    pub lobby: usize,      // per lobby shard
+    pub game: usize,       // per running game
+    pub connection: usize, // per connection, including bots
+}
+
+/// Room hosts may point game notifications at an HTTPS URL, e.g. a Discord webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub enabled: bool,
+    pub allow_http: bool, // plain http:// URLs too, for local testing
+    pub allow_internal_addresses: bool, // loopback and private network targets, for local testing
+    pub max_attempts: u32, // per notification, the wait between them doubling from 1s
+    pub timeout_secs: u64, // per attempt, connecting included
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatConfig {
+    pub banned_words: Vec<String>, // rejected in chat, whatever their case
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverflowPolicy {
+    Drop,       // skip messages until the queue has room again
+    Disconnect, // close the connection
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf, // PEM certificate chain
+    pub key_path: PathBuf,  // PEM private key (PKCS#8 or RSA)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: "127.0.0.1:8080".to_string(),
+            extra_bind_addresses: Vec::new(),
+            metrics_address: None,
+            max_rooms: None,
+            max_connections: None,
+            max_games: None,
+            lobby_shards: 1,
+            log_level: LogLevel::Info,
+            timers: TimerConfig::default(),
+            game: GameConfig::default(),
+            outbound: OutboundConfig::default(),
+            inbound: InboundConfig::default(),
+            mailboxes: MailboxConfig::default(),
+            webhooks: WebhookConfig::default(),
+            chat: ChatConfig::default(),
+            tls: None,
+            snapshot_dir: None,
+            game_store_dir: None,
+            ratings_path: None,
+            replay_dir: None,
+            dump_dir: None,
+        }
+    }
+}
+
+impl Default for TimerConfig {
+    fn default() -> Self {
+        Self {
+            bot_think_ms: 300,
+            chat_rate_window_secs: 10,
+            shutdown_drain_secs: 10,
+            start_countdown_secs: 5,
+            room_idle_ttl_secs: 1800,
+        }
+    }
+}
+
+impl Default for OutboundConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: 256,
+            on_overflow: OverflowPolicy::Disconnect,
+            compress_above_bytes: 4096,
+        }
+    }
+}
+
+impl Default for InboundConfig {
+    fn default() -> Self {
+        Self {
+            max_frame_bytes: 64 * 1024,
+            max_json_depth: 32,
+            max_malformed_frames: 10,
+            max_batched_messages: 16,
+            max_reliable_window: 64,
+        }
+    }
+}
+
+impl Default for MailboxConfig {
+    fn default() -> Self {
+        Self {
+            lobby: 4096,
+            game: 1024,
+            connection: 256,
+        }
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allow_http: false,
+            allow_internal_addresses: false,
+            max_attempts: 4,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            roll_for_first_player: false,
+            mulligan: true,
+            mulligan_timeout_secs: 30,
+            choice_timeout_secs: 30,
+            turn_timeout_secs: 120,
+            priority_timeout_secs: 30,
+            afk_after_timeouts: 2,
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn mulligan_timeout(&self) -> Duration {
+        Duration::from_secs(self.mulligan_timeout_secs)
+    }
+
+    /// The timers a room's speed stands for, Normal being the ones configured here
+    pub fn speed_timers(&self, speed: GameSpeed) -> SpeedTimers {
+        speed.preset().unwrap_or(SpeedTimers {
+            turn_secs: self.turn_timeout_secs,
+            priority_secs: self.priority_timeout_secs,
+            choice_secs: self.choice_timeout_secs,
+        })
+    }
+}
+
+impl TimerConfig {
+    pub fn bot_think_time(&self) -> Duration {
+        Duration::from_millis(self.bot_think_ms)
+    }
+
+    pub fn chat_rate_window(&self) -> Duration {
+        Duration::from_secs(self.chat_rate_window_secs)
+    }
+
+    pub fn shutdown_drain(&self) -> Duration {
+        Duration::from_secs(self.shutdown_drain_secs)
+    }
+
+    pub fn room_idle_ttl(&self) -> Option<Duration> {
+        (self.room_idle_ttl_secs > 0).then(|| Duration::from_secs(self.room_idle_ttl_secs))
+    }
+}
+
+impl From<LogLevel> for log::LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Every address the server listens on, bind_address first
+    pub fn bind_addresses(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.bind_address.as_str())
+            .chain(self.extra_bind_addresses.iter().map(String::as_str))
+    }
+
+    pub fn from_file(path: &Path) -> AppResult<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| AppError::ConfigError {
+            message: format!("{}: {}", path.display(), e),
+        })?;
+        toml::from_str(&contents).map_err(|e| AppError::ConfigError {
+            message: format!("{}: {}", path.display(), e),
+        })
+    }
+
+    /// Read the file named by IFS_CONFIG (or ./server.toml when present), then apply
+    /// environment overrides on top
+    pub fn load() -> AppResult<Self> {
+        Self::load_with_args(std::iter::empty())
+    }
+
+    /// Like `load`, with command line arguments on top of the environment: `--config <path>`
+    /// picks the file, and `--<setting> <value>` (or `--<setting>=<value>`) sets any of
+    /// `SETTINGS`. Command line beats environment beats file beats defaults.
+    pub fn load_with_args(args: impl IntoIterator<Item = String>) -> AppResult<Self> {
+        let mut config_path = std::env::var("IFS_CONFIG").ok().map(PathBuf::from);
+        let mut overrides = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                return Err(AppError::ConfigError {
+                    message: format!("unexpected argument '{}'", arg),
+                });
+            };
+            let (key, value) = match flag.split_once('=') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                None => {
+                    let value = args.next().ok_or_else(|| AppError::ConfigError {
+                        message: format!("--{}: no value given", flag),
+                    })?;
+                    (flag.to_string(), value)
+                }
+            };
+            if key == "config" {
+                config_path = Some(PathBuf::from(value));
+            } else {
+                overrides.push((key, value));
+            }
+        }
+
+        let mut config = match config_path {
+            Some(path) => Self::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        }
+        .with_env_overrides()?;
+        for (key, value) in overrides {
+            config
+                .set(&key, &value)
+                .map_err(|e| AppError::ConfigError {
+                    message: format!("--{}: {}", key, e),
+                })?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Apply IFS_<SETTING> for every one of `SETTINGS` that is set
+    pub fn with_env_overrides(mut self) -> AppResult<Self> {
+        for key in SETTINGS {
+            let name = env_name(key);
+            if let Ok(value) = std::env::var(&name) {
+                self.set(key, &value).map_err(|e| AppError::ConfigError {
+                    message: format!("{}: {}", name, e),
+                })?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Set one of `SETTINGS` from its text form. Lists are comma separated; `bind_address`
+    /// takes the first entry and replaces `extra_bind_addresses` with the rest.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "bind_address" => {
+                let mut addresses = list(value).into_iter();
+                self.bind_address = addresses.next().ok_or("no address given")?;
+                self.extra_bind_addresses = addresses.collect();
+            }
+            "extra_bind_addresses" => self.extra_bind_addresses = list(value),
+            "metrics_address" => self.metrics_address = Some(value.to_string()),
+            "max_rooms" => self.max_rooms = Some(number(value)?),
+            "max_connections" => self.max_connections = Some(number(value)?),
+            "max_games" => self.max_games = Some(number(value)?),
+            "lobby_shards" => self.lobby_shards = number(value)?,
+            "log_level" => self.log_level = variant(value)?,
+            "snapshot_dir" => self.snapshot_dir = Some(value.into()),
+            "game_store_dir" => self.game_store_dir = Some(value.into()),
+            "ratings_path" => self.ratings_path = Some(value.into()),
+            "replay_dir" => self.replay_dir = Some(value.into()),
+            "dump_dir" => self.dump_dir = Some(value.into()),
+            "timers.bot_think_ms" => self.timers.bot_think_ms = number(value)?,
+            "timers.chat_rate_window_secs" => self.timers.chat_rate_window_secs = number(value)?,
+            "timers.shutdown_drain_secs" => self.timers.shutdown_drain_secs = number(value)?,
+            "timers.start_countdown_secs" => self.timers.start_countdown_secs = number(value)?,
+            "timers.room_idle_ttl_secs" => self.timers.room_idle_ttl_secs = number(value)?,
+            "game.roll_for_first_player" => self.game.roll_for_first_player = flag(value)?,
+            "game.mulligan" => self.game.mulligan = flag(value)?,
+            "game.mulligan_timeout_secs" => self.game.mulligan_timeout_secs = number(value)?,
+            "game.choice_timeout_secs" => self.game.choice_timeout_secs = number(value)?,
+            "game.turn_timeout_secs" => self.game.turn_timeout_secs = number(value)?,
+            "game.priority_timeout_secs" => self.game.priority_timeout_secs = number(value)?,
+            "game.afk_after_timeouts" => self.game.afk_after_timeouts = number(value)?,
+            "outbound.queue_size" => self.outbound.queue_size = number(value)?,
+            "outbound.on_overflow" => self.outbound.on_overflow = variant(value)?,
+            "outbound.compress_above_bytes" => self.outbound.compress_above_bytes = number(value)?,
+            "inbound.max_frame_bytes" => self.inbound.max_frame_bytes = number(value)?,
+            "inbound.max_json_depth" => self.inbound.max_json_depth = number(value)?,
+            "inbound.max_malformed_frames" => self.inbound.max_malformed_frames = number(value)?,
+            "inbound.max_batched_messages" => self.inbound.max_batched_messages = number(value)?,
+            "inbound.max_reliable_window" => self.inbound.max_reliable_window = number(value)?,
+            "mailboxes.lobby" => self.mailboxes.lobby = number(value)?,
+            "mailboxes.game" => self.mailboxes.game = number(value)?,
+            "mailboxes.connection" => self.mailboxes.connection = number(value)?,
+            "webhooks.enabled" => self.webhooks.enabled = flag(value)?,
+            "webhooks.allow_http" => self.webhooks.allow_http = flag(value)?,
+            "webhooks.allow_internal_addresses" => {
+                self.webhooks.allow_internal_addresses = flag(value)?
+            }
+            "webhooks.max_attempts" => self.webhooks.max_attempts = number(value)?,
+            "webhooks.timeout_secs" => self.webhooks.timeout_secs = number(value)?,
+            "chat.banned_words" => self.chat.banned_words = list(value),
+            // Either path alone starts the section, the other has to come from somewhere too
+            "tls.cert_path" => self.tls_section().cert_path = value.into(),
+            "tls.key_path" => self.tls_section().key_path = value.into(),
+            _ => return Err(format!("unknown setting '{}'", key)),
+        }
+        Ok(())
+    }
+
+    fn tls_section(&mut self) -> &mut TlsConfig {
+        self.tls.get_or_insert_with(|| TlsConfig {
+            cert_path: PathBuf::new(),
+            key_path: PathBuf::new(),
+        })
+    }
+
+    /// Refuse a configuration the server can't start with
+    pub fn validate(&self) -> AppResult<()> {
+        if self.lobby_shards == 0 {
+            return Err(AppError::ConfigError {
+                message: "lobby_shards needs to be at least 1".to_string(),
+            });
+        }
+        // A channel can't be built with room for nothing
+        for (name, size) in [
+            ("lobby", self.mailboxes.lobby),
+            ("game", self.mailboxes.game),
+            ("connection", self.mailboxes.connection),
+        ] {
+            if size == 0 {
+                return Err(AppError::ConfigError {
+                    message: format!("mailboxes.{} needs to be at least 1", name),
+                });
+            }
+        }
+        if let Some(tls) = &self.tls {
+            if tls.cert_path.as_os_str().is_empty() || tls.key_path.as_os_str().is_empty() {
+                return Err(AppError::ConfigError {
+                    message: "tls needs both cert_path and key_path".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// IFS_ followed by the setting in capitals, sections joined with an underscore
+pub fn env_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))
+}
+
+fn flag(value: &str) -> Result<bool, String> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" => Ok(false),
+        _ => Err(format!("'{}' is not true or false", value)),
+    }
+}
+
+// Enums are spelled as in server.toml
+fn variant<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, String> {
+    toml::Value::String(value.trim().to_lowercase())
+        .try_into()
+        .map_err(|_| format!("unknown value '{}'", value))
+}
+
+fn list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
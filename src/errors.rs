@@ -19,6 +19,19 @@ pub enum AppError {
     #[error("Room '{room_id}' is already in game")]
     RoomInGame { room_id: String },
 
+    #[error("Room '{room_id}' is starting its game, the ready set is locked")]
+    RoomStarting { room_id: String },
+
+    #[error("Room '{room_id}' can't go from {from} to {to}")]
+    InvalidRoomTransition {
+        room_id: String,
+        from: String,
+        to: String,
+    },
+
+    #[error("Server is at capacity ({limit} {resource}), try again later")]
+    ServerAtCapacity { resource: String, limit: usize },
+
     #[error("Room name cannot be empty")]
     RoomNameEmpty,
 
@@ -42,6 +55,9 @@ pub enum AppError {
     #[error("Failed to send event to game loop: {reason}")]
     GameEventSendFailed { reason: String },
 
+    #[error("Server is busy ({mailbox} queue full), try again shortly")]
+    ServerBusy { mailbox: String },
+
     #[error("Turn order not initialized")]
     TurnOrderNotInitialized,
 
@@ -65,6 +81,9 @@ pub enum AppError {
     #[error("Unknown message: {message}")]
     UnknownMessage { message: String },
 
+    #[error("Malformed frame: {reason}")]
+    MalformedFrame { reason: String },
+
     #[error("Game ended unexpectedly")]
     GameEndedUnexpectedly,
 
@@ -89,8 +108,146 @@ pub enum AppError {
     #[error("Not player's turn")]
     NotPlayerTurn,
 
+    #[error("Waiting for every player to keep or mulligan their opening hand")]
+    MulliganInProgress,
+
+    #[error("You have already decided on your opening hand")]
+    MulliganNotAllowed,
+
+    #[error("Treasure deck is empty")]
+    EmptyTreasureDeck,
+
+    #[error("Item not found")]
+    ItemNotFound,
+
+    #[error("Curse deck is empty")]
+    EmptyCurseDeck,
+
+    #[error("Event deck is empty")]
+    EmptyEventDeck,
+
+    #[error("Curse or event not found")]
+    AttachmentNotFound,
+
+    #[error("Invalid deck order: {reason}")]
+    InvalidDeckOrder { reason: String },
+
+    #[error("Item can't be activated: {reason}")]
+    ItemNotActivatable { reason: String },
+
+    #[error("Waiting for a player to make a choice")]
+    ChoicePending,
+
+    #[error("No such choice is waiting on you")]
+    ChoiceNotFound,
+
+    #[error("'{option_id}' is not one of the options")]
+    InvalidChoiceOption { option_id: String },
+
+    #[error("Trade not found")]
+    TradeNotFound,
+
+    #[error("Invalid trade: {reason}")]
+    InvalidTrade { reason: String },
+
+    #[error("Can't undo: {reason}")]
+    UndoNotAllowed { reason: String },
+
+    #[error("No undo is waiting on you")]
+    UndoNotFound,
+
+    #[error("Can't offer a draw: {reason}")]
+    DrawNotAllowed { reason: String },
+
+    #[error("No draw offer is waiting on you")]
+    DrawOfferNotFound,
+
+    #[error("Can't play loot: {reason}")]
+    LootPlayNotAllowed { reason: String },
+
+    #[error("Can't buy that item: {reason}")]
+    PurchaseNotAllowed { reason: String },
+
+    #[error("The game is paused")]
+    GamePaused,
+
+    #[error("The game isn't paused")]
+    GameNotPaused,
+
     #[error("Internal server error: {message}")]
     Internal { message: String },
+
+    #[error("Card database error: {message}")]
+    CardDatabaseError { message: String },
+
+    #[error("Permission denied: {action}")]
+    PermissionDenied { action: String },
+
+    #[error("Unknown bot strategy '{name}'")]
+    BotStrategyNotFound { name: String },
+
+    #[error("Unknown card set '{set}'")]
+    UnknownCardSet { set: String },
+
+    #[error("Invalid house rules: {reason}")]
+    InvalidRuleSet { reason: String },
+
+    #[error("Chat message rejected: {reason}")]
+    ChatRejected { reason: String },
+
+    #[error("Profile not found")]
+    ProfileNotFound,
+
+    #[error("Authentication failed: {reason}")]
+    Unauthenticated { reason: String },
+
+    #[error("Configuration error: {message}")]
+    ConfigError { message: String },
+
+    #[error("Game session not found or expired")]
+    SessionNotFound,
+
+    #[error("No summary for game {game_id}")]
+    GameSummaryNotFound { game_id: String },
+
+    #[error("You are banned from room {room_id}")]
+    BannedFromRoom { room_id: String },
+
+    #[error("The host cannot kick themselves")]
+    CannotKickSelf,
+
+    #[error("A solo game needs between 1 and {max} bots, not {requested}")]
+    InvalidBotCount { requested: usize, max: usize },
+
+    #[error("This connection is in {games} games, send the message inside Game with its game_id")]
+    GameIdRequired { games: usize },
+
+    #[error("Someone in the room is already called '{player_name}'")]
+    PlayerNameTaken { player_name: String },
+
+    #[error("Player {profile_id} is not online")]
+    PlayerOffline { profile_id: String },
+
+    #[error("Invalid webhook: {reason}")]
+    InvalidWebhook { reason: String },
+
+    #[error("Webhooks are disabled on this server")]
+    WebhooksDisabled,
+
+    #[error("Invalid announcement: {reason}")]
+    InvalidAnnouncement { reason: String },
+
+    #[error("No scheduled announcement {announcement_id}")]
+    AnnouncementNotFound { announcement_id: String },
+
+    #[error("Replay failed verification: {reason}")]
+    ReplayTampered { reason: String },
+
+    #[error("No tournament {tournament_id}")]
+    TournamentNotFound { tournament_id: String },
+
+    #[error("Invalid tournament request: {reason}")]
+    InvalidTournament { reason: String },
 }
 
 pub type AppResult<T> = Result<T, AppError>;
@@ -110,23 +267,52 @@ impl AppError {
             | AppError::PlayerAlreadyInRoom { .. }
             | AppError::RoomFull { .. }
             | AppError::RoomInGame { .. }
-            | AppError::ConnectionNotInRoom { .. }
+            | AppError::RoomStarting { .. }
+            | AppError::InvalidRoomTransition { .. }
+            | AppError::ConnectionNotInRoom
             | AppError::TurnOrderNotInitialized
-            | AppError::UnknownMessage { .. } => ErrorCategory::ClientError,
+            | AppError::UnknownMessage { .. }
+            | AppError::MalformedFrame { .. }
+            | AppError::PermissionDenied { .. }
+            | AppError::BotStrategyNotFound { .. }
+            | AppError::UnknownCardSet { .. }
+            | AppError::ProfileNotFound
+            | AppError::Unauthenticated { .. }
+            | AppError::SessionNotFound
+            | AppError::GameSummaryNotFound { .. }
+            | AppError::BannedFromRoom { .. }
+            | AppError::PlayerOffline { .. }
+            | AppError::PlayerNameTaken { .. }
+            | AppError::GameIdRequired { .. }
+            | AppError::WebhooksDisabled
+            | AppError::AnnouncementNotFound { .. }
+            | AppError::TournamentNotFound { .. } => ErrorCategory::ClientError,
 
             AppError::InvalidPlayerName { .. }
             | AppError::InvalidRoomName { .. }
-            | AppError::RoomNameEmpty => ErrorCategory::ValidationError,
+            | AppError::RoomNameEmpty
+            | AppError::InvalidRuleSet { .. }
+            | AppError::ChatRejected { .. }
+            | AppError::CannotKickSelf
+            | AppError::InvalidBotCount { .. }
+            | AppError::InvalidWebhook { .. }
+            | AppError::InvalidAnnouncement { .. }
+            | AppError::ReplayTampered { .. }
+            | AppError::InvalidTournament { .. } => ErrorCategory::ValidationError,
 
             AppError::ConnectionNotFound { .. }
             | AppError::MessageSendFailed { .. }
             | AppError::GameMessageLoopNotFound { .. }
             | AppError::GameEventSendFailed { .. }
+            | AppError::ServerBusy { .. }
+            | AppError::ServerAtCapacity { .. }
             | AppError::SerializationError { .. }
             | AppError::WebSocketError { .. }
             | AppError::Internal { .. }
-            | AppError::GameEndedUnexpectedly { .. }
-            | AppError::GameNotFound { .. } => ErrorCategory::ServerError,
+            | AppError::GameEndedUnexpectedly
+            | AppError::GameNotFound { .. }
+            | AppError::CardDatabaseError { .. }
+            | AppError::ConfigError { .. } => ErrorCategory::ServerError,
 
             AppError::PlayersNotReady { .. }
             | AppError::NotPlayerTurn
@@ -135,6 +321,28 @@ impl AppError {
             | AppError::CardNotInHand
             | AppError::InvalidPriorityPass
             | AppError::InvalidTurnPass
+            | AppError::MulliganInProgress
+            | AppError::MulliganNotAllowed
+            | AppError::EmptyTreasureDeck
+            | AppError::ItemNotFound
+            | AppError::EmptyCurseDeck
+            | AppError::EmptyEventDeck
+            | AppError::AttachmentNotFound
+            | AppError::InvalidDeckOrder { .. }
+            | AppError::ItemNotActivatable { .. }
+            | AppError::ChoicePending
+            | AppError::ChoiceNotFound
+            | AppError::InvalidChoiceOption { .. }
+            | AppError::TradeNotFound
+            | AppError::InvalidTrade { .. }
+            | AppError::UndoNotAllowed { .. }
+            | AppError::UndoNotFound
+            | AppError::DrawNotAllowed { .. }
+            | AppError::DrawOfferNotFound
+            | AppError::LootPlayNotAllowed { .. }
+            | AppError::PurchaseNotAllowed { .. }
+            | AppError::GamePaused
+            | AppError::GameNotPaused
             | AppError::GameEnded => ErrorCategory::GameError,
         }
     }
@@ -159,28 +367,102 @@ impl AppError {
             AppError::RoomNotFound { .. } => "RoomNotFound",
             AppError::RoomFull { .. } => "RoomFull",
             AppError::RoomInGame { .. } => "RoomInGame",
+            AppError::RoomStarting { .. } => "RoomStarting",
+            AppError::InvalidRoomTransition { .. } => "InvalidRoomTransition",
             AppError::RoomNameEmpty => "RoomNameEmpty",
             AppError::PlayersNotReady { .. } => "PlayersNotReady",
             AppError::ConnectionNotFound { .. } => "ConnectionNotFound",
             AppError::MessageSendFailed { .. } => "MessageSendFailed",
             AppError::GameMessageLoopNotFound { .. } => "GameMessageLoopNotFound",
             AppError::GameEventSendFailed { .. } => "GameEventSendFailed",
+            AppError::ServerBusy { .. } => "ServerBusy",
+            AppError::ServerAtCapacity { .. } => "ServerAtCapacity",
             AppError::TurnOrderNotInitialized => "TurnOrderNotInitialized",
             AppError::InvalidPlayerName { .. } => "InvalidPlayerName",
             AppError::InvalidRoomName { .. } => "InvalidRoomName",
             AppError::SerializationError { .. } => "SerializationError",
             AppError::NotPlayerTurn => "NotPlayerTurn",
+            AppError::MulliganInProgress => "MulliganInProgress",
+            AppError::MulliganNotAllowed => "MulliganNotAllowed",
+            AppError::EmptyTreasureDeck => "EmptyTreasureDeck",
+            AppError::ItemNotFound => "ItemNotFound",
+            AppError::EmptyCurseDeck => "EmptyCurseDeck",
+            AppError::EmptyEventDeck => "EmptyEventDeck",
+            AppError::AttachmentNotFound => "AttachmentNotFound",
+            AppError::InvalidDeckOrder { .. } => "InvalidDeckOrder",
+            AppError::ItemNotActivatable { .. } => "ItemNotActivatable",
+            AppError::ChoicePending => "ChoicePending",
+            AppError::ChoiceNotFound => "ChoiceNotFound",
+            AppError::InvalidChoiceOption { .. } => "InvalidChoiceOption",
+            AppError::TradeNotFound => "TradeNotFound",
+            AppError::InvalidTrade { .. } => "InvalidTrade",
+            AppError::UndoNotAllowed { .. } => "UndoNotAllowed",
+            AppError::UndoNotFound => "UndoNotFound",
+            AppError::DrawNotAllowed { .. } => "DrawNotAllowed",
+            AppError::DrawOfferNotFound => "DrawOfferNotFound",
+            AppError::LootPlayNotAllowed { .. } => "LootPlayNotAllowed",
+            AppError::PurchaseNotAllowed { .. } => "PurchaseNotAllowed",
+            AppError::GamePaused => "GamePaused",
+            AppError::GameNotPaused => "GameNotPaused",
             AppError::GameEndedUnexpectedly => "GameEndedUnexpectedly",
             AppError::WebSocketError { .. } => "WebSocketError",
             AppError::UnknownMessage { .. } => "UnknownMessage",
+            AppError::MalformedFrame { .. } => "MalformedFrame",
             AppError::Internal { .. } => "Internal",
-            AppError::CardNotInHand { .. } => "CardNotInHand",
-            AppError::PlayerNotFound { .. } => "PlayerNotFound",
-            AppError::EmptyLootDeck { .. } => "EmptyLootDeck",
-            AppError::InvalidPriorityPass { .. } => "InvalidPriorityPass",
-            AppError::InvalidTurnPass { .. } => "InvalidTurnPass",
-            AppError::GameEnded { .. } => "GameEnded",
+            AppError::CardNotInHand => "CardNotInHand",
+            AppError::PlayerNotFound => "PlayerNotFound",
+            AppError::EmptyLootDeck => "EmptyLootDeck",
+            AppError::InvalidPriorityPass => "InvalidPriorityPass",
+            AppError::InvalidTurnPass => "InvalidTurnPass",
+            AppError::GameEnded => "GameEnded",
             AppError::GameNotFound { .. } => "GameNotFound",
+            AppError::CardDatabaseError { .. } => "CardDatabaseError",
+            AppError::PermissionDenied { .. } => "PermissionDenied",
+            AppError::BotStrategyNotFound { .. } => "BotStrategyNotFound",
+            AppError::UnknownCardSet { .. } => "UnknownCardSet",
+            AppError::InvalidRuleSet { .. } => "InvalidRuleSet",
+            AppError::ChatRejected { .. } => "ChatRejected",
+            AppError::ProfileNotFound => "ProfileNotFound",
+            AppError::Unauthenticated { .. } => "Unauthenticated",
+            AppError::ConfigError { .. } => "ConfigError",
+            AppError::SessionNotFound => "SessionNotFound",
+            AppError::GameSummaryNotFound { .. } => "GameSummaryNotFound",
+            AppError::BannedFromRoom { .. } => "BannedFromRoom",
+            AppError::CannotKickSelf => "CannotKickSelf",
+            AppError::InvalidBotCount { .. } => "InvalidBotCount",
+            AppError::PlayerOffline { .. } => "PlayerOffline",
+            AppError::PlayerNameTaken { .. } => "PlayerNameTaken",
+            AppError::GameIdRequired { .. } => "GameIdRequired",
+            AppError::InvalidWebhook { .. } => "InvalidWebhook",
+            AppError::WebhooksDisabled => "WebhooksDisabled",
+            AppError::InvalidAnnouncement { .. } => "InvalidAnnouncement",
+            AppError::AnnouncementNotFound { .. } => "AnnouncementNotFound",
+            AppError::ReplayTampered { .. } => "ReplayTampered",
+            AppError::TournamentNotFound { .. } => "TournamentNotFound",
+            AppError::InvalidTournament { .. } => "InvalidTournament",
+        }
+    }
+
+    /// The variant's fields as JSON, e.g. `{"room_id": "...", "max_players": 4}` for RoomFull, so
+    /// clients can act on them without parsing the message. None for variants without fields
+    /// and for internal failures whose fields are only meant for the server log.
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AppError::Internal { .. }
+            | AppError::SerializationError { .. }
+            | AppError::WebSocketError { .. }
+            | AppError::CardDatabaseError { .. }
+            | AppError::ConfigError { .. }
+            | AppError::ConnectionNotFound { .. }
+            | AppError::MessageSendFailed { .. }
+            | AppError::GameMessageLoopNotFound { .. }
+            | AppError::GameEventSendFailed { .. } => return None,
+            _ => {}
+        }
+        // Struct variants serialize as {"Variant": {fields}}, unit variants as a bare string
+        match serde_json::to_value(self).ok()? {
+            serde_json::Value::Object(mut variant) => variant.remove(self.variant_name()),
+            _ => None,
         }
     }
 
@@ -1,20 +1,51 @@
-use rand::rng;
-use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::game::card_loader::create_loot_deck;
-use crate::game::cards_types::LootCard;
+use crate::game::card_loader::get_database;
+use crate::game::cards_types::{
+    Attachment, AttachmentDuration, Card, CardType, Item, ItemActivation, LootCard, Zone,
+};
+use crate::game::deck::Deck;
+use crate::game::rules::RuleSet;
+use crate::game::trades::TradeOffer;
 use crate::{AppError, AppResult};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum DeckKind {
+    Loot,
+    Treasure,
+    Curse,
+    Event,
+}
+
+/// Cards left in each deck, shown to everyone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DeckSizes {
+    pub loot: usize,
+    pub treasure: usize,
+    pub curse: usize,
+    pub event: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Player {
     // pub items:
     pub max_health: u32,
     pub current_health: u32,
-    pub loot_play_turn: bool,
-    pub loot_play_char: bool,
+    pub loot_play_turn: bool, // loot plays are left this turn
+    pub loot_play_char: bool, // the character is untapped and can pay for one more
     pub hand_size: usize,
+    #[serde(default)]
+    pub coins: u32,
+    #[serde(default)]
+    pub dead: bool, // until the start of their next turn
+    #[serde(default)]
+    pub loot_plays_made: u32, // this turn, without the character's
+    #[serde(default)]
+    pub extra_loot_plays: u32, // granted by effects for this turn
 }
 
 impl Player {
@@ -24,6 +55,7 @@ impl Player {
         loot_play_turn: bool,
         loot_play_char: bool,
         hand_size: usize,
+        coins: u32,
     ) -> Self {
         Self {
             current_health,
@@ -31,46 +63,663 @@ impl Player {
             loot_play_turn,
             max_health,
             hand_size,
+            coins,
+            dead: false,
+            loot_plays_made: 0,
+            extra_loot_plays: 0,
         }
     }
 }
 
+/// What a player's latest deck search turned up. Only that player is told, the deck is
+/// shuffled straight after so nobody else learns anything from where the card was.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DeckSearch {
+    pub deck: DeckKind,
+    pub template_id: String, // the card searched for
+    pub found: Option<Card>, // the copy taken, None if the deck had none
+}
+
+/// How far the rng and decks had got at some point, see `Board::revealed_since`
 #[derive(Debug, Clone)]
+pub struct RevealMark {
+    rng: ChaCha12Rng,
+    deck_sizes: DeckSizes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Board {
-    pub loot_deck: Vec<LootCard>,
+    pub loot_deck: Deck<LootCard>,
     pub loot_discard: Vec<LootCard>,
     pub players: HashMap<String, Player>,
     pub players_hands: HashMap<String, Vec<LootCard>>,
+    #[serde(default)]
+    pub treasure_deck: Deck<Item>,
+    #[serde(default)]
+    pub treasure_discard: Vec<Item>,
+    #[serde(default)]
+    pub players_items: HashMap<String, Vec<Item>>, // player_id -> items in play
+    #[serde(default)]
+    pub coin_cap: Option<u32>, // room option, None means unlimited
+    #[serde(default)]
+    pub shop: Vec<Item>, // face-up treasures, up to rules.shop_slots
+    #[serde(default)]
+    pub curse_deck: Deck<Attachment>,
+    #[serde(default)]
+    pub event_deck: Deck<Attachment>,
+    #[serde(default)]
+    pub players_curses: HashMap<String, Vec<Attachment>>, // player_id -> curses, oldest first
+    #[serde(default)]
+    pub active_events: Vec<Attachment>, // oldest first
+    #[serde(default)]
+    pub attachment_discard: Vec<Attachment>, // curses and events that left play
+    #[serde(default)]
+    pub deck_searches: HashMap<String, DeckSearch>, // player_id -> their latest search
+    #[serde(default)]
+    pub rules: RuleSet,
+    #[serde(default)]
+    pub card_set_version: String, // of the card database the decks were built from
+    #[serde(default)]
+    next_timestamp: u64, // stamped on items as they enter play
+    // Every random decision on the board draws from this, so a seed fully determines the game.
+    // ChaCha is used directly (rather than StdRng) so its state can be saved with the board.
+    rng: ChaCha12Rng,
 }
 
 impl Board {
-    pub fn new(player_ids: Vec<String>) -> Self {
-        let mut loot_deck = create_loot_deck();
-        let mut rng = rng();
-        loot_deck.shuffle(&mut rng);
+    /// Decks are built from the cards in `card_sets` only
+    pub fn new(player_ids: Vec<String>, seed: u64, card_sets: &[String], rules: &RuleSet) -> Self {
+        // Every deck comes from the same database, even if the cards are reloaded meanwhile
+        let database = get_database();
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        // Built shuffled; from here on only a reshuffle of the discard pile changes the order
+        let mut loot_deck = Deck::new(database.create_loot_deck(&mut rng, card_sets));
 
         let mut players: HashMap<String, Player> = HashMap::new();
         let mut players_hands: HashMap<String, Vec<LootCard>> = HashMap::new();
         for player_id in player_ids {
-            let mut card_drawn: Vec<LootCard> = Vec::new();
-            for _ in 1..=3 {
-                let card = loot_deck
-                    .pop()
-                    .expect("Full deck not enough for all players"); // Unreachable error on full deck
-                card_drawn.push(card);
-            }
+            // Big starting hands on a small deck deal as far as the deck goes
+            let card_drawn = loot_deck.draw_up_to(rules.starting_hand_size);
             let card_drawn_size = card_drawn.len();
             players_hands.insert(player_id.clone(), card_drawn);
             // Characters with different healths defined here
-            let player: Player = Player::new(2, 2, true, true, card_drawn_size);
+            let player: Player = Player::new(
+                2,
+                2,
+                rules.loot_plays_per_turn > 0,
+                true,
+                card_drawn_size,
+                rules.starting_coins,
+            );
             players.insert(player_id, player);
         }
 
-        Self {
+        let treasure_deck = Deck::new(database.create_treasure_deck(&mut rng, card_sets));
+        let curse_deck = Deck::new(database.create_curse_deck(&mut rng, card_sets));
+        let event_deck = Deck::new(database.create_event_deck(&mut rng, card_sets));
+        let mut board = Self {
             loot_deck,
             loot_discard: Vec::new(),
             players,
             players_hands,
+            treasure_deck,
+            treasure_discard: Vec::new(),
+            players_items: HashMap::new(),
+            coin_cap: None,
+            shop: Vec::new(),
+            curse_deck,
+            event_deck,
+            players_curses: HashMap::new(),
+            active_events: Vec::new(),
+            attachment_discard: Vec::new(),
+            deck_searches: HashMap::new(),
+            rules: rules.clone(),
+            card_set_version: database.version.clone(),
+            next_timestamp: 0,
+            rng,
+        };
+
+        // Everyone starts with one item, dealt in the same sorted order as the hands
+        let mut player_ids: Vec<String> = board.players.keys().cloned().collect();
+        player_ids.sort();
+        for player_id in player_ids {
+            if let Ok(item) = board.gain_treasure_for_player(&player_id) {
+                board.set_eternal(&player_id, &item.entity_id);
+            }
+        }
+        board.restock_shop();
+        board
+    }
+
+    /// Fill empty shop slots from the top of the treasure deck, as far as it goes.
+    /// Returns the items put up for sale.
+    pub fn restock_shop(&mut self) -> Vec<Item> {
+        let mut stocked = Vec::new();
+        while self.shop.len() < self.rules.shop_slots {
+            let Some(mut item) = self.treasure_deck.draw() else {
+                break;
+            };
+            item.card.zone = Zone::Shop;
+            self.shop.push(item.clone());
+            stocked.push(item);
+        }
+        stocked
+    }
+
+    pub fn deck_sizes(&self) -> DeckSizes {
+        DeckSizes {
+            loot: self.loot_deck.len(),
+            treasure: self.treasure_deck.len(),
+            curse: self.curse_deck.len(),
+            event: self.event_deck.len(),
+        }
+    }
+
+    // Decks are drawn from the back, so the top card is the last one
+    /// Where the rng and decks stand now, to check later with `revealed_since`
+    pub fn reveal_mark(&self) -> RevealMark {
+        RevealMark {
+            rng: self.rng.clone(),
+            deck_sizes: self.deck_sizes(),
+        }
+    }
+
+    /// Whether anything hidden came to light since `mark`: the rng was drawn from or a card
+    /// left a deck
+    pub fn revealed_since(&self, mark: &RevealMark) -> bool {
+        self.rng != mark.rng || self.deck_sizes() != mark.deck_sizes
+    }
+
+    /// A deck's cards, top card first, borrowed from the deck as it is
+    pub fn deck_cards(&self, deck: DeckKind) -> impl Iterator<Item = &Card> + '_ {
+        fn top_first<T: std::ops::Deref<Target = Card>>(
+            cards: &[T],
+        ) -> impl Iterator<Item = &Card> {
+            cards.iter().rev().map(|card| &**card)
+        }
+        // The decks hold different card types; the ones not asked for stay empty
+        let (loot, treasure, curse, event): (&[LootCard], &[Item], &[Attachment], &[Attachment]) =
+            match deck {
+                DeckKind::Loot => (self.loot_deck.as_slice(), &[], &[], &[]),
+                DeckKind::Treasure => (&[], self.treasure_deck.as_slice(), &[], &[]),
+                DeckKind::Curse => (&[], &[], self.curse_deck.as_slice(), &[]),
+                DeckKind::Event => (&[], &[], &[], self.event_deck.as_slice()),
+            };
+        top_first(loot)
+            .chain(top_first(treasure))
+            .chain(top_first(curse))
+            .chain(top_first(event))
+    }
+
+    /// Look at the top `count` cards of a deck, top card first
+    pub fn peek_top(&self, deck: DeckKind, count: usize) -> Vec<Card> {
+        self.deck_cards(deck).take(count).cloned().collect()
+    }
+
+    /// Put the top cards of a deck back in the given order, top card first. `order` must
+    /// name exactly the top `order.len()` cards, as returned by `peek_top`.
+    pub fn reorder_top(&mut self, deck: DeckKind, order: &[String]) -> AppResult<()> {
+        match deck {
+            DeckKind::Loot => self.loot_deck.reorder_top(order),
+            DeckKind::Treasure => self.treasure_deck.reorder_top(order),
+            DeckKind::Curse => self.curse_deck.reorder_top(order),
+            DeckKind::Event => self.event_deck.reorder_top(order),
+        }
+    }
+
+    /// Move the top card of a deck to its bottom, for effects that look at a card and may
+    /// leave it for last. Returns the card that was moved.
+    pub fn put_top_on_bottom(&mut self, deck: DeckKind) -> AppResult<Card> {
+        fn top_to_bottom<T: std::ops::Deref<Target = Card>>(deck: &mut Deck<T>) -> Option<Card> {
+            let card = deck.draw()?;
+            let moved = (*card).clone();
+            deck.put_on_bottom(card);
+            Some(moved)
+        }
+        let moved = match deck {
+            DeckKind::Loot => top_to_bottom(&mut self.loot_deck),
+            DeckKind::Treasure => top_to_bottom(&mut self.treasure_deck),
+            DeckKind::Curse => top_to_bottom(&mut self.curse_deck),
+            DeckKind::Event => top_to_bottom(&mut self.event_deck),
+        };
+        moved.ok_or(match deck {
+            DeckKind::Loot => AppError::EmptyLootDeck,
+            DeckKind::Treasure => AppError::EmptyTreasureDeck,
+            DeckKind::Curse => AppError::EmptyCurseDeck,
+            DeckKind::Event => AppError::EmptyEventDeck,
+        })
+    }
+
+    /// A discard pile, the last card discarded first. Curses and events share one pile, each
+    /// kind is read from it on its own.
+    pub fn discard_cards(&self, deck: DeckKind) -> Vec<&Card> {
+        fn newest_first<T: std::ops::Deref<Target = Card>>(cards: &[T]) -> Vec<&Card> {
+            cards.iter().rev().map(|card| &**card).collect()
+        }
+        match deck {
+            DeckKind::Loot => newest_first(&self.loot_discard),
+            DeckKind::Treasure => newest_first(&self.treasure_discard),
+            DeckKind::Curse | DeckKind::Event => newest_first(&self.attachment_discard)
+                .into_iter()
+                .filter(|card| match deck {
+                    DeckKind::Curse => matches!(card.card_type, CardType::Curse),
+                    _ => matches!(card.card_type, CardType::Event),
+                })
+                .collect(),
+        }
+    }
+
+    /// Search a deck for a copy of `template_id` and take it the way the top card of that
+    /// deck is taken: loot into the hand, a treasure into play, a curse onto the player and
+    /// an event onto the board. The deck is shuffled afterwards, found or not.
+    pub fn search_deck(
+        &mut self,
+        deck: DeckKind,
+        player_id: &str,
+        template_id: &str,
+    ) -> AppResult<DeckSearch> {
+        if !self.players.contains_key(player_id) {
+            return Err(AppError::PlayerNotFound);
+        }
+
+        let wanted = |card: &Card| card.template_id == template_id;
+        let on_top = match deck {
+            DeckKind::Loot => self.loot_deck.bring_to_top(|card| wanted(card)),
+            DeckKind::Treasure => self.treasure_deck.bring_to_top(|card| wanted(card)),
+            DeckKind::Curse => self.curse_deck.bring_to_top(|card| wanted(card)),
+            DeckKind::Event => self.event_deck.bring_to_top(|card| wanted(card)),
+        };
+        let found = match (on_top, deck) {
+            (false, _) => None,
+            (true, DeckKind::Loot) => Some(self.draw_loot_for_player(player_id)?.card),
+            (true, DeckKind::Treasure) => Some(self.gain_treasure_for_player(player_id)?.card),
+            (true, DeckKind::Curse) => Some(self.attach_curse(player_id)?.card),
+            (true, DeckKind::Event) => Some(self.reveal_event()?.card),
+        };
+        match deck {
+            DeckKind::Loot => self.loot_deck.shuffle(&mut self.rng),
+            DeckKind::Treasure => self.treasure_deck.shuffle(&mut self.rng),
+            DeckKind::Curse => self.curse_deck.shuffle(&mut self.rng),
+            DeckKind::Event => self.event_deck.shuffle(&mut self.rng),
+        }
+
+        let search = DeckSearch {
+            deck,
+            template_id: template_id.to_string(),
+            found,
+        };
+        self.deck_searches
+            .insert(player_id.to_string(), search.clone());
+        Ok(search)
+    }
+
+    /// Put the top treasure into play under a player's control
+    pub fn gain_treasure_for_player(&mut self, player_id: &str) -> AppResult<Item> {
+        if !self.players.contains_key(player_id) {
+            return Err(AppError::PlayerNotFound);
+        }
+
+        let mut item = self
+            .treasure_deck
+            .draw()
+            .ok_or(AppError::EmptyTreasureDeck)?;
+        item.card.zone = Zone::Item;
+        item.card.owner_id = player_id.to_string();
+        self.next_timestamp += 1;
+        item.entered_play = self.next_timestamp;
+
+        self.players_items
+            .entry(player_id.to_string())
+            .or_default()
+            .push(item.clone());
+
+        println!("💎 Player {} gained: {}", player_id, item.name);
+        Ok(item)
+    }
+
+    /// Buy the item in a shop slot: pay the shop price, put it into play under the player's
+    /// control and restock the empty slot
+    pub fn buy_from_shop(&mut self, player_id: &str, shop_index: usize) -> AppResult<Item> {
+        self.check_purchase(player_id, shop_index)?;
+        let price = self.rules.shop_price;
+        self.lose_coins(player_id, price)?;
+
+        let mut item = self.shop.remove(shop_index);
+        item.card.zone = Zone::Item;
+        item.card.owner_id = player_id.to_string();
+        self.next_timestamp += 1;
+        item.entered_play = self.next_timestamp;
+
+        self.players_items
+            .entry(player_id.to_string())
+            .or_default()
+            .push(item.clone());
+        self.restock_shop();
+
+        println!(
+            "🛒 Player {} bought: {} for {}¢",
+            player_id, item.name, price
+        );
+        Ok(item)
+    }
+
+    /// Whether `buy_from_shop` would accept this purchase, without paying for it
+    pub fn check_purchase(&self, player_id: &str, shop_index: usize) -> AppResult<()> {
+        let player = self
+            .players
+            .get(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        if shop_index >= self.shop.len() {
+            return Err(AppError::ItemNotFound);
+        }
+        if player.coins < self.rules.shop_price {
+            return Err(AppError::PurchaseNotAllowed {
+                reason: format!(
+                    "the shop's items cost {}¢, you have {}¢",
+                    self.rules.shop_price, player.coins
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Attach the top curse to a player
+    pub fn attach_curse(&mut self, player_id: &str) -> AppResult<Attachment> {
+        if !self.players.contains_key(player_id) {
+            return Err(AppError::PlayerNotFound);
+        }
+
+        let mut curse = self.curse_deck.draw().ok_or(AppError::EmptyCurseDeck)?;
+        curse.card.zone = Zone::Attached;
+        curse.card.owner_id = player_id.to_string();
+        self.next_timestamp += 1;
+        curse.entered_play = self.next_timestamp;
+
+        self.players_curses
+            .entry(player_id.to_string())
+            .or_default()
+            .push(curse.clone());
+
+        println!("💀 Player {} was cursed: {}", player_id, curse.name);
+        Ok(curse)
+    }
+
+    pub fn remove_curse(&mut self, player_id: &str, curse_id: &str) -> AppResult<Attachment> {
+        let curses = self
+            .players_curses
+            .get_mut(player_id)
+            .ok_or(AppError::AttachmentNotFound)?;
+        let index = curses
+            .iter()
+            .position(|curse| curse.entity_id == curse_id)
+            .ok_or(AppError::AttachmentNotFound)?;
+        let curse = curses.remove(index);
+        self.discard_attachment(curse.clone());
+        Ok(curse)
+    }
+
+    pub fn remove_oldest_curse(&mut self, player_id: &str) -> AppResult<Attachment> {
+        let curse_id = self
+            .players_curses
+            .get(player_id)
+            .and_then(|curses| curses.first())
+            .map(|curse| curse.entity_id.clone())
+            .ok_or(AppError::AttachmentNotFound)?;
+        self.remove_curse(player_id, &curse_id)
+    }
+
+    /// Put the top event into play on the board
+    pub fn reveal_event(&mut self) -> AppResult<Attachment> {
+        let mut event = self.event_deck.draw().ok_or(AppError::EmptyEventDeck)?;
+        event.card.zone = Zone::Attached;
+        self.next_timestamp += 1;
+        event.entered_play = self.next_timestamp;
+        self.active_events.push(event.clone());
+
+        println!("📜 Event revealed: {}", event.name);
+        Ok(event)
+    }
+
+    pub fn end_event(&mut self, event_id: &str) -> AppResult<Attachment> {
+        let index = self
+            .active_events
+            .iter()
+            .position(|event| event.entity_id == event_id)
+            .ok_or(AppError::AttachmentNotFound)?;
+        let event = self.active_events.remove(index);
+        self.discard_attachment(event.clone());
+        Ok(event)
+    }
+
+    /// Discard the events that only last until the end of the turn
+    pub fn expire_events(&mut self) {
+        let (expired, lasting) = std::mem::take(&mut self.active_events)
+            .into_iter()
+            .partition(|event| event.duration == AttachmentDuration::EndOfTurn);
+        self.active_events = lasting;
+        for event in expired {
+            self.discard_attachment(event);
+        }
+    }
+
+    fn discard_attachment(&mut self, mut attachment: Attachment) {
+        attachment.card.zone = Zone::AttachmentDiscard;
+        attachment.card.owner_id = String::new();
+        self.attachment_discard.push(attachment);
+    }
+
+    /// Play a loot card from hand. On your own turn it takes one of the turn's loot plays,
+    /// tapping your character pays for one more at any time. Loot effects aren't resolved yet,
+    /// the card goes straight to the discard pile.
+    pub fn play_loot(
+        &mut self,
+        player_id: &str,
+        card_id: &str,
+        own_turn: bool,
+        tap_character: bool,
+    ) -> AppResult<LootCard> {
+        self.check_loot_play(player_id, card_id, own_turn, tap_character)?;
+        let card = self.discard_from_hand(player_id, card_id)?;
+        let allowed = self.rules.loot_plays_per_turn;
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        if tap_character {
+            player.loot_play_char = false;
+        } else {
+            player.loot_plays_made += 1;
+            player.loot_play_turn = player.loot_plays_made < allowed + player.extra_loot_plays;
+        }
+        Ok(card)
+    }
+
+    /// Whether `play_loot` would accept this play, without making it
+    pub fn check_loot_play(
+        &self,
+        player_id: &str,
+        card_id: &str,
+        own_turn: bool,
+        tap_character: bool,
+    ) -> AppResult<()> {
+        let player = self
+            .players
+            .get(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        let refused = if tap_character {
+            (!player.loot_play_char).then_some("your character is already tapped")
+        } else if !own_turn {
+            Some("outside your turn a loot play needs your character tapped")
+        } else {
+            (!player.loot_play_turn).then_some("no loot plays left this turn")
+        };
+        if let Some(reason) = refused {
+            return Err(AppError::LootPlayNotAllowed {
+                reason: reason.to_string(),
+            });
+        }
+        let hand = self
+            .players_hands
+            .get(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        if !hand.iter().any(|card| card.entity_id == card_id) {
+            return Err(AppError::CardNotInHand);
+        }
+        Ok(())
+    }
+
+    /// For effects that let a player play more loot this turn
+    pub fn grant_loot_plays(&mut self, player_id: &str, count: u32) {
+        let allowed = self.rules.loot_plays_per_turn;
+        if let Some(player) = self.players.get_mut(player_id) {
+            player.extra_loot_plays += count;
+            player.loot_play_turn = player.loot_plays_made < allowed + player.extra_loot_plays;
+        }
+    }
+
+    /// Start of a player's turn: fresh loot plays and an untapped character
+    pub fn reset_loot_plays(&mut self, player_id: &str) {
+        let allowed = self.rules.loot_plays_per_turn;
+        if let Some(player) = self.players.get_mut(player_id) {
+            player.loot_plays_made = 0;
+            player.extra_loot_plays = 0;
+            player.loot_play_turn = allowed > 0;
+            player.loot_play_char = true;
+        }
+    }
+
+    /// Pay an item's activation cost: tap it or spend a charge
+    pub fn activate_item(&mut self, player_id: &str, item_id: &str) -> AppResult<Item> {
+        self.check_item_activation(player_id, item_id)?;
+        let item = self
+            .players_items
+            .get_mut(player_id)
+            .and_then(|items| items.iter_mut().find(|item| item.entity_id == item_id))
+            .ok_or(AppError::ItemNotFound)?;
+        match item.activation {
+            ItemActivation::Tap => item.tapped = true,
+            ItemActivation::Charge { .. } => item.charges -= 1,
+            ItemActivation::Passive => {}
+        }
+        Ok(item.clone())
+    }
+
+    /// Whether `activate_item` would accept this activation, without paying for it
+    pub fn check_item_activation(&self, player_id: &str, item_id: &str) -> AppResult<()> {
+        let item = self
+            .players_items
+            .get(player_id)
+            .and_then(|items| items.iter().find(|item| item.entity_id == item_id))
+            .ok_or(AppError::ItemNotFound)?;
+
+        let refused = match item.activation {
+            ItemActivation::Passive => Some("passive items are always on"),
+            ItemActivation::Tap if item.tapped => Some("already tapped"),
+            ItemActivation::Charge { .. } if item.charges == 0 => Some("no charges left"),
+            ItemActivation::Tap | ItemActivation::Charge { .. } => None,
+        };
+        match refused {
+            Some(reason) => Err(AppError::ItemNotActivatable {
+                reason: reason.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn set_eternal(&mut self, player_id: &str, item_id: &str) {
+        if let Some(item) = self
+            .players_items
+            .get_mut(player_id)
+            .and_then(|items| items.iter_mut().find(|item| item.entity_id == item_id))
+        {
+            item.eternal = true;
+        }
+    }
+
+    /// Deal damage to a living player. Returns true if it killed them.
+    /// Whether `damage_player` with this much would kill the player
+    pub fn would_die(&self, player_id: &str, amount: u32) -> bool {
+        self.players
+            .get(player_id)
+            .is_some_and(|player| !player.dead && amount > 0 && player.current_health <= amount)
+    }
+
+    pub fn damage_player(&mut self, player_id: &str, amount: u32) -> AppResult<bool> {
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        if player.dead || amount == 0 {
+            return Ok(false);
+        }
+        player.current_health = player.current_health.saturating_sub(amount);
+        if player.current_health == 0 {
+            player.dead = true;
+            println!("💀 Player {} died", player_id);
+        }
+        Ok(player.dead)
+    }
+
+    /// Discard a specific card from a player's hand
+    pub fn discard_from_hand(&mut self, player_id: &str, card_id: &str) -> AppResult<LootCard> {
+        let card = self.remove_card_from_hand(player_id, card_id)?;
+        self.discard_loot_card(card.clone());
+        Ok(card)
+    }
+
+    /// Destroy an item in play. Eternal items can't be destroyed.
+    pub fn destroy_item(&mut self, player_id: &str, item_id: &str) -> AppResult<Item> {
+        let items = self
+            .players_items
+            .get_mut(player_id)
+            .ok_or(AppError::ItemNotFound)?;
+        let pos = items
+            .iter()
+            .position(|item| item.entity_id == item_id && !item.eternal)
+            .ok_or(AppError::ItemNotFound)?;
+        let mut item = items.remove(pos);
+        println!("🗑️ Destroying item: {}", item.name);
+        item.card.zone = Zone::TreasureDiscard;
+        item.card.owner_id = String::new();
+        self.treasure_discard.push(item.clone());
+        Ok(item)
+    }
+
+    /// Take a conceding player off the board: their hand, items (eternal ones too) and curses
+    /// go to the discard piles and their coins go back to the bank
+    pub fn remove_player(&mut self, player_id: &str) {
+        for card in self.players_hands.remove(player_id).unwrap_or_default() {
+            self.discard_loot_card(card);
+        }
+        for mut item in self.players_items.remove(player_id).unwrap_or_default() {
+            item.card.zone = Zone::TreasureDiscard;
+            item.card.owner_id = String::new();
+            self.treasure_discard.push(item);
+        }
+        for curse in self.players_curses.remove(player_id).unwrap_or_default() {
+            self.discard_attachment(curse);
+        }
+        self.players.remove(player_id);
+    }
+
+    /// Bring a dead player back at full health
+    pub fn respawn_player(&mut self, player_id: &str) -> bool {
+        match self.players.get_mut(player_id) {
+            Some(player) if player.dead => {
+                player.dead = false;
+                player.current_health = player.max_health;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Untap every item a player controls, at the start of their turn
+    pub fn untap_items(&mut self, player_id: &str) {
+        if let Some(items) = self.players_items.get_mut(player_id) {
+            for item in items {
+                item.tapped = false;
+            }
         }
     }
 
@@ -87,7 +736,7 @@ impl Board {
         }
 
         // Draw card and add to player's hand
-        let drawn_card = self.loot_deck.pop().ok_or(AppError::EmptyLootDeck)?;
+        let drawn_card = self.loot_deck.draw().ok_or(AppError::EmptyLootDeck)?;
 
         self.players_hands
             .get_mut(player_id)
@@ -98,14 +747,115 @@ impl Board {
         Ok(drawn_card)
     }
 
-    /// Get a player's hand (read-only)
-    pub fn get_player_hand(&self, player_id: &str) -> AppResult<Vec<LootCard>> {
-        let player_hand = self
-            .players_hands
+    /// How many cards the player has to discard to get within the house rule hand limit
+    pub fn cards_over_hand_limit(&self, player_id: &str) -> usize {
+        let Some(limit) = self.rules.hand_size_limit else {
+            return 0;
+        };
+        self.players_hands
             .get(player_id)
-            .ok_or(AppError::PlayerNotFound)?
-            .clone();
-        Ok(player_hand)
+            .map_or(0, |hand| hand.len().saturating_sub(limit))
+    }
+
+    /// Mulligan: discard the whole hand and draw the same number of cards
+    pub fn redraw_hand(&mut self, player_id: &str) -> AppResult<usize> {
+        // Drawing checks this too, but only after the hand is gone
+        if !self.players.contains_key(player_id) {
+            return Err(AppError::PlayerNotFound);
+        }
+        let hand = std::mem::take(
+            self.players_hands
+                .get_mut(player_id)
+                .ok_or(AppError::PlayerNotFound)?,
+        );
+        let hand_size = hand.len();
+        for card in hand {
+            self.discard_loot_card(card);
+        }
+        for _ in 0..hand_size {
+            self.draw_loot_for_player(player_id)?;
+        }
+        Ok(hand_size)
+    }
+
+    /// Add coins to a player, up to the coin cap. Returns how many were actually gained.
+    pub fn gain_coins(&mut self, player_id: &str, amount: u32) -> AppResult<u32> {
+        let coin_cap = self.coin_cap.unwrap_or(u32::MAX);
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        let before = player.coins;
+        player.coins = player.coins.saturating_add(amount).min(coin_cap);
+        Ok(player.coins.saturating_sub(before))
+    }
+
+    /// Take coins from a player, never below zero. Returns how many were actually lost.
+    pub fn lose_coins(&mut self, player_id: &str, amount: u32) -> AppResult<u32> {
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        let lost = amount.min(player.coins);
+        player.coins -= lost;
+        Ok(lost)
+    }
+
+    /// Move coins between players. Coins over the thief's cap go back to the bank.
+    /// Returns how many the victim lost.
+    pub fn steal_coins(&mut self, thief_id: &str, victim_id: &str, amount: u32) -> AppResult<u32> {
+        if !self.players.contains_key(thief_id) {
+            return Err(AppError::PlayerNotFound);
+        }
+        let stolen = self.lose_coins(victim_id, amount)?;
+        self.gain_coins(thief_id, stolen)?;
+        Ok(stolen)
+    }
+
+    /// Hand coins and loot cards from one player to another. Coins over the receiver's
+    /// cap go back to the bank.
+    pub fn transfer(&mut self, from_id: &str, to_id: &str, offer: &TradeOffer) -> AppResult<()> {
+        if !self.players_hands.contains_key(to_id) {
+            return Err(AppError::PlayerNotFound);
+        }
+        let coins = self.lose_coins(from_id, offer.coins)?;
+        self.gain_coins(to_id, coins)?;
+
+        for card_id in &offer.loot {
+            let hand = self
+                .players_hands
+                .get_mut(from_id)
+                .ok_or(AppError::PlayerNotFound)?;
+            let pos = hand
+                .iter()
+                .position(|card| card.entity_id == *card_id)
+                .ok_or(AppError::CardNotInHand)?;
+            let card = hand.remove(pos);
+            self.players_hands
+                .get_mut(to_id)
+                .ok_or(AppError::PlayerNotFound)?
+                .push(card);
+        }
+        Ok(())
+    }
+
+    /// Set a player's coins outright, clamped to the coin cap
+    pub fn set_coins(&mut self, player_id: &str, amount: u32) -> AppResult<()> {
+        let coin_cap = self.coin_cap.unwrap_or(u32::MAX);
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        player.coins = amount.min(coin_cap);
+        Ok(())
+    }
+
+    /// Get a player's hand (read-only), oldest card first
+    pub fn get_player_hand(&self, player_id: &str) -> AppResult<&[LootCard]> {
+        self.players_hands
+            .get(player_id)
+            .map(Vec::as_slice)
+            .ok_or(AppError::PlayerNotFound)
     }
 
     /// Get hand size for a player
@@ -113,19 +863,18 @@ impl Board {
         Ok(self.get_player_hand(player_id)?.len())
     }
 
-    /// Remove a card from a player's hand (for playing cards)
+    /// Remove a card from a player's hand (for playing cards). `card_id` is the copy's
+    /// `entity_id`: two copies of the same loot card share a `template_id`.
     pub fn remove_card_from_hand(&mut self, player_id: &str, card_id: &str) -> AppResult<LootCard> {
-        let mut hand = self
+        let hand = self
             .players_hands
-            .get(player_id)
-            .ok_or(AppError::PlayerNotFound)?
-            .clone();
-
-        if let Some(pos) = hand.iter().position(|card| card.template_id == card_id) {
-            Ok(hand.remove(pos))
-        } else {
-            Err(AppError::CardNotInHand)
-        }
+            .get_mut(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        let pos = hand
+            .iter()
+            .position(|card| card.entity_id == card_id)
+            .ok_or(AppError::CardNotInHand)?;
+        Ok(hand.remove(pos))
     }
 
     /// Add a card to the loot discard pile
@@ -142,10 +891,8 @@ impl Board {
 
         if !self.loot_discard.is_empty() {
             println!("🔄 Reshuffling loot discard pile into deck");
-            self.loot_deck.append(&mut self.loot_discard);
-
-            let mut rng = rng();
-            self.loot_deck.shuffle(&mut rng);
+            self.loot_deck
+                .shuffle_in(&mut self.loot_discard, &mut self.rng);
         }
 
         Ok(())
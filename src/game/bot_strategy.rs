@@ -0,0 +1,130 @@
+use once_cell::sync::Lazy;
+use rand::seq::IndexedRandom;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::game::cards_types::{Item, LootCard};
+use crate::game::game_state::TurnPhases;
+use crate::{AppError, AppResult};
+
+pub const DEFAULT_STRATEGY: &str = "random";
+
+/// What a bot knows about the game, rebuilt from the same responses a client receives
+#[derive(Debug, Clone, Default)]
+pub struct GameStateView {
+    pub player_id: String,
+    pub active_player: Option<String>,
+    pub priority_player: Option<String>,
+    pub phase: Option<TurnPhases>,
+    pub hand: Vec<LootCard>,
+    pub loot_deck_size: usize,
+    pub coins: u32,
+    pub shop: Vec<Item>,
+    pub shop_price: u32,
+}
+
+impl GameStateView {
+    pub fn is_my_turn(&self) -> bool {
+        self.active_player.as_deref() == Some(self.player_id.as_str())
+    }
+
+    pub fn has_priority(&self) -> bool {
+        self.priority_player.as_deref() == Some(self.player_id.as_str())
+    }
+
+    /// The shop slot of the cheapest item this player can pay for. Every item costs the
+    /// shop price, so that's the first one.
+    pub fn cheapest_affordable_item(&self) -> Option<usize> {
+        (!self.shop.is_empty() && self.coins >= self.shop_price).then_some(0)
+    }
+
+    /// Actions the server currently accepts from this player
+    pub fn legal_actions(&self) -> Vec<BotAction> {
+        let mut actions = Vec::new();
+        if self.has_priority() {
+            if self.is_my_turn() && self.phase == Some(TurnPhases::ActionStep) {
+                if let Some(shop_index) = self.cheapest_affordable_item() {
+                    actions.push(BotAction::BuyItem { shop_index });
+                }
+            }
+            // Bots never respond on other players' turns, they hand priority on
+            actions.push(if self.is_my_turn() {
+                BotAction::PassTurn
+            } else {
+                BotAction::PassPriority
+            });
+        }
+        actions
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BotAction {
+    PassTurn,
+    PassPriority,
+    BuyItem { shop_index: usize },
+    Wait,
+}
+
+pub trait BotStrategy: Send {
+    fn name(&self) -> &str;
+    fn decide_action(&mut self, view: &GameStateView) -> BotAction;
+
+    /// Answer another player's draw offer. Bots play on unless their strategy says otherwise.
+    fn accept_draw(&mut self, _view: &GameStateView) -> bool {
+        false
+    }
+}
+
+/// Picks uniformly among the legal actions, waiting when there are none
+#[derive(Debug, Default)]
+pub struct RandomStrategy;
+
+impl BotStrategy for RandomStrategy {
+    fn name(&self) -> &str {
+        DEFAULT_STRATEGY
+    }
+
+    fn decide_action(&mut self, view: &GameStateView) -> BotAction {
+        view.legal_actions()
+            .choose(&mut rand::rng())
+            .cloned()
+            .unwrap_or(BotAction::Wait)
+    }
+}
+
+pub type StrategyFactory = fn() -> Box<dyn BotStrategy>;
+
+static STRATEGIES: Lazy<RwLock<HashMap<String, StrategyFactory>>> = Lazy::new(|| {
+    let mut strategies: HashMap<String, StrategyFactory> = HashMap::new();
+    strategies.insert(DEFAULT_STRATEGY.to_string(), || Box::new(RandomStrategy));
+    RwLock::new(strategies)
+});
+
+/// Make a strategy available to `AddBot { strategy }` under `name`, replacing any existing one
+pub fn register_strategy(name: &str, factory: StrategyFactory) {
+    STRATEGIES
+        .write()
+        .expect("Bot strategy registry lock poisoned")
+        .insert(name.to_string(), factory);
+}
+
+pub fn create_strategy(name: &str) -> AppResult<Box<dyn BotStrategy>> {
+    STRATEGIES
+        .read()
+        .expect("Bot strategy registry lock poisoned")
+        .get(name)
+        .map(|factory| factory())
+        .ok_or(AppError::BotStrategyNotFound {
+            name: name.to_string(),
+        })
+}
+
+pub fn strategy_names() -> Vec<String> {
+    STRATEGIES
+        .read()
+        .expect("Bot strategy registry lock poisoned")
+        .keys()
+        .cloned()
+        .collect()
+}
@@ -1,37 +1,169 @@
+#[cfg(feature = "server")]
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use once_cell::sync::Lazy;
-use rand::rng;
 use rand::seq::SliceRandom;
+use rand::Rng;
+use ring::digest;
+#[cfg(feature = "server")]
+use std::path::Path;
+use std::sync::{Arc, RwLock};
 use std::{collections::HashMap, error::Error, fs};
-use uuid::Uuid;
+use uuid::Builder;
 
 use serde::{Deserialize, Serialize};
 
-use crate::game::cards_types::{Card, CardTemplate, CardType, LootCard, Zone};
+use crate::game::cards_types::{
+    Attachment, Card, CardTemplate, CardType, CatalogCard, GlossaryEntry, Item, ItemActivation,
+    Keyword, LootCard, Zone, DEFAULT_LANGUAGE,
+};
+use crate::{AppError, AppResult};
+
+pub const LOOT_DATABASE_PATH: &str = "src/data/cards/loot.json";
+pub const TREASURE_DATABASE_PATH: &str = "src/data/cards/treasure.json";
+pub const CURSE_DATABASE_PATH: &str = "src/data/cards/curses.json";
+pub const EVENT_DATABASE_PATH: &str = "src/data/cards/events.json";
+pub const GLOSSARY_PATH: &str = "src/data/cards/glossary.json";
+// Cards without a "set" belong to the base game, which every room plays with
+pub const BASE_SET: &str = "base";
+const DATABASE_PATHS: [&str; 4] = [
+    LOOT_DATABASE_PATH,
+    TREASURE_DATABASE_PATH,
+    CURSE_DATABASE_PATH,
+    EVENT_DATABASE_PATH,
+];
+// The same files as they were at build time, in the same order
+const EMBEDDED_DATABASE: [&str; 4] = [
+    include_str!("../data/cards/loot.json"),
+    include_str!("../data/cards/treasure.json"),
+    include_str!("../data/cards/curses.json"),
+    include_str!("../data/cards/events.json"),
+];
+const EMBEDDED_GLOSSARY: &str = include_str!("../data/cards/glossary.json");
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Database {
     pub loot_templates: HashMap<String, CardTemplate>,
+    #[serde(default)]
+    pub treasure_templates: HashMap<String, CardTemplate>,
+    #[serde(default)]
+    pub curse_templates: HashMap<String, CardTemplate>,
+    #[serde(default)]
+    pub event_templates: HashMap<String, CardTemplate>,
+    #[serde(default)]
+    pub glossary: Vec<Keyword>, // in the order the glossary file lists them
+    // Digest of the source files, reported to clients so they know which card set is active
+    pub version: String,
 }
 
 impl Database {
     pub fn load() -> Result<Self, Box<dyn Error>> {
         println!("🃏 Loading card databases...");
-        let database_path = fs::read_to_string("src/data/cards/loot.json")?;
-        let data: Vec<CardTemplate> = serde_json::from_str(&database_path)?;
-        let mut loot_templates = HashMap::new();
+        let [loot, treasure, curses, events] = DATABASE_PATHS.map(fs::read_to_string);
+        Self::parse([&loot?, &treasure?, &curses?, &events?])?
+            .with_glossary(&fs::read_to_string(GLOSSARY_PATH)?)
+    }
 
-        for database_card in data {
-            loot_templates.insert(database_card.id.clone(), database_card);
-        }
-        Ok(Self { loot_templates })
+    /// The card files compiled into the binary, for builds without a filesystem (wasm).
+    /// Same version as `load` while the files on disk are unchanged.
+    pub fn embedded() -> Result<Self, Box<dyn Error>> {
+        Self::parse(EMBEDDED_DATABASE)?.with_glossary(EMBEDDED_GLOSSARY)
+    }
+
+    /// Loot, treasure, curse and event definitions, in that order
+    pub fn parse(sources: [&str; 4]) -> Result<Self, Box<dyn Error>> {
+        let version = version_of(&sources);
+        let [loot_templates, treasure_templates, curse_templates, event_templates] =
+            sources.map(|contents| -> Result<_, Box<dyn Error>> {
+                let data: Vec<CardTemplate> = serde_json::from_str(contents)?;
+                Ok(data
+                    .into_iter()
+                    .map(|database_card| (database_card.id.clone(), database_card))
+                    .collect::<HashMap<_, _>>())
+            });
+
+        Ok(Self {
+            loot_templates: loot_templates?,
+            treasure_templates: treasure_templates?,
+            curse_templates: curse_templates?,
+            event_templates: event_templates?,
+            glossary: Vec::new(),
+            version,
+        })
+    }
+
+    /// Add the keyword glossary, which is part of the version too
+    pub fn with_glossary(mut self, source: &str) -> Result<Self, Box<dyn Error>> {
+        self.glossary = serde_json::from_str(source)?;
+        self.version = version_of(&[&self.version, source]);
+        Ok(self)
+    }
+
+    /// Every set with at least one card, sorted
+    pub fn card_sets(&self) -> Vec<String> {
+        let mut sets: Vec<String> = self
+            .loot_templates
+            .values()
+            .chain(self.treasure_templates.values())
+            .chain(self.curse_templates.values())
+            .chain(self.event_templates.values())
+            .map(|template| template.set.clone())
+            .collect();
+        sets.sort();
+        sets.dedup();
+        sets
+    }
+
+    /// Every card definition in every set, sorted by id, for clients to cache against
+    /// `catalog_version`
+    pub fn catalog(&self) -> Vec<CatalogCard> {
+        self.catalog_in(DEFAULT_LANGUAGE)
+    }
+
+    /// What a catalog in `language` is cached against: it changes with the cards and differs
+    /// between languages, so a cached catalog is never taken for one in another language
+    pub fn catalog_version(&self, language: &str) -> String {
+        format!("{}-{}", self.version, language)
+    }
+
+    /// The catalog with names and texts in `language` (a `Locale` code), English where a card
+    /// has no translation
+    pub fn catalog_in(&self, language: &str) -> Vec<CatalogCard> {
+        let mut cards: Vec<CatalogCard> = self
+            .loot_templates
+            .values()
+            .chain(self.treasure_templates.values())
+            .chain(self.curse_templates.values())
+            .chain(self.event_templates.values())
+            .map(|template| CatalogCard::new(template, language))
+            .collect();
+        cards.sort_by(|a, b| a.id.cmp(&b.id));
+        cards
+    }
+
+    /// Every keyword in `language`, English where it has no translation
+    pub fn glossary_in(&self, language: &str) -> Vec<GlossaryEntry> {
+        self.glossary
+            .iter()
+            .map(|keyword| keyword.entry(language))
+            .collect()
     }
 
-    pub fn create_loot_deck(&self) -> Vec<LootCard> {
+    // Templates are walked in id order and ids come from the given rng so a seeded
+    // rng always yields the same deck. Only cards from `card_sets` are included.
+    pub fn create_loot_deck<R: Rng>(
+        &self,
+        random_generator: &mut R,
+        card_sets: &[String],
+    ) -> Vec<LootCard> {
+        let templates = in_sets(&self.loot_templates, card_sets);
+
         let mut deck = Vec::new();
-        for template in self.loot_templates.values() {
+        for template in templates {
             for _ in 0..template.count {
                 let card = Card {
-                    entity_id: Uuid::new_v4().to_string(),
+                    entity_id: Builder::from_random_bytes(random_generator.random())
+                        .into_uuid()
+                        .to_string(),
                     template_id: template.id.clone(),
                     name: template.name.clone(),
                     description: template.description.clone(),
@@ -44,23 +176,209 @@ impl Database {
                 deck.push(LootCard { card });
             }
         }
-        let mut random_generator = rng();
-        deck.shuffle(&mut random_generator);
-        println!("{:?}", deck);
+        deck.shuffle(random_generator);
+        log::trace!("{:?}", deck);
+        deck
+    }
+
+    // Same ordering guarantees as the loot deck
+    pub fn create_treasure_deck<R: Rng>(
+        &self,
+        random_generator: &mut R,
+        card_sets: &[String],
+    ) -> Vec<Item> {
+        let templates = in_sets(&self.treasure_templates, card_sets);
+
+        let mut deck = Vec::new();
+        for template in templates {
+            for _ in 0..template.count {
+                let card = Card {
+                    entity_id: Builder::from_random_bytes(random_generator.random())
+                        .into_uuid()
+                        .to_string(),
+                    template_id: template.id.clone(),
+                    name: template.name.clone(),
+                    description: template.description.clone(),
+                    zone: Zone::TreasureDeck,
+                    card_type: CardType::Treasure,
+                    owner_id: String::new(), // Set when gained
+                    subtype: template.subtype.clone(),
+                };
+                let charges = match template.activation {
+                    ItemActivation::Charge { charges } => charges,
+                    ItemActivation::Passive | ItemActivation::Tap => 0,
+                };
+
+                deck.push(Item {
+                    card,
+                    activation: template.activation,
+                    tapped: false,
+                    charges,
+                    eternal: false,
+                    triggers: template.triggers.clone(),
+                    entered_play: 0,
+                });
+            }
+        }
+        deck.shuffle(random_generator);
+        deck
+    }
+
+    pub fn create_curse_deck<R: Rng>(
+        &self,
+        random_generator: &mut R,
+        card_sets: &[String],
+    ) -> Vec<Attachment> {
+        Self::create_attachment_deck(
+            in_sets(&self.curse_templates, card_sets),
+            CardType::Curse,
+            Zone::CurseDeck,
+            random_generator,
+        )
+    }
+
+    pub fn create_event_deck<R: Rng>(
+        &self,
+        random_generator: &mut R,
+        card_sets: &[String],
+    ) -> Vec<Attachment> {
+        Self::create_attachment_deck(
+            in_sets(&self.event_templates, card_sets),
+            CardType::Event,
+            Zone::EventDeck,
+            random_generator,
+        )
+    }
+
+    // Same ordering guarantees as the loot deck
+    fn create_attachment_deck<R: Rng>(
+        templates: Vec<&CardTemplate>,
+        card_type: CardType,
+        zone: Zone,
+        random_generator: &mut R,
+    ) -> Vec<Attachment> {
+        let mut deck = Vec::new();
+        for template in templates {
+            for _ in 0..template.count {
+                let card = Card {
+                    entity_id: Builder::from_random_bytes(random_generator.random())
+                        .into_uuid()
+                        .to_string(),
+                    template_id: template.id.clone(),
+                    name: template.name.clone(),
+                    description: template.description.clone(),
+                    zone: zone.clone(),
+                    card_type: card_type.clone(),
+                    owner_id: String::new(), // Set when attached
+                    subtype: template.subtype.clone(),
+                };
+                deck.push(Attachment {
+                    card,
+                    triggers: template.triggers.clone(),
+                    duration: template.duration,
+                    entered_play: 0,
+                });
+            }
+        }
+        deck.shuffle(random_generator);
         deck
     }
 }
-static CARD_DATABASE: Lazy<Database> =
-    Lazy::new(|| Database::load().expect("Failed to load card database"));
-pub fn get_database() -> &'static Database {
-    &CARD_DATABASE
+
+/// The first 8 bytes of a SHA-256 over `sources`, hex encoded. Unlike std's hashers it comes
+/// out the same on every build and platform, so a client can check it against the server's.
+fn version_of(sources: &[&str]) -> String {
+    let mut context = digest::Context::new(&digest::SHA256);
+    for source in sources {
+        // Length first, so text moved from one file to the next still changes the version
+        context.update(&(source.len() as u64).to_le_bytes());
+        context.update(source.as_bytes());
+    }
+    context.finish().as_ref()[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
 }
 
-pub fn create_loot_deck() -> Vec<LootCard> {
-    CARD_DATABASE.create_loot_deck()
+/// Templates from the given sets, in id order
+fn in_sets<'a>(
+    templates: &'a HashMap<String, CardTemplate>,
+    card_sets: &[String],
+) -> Vec<&'a CardTemplate> {
+    let mut templates: Vec<&CardTemplate> = templates
+        .values()
+        .filter(|template| card_sets.contains(&template.set))
+        .collect();
+    templates.sort_by(|a, b| a.id.cmp(&b.id));
+    templates
+}
+
+// Swapped wholesale on reload: games already running keep the deck they were dealt,
+// rooms still in Lobby pick up the new definitions when their Board is built
+static CARD_DATABASE: Lazy<RwLock<Arc<Database>>> = Lazy::new(|| {
+    #[cfg(feature = "server")]
+    let database = Database::load();
+    #[cfg(not(feature = "server"))]
+    let database = Database::embedded();
+    RwLock::new(Arc::new(database.expect("Failed to load card database")))
+});
+
+pub fn get_database() -> Arc<Database> {
+    CARD_DATABASE
+        .read()
+        .expect("Card database lock poisoned")
+        .clone()
 }
 
 pub fn initialize_database() {
     let _ = &*CARD_DATABASE;
     println!("🎮 Global card database initialized");
 }
+
+/// Reload card definitions from disk, keeping the current set if the new one fails to parse
+pub fn reload_database() -> AppResult<String> {
+    let database = Database::load().map_err(|e| AppError::CardDatabaseError {
+        message: e.to_string(),
+    })?;
+    let version = database.version.clone();
+    replace_database(database);
+
+    println!("🔄 Card database reloaded, version {}", version);
+    Ok(version)
+}
+
+/// Swap in a database from elsewhere, e.g. a client matching the server's `card_set_version`
+pub fn replace_database(database: Database) {
+    *CARD_DATABASE.write().expect("Card database lock poisoned") = Arc::new(database);
+}
+
+/// Watch the card data directory and reload on change. The returned watcher must be kept alive.
+#[cfg(feature = "server")]
+pub fn watch_database() -> notify::Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(|result: notify::Result<Event>| match result {
+        Ok(event) => {
+            let touches_database = event.paths.iter().any(|path| {
+                DATABASE_PATHS
+                    .iter()
+                    .chain([&GLOSSARY_PATH])
+                    .any(|database| path.ends_with(database))
+            });
+            if touches_database && matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            {
+                if let Err(e) = reload_database() {
+                    eprintln!("❌ Card database reload failed: {}", e);
+                }
+            }
+        }
+        Err(e) => eprintln!("❌ Card database watcher error: {}", e),
+    })?;
+
+    // Watch the directory rather than the file so editors that replace the file are picked up
+    let watched_dir = Path::new(LOOT_DATABASE_PATH)
+        .parent()
+        .unwrap_or(Path::new("."));
+    watcher.watch(watched_dir, RecursiveMode::NonRecursive)?;
+    println!("👀 Watching {} for card changes", watched_dir.display());
+
+    Ok(watcher)
+}
@@ -1,6 +1,14 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::ops::Deref;
 
+use crate::game::card_loader::BASE_SET;
+use crate::game::triggers::Trigger;
+
+// What the card files are written in; `translations` add the other languages
+pub const DEFAULT_LANGUAGE: &str = "en";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CardTemplate {
     pub id: String,
@@ -9,30 +17,177 @@ pub struct CardTemplate {
     pub subtype: String,
     pub description: String,
     pub count: u32, // How many copies to create
+    #[serde(default = "base_set")]
+    pub set: String, // Expansion the card ships in, e.g. "gold_box"
+    #[serde(default)]
+    pub activation: ItemActivation, // Only meaningful for treasures
+    #[serde(default)]
+    pub triggers: Vec<Trigger>, // Treasures, curses and events
+    #[serde(default)]
+    pub duration: AttachmentDuration, // Only meaningful for events
+    #[serde(default)]
+    pub assets: CardAssets,
+    #[serde(default)]
+    pub translations: HashMap<String, CardText>, // language code ("it", "es") -> name and text
+}
+
+impl CardTemplate {
+    /// Name and text in `language`, English where the card has no translation for it
+    pub fn text(&self, language: &str) -> CardText {
+        localized(&self.name, &self.description, &self.translations, language)
+    }
+}
+
+fn localized(
+    name: &str,
+    description: &str,
+    translations: &HashMap<String, CardText>,
+    language: &str,
+) -> CardText {
+    translations
+        .get(language)
+        .cloned()
+        .unwrap_or_else(|| CardText {
+            name: name.to_string(),
+            description: description.to_string(),
+        })
+}
+
+fn base_set() -> String {
+    BASE_SET.to_string()
 }
 
+/// How clients draw a card. All optional in the card files, the catalog fills in defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CardAssets {
+    pub image_url: Option<String>, // defaults to "cards/<set>/<id>.png", relative to the client's asset root
+    pub frame: Option<String>,     // defaults to the card type, e.g. "loot"
+    pub name_key: Option<String>,  // localization keys, "cards.<id>.name" and "cards.<id>.text"
+    pub text_key: Option<String>,
+}
+
+/// A card's name and text in one language
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CardText {
+    pub name: String,
+    pub description: String,
+}
+
+/// A rules keyword explained for players, from `glossary.json`
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keyword {
+    pub keyword: String, // e.g. "tap", never translated
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub translations: HashMap<String, CardText>,
+}
+
+/// A keyword as listed in `Glossary`, in one language
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GlossaryEntry {
+    pub keyword: String,
+    pub name: String,
+    pub description: String,
+}
+
+impl Keyword {
+    pub fn entry(&self, language: &str) -> GlossaryEntry {
+        let text = localized(&self.name, &self.description, &self.translations, language);
+        GlossaryEntry {
+            keyword: self.keyword.clone(),
+            name: text.name,
+            description: text.description,
+        }
+    }
+}
+
+/// A card definition as listed in `CardCatalog`, with its assets resolved
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CatalogCard {
+    pub id: String, // a card's template_id
+    pub name: String,
+    pub card_type: String,
+    pub subtype: String,
+    pub description: String,
+    pub set: String,
+    pub count: u32,
+    pub image_url: String,
+    pub frame: String,
+    pub name_key: String,
+    pub text_key: String,
+}
+
+impl From<&CardTemplate> for CatalogCard {
+    fn from(template: &CardTemplate) -> Self {
+        Self::new(template, DEFAULT_LANGUAGE)
+    }
+}
+
+impl CatalogCard {
+    /// The definition with its name and text in `language`
+    pub fn new(template: &CardTemplate, language: &str) -> Self {
+        let assets = &template.assets;
+        let text = template.text(language);
+        Self {
+            id: template.id.clone(),
+            name: text.name,
+            card_type: template.card_type.clone(),
+            subtype: template.subtype.clone(),
+            description: text.description,
+            set: template.set.clone(),
+            count: template.count,
+            image_url: assets
+                .image_url
+                .clone()
+                .unwrap_or_else(|| format!("cards/{}/{}.png", template.set, template.id)),
+            frame: assets
+                .frame
+                .clone()
+                .unwrap_or_else(|| template.card_type.clone()),
+            name_key: assets
+                .name_key
+                .clone()
+                .unwrap_or_else(|| format!("cards.{}.name", template.id)),
+            text_key: assets
+                .text_key
+                .clone()
+                .unwrap_or_else(|| format!("cards.{}.text", template.id)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum Zone {
     Hand,
     LootDeck,
     LootDiscard,
+    TreasureDeck,
+    TreasureDiscard,
     Playing,
     Item,
+    CurseDeck,
+    EventDeck,
+    Attached, // a curse on a player or an event on the board
+    Shop,
+    AttachmentDiscard,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum CardType {
     Monster,
     Loot,
     Treasure,
     Character,
     BonusSoul,
+    Curse,
+    Event,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Card {
-    pub entity_id: String,
-    pub template_id: String,
+    pub entity_id: String, // unique per copy, assigned when the deck is built; what messages refer to
+    pub template_id: String, // the card definition, shared by every copy of it
     pub name: String,
     pub description: String,
     pub zone: Zone,
@@ -41,7 +196,7 @@ pub struct Card {
     pub subtype: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LootCard {
     #[serde(flatten)]
     pub card: Card,
@@ -54,3 +209,67 @@ impl Deref for LootCard {
         &self.card
     }
 }
+
+/// How an item in play is used
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum ItemActivation {
+    #[default]
+    Passive, // Always on, can't be activated
+    Tap, // Untaps at the start of its owner's turn
+    Charge {
+        charges: u32,
+    }, // Spends a charge per use, never untaps on its own
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Item {
+    #[serde(flatten)]
+    pub card: Card,
+    pub activation: ItemActivation,
+    pub tapped: bool,
+    pub charges: u32, // Charges left, only used by Charge items
+    #[serde(default)]
+    pub eternal: bool, // Starting items, never destroyed
+    #[serde(default)]
+    pub triggers: Vec<Trigger>,
+    #[serde(default)]
+    pub entered_play: u64, // timestamp, triggers resolve oldest item first
+}
+
+impl Deref for Item {
+    type Target = Card;
+
+    fn deref(&self) -> &Self::Target {
+        &self.card
+    }
+}
+
+/// How long an attachment stays once it comes into play
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum AttachmentDuration {
+    #[default]
+    UntilRemoved,
+    EndOfTurn, // discarded as the turn it came into play ends
+}
+
+/// A curse attached to a player or an event on the board, whose triggers apply for as long
+/// as it stays there. Curse triggers fire for the cursed player, event triggers for everyone.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Attachment {
+    #[serde(flatten)]
+    pub card: Card,
+    #[serde(default)]
+    pub triggers: Vec<Trigger>,
+    #[serde(default)]
+    pub duration: AttachmentDuration,
+    #[serde(default)]
+    pub entered_play: u64, // same timestamps as items
+}
+
+impl Deref for Attachment {
+    type Target = Card;
+
+    fn deref(&self) -> &Self::Target {
+        &self.card
+    }
+}
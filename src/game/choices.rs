@@ -0,0 +1,38 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What answering a choice does to the board
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ChoiceKind {
+    DiscardLoot, // death penalty, options are loot cards in hand
+    DestroyItem, // death penalty, options are non-eternal items in play
+    HandLimit,   // end of turn, options are loot cards in hand, newest first
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ChoiceOption {
+    pub option_id: String, // entity id of the card it stands for
+    pub label: String,
+}
+
+/// A decision the server is waiting on. The first option is taken if the player doesn't answer in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingChoice {
+    pub choice_id: String,
+    pub player_id: String,
+    pub kind: ChoiceKind,
+    pub prompt: String,
+    pub options: Vec<ChoiceOption>,
+}
+
+impl PendingChoice {
+    pub fn default_option(&self) -> Option<&ChoiceOption> {
+        self.options.first()
+    }
+
+    pub fn has_option(&self, option_id: &str) -> bool {
+        self.options
+            .iter()
+            .any(|option| option.option_id == option_id)
+    }
+}
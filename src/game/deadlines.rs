@@ -0,0 +1,130 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::game::game_state::{GameState, TurnPhases};
+use crate::game::rules::SpeedTimers;
+
+// Turn, phase, holder and passes so far: priority coming back to a player is a new window
+pub type PriorityWindow = (u32, TurnPhases, String, usize);
+
+pub fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|since| since.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// The priority window players are waiting on, if any. Open choices and mulligans are timed
+/// on their own and hold priority up until they're answered.
+pub fn priority_window(state: &GameState) -> Option<PriorityWindow> {
+    if !state.waiting_for_priority
+        || state.is_mulligan_pending()
+        || state.pending_choice().is_some()
+    {
+        return None;
+    }
+    Some((
+        state.turn_order.get_turn_counter(),
+        state.current_phase.clone(),
+        state.current_priority_player.clone(),
+        state.players_passed_priority.len(),
+    ))
+}
+
+/// A timed window as a client sees it: when it runs out in unix millis, and how long that is
+/// from the moment it was sent, for clients whose clock is off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct WindowTimer {
+    pub deadline: u64,
+    pub remaining_ms: u64,
+}
+
+impl WindowTimer {
+    pub fn at(deadline: u64, now: u64) -> Self {
+        Self {
+            deadline,
+            remaining_ms: deadline.saturating_sub(now),
+        }
+    }
+}
+
+/// When the turn, priority and choice windows opened by the game so far run out, in unix
+/// millis. The coordinator stamps them on the responses that open a window and the game actor
+/// starts its own timers from them, so what clients count down to is what they are held to.
+#[derive(Debug, Clone, Default)]
+pub struct WindowClock {
+    timers: SpeedTimers,
+    turn: Option<(u32, Option<u64>)>, // (turn counter, deadline), the deadline None without a limit
+    priority: Option<(PriorityWindow, Option<u64>)>,
+    choice: Option<(String, u64)>, // (choice_id, deadline)
+}
+
+/// Which windows a `refresh` opened
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenedWindows {
+    pub turn: bool,
+    pub priority: bool,
+    pub choice: bool,
+}
+
+impl WindowClock {
+    pub fn new(timers: SpeedTimers) -> Self {
+        Self {
+            timers,
+            ..Self::default()
+        }
+    }
+
+    /// Open whatever windows the game moved into since the last refresh, with the full time
+    /// from `now`. Windows still open keep their deadline.
+    pub fn refresh(&mut self, state: &GameState, now: u64) -> OpenedWindows {
+        let mut opened = OpenedWindows::default();
+        let after = |secs: u64| (secs > 0).then(|| now + secs * 1000);
+
+        let turn = state.turn_order.get_turn_counter();
+        if self.turn.map(|(timed_turn, _)| timed_turn) != Some(turn) {
+            self.turn = Some((turn, after(self.timers.turn_secs)));
+            opened.turn = true;
+        }
+        let window = priority_window(state);
+        if window.as_ref() != self.priority.as_ref().map(|(window, _)| window) {
+            opened.priority = window.is_some();
+            self.priority = window.map(|window| (window, after(self.timers.priority_secs)));
+        }
+        let choice_id = state.pending_choice().map(|choice| &choice.choice_id);
+        if choice_id != self.choice.as_ref().map(|(choice_id, _)| choice_id) {
+            opened.choice = choice_id.is_some();
+            self.choice = choice_id
+                .map(|choice_id| (choice_id.clone(), now + self.timers.choice_secs * 1000));
+        }
+        opened
+    }
+
+    /// Push every open window's deadline back by `millis`, the time the game spent paused
+    pub fn shift(&mut self, millis: u64) {
+        let deadlines = [
+            self.turn
+                .as_mut()
+                .and_then(|(_, deadline)| deadline.as_mut()),
+            self.priority
+                .as_mut()
+                .and_then(|(_, deadline)| deadline.as_mut()),
+            self.choice.as_mut().map(|(_, deadline)| deadline),
+        ];
+        for deadline in deadlines.into_iter().flatten() {
+            *deadline += millis;
+        }
+    }
+
+    pub fn turn_deadline(&self) -> Option<u64> {
+        self.turn.and_then(|(_, deadline)| deadline)
+    }
+
+    pub fn priority_deadline(&self) -> Option<u64> {
+        self.priority.as_ref().and_then(|(_, deadline)| *deadline)
+    }
+
+    pub fn choice_deadline(&self) -> Option<u64> {
+        self.choice.as_ref().map(|(_, deadline)| *deadline)
+    }
+}
@@ -0,0 +1,123 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::ops::Deref;
+
+use crate::game::cards_types::Card;
+use crate::{AppError, AppResult};
+
+/// A face-down pile in a fixed order, shuffled when it is built and again only when a discard
+/// pile is shuffled back in. The top card is the last one, so drawing never moves the rest.
+/// Saved the same way as the plain list it replaces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Deck<T> {
+    cards: Vec<T>,
+}
+
+impl<T> Default for Deck<T> {
+    fn default() -> Self {
+        Self { cards: Vec::new() }
+    }
+}
+
+impl<T> Deck<T> {
+    /// The cards in the order given, the last one on top
+    pub fn new(cards: Vec<T>) -> Self {
+        Self { cards }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// Bottom card first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.cards.iter()
+    }
+
+    /// Bottom card first
+    pub fn as_slice(&self) -> &[T] {
+        &self.cards
+    }
+
+    pub fn draw(&mut self) -> Option<T> {
+        self.cards.pop()
+    }
+
+    /// Up to `count` cards off the top, the first one drawn first
+    pub fn draw_up_to(&mut self, count: usize) -> Vec<T> {
+        let split_at = self.cards.len().saturating_sub(count);
+        self.cards.split_off(split_at).into_iter().rev().collect()
+    }
+
+    /// The top `count` cards, top card first
+    pub fn peek(&self, count: usize) -> impl Iterator<Item = &T> {
+        self.cards.iter().rev().take(count)
+    }
+
+    pub fn put_on_top(&mut self, card: T) {
+        self.cards.push(card);
+    }
+
+    pub fn put_on_bottom(&mut self, card: T) {
+        self.cards.insert(0, card);
+    }
+
+    /// Move the first card from the top that matches to the top, leaving the rest in order.
+    /// Returns whether one was found.
+    pub fn bring_to_top(&mut self, matches: impl Fn(&T) -> bool) -> bool {
+        match self.cards.iter().rposition(matches) {
+            Some(index) => {
+                let card = self.cards.remove(index);
+                self.cards.push(card);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
+    }
+
+    /// Add `cards` (e.g. a discard pile, left empty) and shuffle the whole deck
+    pub fn shuffle_in<R: Rng>(&mut self, cards: &mut Vec<T>, rng: &mut R) {
+        self.cards.append(cards);
+        self.cards.shuffle(rng);
+    }
+}
+
+impl<T: Deref<Target = Card>> Deck<T> {
+    /// Put the top cards back in the given order, top card first. `order` must name exactly
+    /// the top `order.len()` cards.
+    pub fn reorder_top(&mut self, order: &[String]) -> AppResult<()> {
+        let mut expected: Vec<&str> = self
+            .peek(order.len())
+            .map(|card| card.entity_id.as_str())
+            .collect();
+        let mut given: Vec<&str> = order.iter().map(String::as_str).collect();
+        expected.sort_unstable();
+        given.sort_unstable();
+        if expected != given {
+            return Err(AppError::InvalidDeckOrder {
+                reason: format!("must list the top {} cards of the deck", order.len()),
+            });
+        }
+
+        let mut top = self.cards.split_off(self.cards.len() - order.len());
+        // The last card pushed back is the new top
+        for entity_id in order.iter().rev() {
+            let index = top
+                .iter()
+                .position(|card| card.entity_id == *entity_id)
+                .expect("order was checked against the top of the deck");
+            self.cards.push(top.remove(index));
+        }
+        Ok(())
+    }
+}
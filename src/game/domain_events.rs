@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::game_state::{GameState, TurnPhases};
+use crate::game::trades::TradeOffer;
+use crate::game::triggers::GameMoment;
+use crate::AppResult;
+
+/// Something that happened to a game. A `GameEvent` is what a player or the rules ask for;
+/// once it's accepted it becomes one of these, and applying these in order is the only way a
+/// `GameState` changes. The board's rng is part of the state, so the same events applied to
+/// the same starting game always give the same game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DomainEvent {
+    MulliganDecided {
+        player_id: String,
+        keep: bool,
+    },
+    ItemActivated {
+        player_id: String,
+        item_id: String,
+    },
+    ItemBought {
+        player_id: String,
+        shop_index: usize,
+    },
+    LootPlayed {
+        player_id: String,
+        card_id: String,
+        tap_character: bool,
+    },
+    ChoiceAnswered {
+        player_id: String,
+        choice_id: String,
+        option_id: String,
+    },
+    TradeProposed {
+        player_id: String,
+        to_player: String,
+        offer: TradeOffer,
+        request: TradeOffer,
+    },
+    TradeAnswered {
+        player_id: String,
+        trade_id: String,
+        accept: bool,
+    },
+    CurseAttached {
+        player_id: String,
+    },
+    EventRevealed,
+    PlayerDamaged {
+        target_id: String,
+        amount: u32,
+    },
+    DiceRolled {
+        player_id: String,
+        value: u8,
+    },
+    TurnPassed {
+        player_id: String,
+    },
+    PriorityPassed {
+        player_id: String,
+    },
+    PlayerConceded {
+        player_id: String,
+    },
+    // The rest come from the coordinator itself, e.g. while it opens the first turn
+    TriggersFired {
+        moment: GameMoment,
+    },
+    LootDrawn {
+        player_id: String,
+    },
+    PhaseEntered {
+        phase: TurnPhases,
+    },
+    GameEnded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub seq: u64, // starts at 1, like the event log
+    pub event: DomainEvent,
+}
+
+/// Every event applied to a game since it started, oldest first. Folding it over the game's
+/// starting state rebuilds the game, and `since` gives what changed after a known point.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventStream {
+    events: Vec<RecordedEvent>,
+}
+
+impl EventStream {
+    pub fn push(&mut self, event: DomainEvent) -> u64 {
+        let seq = self.last_seq() + 1;
+        self.events.push(RecordedEvent { seq, event });
+        seq
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Seq of the newest event, 0 before anything happened
+    pub fn last_seq(&self) -> u64 {
+        self.events.last().map_or(0, |recorded| recorded.seq)
+    }
+
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Events after `seq`, oldest first
+    pub fn since(&self, seq: u64) -> &[RecordedEvent] {
+        let start = self.events.partition_point(|recorded| recorded.seq <= seq);
+        &self.events[start..]
+    }
+
+    /// Add events recorded by another copy of this stream, skipping the ones it already has
+    pub fn extend(&mut self, events: Vec<RecordedEvent>) {
+        let last_seq = self.last_seq();
+        self.events.extend(
+            events
+                .into_iter()
+                .filter(|recorded| recorded.seq > last_seq),
+        );
+    }
+
+    /// Drop the events from `len` on, e.g. when an action is taken back
+    pub fn truncate(&mut self, len: usize) {
+        self.events.truncate(len);
+    }
+
+    /// Apply every event, in order, to the game as it started
+    pub fn rebuild(&self, mut state: GameState) -> AppResult<GameState> {
+        for recorded in &self.events {
+            state.apply(&recorded.event)?;
+        }
+        Ok(state)
+    }
+}
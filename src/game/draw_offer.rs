@@ -0,0 +1,8 @@
+use std::collections::HashSet;
+
+/// A draw offered by one player, waiting on everyone else still in the game to agree
+#[derive(Debug, Clone)]
+pub struct DrawOffer {
+    pub player_id: String,
+    pub waiting_on: HashSet<String>,
+}
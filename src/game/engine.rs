@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use crate::game::card_loader::BASE_SET;
+use crate::game::choices::PendingChoice;
+use crate::game::domain_events::EventStream;
+use crate::game::game_coordinator::{GameCoordinator, GameEvent};
+use crate::game::game_state::GameState;
+use crate::game::outbox::OutboxQueue;
+use crate::game::replay::Replay;
+use crate::game::rules::RuleSet;
+use crate::{AppResult, TurnOrder};
+
+pub use crate::game::outbox::OutboundEvent;
+
+/// The rules engine on its own, for embedding it in other binaries and for fuzzing: events
+/// go in, the messages each player would have received come out. No runtime, sockets or
+/// actors are involved and the same seed and events always give the same game.
+pub struct GameEngine {
+    coordinator: GameCoordinator,
+    outbox: OutboxQueue,
+}
+
+impl GameEngine {
+    /// A base set game with `players` seated in that order. The opening messages (hands,
+    /// boards, the first turn) are waiting in `take_outbound`.
+    pub fn new_game(players: Vec<String>, rules: RuleSet, seed: u64) -> Self {
+        let outbox = OutboxQueue::new();
+        // Player ids double as connection ids, so recipients come out as player ids
+        let connections: HashMap<String, String> = players
+            .iter()
+            .map(|player_id| (player_id.clone(), player_id.clone()))
+            .collect();
+        let mut coordinator = GameCoordinator::with_seed(
+            connections,
+            TurnOrder::from_order(players),
+            outbox.clone(),
+            seed,
+            &[BASE_SET.to_string()],
+            &rules,
+        );
+        run_now(coordinator.initialize_game());
+
+        Self {
+            coordinator,
+            outbox,
+        }
+    }
+
+    /// Play one event. On success returns everything sent because of it; a rejected event
+    /// changes nothing and returns its error.
+    pub fn apply(&mut self, event: GameEvent) -> AppResult<Vec<OutboundEvent>> {
+        run_now(self.coordinator.handle_event(event))?;
+        Ok(self.take_outbound())
+    }
+
+    /// Messages sent since the last `apply` or `take_outbound`, oldest first
+    pub fn take_outbound(&mut self) -> Vec<OutboundEvent> {
+        self.outbox.take()
+    }
+
+    pub fn state(&self) -> &GameState {
+        self.coordinator.game_state()
+    }
+
+    pub fn pending_choice(&self) -> Option<&PendingChoice> {
+        self.coordinator.pending_choice()
+    }
+
+    pub fn winner(&self) -> Option<&str> {
+        self.coordinator.winner()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.coordinator.is_running()
+    }
+
+    /// The seed and every accepted event, enough to play the game again with `ReplayPlayer`
+    pub fn replay(&self) -> &Replay {
+        self.coordinator.replay()
+    }
+
+    /// Everything that happened to the state so far, see `DomainEvent`
+    pub fn events(&self) -> &EventStream {
+        self.coordinator.events()
+    }
+}
+
+// The coordinator is async only because the server drives it from its actors; nothing in it
+// waits, so each call completes on its first poll
+fn run_now<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("The game coordinator never waits"),
+    }
+}
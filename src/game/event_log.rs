@@ -0,0 +1,171 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+// Oldest entries are dropped first
+const MAX_LOG_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LogEntry {
+    pub seq: u64,       // starts at 1, clients pass the last one they saw as `since`
+    pub timestamp: u64, // unix millis
+    pub text: String,
+}
+
+/// How far a log had got: its newest entry and the newest of each private log
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogMark {
+    public: u64,
+    private: HashMap<String, u64>, // player_id -> seq
+}
+
+/// What was logged after a `LogMark`, for adding back to a copy of the log taken there
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogProgress {
+    pub entries: Vec<LogEntry>,
+    #[serde(default)]
+    pub private: HashMap<String, Vec<LogEntry>>, // player_id -> entries
+}
+
+impl LogProgress {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.private.is_empty()
+    }
+}
+
+/// Human-readable history of a game, for clients that join or reconnect mid-game
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventLog {
+    entries: VecDeque<LogEntry>,
+    next_seq: u64,
+    player_names: HashMap<String, String>, // player_id -> name shown in entries
+    #[serde(default)]
+    private: HashMap<String, EventLog>, // player_id -> what only they saw, numbered on its own
+}
+
+impl EventLog {
+    pub fn set_player_names(&mut self, player_names: HashMap<String, String>) {
+        self.player_names = player_names;
+    }
+
+    /// Falls back to the player id when the name isn't known (replays)
+    pub fn name<'a>(&'a self, player_id: &'a str) -> &'a str {
+        self.player_names
+            .get(player_id)
+            .map_or(player_id, String::as_str)
+    }
+
+    pub fn push(&mut self, text: String) {
+        self.next_seq += 1;
+        if self.entries.len() == MAX_LOG_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            seq: self.next_seq,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64),
+            text,
+        });
+    }
+
+    /// An entry only `player_id` gets to see, e.g. the card they drew. Returns it so it can
+    /// be sent right away.
+    pub fn push_private(&mut self, player_id: &str, text: String) -> LogEntry {
+        let log = self.private.entry(player_id.to_string()).or_default();
+        log.push(text);
+        log.entries
+            .back()
+            .cloned()
+            .expect("An entry was just pushed")
+    }
+
+    /// `player_id`'s private entries after `since`, or all of them still kept
+    pub fn private_since(&self, player_id: &str, since: Option<u64>) -> Vec<LogEntry> {
+        self.private
+            .get(player_id)
+            .map(|log| log.since(since))
+            .unwrap_or_default()
+    }
+
+    /// Seq of the newest entry, 0 before anything is logged
+    pub fn last_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Entries after `since`, or everything still kept
+    pub fn since(&self, since: Option<u64>) -> Vec<LogEntry> {
+        let since = since.unwrap_or(0);
+        self.entries
+            .iter()
+            .filter(|entry| entry.seq > since)
+            .cloned()
+            .collect()
+    }
+
+    pub fn mark(&self) -> LogMark {
+        LogMark {
+            public: self.next_seq,
+            private: self
+                .private
+                .iter()
+                .map(|(player_id, log)| (player_id.clone(), log.next_seq))
+                .collect(),
+        }
+    }
+
+    /// What was logged after `mark`, newest entries only, however long the game has been
+    pub fn progress_since(&self, mark: &LogMark) -> LogProgress {
+        let private = self
+            .private
+            .iter()
+            .filter_map(|(player_id, log)| {
+                let seen = mark.private.get(player_id).copied().unwrap_or(0);
+                let entries = log.newer_than(seen);
+                (!entries.is_empty()).then(|| (player_id.clone(), entries))
+            })
+            .collect();
+        LogProgress {
+            entries: self.newer_than(mark.public),
+            private,
+        }
+    }
+
+    /// Add back what `progress_since` returned; entries the log already has are skipped
+    pub fn extend(&mut self, progress: LogProgress) {
+        for entry in progress.entries {
+            self.append(entry);
+        }
+        for (player_id, entries) in progress.private {
+            let log = self.private.entry(player_id).or_default();
+            for entry in entries {
+                log.append(entry);
+            }
+        }
+    }
+
+    fn newer_than(&self, seq: u64) -> Vec<LogEntry> {
+        let newer = self
+            .entries
+            .iter()
+            .rev()
+            .take_while(|entry| entry.seq > seq)
+            .count();
+        self.entries
+            .iter()
+            .skip(self.entries.len() - newer)
+            .cloned()
+            .collect()
+    }
+
+    fn append(&mut self, entry: LogEntry) {
+        if entry.seq <= self.next_seq {
+            return;
+        }
+        if self.entries.len() == MAX_LOG_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.next_seq = entry.seq;
+        self.entries.push_back(entry);
+    }
+}
@@ -1,45 +1,403 @@
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
-use crate::game::game_state::{GameState, TurnPhases};
+use crate::game::board::{DeckKind, Player, RevealMark};
+use crate::game::card_loader::BASE_SET;
+use crate::game::cards_types::{Item, LootCard};
+use crate::game::choices::PendingChoice;
+use crate::game::deadlines::{unix_millis, OpenedWindows, WindowClock};
+use crate::game::domain_events::{DomainEvent, EventStream};
+use crate::game::draw_offer::DrawOffer;
+use crate::game::event_log::{EventLog, LogEntry};
+use crate::game::game_state::{GameState, StateSnapshot, TurnPhases};
+use crate::game::outbox::Outbox;
+use crate::game::replay::{Replay, ReplayKey};
+use crate::game::rules::{RuleSet, SpeedTimers};
 use crate::game::state_broadcaster::StateBroadcaster;
-use crate::{AppError, ConnectionCommand};
+use crate::game::stats::GameStats;
+use crate::game::trades::{Trade, TradeOffer};
+use crate::game::triggers::GameMoment;
+use crate::game::undo::{UndoPoint, UndoRequest};
+use crate::network::messages::ServerResponse;
+use crate::AppError;
 use crate::{AppResult, TurnOrder};
-use tokio::sync::mpsc;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GameEvent {
-    TurnPass { player_id: String },
-    // PriorityPass { player_id: String },
+    TurnPass {
+        player_id: String,
+    },
+    MulliganDecision {
+        player_id: String,
+        keep: bool,
+    },
+    ActivateItem {
+        player_id: String,
+        item_id: String,
+    },
+    BuyItem {
+        player_id: String,
+        shop_index: usize,
+    },
+    PlayLootCard {
+        player_id: String,
+        card_id: String,
+        #[serde(default)]
+        tap_character: bool,
+    },
+    DealDamage {
+        source_id: Option<String>, // None for damage that doesn't come from a player
+        target_id: String,
+        amount: u32,
+    },
+    ChoiceResponse {
+        player_id: String,
+        choice_id: String,
+        option_id: String,
+    },
+    ProposeTrade {
+        player_id: String,
+        to_player: String,
+        offer: TradeOffer,
+        request: TradeOffer,
+    },
+    RespondToTrade {
+        player_id: String,
+        trade_id: String,
+        accept: bool,
+    },
+    // Like DealDamage, these come from the rules rather than from a player
+    AttachCurse {
+        player_id: String,
+    },
+    RevealEvent,
+    PriorityPass {
+        player_id: String,
+    },
+    Concede {
+        player_id: String,
+    },
+}
+
+impl GameEvent {
+    /// The player who took the action, None for events that come from the rules
+    pub fn player_id(&self) -> Option<&str> {
+        match self {
+            GameEvent::TurnPass { player_id }
+            | GameEvent::PriorityPass { player_id }
+            | GameEvent::MulliganDecision { player_id, .. }
+            | GameEvent::ActivateItem { player_id, .. }
+            | GameEvent::BuyItem { player_id, .. }
+            | GameEvent::PlayLootCard { player_id, .. }
+            | GameEvent::ChoiceResponse { player_id, .. }
+            | GameEvent::ProposeTrade { player_id, .. }
+            | GameEvent::RespondToTrade { player_id, .. }
+            | GameEvent::Concede { player_id } => Some(player_id),
+            GameEvent::DealDamage { .. }
+            | GameEvent::AttachCurse { .. }
+            | GameEvent::RevealEvent => None,
+        }
+    }
+}
+
+/// Everything needed to diagnose a live game: the whole state (pending choices and trades
+/// included), the rng seed and the most recent accepted events
+#[derive(Debug, Serialize)]
+pub struct GameDump<'a> {
+    pub seed: u64,
+    pub state: &'a GameState,
+    pub stats: &'a GameStats,
+    pub winner: Option<&'a str>,
+    pub total_events: usize,
+    pub recent_events: &'a [GameEvent], // oldest first
+    pub log: Vec<LogEntry>,
+}
+
+/// The parts of the game an accepted action is compared against afterwards, copied instead
+/// of the whole state: the turn, everyone's stats and hand sizes, open choices and trades
+struct Before {
+    turn_order: TurnOrder,
+    players: HashMap<String, Player>,
+    hand_sizes: HashMap<String, usize>,
+    pending_choice: Option<PendingChoice>,
+    pending_trades: Vec<Trade>,
+    mulligan_pending: bool,
+    reveal_mark: RevealMark,
+    // What a player about to take damage holds, to tell what dying cost them
+    target_hand: Vec<LootCard>,
+    target_items: Vec<Item>,
+    // The whole game, only when the action could be taken back
+    undo_snapshot: Option<StateSnapshot>,
+}
+
+impl Before {
+    fn take(state: &GameState, event: &GameEvent, undo: bool) -> Self {
+        let board = &state.board;
+        let (target_hand, target_items) = match event {
+            GameEvent::DealDamage { target_id, .. } => (
+                board
+                    .players_hands
+                    .get(target_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                board
+                    .players_items
+                    .get(target_id)
+                    .cloned()
+                    .unwrap_or_default(),
+            ),
+            _ => Default::default(),
+        };
+        let undoable = undo
+            && !state.is_mulligan_pending()
+            && event.player_id() == Some(state.turn_order.active_player_id.as_str());
+        Self {
+            turn_order: state.turn_order.clone(),
+            players: board.players.clone(),
+            hand_sizes: board
+                .players_hands
+                .iter()
+                .map(|(player_id, hand)| (player_id.clone(), hand.len()))
+                .collect(),
+            pending_choice: state.pending_choice().cloned(),
+            pending_trades: state.pending_trades.clone(),
+            mulligan_pending: state.is_mulligan_pending(),
+            reveal_mark: board.reveal_mark(),
+            target_hand,
+            target_items,
+            undo_snapshot: undoable.then(|| state.snapshot()),
+        }
+    }
+
+    fn hand_size(&self, player_id: &str) -> usize {
+        self.hand_sizes.get(player_id).copied().unwrap_or(0)
+    }
 }
 
 pub struct GameCoordinator {
     game_state: GameState,
     state_broadcaster: StateBroadcaster,
+    replay: Replay,
+    stats: GameStats,
+    winner: Option<String>,
+    mulligan_timeout_secs: u64, // only reported to clients, the game actor enforces it
+    clock: WindowClock,         // deadlines of the open windows, the game actor enforces them too
+    prompted_choice: Option<String>, // last choice sent out, so each is prompted once
+    event_log: EventLog,
+    events: EventStream, // everything that happened to game_state, see `rebuild_state`
+    undo_point: Option<UndoPoint>, // only kept in rooms that allow undo
+    undo_request: Option<UndoRequest>,
+    draw_offer: Option<DrawOffer>,
+    seen_hands: HashMap<String, HashSet<String>>, // player_id -> card entity ids already logged
 }
 
 impl GameCoordinator {
     pub fn new(
         players_id_to_connection_id: HashMap<String, String>,
         turn_order: TurnOrder,
-        cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+        outbox: impl Outbox + 'static,
+    ) -> Self {
+        Self::with_seed(
+            players_id_to_connection_id,
+            turn_order,
+            outbox,
+            rand::random(),
+            &[BASE_SET.to_string()],
+            &RuleSet::default(),
+        )
+    }
+
+    /// Decks are built from the cards in `card_sets` and the game follows `rules`, see
+    /// `RoomOptions`
+    pub fn with_seed(
+        players_id_to_connection_id: HashMap<String, String>,
+        turn_order: TurnOrder,
+        outbox: impl Outbox + 'static,
+        seed: u64,
+        card_sets: &[String],
+        rules: &RuleSet,
     ) -> Self {
+        let mut replay = Replay::new(seed, turn_order.order.clone(), card_sets.to_vec());
+        replay.rules = rules.clone();
+        let stats = GameStats::new(&turn_order.order);
+
         let player_ids = players_id_to_connection_id.keys().cloned().collect();
-        let game_state = GameState::new(player_ids, turn_order);
+        let game_state = GameState::new(player_ids, turn_order, seed, card_sets, rules);
 
-        let state_broadcaster = StateBroadcaster::new(players_id_to_connection_id, cmd_sender);
+        let state_broadcaster = StateBroadcaster::new(players_id_to_connection_id, outbox);
 
         Self {
             game_state,
             state_broadcaster,
+            replay,
+            stats,
+            winner: None,
+            mulligan_timeout_secs: 0,
+            clock: WindowClock::default(),
+            prompted_choice: None,
+            event_log: EventLog::default(),
+            events: EventStream::default(),
+            undo_point: None,
+            undo_request: None,
+            draw_offer: None,
+            seen_hands: HashMap::new(),
+        }
+    }
+
+    /// Pick up a saved game where it left off. Nobody is connected until players resume.
+    /// Refused when the replay's signatures don't hold up against the server's key.
+    pub fn restore(
+        game_state: GameState,
+        mut replay: Replay,
+        stats: GameStats,
+        event_log: EventLog,
+        events: EventStream,
+        outbox: impl Outbox + 'static,
+        replay_key: Option<ReplayKey>,
+    ) -> AppResult<Self> {
+        // The key isn't saved with the game
+        if let Some(key) = replay_key {
+            replay.resume_signing(key)?;
+            if !replay.is_signing() {
+                eprintln!("⚠️ Restored game was saved unsigned, its replay stays unsigned");
+            }
         }
+        // A checkpoint's state is older than the events appended after it
+        let game_state = if events.is_empty() {
+            game_state
+        } else {
+            events.rebuild(replay.initial_state()).unwrap_or_else(|e| {
+                eprintln!(
+                    "Failed to rebuild saved game, using its checkpoint: {:?}",
+                    e
+                );
+                game_state
+            })
+        };
+        Ok(Self {
+            game_state,
+            state_broadcaster: StateBroadcaster::new(HashMap::new(), outbox),
+            replay,
+            stats,
+            winner: None,
+            mulligan_timeout_secs: 0,
+            clock: WindowClock::default(),
+            prompted_choice: None,
+            event_log,
+            events,
+            undo_point: None,
+            undo_request: None,
+            draw_offer: None,
+            seen_hands: HashMap::new(),
+        }
+        .with_hands_seen())
+    }
+
+    // Cards already in hand were logged before the game was saved
+    fn with_hands_seen(mut self) -> Self {
+        self.seen_hands = self.current_hands();
+        self
+    }
+
+    /// Open with a mulligan phase; call before `initialize_game`
+    pub fn enable_mulligan(&mut self, timeout_secs: u64) {
+        self.game_state.pending_mulligans =
+            self.game_state.turn_order.order.iter().cloned().collect();
+        self.mulligan_timeout_secs = timeout_secs;
+        self.replay.mulligan = true;
+    }
+
+    /// How long turns, priority and choices last; call before `initialize_game`
+    pub fn set_timers(&mut self, timers: SpeedTimers) {
+        self.clock = WindowClock::new(timers);
+    }
+
+    /// Give the windows the game moved into their deadlines. Run before anything announcing a
+    /// window goes out, and by the game actor before it times one.
+    pub fn refresh_windows(&mut self) -> OpenedWindows {
+        self.clock.refresh(&self.game_state, unix_millis())
+    }
+
+    /// The game was paused for `millis`: the open windows run out that much later
+    pub fn shift_windows(&mut self, millis: u64) {
+        self.clock.shift(millis);
+    }
+
+    pub fn windows(&self) -> &WindowClock {
+        &self.clock
+    }
+
+    /// Room option: nobody holds more than `coin_cap` cents
+    pub fn enable_coin_cap(&mut self, coin_cap: u32) {
+        self.game_state.board.coin_cap = Some(coin_cap);
+        self.replay.coin_cap = Some(coin_cap);
+    }
+
+    /// Sign the replay with `key` as events are recorded
+    pub fn enable_replay_signing(&mut self, key: ReplayKey) {
+        self.replay.sign_with(key);
+    }
+
+    /// Room option: the active player can take back their last action if everyone agrees
+    pub fn enable_undo(&mut self) {
+        self.replay.undo = true;
+    }
+
+    pub fn set_spectator_delay(&mut self, delay: Duration) {
+        self.state_broadcaster.set_spectator_delay(delay);
+    }
+
+    pub fn spectator_delay(&self) -> Duration {
+        self.state_broadcaster.spectator_delay()
+    }
+
+    pub fn add_spectator(&mut self, connection_id: String) {
+        self.state_broadcaster
+            .add_spectator(connection_id, &self.game_state, &self.clock);
+    }
+
+    pub fn remove_spectator(&mut self, connection_id: &str) -> bool {
+        self.state_broadcaster.remove_spectator(connection_id)
     }
 
     pub async fn initialize_game(&mut self) {
+        self.log_private_draws().await;
+        if self.game_state.is_mulligan_pending() {
+            // Players see their opening hand, the first turn starts once everyone decided
+            self.state_broadcaster
+                .broadcast_full_state(&self.game_state)
+                .await;
+            self.state_broadcaster
+                .broadcast(ServerResponse::MulliganStart {
+                    timeout_secs: self.mulligan_timeout_secs,
+                })
+                .await;
+            return;
+        }
+        self.start_first_turn().await;
+    }
+
+    async fn start_first_turn(&mut self) {
         // Temporary for shortcircuiting priority
-        let _ = self
-            .game_state
-            .board
-            .draw_loot_for_player(&self.game_state.turn_order.active_player_id);
+        let active_player_id = self.game_state.turn_order.active_player_id.clone();
+        let name = self.event_log.name(&active_player_id).to_string();
+        self.event_log.push(format!("Turn 1: {}'s turn", name));
+        self.apply_rules(DomainEvent::TriggersFired {
+            moment: GameMoment::TurnStart {
+                player_id: active_player_id.clone(),
+            },
+        });
+        self.log_resolved_triggers();
+        self.draw_loot(&active_player_id);
+        self.event_log.push(format!("{} drew 1 loot", name));
+        self.log_private_draws().await;
+        self.apply_rules(DomainEvent::TriggersFired {
+            moment: GameMoment::LootStep {
+                player_id: active_player_id.clone(),
+            },
+        });
+        self.log_resolved_triggers();
 
         // Send initial state to all players
         self.state_broadcaster
@@ -52,72 +410,953 @@ impl GameCoordinator {
     }
 
     pub async fn handle_event(&mut self, event: GameEvent) -> Result<(), AppError> {
-        match self.handle_game_event(event, &self.game_state).await {
-            Ok(new_state) => {
-                self.game_state = new_state;
+        let domain_event = self.decide(&event)?;
+        let mut before = Before::take(&self.game_state, &event, self.replay.undo);
+        // A refused event leaves the game as it was: the rules check before they change
+        // anything, and a death puts back the damage if its penalty is refused
+        self.apply(domain_event)?;
+
+        // Passing always moves the game on; an answer or a death may have ended the turn
+        let turn_ended =
+            self.game_state.turn_order.get_turn_counter() != before.turn_order.get_turn_counter();
+        let phase_started = match &event {
+            GameEvent::TurnPass { .. } | GameEvent::PriorityPass { .. } => true,
+            GameEvent::ChoiceResponse { .. } | GameEvent::DealDamage { .. } => turn_ended,
+            _ => false,
+        };
+        // Priority also reopens once a choice or the mulligan is out of the way, or when an
+        // action resets the passes, and the new deadline has to go out with it
+        let opened = self.refresh_windows();
+        if phase_started || opened.turn || opened.priority {
+            self.state_broadcaster
+                .broadcast_phase_start(&self.game_state, &self.clock)
+                .await;
+        }
+
+        // Taken before anything else changes, in case this turns out to be an action that
+        // can be undone
+        let undo_base = self
+            .replay
+            .undo
+            .then(|| (self.stats.clone(), self.event_log.last_seq()));
+        self.record_stats(&before, &event);
+        self.replay.record(event.clone());
+        self.log_event(&before, &event);
+        self.log_resolved_triggers();
+        self.log_private_draws().await;
+
+        self.state_broadcaster
+            .broadcast_full_state(&self.game_state)
+            .await;
+        self.broadcast_life_changes(&before, &event).await;
+        self.broadcast_turn_order_changes(&before).await;
+
+        if let GameEvent::ChoiceResponse {
+            player_id,
+            choice_id,
+            option_id,
+        } = &event
+        {
+            let option = before
+                .pending_choice
+                .as_ref()
+                .and_then(|choice| {
+                    choice
+                        .options
+                        .iter()
+                        .find(|option| option.option_id == *option_id)
+                })
+                .cloned();
+            if let Some(option) = option {
+                let text = format!("{} chose {}", self.event_log.name(player_id), option.label);
+                self.event_log.push(text);
+                self.state_broadcaster
+                    .broadcast(ServerResponse::ChoiceMade {
+                        choice_id: choice_id.clone(),
+                        player_id: player_id.clone(),
+                        option,
+                    })
+                    .await;
+            }
+        }
+        self.prompt_pending_choice().await;
+        self.broadcast_trade_changes(&before, &event).await;
 
+        if let GameEvent::MulliganDecision { player_id, keep } = &event {
+            self.state_broadcaster
+                .broadcast(ServerResponse::MulliganDecided {
+                    player_id: player_id.clone(),
+                    keep: *keep,
+                })
+                .await;
+            if !self.game_state.is_mulligan_pending() {
+                self.start_first_turn().await;
+            }
+        }
+
+        // Item effects aren't resolved yet, activating only pays the tap or charge cost
+        if let GameEvent::ActivateItem { player_id, item_id } = &event {
+            let item = self
+                .game_state
+                .board
+                .players_items
+                .get(player_id)
+                .and_then(|items| items.iter().find(|item| item.entity_id == *item_id));
+            if let Some(item) = item {
+                self.state_broadcaster
+                    .broadcast(ServerResponse::ItemActivated {
+                        player_id: player_id.clone(),
+                        item: item.clone(),
+                    })
+                    .await;
+            }
+        }
+        // A bought item enters play last, behind the player's other items
+        if let GameEvent::BuyItem { player_id, .. } = &event {
+            let item = self
+                .game_state
+                .board
+                .players_items
+                .get(player_id)
+                .and_then(|items| items.last());
+            if let Some(item) = item {
+                self.state_broadcaster
+                    .broadcast(ServerResponse::ItemBought {
+                        player_id: player_id.clone(),
+                        item: item.clone(),
+                    })
+                    .await;
+            }
+        }
+        // Same for loot, the played card is simply discarded
+        if let GameEvent::PlayLootCard { player_id, .. } = &event {
+            if let Some(card) = self.game_state.board.loot_discard.last() {
                 self.state_broadcaster
-                    .broadcast_full_state(&self.game_state)
+                    .broadcast(ServerResponse::LootPlayed {
+                        player_id: player_id.clone(),
+                        card: card.clone(),
+                    })
                     .await;
+            }
+        }
 
-                // Check win condition
-                if self.check_win_condition() {
-                    if let Some(winner) = self.get_winner() {
-                        self.end_game(winner).await;
-                    }
+        let undo_snapshot = before.undo_snapshot.take();
+        self.remember_undo_point(&before, undo_snapshot, &event, undo_base)
+            .await;
+
+        // Check win condition
+        if self.check_win_condition() {
+            if let Some(winner) = self.get_winner() {
+                self.end_game(Some(winner)).await;
+            }
+        }
+
+        if let GameEvent::Concede { player_id } = &event {
+            self.state_broadcaster
+                .broadcast(ServerResponse::PlayerConceded {
+                    player_id: player_id.clone(),
+                })
+                .await;
+            // The last player standing wins; a solo game just ends
+            match self.game_state.turn_order.order.as_slice() {
+                [] => self.end_game(None).await,
+                [winner] if before.turn_order.order.len() > 1 => {
+                    self.end_game(Some(winner.clone())).await
                 }
+                _ => self.withdraw_from_draw_offer(player_id).await?,
+            }
+        }
+
+        // An offer is only good for the turn it was made in
+        if turn_ended && self.is_running() {
+            self.expire_draw_offer().await;
+        }
 
-                Ok(())
+        Ok(())
+    }
+
+    /// Check `event` against what the game as a whole is waiting on and turn it into what
+    /// happened; the rules for the action itself are checked as it's applied
+    fn decide(&self, event: &GameEvent) -> AppResult<DomainEvent> {
+        let current_state = &self.game_state;
+        if current_state.is_mulligan_pending()
+            && !matches!(event, GameEvent::MulliganDecision { .. })
+        {
+            return Err(AppError::MulliganInProgress);
+        }
+        // Conceding drops the player's own choices, whoever's choice is open
+        if current_state.pending_choice().is_some()
+            && !matches!(
+                event,
+                GameEvent::ChoiceResponse { .. } | GameEvent::Concede { .. }
+            )
+        {
+            return Err(AppError::ChoicePending);
+        }
+        let domain_event = match event.clone() {
+            GameEvent::MulliganDecision { player_id, keep } => {
+                DomainEvent::MulliganDecided { player_id, keep }
+            }
+            GameEvent::ActivateItem { player_id, item_id } => {
+                DomainEvent::ItemActivated { player_id, item_id }
             }
-            Err(error) => Err(error),
+            GameEvent::BuyItem {
+                player_id,
+                shop_index,
+            } => DomainEvent::ItemBought {
+                player_id,
+                shop_index,
+            },
+            GameEvent::PlayLootCard {
+                player_id,
+                card_id,
+                tap_character,
+            } => DomainEvent::LootPlayed {
+                player_id,
+                card_id,
+                tap_character,
+            },
+            GameEvent::ChoiceResponse {
+                player_id,
+                choice_id,
+                option_id,
+            } => DomainEvent::ChoiceAnswered {
+                player_id,
+                choice_id,
+                option_id,
+            },
+            GameEvent::ProposeTrade {
+                player_id,
+                to_player,
+                offer,
+                request,
+            } => DomainEvent::TradeProposed {
+                player_id,
+                to_player,
+                offer,
+                request,
+            },
+            GameEvent::RespondToTrade {
+                player_id,
+                trade_id,
+                accept,
+            } => DomainEvent::TradeAnswered {
+                player_id,
+                trade_id,
+                accept,
+            },
+            GameEvent::AttachCurse { player_id } => DomainEvent::CurseAttached { player_id },
+            GameEvent::RevealEvent => DomainEvent::EventRevealed,
+            GameEvent::DealDamage {
+                target_id, amount, ..
+            } => DomainEvent::PlayerDamaged { target_id, amount },
+            GameEvent::TurnPass { player_id } => DomainEvent::TurnPassed { player_id },
+            GameEvent::PriorityPass { player_id } => DomainEvent::PriorityPassed { player_id },
+            GameEvent::Concede { player_id } => DomainEvent::PlayerConceded { player_id },
+        };
+        Ok(domain_event)
+    }
+
+    // The one way the coordinator changes the game: apply `event` and add it to the stream
+    fn apply(&mut self, event: DomainEvent) -> AppResult<()> {
+        self.game_state.apply(&event)?;
+        self.events.push(event);
+        Ok(())
+    }
+
+    // For what the rules do on their own, which is never refused
+    fn apply_rules(&mut self, event: DomainEvent) {
+        if let Err(e) = self.apply(event) {
+            eprintln!("❌ The rules produced an event the game refused: {:?}", e);
         }
     }
 
-    pub async fn handle_game_event(
-        &self,
-        event: GameEvent,
-        current_state: &GameState,
-    ) -> AppResult<GameState> {
+    // The top loot card for `player_id`, counted in their stats if there was one
+    fn draw_loot(&mut self, player_id: &str) {
+        let hand_size =
+            |state: &GameState| state.board.players_hands.get(player_id).map_or(0, Vec::len);
+        let before = hand_size(&self.game_state);
+        self.apply_rules(DomainEvent::LootDrawn {
+            player_id: player_id.to_string(),
+        });
+        if hand_size(&self.game_state) > before {
+            self.stats.record_cards_drawn(player_id, 1);
+        }
+    }
+
+    fn record_stats(&mut self, before: &Before, event: &GameEvent) {
+        let new_state = &self.game_state;
+        match event {
+            GameEvent::MulliganDecision { player_id, keep } => {
+                if !keep {
+                    let hand_size = new_state
+                        .board
+                        .players_hands
+                        .get(player_id)
+                        .map_or(0, Vec::len);
+                    self.stats.record_cards_drawn(player_id, hand_size as u32);
+                }
+            }
+            GameEvent::DealDamage {
+                source_id: Some(source_id),
+                target_id,
+                ..
+            } => {
+                let health = |players: &HashMap<String, Player>| {
+                    players
+                        .get(target_id)
+                        .map_or(0, |player| player.current_health)
+                };
+                let dealt =
+                    health(&before.players).saturating_sub(health(&new_state.board.players));
+                self.stats.record_damage(source_id, dealt);
+            }
+            GameEvent::TurnPass { .. }
+            | GameEvent::PriorityPass { .. }
+            | GameEvent::ActivateItem { .. }
+            | GameEvent::BuyItem { .. }
+            | GameEvent::PlayLootCard { .. }
+            | GameEvent::ChoiceResponse { .. }
+            | GameEvent::ProposeTrade { .. }
+            | GameEvent::RespondToTrade { .. }
+            | GameEvent::AttachCurse { .. }
+            | GameEvent::RevealEvent
+            | GameEvent::DealDamage { .. }
+            | GameEvent::Concede { .. } => {}
+        }
+
+        // Passing or dying hands the turn on, and the next player draws as their turn starts
+        if new_state.turn_order.get_turn_counter() != before.turn_order.get_turn_counter() {
+            self.stats.record_turn(&before.turn_order.active_player_id);
+            let next_player_id = &new_state.turn_order.active_player_id;
+            let hand_size = new_state
+                .board
+                .players_hands
+                .get(next_player_id)
+                .map_or(0, Vec::len);
+            let drawn = hand_size.saturating_sub(before.hand_size(next_player_id));
+            self.stats.record_cards_drawn(next_player_id, drawn as u32);
+        }
+    }
+
+    fn log_resolved_triggers(&mut self) {
+        for entry in self.game_state.take_resolved_triggers() {
+            let text = format!(
+                "{}: {} {}",
+                entry.source_name,
+                self.event_log.name(&entry.owner_id),
+                entry.effect
+            );
+            self.event_log.push(text);
+        }
+    }
+
+    // Entries for events that have no dedicated broadcast helper
+    fn log_event(&mut self, before: &Before, event: &GameEvent) {
+        let log = &self.event_log;
+        let mut lines = Vec::new();
         match event {
             GameEvent::TurnPass { player_id } => {
-                if current_state.can_player_pass_turn(&player_id) {
-                    let new_state = current_state.with_phase_transition(TurnPhases::TurnEnd);
-                    let _ = self.state_broadcaster.broadcast_phase_start(&new_state);
-                    Ok(new_state)
+                lines.push(format!("{} passed the turn", log.name(player_id)));
+            }
+            GameEvent::MulliganDecision { player_id, keep } => {
+                let decision = if *keep {
+                    "kept their opening hand"
                 } else {
-                    Err(AppError::NotPlayerTurn)
+                    "took a mulligan"
+                };
+                lines.push(format!("{} {}", log.name(player_id), decision));
+            }
+            GameEvent::ActivateItem { player_id, item_id } => {
+                let item = self
+                    .game_state
+                    .board
+                    .players_items
+                    .get(player_id)
+                    .and_then(|items| items.iter().find(|item| item.entity_id == *item_id));
+                if let Some(item) = item {
+                    lines.push(format!("{} activated {}", log.name(player_id), item.name));
+                }
+            }
+            GameEvent::BuyItem { player_id, .. } => {
+                let item = self
+                    .game_state
+                    .board
+                    .players_items
+                    .get(player_id)
+                    .and_then(|items| items.last());
+                if let Some(item) = item {
+                    lines.push(format!("{} bought {}", log.name(player_id), item.name));
+                }
+            }
+            GameEvent::PlayLootCard { player_id, .. } => {
+                if let Some(card) = self.game_state.board.loot_discard.last() {
+                    lines.push(format!("{} played {}", log.name(player_id), card.name));
+                }
+            }
+            GameEvent::AttachCurse { player_id } => {
+                let curse = self
+                    .game_state
+                    .board
+                    .players_curses
+                    .get(player_id)
+                    .and_then(|curses| curses.last());
+                if let Some(curse) = curse {
+                    lines.push(format!(
+                        "{} was cursed: {}",
+                        log.name(player_id),
+                        curse.name
+                    ));
+                }
+            }
+            GameEvent::RevealEvent => {
+                if let Some(event) = self.game_state.board.active_events.last() {
+                    lines.push(format!("Event: {}", event.name));
+                }
+            }
+            GameEvent::Concede { player_id } => {
+                lines.push(format!("{} conceded", log.name(player_id)));
+            }
+            _ => {}
+        }
+
+        let turn_order = &self.game_state.turn_order;
+        if turn_order.get_turn_counter() != before.turn_order.get_turn_counter() {
+            let active_player_id = &turn_order.active_player_id;
+            lines.push(format!(
+                "Turn {}: {}'s turn",
+                turn_order.get_turn_counter() + 1,
+                log.name(active_player_id)
+            ));
+            let hand_size = self
+                .game_state
+                .board
+                .players_hands
+                .get(active_player_id)
+                .map_or(0, Vec::len);
+            let drawn = hand_size.saturating_sub(before.hand_size(active_player_id));
+            if drawn > 0 {
+                lines.push(format!(
+                    "{} drew {} loot",
+                    log.name(active_player_id),
+                    drawn
+                ));
+            }
+        }
+
+        for line in lines {
+            self.event_log.push(line);
+        }
+    }
+
+    fn current_hands(&self) -> HashMap<String, HashSet<String>> {
+        self.game_state
+            .board
+            .players_hands
+            .iter()
+            .map(|(player_id, hand)| {
+                let cards = hand.iter().map(|card| card.entity_id.clone()).collect();
+                (player_id.clone(), cards)
+            })
+            .collect()
+    }
+
+    /// Log and send each player the cards that came into their hand since the last call.
+    /// Only the player who drew them learns which cards they were.
+    async fn log_private_draws(&mut self) {
+        for (player_id, hand) in &self.game_state.board.players_hands {
+            let seen = self.seen_hands.entry(player_id.clone()).or_default();
+            let entries: Vec<LogEntry> = hand
+                .iter()
+                .filter(|card| !seen.contains(&card.entity_id))
+                .map(|card| {
+                    self.event_log
+                        .push_private(player_id, format!("You drew {}", card.name))
+                })
+                .collect();
+            *seen = hand.iter().map(|card| card.entity_id.clone()).collect();
+            if !entries.is_empty() {
+                self.state_broadcaster
+                    .send_to_player(player_id, ServerResponse::PrivateLog { entries })
+                    .await;
+            }
+        }
+    }
+
+    async fn broadcast_life_changes(&mut self, before: &Before, event: &GameEvent) {
+        let board = &self.game_state.board;
+
+        // Worked out from the previous state: in a solo game the player dies and respawns
+        // within the same event
+        let mut died = None;
+        if let GameEvent::DealDamage {
+            source_id,
+            target_id,
+            amount,
+        } = event
+        {
+            if let Some(previous) = before.players.get(target_id) {
+                let current_health = previous.current_health.saturating_sub(*amount);
+                self.state_broadcaster
+                    .broadcast(ServerResponse::PlayerDamaged {
+                        player_id: target_id.clone(),
+                        source_id: source_id.clone(),
+                        amount: *amount,
+                        current_health,
+                    })
+                    .await;
+                let text = match source_id {
+                    Some(source_id) => format!(
+                        "{} dealt {} damage to {}",
+                        self.event_log.name(source_id),
+                        amount,
+                        self.event_log.name(target_id)
+                    ),
+                    None => format!("{} took {} damage", self.event_log.name(target_id), amount),
+                };
+                self.event_log.push(text);
+                if !previous.dead && *amount > 0 && current_health == 0 {
+                    died = Some(target_id);
                 }
-            } // GameEvent::PriorityPass { player_id } => {
-              //     match current_state.with_priority_pass(player_id) {
-              //         Ok(new_state) => {
-              //             let _ = self.state_broadcaster.broadcast_phase_start(&new_state);
-              //             Ok(new_state)
-              //         }
-              //         Err(AppError::InvalidPriorityPass) => Err(AppError::InvalidPriorityPass),
-              //         _ => Err(AppError::Internal {
-              //             message: "Unexpected game state error".to_string(),
-              //         }),
-              //     }
-              // }
+            }
+        }
+
+        if let Some(player_id) = died {
+            // Players never leave the board, both states have them
+            let previous = &before.players[player_id];
+            let player = &board.players[player_id];
+            let hand = board.players_hands.get(player_id);
+            let loot_discarded = before
+                .target_hand
+                .iter()
+                .find(|card| {
+                    !hand.is_some_and(|hand| {
+                        hand.iter().any(|kept| kept.entity_id == card.entity_id)
+                    })
+                })
+                .cloned();
+            let items = board.players_items.get(player_id);
+            let item_destroyed = before
+                .target_items
+                .iter()
+                .find(|item| {
+                    !items.is_some_and(|items| {
+                        items.iter().any(|kept| kept.entity_id == item.entity_id)
+                    })
+                })
+                .cloned();
+            let text = format!("{} died", self.event_log.name(player_id));
+            self.event_log.push(text);
+            self.state_broadcaster
+                .broadcast(ServerResponse::PlayerDied {
+                    player_id: player_id.clone(),
+                    coins_lost: previous.coins.saturating_sub(player.coins),
+                    loot_discarded,
+                    item_destroyed,
+                })
+                .await;
+        }
+
+        for (player_id, player) in &board.players {
+            let was_dead = died == Some(player_id)
+                || before
+                    .players
+                    .get(player_id)
+                    .is_some_and(|previous| previous.dead);
+            if was_dead && !player.dead {
+                let text = format!("{} respawned", self.event_log.name(player_id));
+                self.event_log.push(text);
+                self.state_broadcaster
+                    .broadcast(ServerResponse::PlayerRespawned {
+                        player_id: player_id.clone(),
+                    })
+                    .await;
+            }
+        }
+    }
+
+    /// Modifiers queued, or used up as the turn was handed on
+    async fn broadcast_turn_order_changes(&self, before: &Before) {
+        let turn_order = &self.game_state.turn_order;
+        let previous = &before.turn_order;
+        if turn_order.modifiers() != previous.modifiers()
+            || turn_order.is_reversed() != previous.is_reversed()
+            || turn_order.order != previous.order
+        {
+            self.state_broadcaster
+                .broadcast(ServerResponse::TurnOrderChanged {
+                    play_order: turn_order.play_order(),
+                    reversed: turn_order.is_reversed(),
+                    pending: turn_order.modifiers().to_vec(),
+                })
+                .await;
+        }
+    }
+
+    // Trades are public so everyone at the table sees every deal
+    async fn broadcast_trade_changes(&mut self, before: &Before, event: &GameEvent) {
+        match event {
+            GameEvent::ProposeTrade { .. } => {
+                if let Some(trade) = self.game_state.pending_trades.last() {
+                    let text = format!(
+                        "{} offered {} a trade",
+                        self.event_log.name(&trade.from_player),
+                        self.event_log.name(&trade.to_player)
+                    );
+                    self.event_log.push(text);
+                    self.state_broadcaster
+                        .broadcast(ServerResponse::TradeProposed {
+                            trade: trade.clone(),
+                        })
+                        .await;
+                }
+            }
+            GameEvent::RespondToTrade {
+                player_id,
+                trade_id,
+                accept,
+            } => {
+                let trade = before
+                    .pending_trades
+                    .iter()
+                    .find(|trade| trade.trade_id == *trade_id);
+                let text = match trade {
+                    Some(trade) if *accept => format!(
+                        "{} accepted {}'s trade",
+                        self.event_log.name(player_id),
+                        self.event_log.name(&trade.from_player)
+                    ),
+                    _ => format!("{} declined a trade", self.event_log.name(player_id)),
+                };
+                self.event_log.push(text);
+                let response = match trade {
+                    Some(trade) if *accept => ServerResponse::TradeCompleted {
+                        trade: trade.clone(),
+                    },
+                    _ => ServerResponse::TradeDeclined {
+                        trade_id: trade_id.clone(),
+                        player_id: player_id.clone(),
+                    },
+                };
+                self.state_broadcaster.broadcast(response).await;
+            }
+            _ => {}
+        }
+    }
+
+    async fn prompt_pending_choice(&mut self) {
+        let Some(choice) = self.game_state.pending_choice() else {
+            return;
+        };
+        if self.prompted_choice.as_deref() == Some(choice.choice_id.as_str()) {
+            return;
+        }
+        self.prompted_choice = Some(choice.choice_id.clone());
+        self.clock.refresh(&self.game_state, unix_millis());
+        self.send_choice(choice).await;
+    }
+
+    // Sent again on reconnecting, with the time left rather than a fresh timeout
+    async fn send_choice(&self, choice: &PendingChoice) {
+        let deadline = self.clock.choice_deadline().unwrap_or_else(unix_millis);
+        self.state_broadcaster
+            .send_to_player(
+                &choice.player_id,
+                ServerResponse::ChoiceRequired {
+                    choice_id: choice.choice_id.clone(),
+                    prompt: choice.prompt.clone(),
+                    options: choice.options.clone(),
+                    timeout_secs: deadline.saturating_sub(unix_millis()).div_ceil(1000),
+                    deadline,
+                },
+            )
+            .await;
+    }
+
+    /// Keep the game as it was before `event` if its player may take it back. Any undo still
+    /// being voted on is dropped, the game has moved past it.
+    async fn remember_undo_point(
+        &mut self,
+        before: &Before,
+        undo_snapshot: Option<StateSnapshot>,
+        event: &GameEvent,
+        undo_base: Option<(GameStats, u64)>,
+    ) {
+        if let Some(request) = self.undo_request.take() {
+            self.state_broadcaster
+                .broadcast(ServerResponse::UndoDeclined {
+                    player_id: event.player_id().unwrap_or(&request.player_id).to_string(),
+                })
+                .await;
+        }
+        self.undo_point = undo_base.and_then(|(stats, log_seq)| {
+            let player_id = event.player_id()?;
+            let snapshot = undo_snapshot?;
+            let turn_order = &before.turn_order;
+            let undoable = player_id == turn_order.active_player_id
+                && !before.mulligan_pending
+                && self.game_state.turn_order.get_turn_counter() == turn_order.get_turn_counter()
+                && !self.game_state.board.revealed_since(&before.reveal_mark);
+            let action = self
+                .event_log
+                .since(Some(log_seq))
+                .into_iter()
+                .map(|entry| entry.text)
+                .collect::<Vec<_>>()
+                .join(", ");
+            undoable.then(|| UndoPoint {
+                player_id: player_id.to_string(),
+                action,
+                snapshot,
+                replay_len: self.replay.events.len() - 1,
+                events_len: self.events.len() - 1,
+                stats,
+            })
+        });
+    }
+
+    /// The active player asks to take back their last action; every opponent has to agree
+    pub async fn request_undo(&mut self, player_id: &str) -> AppResult<()> {
+        let not_allowed = |reason: &str| AppError::UndoNotAllowed {
+            reason: reason.to_string(),
+        };
+        if !self.replay.undo {
+            return Err(not_allowed("this room doesn't allow undo"));
+        }
+        if self.undo_request.is_some() {
+            return Err(not_allowed(
+                "an undo is already waiting on the other players",
+            ));
+        }
+        let action = self
+            .undo_point
+            .as_ref()
+            .filter(|point| point.player_id == player_id)
+            .map(|point| point.action.clone())
+            .ok_or_else(|| not_allowed("you have no action that can be taken back"))?;
+
+        let text = format!(
+            "{} asked to undo: {}",
+            self.event_log.name(player_id),
+            action
+        );
+        self.event_log.push(text);
+        self.state_broadcaster
+            .broadcast(ServerResponse::UndoRequested {
+                player_id: player_id.to_string(),
+                action,
+            })
+            .await;
+
+        let waiting_on: HashSet<String> = self
+            .game_state
+            .turn_order
+            .order
+            .iter()
+            .filter(|other| *other != player_id)
+            .cloned()
+            .collect();
+        if waiting_on.is_empty() {
+            return self.apply_undo().await;
+        }
+        self.undo_request = Some(UndoRequest {
+            player_id: player_id.to_string(),
+            waiting_on,
+        });
+        Ok(())
+    }
+
+    pub async fn respond_to_undo(&mut self, player_id: &str, accept: bool) -> AppResult<()> {
+        let request = self
+            .undo_request
+            .as_mut()
+            .filter(|request| request.waiting_on.contains(player_id))
+            .ok_or(AppError::UndoNotFound)?;
+        if !accept {
+            self.undo_request = None;
+            let text = format!("{} declined the undo", self.event_log.name(player_id));
+            self.event_log.push(text);
+            self.state_broadcaster
+                .broadcast(ServerResponse::UndoDeclined {
+                    player_id: player_id.to_string(),
+                })
+                .await;
+            return Ok(());
+        }
+        request.waiting_on.remove(player_id);
+        if request.waiting_on.is_empty() {
+            self.undo_request = None;
+            self.apply_undo().await?;
+        }
+        Ok(())
+    }
+
+    /// Offer to end the game with no winner; everyone else still playing has to agree
+    pub async fn offer_draw(&mut self, player_id: &str) -> AppResult<()> {
+        if self.draw_offer.is_some() {
+            return Err(AppError::DrawNotAllowed {
+                reason: "a draw is already waiting on the other players".to_string(),
+            });
+        }
+        let order = &self.game_state.turn_order.order;
+        if !order.iter().any(|seated| seated == player_id) {
+            return Err(AppError::PlayerNotFound);
+        }
+        let waiting_on: HashSet<String> = order
+            .iter()
+            .filter(|other| *other != player_id)
+            .cloned()
+            .collect();
+
+        let text = format!("{} offered a draw", self.event_log.name(player_id));
+        self.event_log.push(text);
+        self.state_broadcaster
+            .broadcast(ServerResponse::DrawOffered {
+                player_id: player_id.to_string(),
+            })
+            .await;
+        if waiting_on.is_empty() {
+            self.end_game(None).await;
+            return Ok(());
+        }
+        self.draw_offer = Some(DrawOffer {
+            player_id: player_id.to_string(),
+            waiting_on,
+        });
+        Ok(())
+    }
+
+    pub async fn respond_to_draw(&mut self, player_id: &str, accept: bool) -> AppResult<()> {
+        let offer = self
+            .draw_offer
+            .as_mut()
+            .filter(|offer| offer.waiting_on.contains(player_id))
+            .ok_or(AppError::DrawOfferNotFound)?;
+        if !accept {
+            self.draw_offer = None;
+            let text = format!("{} declined the draw", self.event_log.name(player_id));
+            self.event_log.push(text);
+            self.state_broadcaster
+                .broadcast(ServerResponse::DrawDeclined {
+                    player_id: player_id.to_string(),
+                })
+                .await;
+            return Ok(());
+        }
+        offer.waiting_on.remove(player_id);
+        if offer.waiting_on.is_empty() {
+            self.draw_offer = None;
+            self.end_game(None).await;
         }
+        Ok(())
+    }
+
+    /// Take back a draw `player_id` offered before everyone answered
+    pub async fn withdraw_draw_offer(&mut self, player_id: &str) -> AppResult<()> {
+        if self
+            .draw_offer
+            .as_ref()
+            .is_none_or(|offer| offer.player_id != player_id)
+        {
+            return Err(AppError::DrawOfferNotFound);
+        }
+        let text = format!("{} withdrew the draw", self.event_log.name(player_id));
+        self.event_log.push(text);
+        self.expire_draw_offer().await;
+        Ok(())
+    }
+
+    // Drop the open offer, if any, as though whoever made it declined it
+    async fn expire_draw_offer(&mut self) {
+        if let Some(offer) = self.draw_offer.take() {
+            self.state_broadcaster
+                .broadcast(ServerResponse::DrawDeclined {
+                    player_id: offer.player_id,
+                })
+                .await;
+        }
+    }
+
+    // A conceding player's offer is withdrawn, and the others no longer wait on their answer
+    async fn withdraw_from_draw_offer(&mut self, player_id: &str) -> AppResult<()> {
+        let Some(offer) = self.draw_offer.as_mut() else {
+            return Ok(());
+        };
+        if offer.player_id == player_id {
+            self.expire_draw_offer().await;
+        } else if offer.waiting_on.remove(player_id) && offer.waiting_on.is_empty() {
+            self.draw_offer = None;
+            self.end_game(None).await;
+        }
+        Ok(())
+    }
+
+    async fn apply_undo(&mut self) -> AppResult<()> {
+        let point = self
+            .undo_point
+            .take()
+            .ok_or_else(|| AppError::UndoNotAllowed {
+                reason: "the action can no longer be taken back".to_string(),
+            })?;
+        self.game_state = GameState::restore(&point.snapshot)?;
+        self.replay.truncate(point.replay_len);
+        self.events.truncate(point.events_len);
+        self.stats = point.stats;
+        // Taking an action back never draws anything
+        self.seen_hands = self.current_hands();
+        // A choice answered by the undone action is open again and has to be prompted anew
+        self.prompted_choice = None;
+
+        let text = format!(
+            "{} took back: {}",
+            self.event_log.name(&point.player_id),
+            point.action
+        );
+        self.event_log.push(text);
+        self.state_broadcaster
+            .broadcast(ServerResponse::ActionUndone {
+                player_id: point.player_id,
+                action: point.action,
+            })
+            .await;
+        self.state_broadcaster
+            .broadcast_full_state(&self.game_state)
+            .await;
+        self.prompt_pending_choice().await;
+        Ok(())
+    }
+
+    /// Damage from a card effect or combat
+    pub async fn deal_damage(
+        &mut self,
+        source_id: Option<String>,
+        target_id: String,
+        amount: u32,
+    ) -> AppResult<()> {
+        self.handle_event(GameEvent::DealDamage {
+            source_id,
+            target_id,
+            amount,
+        })
+        .await
     }
 
     async fn transition_to_phase(&mut self, new_phase: TurnPhases) {
-        self.game_state = self.game_state.with_phase_transition(new_phase);
+        self.apply_rules(DomainEvent::PhaseEntered { phase: new_phase });
 
         // Handle phase-specific logic
         if matches!(self.game_state.current_phase, TurnPhases::LootStep) {
             // Draw loot for active player
-            let _ = self
-                .game_state
-                .board
-                .draw_loot_for_player(&self.game_state.turn_order.active_player_id);
+            let active_player_id = self.game_state.turn_order.active_player_id.clone();
+            self.draw_loot(&active_player_id);
+            self.log_private_draws().await;
         }
 
         // Start priority if not TurnEnd
         if !matches!(self.game_state.current_phase, TurnPhases::TurnEnd) {
+            self.refresh_windows();
             self.state_broadcaster
-                .broadcast_phase_start(&self.game_state)
+                .broadcast_phase_start(&self.game_state, &self.clock)
                 .await;
         }
     }
@@ -130,9 +1369,143 @@ impl GameCoordinator {
         self.game_state.turn_order.order.first().cloned()
     }
 
-    async fn end_game(&mut self, winner_id: String) {
-        self.game_state.game_running = false;
-        self.state_broadcaster.broadcast_game_ended(winner_id).await;
+    /// None when the players agreed to a draw or nobody is left
+    async fn end_game(&mut self, winner_id: Option<String>) {
+        self.apply_rules(DomainEvent::GameEnded);
+        self.winner = winner_id.clone();
+        let text = match &winner_id {
+            Some(winner_id) => format!("{} won the game", self.event_log.name(winner_id)),
+            None => "The game ended in a draw".to_string(),
+        };
+        self.event_log.push(text);
+        self.state_broadcaster
+            .broadcast_game_ended(winner_id, self.stats.clone())
+            .await;
+    }
+
+    pub async fn send_simple_state(&self, player_id: &str) -> AppResult<()> {
+        let state = self.game_state.simple_view(player_id)?;
+        self.state_broadcaster
+            .send_to_player(player_id, ServerResponse::SimpleState { state })
+            .await;
+        Ok(())
+    }
+
+    pub async fn send_legal_actions(&self, player_id: &str) -> AppResult<()> {
+        let actions = self.game_state.legal_actions(player_id)?;
+        self.state_broadcaster
+            .send_to_player(player_id, ServerResponse::LegalActions { actions })
+            .await;
+        Ok(())
+    }
+
+    pub async fn send_discard_pile(&self, player_id: &str, deck_type: DeckKind) {
+        let cards = self
+            .game_state
+            .board
+            .discard_cards(deck_type)
+            .into_iter()
+            .cloned()
+            .collect();
+        self.state_broadcaster
+            .send_to_player(player_id, ServerResponse::DiscardPile { deck_type, cards })
+            .await;
+    }
+
+    pub async fn reconnect_player(&mut self, player_id: &str, connection_id: String) {
+        self.state_broadcaster
+            .set_player_connection(player_id, connection_id);
+        self.refresh_windows();
+        self.state_broadcaster
+            .send_full_state_to_player(&self.game_state, &self.clock, player_id)
+            .await;
+        self.send_private_log(player_id, None).await;
+        if let Some(choice) = self
+            .game_state
+            .pending_choice()
+            .filter(|choice| choice.player_id == player_id)
+        {
+            self.send_choice(choice).await;
+        }
+    }
+
+    pub fn game_state(&self) -> &GameState {
+        &self.game_state
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn event_log(&self) -> &EventLog {
+        &self.event_log
+    }
+
+    pub fn events(&self) -> &EventStream {
+        &self.events
+    }
+
+    /// The game rebuilt from its starting state and the event stream alone, which should
+    /// always equal `game_state`. Games saved before the stream was kept start it empty and
+    /// can't be rebuilt this way.
+    pub fn rebuild_state(&self) -> AppResult<GameState> {
+        self.events.rebuild(self.replay.initial_state())
+    }
+
+    pub fn set_player_names(&mut self, player_names: HashMap<String, String>) {
+        self.event_log.set_player_names(player_names);
+    }
+
+    pub async fn send_event_log(&self, player_id: &str, since: Option<u64>) {
+        self.state_broadcaster
+            .send_to_player(
+                player_id,
+                ServerResponse::EventLog {
+                    entries: self.event_log.since(since),
+                },
+            )
+            .await;
+    }
+
+    pub async fn send_private_log(&self, player_id: &str, since: Option<u64>) {
+        self.state_broadcaster
+            .send_to_player(
+                player_id,
+                ServerResponse::PrivateLog {
+                    entries: self.event_log.private_since(player_id, since),
+                },
+            )
+            .await;
+    }
+
+    pub fn debug_dump(&self, recent_events: usize) -> GameDump<'_> {
+        let events = &self.replay.events;
+        GameDump {
+            seed: self.replay.seed,
+            state: &self.game_state,
+            stats: &self.stats,
+            winner: self.winner.as_deref(),
+            total_events: events.len(),
+            recent_events: &events[events.len().saturating_sub(recent_events)..],
+            log: self.event_log.since(None),
+        }
+    }
+
+    pub fn stats(&self) -> &GameStats {
+        &self.stats
+    }
+
+    pub fn winner(&self) -> Option<&str> {
+        self.winner.as_deref()
+    }
+
+    pub fn pending_choice(&self) -> Option<&PendingChoice> {
+        self.game_state.pending_choice()
+    }
+
+    /// Players who haven't decided on their opening hand yet
+    pub fn pending_mulligans(&self) -> Vec<String> {
+        self.game_state.pending_mulligans.iter().cloned().collect()
     }
 
     pub fn is_running(&self) -> bool {
@@ -1,10 +1,17 @@
-use serde::Serialize;
-use std::collections::HashSet;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use crate::game::board::Board;
+use crate::game::board::{Board, Player};
+use crate::game::cards_types::{Item, LootCard};
+use crate::game::choices::{ChoiceKind, ChoiceOption, PendingChoice};
+use crate::game::domain_events::DomainEvent;
+use crate::game::rules::RuleSet;
+use crate::game::trades::{Trade, TradeOffer};
+use crate::game::triggers::{GameMoment, StackEntry};
 use crate::{AppError, AppResult, TurnOrder};
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
 pub enum TurnPhases {
     UntapStartStep,
     LootStep,
@@ -13,7 +20,76 @@ pub enum TurnPhases {
     TurnEnd,
 }
 
-#[derive(Debug, Clone)]
+/// Compact, human-readable view of the game for minimal clients and accessibility tools
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SimpleStateView {
+    pub turn: u32,
+    pub active_player: String,
+    pub your_turn: bool,
+    pub phase: TurnPhases,
+    pub priority_player: String,
+    pub hand: Vec<String>,
+    pub health: u32,
+    pub max_health: u32,
+    pub coins: u32,
+    pub choice: Option<String>, // prompt of a choice waiting on this player
+    pub summary: String,
+}
+
+/// Everything a player may do right now, worked out with the same checks the actions go
+/// through. Effects don't take targets yet; the only targets are the players a trade can go to.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LegalActions {
+    pub pass_turn: bool,
+    pub pass_priority: bool,
+    pub mulligan: bool, // keep or redraw the opening hand
+    pub loot_cards: Vec<LegalLootPlay>,
+    pub items: Vec<String>, // entity ids of the items that can be activated
+    pub shop: Vec<usize>,   // shop slots the player can afford and buy now
+    pub choice: Option<LegalChoice>, // the choice waiting on this player, if any
+    pub trade_partners: Vec<String>, // players a trade can be offered to
+    pub trades_to_answer: Vec<String>, // trade ids offered to this player
+    pub concede: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LegalLootPlay {
+    pub card_id: String,
+    pub tap_character: bool, // only playable by tapping the character
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LegalChoice {
+    pub choice_id: String,
+    pub option_ids: Vec<String>,
+}
+
+/// What the whole table sees of a player: how many loot cards they hold, never which
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PublicPlayerView {
+    pub hand_count: usize,
+    pub current_health: u32,
+    pub max_health: u32,
+    pub coins: u32,
+    pub dead: bool,
+    pub character_tapped: bool,
+    pub items: Vec<Item>,
+}
+
+const SNAPSHOT_FORMAT: u32 = 1; // bump when GameState changes in a way old snapshots can't load
+
+/// The whole game frozen at one point: board and rng, turn order, phase, open choices,
+/// trades and stack. The state is held as compact JSON so keeping many of them is cheap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub format: u32,
+    pub turn: u32,
+    pub phase: TurnPhases,
+    pub active_player: String,
+    state: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameState {
     pub turn_order: TurnOrder,
     pub current_phase: TurnPhases,
@@ -22,11 +98,38 @@ pub struct GameState {
     pub board: Board,
     pub game_running: bool,
     pub waiting_for_priority: bool,
+    pub seed: u64,
+    #[serde(default)]
+    pub pending_mulligans: HashSet<String>, // players yet to keep or redraw their opening hand
+    #[serde(default)]
+    pub pending_choices: VecDeque<PendingChoice>, // answered front to back
+    #[serde(default)]
+    next_choice_id: u64,
+    #[serde(default)]
+    pub pending_trades: Vec<Trade>,
+    #[serde(default)]
+    next_trade_id: u64,
+    #[serde(default)]
+    pub stack: VecDeque<StackEntry>, // triggered effects, resolved front to back
+    #[serde(default)]
+    pub turn_ending: bool, // the turn passed but waits on choices, such as the hand limit
+    #[serde(default)]
+    pub conceded: Vec<String>, // players who left the game, in the order they conceded
+    #[serde(skip)]
+    resolved_triggers: Vec<StackEntry>, // since the coordinator last took them, for the log
 }
 
 impl GameState {
-    pub fn new(player_ids: Vec<String>, turn_order: TurnOrder) -> Self {
-        let board = Board::new(player_ids);
+    pub fn new(
+        mut player_ids: Vec<String>,
+        turn_order: TurnOrder,
+        seed: u64,
+        card_sets: &[String],
+        rules: &RuleSet,
+    ) -> Self {
+        // Callers collect ids from HashMaps; sort so dealing order only depends on the seed
+        player_ids.sort();
+        let board = Board::new(player_ids, seed, card_sets, rules);
         Self {
             current_priority_player: turn_order.active_player_id.clone(),
             current_phase: TurnPhases::UntapStartStep,
@@ -35,7 +138,473 @@ impl GameState {
             players_passed_priority: HashSet::new(),
             game_running: true,
             waiting_for_priority: false,
+            seed,
+            pending_mulligans: HashSet::new(),
+            pending_choices: VecDeque::new(),
+            next_choice_id: 0,
+            pending_trades: Vec::new(),
+            next_trade_id: 0,
+            stack: VecDeque::new(),
+            turn_ending: false,
+            conceded: Vec::new(),
+            resolved_triggers: Vec::new(),
+        }
+    }
+
+    /// The house rules this game is played with
+    pub fn rules(&self) -> &RuleSet {
+        &self.board.rules
+    }
+
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            format: SNAPSHOT_FORMAT,
+            turn: self.turn_order.get_turn_counter(),
+            phase: self.current_phase.clone(),
+            active_player: self.turn_order.active_player_id.clone(),
+            state: serde_json::to_string(self).expect("Game state always serializes"),
+        }
+    }
+
+    /// Rebuild the game exactly as it was snapshotted, rng included, so it plays on the same way
+    pub fn restore(snapshot: &StateSnapshot) -> AppResult<Self> {
+        if snapshot.format != SNAPSHOT_FORMAT {
+            return Err(AppError::SerializationError {
+                message: format!(
+                    "Snapshot format {} is not supported (expected {})",
+                    snapshot.format, SNAPSHOT_FORMAT
+                ),
+            });
+        }
+        serde_json::from_str(&snapshot.state).map_err(|e| AppError::SerializationError {
+            message: e.to_string(),
+        })
+    }
+
+    /// Put every trigger listening for `moment` on the stack, oldest card first, and resolve it.
+    /// Priority passing is off for now, so nobody gets to respond before the stack resolves.
+    fn fire_triggers(&mut self, moment: GameMoment) {
+        let board = &self.board;
+        let items = board.players_items.iter().flat_map(|(owner_id, items)| {
+            items.iter().map(move |item| {
+                (
+                    owner_id.as_str(),
+                    &item.card,
+                    &item.triggers,
+                    item.entered_play,
+                )
+            })
+        });
+        let curses = board.players_curses.iter().flat_map(|(owner_id, curses)| {
+            curses.iter().map(move |curse| {
+                (
+                    owner_id.as_str(),
+                    &curse.card,
+                    &curse.triggers,
+                    curse.entered_play,
+                )
+            })
+        });
+        // Events belong to nobody, they apply to whoever the moment is about
+        let events = board.active_events.iter().map(|event| {
+            (
+                moment.player_id(),
+                &event.card,
+                &event.triggers,
+                event.entered_play,
+            )
+        });
+
+        let mut fired: Vec<(u64, StackEntry)> = items
+            .chain(curses)
+            .chain(events)
+            .flat_map(|(owner_id, card, triggers, entered_play)| {
+                triggers
+                    .iter()
+                    .filter(|trigger| trigger.on.matches(owner_id, &moment))
+                    .map(move |trigger| {
+                        let entry = StackEntry {
+                            source_id: card.entity_id.clone(),
+                            source_name: card.name.clone(),
+                            owner_id: owner_id.to_string(),
+                            effect: trigger.effect.clone(),
+                        };
+                        (entered_play, entry)
+                    })
+            })
+            .collect();
+        fired.sort_by_key(|(entered_play, _)| *entered_play);
+        self.stack.extend(fired.into_iter().map(|(_, entry)| entry));
+        self.resolve_stack();
+    }
+
+    fn resolve_stack(&mut self) {
+        while let Some(entry) = self.stack.pop_front() {
+            entry.resolve(&mut self.board, &mut self.turn_order);
+            self.resolved_triggers.push(entry);
+        }
+    }
+
+    /// Triggers resolved since the last call
+    pub fn take_resolved_triggers(&mut self) -> Vec<StackEntry> {
+        std::mem::take(&mut self.resolved_triggers)
+    }
+
+    /// Apply something that happened, the one way the game changes. An event the rules
+    /// refuse is refused before anything changes.
+    pub fn apply(&mut self, event: &DomainEvent) -> AppResult<()> {
+        match event {
+            DomainEvent::MulliganDecided { player_id, keep } => {
+                self.decide_mulligan(player_id, *keep)
+            }
+            DomainEvent::ItemActivated { player_id, item_id } => {
+                if !self.turn_order.is_player_turn(player_id) {
+                    return Err(AppError::NotPlayerTurn);
+                }
+                self.board.activate_item(player_id, item_id).map(drop)
+            }
+            DomainEvent::ItemBought {
+                player_id,
+                shop_index,
+            } => {
+                self.check_purchase(player_id, *shop_index)?;
+                self.board.buy_from_shop(player_id, *shop_index).map(drop)
+            }
+            DomainEvent::LootPlayed {
+                player_id,
+                card_id,
+                tap_character,
+            } => {
+                let own_turn = self.turn_order.is_player_turn(player_id);
+                self.board
+                    .play_loot(player_id, card_id, own_turn, *tap_character)
+                    .map(drop)
+            }
+            DomainEvent::ChoiceAnswered {
+                player_id,
+                choice_id,
+                option_id,
+            } => self.answer_choice(player_id, choice_id, option_id),
+            DomainEvent::TradeProposed {
+                player_id,
+                to_player,
+                offer,
+                request,
+            } => self.propose_trade(player_id, to_player, offer, request),
+            DomainEvent::TradeAnswered {
+                player_id,
+                trade_id,
+                accept,
+            } => self.answer_trade(player_id, trade_id, *accept),
+            DomainEvent::CurseAttached { player_id } => {
+                self.board.attach_curse(player_id).map(drop)
+            }
+            DomainEvent::EventRevealed => self.board.reveal_event().map(drop),
+            DomainEvent::PlayerDamaged { target_id, amount } => {
+                self.damage_player(target_id, *amount)
+            }
+            // Only triggers care about the result so far
+            DomainEvent::DiceRolled { player_id, value } => {
+                if !self.board.players.contains_key(player_id) {
+                    return Err(AppError::PlayerNotFound);
+                }
+                self.fire_triggers(GameMoment::DiceRolled {
+                    player_id: player_id.clone(),
+                    value: *value,
+                });
+                Ok(())
+            }
+            DomainEvent::TurnPassed { player_id } => {
+                if !self.can_player_pass_turn(player_id) {
+                    return Err(AppError::NotPlayerTurn);
+                }
+                self.enter_phase(TurnPhases::TurnEnd);
+                Ok(())
+            }
+            DomainEvent::PriorityPassed { player_id } => self.pass_priority(player_id),
+            DomainEvent::PlayerConceded { player_id } => self.concede(player_id),
+            DomainEvent::TriggersFired { moment } => {
+                self.fire_triggers(moment.clone());
+                Ok(())
+            }
+            // An empty deck with nothing to reshuffle simply draws nothing
+            DomainEvent::LootDrawn { player_id } => {
+                let _ = self.board.draw_loot_for_player(player_id);
+                Ok(())
+            }
+            DomainEvent::PhaseEntered { phase } => {
+                self.enter_phase(phase.clone());
+                Ok(())
+            }
+            DomainEvent::GameEnded => {
+                self.game_running = false;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn is_mulligan_pending(&self) -> bool {
+        !self.pending_mulligans.is_empty()
+    }
+
+    fn decide_mulligan(&mut self, player_id: &str, keep: bool) -> AppResult<()> {
+        if !self.pending_mulligans.contains(player_id) {
+            return Err(AppError::MulliganNotAllowed);
+        }
+        if !keep {
+            self.board.redraw_hand(player_id)?;
+        }
+        self.pending_mulligans.remove(player_id);
+        Ok(())
+    }
+
+    fn propose_trade(
+        &mut self,
+        player_id: &str,
+        to_player: &str,
+        offer: &TradeOffer,
+        request: &TradeOffer,
+    ) -> AppResult<()> {
+        if player_id == to_player {
+            return Err(AppError::InvalidTrade {
+                reason: "you can't trade with yourself".to_string(),
+            });
+        }
+        if !self.board.players.contains_key(to_player) {
+            return Err(AppError::PlayerNotFound);
+        }
+        if offer.is_empty() && request.is_empty() {
+            return Err(AppError::InvalidTrade {
+                reason: "nothing is being traded".to_string(),
+            });
+        }
+        self.check_can_give(player_id, offer)?;
+        self.check_can_give(to_player, request)?;
+
+        self.next_trade_id += 1;
+        self.pending_trades.push(Trade {
+            trade_id: format!("trade-{}", self.next_trade_id),
+            from_player: player_id.to_string(),
+            to_player: to_player.to_string(),
+            offer: offer.clone(),
+            request: request.clone(),
+        });
+        Ok(())
+    }
+
+    /// The receiver accepts or declines; the proposer can only decline, withdrawing the offer.
+    /// Holdings are checked again on accept since they may have changed since the proposal.
+    fn answer_trade(&mut self, player_id: &str, trade_id: &str, accept: bool) -> AppResult<()> {
+        let pos = self
+            .pending_trades
+            .iter()
+            .position(|trade| {
+                trade.trade_id == trade_id
+                    && (trade.to_player == player_id || (!accept && trade.from_player == player_id))
+            })
+            .ok_or(AppError::TradeNotFound)?;
+
+        if accept {
+            let trade = &self.pending_trades[pos];
+            self.check_can_give(&trade.from_player, &trade.offer)?;
+            self.check_can_give(&trade.to_player, &trade.request)?;
+        }
+        let trade = self.pending_trades.remove(pos);
+        if accept {
+            self.board
+                .transfer(&trade.from_player, &trade.to_player, &trade.offer)?;
+            self.board
+                .transfer(&trade.to_player, &trade.from_player, &trade.request)?;
+        }
+        Ok(())
+    }
+
+    fn check_can_give(&self, player_id: &str, offer: &TradeOffer) -> AppResult<()> {
+        let player = self
+            .board
+            .players
+            .get(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        if player.coins < offer.coins {
+            return Err(AppError::InvalidTrade {
+                reason: format!("{} doesn't have {}¢", player_id, offer.coins),
+            });
+        }
+        let hand = self.board.get_player_hand(player_id)?;
+        for (i, card_id) in offer.loot.iter().enumerate() {
+            let in_hand = hand.iter().any(|card| card.entity_id == *card_id);
+            if !in_hand || offer.loot[..i].contains(card_id) {
+                return Err(AppError::InvalidTrade {
+                    reason: format!("{} doesn't hold card {}", player_id, card_id),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The choice the game is waiting on, if any
+    pub fn pending_choice(&self) -> Option<&PendingChoice> {
+        self.pending_choices.front()
+    }
+
+    fn answer_choice(
+        &mut self,
+        player_id: &str,
+        choice_id: &str,
+        option_id: &str,
+    ) -> AppResult<()> {
+        let choice = self
+            .pending_choice()
+            .filter(|choice| choice.choice_id == choice_id && choice.player_id == player_id)
+            .ok_or(AppError::ChoiceNotFound)?;
+        if !choice.has_option(option_id) {
+            return Err(AppError::InvalidChoiceOption {
+                option_id: option_id.to_string(),
+            });
+        }
+
+        // Applied before the choice is closed, so a refused answer leaves it open
+        let kind = choice.kind.clone();
+        self.apply_choice(&kind, player_id, option_id)?;
+        self.pending_choices.pop_front();
+        // The last answer lets a waiting turn end, or asks for the next hand limit discard
+        if self.turn_ending && self.pending_choices.is_empty() {
+            self.enter_phase(TurnPhases::TurnEnd);
         }
+        Ok(())
+    }
+
+    fn apply_choice(
+        &mut self,
+        kind: &ChoiceKind,
+        player_id: &str,
+        option_id: &str,
+    ) -> AppResult<()> {
+        match kind {
+            ChoiceKind::DiscardLoot | ChoiceKind::HandLimit => {
+                self.board.discard_from_hand(player_id, option_id)?;
+            }
+            ChoiceKind::DestroyItem => {
+                self.board.destroy_item(player_id, option_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Ask a player to pick one of `options`. With a single option there's nothing to
+    /// decide, so it is applied straight away.
+    fn request_choice(
+        &mut self,
+        player_id: &str,
+        kind: ChoiceKind,
+        prompt: &str,
+        options: Vec<ChoiceOption>,
+    ) -> AppResult<()> {
+        match options.as_slice() {
+            [] => Ok(()),
+            [only] => {
+                let option_id = only.option_id.clone();
+                self.apply_choice(&kind, player_id, &option_id)
+            }
+            _ => {
+                self.next_choice_id += 1;
+                self.pending_choices.push_back(PendingChoice {
+                    choice_id: format!("choice-{}", self.next_choice_id),
+                    player_id: player_id.to_string(),
+                    kind,
+                    prompt: prompt.to_string(),
+                    options,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Death penalty: lose 1¢, then the player picks a loot card to discard and a
+    /// non-eternal item to destroy
+    fn pay_death_penalty(&mut self, player_id: &str) -> AppResult<()> {
+        self.board.lose_coins(player_id, 1)?;
+
+        let loot_options = self
+            .board
+            .get_player_hand(player_id)?
+            .iter()
+            .map(|card| ChoiceOption {
+                option_id: card.entity_id.clone(),
+                label: card.name.clone(),
+            })
+            .collect();
+        self.request_choice(
+            player_id,
+            ChoiceKind::DiscardLoot,
+            "You died: choose a loot card to discard",
+            loot_options,
+        )?;
+
+        let item_options = self
+            .board
+            .players_items
+            .get(player_id)
+            .into_iter()
+            .flatten()
+            .filter(|item| !item.eternal)
+            .map(|item| ChoiceOption {
+                option_id: item.entity_id.clone(),
+                label: item.name.clone(),
+            })
+            .collect();
+        self.request_choice(
+            player_id,
+            ChoiceKind::DestroyItem,
+            "You died: choose an item to destroy",
+            item_options,
+        )
+    }
+
+    /// End of turn over the hand limit: one card at a time, the newest first when the player
+    /// lets the choice time out
+    fn request_hand_limit_discard(&mut self, player_id: &str) -> AppResult<()> {
+        let options = self
+            .board
+            .get_player_hand(player_id)?
+            .iter()
+            .rev()
+            .map(|card| ChoiceOption {
+                option_id: card.entity_id.clone(),
+                label: card.name.clone(),
+            })
+            .collect();
+        let prompt = format!(
+            "Hand limit: discard {} more",
+            self.board.cards_over_hand_limit(player_id)
+        );
+        self.request_choice(player_id, ChoiceKind::HandLimit, &prompt, options)
+    }
+
+    /// Damage a player. A death pays the death penalty and, on the active player, ends the turn.
+    fn damage_player(&mut self, target_id: &str, amount: u32) -> AppResult<()> {
+        // Only a death can be refused, so only a death needs the undo
+        let undo = if self.board.would_die(target_id, amount) {
+            DeathUndo::take(self, target_id)
+        } else {
+            None
+        };
+        if self.board.damage_player(target_id, amount)? {
+            // The damage is already dealt when the penalty is worked out
+            if let Err(e) = self.pay_death_penalty(target_id) {
+                if let Some(undo) = undo {
+                    undo.put_back(self, target_id);
+                }
+                return Err(e);
+            }
+            self.fire_triggers(GameMoment::PlayerDied {
+                player_id: target_id.to_string(),
+            });
+            if self.turn_order.is_player_turn(target_id) {
+                self.enter_phase(TurnPhases::TurnEnd);
+            }
+        }
+        Ok(())
     }
 
     // Pure state validation - no side effects
@@ -51,6 +620,182 @@ impl GameState {
         self.players_passed_priority.len() == self.turn_order.order.len()
     }
 
+    /// Every player as the public board shows them; hands stay in PrivateBoardState
+    pub fn public_players(&self) -> HashMap<String, PublicPlayerView> {
+        self.board
+            .players
+            .iter()
+            .map(|(player_id, player)| {
+                let view = PublicPlayerView {
+                    hand_count: self.board.get_hand_size(player_id).unwrap_or(0),
+                    current_health: player.current_health,
+                    max_health: player.max_health,
+                    coins: player.coins,
+                    dead: player.dead,
+                    character_tapped: !player.loot_play_char,
+                    items: self
+                        .board
+                        .players_items
+                        .get(player_id)
+                        .cloned()
+                        .unwrap_or_default(),
+                };
+                (player_id.clone(), view)
+            })
+            .collect()
+    }
+
+    /// What `player_id` can do in this state. An open mulligan or choice holds up everything
+    /// else, as it does when the actions are sent.
+    pub fn legal_actions(&self, player_id: &str) -> AppResult<LegalActions> {
+        if !self.board.players.contains_key(player_id) {
+            return Err(AppError::PlayerNotFound);
+        }
+        let mut actions = LegalActions::default();
+        if self.is_mulligan_pending() {
+            actions.mulligan = self.pending_mulligans.contains(player_id);
+            return Ok(actions);
+        }
+        actions.concede = true;
+        if let Some(choice) = self.pending_choice() {
+            if choice.player_id == player_id {
+                actions.choice = Some(LegalChoice {
+                    choice_id: choice.choice_id.clone(),
+                    option_ids: choice
+                        .options
+                        .iter()
+                        .map(|option| option.option_id.clone())
+                        .collect(),
+                });
+            }
+            return Ok(actions);
+        }
+
+        actions.pass_turn = self.can_player_pass_turn(player_id);
+        actions.pass_priority = self.can_player_pass_priority(player_id);
+        // Checked against the board as it is, nothing is played to find out
+        let own_turn = self.turn_order.is_player_turn(player_id);
+        let can_play = |card_id: &str, tap_character: bool| {
+            self.board
+                .check_loot_play(player_id, card_id, own_turn, tap_character)
+                .is_ok()
+        };
+        let hand = self.board.players_hands.get(player_id);
+        for card in hand.into_iter().flatten() {
+            let card_id = &card.entity_id;
+            let tap_character = if can_play(card_id, false) {
+                false
+            } else if can_play(card_id, true) {
+                true
+            } else {
+                continue;
+            };
+            actions.loot_cards.push(LegalLootPlay {
+                card_id: card_id.clone(),
+                tap_character,
+            });
+        }
+        let items = self.board.players_items.get(player_id);
+        actions.items = items
+            .into_iter()
+            .flatten()
+            .filter(|item| {
+                own_turn
+                    && self
+                        .board
+                        .check_item_activation(player_id, &item.entity_id)
+                        .is_ok()
+            })
+            .map(|item| item.entity_id.clone())
+            .collect();
+        actions.shop = (0..self.board.shop.len())
+            .filter(|&shop_index| self.check_purchase(player_id, shop_index).is_ok())
+            .collect();
+        actions.trade_partners = self
+            .turn_order
+            .order
+            .iter()
+            .filter(|other| *other != player_id)
+            .cloned()
+            .collect();
+        actions.trades_to_answer = self
+            .pending_trades
+            .iter()
+            .filter(|trade| trade.to_player == player_id)
+            .map(|trade| trade.trade_id.clone())
+            .collect();
+        Ok(actions)
+    }
+
+    pub fn simple_view(&self, player_id: &str) -> AppResult<SimpleStateView> {
+        let player = self
+            .board
+            .players
+            .get(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        let hand: Vec<String> = self
+            .board
+            .get_player_hand(player_id)?
+            .iter()
+            .map(|card| card.name.clone())
+            .collect();
+
+        let choice = self
+            .pending_choice()
+            .filter(|choice| choice.player_id == player_id)
+            .map(|choice| choice.prompt.clone());
+
+        let turn = self.turn_order.get_turn_counter() + 1;
+        let your_turn = self.turn_order.is_player_turn(player_id);
+        let whose_turn = if your_turn {
+            "Your turn".to_string()
+        } else {
+            format!("{}'s turn", self.turn_order.active_player_id)
+        };
+        let hand_text = if hand.is_empty() {
+            "empty".to_string()
+        } else {
+            hand.join(", ")
+        };
+        let summary = format!(
+            "Turn {}: {}, {:?}. Your hand: {}. HP {}/{}. {}¢.",
+            turn,
+            whose_turn,
+            self.current_phase,
+            hand_text,
+            player.current_health,
+            player.max_health,
+            player.coins
+        );
+
+        Ok(SimpleStateView {
+            turn,
+            active_player: self.turn_order.active_player_id.clone(),
+            your_turn,
+            phase: self.current_phase.clone(),
+            priority_player: self.current_priority_player.clone(),
+            hand,
+            health: player.current_health,
+            max_health: player.max_health,
+            coins: player.coins,
+            choice,
+            summary,
+        })
+    }
+
+    /// Items are bought on your own turn, in the action step, with coins to pay for them
+    fn check_purchase(&self, player_id: &str, shop_index: usize) -> AppResult<()> {
+        if !self.turn_order.is_player_turn(player_id) {
+            return Err(AppError::NotPlayerTurn);
+        }
+        if self.current_phase != TurnPhases::ActionStep {
+            return Err(AppError::PurchaseNotAllowed {
+                reason: "items can only be bought in your action step".to_string(),
+            });
+        }
+        self.board.check_purchase(player_id, shop_index)
+    }
+
     pub fn get_next_phase(&self) -> TurnPhases {
         match self.current_phase {
             TurnPhases::UntapStartStep => TurnPhases::LootStep,
@@ -79,57 +824,174 @@ impl GameState {
         None
     }
 
-    // State mutation methods - return new state or error
-    pub fn with_priority_pass(&self, player_id: String) -> AppResult<Self> {
-        if !self.can_player_pass_priority(&player_id) {
+    fn pass_priority(&mut self, player_id: &str) -> AppResult<()> {
+        if !self.can_player_pass_priority(player_id) {
             println!("❌ Player {} cannot pass priority", player_id);
             return Err(AppError::InvalidPriorityPass);
         }
 
-        let mut new_state = self.clone();
-        new_state.players_passed_priority.insert(player_id.clone());
+        self.players_passed_priority.insert(player_id.to_string());
 
-        if new_state.all_players_passed_priority() {
+        if self.all_players_passed_priority() {
             println!("🎯 All players passed priority, advancing phase");
-            new_state = new_state.with_phase_transition(new_state.get_next_phase());
-        } else if let Some(next_player) = new_state.get_next_priority_player() {
+            self.enter_phase(self.get_next_phase());
+        } else if let Some(next_player) = self.get_next_priority_player() {
             println!("🎯 Next priority player: {}", next_player);
-            new_state.current_priority_player = next_player;
+            self.current_priority_player = next_player;
         } else {
             println!("❌ No next priority player found!");
         }
 
-        Ok(new_state)
+        Ok(())
+    }
+
+    /// Take a player out of the game for good. Their cards go to the discard piles, their
+    /// open choices, trades and triggers are dropped, and if it was their turn the next
+    /// player's turn starts.
+    fn concede(&mut self, player_id: &str) -> AppResult<()> {
+        if !self.turn_order.order.iter().any(|id| id == player_id) {
+            return Err(AppError::PlayerNotFound);
+        }
+        let was_active = self.turn_order.is_player_turn(player_id);
+        let had_priority = self.current_priority_player == player_id;
+        self.board.remove_player(player_id);
+        self.conceded.push(player_id.to_string());
+        self.pending_mulligans.remove(player_id);
+        self.pending_choices
+            .retain(|choice| choice.player_id != player_id);
+        self.pending_trades
+            .retain(|trade| trade.from_player != player_id && trade.to_player != player_id);
+        self.stack.retain(|entry| entry.owner_id != player_id);
+        self.players_passed_priority.remove(player_id);
+        // Worked out while they still have a seat to count from
+        let next_priority_player = self.get_next_priority_player();
+
+        self.turn_order.remove_player(player_id);
+        if self.turn_order.order.is_empty() {
+            return Ok(());
+        }
+        if was_active {
+            self.turn_ending = false;
+            self.begin_turn();
+        } else if self.waiting_for_priority && self.all_players_passed_priority() {
+            self.enter_phase(self.get_next_phase());
+        } else if had_priority {
+            if let Some(next_player) = next_priority_player {
+                self.current_priority_player = next_player;
+            }
+        }
+        Ok(())
     }
 
-    pub fn with_phase_transition(&self, new_phase: TurnPhases) -> Self {
-        let mut new_state = self.clone();
-        new_state.current_phase = new_phase.clone();
+    // The active player's turn starting from its untap step, after the turn order moved on
+    fn begin_turn(&mut self) {
+        self.current_phase = TurnPhases::UntapStartStep;
+        self.current_priority_player = self.turn_order.active_player_id.clone();
+        self.waiting_for_priority = true;
+        self.players_passed_priority.clear();
+        let active_player_id = self.turn_order.active_player_id.clone();
+        self.board.respawn_player(&active_player_id);
+        self.board.untap_items(&active_player_id);
+        self.board.reset_loot_plays(&active_player_id);
+        self.fire_triggers(GameMoment::TurnStart {
+            player_id: active_player_id.clone(),
+        });
+        // The loot step's draw happens as the turn starts, before anyone holds priority
+        let _ = self
+            .board
+            .draw_loot_for_player(&self.current_priority_player);
+        self.fire_triggers(GameMoment::LootStep {
+            player_id: active_player_id,
+        });
+    }
+
+    fn enter_phase(&mut self, new_phase: TurnPhases) {
+        let previous_phase = std::mem::replace(&mut self.current_phase, new_phase.clone());
+        let active_player_id = self.turn_order.active_player_id.clone();
 
         if matches!(new_phase, TurnPhases::TurnEnd) {
-            new_state.turn_order.advance_turn();
-            new_state.current_phase = TurnPhases::UntapStartStep;
-            new_state.current_priority_player = new_state.turn_order.active_player_id.clone();
-            new_state.waiting_for_priority = true;
-            new_state.players_passed_priority.clear();
-            // Temporary since Priority is commented
-            new_state
-                .board
-                .draw_loot_for_player(&new_state.current_priority_player);
+            // Passing the turn skips straight here, so the end step happens on the way out
+            if previous_phase != TurnPhases::EndStep {
+                self.fire_triggers(GameMoment::EndStep {
+                    player_id: active_player_id.clone(),
+                });
+            }
+            // Over the hand limit the turn stays in its end step until the player discarded
+            // down; other open choices (a death penalty) are answered first
+            if self.board.cards_over_hand_limit(&active_player_id) > 0
+                && (!self.pending_choices.is_empty()
+                    || self.request_hand_limit_discard(&active_player_id).is_ok())
+            {
+                self.current_phase = TurnPhases::EndStep;
+                self.turn_ending = true;
+                return;
+            }
+            self.turn_ending = false;
+            self.board.expire_events();
+            self.turn_order.advance_turn();
+            self.begin_turn();
         } else {
-            new_state.waiting_for_priority = true;
-            new_state.players_passed_priority.clear();
-            new_state.current_priority_player = new_state.turn_order.active_player_id.clone();
+            self.waiting_for_priority = true;
+            self.players_passed_priority.clear();
+            self.current_priority_player = self.turn_order.active_player_id.clone();
+            match new_phase {
+                TurnPhases::LootStep => self.fire_triggers(GameMoment::LootStep {
+                    player_id: active_player_id,
+                }),
+                TurnPhases::EndStep => self.fire_triggers(GameMoment::EndStep {
+                    player_id: active_player_id,
+                }),
+                _ => {}
+            }
         }
 
         println!(
             "🔄 New state - Phase: {:?}, Priority player: {}, Waiting: {}, Passed: {:?}",
-            new_state.current_phase,
-            new_state.current_priority_player,
-            new_state.waiting_for_priority,
-            new_state.players_passed_priority
+            self.current_phase,
+            self.current_priority_player,
+            self.waiting_for_priority,
+            self.players_passed_priority
         );
+    }
+}
+
+/// What a death changes before its penalty can still be refused, for putting it back if it is
+struct DeathUndo {
+    player: Player,
+    hand: Option<Vec<LootCard>>,
+    items: Option<Vec<Item>>,
+    loot_discard: usize,
+    treasure_discard: usize,
+    pending_choices: usize,
+    next_choice_id: u64,
+}
 
-        new_state
+impl DeathUndo {
+    fn take(state: &GameState, player_id: &str) -> Option<Self> {
+        let board = &state.board;
+        Some(Self {
+            player: board.players.get(player_id)?.clone(),
+            hand: board.players_hands.get(player_id).cloned(),
+            items: board.players_items.get(player_id).cloned(),
+            loot_discard: board.loot_discard.len(),
+            treasure_discard: board.treasure_discard.len(),
+            pending_choices: state.pending_choices.len(),
+            next_choice_id: state.next_choice_id,
+        })
+    }
+
+    fn put_back(self, state: &mut GameState, player_id: &str) {
+        let board = &mut state.board;
+        board.players.insert(player_id.to_string(), self.player);
+        if let Some(hand) = self.hand {
+            board.players_hands.insert(player_id.to_string(), hand);
+        }
+        if let Some(items) = self.items {
+            board.players_items.insert(player_id.to_string(), items);
+        }
+        board.loot_discard.truncate(self.loot_discard);
+        board.treasure_discard.truncate(self.treasure_discard);
+        state.pending_choices.truncate(self.pending_choices);
+        state.next_choice_id = self.next_choice_id;
     }
 }
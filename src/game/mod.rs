@@ -1,7 +1,25 @@
 pub mod board;
+pub mod bot_strategy;
 pub mod card_loader;
 pub mod cards_types;
+pub mod choices;
+pub mod deadlines;
+pub mod deck;
+pub mod domain_events;
+pub mod draw_offer;
+pub mod engine;
+pub mod event_log;
 pub mod game_coordinator;
 pub mod game_state;
+pub mod outbox;
+#[cfg(feature = "server")]
+pub mod persistence;
+pub mod ratings;
+pub mod replay;
+pub mod rules;
 pub mod state_broadcaster;
+pub mod stats;
+pub mod trades;
+pub mod triggers;
 pub mod turn_order;
+pub mod undo;
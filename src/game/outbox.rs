@@ -0,0 +1,84 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::network::messages::SharedMessage;
+
+/// Where a game's messages go. The server's `Messenger` hands them to the players'
+/// connections; `OutboxQueue` keeps them for whoever runs the engine on its own.
+pub trait Outbox: Send + Sync {
+    /// Address `player_id` at `connection_id` from now on
+    fn bind_player(&self, player_id: &str, connection_id: &str);
+
+    fn deliver_to_player(&self, player_id: &str, message: SharedMessage);
+
+    /// One message to every listed player that can be reached
+    fn deliver_to_players(&self, player_ids: &[String], message: SharedMessage);
+
+    /// For audiences without a seat, such as spectators
+    fn deliver_to_connections(&self, connections_id: Vec<String>, message: SharedMessage);
+
+    /// The same recipients, with everything held back by `delay` and sent in order
+    fn delayed(&self, delay: Duration) -> Box<dyn Outbox>;
+}
+
+/// A message the server would have sent, addressed by player id
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboundEvent {
+    pub recipients: Vec<String>,
+    pub message: String, // a serialized ServerResponse, exactly as it goes over the wire
+}
+
+impl OutboundEvent {
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.message).unwrap_or_default()
+    }
+}
+
+/// Collects everything sent, recipients being player ids as given. Clones share the queue.
+#[derive(Debug, Clone, Default)]
+pub struct OutboxQueue {
+    sent: Arc<Mutex<Vec<OutboundEvent>>>,
+}
+
+impl OutboxQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Messages sent since the last `take`, oldest first
+    pub fn take(&self) -> Vec<OutboundEvent> {
+        std::mem::take(&mut *self.sent.lock().expect("Outbox lock poisoned"))
+    }
+
+    fn push(&self, recipients: Vec<String>, message: SharedMessage) {
+        self.sent
+            .lock()
+            .expect("Outbox lock poisoned")
+            .push(OutboundEvent {
+                recipients,
+                message: message.to_string(),
+            });
+    }
+}
+
+impl Outbox for OutboxQueue {
+    // Players are their own recipients, there are no connections to bind them to
+    fn bind_player(&self, _player_id: &str, _connection_id: &str) {}
+
+    fn deliver_to_player(&self, player_id: &str, message: SharedMessage) {
+        self.push(vec![player_id.to_string()], message);
+    }
+
+    fn deliver_to_players(&self, player_ids: &[String], message: SharedMessage) {
+        self.push(player_ids.to_vec(), message);
+    }
+
+    fn deliver_to_connections(&self, connections_id: Vec<String>, message: SharedMessage) {
+        self.push(connections_id, message);
+    }
+
+    // Nothing runs the clock, so nothing is held back
+    fn delayed(&self, _delay: Duration) -> Box<dyn Outbox> {
+        Box::new(self.clone())
+    }
+}
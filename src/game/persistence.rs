@@ -0,0 +1,312 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::game::domain_events::{EventStream, RecordedEvent};
+use crate::game::event_log::{EventLog, LogProgress};
+use crate::game::game_coordinator::GameEvent;
+use crate::game::game_state::GameState;
+use crate::game::replay::Replay;
+use crate::game::rules::GameSpeed;
+use crate::game::stats::GameStats;
+use crate::network::profiles::PlayerProfile;
+use crate::{AppError, AppResult};
+
+/// A running game as written to storage: enough to rebuild its actor after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub game_id: String,
+    pub game_state: GameState,
+    pub replay: Replay,
+    pub sessions: HashMap<String, String>, // session token -> player_id
+    #[serde(default)]
+    pub rated_players: HashMap<String, PlayerProfile>, // player_id -> profile
+    #[serde(default)]
+    pub stats: GameStats,
+    #[serde(default)]
+    pub event_log: EventLog,
+    #[serde(default)]
+    pub events: EventStream,
+    #[serde(default)]
+    pub speed: GameSpeed,
+    #[serde(default)]
+    pub spectator_delay_secs: u64,
+    #[serde(default)]
+    pub checkpoint: u64, // bumped on every full save, progress from an older one is stale
+}
+
+impl SavedGame {
+    /// Add progress appended after this checkpoint. The game state is left as it was saved;
+    /// restoring rebuilds it from the events.
+    pub fn catch_up(&mut self, progress: GameProgress) {
+        if progress.checkpoint != self.checkpoint
+            || progress.replay_from != self.replay.events.len()
+            || progress.signatures_from != self.replay.signatures.len()
+        {
+            return;
+        }
+        self.events.extend(progress.events);
+        self.replay.events.extend(progress.replay_events);
+        self.replay.signatures.extend(progress.signatures);
+        self.stats = progress.stats;
+        self.event_log.extend(progress.log);
+    }
+}
+
+/// What a game added since its last save, appended to it instead of writing the game out again
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameProgress {
+    pub checkpoint: u64,
+    pub events: Vec<RecordedEvent>,
+    pub replay_from: usize, // how many replay events the save already had
+    pub replay_events: Vec<GameEvent>,
+    pub signatures_from: usize,
+    pub signatures: Vec<String>,
+    pub stats: GameStats,
+    pub log: LogProgress,
+}
+
+impl GameProgress {
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+            && self.replay_events.is_empty()
+            && self.signatures.is_empty()
+            && self.log.is_empty()
+    }
+}
+
+/// Where running games are kept between restarts. `save` writes a checkpoint of the whole
+/// game, `append` adds progress to the latest one and `load_all` returns games caught up.
+pub trait GameStore: Send + Sync {
+    fn save(&self, game: &SavedGame) -> AppResult<()>;
+    fn append(&self, game_id: &str, progress: &GameProgress) -> AppResult<()>;
+    fn remove(&self, game_id: &str) -> AppResult<()>;
+    fn load_all(&self) -> AppResult<Vec<SavedGame>>;
+}
+
+/// One JSON file per game in a directory, next to a journal of JSON lines with the progress
+/// made since it was written
+pub struct FileGameStore {
+    dir: PathBuf,
+}
+
+impl FileGameStore {
+    pub fn new(dir: impl Into<PathBuf>) -> AppResult<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| storage_error(&dir, e))?;
+        Ok(Self { dir })
+    }
+
+    fn path_for(&self, game_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", game_id))
+    }
+
+    fn journal_path_for(&self, game_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.journal", game_id))
+    }
+
+    fn catch_up(&self, game: &mut SavedGame) {
+        let Ok(journal) = fs::read_to_string(self.journal_path_for(&game.game_id)) else {
+            return;
+        };
+        // A crash mid-append leaves the last line cut short, everything before it still counts
+        for line in journal.lines() {
+            match serde_json::from_str(line) {
+                Ok(progress) => game.catch_up(progress),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl GameStore for FileGameStore {
+    fn save(&self, game: &SavedGame) -> AppResult<()> {
+        let serialized = serde_json::to_string(game).map_err(|e| AppError::SerializationError {
+            message: e.to_string(),
+        })?;
+        // Write then rename so a crash mid-write never leaves a truncated save behind
+        let path = self.path_for(&game.game_id);
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, serialized).map_err(|e| storage_error(&temp_path, e))?;
+        fs::rename(&temp_path, &path).map_err(|e| storage_error(&path, e))?;
+        // Progress left over from the previous checkpoint is skipped on load if this fails
+        remove_if_present(&self.journal_path_for(&game.game_id))
+    }
+
+    fn append(&self, game_id: &str, progress: &GameProgress) -> AppResult<()> {
+        let mut line =
+            serde_json::to_string(progress).map_err(|e| AppError::SerializationError {
+                message: e.to_string(),
+            })?;
+        line.push('\n');
+        let path = self.journal_path_for(game_id);
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut journal| journal.write_all(line.as_bytes()))
+            .map_err(|e| storage_error(&path, e))
+    }
+
+    fn remove(&self, game_id: &str) -> AppResult<()> {
+        remove_if_present(&self.path_for(game_id))?;
+        remove_if_present(&self.journal_path_for(game_id))
+    }
+
+    fn load_all(&self) -> AppResult<Vec<SavedGame>> {
+        let entries = fs::read_dir(&self.dir).map_err(|e| storage_error(&self.dir, e))?;
+        let mut games = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            // A single unreadable save shouldn't keep the other games from coming back
+            match fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| serde_json::from_str(&contents).map_err(|e| e.to_string()))
+            {
+                Ok(mut game) => {
+                    self.catch_up(&mut game);
+                    games.push(game);
+                }
+                Err(e) => eprintln!("❌ Skipping saved game {}: {}", path.display(), e),
+            }
+        }
+        Ok(games)
+    }
+}
+
+/// Keeps games for the lifetime of the process only, e.g. for embedding and tests
+#[derive(Default)]
+pub struct MemoryGameStore {
+    games: Mutex<HashMap<String, SavedGame>>,
+}
+
+impl MemoryGameStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl GameStore for MemoryGameStore {
+    fn save(&self, game: &SavedGame) -> AppResult<()> {
+        self.games
+            .lock()
+            .expect("Game store lock poisoned")
+            .insert(game.game_id.clone(), game.clone());
+        Ok(())
+    }
+
+    fn append(&self, game_id: &str, progress: &GameProgress) -> AppResult<()> {
+        if let Some(game) = self
+            .games
+            .lock()
+            .expect("Game store lock poisoned")
+            .get_mut(game_id)
+        {
+            game.catch_up(progress.clone());
+        }
+        Ok(())
+    }
+
+    fn remove(&self, game_id: &str) -> AppResult<()> {
+        self.games
+            .lock()
+            .expect("Game store lock poisoned")
+            .remove(game_id);
+        Ok(())
+    }
+
+    fn load_all(&self) -> AppResult<Vec<SavedGame>> {
+        Ok(self
+            .games
+            .lock()
+            .expect("Game store lock poisoned")
+            .values()
+            .cloned()
+            .collect())
+    }
+}
+
+/// Latest state of a running game plus who is seated where, used to restart its actor in
+/// place after a crash
+#[derive(Debug, Clone)]
+pub struct GameSnapshot {
+    pub saved_game: SavedGame,
+    pub players_id_to_connection_id: HashMap<String, String>,
+}
+
+/// In-memory snapshots of every running game, kept whether or not a GameStore is configured
+#[derive(Default)]
+pub struct GameSnapshots {
+    games: Mutex<HashMap<String, GameSnapshot>>,
+}
+
+impl GameSnapshots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn save(&self, snapshot: GameSnapshot) {
+        self.games
+            .lock()
+            .expect("Game snapshots lock poisoned")
+            .insert(snapshot.saved_game.game_id.clone(), snapshot);
+    }
+
+    /// Catch the game's snapshot up with what it did since
+    pub fn append(&self, game_id: &str, progress: GameProgress) {
+        if let Some(snapshot) = self
+            .games
+            .lock()
+            .expect("Game snapshots lock poisoned")
+            .get_mut(game_id)
+        {
+            snapshot.saved_game.catch_up(progress);
+        }
+    }
+
+    /// Point the game's snapshot at the connections its players are on now
+    pub fn reseat(&self, game_id: &str, players_id_to_connection_id: HashMap<String, String>) {
+        if let Some(snapshot) = self
+            .games
+            .lock()
+            .expect("Game snapshots lock poisoned")
+            .get_mut(game_id)
+        {
+            snapshot.players_id_to_connection_id = players_id_to_connection_id;
+        }
+    }
+
+    pub fn get(&self, game_id: &str) -> Option<GameSnapshot> {
+        self.games
+            .lock()
+            .expect("Game snapshots lock poisoned")
+            .get(game_id)
+            .cloned()
+    }
+
+    pub fn remove(&self, game_id: &str) {
+        self.games
+            .lock()
+            .expect("Game snapshots lock poisoned")
+            .remove(game_id);
+    }
+}
+
+fn remove_if_present(path: &std::path::Path) -> AppResult<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(storage_error(path, e)),
+        _ => Ok(()),
+    }
+}
+
+fn storage_error(path: &std::path::Path, error: std::io::Error) -> AppError {
+    AppError::Internal {
+        message: format!("Game storage {}: {}", path.display(), error),
+    }
+}
@@ -0,0 +1,230 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::network::profiles::PlayerProfile;
+use crate::{AppError, AppResult};
+
+pub const DEFAULT_RATING: f64 = 1500.0;
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PlayerRating {
+    pub profile_id: String,
+    pub display_name: String,
+    pub rating: f64,
+    pub games_played: u32,
+    pub wins: u32,
+}
+
+impl PlayerRating {
+    fn new(profile_id: String, display_name: String) -> Self {
+        Self {
+            profile_id,
+            display_name,
+            rating: DEFAULT_RATING,
+            games_played: 0,
+            wins: 0,
+        }
+    }
+}
+
+/// Where ratings live between games and restarts
+pub trait RatingStore: Send + Sync {
+    fn get(&self, profile_id: &str) -> AppResult<Option<PlayerRating>>;
+    fn save(&self, ratings: &[PlayerRating]) -> AppResult<()>;
+    fn top(&self, limit: usize) -> AppResult<Vec<PlayerRating>>;
+}
+
+#[derive(Default)]
+pub struct MemoryRatingStore {
+    ratings: Mutex<HashMap<String, PlayerRating>>, // profile_id -> rating
+}
+
+impl MemoryRatingStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RatingStore for MemoryRatingStore {
+    fn get(&self, profile_id: &str) -> AppResult<Option<PlayerRating>> {
+        Ok(self
+            .ratings
+            .lock()
+            .expect("Rating store lock poisoned")
+            .get(profile_id)
+            .cloned())
+    }
+
+    fn save(&self, ratings: &[PlayerRating]) -> AppResult<()> {
+        let mut stored = self.ratings.lock().expect("Rating store lock poisoned");
+        for rating in ratings {
+            stored.insert(rating.profile_id.clone(), rating.clone());
+        }
+        Ok(())
+    }
+
+    fn top(&self, limit: usize) -> AppResult<Vec<PlayerRating>> {
+        let stored = self.ratings.lock().expect("Rating store lock poisoned");
+        Ok(sorted_by_rating(stored.values().cloned().collect(), limit))
+    }
+}
+
+/// All ratings in a single JSON file, rewritten after every rated game
+pub struct FileRatingStore {
+    path: PathBuf,
+    ratings: Mutex<HashMap<String, PlayerRating>>,
+}
+
+impl FileRatingStore {
+    pub fn new(path: impl Into<PathBuf>) -> AppResult<Self> {
+        let path = path.into();
+        let ratings = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| AppError::Internal {
+                message: format!("Rating store {}: {}", path.display(), e),
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(AppError::Internal {
+                    message: format!("Rating store {}: {}", path.display(), e),
+                })
+            }
+        };
+        Ok(Self {
+            path,
+            ratings: Mutex::new(ratings),
+        })
+    }
+}
+
+impl RatingStore for FileRatingStore {
+    fn get(&self, profile_id: &str) -> AppResult<Option<PlayerRating>> {
+        Ok(self
+            .ratings
+            .lock()
+            .expect("Rating store lock poisoned")
+            .get(profile_id)
+            .cloned())
+    }
+
+    fn save(&self, ratings: &[PlayerRating]) -> AppResult<()> {
+        let mut stored = self.ratings.lock().expect("Rating store lock poisoned");
+        for rating in ratings {
+            stored.insert(rating.profile_id.clone(), rating.clone());
+        }
+        let serialized =
+            serde_json::to_string_pretty(&*stored).map_err(|e| AppError::SerializationError {
+                message: e.to_string(),
+            })?;
+        fs::write(&self.path, serialized).map_err(|e| AppError::Internal {
+            message: format!("Rating store {}: {}", self.path.display(), e),
+        })
+    }
+
+    fn top(&self, limit: usize) -> AppResult<Vec<PlayerRating>> {
+        let stored = self.ratings.lock().expect("Rating store lock poisoned");
+        Ok(sorted_by_rating(stored.values().cloned().collect(), limit))
+    }
+}
+
+fn sorted_by_rating(mut ratings: Vec<PlayerRating>, limit: usize) -> Vec<PlayerRating> {
+    ratings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+    ratings.truncate(limit);
+    ratings
+}
+
+/// Elo ratings keyed by profile id. Only players with a profile are rated.
+pub struct Ratings {
+    store: Box<dyn RatingStore>,
+    update_lock: Mutex<()>, // games can end concurrently; keep read-modify-write atomic
+}
+
+impl Ratings {
+    pub fn new(store: Box<dyn RatingStore>) -> Self {
+        Self {
+            store,
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    /// A game is scored as the winner beating every other seat. Without a winner it was drawn:
+    /// the seats still in it draw with each other and beat whoever conceded. Seats without a
+    /// profile (guests, bots) count as a default-rated opponent but are not stored.
+    pub fn record_game(
+        &self,
+        winner_id: Option<&str>,
+        seated: &[String],
+        conceded: &[String],
+        rated_players: &HashMap<String, PlayerProfile>, // player_id -> profile
+    ) -> AppResult<()> {
+        if rated_players.is_empty() {
+            return Ok(());
+        }
+        let _guard = self.update_lock.lock().expect("Rating lock poisoned");
+
+        let mut current: HashMap<String, PlayerRating> = HashMap::new();
+        for (player_id, profile) in rated_players {
+            let mut rating = self.store.get(&profile.profile_id)?.unwrap_or_else(|| {
+                PlayerRating::new(profile.profile_id.clone(), profile.display_name.clone())
+            });
+            // Keep the latest name so the leaderboard follows profile renames
+            rating.display_name = profile.display_name.clone();
+            current.insert(player_id.clone(), rating);
+        }
+        let rating_of = |player_id: &str| {
+            current
+                .get(player_id)
+                .map_or(DEFAULT_RATING, |rating| rating.rating)
+        };
+        // 2 for the winner, 1 for a seat that drew, 0 for a loss
+        let placing = |player_id: &str| match winner_id {
+            Some(winner_id) if winner_id == player_id => 2,
+            None if seated.iter().any(|id| id == player_id) => 1,
+            _ => 0,
+        };
+
+        let seats: Vec<&String> = seated.iter().chain(conceded).collect();
+        let mut changes: HashMap<String, f64> = HashMap::new();
+        for (i, player_id) in seats.iter().enumerate() {
+            for opponent_id in &seats[i + 1..] {
+                let (placing, opponent_placing) = (placing(player_id), placing(opponent_id));
+                // Two losers didn't play each other for the result
+                if placing == 0 && opponent_placing == 0 {
+                    continue;
+                }
+                let score = match placing.cmp(&opponent_placing) {
+                    std::cmp::Ordering::Greater => 1.0,
+                    std::cmp::Ordering::Equal => 0.5,
+                    std::cmp::Ordering::Less => 0.0,
+                };
+                let change = K_FACTOR
+                    * (score - expected_score(rating_of(player_id), rating_of(opponent_id)));
+                *changes.entry(player_id.to_string()).or_default() += change;
+                *changes.entry(opponent_id.to_string()).or_default() -= change;
+            }
+        }
+
+        let mut updated = Vec::new();
+        for (player_id, mut rating) in current {
+            rating.rating += changes.get(&player_id).copied().unwrap_or_default();
+            rating.games_played += 1;
+            if winner_id == Some(player_id.as_str()) {
+                rating.wins += 1;
+            }
+            updated.push(rating);
+        }
+        self.store.save(&updated)
+    }
+
+    pub fn leaderboard(&self, limit: usize) -> AppResult<Vec<PlayerRating>> {
+        self.store.top(limit)
+    }
+}
+
+fn expected_score(rating: f64, opponent_rating: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_rating - rating) / 400.0))
+}
@@ -0,0 +1,313 @@
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::game::card_loader::BASE_SET;
+use crate::game::game_coordinator::{GameCoordinator, GameEvent};
+use crate::game::game_state::GameState;
+use crate::game::outbox::OutboxQueue;
+use crate::game::rules::RuleSet;
+use crate::{AppError, AppResult, TurnOrder};
+
+// Keeps these MACs from being mistaken for any other use of the same key
+const CHAIN_CONTEXT: &[u8] = b"isaac-four-souls replay chain v1";
+
+/// Secret the server signs replays with, read from IFS_REPLAY_KEY once at startup
+#[derive(Debug, Clone)]
+pub struct ReplayKey(hmac::Key);
+
+impl ReplayKey {
+    pub fn new(secret: &[u8]) -> Self {
+        Self(hmac::Key::new(hmac::HMAC_SHA256, secret))
+    }
+
+    /// None when IFS_REPLAY_KEY is unset or empty: replays are then recorded unsigned
+    pub fn from_env() -> Option<Self> {
+        std::env::var("IFS_REPLAY_KEY")
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| Self::new(secret.as_bytes()))
+    }
+
+    // HMAC-SHA256 over the previous link and the entry, hex encoded
+    fn link(&self, previous: &str, entry: &impl Serialize) -> String {
+        let mut context = hmac::Context::with_key(&self.0);
+        context.update(CHAIN_CONTEXT);
+        context.update(previous.as_bytes());
+        context.update(canonical_json(entry).as_bytes());
+        context
+            .sign()
+            .as_ref()
+            .iter()
+            .fold(String::new(), |mut hex, byte| {
+                let _ = write!(hex, "{:02x}", byte);
+                hex
+            })
+    }
+}
+
+// Through Value, whose maps are sorted, so the bytes don't depend on field or insertion order
+fn canonical_json(entry: &impl Serialize) -> String {
+    serde_json::to_value(entry)
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+/// What the first link of the chain covers, so the seed and options can't be swapped either
+#[derive(Serialize)]
+struct ReplayHeader<'a> {
+    seed: u64,
+    turn_order: &'a [String],
+    mulligan: bool,
+    coin_cap: Option<u32>,
+    undo: bool,
+    card_sets: &'a [String],
+    rules: &'a RuleSet,
+}
+
+/// Everything needed to rebuild a game: the rng seed, the starting turn order and
+/// every accepted event in the order it was applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub turn_order: Vec<String>,
+    #[serde(default)]
+    pub mulligan: bool, // the game opened with a mulligan phase
+    #[serde(default)]
+    pub coin_cap: Option<u32>,
+    #[serde(default)]
+    pub undo: bool, // the room let players take back actions, undone events aren't recorded
+    #[serde(default = "base_sets")]
+    pub card_sets: Vec<String>, // the decks were built from these sets
+    #[serde(default)]
+    pub rules: RuleSet,
+    pub events: Vec<GameEvent>,
+    // With a signing key: an HMAC chain, the header's link first and then one per event,
+    // each covering the link before it. Empty for unsigned replays.
+    #[serde(default)]
+    pub signatures: Vec<String>,
+    #[serde(skip)]
+    signing_key: Option<ReplayKey>,
+}
+
+// Replays recorded before expansions only had the base set
+fn base_sets() -> Vec<String> {
+    vec![BASE_SET.to_string()]
+}
+
+impl Replay {
+    /// An unsigned replay, see `sign_with`
+    pub fn new(seed: u64, turn_order: Vec<String>, card_sets: Vec<String>) -> Self {
+        Self {
+            seed,
+            turn_order,
+            card_sets,
+            rules: RuleSet::default(),
+            mulligan: false,
+            coin_cap: None,
+            undo: false,
+            events: Vec::new(),
+            signatures: Vec::new(),
+            signing_key: None,
+        }
+    }
+
+    /// The game as it stood before its first event
+    pub fn initial_state(&self) -> GameState {
+        let mut state = GameState::new(
+            self.turn_order.clone(),
+            TurnOrder::from_order(self.turn_order.clone()),
+            self.seed,
+            &self.card_sets,
+            &self.rules,
+        );
+        if self.mulligan {
+            state.pending_mulligans = self.turn_order.iter().cloned().collect();
+        }
+        state.board.coin_cap = self.coin_cap;
+        state
+    }
+
+    /// Sign with `key` from now on, re-signing what was recorded so far
+    pub fn sign_with(&mut self, key: ReplayKey) {
+        self.signing_key = Some(key);
+        self.signatures.clear();
+        for index in 0..self.events.len() {
+            self.sign_event(index);
+        }
+    }
+
+    /// Go on signing with `key` after a restart, extending the chain the replay was saved
+    /// with. That chain has to verify first, re-signing it would vouch for whatever the save
+    /// was changed to. A replay saved unsigned with events in it stays unsigned.
+    pub fn resume_signing(&mut self, key: ReplayKey) -> AppResult<()> {
+        if self.signatures.is_empty() {
+            if self.events.is_empty() {
+                self.signing_key = Some(key);
+            }
+            return Ok(());
+        }
+        self.verify(&key)?;
+        self.signing_key = Some(key);
+        Ok(())
+    }
+
+    pub fn is_signing(&self) -> bool {
+        self.signing_key.is_some()
+    }
+
+    pub fn record(&mut self, event: GameEvent) {
+        self.events.push(event);
+        self.sign_event(self.events.len() - 1);
+    }
+
+    /// Drop the events from `len` on, e.g. when an action is taken back
+    pub fn truncate(&mut self, len: usize) {
+        self.events.truncate(len);
+        if !self.signatures.is_empty() {
+            self.signatures.truncate(len + 1);
+        }
+    }
+
+    // The header is only covered once the first event comes in, options are set until then
+    fn sign_event(&mut self, index: usize) {
+        let Some(key) = &self.signing_key else {
+            return;
+        };
+        if self.signatures.is_empty() {
+            self.signatures.push(key.link("", &self.header()));
+        }
+        let link = key.link(&self.signatures[index], &self.events[index]);
+        self.signatures.push(link);
+    }
+
+    fn header(&self) -> ReplayHeader<'_> {
+        ReplayHeader {
+            seed: self.seed,
+            turn_order: &self.turn_order,
+            mulligan: self.mulligan,
+            coin_cap: self.coin_cap,
+            undo: self.undo,
+            card_sets: &self.card_sets,
+            rules: &self.rules,
+        }
+    }
+
+    /// Check the whole chain against `key`: any event changed, added, removed or reordered,
+    /// or a changed header, is reported along with where the chain first breaks
+    pub fn verify(&self, key: &ReplayKey) -> AppResult<()> {
+        let tampered = |reason: String| Err(AppError::ReplayTampered { reason });
+        if self.signatures.is_empty() {
+            return tampered("the replay isn't signed".to_string());
+        }
+        if self.signatures.len() != self.events.len() + 1 {
+            return tampered(format!(
+                "{} events but {} signatures",
+                self.events.len(),
+                self.signatures.len() - 1
+            ));
+        }
+        if key.link("", &self.header()) != self.signatures[0] {
+            return tampered("the header doesn't match its signature".to_string());
+        }
+        for (index, event) in self.events.iter().enumerate() {
+            if key.link(&self.signatures[index], event) != self.signatures[index + 1] {
+                return tampered(format!("event {} doesn't match its signature", index));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> AppResult<()> {
+        let serialized =
+            serde_json::to_string_pretty(self).map_err(|e| AppError::SerializationError {
+                message: e.to_string(),
+            })?;
+        fs::write(path, serialized).map_err(|e| AppError::Internal {
+            message: format!("Failed to write replay: {}", e),
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> AppResult<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| AppError::Internal {
+            message: format!("Failed to read replay: {}", e),
+        })?;
+        serde_json::from_str(&contents).map_err(|e| AppError::SerializationError {
+            message: e.to_string(),
+        })
+    }
+}
+
+/// Drives a fresh, headless GameCoordinator through a recorded replay
+pub struct ReplayPlayer {
+    events: Vec<GameEvent>,
+    cursor: usize,
+    coordinator: GameCoordinator,
+}
+
+impl ReplayPlayer {
+    pub async fn new(replay: Replay) -> Self {
+        // Player ids double as connection ids, nothing is actually delivered
+        let players_id_to_connection_id: HashMap<String, String> = replay
+            .turn_order
+            .iter()
+            .map(|player_id| (player_id.clone(), player_id.clone()))
+            .collect();
+
+        let mut coordinator = GameCoordinator::with_seed(
+            players_id_to_connection_id,
+            TurnOrder::from_order(replay.turn_order),
+            OutboxQueue::new(),
+            replay.seed,
+            &replay.card_sets,
+            &replay.rules,
+        );
+        if replay.mulligan {
+            coordinator.enable_mulligan(0);
+        }
+        if let Some(coin_cap) = replay.coin_cap {
+            coordinator.enable_coin_cap(coin_cap);
+        }
+        coordinator.initialize_game().await;
+
+        Self {
+            events: replay.events,
+            cursor: 0,
+            coordinator,
+        }
+    }
+
+    pub async fn from_file(path: impl AsRef<Path>) -> AppResult<Self> {
+        Ok(Self::new(Replay::load(path)?).await)
+    }
+
+    /// Apply the next recorded event. Returns None once the replay is exhausted; an
+    /// error means the engine no longer accepts what it accepted when recording.
+    pub async fn step(&mut self) -> Option<AppResult<()>> {
+        let event = self.events.get(self.cursor)?.clone();
+        self.cursor += 1;
+        Some(self.coordinator.handle_event(event).await)
+    }
+
+    pub async fn play_to_end(&mut self) -> AppResult<&GameState> {
+        while let Some(result) = self.step().await {
+            result?;
+        }
+        Ok(self.coordinator.game_state())
+    }
+
+    pub fn state(&self) -> &GameState {
+        self.coordinator.game_state()
+    }
+
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.events.len()
+    }
+}
@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::game::card_loader::{self, BASE_SET};
+use crate::{AppError, AppResult};
+
+/// House rules a room can change. The defaults are the printed rules; every check in the
+/// board and coordinator reads from here rather than from constants.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct RuleSet {
+    pub starting_coins: u32,
+    pub starting_hand_size: usize,
+    pub hand_size_limit: Option<usize>, // discarded down to at the end of each turn; None = no limit
+    pub shop_slots: usize,
+    pub shop_price: u32,          // cents for any item in the shop
+    pub loot_plays_per_turn: u32, // on your own turn, the character's tap pays for one more
+    // Carried for shop rerolls, which the engine doesn't have yet
+    pub free_shop_rerolls: u32,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            starting_coins: 3,
+            starting_hand_size: 3,
+            hand_size_limit: Some(10),
+            shop_slots: 2,
+            shop_price: 10,
+            loot_plays_per_turn: 1,
+            free_shop_rerolls: 0,
+        }
+    }
+}
+
+impl RuleSet {
+    const MAX_STARTING_HAND: usize = 5;
+    const MAX_SHOP_SLOTS: usize = 6;
+    const MAX_STARTING_COINS: u32 = 25;
+
+    /// Refuse settings that would break the game before it starts
+    pub fn validate(&self) -> AppResult<()> {
+        let reason = if self.starting_hand_size > Self::MAX_STARTING_HAND {
+            format!(
+                "starting_hand_size can be at most {}",
+                Self::MAX_STARTING_HAND
+            )
+        } else if self.shop_slots > Self::MAX_SHOP_SLOTS {
+            format!("shop_slots can be at most {}", Self::MAX_SHOP_SLOTS)
+        } else if self.starting_coins > Self::MAX_STARTING_COINS {
+            format!("starting_coins can be at most {}", Self::MAX_STARTING_COINS)
+        } else if self.hand_size_limit == Some(0) {
+            "hand_size_limit must allow at least one card".to_string()
+        } else {
+            return Ok(());
+        };
+        Err(AppError::InvalidRuleSet { reason })
+    }
+}
+
+/// What happens when someone joins under a name already seated in the room
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateNames {
+    #[default]
+    Suffix, // the newcomer becomes "Alice (2)", then "Alice (3)"
+    Reject,
+}
+
+/// How long players get to act. Normal is the pace the server is configured with, the other
+/// two are fixed presets.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameSpeed {
+    Blitz,
+    #[default]
+    Normal,
+    Relaxed,
+}
+
+impl GameSpeed {
+    /// The preset's timers, None for Normal
+    pub fn preset(self) -> Option<SpeedTimers> {
+        match self {
+            GameSpeed::Blitz => Some(SpeedTimers {
+                turn_secs: 45,
+                priority_secs: 8,
+                choice_secs: 10,
+            }),
+            GameSpeed::Normal => None,
+            GameSpeed::Relaxed => Some(SpeedTimers {
+                turn_secs: 300,
+                priority_secs: 60,
+                choice_secs: 90,
+            }),
+        }
+    }
+}
+
+/// The deadlines a game speed holds players to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SpeedTimers {
+    pub turn_secs: u64, // 0 = no limit; otherwise the turn is passed for the player
+    pub priority_secs: u64, // 0 = no limit; otherwise priority is passed for the holder
+    pub choice_secs: u64, // unanswered choices take their first option after this
+}
+
+impl SpeedTimers {
+    pub fn turn_timeout(&self) -> Option<Duration> {
+        (self.turn_secs > 0).then(|| Duration::from_secs(self.turn_secs))
+    }
+
+    pub fn priority_timeout(&self) -> Option<Duration> {
+        (self.priority_secs > 0).then(|| Duration::from_secs(self.priority_secs))
+    }
+
+    pub fn choice_timeout(&self) -> Duration {
+        Duration::from_secs(self.choice_secs)
+    }
+}
+
+/// House rules chosen when the room is created, applied to every game played in it
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct RoomOptions {
+    #[serde(default)]
+    pub coin_cap: bool, // nobody can hold more than 25¢
+    #[serde(default)]
+    pub undo: bool, // casual rooms: the active player can take back their last action
+    #[serde(default)]
+    pub card_sets: Vec<String>, // expansions to shuffle in, e.g. ["gold_box"]
+    #[serde(default)]
+    pub rules: RuleSet, // starting coins, hand limit and other house rules
+    #[serde(default)]
+    pub duplicate_names: DuplicateNames,
+    #[serde(default)]
+    pub speed: GameSpeed, // turn, priority and choice timers together
+    #[serde(default)]
+    pub spectator_delay_secs: u64, // streamed games: spectators see everything this much later
+}
+
+impl RoomOptions {
+    pub const COIN_CAP: u32 = 25;
+    pub const MAX_SPECTATOR_DELAY_SECS: u64 = 600;
+
+    pub fn coin_cap(&self) -> Option<u32> {
+        self.coin_cap.then_some(Self::COIN_CAP)
+    }
+
+    /// The sets the decks are built from: the base game plus the chosen expansions, sorted
+    pub fn card_sets(&self) -> Vec<String> {
+        let mut card_sets = self.card_sets.clone();
+        card_sets.push(BASE_SET.to_string());
+        card_sets.sort();
+        card_sets.dedup();
+        card_sets
+    }
+
+    pub fn spectator_delay(&self) -> Duration {
+        Duration::from_secs(self.spectator_delay_secs)
+    }
+
+    /// Every chosen set has to exist in the card database and the house rules must be playable
+    pub fn validate(&self) -> AppResult<()> {
+        self.rules.validate()?;
+        if self.spectator_delay_secs > Self::MAX_SPECTATOR_DELAY_SECS {
+            return Err(AppError::InvalidRuleSet {
+                reason: format!(
+                    "spectator_delay_secs can be at most {}",
+                    Self::MAX_SPECTATOR_DELAY_SECS
+                ),
+            });
+        }
+        let known_sets = card_loader::get_database().card_sets();
+        match self.card_sets.iter().find(|set| !known_sets.contains(set)) {
+            Some(set) => Err(AppError::UnknownCardSet { set: set.clone() }),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,27 +1,73 @@
+use crate::game::cards_types::LootCard;
+use crate::game::deadlines::WindowClock;
 use crate::game::game_state::GameState;
-use crate::network::messages::{serialize_response, ServerResponse};
-use crate::ConnectionCommand;
+use crate::game::outbox::Outbox;
+use crate::game::stats::GameStats;
+use crate::network::messages::{serialize_response, ServerResponse, SharedMessage};
 use std::collections::HashMap;
-use tokio::sync::mpsc;
+use std::time::Duration;
 
 pub struct StateBroadcaster {
-    players_id_to_connection_id: HashMap<String, String>,
-    room_connections_id: Vec<String>,
-    cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+    connected_players: Vec<String>, // restored games only reach players once they resume
+    spectators: Vec<String>,        // connection ids; they only ever see what all players see
+    spectator_delay: Duration,
+    spectator_outbox: Option<Box<dyn Outbox>>, // with a delay, once watched
+    outbox: Box<dyn Outbox>,
 }
 
 impl StateBroadcaster {
     pub fn new(
         players_id_to_connection_id: HashMap<String, String>,
-        cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+        outbox: impl Outbox + 'static,
     ) -> Self {
-        let room_connections_id = players_id_to_connection_id.values().cloned().collect();
+        for (player_id, connection_id) in &players_id_to_connection_id {
+            outbox.bind_player(player_id, connection_id);
+        }
 
         Self {
-            players_id_to_connection_id,
-            room_connections_id,
-            cmd_sender,
+            connected_players: players_id_to_connection_id.into_keys().collect(),
+            spectators: Vec::new(),
+            spectator_delay: Duration::ZERO,
+            spectator_outbox: None,
+            outbox: Box::new(outbox),
+        }
+    }
+
+    /// Hold everything spectators are sent back by `delay`, e.g. for streamed games
+    pub fn set_spectator_delay(&mut self, delay: Duration) {
+        self.spectator_delay = delay;
+    }
+
+    pub fn spectator_delay(&self) -> Duration {
+        self.spectator_delay
+    }
+
+    /// Start sending a connection the public side of the game, beginning with the board as
+    /// it is now (or as it was `spectator_delay` ago, once that has passed)
+    pub fn add_spectator(&mut self, connection_id: String, state: &GameState, clock: &WindowClock) {
+        if self.spectator_outbox.is_none() && !self.spectator_delay.is_zero() {
+            self.spectator_outbox = Some(self.outbox.delayed(self.spectator_delay));
+        }
+        if !self.spectators.contains(&connection_id) {
+            self.spectators.push(connection_id.clone());
         }
+        let watcher = vec![connection_id];
+        self.send_to_spectators(
+            watcher.clone(),
+            serialize_response(Self::public_state(state)),
+        );
+        self.send_to_spectators(
+            watcher,
+            serialize_response(Self::phase_change(state, clock)),
+        );
+    }
+
+    /// Broadcasts already held back still reach them
+    pub fn remove_spectator(&mut self, connection_id: &str) -> bool {
+        let watching = self.spectators.len();
+        self.spectators
+            .retain(|spectator| spectator != connection_id);
+        self.spectators.len() != watching
     }
 
     pub async fn broadcast_full_state(&self, state: &GameState) {
@@ -29,57 +75,114 @@ impl StateBroadcaster {
         self.broadcast_private_states(state).await;
     }
 
+    /// Point a player at a new connection, e.g. after they resume their session
+    pub fn set_player_connection(&mut self, player_id: &str, connection_id: String) {
+        self.outbox.bind_player(player_id, &connection_id);
+        if !self.connected_players.iter().any(|id| id == player_id) {
+            self.connected_players.push(player_id.to_string());
+        }
+    }
+
+    /// Bring a single player up to date without re-sending everyone else's state
+    pub async fn send_full_state_to_player(
+        &self,
+        state: &GameState,
+        clock: &WindowClock,
+        player_id: &str,
+    ) {
+        self.send_to_player(player_id, Self::public_state(state))
+            .await;
+        if let Some(player_hand) = state.board.players_hands.get(player_id) {
+            self.send_to_player(
+                player_id,
+                Self::private_state(state, player_id, player_hand),
+            )
+            .await;
+        }
+        self.send_to_player(player_id, Self::phase_change(state, clock))
+            .await;
+    }
+
+    fn phase_change(state: &GameState, clock: &WindowClock) -> ServerResponse {
+        ServerResponse::TurnPhaseChange {
+            player_id: state.current_priority_player.clone(),
+            phase: state.current_phase.clone(),
+            active_player: state.turn_order.active_player_id.clone(),
+            turn_deadline: clock.turn_deadline(),
+            priority_deadline: clock.priority_deadline(),
+        }
+    }
+
+    fn private_state(state: &GameState, player_id: &str, hand: &[LootCard]) -> ServerResponse {
+        ServerResponse::PrivateBoardState {
+            hand: hand.to_vec(),
+            last_search: state.board.deck_searches.get(player_id).cloned(),
+        }
+    }
+
+    fn public_state(state: &GameState) -> ServerResponse {
+        ServerResponse::PublicBoardState {
+            loot_deck_size: state.board.loot_deck.len(),
+            loot_discard: state.board.loot_discard.clone(),
+            current_phase: state.current_phase.clone(),
+            active_player: state.turn_order.active_player_id.clone(),
+            players: state.public_players(),
+            items: state.board.players_items.clone(),
+            curses: state.board.players_curses.clone(),
+            events: state.board.active_events.clone(),
+            shop: state.board.shop.clone(),
+            shop_price: state.board.rules.shop_price,
+            deck_sizes: state.board.deck_sizes(),
+        }
+    }
+
     async fn broadcast_public_state(&self, state: &GameState) {
-        let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayers {
-            connections_id: self.room_connections_id.clone(),
-            message: serialize_response(ServerResponse::PublicBoardState {
-                loot_deck_size: state.board.loot_deck.len(),
-                loot_discard: state.board.loot_discard.clone(),
-                current_phase: state.current_phase.clone(),
-                active_player: state.turn_order.active_player_id.clone(),
-                players: state.board.players.clone(),
-            }),
-        });
+        self.broadcast(Self::public_state(state)).await;
     }
 
     async fn broadcast_private_states(&self, state: &GameState) {
-        for (player_id, conn_id) in &self.players_id_to_connection_id {
-            let player_hand = state.board.players_hands.get(player_id).cloned();
-            match player_hand {
-                None => {
-                    let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayer {
-                        connection_id: conn_id.clone(),
-                        message: serialize_response(ServerResponse::from_app_error(
-                            &crate::AppError::PlayerNotFound,
-                        )),
-                    });
-                }
-                Some(player_hand) => {
-                    let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayer {
-                        connection_id: conn_id.clone(),
-                        message: serialize_response(ServerResponse::PrivateBoardState {
-                            hand: player_hand,
-                        }),
-                    });
-                }
-            }
+        // Players who conceded keep watching the public board, they hold no cards
+        let seated = self
+            .connected_players
+            .iter()
+            .filter(|player_id| !state.conceded.contains(player_id));
+        for player_id in seated {
+            let response = match state.board.players_hands.get(player_id) {
+                Some(player_hand) => Self::private_state(state, player_id, player_hand),
+                None => ServerResponse::from_app_error(&crate::AppError::PlayerNotFound),
+            };
+            self.send_to_player(player_id, response).await;
+        }
+    }
+
+    /// To every player right away, and to spectators once the room's delay has passed
+    pub async fn broadcast(&self, response: ServerResponse) {
+        let message = serialize_response(response);
+        self.outbox
+            .deliver_to_players(&self.connected_players, message.clone());
+        if !self.spectators.is_empty() {
+            self.send_to_spectators(self.spectators.clone(), message);
         }
     }
 
-    pub async fn broadcast_phase_start(&self, state: &GameState) {
-        let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayers {
-            connections_id: self.room_connections_id.clone(),
-            message: serialize_response(ServerResponse::TurnPhaseChange {
-                player_id: state.current_priority_player.clone(),
-                phase: state.current_phase.clone(),
-            }),
-        });
+    fn send_to_spectators(&self, connections_id: Vec<String>, message: SharedMessage) {
+        self.spectator_outbox
+            .as_ref()
+            .unwrap_or(&self.outbox)
+            .deliver_to_connections(connections_id, message);
+    }
+
+    pub async fn broadcast_phase_start(&self, state: &GameState, clock: &WindowClock) {
+        self.broadcast(Self::phase_change(state, clock)).await;
+    }
+
+    pub async fn send_to_player(&self, player_id: &str, response: ServerResponse) {
+        self.outbox
+            .deliver_to_player(player_id, serialize_response(response));
     }
 
-    pub async fn broadcast_game_ended(&self, winner_id: String) {
-        let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayers {
-            connections_id: self.room_connections_id.clone(),
-            message: serialize_response(ServerResponse::GameEnded { winner_id }),
-        });
+    pub async fn broadcast_game_ended(&self, winner_id: Option<String>, stats: GameStats) {
+        self.broadcast(ServerResponse::GameEnded { winner_id, stats })
+            .await;
     }
 }
@@ -0,0 +1,90 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+// Finished games kept for GetGameSummary, oldest dropped first
+const MAX_KEPT_SUMMARIES: usize = 256;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PlayerStats {
+    pub turns_taken: u32,
+    pub cards_drawn: u32,
+    pub damage_dealt: u32,
+    pub souls_earned: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct GameStats {
+    pub players: HashMap<String, PlayerStats>, // player_id -> stats
+}
+
+impl GameStats {
+    pub fn new(player_ids: &[String]) -> Self {
+        Self {
+            players: player_ids
+                .iter()
+                .map(|player_id| (player_id.clone(), PlayerStats::default()))
+                .collect(),
+        }
+    }
+
+    pub fn record_turn(&mut self, player_id: &str) {
+        self.player(player_id).turns_taken += 1;
+    }
+
+    pub fn record_cards_drawn(&mut self, player_id: &str, count: u32) {
+        self.player(player_id).cards_drawn += count;
+    }
+
+    pub fn record_damage(&mut self, player_id: &str, amount: u32) {
+        self.player(player_id).damage_dealt += amount;
+    }
+
+    pub fn record_soul(&mut self, player_id: &str) {
+        self.player(player_id).souls_earned += 1;
+    }
+
+    fn player(&mut self, player_id: &str) -> &mut PlayerStats {
+        self.players.entry(player_id.to_string()).or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GameSummary {
+    pub game_id: String,
+    pub winner_id: Option<String>, // None for a draw
+    pub turn_order: Vec<String>,
+    pub stats: GameStats,
+}
+
+/// Summaries of recently finished games, in memory only
+#[derive(Default)]
+pub struct GameSummaries {
+    summaries: Mutex<VecDeque<GameSummary>>,
+}
+
+impl GameSummaries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, summary: GameSummary) {
+        let mut summaries = self.summaries.lock().expect("Game summaries lock poisoned");
+        // Room ids are reused as game ids, only the latest game in a room is kept
+        summaries.retain(|kept| kept.game_id != summary.game_id);
+        if summaries.len() == MAX_KEPT_SUMMARIES {
+            summaries.pop_front();
+        }
+        summaries.push_back(summary);
+    }
+
+    pub fn get(&self, game_id: &str) -> Option<GameSummary> {
+        self.summaries
+            .lock()
+            .expect("Game summaries lock poisoned")
+            .iter()
+            .find(|summary| summary.game_id == game_id)
+            .cloned()
+    }
+}
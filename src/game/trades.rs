@@ -0,0 +1,27 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// One side of a deal: coins plus loot cards from hand, by entity id
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct TradeOffer {
+    #[serde(default)]
+    pub coins: u32,
+    #[serde(default)]
+    pub loot: Vec<String>,
+}
+
+impl TradeOffer {
+    pub fn is_empty(&self) -> bool {
+        self.coins == 0 && self.loot.is_empty()
+    }
+}
+
+/// A proposed deal, open until the receiver accepts or either side declines
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Trade {
+    pub trade_id: String,
+    pub from_player: String,
+    pub to_player: String,
+    pub offer: TradeOffer,   // what from_player gives
+    pub request: TradeOffer, // what from_player gets in return
+}
@@ -0,0 +1,158 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::game::board::{Board, DeckKind};
+use crate::game::turn_order::{TurnModifier, TurnOrder};
+
+/// When a triggered ability fires. Every condition is about the item's owner ("your turn",
+/// "you roll", "you die").
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum TriggerCondition {
+    StartOfTurn,
+    LootStep,
+    EndStep,
+    DiceRoll { value: Option<u8> }, // None = any result
+    Death,
+}
+
+/// What a resolved trigger does to the board
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum TriggerEffect {
+    GainCoins { amount: u32 },
+    LoseCoins { amount: u32 },
+    DrawLoot { count: u32 },
+    GainCurse,   // the top curse is attached to the owner
+    RemoveCurse, // the owner's oldest curse is discarded
+    // The owner takes a copy of the card from the deck, which is shuffled after
+    SearchDeck { deck: DeckKind, template_id: String },
+    // Turn order changes wait for the current turn to end
+    SkipNextTurn,
+    ExtraTurn,
+    ReverseTurnOrder,
+}
+
+impl fmt::Display for TriggerEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerEffect::GainCoins { amount } => write!(f, "gained {}¢", amount),
+            TriggerEffect::LoseCoins { amount } => write!(f, "lost {}¢", amount),
+            TriggerEffect::DrawLoot { count } => write!(f, "drew {} loot", count),
+            TriggerEffect::GainCurse => write!(f, "was cursed"),
+            TriggerEffect::RemoveCurse => write!(f, "removed a curse"),
+            TriggerEffect::SearchDeck { deck, .. } => {
+                let deck = match deck {
+                    DeckKind::Loot => "loot",
+                    DeckKind::Treasure => "treasure",
+                    DeckKind::Curse => "curse",
+                    DeckKind::Event => "event",
+                };
+                write!(f, "searched the {} deck", deck)
+            }
+            TriggerEffect::SkipNextTurn => write!(f, "will skip their next turn"),
+            TriggerEffect::ExtraTurn => write!(f, "will take an extra turn"),
+            TriggerEffect::ReverseTurnOrder => write!(f, "reversed the turn order"),
+        }
+    }
+}
+
+/// A listener printed on a card, e.g. `{"on": {"DiceRoll": {"value": 1}}, "effect": {"GainCoins": {"amount": 1}}}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Trigger {
+    pub on: TriggerCondition,
+    pub effect: TriggerEffect,
+}
+
+/// Something that just happened in the game, checked against every trigger in play
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameMoment {
+    TurnStart { player_id: String },
+    LootStep { player_id: String },
+    EndStep { player_id: String },
+    DiceRolled { player_id: String, value: u8 },
+    PlayerDied { player_id: String },
+}
+
+impl GameMoment {
+    pub fn player_id(&self) -> &str {
+        match self {
+            GameMoment::TurnStart { player_id }
+            | GameMoment::LootStep { player_id }
+            | GameMoment::EndStep { player_id }
+            | GameMoment::DiceRolled { player_id, .. }
+            | GameMoment::PlayerDied { player_id } => player_id,
+        }
+    }
+}
+
+impl TriggerCondition {
+    pub fn matches(&self, owner_id: &str, moment: &GameMoment) -> bool {
+        if moment.player_id() != owner_id {
+            return false;
+        }
+        match (self, moment) {
+            (TriggerCondition::StartOfTurn, GameMoment::TurnStart { .. })
+            | (TriggerCondition::LootStep, GameMoment::LootStep { .. })
+            | (TriggerCondition::EndStep, GameMoment::EndStep { .. })
+            | (TriggerCondition::Death, GameMoment::PlayerDied { .. }) => true,
+            (
+                TriggerCondition::DiceRoll { value },
+                GameMoment::DiceRolled { value: rolled, .. },
+            ) => value.is_none_or(|value| value == *rolled),
+            _ => false,
+        }
+    }
+}
+
+/// A triggered effect waiting on the stack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackEntry {
+    pub source_id: String, // entity id of the item whose trigger fired
+    pub source_name: String,
+    pub owner_id: String,
+    pub effect: TriggerEffect,
+}
+
+impl StackEntry {
+    /// Effects that can't happen any more (an empty deck, a player gone) fizzle
+    pub fn resolve(&self, board: &mut Board, turn_order: &mut TurnOrder) {
+        let resolved = match self.effect {
+            TriggerEffect::GainCoins { amount } => {
+                board.gain_coins(&self.owner_id, amount).map(|_| ())
+            }
+            TriggerEffect::LoseCoins { amount } => {
+                board.lose_coins(&self.owner_id, amount).map(|_| ())
+            }
+            TriggerEffect::DrawLoot { count } => {
+                (0..count).try_for_each(|_| board.draw_loot_for_player(&self.owner_id).map(|_| ()))
+            }
+            TriggerEffect::GainCurse => board.attach_curse(&self.owner_id).map(|_| ()),
+            TriggerEffect::RemoveCurse => board.remove_oldest_curse(&self.owner_id).map(|_| ()),
+            TriggerEffect::SearchDeck {
+                deck,
+                ref template_id,
+            } => board
+                .search_deck(deck, &self.owner_id, template_id)
+                .map(|_| ()),
+            TriggerEffect::SkipNextTurn => {
+                turn_order.add_modifier(TurnModifier::SkipTurn {
+                    player_id: self.owner_id.clone(),
+                });
+                Ok(())
+            }
+            TriggerEffect::ExtraTurn => {
+                turn_order.add_modifier(TurnModifier::ExtraTurn {
+                    player_id: self.owner_id.clone(),
+                });
+                Ok(())
+            }
+            TriggerEffect::ReverseTurnOrder => {
+                turn_order.add_modifier(TurnModifier::Reverse);
+                Ok(())
+            }
+        };
+        if let Err(e) = resolved {
+            println!("⚡ {} fizzled: {}", self.source_name, e);
+        }
+    }
+}
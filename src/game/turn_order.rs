@@ -1,25 +1,117 @@
-use rand::rng;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
+use crate::game::rules::RoomOptions;
+
+// The board draws from stream 0 of the same seed
+const TURN_ORDER_RNG_STREAM: u64 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TurnOrder {
     pub order: Vec<String>,
     pub active_player_id: String,
     turn_counter: u32,
+    #[serde(default)]
+    reversed: bool, // play goes backwards through `order`
+    #[serde(default)]
+    modifiers: Vec<TurnModifier>, // oldest first, applied as turns are handed on
+    #[serde(default)]
+    resume_after: Option<String>, // during an extra turn, whose regular turn play continues from
+}
+
+/// A change to the turn order waiting for the current turn to end
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum TurnModifier {
+    SkipTurn { player_id: String },  // their next turn is passed over
+    ExtraTurn { player_id: String }, // they take a turn before play carries on
+    Reverse,                         // play changes direction
+}
+
+/// Everything decided before the first turn
+#[derive(Debug, Clone)]
+pub struct GameOpening {
+    pub seed: u64,
+    pub turn_order: TurnOrder,
+    pub first_player_rolls: Vec<HashMap<String, u8>>, // one d6 per contender each round, empty when not rolled
+    pub options: RoomOptions,
+    pub player_names: HashMap<String, String>, // player_id -> name in the room
+    pub card_set_version: String, // of the cards the game was dealt from, once its board is built
+}
+
+impl GameOpening {
+    /// Seat players in a seed-determined order. With `roll_for_first_player`, everyone
+    /// rolls a die and the highest roll goes first (ties reroll), keeping the seating.
+    pub fn new(player_ids: Vec<String>, seed: u64, roll_for_first_player: bool) -> Self {
+        let mut rng = turn_order_rng(seed);
+        let mut turn_order = TurnOrder::shuffled(player_ids, &mut rng);
+
+        let mut first_player_rolls = Vec::new();
+        if roll_for_first_player {
+            let mut contenders = turn_order.order.clone();
+            while contenders.len() > 1 {
+                let rolls: HashMap<String, u8> = contenders
+                    .iter()
+                    .map(|player_id| (player_id.clone(), rng.random_range(1..=6)))
+                    .collect();
+                let best = rolls.values().copied().max().unwrap_or_default();
+                contenders.retain(|player_id| rolls[player_id] == best);
+                first_player_rolls.push(rolls);
+            }
+            turn_order.start_with(&contenders[0]);
+        }
+
+        Self {
+            seed,
+            turn_order,
+            first_player_rolls,
+            options: RoomOptions::default(),
+            player_names: HashMap::new(),
+            card_set_version: String::new(),
+        }
+    }
+}
+
+fn turn_order_rng(seed: u64) -> ChaCha12Rng {
+    let mut rng = ChaCha12Rng::seed_from_u64(seed);
+    rng.set_stream(TURN_ORDER_RNG_STREAM);
+    rng
 }
 
 impl TurnOrder {
-    pub fn new(player_ids: Vec<String>) -> Self {
-        let mut random_generator = rng();
-        let mut order = player_ids.clone();
-        order.shuffle(&mut random_generator);
+    pub fn new(player_ids: Vec<String>, seed: u64) -> Self {
+        Self::shuffled(player_ids, &mut turn_order_rng(seed))
+    }
 
+    fn shuffled(mut player_ids: Vec<String>, rng: &mut ChaCha12Rng) -> Self {
+        // Callers collect ids from HashMaps; sort so the order only depends on the seed
+        player_ids.sort();
+        player_ids.shuffle(rng);
+        Self::from_order(player_ids)
+    }
+
+    /// Build a turn order without shuffling, e.g. when replaying a recorded game
+    pub fn from_order(order: Vec<String>) -> Self {
         let active_player_id = order[0].clone();
 
         Self {
             order,
             active_player_id,
             turn_counter: 0,
+            reversed: false,
+            modifiers: Vec::new(),
+            resume_after: None,
+        }
+    }
+
+    /// Rotate the seating so `player_id` takes the first turn
+    fn start_with(&mut self, player_id: &str) {
+        if let Some(index) = self.order.iter().position(|id| id == player_id) {
+            self.order.rotate_left(index);
+            self.active_player_id = self.order[0].clone();
         }
     }
 
@@ -31,16 +123,139 @@ impl TurnOrder {
         self.active_player_id == player_id
     }
 
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    pub fn modifiers(&self) -> &[TurnModifier] {
+        &self.modifiers
+    }
+
+    /// Queue a change for when the current turn ends. Modifiers naming someone who isn't
+    /// seated are ignored.
+    pub fn add_modifier(&mut self, modifier: TurnModifier) {
+        let seated = match &modifier {
+            TurnModifier::SkipTurn { player_id } | TurnModifier::ExtraTurn { player_id } => {
+                self.order.contains(player_id)
+            }
+            TurnModifier::Reverse => true,
+        };
+        if seated {
+            self.modifiers.push(modifier);
+        }
+    }
+
+    /// Every seat in the direction of play, starting with the active player
+    pub fn play_order(&self) -> Vec<String> {
+        let mut order = self.order.clone();
+        if self.reversed {
+            order.reverse();
+        }
+        if let Some(index) = order.iter().position(|id| *id == self.active_player_id) {
+            order.rotate_left(index);
+        }
+        order
+    }
+
+    /// Hand the turn on. Reversals apply first, then the oldest extra turn is taken; otherwise
+    /// play moves to the next seat, passing over (and using up) skipped turns.
     pub fn advance_turn(&mut self) -> String {
-        if let Some(current_index) = self
-            .order
+        let reversals = self
+            .modifiers
             .iter()
-            .position(|id| id == &self.active_player_id)
-        {
-            let next_index = (current_index + 1) % self.order.len();
-            self.active_player_id = self.order[next_index].clone();
+            .filter(|modifier| **modifier == TurnModifier::Reverse)
+            .count();
+        self.reversed ^= reversals % 2 == 1;
+        self.modifiers
+            .retain(|modifier| *modifier != TurnModifier::Reverse);
+
+        let extra_turn = self
+            .modifiers
+            .iter()
+            .position(|modifier| matches!(modifier, TurnModifier::ExtraTurn { .. }));
+        if let Some(index) = extra_turn {
+            let TurnModifier::ExtraTurn { player_id } = self.modifiers.remove(index) else {
+                unreachable!("position matched an extra turn");
+            };
+            if self.resume_after.is_none() {
+                self.resume_after = Some(self.active_player_id.clone());
+            }
+            self.active_player_id = player_id;
             self.turn_counter += 1;
+            return self.active_player_id.clone();
         }
+
+        let from = self
+            .resume_after
+            .take()
+            .unwrap_or_else(|| self.active_player_id.clone());
+        let Some(mut index) = self.order.iter().position(|id| *id == from) else {
+            return self.active_player_id.clone();
+        };
+        for _ in 0..=self.modifiers.len() {
+            index = self.next_seat(index);
+            let skip = self.modifiers.iter().position(|modifier| {
+                matches!(modifier, TurnModifier::SkipTurn { player_id } if *player_id == self.order[index])
+            });
+            match skip {
+                Some(skip) => {
+                    self.modifiers.remove(skip);
+                }
+                None => break,
+            }
+        }
+        self.active_player_id = self.order[index].clone();
+        self.turn_counter += 1;
         self.active_player_id.clone()
     }
+
+    /// Take a player out of the game. Turn changes naming them are dropped, and if it was
+    /// their turn it ends as if they had passed it.
+    pub fn remove_player(&mut self, player_id: &str) {
+        let Some(index) = self.order.iter().position(|id| id == player_id) else {
+            return;
+        };
+        self.modifiers.retain(|modifier| match modifier {
+            TurnModifier::SkipTurn { player_id: named }
+            | TurnModifier::ExtraTurn { player_id: named } => named != player_id,
+            TurnModifier::Reverse => true,
+        });
+        // Play was to carry on after them, so it carries on after whoever sat before them
+        if self.resume_after.as_deref() == Some(player_id) {
+            self.resume_after = Some(self.order[self.previous_seat(index)].clone());
+        }
+        let turn_counter = self.turn_counter;
+        if self.active_player_id == player_id && self.order.len() > 1 {
+            self.advance_turn();
+        }
+        self.order.remove(index);
+        // Everyone else was skipped and the turn came back to them: the next seat takes it
+        if self.active_player_id == player_id && !self.order.is_empty() {
+            let next = if self.reversed {
+                (index + self.order.len() - 1) % self.order.len()
+            } else {
+                index % self.order.len()
+            };
+            self.active_player_id = self.order[next].clone();
+            self.turn_counter = turn_counter + 1;
+        }
+    }
+
+    fn previous_seat(&self, index: usize) -> usize {
+        let seats = self.order.len();
+        if self.reversed {
+            (index + 1) % seats
+        } else {
+            (index + seats - 1) % seats
+        }
+    }
+
+    fn next_seat(&self, index: usize) -> usize {
+        let seats = self.order.len();
+        if self.reversed {
+            (index + seats - 1) % seats
+        } else {
+            (index + 1) % seats
+        }
+    }
 }
@@ -0,0 +1,24 @@
+use std::collections::HashSet;
+
+use crate::game::game_state::StateSnapshot;
+use crate::game::stats::GameStats;
+
+/// The game just before the active player's last action, kept for as long as that action can
+/// still be taken back. Anything that drew from the rng or a deck can't be, the player has
+/// already seen what came out.
+#[derive(Debug, Clone)]
+pub struct UndoPoint {
+    pub player_id: String,
+    pub action: String, // log lines the action produced, shown to whoever has to confirm
+    pub snapshot: StateSnapshot,
+    pub replay_len: usize, // the replay is cut back to this so it still rebuilds the game
+    pub events_len: usize, // and the event stream to this
+    pub stats: GameStats,
+}
+
+/// An undo asked for by the active player, waiting on every opponent to agree
+#[derive(Debug, Clone)]
+pub struct UndoRequest {
+    pub player_id: String,
+    pub waiting_on: HashSet<String>,
+}
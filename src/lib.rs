@@ -1,12 +1,23 @@
+#[cfg(feature = "server")]
 pub mod actors;
+#[cfg(feature = "server")]
+pub mod config;
 pub mod errors;
 pub mod game;
 pub mod network;
 
+#[cfg(feature = "server")]
+pub use config::ServerConfig;
 pub use errors::{AppError, AppResult};
+pub use game::engine::{GameEngine, OutboundEvent};
 pub use game::turn_order::TurnOrder;
+#[cfg(feature = "server")]
 pub use network::connection_commands::{CommandProcessor, ConnectionCommand};
+#[cfg(feature = "server")]
 pub use network::connection_handler::ConnectionHandler;
+#[cfg(feature = "server")]
 pub use network::connection_manager::ConnectionManager;
+#[cfg(feature = "server")]
 pub use network::room::Room;
+#[cfg(feature = "server")]
 pub use network::server::WebsocketServer;
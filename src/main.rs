@@ -1,11 +1,32 @@
 use isaac_four_souls::game::card_loader;
-use isaac_four_souls::WebsocketServer;
+use isaac_four_souls::game::replay::ReplayKey;
+use isaac_four_souls::network::auth::AuthMode;
+use isaac_four_souls::network::permissions::AdminKey;
+use isaac_four_souls::{ServerConfig, WebsocketServer};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ServerConfig::load_with_args(std::env::args().skip(1))?;
+    env_logger::Builder::new()
+        .filter_level(config.log_level.into())
+        .init();
+
     card_loader::initialize_database();
+    // Kept alive for the lifetime of the server
+    let _card_watcher = match card_loader::watch_database() {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            eprintln!("❌ Card hot-reload disabled: {}", e);
+            None
+        }
+    };
     println!("🎮 Starting Isaac Four Souls TCP Server...");
-    let server = WebsocketServer::new("127.0.0.1:8080");
+    let server = WebsocketServer::builder()
+        .config(config)
+        .auth(AuthMode::from_env())
+        .admin_key(AdminKey::from_env())
+        .replay_key(ReplayKey::from_env())
+        .build();
     server.run().await?;
     Ok(())
 }
@@ -0,0 +1,137 @@
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::{AppError, AppResult};
+
+const API_KEY_PARAM: &str = "api_key";
+const TOKEN_PARAM: &str = "token";
+
+#[derive(Debug, Clone)]
+pub enum AuthMode {
+    Disabled,
+    ApiKeys(HashSet<String>),
+    Jwt { secret: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthMethod {
+    Anonymous,
+    ApiKey,
+    Jwt,
+}
+
+/// Who opened the connection, as established during the websocket handshake
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub method: AuthMethod,
+    pub subject: Option<String>, // JWT `sub` claim
+    pub admin: bool,             // JWT `admin` claim
+}
+
+impl AuthContext {
+    pub fn anonymous() -> Self {
+        Self {
+            method: AuthMethod::Anonymous,
+            subject: None,
+            admin: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: Option<String>,
+    #[serde(default)]
+    admin: bool,
+}
+
+impl AuthMode {
+    /// IFS_AUTH_JWT_SECRET takes precedence over the comma-separated IFS_AUTH_API_KEYS;
+    /// with neither set every connection is accepted
+    pub fn from_env() -> Self {
+        if let Ok(secret) = std::env::var("IFS_AUTH_JWT_SECRET") {
+            if !secret.is_empty() {
+                return AuthMode::Jwt { secret };
+            }
+        }
+
+        let api_keys: HashSet<String> = std::env::var("IFS_AUTH_API_KEYS")
+            .map(|keys| {
+                keys.split(',')
+                    .map(|key| key.trim().to_string())
+                    .filter(|key| !key.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if api_keys.is_empty() {
+            AuthMode::Disabled
+        } else {
+            AuthMode::ApiKeys(api_keys)
+        }
+    }
+
+    /// How connections are checked, without the keys or secret, for `/info`
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuthMode::Disabled => "disabled",
+            AuthMode::ApiKeys(_) => "api_keys",
+            AuthMode::Jwt { .. } => "jwt",
+        }
+    }
+
+    /// Check the credentials carried in the upgrade request query string
+    pub fn authenticate(&self, query: Option<&str>) -> AppResult<AuthContext> {
+        match self {
+            AuthMode::Disabled => Ok(AuthContext::anonymous()),
+            AuthMode::ApiKeys(keys) => {
+                let key =
+                    query_param(query, API_KEY_PARAM).ok_or_else(|| AppError::Unauthenticated {
+                        reason: format!("missing '{}' query parameter", API_KEY_PARAM),
+                    })?;
+                if !keys.contains(key) {
+                    return Err(AppError::Unauthenticated {
+                        reason: "invalid API key".to_string(),
+                    });
+                }
+                Ok(AuthContext {
+                    method: AuthMethod::ApiKey,
+                    subject: None,
+                    admin: false,
+                })
+            }
+            AuthMode::Jwt { secret } => {
+                let token =
+                    query_param(query, TOKEN_PARAM).ok_or_else(|| AppError::Unauthenticated {
+                        reason: format!("missing '{}' query parameter", TOKEN_PARAM),
+                    })?;
+                // Tokens must carry an `exp`, a leaked one can't be used forever
+                let validation = Validation::new(Algorithm::HS256);
+                let claims = decode::<Claims>(
+                    token,
+                    &DecodingKey::from_secret(secret.as_bytes()),
+                    &validation,
+                )
+                .map_err(|e| AppError::Unauthenticated {
+                    reason: format!("invalid token: {}", e),
+                })?
+                .claims;
+                Ok(AuthContext {
+                    method: AuthMethod::Jwt,
+                    subject: claims.sub,
+                    admin: claims.admin,
+                })
+            }
+        }
+    }
+}
+
+// API keys and JWTs are URL-safe, so no percent-decoding is needed
+fn query_param<'a>(query: Option<&'a str>, name: &str) -> Option<&'a str> {
+    query?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
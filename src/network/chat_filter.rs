@@ -0,0 +1,85 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::config::ChatConfig;
+use crate::{AppError, AppResult};
+
+const MAX_MESSAGE_LENGTH: usize = 500;
+const DEFAULT_RATE_LIMIT: usize = 5; // messages per window
+
+pub struct ChatFilter {
+    banned_words: Vec<String>, // lowercase
+    rate_limit: usize,
+    rate_window: Duration,
+    recent_messages: HashMap<String, VecDeque<Instant>>, // connection_id -> send times
+}
+
+impl ChatFilter {
+    pub fn new(banned_words: Vec<String>, rate_limit: usize, rate_window: Duration) -> Self {
+        Self {
+            banned_words: banned_words
+                .into_iter()
+                .map(|word| word.trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect(),
+            rate_limit,
+            rate_window,
+            recent_messages: HashMap::new(),
+        }
+    }
+
+    /// Banned words come from `chat.banned_words`
+    pub fn from_config(chat: &ChatConfig, rate_window: Duration) -> Self {
+        Self::new(chat.banned_words.clone(), DEFAULT_RATE_LIMIT, rate_window)
+    }
+
+    /// Validate a message and count it against the sender's rate limit
+    pub fn check(&mut self, connection_id: &str, message: &str) -> AppResult<()> {
+        if message.trim().is_empty() {
+            return Err(AppError::ChatRejected {
+                reason: "Message cannot be empty".to_string(),
+            });
+        }
+        if message.chars().count() > MAX_MESSAGE_LENGTH {
+            return Err(AppError::ChatRejected {
+                reason: format!("Message cannot exceed {} characters", MAX_MESSAGE_LENGTH),
+            });
+        }
+        if self.contains_banned_word(message) {
+            return Err(AppError::ChatRejected {
+                reason: "Message contains a banned word".to_string(),
+            });
+        }
+
+        let now = Instant::now();
+        let sent = self
+            .recent_messages
+            .entry(connection_id.to_string())
+            .or_default();
+        while sent
+            .front()
+            .is_some_and(|sent_at| now.duration_since(*sent_at) > self.rate_window)
+        {
+            sent.pop_front();
+        }
+        if sent.len() >= self.rate_limit {
+            return Err(AppError::ChatRejected {
+                reason: "You are sending messages too quickly".to_string(),
+            });
+        }
+        sent.push_back(now);
+
+        Ok(())
+    }
+
+    pub fn forget_connection(&mut self, connection_id: &str) {
+        self.recent_messages.remove(connection_id);
+    }
+
+    fn contains_banned_word(&self, message: &str) -> bool {
+        message
+            .split(|c: char| !c.is_alphanumeric())
+            .map(str::to_lowercase)
+            .any(|word| self.banned_words.contains(&word))
+    }
+}
@@ -0,0 +1,56 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+// Per room, the oldest messages are dropped first
+pub const CHAT_HISTORY_SIZE: usize = 50;
+
+/// A room chat message as it was sent
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ChatEntry {
+    pub player_id: String,
+    pub player_name: String,
+    pub message: String,
+    pub timestamp: u64, // unix millis
+}
+
+impl ChatEntry {
+    pub fn now(player_id: String, player_name: String, message: String) -> Self {
+        Self {
+            player_id,
+            player_name,
+            message,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64),
+        }
+    }
+}
+
+/// The last messages of every room, replayed to players as they join or rejoin
+#[derive(Default)]
+pub struct ChatHistory {
+    rooms: HashMap<String, VecDeque<ChatEntry>>, // room_id -> messages, oldest first
+}
+
+impl ChatHistory {
+    pub fn record(&mut self, room_id: &str, entry: ChatEntry) {
+        let messages = self.rooms.entry(room_id.to_string()).or_default();
+        if messages.len() == CHAT_HISTORY_SIZE {
+            messages.pop_front();
+        }
+        messages.push_back(entry);
+    }
+
+    pub fn recent(&self, room_id: &str) -> Vec<ChatEntry> {
+        self.rooms
+            .get(room_id)
+            .map(|messages| messages.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// For rooms that closed
+    pub fn forget(&mut self, room_id: &str) {
+        self.rooms.remove(room_id);
+    }
+}
@@ -0,0 +1,60 @@
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use tokio_tungstenite::tungstenite::Message;
+
+const COMPRESS_PARAM: &str = "compress";
+
+/// How a connection wants large messages sent, picked during the websocket handshake.
+/// Browsers can't set headers on a websocket request, so clients ask in the query string,
+/// e.g. `ws://host:8080/?compress=gzip`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip, // messages over the threshold go out as binary frames of gzipped JSON
+}
+
+impl Compression {
+    pub fn negotiate(query: Option<&str>) -> Self {
+        let requested = query.and_then(|query| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == COMPRESS_PARAM)
+                .map(|(_, value)| value)
+        });
+        match requested {
+            Some(value) if value.eq_ignore_ascii_case("gzip") => Compression::Gzip,
+            _ => Compression::None,
+        }
+    }
+
+    /// The frame a message goes out in: gzipped once it is over `threshold` bytes, as text
+    /// otherwise. A threshold of 0 never compresses.
+    pub fn frame(self, message: &str, threshold: usize) -> Message {
+        match self {
+            Compression::Gzip if threshold > 0 && message.len() > threshold => {
+                match gzip(message) {
+                    Ok(bytes) => Message::Binary(bytes),
+                    Err(_) => Message::Text(message.to_string()),
+                }
+            }
+            _ => Message::Text(message.to_string()),
+        }
+    }
+}
+
+pub fn gzip(text: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    encoder.write_all(text.as_bytes())?;
+    encoder.finish()
+}
+
+/// For clients: the JSON inside a binary frame
+pub fn gunzip(bytes: &[u8]) -> std::io::Result<String> {
+    let mut text = String::new();
+    GzDecoder::new(bytes).read_to_string(&mut text)?;
+    Ok(text)
+}
@@ -1,28 +1,42 @@
 use std::error::Error;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
 
-use futures_util::stream::SplitSink;
-use tokio::net::TcpStream;
-use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+use crate::network::connection_manager::ConnectionSender;
+use crate::network::messages::SharedMessage;
 
 #[derive(Debug)]
 pub enum ConnectionCommand {
     AddConnection {
         id: String,
-        sender: SplitSink<WebSocketStream<TcpStream>, Message>,
+        sender: ConnectionSender,
     },
     RemoveConnection {
         id: String,
     },
+    // Close one websocket after what's already queued for it, then drop it
+    Close {
+        id: String,
+        close_frame: CloseFrame<'static>,
+    },
     SendToAll {
-        message: String,
+        message: SharedMessage,
     },
     SendToPlayer {
         connection_id: String,
-        message: String,
+        message: SharedMessage,
     },
     SendToPlayers {
         connections_id: Vec<String>,
-        message: String,
+        message: SharedMessage,
+    },
+    // Send a close frame to every websocket and drop all connections
+    CloseAll,
+    // A game's own channel, fanned out on a task of its own. The task starts once everything
+    // sent before this command has been queued, so a game never overtakes what the lobby sent.
+    OpenGameChannel {
+        game_id: String,
+        commands: mpsc::UnboundedReceiver<ConnectionCommand>,
     },
 }
 
@@ -31,7 +45,7 @@ pub struct CommandProcessor;
 impl CommandProcessor {
     pub async fn process_command(
         command: ConnectionCommand,
-        connection_manager: &mut crate::ConnectionManager,
+        connection_manager: &crate::ConnectionManager,
     ) -> Result<(), Box<dyn Error>> {
         match command {
             ConnectionCommand::AddConnection { id, sender } => {
@@ -40,26 +54,29 @@ impl CommandProcessor {
             ConnectionCommand::RemoveConnection { id } => {
                 connection_manager.remove_connection(&id);
             }
+            ConnectionCommand::Close { id, close_frame } => {
+                connection_manager.close_connection(&id, close_frame);
+            }
             ConnectionCommand::SendToAll { message } => {
-                connection_manager.send_to_all(&message).await;
+                connection_manager.send_to_all(&message);
             }
             ConnectionCommand::SendToPlayer {
                 connection_id,
                 message,
             } => {
-                connection_manager
-                    .send_to_player(&connection_id, &message)
-                    .await?;
+                connection_manager.send_to_player(&connection_id, &message)?;
             }
             ConnectionCommand::SendToPlayers {
                 connections_id,
                 message,
             } => {
-                for connection_id in connections_id {
-                    connection_manager
-                        .send_to_player(&*connection_id, &message)
-                        .await?;
-                }
+                connection_manager.send_to_players(&connections_id, &message);
+            }
+            ConnectionCommand::CloseAll => {
+                connection_manager.close_all();
+            }
+            ConnectionCommand::OpenGameChannel { game_id, commands } => {
+                connection_manager.spawn_fan_out(game_id, commands);
             }
         }
         Ok(())
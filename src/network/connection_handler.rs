@@ -1,33 +1,129 @@
-use futures_util::StreamExt;
+use futures_util::{SinkExt, StreamExt};
 use std::error::Error;
 use std::sync::Arc;
-use tokio::net::TcpStream;
-use tokio::sync::mpsc;
-use tokio_tungstenite::{accept_async, tungstenite::Message};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::http::header::ACCEPT_LANGUAGE;
+use tokio_tungstenite::tungstenite::protocol::{
+    frame::coding::CloseCode, CloseFrame, WebSocketConfig,
+};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::{accept_hdr_async, accept_hdr_async_with_config};
 
 use crate::actors::actor_registry::ActorRegistry;
-use crate::actors::connection_actor::{ConnectionActor, ConnectionMessage};
+use crate::actors::connection_actor::{ConnectionActor, ConnectionMessage, ReliableClientMessage};
+use crate::actors::lobby_actor::LobbyMessage;
+use crate::config::InboundConfig;
+use crate::network::auth::AuthMode;
+use crate::network::compression::Compression;
+use crate::network::connection_manager::{ClientStream, ConnectionSender};
+use crate::network::frame_guard::FrameGuard;
+use crate::network::locale::Locale;
 use crate::network::messages::{deserialize_message, serialize_response, ServerResponse};
 use crate::{AppError, ConnectionCommand};
 
 pub struct ConnectionHandler;
 
+fn negotiate_locale(request: &Request) -> Locale {
+    let accept_language = request
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok());
+    Locale::negotiate(request.uri().query(), accept_language)
+}
+
 impl ConnectionHandler {
+    /// Complete the handshake only to tell the client why it can't stay
+    // The handshake callback signature, and its large error response, is fixed by tungstenite
+    #[allow(clippy::result_large_err)]
+    pub async fn reject_connection(stream: ClientStream, connection_id: String, error: AppError) {
+        let mut locale = Locale::default();
+        let handshake = accept_hdr_async(stream, |request: &Request, response: Response| {
+            locale = negotiate_locale(request);
+            Ok(response)
+        });
+        let mut ws_stream = match handshake.await {
+            Ok(ws_stream) => ws_stream,
+            Err(e) => {
+                eprintln!(
+                    "❌ Handshake failed for rejected connection {}: {}",
+                    connection_id, e
+                );
+                return;
+            }
+        };
+        println!("🚧 Rejected connection {}: {}", connection_id, error);
+        let response = serialize_response(ServerResponse::localized_error(&error, locale));
+        let _ = ws_stream.send(Message::Text(response.to_string())).await;
+        let _ = ws_stream
+            .close(Some(CloseFrame {
+                code: CloseCode::Again,
+                reason: error.to_string().into(),
+            }))
+            .await;
+    }
+
+    // The handshake callback signature, and its large error response, is fixed by tungstenite
+    #[allow(clippy::result_large_err)]
     pub async fn handle_connection(
-        stream: TcpStream,
+        stream: ClientStream,
         connection_id: String,
+        auth_mode: Arc<AuthMode>,
+        inbound: InboundConfig,
         actor_registry: Arc<ActorRegistry>,
         cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
     ) -> Result<(), Box<dyn Error>> {
-        let ws_stream = accept_async(stream).await?;
-        println!("✅ WebSocket connection {} established", connection_id);
+        // Frames up to twice the limit are read and refused politely by the FrameGuard,
+        // anything bigger is cut off by the protocol layer before it is buffered
+        let hard_limit = inbound.max_frame_bytes.saturating_mul(2);
+        let websocket_config = WebSocketConfig {
+            max_message_size: Some(hard_limit),
+            max_frame_size: Some(hard_limit),
+            ..WebSocketConfig::default()
+        };
+        let mut auth_result = None;
+        let mut locale = Locale::default();
+        let mut compression = Compression::None;
+        let mut ws_stream = accept_hdr_async_with_config(
+            stream,
+            |request: &Request, response: Response| {
+                auth_result = Some(auth_mode.authenticate(request.uri().query()));
+                locale = negotiate_locale(request);
+                compression = Compression::negotiate(request.uri().query());
+                Ok(response)
+            },
+            Some(websocket_config),
+        )
+        .await?;
+
+        // Browsers can't read the status of a refused upgrade, so the handshake completes
+        // and the reason is delivered in a policy-violation close frame instead
+        let auth_context = match auth_result.expect("Handshake callback always runs") {
+            Ok(auth_context) => auth_context,
+            Err(error) => {
+                println!("🔒 Rejected connection {}: {}", connection_id, error);
+                let _ = ws_stream
+                    .close(Some(CloseFrame {
+                        code: CloseCode::Policy,
+                        reason: error.to_string().into(),
+                    }))
+                    .await;
+                return Ok(());
+            }
+        };
+        println!(
+            "✅ WebSocket connection {} established ({:?}, {:?}, {:?})",
+            connection_id, auth_context.method, locale, compression
+        );
 
         let (ws_sender, mut ws_receiver) = ws_stream.split();
+        let (writer_handle, mut writer_stopped) = oneshot::channel::<()>();
 
         // Add WebSocket connection to connection manager
         cmd_sender.send(ConnectionCommand::AddConnection {
             id: connection_id.clone(),
-            sender: ws_sender,
+            sender: ConnectionSender::WebSocket(ws_sender, compression, writer_handle),
         })?;
 
         // Send connection ID to client
@@ -39,65 +135,127 @@ impl ConnectionHandler {
             message: connection_id_message,
         })?;
 
-        let (conn_sender, conn_receiver) = mpsc::unbounded_channel::<ConnectionMessage>();
+        let frame_guard = FrameGuard::new(inbound.clone());
+        let (conn_sender, conn_receiver) =
+            mpsc::channel::<ConnectionMessage>(actor_registry.mailboxes().connection);
         let mut connection_actor = ConnectionActor::new(
             connection_id.clone(),
+            auth_context,
+            locale,
+            &inbound,
+            frame_guard.malformed_frames(),
             actor_registry.clone(),
             cmd_sender.clone(),
         );
 
         // Register connection actor in registry
         actor_registry.register_connection_actor(connection_id.clone(), conn_sender.clone());
+        actor_registry.set_locale(&connection_id, locale);
+        actor_registry.deliver_lobby_message(LobbyMessage::ConnectionOpened {
+            connection_id: connection_id.clone(),
+        })?;
 
         // Spawn connection actor task
         tokio::spawn(async move {
             connection_actor.run(conn_receiver).await;
         });
 
-        // Main WebSocket loop just forwards messages to connection actor
-        while let Some(msg) = ws_receiver.next().await {
-            match msg {
-                Ok(Message::Text(text)) => {
-                    match deserialize_message(&text) {
-                        Ok(client_message) => {
-                            let connection_message = ConnectionMessage::ClientMessage {
-                                message: client_message,
-                            };
-
-                            if let Err(_) = conn_sender.send(connection_message) {
-                                // Connection actor is gone, break the loop
-                                eprintln!("Connection actor for {} is gone", connection_id);
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Parse error from {}: {}", connection_id, e);
-                            // Send error but continue
-                            let _ = cmd_sender.send(ConnectionCommand::SendToPlayer {
-                                connection_id: connection_id.clone(),
-                                message: serialize_response(ServerResponse::from_app_error(
-                                    &AppError::UnknownMessage {
-                                        message: format!("Parse error: {}", e),
-                                    },
-                                )),
-                            });
-                        }
-                    }
+        let send_error = |error: AppError| {
+            let _ = cmd_sender.send(ConnectionCommand::SendToPlayer {
+                connection_id: connection_id.clone(),
+                message: serialize_response(ServerResponse::localized_error(&error, locale)),
+            });
+        };
+
+        // Main WebSocket loop just forwards messages to connection actor, until the client or
+        // the connection's writer is done with the socket
+        loop {
+            let msg = tokio::select! {
+                msg = ws_receiver.next() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                _ = &mut writer_stopped => {
+                    println!("🔌 Writer for {} stopped, closing the socket", connection_id);
+                    break;
                 }
+            };
+            let frame = match msg {
                 Ok(Message::Close(_)) => {
                     println!("🔌 WebSocket close for {}", connection_id);
                     break;
                 }
-                Ok(_) => continue, // Ignore other message types
+                Ok(frame) => frame,
+                Err(WsError::Capacity(e)) => {
+                    eprintln!("Oversized frame from {}: {}", connection_id, e);
+                    send_error(AppError::MalformedFrame {
+                        reason: e.to_string(),
+                    });
+                    break;
+                }
                 Err(e) => {
                     eprintln!("WebSocket error {}: {}", connection_id, e);
                     break;
                 }
+            };
+
+            // Refused frames and frames that don't parse both count towards a disconnect
+            let parsed = match frame_guard.inspect(&frame) {
+                Ok(None) => continue, // pings and pongs
+                Ok(Some(text)) => {
+                    // Reliable frames wrap a ClientMessage, anything else is a bare one
+                    match serde_json::from_str::<ReliableClientMessage>(text) {
+                        Ok(reliable) => Ok(ConnectionMessage::from(reliable)),
+                        Err(_) => deserialize_message(text)
+                            .map(|client_message| ConnectionMessage::ClientMessage {
+                                message: client_message,
+                            })
+                            .map_err(|e| AppError::UnknownMessage {
+                                message: format!("Parse error: {}", e),
+                            }),
+                    }
+                }
+                Err(error) => Err(error),
+            };
+            match parsed {
+                Ok(connection_message) => match conn_sender.try_send(connection_message) {
+                    Ok(()) => {}
+                    // The client is sending faster than it's being served, shed the frame
+                    Err(TrySendError::Full(_)) => send_error(AppError::ServerBusy {
+                        mailbox: "connection".to_string(),
+                    }),
+                    Err(TrySendError::Closed(_)) => {
+                        // Connection actor is gone, break the loop
+                        eprintln!("Connection actor for {} is gone", connection_id);
+                        break;
+                    }
+                },
+                Err(error) => {
+                    eprintln!("Bad frame from {}: {}", connection_id, error);
+                    send_error(error);
+                    if frame_guard.record_malformed() {
+                        println!(
+                            "🚫 Disconnecting {}: too many malformed frames",
+                            connection_id
+                        );
+                        let _ = cmd_sender.send(ConnectionCommand::Close {
+                            id: connection_id.clone(),
+                            close_frame: CloseFrame {
+                                code: CloseCode::Policy,
+                                reason: "Too many malformed frames".into(),
+                            },
+                        });
+                        break;
+                    }
+                }
             }
         }
 
         // Notify connection actor to disconnect
         let _ = actor_registry.disconnect_connection_actor(&connection_id);
+        let _ = actor_registry.deliver_lobby_message(LobbyMessage::ConnectionClosed {
+            connection_id: connection_id.clone(),
+        });
 
         // Remove WebSocket connection
         cmd_sender.send(ConnectionCommand::RemoveConnection {
@@ -1,69 +1,284 @@
+use dashmap::DashMap;
 use futures_util::{stream::SplitSink, SinkExt};
-use std::collections::HashMap;
-use tokio::net::TcpStream;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio_tungstenite::tungstenite::protocol::{frame::coding::CloseCode, CloseFrame};
 use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
 
+use crate::config::{OutboundConfig, OverflowPolicy};
+use crate::network::compression::Compression;
+use crate::network::messages::SharedMessage;
+use crate::network::metrics::ServerMetrics;
+use crate::{CommandProcessor, ConnectionCommand};
+
+/// Any transport a websocket can run over, so plain TCP and TLS streams share one handler
+pub trait ClientIo: AsyncRead + AsyncWrite + Unpin + Send + Debug {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Debug> ClientIo for T {}
+
+pub type ClientStream = Box<dyn ClientIo>;
+
+// A client too slow to be kept gets this long to take its close frame before the socket goes
+const FORCED_CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub enum ConnectionSender {
+    // With the compression the client negotiated in the handshake, and a handle dropped with
+    // the writer task so the socket's read loop lets go of the socket too
+    WebSocket(
+        SplitSink<WebSocketStream<ClientStream>, Message>,
+        Compression,
+        oneshot::Sender<()>,
+    ),
+    // In-process connections (bots) receive the serialized responses over a channel
+    Local(mpsc::UnboundedSender<SharedMessage>),
+}
+
+impl ConnectionSender {
+    async fn send(&mut self, message: SharedMessage, compress_above: usize) -> Result<(), String> {
+        match self {
+            ConnectionSender::WebSocket(sink, compression, _) => sink
+                .send(compression.frame(&message, compress_above))
+                .await
+                .map_err(|e| e.to_string()),
+            ConnectionSender::Local(sender) => sender
+                .send(message)
+                .map_err(|_| "Local receiver closed".to_string()),
+        }
+    }
+
+    async fn close(&mut self, close_frame: CloseFrame<'static>) -> Result<(), String> {
+        match self {
+            ConnectionSender::WebSocket(sink, _, _) => sink
+                .send(Message::Close(Some(close_frame)))
+                .await
+                .map_err(|e| e.to_string()),
+            ConnectionSender::Local(_) => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug)]
-pub struct WebSocketConnection {
-    pub sender: SplitSink<WebSocketStream<TcpStream>, Message>,
+enum Outgoing {
+    Text(SharedMessage),
+    Close(CloseFrame<'static>),
+}
+
+/// Handle to a connection's writer task. Messages are queued, never awaited, so one slow
+/// client can't hold up everyone else.
+#[derive(Debug)]
+pub struct Connection {
+    queue: mpsc::Sender<Outgoing>,
+    // Skips whatever is still queued and closes right away
+    force_close: Arc<Notify>,
+}
+
+impl Connection {
+    // Compressing happens here, on the connection's own task, rather than in the manager
+    fn spawn(
+        id: String,
+        mut sender: ConnectionSender,
+        outbound: &OutboundConfig,
+        metrics: Arc<ServerMetrics>,
+    ) -> Self {
+        let (queue_size, compress_above) = (outbound.queue_size, outbound.compress_above_bytes);
+        let (queue, mut outgoing) = mpsc::channel::<Outgoing>(queue_size.max(1));
+        let force_close = Arc::new(Notify::new());
+        let closing = force_close.clone();
+
+        tokio::spawn(async move {
+            // A client that stops reading leaves a write hanging, so every one of them is
+            // raced against the forced close
+            let forced = tokio::select! {
+                biased;
+                _ = closing.notified() => true,
+                _ = write_queued(&id, &mut sender, &mut outgoing, compress_above, &metrics) => false,
+            };
+            if forced {
+                let close_frame = CloseFrame {
+                    code: CloseCode::Policy,
+                    reason: "Too many unsent messages".into(),
+                };
+                let _ = tokio::time::timeout(FORCED_CLOSE_TIMEOUT, sender.close(close_frame)).await;
+            }
+            // Dropping the sender ends the read loop, which closes the socket
+        });
+
+        Self { queue, force_close }
+    }
+
+    fn close(&self, id: &str, close_frame: CloseFrame<'static>) {
+        if self.queue.try_send(Outgoing::Close(close_frame)).is_err() {
+            eprintln!("❌ Send queue full for {}, closing without flushing", id);
+            self.force_close.notify_one();
+        }
+    }
+}
+
+/// Write everything queued for a connection until its close frame, a failed write, or until
+/// it is removed from the manager
+async fn write_queued(
+    id: &str,
+    sender: &mut ConnectionSender,
+    outgoing: &mut mpsc::Receiver<Outgoing>,
+    compress_above: usize,
+    metrics: &ServerMetrics,
+) {
+    // None once removed from the manager and everything queued has been written
+    while let Some(next) = outgoing.recv().await {
+        match next {
+            Outgoing::Text(message) => {
+                if let Err(e) = sender.send(message, compress_above).await {
+                    eprintln!("❌ Failed to send to connection {}: {}", id, e);
+                    metrics.send_failed();
+                    return;
+                }
+            }
+            Outgoing::Close(close_frame) => {
+                if let Err(e) = sender.close(close_frame).await {
+                    eprintln!("❌ Failed to close connection {}: {}", id, e);
+                }
+                return;
+            }
+        }
+    }
 }
+
+/// Hands messages to each connection's writer task. Clones share the connections, so every
+/// running game fans its messages out on a task of its own (see `spawn_fan_out`) instead of
+/// queueing behind the server-wide command loop.
+#[derive(Default, Clone)]
 pub struct ConnectionManager {
-    pub connections: HashMap<String, WebSocketConnection>,
+    pub connections: Arc<DashMap<String, Connection>>,
+    outbound: OutboundConfig,
+    metrics: Arc<ServerMetrics>,
 }
 impl ConnectionManager {
-    pub fn new() -> Self {
+    pub fn new(outbound: OutboundConfig, metrics: Arc<ServerMetrics>) -> Self {
         Self {
-            connections: HashMap::new(),
+            connections: Arc::new(DashMap::new()),
+            outbound,
+            metrics,
         }
     }
 
-    pub fn add_connection(
-        &mut self,
-        id: String,
-        sender: SplitSink<WebSocketStream<TcpStream>, Message>,
-    ) {
-        let connection = WebSocketConnection { sender };
-        self.connections.insert(id.clone(), connection);
+    pub fn add_connection(&self, id: String, sender: ConnectionSender) {
+        let connection =
+            Connection::spawn(id.clone(), sender, &self.outbound, self.metrics.clone());
+        self.connections.insert(id, connection);
     }
 
-    pub fn remove_connection(&mut self, id: &str) {
+    pub fn remove_connection(&self, id: &str) {
         self.connections.remove(id);
     }
 
-    pub async fn send_to_all(&mut self, message: &str) {
-        println!("📢 Broadcasting: {}", message);
+    /// Run a game's commands until every sender of its channel is gone
+    pub fn spawn_fan_out(
+        &self,
+        game_id: String,
+        mut commands: mpsc::UnboundedReceiver<ConnectionCommand>,
+    ) {
+        let connection_manager = self.clone();
+        tokio::spawn(async move {
+            while let Some(command) = commands.recv().await {
+                if let Err(e) =
+                    CommandProcessor::process_command(command, &connection_manager).await
+                {
+                    eprintln!("❌ Connection command for game {} failed: {}", game_id, e);
+                }
+            }
+        });
+    }
 
-        let mut failed_connections = Vec::new();
+    pub fn send_to_all(&self, message: &SharedMessage) {
+        log::debug!("📢 Broadcasting: {}", message);
+        self.metrics.broadcast_sent();
 
-        for (id, connection) in &mut self.connections {
-            if let Err(e) = connection
-                .sender
-                .send(Message::Text(message.to_string()))
-                .await
-            {
+        let ids: Vec<String> = self
+            .connections
+            .iter()
+            .map(|connection| connection.key().clone())
+            .collect();
+        for id in ids {
+            if let Err(e) = self.send_to_player(&id, message) {
                 eprintln!("❌ Failed to send to connection {}: {}", id, e);
-                failed_connections.push(id.clone());
             }
         }
+    }
+
+    pub fn send_to_players(&self, connection_ids: &[String], message: &SharedMessage) {
+        self.metrics.broadcast_sent();
+        // One unreachable member must not stop the rest of the room from receiving
+        for connection_id in connection_ids {
+            if let Err(e) = self.send_to_player(connection_id, message) {
+                eprintln!("❌ Failed to send to connection {}: {}", connection_id, e);
+            }
+        }
+    }
+
+    /// Queue a close frame behind each connection's pending messages and drop all connections
+    pub fn close_all(&self) {
+        let ids: Vec<String> = self
+            .connections
+            .iter()
+            .map(|connection| connection.key().clone())
+            .collect();
+        for id in ids {
+            let close_frame = CloseFrame {
+                code: CloseCode::Away,
+                reason: "Server shutting down".into(),
+            };
+            self.close_connection(&id, close_frame);
+        }
+    }
 
-        // Remove failed connections
-        for failed_id in failed_connections {
-            self.remove_connection(&failed_id);
+    pub fn close_connection(&self, id: &str, close_frame: CloseFrame<'static>) {
+        if let Some((_, connection)) = self.connections.remove(id) {
+            connection.close(id, close_frame);
         }
     }
 
-    pub async fn send_to_player(
-        &mut self,
+    pub fn send_to_player(
+        &self,
         connection_id: &str,
-        message: &str,
+        message: &SharedMessage,
     ) -> Result<(), String> {
-        self.connections
-            .get_mut(connection_id)
-            .ok_or_else(|| "Connection not found".to_string())?
-            .sender
-            .send(Message::Text(message.to_string()))
-            .await
-            .map_err(|e| format!("Failed to send message: {}", e))?;
-        Ok(())
+        let result = self.queue_for(connection_id, message);
+        if result.is_err() {
+            self.metrics.send_failed();
+        }
+        result
+    }
+
+    fn queue_for(&self, connection_id: &str, message: &SharedMessage) -> Result<(), String> {
+        // The map entry stays locked while it's borrowed, so removals wait until it's released
+        let (result, remove) = {
+            let connection = self
+                .connections
+                .get(connection_id)
+                .ok_or_else(|| "Connection not found".to_string())?;
+
+            match connection.queue.try_send(Outgoing::Text(message.clone())) {
+                Ok(()) => (Ok(()), false),
+                Err(mpsc::error::TrySendError::Full(_)) => match self.outbound.on_overflow {
+                    OverflowPolicy::Drop => {
+                        (Err("Send queue full, message dropped".to_string()), false)
+                    }
+                    OverflowPolicy::Disconnect => {
+                        connection.force_close.notify_one();
+                        (Err("Send queue full, disconnecting".to_string()), true)
+                    }
+                },
+                Err(mpsc::error::TrySendError::Closed(_)) => {
+                    (Err("Connection writer stopped".to_string()), true)
+                }
+            }
+        };
+        if remove {
+            self.remove_connection(connection_id);
+        }
+        result
     }
 }
@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::config::InboundConfig;
+use crate::{AppError, AppResult};
+
+/// First line of defence for everything a client sends: frames are checked for size, type and
+/// JSON nesting before serde sees them, and a client that keeps sending garbage is cut off.
+#[derive(Debug)]
+pub struct FrameGuard {
+    limits: InboundConfig,
+    malformed: MalformedFrames,
+}
+
+/// Refused frames from one client, counted by the socket loop and by the connection actor,
+/// which refuses some frames only once it has looked inside them
+#[derive(Debug, Clone)]
+pub struct MalformedFrames {
+    count: Arc<AtomicUsize>,
+    limit: usize,
+}
+
+impl MalformedFrames {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            count: Arc::new(AtomicUsize::new(0)),
+            limit,
+        }
+    }
+
+    /// Count a refused or unparseable frame; true once the client has sent too many
+    pub fn record(&self) -> bool {
+        self.count.fetch_add(1, Ordering::Relaxed) + 1 >= self.limit
+    }
+}
+
+impl FrameGuard {
+    pub fn new(limits: InboundConfig) -> Self {
+        let malformed = MalformedFrames::new(limits.max_malformed_frames);
+        Self { limits, malformed }
+    }
+
+    /// A guard for frames found inside other frames, counting towards the outer guard's limit
+    pub fn sharing(limits: InboundConfig, malformed: MalformedFrames) -> Self {
+        Self { limits, malformed }
+    }
+
+    /// Shares this guard's count with whatever else refuses this client's frames
+    pub fn malformed_frames(&self) -> MalformedFrames {
+        self.malformed.clone()
+    }
+
+    /// The text to parse, None for control frames, or why the frame was refused
+    pub fn inspect<'a>(&self, message: &'a Message) -> AppResult<Option<&'a str>> {
+        match message {
+            Message::Text(text) => {
+                self.check_text(text)?;
+                Ok(Some(text.as_str()))
+            }
+            // There is no binary codec yet, every message is JSON text
+            Message::Binary(_) => Err(malformed("binary frames are not supported, send JSON text")),
+            Message::Ping(_) | Message::Pong(_) | Message::Close(_) | Message::Frame(_) => Ok(None),
+        }
+    }
+
+    pub fn check_text(&self, text: &str) -> AppResult<()> {
+        if text.len() > self.limits.max_frame_bytes {
+            return Err(malformed(&format!(
+                "frame is {} bytes, the limit is {}",
+                text.len(),
+                self.limits.max_frame_bytes
+            )));
+        }
+        if exceeds_depth(text, self.limits.max_json_depth) {
+            return Err(malformed(&format!(
+                "JSON is nested deeper than {} levels",
+                self.limits.max_json_depth
+            )));
+        }
+        Ok(())
+    }
+
+    /// Count a refused or unparseable frame; true once the client has sent too many
+    pub fn record_malformed(&self) -> bool {
+        self.malformed.record()
+    }
+}
+
+fn malformed(reason: &str) -> AppError {
+    AppError::MalformedFrame {
+        reason: reason.to_string(),
+    }
+}
+
+/// Scans brackets outside of strings, stopping as soon as `max_depth` is passed, so a deeply
+/// nested payload costs no more than its length and never reaches serde's recursion
+pub fn exceeds_depth(text: &str, max_depth: usize) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for byte in text.bytes() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    false
+}
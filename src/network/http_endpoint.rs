@@ -0,0 +1,109 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+use crate::actors::actor_registry::ActorRegistry;
+use crate::config::ServerConfig;
+use crate::network::auth::AuthMode;
+use crate::network::protocol::protocol_info;
+
+// Enough for a request line and the headers a scraper sends
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const INFO_CONTENT_TYPE: &str = "application/json";
+const TEXT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// Plain HTTP for operators, kept off the game port so it can be firewalled separately.
+/// Serves `GET /metrics` in the Prometheus text format and `GET /info` (see `info`), one
+/// request per connection.
+pub async fn serve(listener: TcpListener, actor_registry: Arc<ActorRegistry>, info: String) {
+    let info = Arc::new(info);
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _addr)) => stream,
+            Err(e) => {
+                eprintln!("❌ Failed to accept metrics connection: {}", e);
+                continue;
+            }
+        };
+        let actor_registry = actor_registry.clone();
+        let info = info.clone();
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &actor_registry, &info).await {
+                log::debug!("Metrics request failed: {}", e);
+            }
+        });
+    }
+}
+
+/// The effective configuration after file, environment and command line were merged, with the
+/// auth mode by name only: API keys and the JWT secret never leave the process.
+pub fn info(config: &ServerConfig, auth_mode: &AuthMode) -> String {
+    serde_json::json!({
+        "server_version": env!("CARGO_PKG_VERSION"),
+        "protocol_version": protocol_info().protocol_version,
+        "auth_mode": auth_mode.name(),
+        "config": config,
+    })
+    .to_string()
+}
+
+async fn respond(
+    mut stream: TcpStream,
+    actor_registry: &ActorRegistry,
+    info: &str,
+) -> std::io::Result<()> {
+    let request_line = match timeout(REQUEST_TIMEOUT, read_request_line(&mut stream)).await {
+        Ok(request_line) => request_line?,
+        Err(_) => return Ok(()),
+    };
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            METRICS_CONTENT_TYPE,
+            actor_registry.render_metrics(),
+        ),
+        (Some("GET"), Some("/info")) => ("200 OK", INFO_CONTENT_TYPE, info.to_string()),
+        (Some("GET"), Some(_)) => (
+            "404 Not Found",
+            TEXT_CONTENT_TYPE,
+            "Not found\n".to_string(),
+        ),
+        _ => (
+            "405 Method Not Allowed",
+            TEXT_CONTENT_TYPE,
+            "Only GET is supported\n".to_string(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Reads up to the end of the headers and returns the first line; the rest is ignored
+async fn read_request_line(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        if request.len() > MAX_REQUEST_BYTES {
+            break;
+        }
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    Ok(request.lines().next().unwrap_or_default().to_string())
+}
@@ -0,0 +1,180 @@
+#[cfg(feature = "server")]
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::AppError;
+
+const LANG_PARAM: &str = "lang";
+
+/// Language of the text the server writes for a connection. Identifiers in the protocol
+/// (`error_type`, ids, the card names in game payloads) are never translated; the card
+/// catalog and glossary carry the translated names and texts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Locale {
+    #[default]
+    En,
+    It,
+    Es,
+}
+
+impl Locale {
+    /// `en`, `it-IT`, `es_ES`... Only the language part counts
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::En),
+            "it" => Some(Locale::It),
+            "es" => Some(Locale::Es),
+            _ => None,
+        }
+    }
+
+    /// Picked during the websocket handshake: a `lang` query parameter wins, then the first
+    /// supported language of the Accept-Language header (in the order listed), then English
+    pub fn negotiate(query: Option<&str>, accept_language: Option<&str>) -> Self {
+        let requested = query.and_then(|query| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == LANG_PARAM)
+                .and_then(|(_, value)| Self::parse(value))
+        });
+        requested
+            .or_else(|| {
+                accept_language?
+                    .split(',')
+                    .filter_map(|entry| entry.split(';').next())
+                    .find_map(Self::parse)
+            })
+            .unwrap_or_default()
+    }
+
+    /// The language code card translations are keyed by
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::It => "it",
+            Locale::Es => "es",
+        }
+    }
+
+    /// The error as shown to a player, English when the catalog has no translation
+    pub fn error_message(self, error: &AppError) -> String {
+        match self {
+            Locale::En => None,
+            Locale::It => italian(error),
+            Locale::Es => spanish(error),
+        }
+        .unwrap_or_else(|| error.user_friendly_message())
+    }
+}
+
+// Message catalogs. Variants left out fall back to English, add them as they get translated.
+
+fn italian(error: &AppError) -> Option<String> {
+    let message = match error {
+        AppError::RoomFull { max_players, .. } => {
+            format!("La stanza è piena (massimo {} giocatori)", max_players)
+        }
+        AppError::RoomNotFound { .. } => "La stanza che cerchi non esiste".to_string(),
+        AppError::RoomInGame { .. } => "La partita in questa stanza è già iniziata".to_string(),
+        AppError::RoomStarting { .. } => {
+            "La partita sta per iniziare, non puoi più cambiare se sei pronto".to_string()
+        }
+        AppError::ConnectionNotInRoom => "Devi prima entrare in una stanza".to_string(),
+        AppError::RoomNameEmpty => "Il nome della stanza non può essere vuoto".to_string(),
+        AppError::PlayersNotReady {
+            ready_count,
+            total_count,
+        } => format!(
+            "Impossibile iniziare: solo {}/{} giocatori sono pronti",
+            ready_count, total_count
+        ),
+        AppError::ServerBusy { .. } => "Il server è occupato, riprova tra poco".to_string(),
+        AppError::ServerAtCapacity { .. } => {
+            "Il server è al completo, riprova più tardi".to_string()
+        }
+        AppError::SerializationError { .. } => "Formato del messaggio non valido".to_string(),
+        AppError::NotPlayerTurn => "Non è il tuo turno".to_string(),
+        AppError::CardNotInHand => "Quella carta non è nella tua mano".to_string(),
+        AppError::ChoicePending => "In attesa della scelta di un giocatore".to_string(),
+        AppError::MulliganInProgress => {
+            "In attesa che tutti decidano sulla mano iniziale".to_string()
+        }
+        AppError::PermissionDenied { .. } => "Permesso negato".to_string(),
+        AppError::BannedFromRoom { .. } => "Sei stato bandito da questa stanza".to_string(),
+        AppError::SessionNotFound => "Sessione di gioco inesistente o scaduta".to_string(),
+        _ => return None,
+    };
+    Some(message)
+}
+
+fn spanish(error: &AppError) -> Option<String> {
+    let message = match error {
+        AppError::RoomFull { max_players, .. } => {
+            format!("La sala está llena (máximo {} jugadores)", max_players)
+        }
+        AppError::RoomNotFound { .. } => "La sala que buscas no existe".to_string(),
+        AppError::RoomInGame { .. } => "La partida de esta sala ya ha empezado".to_string(),
+        AppError::RoomStarting { .. } => {
+            "La partida está a punto de empezar, ya no puedes cambiar si estás listo".to_string()
+        }
+        AppError::ConnectionNotInRoom => "Primero tienes que unirte a una sala".to_string(),
+        AppError::RoomNameEmpty => "El nombre de la sala no puede estar vacío".to_string(),
+        AppError::PlayersNotReady {
+            ready_count,
+            total_count,
+        } => format!(
+            "No se puede empezar: solo {}/{} jugadores están listos",
+            ready_count, total_count
+        ),
+        AppError::ServerBusy { .. } => {
+            "El servidor está ocupado, inténtalo de nuevo en un momento".to_string()
+        }
+        AppError::ServerAtCapacity { .. } => {
+            "El servidor está lleno, inténtalo más tarde".to_string()
+        }
+        AppError::SerializationError { .. } => "Formato de mensaje no válido".to_string(),
+        AppError::NotPlayerTurn => "No es tu turno".to_string(),
+        AppError::CardNotInHand => "Esa carta no está en tu mano".to_string(),
+        AppError::ChoicePending => "Esperando a que un jugador elija".to_string(),
+        AppError::MulliganInProgress => {
+            "Esperando a que todos decidan sobre su mano inicial".to_string()
+        }
+        AppError::PermissionDenied { .. } => "Permiso denegado".to_string(),
+        AppError::BannedFromRoom { .. } => "Tienes prohibida la entrada a esta sala".to_string(),
+        AppError::SessionNotFound => "La sesión de juego no existe o ha caducado".to_string(),
+        _ => return None,
+    };
+    Some(message)
+}
+
+/// Locale of every open connection, for actors that answer a connection they don't own
+#[cfg(feature = "server")]
+#[derive(Default)]
+pub struct ConnectionLocales {
+    locales: DashMap<String, Locale>,
+}
+
+#[cfg(feature = "server")]
+impl ConnectionLocales {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, connection_id: &str, locale: Locale) {
+        self.locales.insert(connection_id.to_string(), locale);
+    }
+
+    /// English for connections that never negotiated one, such as bots
+    pub fn get(&self, connection_id: &str) -> Locale {
+        self.locales
+            .get(connection_id)
+            .map_or_else(Locale::default, |locale| *locale)
+    }
+
+    pub fn remove(&self, connection_id: &str) {
+        self.locales.remove(connection_id);
+    }
+}
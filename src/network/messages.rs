@@ -1,19 +1,99 @@
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    game::{board::Player, cards_types::LootCard, game_state::TurnPhases},
+    game::{
+        board::{DeckKind, DeckSearch, DeckSizes},
+        cards_types::{Attachment, Card, CatalogCard, GlossaryEntry, Item, LootCard},
+        choices::ChoiceOption,
+        deadlines::WindowTimer,
+        event_log::LogEntry,
+        game_state::{LegalActions, PublicPlayerView, SimpleStateView, TurnPhases},
+        ratings::PlayerRating,
+        rules::{GameSpeed, SpeedTimers},
+        stats::{GameStats, GameSummary},
+        trades::{Trade, TradeOffer},
+        turn_order::TurnModifier,
+    },
+    network::{
+        chat_history::ChatEntry,
+        locale::Locale,
+        profiles::{FriendPresence, PlayerProfile},
+        protocol::ProtocolInfo,
+        room::{RoomOptions, RoomState},
+        tournament::TournamentBracket,
+    },
     AppError,
 };
 
+/// A credential in a client message: the plain string on the wire, never shown by `Debug`, so
+/// messages can be logged without leaking keys and tokens
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ClientMessageCategory {
     LobbyMessage,
     GameMessage,
+    TournamentMessage,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, JsonSchema)]
+pub enum ChatChannel {
+    Lobby, // Everyone connected
+    Room,  // Members of the current room
+}
+
+/// How an announcement is shown; clients render all of them apart from chat
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub enum AnnouncementSeverity {
+    Info,     // event notices
+    Warning,  // e.g. maintenance coming up
+    Critical, // e.g. the server going down now
+}
+
+/// Why the lobby took a connection out of its room
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub enum KickReason {
+    RemovedByHost,
+    Banned,
+    RoomClosed,  // the host destroyed it
+    RoomExpired, // abandoned or idle for too long
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Identity is never part of a message: who is asking is always the connection it arrived on,
+/// and fields a message doesn't declare are refused rather than ignored. A claimed `player_id`
+/// or `connection_id` is dropped by `deserialize_message` until its sunset date.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub enum ClientMessage {
     Ping,
     Chat {
@@ -22,6 +102,8 @@ pub enum ClientMessage {
     CreateRoom {
         room_name: String,
         first_player_name: String,
+        #[serde(default)]
+        options: RoomOptions,
     },
     DestroyRoom {
         room_id: String,
@@ -32,11 +114,294 @@ pub enum ClientMessage {
     },
     LeaveRoom,
     PlayerReady,
+    PlayerUnready,
+    MulliganDecision {
+        keep: bool,
+    },
+    ActivateItem {
+        item_id: String,
+    },
+    BuyItem {
+        shop_index: usize, // slot in the shop, from the left
+    },
+    PlayLootCard {
+        card_id: String,
+        #[serde(default)]
+        tap_character: bool,
+    },
+    ChoiceResponse {
+        choice_id: String,
+        option_id: String,
+    },
+    ProposeTrade {
+        to_player: String,
+        #[serde(default)]
+        offer: TradeOffer,
+        #[serde(default)]
+        request: TradeOffer,
+    },
+    AcceptTrade {
+        trade_id: String,
+    },
+    DeclineTrade {
+        trade_id: String,
+    },
+    RequestUndo,
+    AcceptUndo,
+    DeclineUndo,
+    Concede,
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    WithdrawDraw,
     TurnPass,
     PriorityPass,
+    GetSimpleState,
+    GetLegalActions,
+    GetTimeRemaining, // for clients that (re)joined while a window was already running
+    ViewDiscardPile {
+        deck_type: DeckKind,
+    },
+    GetEventLog {
+        since: Option<u64>,
+    },
+    GetPrivateLog {
+        since: Option<u64>,
+    },
+    GetCardCatalog {
+        known_version: Option<String>, // the version the client cached, if any
+    },
+    GetGlossary,
+    ReloadCards {
+        admin_key: Secret,
+    },
+    GetProtocolInfo,
+    AddBot {
+        strategy: Option<String>,
+    },
+    StartSoloGame {
+        player_name: String,
+        bots: usize,
+        strategy: Option<String>,
+        #[serde(default)]
+        options: RoomOptions,
+    },
+    LobbyChat {
+        message: String,
+    },
+    SetChatMuted {
+        channel: ChatChannel,
+        muted: bool,
+    },
+    MutePlayer {
+        player_id: String,
+    },
+    UnmutePlayer {
+        player_id: String,
+    },
+    KickPlayer {
+        player_id: String,
+    },
+    BanPlayer {
+        player_id: String,
+    },
+    // Host only. None stops the room's notifications.
+    SetRoomWebhook {
+        url: Option<String>,
+    },
+    RegisterProfile {
+        display_name: String,
+        avatar_id: Option<String>,
+    },
+    RestoreProfile {
+        token: Secret,
+    },
+    AddFriend {
+        profile_id: String,
+    },
+    RemoveFriend {
+        profile_id: String,
+    },
+    GetFriends,
+    InviteToRoom {
+        profile_id: String,
+    },
+    ResumeSession {
+        session_token: Secret,
+    },
+    GetLeaderboard {
+        limit: Option<usize>, // defaults to 10
+    },
+    GetGameSummary {
+        game_id: String,
+    },
+    DumpGameState {
+        game_id: String,
+        admin_key: Secret,
+    },
+    // Admin only. Reaches every connection, at send_at (unix millis) or right away when None
+    Announce {
+        admin_key: Secret,
+        text: String,
+        severity: AnnouncementSeverity,
+        send_at: Option<u64>,
+    },
+    CancelAnnouncement {
+        admin_key: Secret,
+        announcement_id: String,
+    },
+    PauseGame,
+    ResumeGame,
+    ImBack,
+    // Watch a room's running game without a seat, seeing only what every player sees. Rooms
+    // with a spectator_delay_secs option send it that much later than to the players.
+    SpectateGame {
+        room_id: String,
+    },
+    StopSpectating {
+        room_id: String,
+    },
+    // The creator organizes it: only they can start it, without taking part unless they register
+    CreateTournament {
+        name: String,
+        #[serde(default)]
+        options: RoomOptions, // for every match of the bracket
+    },
+    RegisterForTournament {
+        tournament_id: String,
+        player_name: String,
+    },
+    WithdrawFromTournament {
+        tournament_id: String,
+    },
+    StartTournament {
+        tournament_id: String,
+    },
+    GetTournament {
+        tournament_id: String,
+    },
+    // A game message for one of several games this connection plays in; a bare game message
+    // goes to the connection's only game
+    Game {
+        game_id: String,
+        message: Box<ClientMessage>,
+    },
+    // Several messages in one frame, e.g. a choice response and a priority pass, handled in the
+    // order listed as if each had come on its own; answered with BatchResults
+    BatchedClientMessages {
+        messages: Vec<BatchedClientMessage>,
+    },
+}
+
+/// One message of a `BatchedClientMessages`, with an id of the client's choosing that its
+/// result comes back under
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BatchedClientMessage {
+    pub request_id: String,
+    pub message: ClientMessage,
+}
+
+/// How one message of a batch went, once the lobby or game it went to has handled it. Their
+/// answers go out as for a message sent on its own; a refusal is the error here instead.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct BatchedResult {
+    pub request_id: String,
+    pub error: Option<BatchedError>,
+}
+
+/// The `Error` the message would have got on its own
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct BatchedError {
+    pub error_type: String,
+    pub message: String,
+    pub code: u16,
+}
+
+impl BatchedError {
+    pub fn localized(error: &AppError, locale: Locale) -> Self {
+        Self {
+            error_type: error.variant_name().to_string(),
+            message: locale.error_message(error),
+            code: error.status_code(),
+        }
+    }
 }
 
 impl ClientMessage {
+    // Reported by GetProtocolInfo, keep in sync with the enum above
+    pub const VARIANT_NAMES: &'static [&'static str] = &[
+        "Ping",
+        "Chat",
+        "CreateRoom",
+        "DestroyRoom",
+        "JoinRoom",
+        "LeaveRoom",
+        "PlayerReady",
+        "PlayerUnready",
+        "MulliganDecision",
+        "ActivateItem",
+        "BuyItem",
+        "ChoiceResponse",
+        "ProposeTrade",
+        "AcceptTrade",
+        "DeclineTrade",
+        "RequestUndo",
+        "AcceptUndo",
+        "DeclineUndo",
+        "Concede",
+        "OfferDraw",
+        "AcceptDraw",
+        "DeclineDraw",
+        "WithdrawDraw",
+        "TurnPass",
+        "PriorityPass",
+        "GetSimpleState",
+        "GetLegalActions",
+        "GetTimeRemaining",
+        "ViewDiscardPile",
+        "GetEventLog",
+        "GetPrivateLog",
+        "GetCardCatalog",
+        "GetGlossary",
+        "ReloadCards",
+        "GetProtocolInfo",
+        "AddBot",
+        "LobbyChat",
+        "SetChatMuted",
+        "MutePlayer",
+        "UnmutePlayer",
+        "KickPlayer",
+        "BanPlayer",
+        "SetRoomWebhook",
+        "RegisterProfile",
+        "RestoreProfile",
+        "ResumeSession",
+        "GetLeaderboard",
+        "GetGameSummary",
+        "DumpGameState",
+        "Announce",
+        "CancelAnnouncement",
+        "PauseGame",
+        "ResumeGame",
+        "ImBack",
+        "Game",
+        "BatchedClientMessages",
+        "PlayLootCard",
+        "StartSoloGame",
+        "AddFriend",
+        "RemoveFriend",
+        "GetFriends",
+        "InviteToRoom",
+        "CreateTournament",
+        "RegisterForTournament",
+        "WithdrawFromTournament",
+        "StartTournament",
+        "GetTournament",
+        "SpectateGame",
+        "StopSpectating",
+    ];
+
     pub fn category(&self) -> ClientMessageCategory {
         match self {
             ClientMessage::Ping
@@ -45,27 +410,139 @@ impl ClientMessage {
             | ClientMessage::DestroyRoom { .. }
             | ClientMessage::JoinRoom { .. }
             | ClientMessage::LeaveRoom
-            | ClientMessage::PlayerReady => ClientMessageCategory::LobbyMessage,
+            | ClientMessage::PlayerReady
+            | ClientMessage::PlayerUnready
+            | ClientMessage::ReloadCards { .. }
+            | ClientMessage::GetProtocolInfo
+            | ClientMessage::GetCardCatalog { .. }
+            | ClientMessage::GetGlossary
+            | ClientMessage::AddBot { .. }
+            | ClientMessage::StartSoloGame { .. }
+            | ClientMessage::LobbyChat { .. }
+            | ClientMessage::SetChatMuted { .. }
+            | ClientMessage::MutePlayer { .. }
+            | ClientMessage::UnmutePlayer { .. }
+            | ClientMessage::KickPlayer { .. }
+            | ClientMessage::BanPlayer { .. }
+            | ClientMessage::SetRoomWebhook { .. }
+            | ClientMessage::RegisterProfile { .. }
+            | ClientMessage::RestoreProfile { .. }
+            | ClientMessage::ResumeSession { .. }
+            | ClientMessage::AddFriend { .. }
+            | ClientMessage::RemoveFriend { .. }
+            | ClientMessage::GetFriends
+            | ClientMessage::InviteToRoom { .. }
+            | ClientMessage::GetLeaderboard { .. }
+            | ClientMessage::GetGameSummary { .. }
+            | ClientMessage::DumpGameState { .. }
+            | ClientMessage::Announce { .. }
+            | ClientMessage::CancelAnnouncement { .. }
+            | ClientMessage::PauseGame
+            | ClientMessage::ResumeGame
+            | ClientMessage::SpectateGame { .. }
+            | ClientMessage::StopSpectating { .. }
+            // Unpacked by the connection, each message in it goes by its own category
+            | ClientMessage::BatchedClientMessages { .. } => ClientMessageCategory::LobbyMessage,
+
+            ClientMessage::TurnPass
+            | ClientMessage::MulliganDecision { .. }
+            | ClientMessage::ActivateItem { .. }
+            | ClientMessage::BuyItem { .. }
+            | ClientMessage::PlayLootCard { .. }
+            | ClientMessage::ChoiceResponse { .. }
+            | ClientMessage::ProposeTrade { .. }
+            | ClientMessage::AcceptTrade { .. }
+            | ClientMessage::DeclineTrade { .. }
+            | ClientMessage::RequestUndo
+            | ClientMessage::AcceptUndo
+            | ClientMessage::DeclineUndo
+            | ClientMessage::Concede
+            | ClientMessage::OfferDraw
+            | ClientMessage::AcceptDraw
+            | ClientMessage::DeclineDraw
+            | ClientMessage::WithdrawDraw
+            | ClientMessage::PriorityPass
+            | ClientMessage::GetSimpleState
+            | ClientMessage::GetLegalActions
+            | ClientMessage::GetTimeRemaining
+            | ClientMessage::ViewDiscardPile { .. }
+            | ClientMessage::GetEventLog { .. }
+            | ClientMessage::GetPrivateLog { .. }
+            | ClientMessage::ImBack
+            | ClientMessage::Game { .. } => ClientMessageCategory::GameMessage,
 
-            ClientMessage::TurnPass | ClientMessage::PriorityPass => {
-                ClientMessageCategory::GameMessage
-            }
+            ClientMessage::CreateTournament { .. }
+            | ClientMessage::RegisterForTournament { .. }
+            | ClientMessage::WithdrawFromTournament { .. }
+            | ClientMessage::StartTournament { .. }
+            | ClientMessage::GetTournament { .. } => ClientMessageCategory::TournamentMessage,
         }
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub enum ServerResponse {
     ConnectionId {
         connection_id: String,
     },
     Pong,
+    ServerShuttingDown,
+    // To every connection, whatever room or game it is in
+    Announcement {
+        announcement_id: String,
+        text: String,
+        severity: AnnouncementSeverity,
+    },
+    // To the admin, for an announcement sent later
+    AnnouncementScheduled {
+        announcement_id: String,
+        send_at: u64, // unix millis
+    },
+    AnnouncementCancelled {
+        announcement_id: String,
+    },
+    // Connection lifecycle, sent as the connection's state changes so clients don't have to
+    // infer it. LobbyEntered follows ConnectionId; from GameJoined on game messages are accepted.
+    LobbyEntered,
+    GameJoined {
+        game_id: String,
+        seat: String, // the player id this connection plays as
+    },
+    ReturnedToLobby,
+    // Game broadcasts follow, delay_secs after the players get them
+    SpectatingGame {
+        game_id: String,
+        delay_secs: u64,
+    },
+    StoppedSpectating {
+        game_id: String,
+    },
+    Kicked {
+        reason: KickReason,
+    },
     ChatMessage {
+        player_id: String,
         player_name: String,
         message: String,
+        timestamp: u64, // unix millis
+    },
+    // The room's recent messages, oldest first, sent on joining or resuming a seat
+    ChatHistory {
+        messages: Vec<ChatEntry>,
+    },
+    LobbyChatMessage {
+        sender_name: String,
+        message: String,
+    },
+    PlayerMuted {
+        player_id: String,
+        muted: bool,
     },
     RoomCreatedBroadcast {
         room_id: String,
+        card_sets: Vec<String>, // the base game plus the expansions the room plays with
+        speed: GameSpeed,
+        timers: SpeedTimers, // what the speed means on this server
     },
     RoomCreated {
         room_id: String,
@@ -74,69 +551,375 @@ pub enum ServerResponse {
     RoomDestroyed {
         room_id: String,
     },
+    // Removed by the lobby's sweep: every member was gone or nobody sent anything for too long
+    RoomExpired {
+        room_id: String,
+    },
     SelfJoined {
         player_name: String,
         player_id: String,
+        profile: Option<PlayerProfile>,
+        card_sets: Vec<String>, // same as in RoomCreatedBroadcast
+        speed: GameSpeed,
+        timers: SpeedTimers,
     },
     PlayerJoined {
         player_name: String,
         player_id: String,
+        profile: Option<PlayerProfile>,
+    },
+    ProfileRegistered {
+        token: String, // Keep this secret, it restores the profile on later connections
+        profile: PlayerProfile,
+    },
+    ProfileRestored {
+        profile: PlayerProfile,
+    },
+    FriendsPresence {
+        friends: Vec<FriendPresence>,
+    },
+    RoomInvite {
+        room_id: String,
+        room_name: String,
+        from: PlayerProfile,
+    },
+    GameSession {
+        game_id: String,
+        player_id: String,
+        session_token: String,
+    },
+    SessionResumed {
+        game_id: String,
+        player_id: String,
+    },
+    Leaderboard {
+        entries: Vec<PlayerRating>, // highest rating first
     },
     PlayerLeft {
         player_name: String,
     },
+    HostChanged {
+        player_id: String,
+    },
+    // To the rest of the room
+    PlayerKicked {
+        player_id: String,
+        player_name: String,
+        banned: bool,
+    },
+    // To the room, without the URL
+    RoomWebhookSet {
+        enabled: bool,
+    },
+    // To every member of a room the host destroyed
+    RoomClosed {
+        room_id: String,
+    },
+    // To the removed player
+    KickedFromRoom {
+        room_id: String,
+        banned: bool,
+    },
     PlayersReady {
         players_ready: HashSet<String>,
     },
+    // To the room whenever it moves along its lifecycle
+    RoomStateChanged {
+        room_id: String,
+        state: RoomState,
+    },
+    GameStartCountdown {
+        seconds_left: u64,
+    },
+    GameStartCancelled,
+    MulliganStart {
+        timeout_secs: u64, // undecided players keep their hand after this
+    },
+    MulliganDecided {
+        player_id: String,
+        keep: bool,
+    },
+    ItemActivated {
+        player_id: String,
+        item: Item, // state after paying the cost
+    },
+    ItemBought {
+        player_id: String,
+        item: Item,
+    },
+    LootPlayed {
+        player_id: String,
+        card: LootCard,
+    },
+    PlayerDamaged {
+        player_id: String,
+        source_id: Option<String>,
+        amount: u32,
+        current_health: u32,
+    },
+    PlayerDied {
+        player_id: String,
+        coins_lost: u32,
+        loot_discarded: Option<LootCard>,
+        item_destroyed: Option<Item>,
+    },
+    PlayerRespawned {
+        player_id: String,
+    },
+    TurnOrderChanged {
+        play_order: Vec<String>, // starting with the active player
+        reversed: bool,
+        pending: Vec<TurnModifier>, // applied, oldest first, as turns are handed on
+    },
+    // Only sent to the player who has to choose
+    ChoiceRequired {
+        choice_id: String,
+        prompt: String,
+        options: Vec<ChoiceOption>,
+        timeout_secs: u64, // the first option is taken after this
+        deadline: u64,     // same moment in unix millis
+    },
+    ChoiceMade {
+        choice_id: String,
+        player_id: String,
+        option: ChoiceOption,
+    },
+    TradeProposed {
+        trade: Trade,
+    },
+    TradeCompleted {
+        trade: Trade,
+    },
+    TradeDeclined {
+        trade_id: String,
+        player_id: String, // who declined, or the proposer withdrawing
+    },
+    // Every opponent answers with AcceptUndo or DeclineUndo
+    UndoRequested {
+        player_id: String,
+        action: String, // what would be taken back
+    },
+    UndoDeclined {
+        player_id: String, // who declined, or whose action moved the game on
+    },
+    // Followed by the board as it was before the action
+    ActionUndone {
+        player_id: String,
+        action: String,
+    },
+    // Out of the game for good; their cards went to the discard piles
+    PlayerConceded {
+        player_id: String,
+    },
+    // Every other player answers with AcceptDraw or DeclineDraw, the game ends once all accept.
+    // The offer lapses when the turn ends.
+    DrawOffered {
+        player_id: String,
+    },
+    DrawDeclined {
+        player_id: String, // who declined, or who offered it when it was withdrawn or lapsed
+    },
+    // Game events are refused and timers stand still until GameResumed
+    GamePaused {
+        player_id: String, // the host who paused
+    },
+    GameResumed {
+        player_id: String,
+    },
+    // An AFK player's turns pass and their choices are answered as soon as they come up
+    PlayerAfk {
+        player_id: String,
+        afk: bool, // false once they sent ImBack
+    },
+    // Die rolls for the first turn, highest wins and ties roll again; sent before RoomGameStart
+    FirstPlayerRolls {
+        rounds: Vec<HashMap<String, u8>>, // player_id -> roll
+        first_player_id: String,
+    },
     LobbyStartedGame {
         room_id: String,
     },
+    LobbyGameEnded {
+        room_id: String,
+    },
+    // To the organizer
+    TournamentCreated {
+        tournament_id: String,
+    },
+    // To everyone whenever a bracket changes, and for GetTournament
+    TournamentUpdate {
+        bracket: TournamentBracket,
+    },
+    // The game's actor crashed; it either restarts from the last event or the room returns to the lobby
+    GameCrashed {
+        game_id: String,
+        restarting: bool,
+    },
     //Broadcast on room enter
     RoomGameStart {
         turn_order: Vec<String>,
+        card_set_version: String,
     },
     //Broadcast for all players
     TurnPhaseChange {
-        player_id: String,
+        player_id: String, // who holds priority, the active player whenever a phase starts
         phase: TurnPhases,
+        active_player: String,
+        turn_deadline: Option<u64>, // unix millis the turn is passed for the player, None untimed
+        priority_deadline: Option<u64>, // same for the holder's priority, None while not waited on
     },
     PublicBoardState {
         loot_deck_size: usize,
         loot_discard: Vec<LootCard>,
         current_phase: TurnPhases,
         active_player: String,
-        players: HashMap<String, Player>,
+        players: HashMap<String, PublicPlayerView>, // no hands, those only go out in PrivateBoardState
+        items: HashMap<String, Vec<Item>>, // deprecated, the same as each player's items in players
+        curses: HashMap<String, Vec<Attachment>>, // player_id -> curses, oldest first
+        events: Vec<Attachment>,           // events in play on the board
+        shop: Vec<Item>,
+        shop_price: u32, // what BuyItem costs, the same for every item
+        deck_sizes: DeckSizes,
     },
     PrivateBoardState {
         hand: Vec<LootCard>, // Only this player's hand
+        #[serde(default)]
+        last_search: Option<DeckSearch>, // what this player's latest deck search turned up
     },
     GameEnded {
-        winner_id: String,
+        winner_id: Option<String>, // None for a draw
+        stats: GameStats,
+    },
+    GameSummary {
+        summary: GameSummary,
+    },
+    CardsReloaded {
+        version: String,
+    },
+    // cards is None when the client already has this version; names and texts are in locale,
+    // and each locale's catalog has a version of its own
+    CardCatalog {
+        version: String,
+        locale: Locale,
+        cards: Option<Vec<CatalogCard>>,
+    },
+    Glossary {
+        locale: Locale,
+        keywords: Vec<GlossaryEntry>,
+    },
+    // Full debug dump of a running game, for admins
+    GameStateDump {
+        game_id: String,
+        dump: serde_json::Value,
+        path: Option<String>, // where the dump was also written, when dump_dir is set
+    },
+    SimpleState {
+        state: SimpleStateView,
+    },
+    LegalActions {
+        actions: LegalActions,
+    },
+    DiscardPile {
+        deck_type: DeckKind,
+        cards: Vec<Card>, // the last card discarded first
+    },
+    // What's left of the running turn, priority and choice windows, None for those not timed
+    TimeRemaining {
+        turn: Option<WindowTimer>,
+        priority: Option<WindowTimer>,
+        choice: Option<WindowTimer>,
+        paused: bool, // the clock stands still, remaining_ms is what's left once resumed
+    },
+    EventLog {
+        entries: Vec<LogEntry>,
+    },
+    // Only to the player it is about: cards drawn and other hidden actions. Sent as they happen,
+    // in full on resuming a seat, and for GetPrivateLog.
+    PrivateLog {
+        entries: Vec<LogEntry>,
+    },
+    ProtocolInfo {
+        info: ProtocolInfo,
+    },
+    // One result per message of a BatchedClientMessages, in the order they were sent
+    BatchResults {
+        results: Vec<BatchedResult>,
     },
     Error {
         error_type: String, // "RoomFull", "PlayerNotFound" variant_name of errror
         message: String,
         code: u16,
-        // details: Option<serde_json::Value>, //Feature for clear frontend error handling(?)
+        details: Option<serde_json::Value>, // the error's fields, e.g. {"max_players": 4} for RoomFull
     },
 }
 
 impl ServerResponse {
     pub fn from_app_error(error: &AppError) -> Self {
+        Self::localized_error(error, Locale::default())
+    }
+
+    /// Only `message` is translated, `error_type` stays the same for every locale
+    pub fn localized_error(error: &AppError, locale: Locale) -> Self {
         ServerResponse::Error {
             error_type: error.variant_name().to_string(),
-            message: error.user_friendly_message(),
+            message: locale.error_message(error),
             code: error.status_code(),
+            details: error.details(),
         }
     }
 }
 
+// Clients from before 0.38.0 still send these, which serde used to ignore. They are dropped
+// rather than refused until the deprecation's sunset date in the changelog.
+const DEPRECATED_IDENTITY_FIELDS: [&str; 2] = ["player_id", "connection_id"];
+
 pub fn deserialize_message(json: &str) -> Result<ClientMessage, serde_json::Error> {
-    serde_json::from_str(json)
+    let error = match serde_json::from_str(json) {
+        Ok(message) => return Ok(message),
+        Err(error) => error,
+    };
+    let mut message: serde_json::Value = serde_json::from_str(json)?;
+    let mut refused = error.to_string();
+    loop {
+        let Some(body) = message
+            .as_object_mut()
+            .filter(|message| message.len() == 1)
+            .and_then(|message| message.values_mut().next())
+        else {
+            return Err(error);
+        };
+        let Some(fields) = body.as_object_mut() else {
+            return Err(error);
+        };
+        // Only fields the message doesn't declare itself, KickPlayer has a player_id of its own
+        if let Some(field) = DEPRECATED_IDENTITY_FIELDS
+            .into_iter()
+            .find(|field| refused.starts_with(&format!("unknown field `{}`", field)))
+        {
+            fields.remove(field);
+        } else if refused.starts_with("invalid type: map, expected unit")
+            && fields
+                .keys()
+                .all(|field| DEPRECATED_IDENTITY_FIELDS.contains(&field.as_str()))
+        {
+            // A message without fields, such as {"PlayerReady": {"player_id": "..."}}
+            *body = serde_json::Value::Null;
+        } else {
+            return Err(error);
+        }
+        match serde_json::from_value(message.clone()) {
+            Ok(message) => return Ok(message),
+            Err(next) => refused = next.to_string(),
+        }
+    }
 }
 
+/// A serialized response. Broadcasts share one allocation between every recipient's send queue.
+pub type SharedMessage = Arc<str>;
+
 // If this fails something is broken in the response code so it's correct to crash with .expect
-pub fn serialize_response(response: ServerResponse) -> String {
+pub fn serialize_response(response: ServerResponse) -> SharedMessage {
     serde_json::to_string(&response)
         .expect("Failed to serialize response - this should never happen with valid data")
+        .into()
 }
@@ -0,0 +1,272 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Instant};
+
+use crate::game::outbox::Outbox;
+use crate::network::messages::{serialize_response, ServerResponse, SharedMessage};
+use crate::{AppError, AppResult, ConnectionCommand};
+
+/// Sends responses to rooms and players by id. The lobby keeps room membership here as
+/// players join and leave, and games rebind a seat when its player resumes on a new
+/// connection, so callers never rebuild connection lists themselves. Clones share the maps.
+#[derive(Clone)]
+pub struct Messenger {
+    cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+    rooms: Arc<DashMap<String, HashSet<String>>>, // room_id -> member connection ids
+    players: Arc<DashMap<String, String>>,        // player_id -> connection_id
+}
+
+impl Messenger {
+    pub fn new(cmd_sender: mpsc::UnboundedSender<ConnectionCommand>) -> Self {
+        Self {
+            cmd_sender,
+            rooms: Arc::new(DashMap::new()),
+            players: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// The same rooms and players, sent through a channel of the game's own so busy games
+    /// don't queue behind each other. It closes once the game and every clone let go of it.
+    pub fn for_game(&self, game_id: &str) -> Self {
+        let (cmd_sender, commands) = mpsc::unbounded_channel();
+        let opened = self.cmd_sender.send(ConnectionCommand::OpenGameChannel {
+            game_id: game_id.to_string(),
+            commands,
+        });
+        Self {
+            // Without the command loop there is nothing to fan out to, keep the shared channel
+            cmd_sender: if opened.is_ok() {
+                cmd_sender
+            } else {
+                self.cmd_sender.clone()
+            },
+            rooms: self.rooms.clone(),
+            players: self.players.clone(),
+        }
+    }
+
+    /// For connection lifecycle commands, which aren't addressed by room or player
+    pub fn commands(&self) -> &mpsc::UnboundedSender<ConnectionCommand> {
+        &self.cmd_sender
+    }
+
+    /// Seat a player's connection in a room. A player already seated elsewhere in the room
+    /// (resuming on a new connection) has the old connection replaced.
+    pub fn join_room(&self, room_id: &str, player_id: &str, connection_id: &str) {
+        let previous = self
+            .players
+            .insert(player_id.to_string(), connection_id.to_string());
+        let mut members = self.rooms.entry(room_id.to_string()).or_default();
+        if let Some(previous) = previous {
+            members.remove(&previous);
+        }
+        members.insert(connection_id.to_string());
+    }
+
+    pub fn leave_room(&self, room_id: &str, player_id: &str, connection_id: &str) {
+        self.players
+            .remove_if(player_id, |_, bound| bound == connection_id);
+        if let Some(mut members) = self.rooms.get_mut(room_id) {
+            members.remove(connection_id);
+        }
+    }
+
+    /// Forget a room and its players' seats; returns the connections that were in it
+    pub fn close_room(&self, room_id: &str) -> Vec<String> {
+        let members = self
+            .rooms
+            .remove(room_id)
+            .map(|(_, members)| members)
+            .unwrap_or_default();
+        self.players
+            .retain(|_, connection_id| !members.contains(connection_id));
+        members.into_iter().collect()
+    }
+
+    /// Point a player at a connection without touching any room, e.g. for a game running
+    /// without a lobby
+    pub fn bind_player(&self, player_id: &str, connection_id: &str) {
+        self.players
+            .insert(player_id.to_string(), connection_id.to_string());
+    }
+
+    pub fn room_connections(&self, room_id: &str) -> AppResult<Vec<String>> {
+        self.rooms
+            .get(room_id)
+            .map(|members| members.iter().cloned().collect())
+            .ok_or(AppError::RoomNotFound {
+                room_id: room_id.to_string(),
+            })
+    }
+
+    pub fn player_connection(&self, player_id: &str) -> Option<String> {
+        self.players
+            .get(player_id)
+            .map(|connection_id| connection_id.clone())
+    }
+
+    pub fn send_to_connection(
+        &self,
+        connection_id: &str,
+        response: ServerResponse,
+    ) -> AppResult<()> {
+        self.cmd_sender.send(ConnectionCommand::SendToPlayer {
+            connection_id: connection_id.to_string(),
+            message: serialize_response(response),
+        })?;
+        Ok(())
+    }
+
+    pub fn send_to_connections(
+        &self,
+        connections_id: Vec<String>,
+        response: ServerResponse,
+    ) -> AppResult<()> {
+        self.send_serialized(connections_id, serialize_response(response))
+    }
+
+    /// For a response serialized once and sent to several audiences
+    pub fn send_serialized(
+        &self,
+        connections_id: Vec<String>,
+        message: SharedMessage,
+    ) -> AppResult<()> {
+        self.cmd_sender.send(ConnectionCommand::SendToPlayers {
+            connections_id,
+            message,
+        })?;
+        Ok(())
+    }
+
+    pub fn send_to_all(&self, response: ServerResponse) -> AppResult<()> {
+        self.cmd_sender.send(ConnectionCommand::SendToAll {
+            message: serialize_response(response),
+        })?;
+        Ok(())
+    }
+
+    pub fn broadcast_to_room(&self, room_id: &str, response: ServerResponse) -> AppResult<()> {
+        let connections_id = self.room_connections(room_id)?;
+        self.send_to_connections(connections_id, response)
+    }
+
+    pub fn send_to_player_id(&self, player_id: &str, response: ServerResponse) -> AppResult<()> {
+        let connection_id = self
+            .player_connection(player_id)
+            .ok_or(AppError::PlayerNotFound)?;
+        self.send_to_connection(&connection_id, response)
+    }
+
+    /// One message to every listed player that has a connection; the rest are skipped
+    pub fn send_to_player_ids(
+        &self,
+        player_ids: &[String],
+        response: ServerResponse,
+    ) -> AppResult<()> {
+        self.send_to_connections(self.player_connections(player_ids), response)
+    }
+
+    /// The connections of the listed players that have one
+    pub fn player_connections(&self, player_ids: &[String]) -> Vec<String> {
+        player_ids
+            .iter()
+            .filter_map(|player_id| self.player_connection(player_id))
+            .collect()
+    }
+}
+
+// A game sends through the outbox without waiting on delivery, a closed channel means the
+// server is shutting down and nobody is left to tell
+impl Outbox for Messenger {
+    fn bind_player(&self, player_id: &str, connection_id: &str) {
+        Messenger::bind_player(self, player_id, connection_id);
+    }
+
+    fn deliver_to_player(&self, player_id: &str, message: SharedMessage) {
+        if let Some(connection_id) = self.player_connection(player_id) {
+            let _ = self.cmd_sender.send(ConnectionCommand::SendToPlayer {
+                connection_id,
+                message,
+            });
+        }
+    }
+
+    fn deliver_to_players(&self, player_ids: &[String], message: SharedMessage) {
+        let _ = self.send_serialized(self.player_connections(player_ids), message);
+    }
+
+    fn deliver_to_connections(&self, connections_id: Vec<String>, message: SharedMessage) {
+        let _ = self.send_serialized(connections_id, message);
+    }
+
+    fn delayed(&self, delay: Duration) -> Box<dyn Outbox> {
+        Box::new(DelayedMessenger::spawn(self.clone(), delay))
+    }
+}
+
+// (when to send it, connections, message) waiting out the delay
+type DelayedSend = (Instant, Vec<String>, SharedMessage);
+
+/// Sends everything `delay` late, e.g. to spectators of a streamed game. Every message is held
+/// back by the same delay, so they arrive in the order they were sent. The queue outlives the
+/// game until it has been drained.
+struct DelayedMessenger {
+    messenger: Messenger,
+    delay: Duration,
+    queue: mpsc::UnboundedSender<DelayedSend>,
+}
+
+impl DelayedMessenger {
+    fn spawn(messenger: Messenger, delay: Duration) -> Self {
+        let (queue, mut receiver) = mpsc::unbounded_channel::<DelayedSend>();
+        let sender = messenger.clone();
+        tokio::spawn(async move {
+            while let Some((due, connections_id, message)) = receiver.recv().await {
+                sleep_until(due).await;
+                let _ = sender.send_serialized(connections_id, message);
+            }
+        });
+        Self {
+            messenger,
+            delay,
+            queue,
+        }
+    }
+}
+
+impl Outbox for DelayedMessenger {
+    fn bind_player(&self, player_id: &str, connection_id: &str) {
+        self.messenger.bind_player(player_id, connection_id);
+    }
+
+    fn deliver_to_player(&self, player_id: &str, message: SharedMessage) {
+        self.deliver_to_players(&[player_id.to_string()], message);
+    }
+
+    // Players are looked up now, whoever is seated when the message is held back gets it
+    fn deliver_to_players(&self, player_ids: &[String], message: SharedMessage) {
+        self.deliver_to_connections(self.messenger.player_connections(player_ids), message);
+    }
+
+    fn deliver_to_connections(&self, connections_id: Vec<String>, message: SharedMessage) {
+        let _ = self
+            .queue
+            .send((Instant::now() + self.delay, connections_id, message));
+    }
+
+    fn delayed(&self, delay: Duration) -> Box<dyn Outbox> {
+        self.messenger.delayed(self.delay + delay)
+    }
+}
+
+impl From<mpsc::error::SendError<ConnectionCommand>> for AppError {
+    fn from(_: mpsc::error::SendError<ConnectionCommand>) -> Self {
+        AppError::Internal {
+            message: "Failed to send connection command".to_string(),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::game::game_state::TurnPhases;
+use crate::AppError;
+
+/// The global limits from `ServerConfig`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Connections,
+    Rooms,
+    Games,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Limit::Connections => "connections",
+            Limit::Rooms => "rooms",
+            Limit::Games => "games",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct MetricsSnapshot {
+    pub connections_rejected: u64,
+    pub rooms_rejected: u64,
+    pub games_rejected: u64,
+    pub rooms: u64,
+    pub broadcasts_sent: u64,
+    pub send_failures: u64,
+    pub games_finished: u64,
+}
+
+/// Time spent in one turn phase, summed over every game
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PhaseTiming {
+    pub count: u64,
+    pub seconds: f64,
+}
+
+/// Values only the registry knows, read when the metrics are rendered
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LiveGauges {
+    pub games_running: usize,
+    pub connections: usize, // bots included
+}
+
+/// Server-wide counters, shared through the actor registry
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    connections_rejected: AtomicU64,
+    rooms_rejected: AtomicU64,
+    games_rejected: AtomicU64,
+    rooms: AtomicU64, // set by the lobby after every message
+    broadcasts_sent: AtomicU64,
+    send_failures: AtomicU64,
+    games_finished: AtomicU64,
+    phases: Mutex<HashMap<TurnPhases, PhaseTiming>>,
+}
+
+impl ServerMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a request turned away by `limit` and build the error sent back for it
+    pub fn limit_hit(&self, limit: Limit, max: usize) -> AppError {
+        let counter = match limit {
+            Limit::Connections => &self.connections_rejected,
+            Limit::Rooms => &self.rooms_rejected,
+            Limit::Games => &self.games_rejected,
+        };
+        let total = counter.fetch_add(1, Ordering::Relaxed) + 1;
+        log::warn!(
+            "🚧 Limit of {} {} reached ({} rejected so far)",
+            max,
+            limit,
+            total
+        );
+        AppError::ServerAtCapacity {
+            resource: limit.to_string(),
+            limit: max,
+        }
+    }
+
+    pub fn set_rooms(&self, rooms: usize) {
+        self.rooms.store(rooms as u64, Ordering::Relaxed);
+    }
+
+    /// A message queued for a whole room or the whole server
+    pub fn broadcast_sent(&self) {
+        self.broadcasts_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A message that couldn't be queued or written to a connection
+    pub fn send_failed(&self) {
+        self.send_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn game_finished(&self) {
+        self.games_finished.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_phase(&self, phase: TurnPhases, elapsed: Duration) {
+        let mut phases = self.phases.lock().expect("Phase metrics lock poisoned");
+        let timing = phases.entry(phase).or_default();
+        timing.count += 1;
+        timing.seconds += elapsed.as_secs_f64();
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            connections_rejected: self.connections_rejected.load(Ordering::Relaxed),
+            rooms_rejected: self.rooms_rejected.load(Ordering::Relaxed),
+            games_rejected: self.games_rejected.load(Ordering::Relaxed),
+            rooms: self.rooms.load(Ordering::Relaxed),
+            broadcasts_sent: self.broadcasts_sent.load(Ordering::Relaxed),
+            send_failures: self.send_failures.load(Ordering::Relaxed),
+            games_finished: self.games_finished.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn phase_timings(&self) -> HashMap<TurnPhases, PhaseTiming> {
+        self.phases
+            .lock()
+            .expect("Phase metrics lock poisoned")
+            .clone()
+    }
+
+    /// Everything in the Prometheus text exposition format, served on `/metrics`
+    pub fn render_prometheus(&self, live: LiveGauges) -> String {
+        let snapshot = self.snapshot();
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            let _ = writeln!(out, "{} {}", name, value);
+        };
+        metric(
+            "ifs_rooms",
+            "gauge",
+            "Rooms open in the lobby, including rooms in a game",
+            snapshot.rooms.to_string(),
+        );
+        metric(
+            "ifs_games_running",
+            "gauge",
+            "Games with a running actor",
+            live.games_running.to_string(),
+        );
+        metric(
+            "ifs_connected_players",
+            "gauge",
+            "Open connections, bots included",
+            live.connections.to_string(),
+        );
+        metric(
+            "ifs_games_finished_total",
+            "counter",
+            "Games played to the end",
+            snapshot.games_finished.to_string(),
+        );
+        metric(
+            "ifs_broadcasts_sent_total",
+            "counter",
+            "Messages sent to a whole room or the whole server",
+            snapshot.broadcasts_sent.to_string(),
+        );
+        metric(
+            "ifs_send_failures_total",
+            "counter",
+            "Messages that could not be queued or written to a connection",
+            snapshot.send_failures.to_string(),
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP ifs_limit_rejections_total Requests turned away by a server-wide limit"
+        );
+        let _ = writeln!(out, "# TYPE ifs_limit_rejections_total counter");
+        for (limit, value) in [
+            (Limit::Connections, snapshot.connections_rejected),
+            (Limit::Rooms, snapshot.rooms_rejected),
+            (Limit::Games, snapshot.games_rejected),
+        ] {
+            let _ = writeln!(
+                out,
+                "ifs_limit_rejections_total{{limit=\"{}\"}} {}",
+                limit, value
+            );
+        }
+
+        let mut phases: Vec<_> = self.phase_timings().into_iter().collect();
+        phases.sort_by_key(|(phase, _)| format!("{:?}", phase));
+        let _ = writeln!(
+            out,
+            "# HELP ifs_phase_duration_seconds Time games spent in each turn phase"
+        );
+        let _ = writeln!(out, "# TYPE ifs_phase_duration_seconds summary");
+        for (phase, timing) in phases {
+            let _ = writeln!(
+                out,
+                "ifs_phase_duration_seconds_sum{{phase=\"{:?}\"}} {}",
+                phase, timing.seconds
+            );
+            let _ = writeln!(
+                out,
+                "ifs_phase_duration_seconds_count{{phase=\"{:?}\"}} {}",
+                phase, timing.count
+            );
+        }
+        out
+    }
+}
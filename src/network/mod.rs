@@ -1,7 +1,37 @@
+#[cfg(feature = "server")]
+pub mod auth;
+#[cfg(feature = "server")]
+pub mod chat_filter;
+pub mod chat_history;
+#[cfg(feature = "server")]
+pub mod compression;
+#[cfg(feature = "server")]
 pub mod connection_commands;
+#[cfg(feature = "server")]
 pub mod connection_handler;
+#[cfg(feature = "server")]
 pub mod connection_manager;
+#[cfg(feature = "server")]
+pub mod frame_guard;
+#[cfg(feature = "server")]
+pub mod http_endpoint;
+pub mod locale;
 pub mod messages;
+#[cfg(feature = "server")]
+pub mod messenger;
+#[cfg(feature = "server")]
+pub mod metrics;
+#[cfg(feature = "server")]
+pub mod permissions;
+pub mod profiles;
+pub mod protocol;
+#[cfg(feature = "server")]
 pub mod reliable_messaging;
 pub mod room;
+#[cfg(feature = "server")]
 pub mod server;
+#[cfg(feature = "server")]
+pub mod tls;
+pub mod tournament;
+#[cfg(feature = "server")]
+pub mod webhooks;
@@ -0,0 +1,116 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::network::auth::AuthContext;
+use crate::network::messages::{ClientMessage, Secret};
+use crate::{AppError, AppResult};
+
+/// The key admin commands (`ReloadCards`, `DumpGameState`, `Announce`, ...) have to carry.
+/// A secret, so it is handed to the server builder apart from the ServerConfig `/info` shows.
+#[derive(Clone, Default)]
+pub struct AdminKey(Option<String>);
+
+impl AdminKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        let key = key.into();
+        Self((!key.is_empty()).then_some(key))
+    }
+
+    /// From IFS_ADMIN_KEY; admin commands are disabled when it is unset or empty
+    pub fn from_env() -> Self {
+        std::env::var("IFS_ADMIN_KEY").map_or_else(|_| Self::default(), Self::new)
+    }
+
+    pub fn check(&self, key: &Secret, action: &str) -> AppResult<()> {
+        match &self.0 {
+            Some(expected) if expected == key.expose() => Ok(()),
+            _ => Err(AppError::PermissionDenied {
+                action: action.to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Debug for AdminKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = if self.0.is_some() { "set" } else { "unset" };
+        f.debug_tuple("AdminKey").field(&state).finish()
+    }
+}
+
+/// What a connection is allowed to do, held by its ConnectionActor and updated by the lobby as
+/// it joins, hosts and leaves rooms. This is a coarse filter in front of the actors: the lobby
+/// and game still check the specifics (which room, whose turn).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Role {
+    Guest,  // connected, not in a room
+    Player, // seated in a room
+    Host,   // seated in a room it manages
+    Admin,  // granted by the `admin` JWT claim, never changes
+}
+
+impl Role {
+    /// Where a connection starts after the handshake
+    pub fn from_auth(auth: &AuthContext) -> Self {
+        if auth.admin {
+            Role::Admin
+        } else {
+            Role::Guest
+        }
+    }
+
+    /// The role after the lobby moved the connection to `role`; admins stay admins
+    pub fn assign(self, role: Role) -> Self {
+        if self == Role::Admin {
+            self
+        } else {
+            role
+        }
+    }
+
+    pub fn check(self, message: &ClientMessage) -> AppResult<()> {
+        if self.allows(message) {
+            Ok(())
+        } else {
+            Err(AppError::PermissionDenied {
+                action: action_name(message),
+            })
+        }
+    }
+
+    pub fn allows(self, message: &ClientMessage) -> bool {
+        if let ClientMessage::Game { message, .. } = message {
+            return self.allows(message);
+        }
+        match self {
+            Role::Admin => true,
+            Role::Host => true,
+            Role::Guest | Role::Player => !is_host_only(message),
+        }
+    }
+}
+
+// Managing the room: its members, its bots, the room itself, its webhook and pausing its game
+fn is_host_only(message: &ClientMessage) -> bool {
+    matches!(
+        message,
+        ClientMessage::DestroyRoom { .. }
+            | ClientMessage::AddBot { .. }
+            | ClientMessage::MutePlayer { .. }
+            | ClientMessage::UnmutePlayer { .. }
+            | ClientMessage::KickPlayer { .. }
+            | ClientMessage::BanPlayer { .. }
+            | ClientMessage::SetRoomWebhook { .. }
+            | ClientMessage::PauseGame
+            | ClientMessage::ResumeGame
+    )
+}
+
+// The variant name, as it appears on the wire
+fn action_name(message: &ClientMessage) -> String {
+    match serde_json::to_value(message) {
+        Ok(serde_json::Value::String(name)) => name,
+        Ok(serde_json::Value::Object(fields)) => fields.keys().next().cloned().unwrap_or_default(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,125 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::errors::validation::validate_player_name;
+use crate::{AppError, AppResult};
+
+/// Public identity shown to other players. The token that owns it is never broadcast.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PlayerProfile {
+    pub profile_id: String,
+    pub display_name: String,
+    pub avatar_id: Option<String>,
+}
+
+/// Where a friend is right now, as far as the lobby knows
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FriendPresence {
+    pub profile: PlayerProfile,
+    pub online: bool,
+    pub room_id: Option<String>, // None when not seated anywhere
+    pub in_game: bool,
+}
+
+#[derive(Default)]
+pub struct ProfileStore {
+    profiles: HashMap<String, PlayerProfile>, // token -> profile
+    friends: HashMap<String, HashSet<String>>, // token -> friends' profile ids, one way like a follow
+}
+
+impl ProfileStore {
+    pub fn new() -> Self {
+        Self {
+            profiles: HashMap::new(),
+            friends: HashMap::new(),
+        }
+    }
+
+    /// Create a profile and return the secret token that restores it on later connections
+    pub fn register(
+        &mut self,
+        display_name: String,
+        avatar_id: Option<String>,
+    ) -> AppResult<(String, PlayerProfile)> {
+        validate_player_name(&display_name)?;
+
+        let token = Uuid::new_v4().to_string();
+        let profile = PlayerProfile {
+            profile_id: Uuid::new_v4().to_string(),
+            display_name,
+            avatar_id,
+        };
+        self.profiles.insert(token.clone(), profile.clone());
+
+        Ok((token, profile))
+    }
+
+    pub fn update(
+        &mut self,
+        token: &str,
+        display_name: String,
+        avatar_id: Option<String>,
+    ) -> AppResult<PlayerProfile> {
+        validate_player_name(&display_name)?;
+
+        let profile = self
+            .profiles
+            .get_mut(token)
+            .ok_or(AppError::ProfileNotFound)?;
+        profile.display_name = display_name;
+        profile.avatar_id = avatar_id;
+
+        Ok(profile.clone())
+    }
+
+    pub fn get(&self, token: &str) -> Option<&PlayerProfile> {
+        self.profiles.get(token)
+    }
+
+    /// The token owning a public profile id, to find its connections
+    pub fn token_of(&self, profile_id: &str) -> Option<&str> {
+        self.profiles
+            .iter()
+            .find(|(_, profile)| profile.profile_id == profile_id)
+            .map(|(token, _)| token.as_str())
+    }
+
+    /// Adding yourself or someone already on the list changes nothing
+    pub fn add_friend(&mut self, token: &str, profile_id: &str) -> AppResult<()> {
+        let own_id = &self.get(token).ok_or(AppError::ProfileNotFound)?.profile_id;
+        if self.token_of(profile_id).is_none() {
+            return Err(AppError::ProfileNotFound);
+        }
+        if own_id != profile_id {
+            self.friends
+                .entry(token.to_string())
+                .or_default()
+                .insert(profile_id.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn remove_friend(&mut self, token: &str, profile_id: &str) {
+        if let Some(friends) = self.friends.get_mut(token) {
+            friends.remove(profile_id);
+        }
+    }
+
+    /// Friends' tokens and profiles, sorted by display name
+    pub fn friends(&self, token: &str) -> Vec<(&str, &PlayerProfile)> {
+        let mut friends: Vec<(&str, &PlayerProfile)> = self
+            .friends
+            .get(token)
+            .into_iter()
+            .flatten()
+            .filter_map(|profile_id| {
+                let friend_token = self.token_of(profile_id)?;
+                Some((friend_token, self.profiles.get(friend_token)?))
+            })
+            .collect();
+        friends.sort_by(|(_, a), (_, b)| a.display_name.cmp(&b.display_name));
+        friends
+    }
+}
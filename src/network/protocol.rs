@@ -0,0 +1,68 @@
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::network::messages::{ClientMessage, ServerResponse};
+
+// Embedded at build time; bump the version and add a changes entry on every protocol change
+const PROTOCOL_CHANGELOG: &str = include_str!("../data/protocol/changelog.json");
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Deprecation {
+    pub item: String,
+    pub replacement: Option<String>,
+    pub deprecated_in: String,
+    pub sunset_date: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ChangelogEntry {
+    pub version: String,
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Changelog {
+    version: String,
+    deprecations: Vec<Deprecation>,
+    changes: Vec<ChangelogEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProtocolInfo {
+    pub protocol_version: String,
+    pub supported_messages: Vec<String>,
+    pub deprecations: Vec<Deprecation>,
+    pub changelog: Vec<ChangelogEntry>,
+}
+
+static PROTOCOL_INFO: Lazy<ProtocolInfo> = Lazy::new(|| {
+    let changelog: Changelog = serde_json::from_str(PROTOCOL_CHANGELOG)
+        .expect("Embedded protocol changelog is invalid JSON");
+
+    ProtocolInfo {
+        protocol_version: changelog.version,
+        supported_messages: ClientMessage::VARIANT_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .collect(),
+        deprecations: changelog.deprecations,
+        changelog: changelog.changes,
+    }
+});
+
+pub fn protocol_info() -> &'static ProtocolInfo {
+    &PROTOCOL_INFO
+}
+
+/// JSON Schema for both directions of the protocol, generated from the serde types.
+/// `cargo run --bin export-schema` writes it out for client code generators.
+pub fn protocol_schema() -> serde_json::Value {
+    serde_json::json!({
+        "protocol_version": protocol_info().protocol_version,
+        "client_message": schemars::schema_for!(ClientMessage),
+        "server_response": schemars::schema_for!(ServerResponse),
+    })
+}
@@ -1,11 +1,15 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Instant;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::time::Instant;
 use uuid::Uuid;
 
-// Global sequence counter - simple and thread-safe
-static GLOBAL_SEQUENCE: AtomicU64 = AtomicU64::new(1);
+use crate::{AppError, AppResult};
+
+// Unacked messages are resent after 0.5s, 1s, 2s, ... and given up on after the last attempt
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(8);
+pub const MAX_SEND_ATTEMPTS: u32 = 5;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReliableMessage {
@@ -20,10 +24,21 @@ pub struct MessageAck {
     pub message_id: String,
 }
 
-pub fn create_reliable_message(payload: String) -> ReliableMessage {
+/// Sent once the server stops retransmitting a message, so the client can skip its sequence
+/// number and resync state instead of waiting for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryFailed {
+    pub message_id: String,
+    pub sequence: u64,
+    pub attempts: u32,
+}
+
+/// Sequences are per connection and start at 1, which is what the other side's
+/// `MessageReceiver` expects first
+pub fn create_reliable_message(sequence: u64, payload: String) -> ReliableMessage {
     ReliableMessage {
         id: Uuid::new_v4().to_string(),
-        sequence: GLOBAL_SEQUENCE.fetch_add(1, Ordering::SeqCst),
+        sequence,
         payload,
         timestamp: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -37,11 +52,97 @@ pub struct PendingMessage {
     pub message: ReliableMessage,
     pub send_time: Instant,
     pub retry_count: u32,
+    pub next_retry: Instant,
+}
+
+impl PendingMessage {
+    fn retry_delay(retry_count: u32) -> Duration {
+        INITIAL_RETRY_DELAY
+            .saturating_mul(2u32.saturating_pow(retry_count))
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+/// Reliable messages sent but not acked yet, with their retransmission schedule
+#[derive(Debug, Default)]
+pub struct ResendQueue {
+    pending: HashMap<String, PendingMessage>,
+}
+
+impl ResendQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a message that was just sent for the first time
+    pub fn track(&mut self, message: ReliableMessage) {
+        let now = Instant::now();
+        self.pending.insert(
+            message.id.clone(),
+            PendingMessage {
+                message,
+                send_time: now,
+                retry_count: 0,
+                next_retry: now + PendingMessage::retry_delay(0),
+            },
+        );
+    }
+
+    /// Returns true if the message was still waiting on this ack
+    pub fn ack(&mut self, message_id: &str) -> bool {
+        self.pending.remove(message_id).is_some()
+    }
+
+    /// When the next retransmission is due, if anything is pending
+    pub fn next_retry(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .map(|pending| pending.next_retry)
+            .min()
+    }
+
+    /// Messages due at `now`: the first list should be sent again, the second ran out of
+    /// attempts and is no longer tracked
+    pub fn take_due(&mut self, now: Instant) -> (Vec<ReliableMessage>, Vec<PendingMessage>) {
+        let due: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.next_retry <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut resend = Vec::new();
+        let mut failed = Vec::new();
+        for id in due {
+            let Some(mut pending) = self.pending.remove(&id) else {
+                continue;
+            };
+            pending.retry_count += 1;
+            if pending.retry_count >= MAX_SEND_ATTEMPTS {
+                failed.push(pending);
+                continue;
+            }
+            pending.next_retry = now + PendingMessage::retry_delay(pending.retry_count);
+            resend.push(pending.message.clone());
+            self.pending.insert(id, pending);
+        }
+        // Resend in the order they were first sent so the client buffers less
+        resend.sort_by_key(|message| message.sequence);
+        (resend, failed)
+    }
 }
 
 pub struct MessageReceiver {
     expected_sequence: u64,
     message_buffer: HashMap<u64, ReliableMessage>,
+    skipped: HashSet<u64>, // given up on by the sender, not waited for once reached
+    window: Option<u64>,   // how far past the expected sequence messages are buffered
+}
+
+impl Default for MessageReceiver {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MessageReceiver {
@@ -49,6 +150,17 @@ impl MessageReceiver {
         Self {
             expected_sequence: 1,
             message_buffer: HashMap::new(),
+            skipped: HashSet::new(),
+            window: None,
+        }
+    }
+
+    /// Buffers at most `window` sequences past the one it's waiting for. Messages further
+    /// ahead are refused unacked, so the sender tries them again once the gap is filled.
+    pub fn with_window(window: u64) -> Self {
+        Self {
+            window: Some(window),
+            ..Self::new()
         }
     }
 
@@ -56,7 +168,17 @@ impl MessageReceiver {
     pub fn receive_message(
         &mut self,
         message: ReliableMessage,
-    ) -> (MessageAck, Vec<ReliableMessage>) {
+    ) -> AppResult<(MessageAck, Vec<ReliableMessage>)> {
+        if let Some(window) = self.window {
+            if message.sequence > self.expected_sequence.saturating_add(window) {
+                return Err(AppError::MalformedFrame {
+                    reason: format!(
+                        "reliable message {} is more than {} past {}, the one awaited",
+                        message.sequence, window, self.expected_sequence
+                    ),
+                });
+            }
+        }
         let ack = MessageAck {
             message_id: message.id.clone(),
         };
@@ -64,25 +186,46 @@ impl MessageReceiver {
         // Always ack, but check for processing
         if message.sequence < self.expected_sequence {
             // Old message - already processed
-            return (ack, vec![]);
+            return Ok((ack, vec![]));
         }
 
         if message.sequence == self.expected_sequence {
             // Process this and any buffered consecutive ones
             let mut to_process = vec![message];
             self.expected_sequence += 1;
+            self.drain_buffered(&mut to_process);
 
-            // Drain consecutive buffered messages
-            while let Some(buffered) = self.message_buffer.remove(&self.expected_sequence) {
-                to_process.push(buffered);
-                self.expected_sequence += 1;
-            }
-
-            (ack, to_process)
+            Ok((ack, to_process))
         } else {
             // Future message - buffer it
             self.message_buffer.insert(message.sequence, message);
-            (ack, vec![])
+            Ok((ack, vec![]))
+        }
+    }
+
+    /// Stop waiting for a sequence number after a `DeliveryFailed`; returns the buffered
+    /// messages that were held back behind it
+    pub fn skip(&mut self, sequence: u64) -> Vec<ReliableMessage> {
+        if sequence < self.expected_sequence {
+            return vec![];
+        }
+        self.skipped.insert(sequence);
+        let mut to_process = Vec::new();
+        self.drain_buffered(&mut to_process);
+        to_process
+    }
+
+    // Drain consecutive buffered messages, stepping over skipped sequence numbers
+    fn drain_buffered(&mut self, to_process: &mut Vec<ReliableMessage>) {
+        loop {
+            if self.skipped.remove(&self.expected_sequence) {
+                self.expected_sequence += 1;
+            } else if let Some(buffered) = self.message_buffer.remove(&self.expected_sequence) {
+                to_process.push(buffered);
+                self.expected_sequence += 1;
+            } else {
+                break;
+            }
         }
     }
 }
@@ -1,7 +1,11 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+// Lives with the rules so they build without the server
+pub use crate::game::rules::{DuplicateNames, GameSpeed, RoomOptions, SpeedTimers};
 use crate::{AppError, AppResult};
 
 #[derive(Debug, Clone)]
@@ -13,51 +17,94 @@ pub struct Room {
     max_players: usize,
     min_players: usize,
     players_ready: HashSet<String>,
+    host_id: Option<String>, // Room creator, then the longest-seated player
+    seat_order: Vec<String>, // player_ids in join order
+    muted_players: HashSet<String>,
+    banned_connections: HashSet<String>,
+    banned_profiles: HashSet<String>, // profile ids, so a ban survives reconnecting
+    options: RoomOptions,
+    last_activity: Instant, // anything a member sent, for expiring abandoned rooms
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Where a room is in its life. It only moves along `Lobby -> Starting -> InGame -> Finished`,
+/// back to `Lobby` from `Starting` when the start is called off, and back to `Lobby` from
+/// `Finished` for the next game.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum RoomState {
-    Lobby, // Waiting for players
+    Lobby,    // Waiting for players
+    Starting, // Everyone is ready, the countdown runs and the ready set is locked
     InGame,
+    Finished, // The game is over, players are on their way back to the lobby
 }
 
 impl Room {
-    const DEFAULT_MAX_PLAYERS: usize = 4;
+    pub const DEFAULT_MAX_PLAYERS: usize = 4;
     const DEFAULT_MIN_PLAYERS: usize = 2;
 
-    pub fn new(name: String) -> Self {
+    pub fn new(name: String, options: RoomOptions) -> Self {
         Self {
-            id: "5edf4e4d-354e-4a84-a2b1-1a1a1f197b9f".to_string(), // TEMPORARY FOR TESTING
+            id: Uuid::new_v4().to_string(),
             name,
-            players: HashMap::new(), // Add-first-player handled in room_manager!
+            players: HashMap::new(), // The lobby actor adds the first player in create_room
             players_ready: HashSet::new(),
             state: RoomState::Lobby,
             max_players: Self::DEFAULT_MAX_PLAYERS,
             min_players: Self::DEFAULT_MIN_PLAYERS,
+            host_id: None,
+            seat_order: Vec::new(),
+            muted_players: HashSet::new(),
+            banned_connections: HashSet::new(),
+            banned_profiles: HashSet::new(),
+            options,
+            last_activity: Instant::now(),
         }
     }
 
-    pub fn add_player(&mut self, player_name: String) -> AppResult<String> {
+    /// Seats a player; returns their id and the name they were seated under, which differs
+    /// from `player_name` when the room suffixes duplicate names
+    pub fn add_player(&mut self, player_name: String) -> AppResult<(String, String)> {
         if self.players.len() >= self.max_players {
             return Err(AppError::RoomFull {
                 room_id: self.get_id(),
                 max_players: self.max_players,
             });
         }
-        if self.state != RoomState::Lobby {
+        // Joining during the countdown calls it off, the newcomer isn't ready yet
+        if !matches!(self.state, RoomState::Lobby | RoomState::Starting) {
             return Err(AppError::RoomInGame {
                 room_id: self.get_id(),
             });
         }
 
+        let player_name = self.seat_name(player_name)?;
+
         let new_player_id = Uuid::new_v4().to_string();
-        self.players.insert(new_player_id.clone(), player_name);
+        if self.players.is_empty() {
+            self.host_id = Some(new_player_id.clone());
+        }
+        self.players
+            .insert(new_player_id.clone(), player_name.clone());
+        self.seat_order.push(new_player_id.clone());
+
+        Ok((new_player_id, player_name))
+    }
 
-        Ok(new_player_id)
+    fn seat_name(&self, player_name: String) -> AppResult<String> {
+        let taken = |name: &str| self.players.values().any(|seated| seated == name);
+        if !taken(&player_name) {
+            return Ok(player_name);
+        }
+        match self.options.duplicate_names {
+            DuplicateNames::Reject => Err(AppError::PlayerNameTaken { player_name }),
+            DuplicateNames::Suffix => Ok((2..)
+                .map(|n| format!("{} ({})", player_name, n))
+                .find(|candidate| !taken(candidate))
+                .expect("A room has fewer players than suffixes")),
+        }
     }
 
     pub fn remove_player(&mut self, player_id: &str) -> AppResult<String> {
-        if self.state != RoomState::Lobby {
+        if !matches!(self.state, RoomState::Lobby | RoomState::Starting) {
             return Err(AppError::RoomInGame {
                 room_id: self.get_id(),
             });
@@ -67,23 +114,51 @@ impl Room {
             .remove(player_id)
             .ok_or(AppError::ConnectionNotInRoom)?;
         self.players_ready.remove(player_id); // Always safe to call
+        self.muted_players.remove(player_id);
+        self.seat_order.retain(|seated_id| seated_id != player_id);
+        if self.is_host(player_id) {
+            self.host_id = self.seat_order.first().cloned();
+        }
 
         Ok(player_name)
     }
 
     pub fn add_player_ready(&mut self, player_id: &str) -> AppResult<HashSet<String>> {
         if !self.players.contains_key(player_id) {
-            Err(AppError::ConnectionNotInRoom)
-        } else if self.players_ready.contains(player_id) {
-            Ok(self.players_ready.clone())
-        } else {
-            self.players_ready.insert(player_id.to_string());
-            Ok(self.players_ready.clone())
+            return Err(AppError::ConnectionNotInRoom);
         }
+        self.check_ready_toggle()?;
+        self.players_ready.insert(player_id.to_string());
+        Ok(self.players_ready.clone())
     }
 
+    pub fn remove_player_ready(&mut self, player_id: &str) -> AppResult<HashSet<String>> {
+        if !self.players.contains_key(player_id) {
+            return Err(AppError::ConnectionNotInRoom);
+        }
+        self.check_ready_toggle()?;
+        self.players_ready.remove(player_id);
+        Ok(self.players_ready.clone())
+    }
+
+    // The ready set only changes while the room waits in the lobby
+    fn check_ready_toggle(&self) -> AppResult<()> {
+        match self.state {
+            RoomState::Lobby => Ok(()),
+            RoomState::Starting => Err(AppError::RoomStarting {
+                room_id: self.get_id(),
+            }),
+            RoomState::InGame | RoomState::Finished => Err(AppError::RoomInGame {
+                room_id: self.get_id(),
+            }),
+        }
+    }
+
+    /// Everyone seated is ready and there are enough of them for a game
     pub fn can_start_game(&self) -> bool {
-        self.players_ready.len() == self.player_count() && self.state == RoomState::Lobby
+        self.players_ready.len() == self.player_count()
+            && self.player_count() >= self.min_players
+            && self.state == RoomState::Lobby
     }
 
     pub fn get_room_info(&self) -> Self {
@@ -95,10 +170,84 @@ impl Room {
             max_players: self.max_players,
             state: self.state.clone(),
             players_ready: self.players_ready.clone(),
+            host_id: self.host_id.clone(),
+            seat_order: self.seat_order.clone(),
+            muted_players: self.muted_players.clone(),
+            banned_connections: self.banned_connections.clone(),
+            banned_profiles: self.banned_profiles.clone(),
+            options: self.options.clone(),
+            last_activity: self.last_activity,
+        }
+    }
+    /// Move the room to `state`, refusing anything the room's lifecycle doesn't allow, such as
+    /// starting a game twice. Back in the lobby after a game, everyone readies up again.
+    pub fn transition(&mut self, state: RoomState) -> AppResult<()> {
+        self.check_transition(&state)?;
+        if self.state == RoomState::Finished {
+            self.players_ready.clear();
         }
+        self.state = state;
+        Ok(())
     }
-    pub fn set_state_in_game(&mut self) {
-        self.state = RoomState::InGame;
+    pub fn check_transition(&self, state: &RoomState) -> AppResult<()> {
+        use RoomState::*;
+        match (&self.state, state) {
+            (Lobby, Starting)
+            | (Starting, Lobby)
+            | (Starting, InGame)
+            | (InGame, Finished)
+            | (Finished, Lobby) => Ok(()),
+            (from, to) => Err(AppError::InvalidRoomTransition {
+                room_id: self.get_id(),
+                from: format!("{:?}", from),
+                to: format!("{:?}", to),
+            }),
+        }
+    }
+    pub fn state(&self) -> &RoomState {
+        &self.state
+    }
+    pub fn is_host(&self, player_id: &str) -> bool {
+        self.host_id.as_deref() == Some(player_id)
+    }
+    pub fn host_id(&self) -> Option<&str> {
+        self.host_id.as_deref()
+    }
+    pub fn is_in_game(&self) -> bool {
+        self.state == RoomState::InGame
+    }
+    pub fn set_player_muted(&mut self, player_id: &str, muted: bool) -> AppResult<()> {
+        if !self.players.contains_key(player_id) {
+            return Err(AppError::PlayerNotFound);
+        }
+        if muted {
+            self.muted_players.insert(player_id.to_string());
+        } else {
+            self.muted_players.remove(player_id);
+        }
+        Ok(())
+    }
+    pub fn ban(&mut self, connection_id: &str, profile_id: Option<&str>) {
+        self.banned_connections.insert(connection_id.to_string());
+        if let Some(profile_id) = profile_id {
+            self.banned_profiles.insert(profile_id.to_string());
+        }
+    }
+    pub fn is_banned(&self, connection_id: &str, profile_id: Option<&str>) -> bool {
+        self.banned_connections.contains(connection_id)
+            || profile_id.is_some_and(|profile_id| self.banned_profiles.contains(profile_id))
+    }
+    pub fn is_player_muted(&self, player_id: &str) -> bool {
+        self.muted_players.contains(player_id)
+    }
+    pub fn max_players(&self) -> usize {
+        self.max_players
+    }
+    pub fn min_players(&self) -> usize {
+        self.min_players
+    }
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= self.max_players
     }
     pub fn player_count(&self) -> usize {
         self.players.len()
@@ -109,6 +258,18 @@ impl Room {
     pub fn get_id(&self) -> String {
         self.id.clone()
     }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn options(&self) -> &RoomOptions {
+        &self.options
+    }
+    pub fn touch(&mut self) {
+        self.last_activity = Instant::now();
+    }
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity.elapsed()
+    }
     pub fn get_players_id(&self) -> Vec<String> {
         self.players.keys().cloned().collect()
     }
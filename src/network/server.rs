@@ -1,58 +1,385 @@
 use crate::actors::actor_registry::ActorRegistry;
+use crate::actors::game_actor::GameServices;
 use crate::actors::lobby_actor::{LobbyActor, LobbyMessage};
+use crate::actors::lobby_shards::{LobbyDirectory, LobbyShards};
+use crate::actors::tournament_actor::{TournamentActor, TournamentMessage};
+use crate::config::{ServerConfig, TimerConfig, TlsConfig};
+use crate::game::persistence::{FileGameStore, GameSnapshots, GameStore};
+use crate::game::ratings::{FileRatingStore, MemoryRatingStore, RatingStore, Ratings};
+use crate::game::replay::ReplayKey;
+use crate::game::stats::GameSummaries;
+use crate::network::auth::AuthMode;
+use crate::network::chat_filter::ChatFilter;
+use crate::network::connection_manager::ClientStream;
+use crate::network::http_endpoint;
+use crate::network::locale::ConnectionLocales;
+use crate::network::messenger::Messenger;
+use crate::network::metrics::{Limit, ServerMetrics};
+use crate::network::permissions::AdminKey;
+use crate::network::tls;
+use crate::network::webhooks::Webhooks;
+use futures_util::future::join_all;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
 use std::{error::Error, sync::Arc};
-use tokio::{net::TcpListener, sync::mpsc};
+use tokio::net::{lookup_host, TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
 use uuid::Uuid;
 
 use crate::{CommandProcessor, ConnectionCommand, ConnectionHandler, ConnectionManager};
 
+// Accept errors such as running out of file descriptors pass once some connections close
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
 pub struct WebsocketServer {
-    address: String,
+    config: Arc<ServerConfig>,
+    auth_mode: Arc<AuthMode>,
+    admin_key: AdminKey,
+    replay_key: Option<ReplayKey>,
+    game_store: Option<Arc<dyn GameStore>>,
+    ratings: Option<Arc<Ratings>>,
 }
 
-impl WebsocketServer {
-    pub fn new(address: &str) -> Self {
+/// Assembles a server for embedding, starting from `ServerConfig::default()`
+pub struct WebsocketServerBuilder {
+    config: ServerConfig,
+    auth_mode: AuthMode,
+    admin_key: AdminKey,
+    replay_key: Option<ReplayKey>,
+    game_store: Option<Arc<dyn GameStore>>,
+    rating_store: Option<Box<dyn RatingStore>>,
+}
+
+impl WebsocketServerBuilder {
+    fn new() -> Self {
         Self {
-            address: address.to_string(),
+            config: ServerConfig::default(),
+            auth_mode: AuthMode::Disabled,
+            admin_key: AdminKey::default(),
+            replay_key: None,
+            game_store: None,
+            rating_store: None,
         }
     }
 
+    /// Replace every setting at once, e.g. with `ServerConfig::load()`
+    pub fn config(mut self, config: ServerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn bind_address(mut self, address: &str) -> Self {
+        self.config.bind_address = address.to_string();
+        self
+    }
+
+    /// Listen on another address as well, sharing the same rooms and games
+    pub fn extra_bind_address(mut self, address: &str) -> Self {
+        self.config.extra_bind_addresses.push(address.to_string());
+        self
+    }
+
+    pub fn max_rooms(mut self, max_rooms: usize) -> Self {
+        self.config.max_rooms = Some(max_rooms);
+        self
+    }
+
+    pub fn max_connections(mut self, max_connections: usize) -> Self {
+        self.config.max_connections = Some(max_connections);
+        self
+    }
+
+    pub fn max_games(mut self, max_games: usize) -> Self {
+        self.config.max_games = Some(max_games);
+        self
+    }
+
+    pub fn timers(mut self, timers: TimerConfig) -> Self {
+        self.config.timers = timers;
+        self
+    }
+
+    pub fn tls(mut self, cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        self.config.tls = Some(TlsConfig {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+        });
+        self
+    }
+
+    pub fn auth(mut self, auth_mode: AuthMode) -> Self {
+        self.auth_mode = auth_mode;
+        self
+    }
+
+    /// Enables the admin commands for clients sending this key
+    pub fn admin_key(mut self, admin_key: AdminKey) -> Self {
+        self.admin_key = admin_key;
+        self
+    }
+
+    /// Signs every game's replay with this key, and checks saved games against it when they
+    /// are restored
+    pub fn replay_key(mut self, replay_key: Option<ReplayKey>) -> Self {
+        self.replay_key = replay_key;
+        self
+    }
+
+    /// Custom storage for running games; takes precedence over `game_store_dir`
+    pub fn game_store(mut self, game_store: Arc<dyn GameStore>) -> Self {
+        self.game_store = Some(game_store);
+        self
+    }
+
+    /// Custom storage for player ratings; takes precedence over `ratings_path`
+    pub fn rating_store(mut self, rating_store: Box<dyn RatingStore>) -> Self {
+        self.rating_store = Some(rating_store);
+        self
+    }
+
+    pub fn build(self) -> WebsocketServer {
+        WebsocketServer {
+            config: Arc::new(self.config),
+            auth_mode: Arc::new(self.auth_mode),
+            admin_key: self.admin_key,
+            replay_key: self.replay_key,
+            game_store: self.game_store,
+            ratings: self
+                .rating_store
+                .map(|rating_store| Arc::new(Ratings::new(rating_store))),
+        }
+    }
+}
+
+impl WebsocketServer {
+    pub fn builder() -> WebsocketServerBuilder {
+        WebsocketServerBuilder::new()
+    }
+
+    pub fn new(address: &str) -> Self {
+        Self::builder().bind_address(address).build()
+    }
+
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    /// Serve until SIGINT/SIGTERM, then shut down gracefully
     pub async fn run(&self) -> Result<(), Box<dyn Error>> {
-        let listener = TcpListener::bind(&self.address).await?;
-        let mut connection_manager = ConnectionManager::new();
+        self.run_until(shutdown_signal()).await
+    }
+
+    /// Serve until `shutdown` completes. The listeners are closed first, then everyone is told
+    /// the server is going away, running games finish their queued events (and are written
+    /// to `snapshot_dir` if set), sockets are closed and every actor task is awaited.
+    pub async fn run_until(
+        &self,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<(), Box<dyn Error>> {
+        // Embedders hand the builder a config that never went through `load`
+        self.config.validate()?;
+        let tls_acceptor = match &self.config.tls {
+            Some(tls_config) => Some(tls::load_acceptor(tls_config)?),
+            None => None,
+        };
+        let game_store: Option<Arc<dyn GameStore>> =
+            match (&self.game_store, &self.config.game_store_dir) {
+                (Some(game_store), _) => Some(game_store.clone()),
+                (None, Some(dir)) => Some(Arc::new(FileGameStore::new(dir)?)),
+                (None, None) => None,
+            };
+        let ratings = match (&self.ratings, &self.config.ratings_path) {
+            (Some(ratings), _) => ratings.clone(),
+            (None, Some(path)) => Arc::new(Ratings::new(Box::new(FileRatingStore::new(path)?))),
+            (None, None) => Arc::new(Ratings::new(Box::new(MemoryRatingStore::new()))),
+        };
+        let (lobby_senders, lobby_receivers): (Vec<_>, Vec<_>) =
+            (0..self.config.lobby_shards.max(1))
+                .map(|_| mpsc::channel::<LobbyMessage>(self.config.mailboxes.lobby))
+                .unzip();
+        let lobby = LobbyShards::new(
+            lobby_senders,
+            Arc::new(LobbyDirectory::new(ChatFilter::from_config(
+                &self.config.chat,
+                self.config.timers.chat_rate_window(),
+            ))),
+        );
+        // Brackets see a fraction of the lobby's traffic, the same bound is plenty
+        let (tournament_sender, tournament_receiver) =
+            mpsc::channel::<TournamentMessage>(self.config.mailboxes.lobby);
+        let metrics = Arc::new(ServerMetrics::new());
+        let services = GameServices {
+            game_store,
+            snapshots: Arc::new(GameSnapshots::new()),
+            ratings,
+            summaries: Arc::new(GameSummaries::new()),
+            lobby: lobby.clone(),
+            game_config: self.config.game.clone(),
+            locales: Arc::new(ConnectionLocales::new()),
+            metrics: metrics.clone(),
+            webhooks: Webhooks::start(self.config.webhooks.clone()),
+            replay_dir: self.config.replay_dir.clone(),
+            dump_dir: self.config.dump_dir.clone(),
+            replay_key: self.replay_key.clone(),
+        };
+        let mut listeners = Vec::new();
+        for address in self.config.bind_addresses() {
+            let listener = bind_listener(address)
+                .await
+                .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+            println!(
+                "🌐 Listening on {}{}",
+                address,
+                if tls_acceptor.is_some() { " (TLS)" } else { "" }
+            );
+            listeners.push((address.to_string(), listener));
+        }
+        let connection_manager = ConnectionManager::new(self.config.outbound.clone(), metrics);
 
         // Create channel for connection management commands
         let (cmd_sender, mut cmd_receiver) = mpsc::unbounded_channel::<ConnectionCommand>();
 
-        tokio::spawn(async move {
+        let command_task = tokio::spawn(async move {
             while let Some(command) = cmd_receiver.recv().await {
-                let processed_command =
-                    CommandProcessor::process_command(command, &mut connection_manager).await;
-                if processed_command.is_err() {
-                    return;
+                // A failed send only concerns that connection, keep serving everyone else
+                if let Err(e) =
+                    CommandProcessor::process_command(command, &connection_manager).await
+                {
+                    eprintln!("❌ Connection command failed: {}", e);
                 }
             }
         });
 
-        let (lobby_sender, lobby_receiver) = mpsc::unbounded_channel::<LobbyMessage>();
-        let actor_registry = Arc::new(ActorRegistry::new(lobby_sender));
+        let actor_registry = Arc::new(ActorRegistry::new(
+            lobby,
+            tournament_sender,
+            services,
+            self.config.mailboxes.clone(),
+            Messenger::new(cmd_sender.clone()),
+        ));
+        actor_registry.restore_games();
 
-        let mut lobby_actor = LobbyActor::new(actor_registry.clone(), cmd_sender.clone());
+        let metrics_task = match &self.config.metrics_address {
+            Some(address) => {
+                let listener = bind_listener(address)
+                    .await
+                    .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+                println!("📈 Serving metrics on http://{}/metrics", address);
+                Some(tokio::spawn(http_endpoint::serve(
+                    listener,
+                    actor_registry.clone(),
+                    http_endpoint::info(&self.config, &self.auth_mode),
+                )))
+            }
+            None => None,
+        };
 
-        tokio::spawn(async move {
-            lobby_actor.run(lobby_receiver).await;
+        let lobby_tasks: Vec<_> = lobby_receivers
+            .into_iter()
+            .enumerate()
+            .map(|(shard, lobby_receiver)| {
+                let mut lobby_actor = LobbyActor::new(
+                    shard,
+                    self.config.clone(),
+                    self.admin_key.clone(),
+                    actor_registry.clone(),
+                );
+                tokio::spawn(async move {
+                    lobby_actor.run(lobby_receiver).await;
+                })
+            })
+            .collect();
+
+        let mut tournament_actor = TournamentActor::new(actor_registry.clone());
+        let tournament_task = tokio::spawn(async move {
+            tournament_actor.run(tournament_receiver).await;
         });
 
-        while let Ok((stream, addr)) = listener.accept().await {
+        // Each listener accepts on its own task and hands the streams over here, so every
+        // connection goes through the same capacity check and ends up in the same set
+        let (accepted_sender, mut accepted_receiver) = mpsc::channel::<TcpStream>(listeners.len());
+        let mut accept_tasks = JoinSet::new();
+        for (address, listener) in listeners {
+            let accepted_sender = accepted_sender.clone();
+            accept_tasks.spawn(async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, _addr)) => {
+                            if accepted_sender.send(stream).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Failed to accept connection on {}: {}", address, e);
+                            tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                        }
+                    }
+                }
+            });
+        }
+        drop(accepted_sender);
+
+        let mut connection_tasks = JoinSet::new();
+        tokio::pin!(shutdown);
+
+        loop {
+            let stream = tokio::select! {
+                // Closed once every listener has failed
+                accepted = accepted_receiver.recv() => match accepted {
+                    Some(stream) => stream,
+                    None => break,
+                },
+                // Reap finished connections so the set doesn't grow for the server's lifetime
+                Some(_) = connection_tasks.join_next(), if !connection_tasks.is_empty() => continue,
+                _ = &mut shutdown => break,
+            };
             let connection_id = Uuid::new_v4().to_string();
 
+            while connection_tasks.try_join_next().is_some() {}
+            let over_capacity = self
+                .config
+                .max_connections
+                .filter(|max_connections| connection_tasks.len() >= *max_connections)
+                .map(|max_connections| {
+                    actor_registry
+                        .metrics()
+                        .limit_hit(Limit::Connections, max_connections)
+                });
+
+            let tls_acceptor = tls_acceptor.clone();
+            let auth_mode = self.auth_mode.clone();
+            let inbound = self.config.inbound.clone();
             let actor_registry = actor_registry.clone();
             let cmd_sender = cmd_sender.clone();
 
-            tokio::spawn(async move {
+            connection_tasks.spawn(async move {
+                let stream: ClientStream = match tls_acceptor {
+                    Some(acceptor) => match acceptor.accept(stream).await {
+                        Ok(tls_stream) => Box::new(tls_stream),
+                        Err(e) => {
+                            eprintln!("❌ TLS handshake failed: {}", e);
+                            return;
+                        }
+                    },
+                    None => Box::new(stream),
+                };
+
+                if let Some(error) = over_capacity {
+                    ConnectionHandler::reject_connection(stream, connection_id, error).await;
+                    return;
+                }
+
                 if let Err(e) = ConnectionHandler::handle_connection(
                     stream,
                     connection_id,
+                    auth_mode,
+                    inbound,
                     actor_registry,
                     cmd_sender,
                 )
@@ -63,6 +390,106 @@ impl WebsocketServer {
             });
         }
 
+        accept_tasks.abort_all();
+        while accept_tasks.join_next().await.is_some() {}
+        if let Some(metrics_task) = metrics_task {
+            metrics_task.abort();
+        }
+        println!("🛑 Shutting down, no longer accepting connections");
+        let drain_time = self.config.timers.shutdown_drain();
+
+        let _ = actor_registry.deliver_lobby_message(LobbyMessage::Shutdown);
+        if timeout(drain_time, join_all(lobby_tasks)).await.is_err() {
+            eprintln!("❌ Lobby actor did not stop in time");
+        }
+        let _ = actor_registry.deliver_tournament_message(TournamentMessage::Shutdown);
+        if timeout(drain_time, tournament_task).await.is_err() {
+            eprintln!("❌ Tournament actor did not stop in time");
+        }
+
+        let game_tasks = actor_registry.shutdown_game_actors(self.config.snapshot_dir.clone());
+        if timeout(drain_time, join_all(game_tasks)).await.is_err() {
+            eprintln!("❌ Some games did not finish their queued events in time");
+        }
+
+        let _ = cmd_sender.send(ConnectionCommand::CloseAll);
+        drop(cmd_sender);
+        // The registry's messenger holds a sender too
+        drop(actor_registry);
+        let connections_closed = async { while connection_tasks.join_next().await.is_some() {} };
+        if timeout(drain_time, connections_closed).await.is_err() {
+            eprintln!("❌ Some clients did not acknowledge the close, dropping them");
+            connection_tasks.abort_all();
+        }
+
+        // The command loop ends once every actor holding a sender has stopped
+        if timeout(drain_time, command_task).await.is_err() {
+            eprintln!("❌ Connection command loop did not stop in time");
+        }
+
+        println!("👋 Server stopped");
         Ok(())
     }
 }
+
+/// Like `TcpListener::bind`, except that IPv6 sockets don't also take IPv4 traffic, so
+/// "0.0.0.0:8080" and "[::]:8080" can be listened on side by side
+async fn bind_listener(address: &str) -> io::Result<TcpListener> {
+    let mut last_error = None;
+    for socket_address in lookup_host(address).await? {
+        match listen_on(socket_address) {
+            Ok(listener) => return Ok(listener),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "address resolved to nothing")
+    }))
+}
+
+fn listen_on(address: SocketAddr) -> io::Result<TcpListener> {
+    let socket = Socket::new(
+        Domain::for_address(address),
+        Type::STREAM,
+        Some(Protocol::TCP),
+    )?;
+    if address.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    // Same as tokio, restarting the server shouldn't wait for old sockets in TIME_WAIT
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(1024)?;
+    TcpListener::from_std(socket.into())
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            eprintln!("❌ Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                eprintln!("❌ Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,55 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use tokio_rustls::rustls::{self, Certificate, PrivateKey};
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::TlsConfig;
+use crate::{AppError, AppResult};
+
+/// Build the acceptor wrapping every incoming TCP stream when TLS is configured
+pub fn load_acceptor(tls: &TlsConfig) -> AppResult<TlsAcceptor> {
+    let config_error = |message: String| AppError::ConfigError { message };
+
+    let mut cert_reader = BufReader::new(
+        File::open(&tls.cert_path)
+            .map_err(|e| config_error(format!("{}: {}", tls.cert_path.display(), e)))?,
+    );
+    let certs: Vec<Certificate> = rustls_pemfile::certs(&mut cert_reader)
+        .map_err(|e| config_error(format!("{}: {}", tls.cert_path.display(), e)))?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+
+    let key = read_private_key(tls)?;
+
+    let server_config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| config_error(format!("TLS: {}", e)))?;
+
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+fn read_private_key(tls: &TlsConfig) -> AppResult<PrivateKey> {
+    let open = || {
+        File::open(&tls.key_path)
+            .map(BufReader::new)
+            .map_err(|e| AppError::ConfigError {
+                message: format!("{}: {}", tls.key_path.display(), e),
+            })
+    };
+
+    let pkcs8_keys = rustls_pemfile::pkcs8_private_keys(&mut open()?).unwrap_or_default();
+    let rsa_keys = rustls_pemfile::rsa_private_keys(&mut open()?).unwrap_or_default();
+
+    pkcs8_keys
+        .into_iter()
+        .chain(rsa_keys)
+        .next()
+        .map(PrivateKey)
+        .ok_or_else(|| AppError::ConfigError {
+            message: format!("{}: no private key found", tls.key_path.display()),
+        })
+}
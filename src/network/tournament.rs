@@ -0,0 +1,295 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::errors::validation::{validate_player_name, validate_room_name};
+use crate::network::room::RoomOptions;
+use crate::{AppError, AppResult};
+
+const MIN_ENTRANTS: usize = 2;
+const MAX_ENTRANTS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum TournamentState {
+    Registering, // open for RegisterForTournament until the organizer starts it
+    Running,
+    Finished,
+}
+
+/// One pairing of a round, as shown to clients
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BracketMatch {
+    pub match_id: String,
+    pub players: Vec<String>, // entrant names, higher seed first; a single one is a bye
+    pub room_id: Option<String>, // while its game is running, for spectators
+    pub winner: Option<String>,
+}
+
+/// The whole bracket, sent to everyone in TournamentUpdate whenever it changes
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TournamentBracket {
+    pub tournament_id: String,
+    pub name: String,
+    pub state: TournamentState,
+    pub entrants: Vec<String>, // names in seed order, i.e. registration order
+    pub rounds: Vec<Vec<BracketMatch>>, // first round first
+    pub champion: Option<String>,
+}
+
+/// A pairing the lobby has to seat in a room of its own
+#[derive(Debug, Clone)]
+pub struct MatchSeats {
+    pub match_id: String,
+    pub room_name: String,
+    pub seats: Vec<(String, String)>, // (connection_id, player_name), higher seed first
+}
+
+#[derive(Debug, Clone)]
+struct Entrant {
+    connection_id: String,
+    player_name: String,
+}
+
+#[derive(Debug, Clone)]
+struct Pairing {
+    match_id: String,
+    seats: Vec<usize>, // entrant indices
+    room_id: Option<String>,
+    winner: Option<usize>,
+}
+
+/// A single elimination bracket. Entrants are seeded in the order they registered, paired
+/// off in that order each round, and an odd one out gets a bye.
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    id: String,
+    name: String,
+    organizer: String, // connection_id of whoever created it, the only one who can start it
+    options: RoomOptions,
+    entrants: Vec<Entrant>,
+    rounds: Vec<Vec<Pairing>>,
+    state: TournamentState,
+}
+
+impl Tournament {
+    pub fn new(name: String, organizer: String, options: RoomOptions) -> AppResult<Self> {
+        validate_room_name(&name).map_err(|e| AppError::InvalidTournament {
+            reason: e.to_string(),
+        })?;
+        options.validate()?;
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            organizer,
+            options,
+            entrants: Vec::new(),
+            rounds: Vec::new(),
+            state: TournamentState::Registering,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn options(&self) -> &RoomOptions {
+        &self.options
+    }
+
+    pub fn register(&mut self, connection_id: &str, player_name: String) -> AppResult<()> {
+        validate_player_name(&player_name)?;
+        self.require_registering()?;
+        if self.entrant(connection_id).is_some() {
+            return Err(self.invalid("you are already registered"));
+        }
+        if self
+            .entrants
+            .iter()
+            .any(|entrant| entrant.player_name == player_name)
+        {
+            return Err(AppError::PlayerNameTaken { player_name });
+        }
+        if self.entrants.len() >= MAX_ENTRANTS {
+            return Err(self.invalid(&format!("it is full ({} entrants)", MAX_ENTRANTS)));
+        }
+        self.entrants.push(Entrant {
+            connection_id: connection_id.to_string(),
+            player_name,
+        });
+        Ok(())
+    }
+
+    pub fn withdraw(&mut self, connection_id: &str) -> AppResult<()> {
+        self.require_registering()?;
+        let index = self
+            .entrant(connection_id)
+            .ok_or_else(|| self.invalid("you aren't registered"))?;
+        self.entrants.remove(index);
+        Ok(())
+    }
+
+    /// Organizer only. Draws the first round and returns the pairings to seat. Once running,
+    /// starting again returns the pairings that never got a room, e.g. because the server was
+    /// at capacity.
+    pub fn start(&mut self, connection_id: &str) -> AppResult<Vec<MatchSeats>> {
+        if connection_id != self.organizer {
+            return Err(AppError::PermissionDenied {
+                action: "StartTournament".to_string(),
+            });
+        }
+        match self.state {
+            TournamentState::Registering => {}
+            TournamentState::Running => return Ok(self.unseated()),
+            TournamentState::Finished => return Err(self.invalid("it is already over")),
+        }
+        if self.entrants.len() < MIN_ENTRANTS {
+            return Err(self.invalid(&format!("it needs at least {} entrants", MIN_ENTRANTS)));
+        }
+        self.state = TournamentState::Running;
+        self.next_round((0..self.entrants.len()).collect());
+        Ok(self.unseated())
+    }
+
+    pub fn match_started(&mut self, match_id: &str, room_id: String) {
+        if let Some(pairing) = self.pairing_mut(match_id) {
+            pairing.room_id = Some(room_id);
+        }
+    }
+
+    /// Advance the winner, a connection id. Without one, e.g. after a draw, a crashed game or
+    /// nobody showing up, the higher seed goes through. Returns the next round's pairings to
+    /// seat once this finished the round.
+    pub fn record_result(&mut self, match_id: &str, winner: Option<&str>) -> Vec<MatchSeats> {
+        let winner = winner.and_then(|connection_id| self.entrant(connection_id));
+        let Some(pairing) = self.pairing_mut(match_id) else {
+            return Vec::new();
+        };
+        if pairing.winner.is_some() {
+            return Vec::new();
+        }
+        pairing.room_id = None;
+        pairing.winner = winner
+            .filter(|winner| pairing.seats.contains(winner))
+            .or(pairing.seats.first().copied());
+
+        let round = self.rounds.last().map(Vec::as_slice).unwrap_or_default();
+        let winners: Option<Vec<usize>> = round.iter().map(|pairing| pairing.winner).collect();
+        match winners {
+            Some(winners) if winners.len() == 1 => {
+                self.state = TournamentState::Finished;
+                Vec::new()
+            }
+            Some(winners) => {
+                self.next_round(winners);
+                self.unseated()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    pub fn bracket(&self) -> TournamentBracket {
+        let name_of = |index: &usize| self.entrants[*index].player_name.clone();
+        let rounds = self
+            .rounds
+            .iter()
+            .map(|round| {
+                round
+                    .iter()
+                    .map(|pairing| BracketMatch {
+                        match_id: pairing.match_id.clone(),
+                        players: pairing.seats.iter().map(name_of).collect(),
+                        room_id: pairing.room_id.clone(),
+                        winner: pairing.winner.as_ref().map(name_of),
+                    })
+                    .collect()
+            })
+            .collect();
+        let champion = match self.state {
+            TournamentState::Finished => self
+                .rounds
+                .last()
+                .and_then(|round| round.first())
+                .and_then(|pairing| pairing.winner.as_ref())
+                .map(name_of),
+            _ => None,
+        };
+        TournamentBracket {
+            tournament_id: self.id.clone(),
+            name: self.name.clone(),
+            state: self.state,
+            entrants: self
+                .entrants
+                .iter()
+                .map(|entrant| entrant.player_name.clone())
+                .collect(),
+            rounds,
+            champion,
+        }
+    }
+
+    // Byes win straight away
+    fn next_round(&mut self, seeds: Vec<usize>) {
+        let round = self.rounds.len();
+        let pairings = seeds
+            .chunks(2)
+            .enumerate()
+            .map(|(index, seats)| Pairing {
+                match_id: format!("r{}m{}", round + 1, index + 1),
+                seats: seats.to_vec(),
+                room_id: None,
+                winner: (seats.len() == 1).then(|| seats[0]),
+            })
+            .collect();
+        self.rounds.push(pairings);
+    }
+
+    // Pairings of the current round still waiting for a room
+    fn unseated(&self) -> Vec<MatchSeats> {
+        let round = self.rounds.len();
+        self.rounds
+            .last()
+            .into_iter()
+            .flatten()
+            .filter(|pairing| pairing.winner.is_none() && pairing.room_id.is_none())
+            .map(|pairing| MatchSeats {
+                match_id: pairing.match_id.clone(),
+                room_name: format!("{}, round {}", self.name, round),
+                seats: pairing
+                    .seats
+                    .iter()
+                    .map(|index| {
+                        let entrant = &self.entrants[*index];
+                        (entrant.connection_id.clone(), entrant.player_name.clone())
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn pairing_mut(&mut self, match_id: &str) -> Option<&mut Pairing> {
+        self.rounds
+            .last_mut()?
+            .iter_mut()
+            .find(|pairing| pairing.match_id == match_id)
+    }
+
+    fn entrant(&self, connection_id: &str) -> Option<usize> {
+        self.entrants
+            .iter()
+            .position(|entrant| entrant.connection_id == connection_id)
+    }
+
+    fn require_registering(&self) -> AppResult<()> {
+        if self.state == TournamentState::Registering {
+            Ok(())
+        } else {
+            Err(self.invalid("registration is closed"))
+        }
+    }
+
+    fn invalid(&self, reason: &str) -> AppError {
+        AppError::InvalidTournament {
+            reason: format!("{}: {}", self.name, reason),
+        }
+    }
+}
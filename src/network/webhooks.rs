@@ -0,0 +1,397 @@
+use dashmap::DashMap;
+use serde::Serialize;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{lookup_host, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout};
+use tokio_rustls::rustls::{self, OwnedTrustAnchor, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+
+use crate::config::WebhookConfig;
+use crate::{AppError, AppResult};
+
+const MAX_URL_LENGTH: usize = 2048;
+// Enough for the status line; the rest of the response is never read
+const MAX_STATUS_LINE_BYTES: usize = 1024;
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// What a room's webhook is told about
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    GameStarted {
+        players: Vec<String>, // names, in no particular order
+    },
+    YourTurn {
+        player_id: String,
+        player_name: String,
+        turn: u32,
+    },
+    GameEnded {
+        winner_name: Option<String>, // None for a draw
+    },
+}
+
+impl WebhookEvent {
+    /// One line for services that show `content` as a chat message, Discord among them
+    fn content(&self, room_name: &str) -> String {
+        match self {
+            WebhookEvent::GameStarted { players } => {
+                format!("🎲 A game started in {}: {}", room_name, players.join(", "))
+            }
+            WebhookEvent::YourTurn {
+                player_name, turn, ..
+            } => format!(
+                "⏳ {}, it's your turn in {} (turn {})",
+                player_name, room_name, turn
+            ),
+            WebhookEvent::GameEnded {
+                winner_name: Some(winner_name),
+            } => format!("🏆 {} won the game in {}", winner_name, room_name),
+            WebhookEvent::GameEnded { winner_name: None } => {
+                format!("🤝 The game in {} ended in a draw", room_name)
+            }
+        }
+    }
+}
+
+/// Where a webhook URL points
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookTarget {
+    pub tls: bool,
+    pub host: String,      // without the brackets of an IPv6 address
+    pub authority: String, // as written in the URL, for the Host header
+    pub port: u16,
+    pub path: String, // with the query; "/" when the URL has neither
+}
+
+impl WebhookTarget {
+    /// Only https:// URLs, unless `allow_http`; no credentials in the URL
+    pub fn parse(url: &str, allow_http: bool) -> AppResult<Self> {
+        let invalid = |reason: &str| AppError::InvalidWebhook {
+            reason: reason.to_string(),
+        };
+        if url.len() > MAX_URL_LENGTH {
+            return Err(invalid("the URL is too long"));
+        }
+        // Anything that could end the request line early
+        if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(invalid("the URL contains whitespace"));
+        }
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://").filter(|_| allow_http) {
+            (false, rest)
+        } else {
+            return Err(invalid("only https:// URLs are accepted"));
+        };
+
+        let (authority, path) = match rest.find(['/', '?', '#']) {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+        let path = path.split('#').next().unwrap_or_default();
+        if authority.contains('@') {
+            return Err(invalid("credentials don't belong in the URL"));
+        }
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, after) = bracketed
+                    .split_once(']')
+                    .ok_or_else(|| invalid("unclosed IPv6 address"))?;
+                (host, after.strip_prefix(':'))
+            }
+            None => match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        if host.is_empty() {
+            return Err(invalid("the URL has no host"));
+        }
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| invalid("the port isn't valid"))?,
+            None if tls => 443,
+            None => 80,
+        };
+
+        Ok(Self {
+            tls,
+            host: host.to_string(),
+            authority: authority.to_string(),
+            port,
+            path: match path {
+                "" => "/".to_string(),
+                query if query.starts_with('?') => format!("/{}", query),
+                path => path.to_string(),
+            },
+        })
+    }
+
+    /// Refuse a host written as an internal address; names are checked once resolved
+    pub fn check_host(&self, allow_internal: bool) -> AppResult<()> {
+        match self.host.parse::<IpAddr>() {
+            Ok(ip) if !allow_internal && is_internal(ip) => Err(internal_target()),
+            _ => Ok(()),
+        }
+    }
+
+    /// The address to connect to. Refused when the host resolves to any internal address,
+    /// so the server can't be made to call services on its own network.
+    pub async fn resolve(&self, allow_internal: bool) -> AppResult<SocketAddr> {
+        let addresses: Vec<SocketAddr> = lookup_host((self.host.as_str(), self.port))
+            .await
+            .map_err(|e| AppError::InvalidWebhook {
+                reason: format!("the host doesn't resolve: {}", e),
+            })?
+            .collect();
+        if !allow_internal && addresses.iter().any(|address| is_internal(address.ip())) {
+            return Err(internal_target());
+        }
+        addresses
+            .first()
+            .copied()
+            .ok_or_else(|| AppError::InvalidWebhook {
+                reason: "the host has no addresses".to_string(),
+            })
+    }
+}
+
+fn internal_target() -> AppError {
+    AppError::InvalidWebhook {
+        reason: "the URL points at an internal address".to_string(),
+    }
+}
+
+/// Loopback, private, link-local, unique-local, unspecified and similar addresses that
+/// aren't reachable from the internet
+pub fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || first == 0
+                || (first == 100 && (64..128).contains(&second)) // carrier-grade NAT
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_internal(IpAddr::V4(mapped));
+            }
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || (first & 0xfe00) == 0xfc00 // unique local, fc00::/7
+                || (first & 0xffc0) == 0xfe80 // link local, fe80::/10
+        }
+    }
+}
+
+struct RoomWebhook {
+    room_name: String,
+    target: WebhookTarget,
+}
+
+struct Delivery {
+    target: WebhookTarget,
+    body: String,
+}
+
+/// Webhooks registered by room hosts. Notifications are queued and POSTed as JSON by a
+/// background task, so a slow or unreachable endpoint never holds up the lobby or a game.
+/// Registrations live in memory only and go away with their room.
+pub struct Webhooks {
+    config: WebhookConfig,
+    rooms: DashMap<String, RoomWebhook>, // room_id -> where its events go
+    deliveries: Option<mpsc::UnboundedSender<Delivery>>, // None when webhooks are disabled
+}
+
+impl Webhooks {
+    /// Spawns the delivery task when webhooks are enabled
+    pub fn start(config: WebhookConfig) -> Arc<Self> {
+        let deliveries = config.enabled.then(|| {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            tokio::spawn(deliver_all(receiver, config.clone()));
+            sender
+        });
+        Arc::new(Self {
+            config,
+            rooms: DashMap::new(),
+            deliveries,
+        })
+    }
+
+    /// Point the room's notifications at `url`, or stop them with None
+    pub fn set(&self, room_id: &str, room_name: &str, url: Option<&str>) -> AppResult<()> {
+        if self.deliveries.is_none() {
+            return Err(AppError::WebhooksDisabled);
+        }
+        match url {
+            Some(url) => {
+                let target = WebhookTarget::parse(url, self.config.allow_http)?;
+                target.check_host(self.config.allow_internal_addresses)?;
+                self.rooms.insert(
+                    room_id.to_string(),
+                    RoomWebhook {
+                        room_name: room_name.to_string(),
+                        target,
+                    },
+                );
+            }
+            None => self.forget(room_id),
+        }
+        Ok(())
+    }
+
+    pub fn forget(&self, room_id: &str) {
+        self.rooms.remove(room_id);
+    }
+
+    pub fn is_registered(&self, room_id: &str) -> bool {
+        self.rooms.contains_key(room_id)
+    }
+
+    /// Queue `event` for the room's webhook, if it has one
+    pub fn notify(&self, room_id: &str, event: WebhookEvent) {
+        let (Some(deliveries), Some(webhook)) = (&self.deliveries, self.rooms.get(room_id)) else {
+            return;
+        };
+        let mut body = serde_json::to_value(&event).unwrap_or_default();
+        body["content"] = event.content(&webhook.room_name).into();
+        body["room_id"] = room_id.into();
+        body["room_name"] = webhook.room_name.as_str().into();
+        let _ = deliveries.send(Delivery {
+            target: webhook.target.clone(),
+            body: body.to_string(),
+        });
+    }
+}
+
+async fn deliver_all(mut receiver: mpsc::UnboundedReceiver<Delivery>, config: WebhookConfig) {
+    let connector = tls_connector();
+    while let Some(delivery) = receiver.recv().await {
+        let connector = connector.clone();
+        let config = config.clone();
+        tokio::spawn(async move { deliver(delivery, &connector, &config).await });
+    }
+}
+
+/// Retries timeouts, connection failures, 429 and 5xx answers, waiting twice as long each time
+async fn deliver(delivery: Delivery, connector: &TlsConnector, config: &WebhookConfig) {
+    let mut delay = FIRST_RETRY_DELAY;
+    for attempt in 1..=config.max_attempts.max(1) {
+        let posted = post(&delivery, connector, config.allow_internal_addresses);
+        let outcome = match timeout(config.timeout(), posted).await {
+            Ok(outcome) => outcome,
+            Err(_) => Err("timed out".to_string()),
+        };
+        let retry = match outcome {
+            Ok(status) if (200..300).contains(&status) => return,
+            Ok(status) => {
+                eprintln!(
+                    "❌ Webhook to {} answered {} (attempt {})",
+                    delivery.target.host, status, attempt
+                );
+                status == 429 || status >= 500
+            }
+            Err(e) => {
+                eprintln!(
+                    "❌ Webhook to {} failed: {} (attempt {})",
+                    delivery.target.host, e, attempt
+                );
+                true
+            }
+        };
+        if !retry || attempt == config.max_attempts {
+            return;
+        }
+        sleep(delay).await;
+        delay *= 2;
+    }
+}
+
+/// One POST on a fresh connection to the address that was checked; returns the response status
+async fn post(
+    delivery: &Delivery,
+    connector: &TlsConnector,
+    allow_internal: bool,
+) -> Result<u16, String> {
+    let target = &delivery.target;
+    let address = target
+        .resolve(allow_internal)
+        .await
+        .map_err(|e| e.to_string())?;
+    let stream = TcpStream::connect(address)
+        .await
+        .map_err(|e| e.to_string())?;
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: isaac-four-souls/{}\r\n\
+         Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        target.path,
+        target.authority,
+        env!("CARGO_PKG_VERSION"),
+        delivery.body.len(),
+        delivery.body
+    );
+    if target.tls {
+        let server_name = ServerName::try_from(target.host.as_str()).map_err(|e| e.to_string())?;
+        let stream = connector
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| e.to_string())?;
+        exchange(stream, &request).await
+    } else {
+        exchange(stream, &request).await
+    }
+}
+
+async fn exchange(
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    request: &str,
+) -> Result<u16, String> {
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    stream.flush().await.map_err(|e| e.to_string())?;
+
+    let mut status_line = Vec::new();
+    let mut byte = [0u8; 1];
+    while !status_line.ends_with(b"\r\n") {
+        if status_line.len() >= MAX_STATUS_LINE_BYTES {
+            return Err("status line too long".to_string());
+        }
+        match stream.read(&mut byte).await.map_err(|e| e.to_string())? {
+            0 => return Err("connection closed before a response".to_string()),
+            _ => status_line.push(byte[0]),
+        }
+    }
+    // "HTTP/1.1 204 No Content"
+    String::from_utf8_lossy(&status_line)
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| "malformed status line".to_string())
+}
+
+fn tls_connector() -> TlsConnector {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+    let client_config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(client_config))
+}
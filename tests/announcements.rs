@@ -0,0 +1,139 @@
+//! Admin announcements reach every connection, in a room or not, right away or at the time
+//! they were scheduled for, unless they were cancelled first.
+
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use isaac_four_souls::network::messages::{AnnouncementSeverity, ClientMessage, ServerResponse};
+use isaac_four_souls::network::permissions::AdminKey;
+use isaac_four_souls::network::room::RoomOptions;
+use isaac_four_souls_client::{Client, ClientError};
+
+const ADMIN_KEY: &str = "announcements-test-key";
+
+async fn announcement(client: &mut Client) -> ServerResponse {
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        client.wait_for(|response| matches!(response, ServerResponse::Announcement { .. })),
+    )
+    .await
+    .expect("No announcement arrived")
+    .unwrap()
+}
+
+fn announce(text: &str, send_at: Option<u64>) -> ClientMessage {
+    ClientMessage::Announce {
+        admin_key: ADMIN_KEY.into(),
+        text: text.to_string(),
+        severity: AnnouncementSeverity::Warning,
+        send_at,
+    }
+}
+
+#[tokio::test]
+async fn announcements_reach_everyone_on_time() {
+    let url = common::server_with(|builder| {
+        common::no_countdown(builder).admin_key(AdminKey::new(ADMIN_KEY))
+    })
+    .await;
+    let mut admin = common::connect(&url).await;
+    let mut alice = common::connect(&url).await;
+    alice
+        .create_room("Basement", "alice", RoomOptions::default())
+        .await
+        .unwrap();
+
+    // A wrong key is refused like any other admin command
+    admin
+        .send(ClientMessage::Announce {
+            admin_key: "wrong".into(),
+            text: "Hello".to_string(),
+            severity: AnnouncementSeverity::Info,
+            send_at: None,
+        })
+        .await
+        .unwrap();
+    let refused = admin
+        .wait_for(|response| matches!(response, ServerResponse::Announcement { .. }))
+        .await;
+    assert!(matches!(
+        refused,
+        Err(ClientError::Server { error_type, .. }) if error_type == "PermissionDenied"
+    ));
+
+    admin
+        .send(announce("Restarting in 10 minutes", None))
+        .await
+        .unwrap();
+    for client in [&mut admin, &mut alice] {
+        assert!(matches!(
+            announcement(client).await,
+            ServerResponse::Announcement { text, severity: AnnouncementSeverity::Warning, .. }
+                if text == "Restarting in 10 minutes"
+        ));
+    }
+
+    let soon = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+        + 300;
+    admin
+        .send(announce("Tournament starts now", Some(soon)))
+        .await
+        .unwrap();
+    let scheduled = admin
+        .wait_for(|response| matches!(response, ServerResponse::AnnouncementScheduled { .. }))
+        .await
+        .unwrap();
+    assert!(matches!(
+        scheduled,
+        ServerResponse::AnnouncementScheduled { send_at, .. } if send_at == soon
+    ));
+    assert!(matches!(
+        announcement(&mut alice).await,
+        ServerResponse::Announcement { text, .. } if text == "Tournament starts now"
+    ));
+
+    // A cancelled announcement is never sent
+    let later = soon + 60_000;
+    admin
+        .send(announce("Never mind", Some(later)))
+        .await
+        .unwrap();
+    let ServerResponse::AnnouncementScheduled {
+        announcement_id, ..
+    } = admin
+        .wait_for(|response| matches!(response, ServerResponse::AnnouncementScheduled { .. }))
+        .await
+        .unwrap()
+    else {
+        unreachable!("wait_for only returns AnnouncementScheduled");
+    };
+    admin
+        .send(ClientMessage::CancelAnnouncement {
+            admin_key: ADMIN_KEY.into(),
+            announcement_id: announcement_id.clone(),
+        })
+        .await
+        .unwrap();
+    admin
+        .wait_for(|response| matches!(response, ServerResponse::AnnouncementCancelled { .. }))
+        .await
+        .unwrap();
+    admin
+        .send(ClientMessage::CancelAnnouncement {
+            admin_key: ADMIN_KEY.into(),
+            announcement_id,
+        })
+        .await
+        .unwrap();
+    let cancelled_again = admin
+        .wait_for(|response| matches!(response, ServerResponse::AnnouncementCancelled { .. }))
+        .await;
+    assert!(matches!(
+        cancelled_again,
+        Err(ClientError::Server { error_type, .. }) if error_type == "AnnouncementNotFound"
+    ));
+}
@@ -0,0 +1,175 @@
+//! With API keys or a JWT secret set, the websocket upgrade must carry a valid `api_key` or
+//! `token`. Anything else still completes the handshake, then is closed with a policy
+//! violation naming what was wrong.
+
+mod common;
+
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_util::StreamExt;
+use isaac_four_souls::network::auth::{AuthMethod, AuthMode};
+use isaac_four_souls::AppError;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde_json::{json, Value};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+
+const SECRET: &str = "basement-secret";
+
+fn api_keys() -> AuthMode {
+    AuthMode::ApiKeys(HashSet::from([
+        "key-one".to_string(),
+        "key-two".to_string(),
+    ]))
+}
+
+fn jwt() -> AuthMode {
+    AuthMode::Jwt {
+        secret: SECRET.to_string(),
+    }
+}
+
+/// An `exp` claim an hour from now
+fn in_an_hour() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600
+}
+
+fn token(claims: Value, secret: &str) -> String {
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap()
+}
+
+fn refusal(result: Result<impl std::fmt::Debug, AppError>) -> String {
+    match result {
+        Err(AppError::Unauthenticated { reason }) => reason,
+        other => panic!("Expected the credentials to be refused, got {:?}", other),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Upgrade {
+    Accepted,
+    Closed(CloseCode, String),
+}
+
+/// Open a websocket with `query` and see whether the server keeps it
+async fn upgrade(url: &str, query: &str) -> Upgrade {
+    let (mut socket, _) = connect_async(format!("{}/?{}", url, query)).await.unwrap();
+    let frame = tokio::time::timeout(Duration::from_secs(10), socket.next())
+        .await
+        .expect("The server didn't answer in time");
+    match frame {
+        Some(Ok(Message::Text(text))) => {
+            let value: Value = serde_json::from_str(&text).unwrap();
+            assert!(value.get("ConnectionId").is_some(), "Got {}", text);
+            Upgrade::Accepted
+        }
+        Some(Ok(Message::Close(Some(frame)))) => Upgrade::Closed(frame.code, frame.reason.into()),
+        other => panic!("Expected a connection id or a close frame, got {:?}", other),
+    }
+}
+
+#[test]
+fn api_keys_are_checked() {
+    let auth = api_keys();
+    let context = auth.authenticate(Some("lang=it&api_key=key-two")).unwrap();
+    assert_eq!(context.method, AuthMethod::ApiKey);
+    assert!(!context.admin);
+
+    assert_eq!(
+        refusal(auth.authenticate(Some("api_key=key-three"))),
+        "invalid API key"
+    );
+    assert!(refusal(auth.authenticate(Some("token=key-one"))).contains("'api_key'"));
+    assert!(refusal(auth.authenticate(None)).contains("'api_key'"));
+}
+
+#[test]
+fn tokens_are_checked() {
+    let auth = jwt();
+    let query = format!(
+        "token={}",
+        token(
+            json!({ "sub": "alice", "admin": true, "exp": in_an_hour() }),
+            SECRET
+        )
+    );
+    let context = auth.authenticate(Some(&query)).unwrap();
+    assert_eq!(context.method, AuthMethod::Jwt);
+    assert_eq!(context.subject.as_deref(), Some("alice"));
+    assert!(context.admin);
+
+    // Neither claim is required, only the expiry
+    let lasting = json!({ "exp": in_an_hour() });
+    let context = auth
+        .authenticate(Some(&format!("token={}", token(lasting, SECRET))))
+        .unwrap();
+    assert_eq!(context.subject, None);
+    assert!(!context.admin);
+
+    let forged = format!(
+        "token={}",
+        token(json!({ "sub": "alice", "exp": in_an_hour() }), "guessed")
+    );
+    assert!(refusal(auth.authenticate(Some(&forged))).starts_with("invalid token"));
+    let expired = format!("token={}", token(json!({ "exp": 1 }), SECRET));
+    assert!(refusal(auth.authenticate(Some(&expired))).starts_with("invalid token"));
+    let everlasting = format!("token={}", token(json!({ "sub": "alice" }), SECRET));
+    assert!(refusal(auth.authenticate(Some(&everlasting))).starts_with("invalid token"));
+    assert!(refusal(auth.authenticate(Some("api_key=key-one"))).contains("'token'"));
+    assert!(refusal(auth.authenticate(None)).contains("'token'"));
+}
+
+#[test]
+fn without_auth_everyone_is_anonymous() {
+    let context = AuthMode::Disabled.authenticate(None).unwrap();
+    assert_eq!(context.method, AuthMethod::Anonymous);
+}
+
+#[tokio::test]
+async fn an_upgrade_needs_a_known_api_key() {
+    let url = common::server_with(|builder| builder.auth(api_keys())).await;
+
+    assert_eq!(upgrade(&url, "api_key=key-one").await, Upgrade::Accepted);
+    assert_eq!(
+        upgrade(&url, "api_key=key-three").await,
+        Upgrade::Closed(
+            CloseCode::Policy,
+            "Authentication failed: invalid API key".to_string()
+        )
+    );
+    match upgrade(&url, "lang=en").await {
+        Upgrade::Closed(CloseCode::Policy, reason) => assert!(reason.contains("'api_key'")),
+        other => panic!("Expected a missing key to be refused, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn an_upgrade_needs_a_signed_token() {
+    let url = common::server_with(|builder| builder.auth(jwt())).await;
+
+    let signed = token(json!({ "sub": "alice", "exp": in_an_hour() }), SECRET);
+    assert_eq!(
+        upgrade(&url, &format!("token={}", signed)).await,
+        Upgrade::Accepted
+    );
+    let forged = token(json!({ "sub": "alice", "exp": in_an_hour() }), "guessed");
+    match upgrade(&url, &format!("token={}", forged)).await {
+        Upgrade::Closed(CloseCode::Policy, reason) => assert!(reason.contains("invalid token")),
+        other => panic!("Expected a forged token to be refused, got {:?}", other),
+    }
+    match upgrade(&url, "api_key=key-one").await {
+        Upgrade::Closed(CloseCode::Policy, reason) => assert!(reason.contains("'token'")),
+        other => panic!("Expected a missing token to be refused, got {:?}", other),
+    }
+}
@@ -0,0 +1,156 @@
+//! Several client messages can share one frame: the connection hands them on in order and,
+//! once the lobby or game has handled each, answers with one result per message, naming it by
+//! its request id.
+
+mod common;
+
+use isaac_four_souls::network::messages::{
+    BatchedClientMessage, BatchedResult, ClientMessage, ServerResponse,
+};
+use isaac_four_souls::network::room::RoomOptions;
+use isaac_four_souls_client::Client;
+
+use common::{connect, no_countdown, server, server_with, within};
+
+fn batched(request_id: &str, message: ClientMessage) -> BatchedClientMessage {
+    BatchedClientMessage {
+        request_id: request_id.to_string(),
+        message,
+    }
+}
+
+/// The results of a batch, failing on a plain Error on the way
+async fn batch_results(client: &mut Client) -> Vec<BatchedResult> {
+    match within(
+        client.wait_for(|response| matches!(response, ServerResponse::BatchResults { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::BatchResults { results } => results,
+        other => panic!("Expected the batch results, got {:?}", other),
+    }
+}
+
+fn error_type(result: &BatchedResult) -> Option<&str> {
+    result.error.as_ref().map(|error| error.error_type.as_str())
+}
+
+#[tokio::test]
+async fn each_message_of_a_batch_gets_its_own_result() {
+    let url = server().await;
+    let mut client = connect(&url).await;
+
+    within(client.send(ClientMessage::BatchedClientMessages {
+        messages: vec![
+            batched("info", ClientMessage::GetProtocolInfo),
+            // Not in a game, refused without holding back the rest
+            batched("pass", ClientMessage::PriorityPass),
+            batched(
+                "nested",
+                ClientMessage::BatchedClientMessages { messages: vec![] },
+            ),
+            batched("glossary", ClientMessage::GetGlossary),
+        ],
+    }))
+    .await
+    .unwrap();
+
+    let results = batch_results(&mut client).await;
+    let ids: Vec<&str> = results
+        .iter()
+        .map(|result| result.request_id.as_str())
+        .collect();
+    assert_eq!(ids, ["info", "pass", "nested", "glossary"]);
+    assert!(results[0].error.is_none());
+    assert_eq!(
+        results[1].error.as_ref().unwrap().error_type,
+        "ConnectionNotInRoom"
+    );
+    assert_eq!(
+        results[2].error.as_ref().unwrap().error_type,
+        "UnknownMessage"
+    );
+    assert!(results[3].error.is_none());
+
+    // The messages handed on are answered as usual
+    within(client.wait_for(|response| matches!(response, ServerResponse::Glossary { .. })))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn lobby_and_game_refusals_come_back_in_the_results() {
+    let url = server_with(no_countdown).await;
+    let mut alice = connect(&url).await;
+    let mut bob = connect(&url).await;
+
+    within(alice.send(ClientMessage::BatchedClientMessages {
+        messages: vec![
+            batched(
+                "join",
+                ClientMessage::JoinRoom {
+                    player_name: "alice".to_string(),
+                    room_id: "no-such-room".to_string(),
+                },
+            ),
+            batched("ping", ClientMessage::Ping),
+        ],
+    }))
+    .await
+    .unwrap();
+    let results = batch_results(&mut alice).await;
+    assert_eq!(error_type(&results[0]), Some("RoomNotFound"));
+    assert_eq!(error_type(&results[1]), None);
+
+    let seat = within(alice.create_room("Basement", "alice", RoomOptions::default()))
+        .await
+        .unwrap();
+    within(bob.join(&seat.room_id, "bob")).await.unwrap();
+    within(alice.ready()).await.unwrap();
+    within(bob.ready()).await.unwrap();
+    within(alice.wait_for(|response| matches!(response, ServerResponse::GameJoined { .. })))
+        .await
+        .unwrap();
+
+    // Opening hands are still being decided, so the game turns the pass down
+    within(alice.send(ClientMessage::BatchedClientMessages {
+        messages: vec![
+            batched("pass", ClientMessage::TurnPass),
+            batched("keep", ClientMessage::MulliganDecision { keep: true }),
+        ],
+    }))
+    .await
+    .unwrap();
+    let results = batch_results(&mut alice).await;
+    assert_eq!(error_type(&results[0]), Some("MulliganInProgress"));
+    assert_eq!(error_type(&results[1]), None);
+}
+
+#[tokio::test]
+async fn a_batch_over_the_limit_is_refused_whole() {
+    let url = server().await;
+    let mut client = connect(&url).await;
+
+    within(
+        client.send(ClientMessage::BatchedClientMessages {
+            messages: (0..100)
+                .map(|i| batched(&i.to_string(), ClientMessage::Ping))
+                .collect(),
+        }),
+    )
+    .await
+    .unwrap();
+    match within(client.wait_for(|response| {
+        matches!(
+            response,
+            ServerResponse::Error { .. } | ServerResponse::BatchResults { .. }
+        )
+    }))
+    .await
+    .unwrap()
+    {
+        ServerResponse::Error { error_type, .. } => assert_eq!(error_type, "MalformedFrame"),
+        other => panic!("Expected the batch to be refused, got {:?}", other),
+    }
+}
@@ -0,0 +1,78 @@
+//! Board queries borrow what the board already keeps in order: a deck is read top card first
+//! straight off the deck, and a hand is the player's own list rather than a copy of it.
+
+use isaac_four_souls::game::board::{Board, DeckKind};
+use isaac_four_souls::game::card_loader::BASE_SET;
+use isaac_four_souls::game::rules::RuleSet;
+
+fn board() -> Board {
+    Board::new(
+        vec!["alice".to_string(), "bob".to_string()],
+        7,
+        &[BASE_SET.to_string()],
+        &RuleSet::default(),
+    )
+}
+
+#[test]
+fn decks_are_read_top_card_first() {
+    let mut board = board();
+    for deck in [
+        DeckKind::Loot,
+        DeckKind::Treasure,
+        DeckKind::Curse,
+        DeckKind::Event,
+    ] {
+        let top: Vec<String> = board
+            .deck_cards(deck)
+            .take(3)
+            .map(|card| card.entity_id.clone())
+            .collect();
+        let peeked: Vec<String> = board
+            .peek_top(deck, 3)
+            .into_iter()
+            .map(|card| card.entity_id)
+            .collect();
+        assert_eq!(top, peeked);
+    }
+
+    let top_loot = board
+        .deck_cards(DeckKind::Loot)
+        .next()
+        .unwrap()
+        .entity_id
+        .clone();
+    assert_eq!(
+        board.draw_loot_for_player("alice").unwrap().entity_id,
+        top_loot
+    );
+}
+
+#[test]
+fn hands_are_the_players_own_lists() {
+    let mut board = board();
+    let drawn = board.draw_loot_for_player("bob").unwrap();
+    let hand = board.get_player_hand("bob").unwrap();
+    assert_eq!(hand.last().unwrap().entity_id, drawn.entity_id);
+    assert_eq!(
+        hand.as_ptr(),
+        board.players_hands["bob"].as_ptr(),
+        "The hand should be borrowed, not copied"
+    );
+    assert_eq!(board.get_hand_size("bob").unwrap(), hand.len());
+    assert!(board.get_player_hand("nobody").is_err());
+}
+
+#[test]
+fn only_a_lethal_hit_would_kill() {
+    let mut board = board();
+    let health = board.players["alice"].current_health;
+    assert!(!board.would_die("alice", 0));
+    assert!(!board.would_die("alice", health - 1));
+    assert!(board.would_die("alice", health));
+    assert!(!board.would_die("dave", health));
+
+    assert!(board.damage_player("alice", health).unwrap());
+    // Already dead, nothing more to lose
+    assert!(!board.would_die("alice", health));
+}
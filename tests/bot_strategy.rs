@@ -0,0 +1,147 @@
+//! Bots only offer the actions the server would take from them: passing when they hold
+//! priority, and buying the cheapest item they can pay for in their own action step. They
+//! answer draw offers as their strategy says, declining unless it agrees.
+
+mod common;
+
+use common::{connect, no_countdown, server_with, within};
+use isaac_four_souls::game::board::Board;
+use isaac_four_souls::game::bot_strategy::{
+    register_strategy, BotAction, BotStrategy, GameStateView, RandomStrategy,
+};
+use isaac_four_souls::game::card_loader::BASE_SET;
+use isaac_four_souls::game::game_state::TurnPhases;
+use isaac_four_souls::game::rules::RuleSet;
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls_client::Client;
+
+fn view(phase: TurnPhases, coins: u32) -> GameStateView {
+    let rules = RuleSet::default();
+    let board = Board::new(vec!["bot".to_string()], 7, &[BASE_SET.to_string()], &rules);
+
+    GameStateView {
+        player_id: "bot".to_string(),
+        active_player: Some("bot".to_string()),
+        priority_player: Some("bot".to_string()),
+        phase: Some(phase),
+        coins,
+        shop: board.shop,
+        shop_price: rules.shop_price,
+        ..GameStateView::default()
+    }
+}
+
+#[test]
+fn the_bot_buys_in_its_action_step_when_it_can_pay() {
+    assert_eq!(
+        view(TurnPhases::ActionStep, 10).legal_actions(),
+        [BotAction::BuyItem { shop_index: 0 }, BotAction::PassTurn]
+    );
+    assert_eq!(
+        view(TurnPhases::ActionStep, 9).legal_actions(),
+        [BotAction::PassTurn]
+    );
+    assert_eq!(
+        view(TurnPhases::LootStep, 10).legal_actions(),
+        [BotAction::PassTurn]
+    );
+}
+
+#[test]
+fn the_bot_only_passes_on_other_turns() {
+    let mut view = view(TurnPhases::ActionStep, 10);
+    view.active_player = Some("alice".to_string());
+    assert_eq!(view.legal_actions(), [BotAction::PassPriority]);
+
+    view.priority_player = Some("alice".to_string());
+    assert!(view.legal_actions().is_empty());
+}
+
+#[test]
+fn an_empty_shop_sells_nothing() {
+    let mut view = view(TurnPhases::ActionStep, 25);
+    view.shop.clear();
+    assert_eq!(view.cheapest_affordable_item(), None);
+}
+
+/// Plays like the default bot but takes any draw it's offered
+struct Agreeable(RandomStrategy);
+
+impl BotStrategy for Agreeable {
+    fn name(&self) -> &str {
+        "agreeable"
+    }
+
+    fn decide_action(&mut self, view: &GameStateView) -> BotAction {
+        self.0.decide_action(view)
+    }
+
+    fn accept_draw(&mut self, _view: &GameStateView) -> bool {
+        true
+    }
+}
+
+/// A solo game against one bot, waiting until it's the human's turn so a draw offer can't
+/// lapse with the bot's. Returns the human's player id.
+async fn solo_game(strategy: Option<&str>) -> (Client, String) {
+    let url = server_with(no_countdown).await;
+    let mut alice = connect(&url).await;
+    within(alice.send(ClientMessage::StartSoloGame {
+        player_name: "alice".to_string(),
+        bots: 1,
+        strategy: strategy.map(str::to_string),
+        options: Default::default(),
+    }))
+    .await
+    .unwrap();
+    let player_id = match within(
+        alice.wait_for(|response| matches!(response, ServerResponse::RoomCreated { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::RoomCreated { player_id, .. } => player_id,
+        _ => unreachable!(),
+    };
+    within(alice.send(ClientMessage::MulliganDecision { keep: true }))
+        .await
+        .unwrap();
+    within(alice.wait_for(|response| {
+        matches!(response, ServerResponse::TurnPhaseChange { active_player, .. } if *active_player == player_id)
+    }))
+    .await
+    .unwrap();
+    (alice, player_id)
+}
+
+#[tokio::test]
+async fn bots_decline_draws_by_default() {
+    let (mut alice, player_id) = solo_game(None).await;
+    for _ in 0..2 {
+        // A declined offer doesn't stand in the way of the next one
+        within(alice.send(ClientMessage::OfferDraw)).await.unwrap();
+        match within(
+            alice.wait_for(|response| matches!(response, ServerResponse::DrawDeclined { .. })),
+        )
+        .await
+        .unwrap()
+        {
+            ServerResponse::DrawDeclined { player_id: bot_id } => assert_ne!(bot_id, player_id),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn a_strategy_can_accept_a_draw() {
+    register_strategy("agreeable", || Box::new(Agreeable(RandomStrategy)));
+    let (mut alice, _) = solo_game(Some("agreeable")).await;
+    within(alice.send(ClientMessage::OfferDraw)).await.unwrap();
+    match within(alice.wait_for(|response| matches!(response, ServerResponse::GameEnded { .. })))
+        .await
+        .unwrap()
+    {
+        ServerResponse::GameEnded { winner_id, .. } => assert_eq!(winner_id, None),
+        _ => unreachable!(),
+    }
+}
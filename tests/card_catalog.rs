@@ -0,0 +1,80 @@
+//! The card catalog resolves every card's assets and tracks the database version, which
+//! boards remember they were dealt from.
+
+use isaac_four_souls::game::board::Board;
+use isaac_four_souls::game::card_loader::{get_database, Database, BASE_SET};
+use isaac_four_souls::game::rules::RuleSet;
+
+const LOOT: &str = r#"[
+  {"id": "one_cent", "name": "A Penny", "card_type": "loot", "subtype": "loot",
+   "description": "Gain 1¢", "count": 3},
+  {"id": "lucky_penny", "name": "Lucky Penny", "card_type": "loot", "subtype": "loot",
+   "description": "Gain 1¢", "count": 1, "set": "gold_box",
+   "assets": {"image_url": "https://cdn.example.com/lucky.png", "frame": "gold", "name_key": "lucky"}}
+]"#;
+
+fn database(loot: &str) -> Database {
+    Database::parse([loot, "[]", "[]", "[]"]).unwrap()
+}
+
+#[test]
+fn missing_assets_get_defaults() {
+    let catalog = database(LOOT).catalog();
+    let penny = catalog.iter().find(|card| card.id == "one_cent").unwrap();
+    assert_eq!(penny.image_url, "cards/base/one_cent.png");
+    assert_eq!(penny.frame, "loot");
+    assert_eq!(penny.name_key, "cards.one_cent.name");
+    assert_eq!(penny.text_key, "cards.one_cent.text");
+    assert_eq!(penny.count, 3);
+}
+
+#[test]
+fn card_files_can_set_assets() {
+    let catalog = database(LOOT).catalog();
+    let lucky = catalog
+        .iter()
+        .find(|card| card.id == "lucky_penny")
+        .unwrap();
+    assert_eq!(lucky.image_url, "https://cdn.example.com/lucky.png");
+    assert_eq!(lucky.frame, "gold");
+    assert_eq!(lucky.name_key, "lucky");
+    assert_eq!(lucky.text_key, "cards.lucky_penny.text");
+    assert_eq!(lucky.set, "gold_box");
+}
+
+#[test]
+fn the_catalog_lists_every_set_in_id_order() {
+    let catalog = Database::embedded().unwrap().catalog();
+    let ids: Vec<&str> = catalog.iter().map(|card| card.id.as_str()).collect();
+    let mut sorted = ids.clone();
+    sorted.sort();
+    assert_eq!(ids, sorted);
+    assert!(ids.contains(&"one_cent") && ids.contains(&"curse_of_greed"));
+}
+
+#[test]
+fn changing_an_asset_changes_the_version() {
+    let before = database(LOOT).version;
+    let after = database(&LOOT.replace("gold\"", "silver\"")).version;
+    assert_ne!(before, after);
+}
+
+#[test]
+fn the_version_is_the_same_on_every_build() {
+    assert_eq!(database("[]").version, "2f79813538e78f0b");
+    assert_ne!(
+        Database::parse(["[]", "[]", "[]", "[ ]"]).unwrap().version,
+        Database::parse(["[]", "[]", "[ ]", "[]"]).unwrap().version
+    );
+}
+
+#[test]
+fn a_board_records_the_cards_it_was_dealt_from() {
+    let board = Board::new(
+        vec!["alice".to_string()],
+        7,
+        &[BASE_SET.to_string()],
+        &RuleSet::default(),
+    );
+    assert_eq!(board.card_set_version, get_database().version);
+}
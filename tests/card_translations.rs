@@ -0,0 +1,155 @@
+//! Card names and texts, and the keyword glossary, reach each client in the language it
+//! negotiated, falling back to English wherever a translation is missing. Each language's
+//! catalog is cached under a version of its own.
+
+mod common;
+
+use isaac_four_souls::game::card_loader::Database;
+use isaac_four_souls::network::locale::Locale;
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+
+use common::{connect, server, within};
+
+const LOOT: &str = r#"[
+  {"id": "one_cent", "name": "A Penny", "card_type": "loot", "subtype": "loot",
+   "description": "Gain 1¢", "count": 3,
+   "translations": {"it": {"name": "Un penny", "description": "Guadagna 1¢"}}}
+]"#;
+
+const GLOSSARY: &str = r#"[
+  {"keyword": "tap", "name": "Tap", "description": "Turn it sideways",
+   "translations": {"es": {"name": "Girar", "description": "Gírala de lado"}}}
+]"#;
+
+fn database() -> Database {
+    Database::parse([LOOT, "[]", "[]", "[]"])
+        .unwrap()
+        .with_glossary(GLOSSARY)
+        .unwrap()
+}
+
+#[test]
+fn the_catalog_is_in_the_asked_language_or_english() {
+    let database = database();
+    let italian = &database.catalog_in(Locale::It.code())[0];
+    assert_eq!(italian.name, "Un penny");
+    assert_eq!(italian.description, "Guadagna 1¢");
+    // Keys and ids stay the same in every language
+    assert_eq!(italian.name_key, "cards.one_cent.name");
+
+    let spanish = &database.catalog_in(Locale::Es.code())[0];
+    assert_eq!(spanish.name, "A Penny");
+    assert_eq!(database.catalog()[0].name, "A Penny");
+}
+
+#[test]
+fn the_glossary_is_translated_the_same_way() {
+    let database = database();
+    let spanish = database.glossary_in("es");
+    assert_eq!(spanish[0].keyword, "tap");
+    assert_eq!(spanish[0].name, "Girar");
+    assert_eq!(database.glossary_in("it")[0].name, "Tap");
+
+    let retranslated = Database::parse([LOOT, "[]", "[]", "[]"])
+        .unwrap()
+        .with_glossary(&GLOSSARY.replace("Girar", "Voltear"))
+        .unwrap();
+    assert_ne!(database.version, retranslated.version);
+}
+
+#[test]
+fn every_card_shipped_has_both_translations() {
+    let database = Database::embedded().unwrap();
+    for locale in [Locale::It, Locale::Es] {
+        let english = database.catalog();
+        let translated = database.catalog_in(locale.code());
+        for (english, translated) in english.iter().zip(&translated) {
+            assert_ne!(
+                (&english.name, &english.description),
+                (&translated.name, &translated.description),
+                "{} has no {:?} translation",
+                english.id,
+                locale
+            );
+        }
+        assert!(!database.glossary_in(locale.code()).is_empty());
+    }
+}
+
+#[tokio::test]
+async fn a_client_gets_cards_and_keywords_in_its_language() {
+    let url = server().await;
+    let mut client = connect(&format!("{}/?lang=it", url)).await;
+
+    within(client.send(ClientMessage::GetCardCatalog {
+        known_version: None,
+    }))
+    .await
+    .unwrap();
+    match within(client.wait_for(|response| matches!(response, ServerResponse::CardCatalog { .. })))
+        .await
+        .unwrap()
+    {
+        ServerResponse::CardCatalog {
+            locale,
+            cards: Some(cards),
+            ..
+        } => {
+            assert_eq!(locale, Locale::It);
+            let penny = cards.iter().find(|card| card.id == "one_cent").unwrap();
+            assert_eq!(penny.name, "Un penny");
+        }
+        other => panic!("Expected the whole catalog, got {:?}", other),
+    }
+
+    within(client.send(ClientMessage::GetGlossary))
+        .await
+        .unwrap();
+    match within(client.wait_for(|response| matches!(response, ServerResponse::Glossary { .. })))
+        .await
+        .unwrap()
+    {
+        ServerResponse::Glossary { locale, keywords } => {
+            assert_eq!(locale, Locale::It);
+            let tap = keywords
+                .iter()
+                .find(|entry| entry.keyword == "tap")
+                .unwrap();
+            assert_eq!(tap.name, "Tappa");
+        }
+        other => panic!("Expected the glossary, got {:?}", other),
+    }
+}
+
+/// The catalog's version and whether its cards came along
+async fn catalog(url: &str, known_version: Option<String>) -> (String, bool) {
+    let mut client = connect(url).await;
+    within(client.send(ClientMessage::GetCardCatalog { known_version }))
+        .await
+        .unwrap();
+    match within(client.wait_for(|response| matches!(response, ServerResponse::CardCatalog { .. })))
+        .await
+        .unwrap()
+    {
+        ServerResponse::CardCatalog { version, cards, .. } => (version, cards.is_some()),
+        other => panic!("Expected the catalog, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn a_catalog_cached_in_one_language_is_not_current_in_another() {
+    let url = server().await;
+    let (english, _) = catalog(&url, None).await;
+    assert_eq!(
+        catalog(&url, Some(english.clone())).await,
+        (english.clone(), false)
+    );
+
+    let (italian, sent) = catalog(&format!("{}/?lang=it", url), Some(english.clone())).await;
+    assert!(sent);
+    assert_ne!(italian, english);
+    assert_eq!(
+        catalog(&format!("{}/?lang=it", url), Some(italian.clone())).await,
+        (italian, false)
+    );
+}
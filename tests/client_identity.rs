@@ -0,0 +1,163 @@
+//! Clients can't claim to be someone else: messages carry no identity, a claimed one is
+//! dropped, and ids that belong to another player are refused by the game.
+
+mod common;
+
+use isaac_four_souls::game::game_coordinator::{GameCoordinator, GameEvent};
+use isaac_four_souls::game::trades::TradeOffer;
+use isaac_four_souls::network::messages::deserialize_message;
+use isaac_four_souls::{AppError, ConnectionCommand};
+use tokio::sync::mpsc;
+
+const PLAYERS: [&str; 3] = ["alice", "bob", "carol"];
+
+async fn game() -> (GameCoordinator, mpsc::UnboundedReceiver<ConnectionCommand>) {
+    common::game(&PLAYERS).start().await
+}
+
+#[test]
+fn claimed_identity_fields_are_dropped_until_their_sunset() {
+    for (claimed, honest) in [
+        (
+            r#"{"JoinRoom":{"player_name":"mallory","room_id":"room","connection_id":"alice"}}"#,
+            r#"{"JoinRoom":{"player_name":"mallory","room_id":"room"}}"#,
+        ),
+        (
+            r#"{"CreateRoom":{"room_name":"r","first_player_name":"mallory","player_id":"alice"}}"#,
+            r#"{"CreateRoom":{"room_name":"r","first_player_name":"mallory"}}"#,
+        ),
+        (
+            r#"{"PlayerReady":{"player_id":"alice"}}"#,
+            r#""PlayerReady""#,
+        ),
+        (r#"{"TurnPass":{"player_id":"alice"}}"#, r#""TurnPass""#),
+        (
+            r#"{"ActivateItem":{"item_id":"item","player_id":"alice"}}"#,
+            r#"{"ActivateItem":{"item_id":"item"}}"#,
+        ),
+        (
+            r#"{"AcceptTrade":{"trade_id":"t","player_id":"alice"}}"#,
+            r#"{"AcceptTrade":{"trade_id":"t"}}"#,
+        ),
+        // A player_id the message declares is kept
+        (
+            r#"{"KickPlayer":{"player_id":"bob","connection_id":"alice"}}"#,
+            r#"{"KickPlayer":{"player_id":"bob"}}"#,
+        ),
+    ] {
+        assert_eq!(
+            format!("{:?}", deserialize_message(claimed).unwrap()),
+            format!("{:?}", deserialize_message(honest).unwrap()),
+        );
+    }
+}
+
+#[test]
+fn other_undeclared_fields_are_refused() {
+    for refused in [
+        r#"{"Chat":{"message":"hi","sender_name":"alice"}}"#,
+        r#"{"TurnPass":{"player_id":"alice","sender_name":"alice"}}"#,
+        r#"{"ChoiceResponse":{"choice_id":"c","player_id":"alice"}}"#,
+    ] {
+        assert!(
+            deserialize_message(refused).is_err(),
+            "{} was accepted",
+            refused
+        );
+    }
+}
+
+#[test]
+fn the_same_messages_without_identity_are_accepted() {
+    for honest in [
+        r#"{"JoinRoom":{"player_name":"mallory","room_id":"room"}}"#,
+        r#""PlayerReady""#,
+        r#""TurnPass""#,
+        r#"{"ActivateItem":{"item_id":"item"}}"#,
+        r#"{"AcceptTrade":{"trade_id":"t"}}"#,
+    ] {
+        assert!(
+            deserialize_message(honest).is_ok(),
+            "{} was refused",
+            honest
+        );
+    }
+}
+
+#[tokio::test]
+async fn only_the_active_player_passes_the_turn() {
+    let (mut coordinator, _commands) = game().await;
+    let active = coordinator.game_state().turn_order.active_player_id.clone();
+    let other = PLAYERS.iter().find(|player| **player != active).unwrap();
+
+    let result = coordinator
+        .handle_event(GameEvent::TurnPass {
+            player_id: other.to_string(),
+        })
+        .await;
+    assert!(matches!(result, Err(AppError::NotPlayerTurn)));
+    assert_eq!(coordinator.game_state().turn_order.active_player_id, active);
+}
+
+#[tokio::test]
+async fn items_of_another_player_cannot_be_activated() {
+    let (mut coordinator, _commands) = game().await;
+    let active = coordinator.game_state().turn_order.active_player_id.clone();
+    let other = PLAYERS.iter().find(|player| **player != active).unwrap();
+    let stolen_item = coordinator.game_state().board.players_items[*other][0]
+        .entity_id
+        .clone();
+
+    let result = coordinator
+        .handle_event(GameEvent::ActivateItem {
+            player_id: active,
+            item_id: stolen_item,
+        })
+        .await;
+    assert!(matches!(result, Err(AppError::ItemNotFound)));
+}
+
+#[tokio::test]
+async fn only_the_receiver_accepts_a_trade() {
+    let (mut coordinator, _commands) = game().await;
+    coordinator
+        .handle_event(GameEvent::ProposeTrade {
+            player_id: "alice".to_string(),
+            to_player: "bob".to_string(),
+            offer: TradeOffer {
+                coins: 1,
+                loot: Vec::new(),
+            },
+            request: TradeOffer::default(),
+        })
+        .await
+        .unwrap();
+    let trade_id = coordinator.game_state().pending_trades[0].trade_id.clone();
+
+    // Neither a bystander nor the proposer can accept on the receiver's behalf
+    for impostor in ["carol", "alice"] {
+        let result = coordinator
+            .handle_event(GameEvent::RespondToTrade {
+                player_id: impostor.to_string(),
+                trade_id: trade_id.clone(),
+                accept: true,
+            })
+            .await;
+        assert!(
+            matches!(result, Err(AppError::TradeNotFound)),
+            "{} accepted",
+            impostor
+        );
+    }
+    assert_eq!(coordinator.game_state().pending_trades.len(), 1);
+
+    coordinator
+        .handle_event(GameEvent::RespondToTrade {
+            player_id: "bob".to_string(),
+            trade_id,
+            accept: true,
+        })
+        .await
+        .unwrap();
+    assert!(coordinator.game_state().pending_trades.is_empty());
+}
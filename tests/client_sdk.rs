@@ -0,0 +1,136 @@
+//! The client crate against a real server on a local port: rooms, readying up and playing a
+//! card, all through the reliable envelope, and the lifecycle events that go with them.
+
+mod common;
+
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls_client::{Client, ClientError};
+
+use common::{connect, no_countdown, server_with, within};
+
+/// Start a server and connect the first client to it
+async fn server() -> (String, Client) {
+    let url = server_with(no_countdown).await;
+    let client = connect(&url).await;
+    (url, client)
+}
+
+async fn game_start(client: &mut Client) -> Vec<String> {
+    match within(
+        client.wait_for(|response| matches!(response, ServerResponse::RoomGameStart { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::RoomGameStart { turn_order, .. } => turn_order,
+        _ => unreachable!(),
+    }
+}
+
+#[tokio::test]
+async fn joined_players_share_the_game() {
+    let (url, mut alice) = server().await;
+    assert!(!alice.connection_id().is_empty());
+    let mut bob = Client::connect(&url).await.unwrap();
+
+    let host = within(alice.create_room("Basement", "alice", Default::default()))
+        .await
+        .unwrap();
+    let guest = within(bob.join(&host.room_id, "bob")).await.unwrap();
+    assert_eq!(guest.room_id, host.room_id);
+    assert_ne!(guest.player_id, host.player_id);
+    within(alice.ready()).await.unwrap();
+    within(bob.ready()).await.unwrap();
+
+    let turn_order = game_start(&mut alice).await;
+    assert_eq!(game_start(&mut bob).await, turn_order);
+    assert!(turn_order.contains(&host.player_id) && turn_order.contains(&guest.player_id));
+}
+
+#[tokio::test]
+async fn the_active_player_plays_a_loot_card() {
+    let (url, mut alice) = server().await;
+    let mut bob = Client::connect(&url).await.unwrap();
+    let host = within(alice.create_room("Basement", "alice", Default::default()))
+        .await
+        .unwrap();
+    within(bob.join(&host.room_id, "bob")).await.unwrap();
+    within(alice.ready()).await.unwrap();
+    within(bob.ready()).await.unwrap();
+
+    let turn_order = game_start(&mut alice).await;
+    let (active, other) = if turn_order[0] == host.player_id {
+        (&mut alice, &mut bob)
+    } else {
+        (&mut bob, &mut alice)
+    };
+    let hand = match within(
+        active.wait_for(|response| matches!(response, ServerResponse::PrivateBoardState { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::PrivateBoardState { hand, .. } => hand,
+        _ => unreachable!(),
+    };
+    for client in [&mut *active, &mut *other] {
+        within(client.send(ClientMessage::MulliganDecision { keep: true }))
+            .await
+            .unwrap();
+    }
+
+    let card_id = &hand[0].card.entity_id;
+    match within(active.play_card(card_id)).await.unwrap() {
+        ServerResponse::LootPlayed { card, .. } => assert_eq!(&card.card.entity_id, card_id),
+        other => panic!("Expected LootPlayed, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn refused_requests_come_back_as_errors() {
+    let (_, mut alice) = server().await;
+    match within(alice.join("no-such-room", "alice")).await {
+        Err(ClientError::Server { error_type, .. }) => assert_eq!(error_type, "RoomNotFound"),
+        other => panic!(
+            "Expected RoomNotFound, got {:?}",
+            other.map(|seat| seat.room_id)
+        ),
+    }
+}
+
+#[tokio::test]
+async fn lifecycle_events_follow_the_connection() {
+    let (_, mut alice) = server().await;
+    assert!(matches!(
+        within(alice.recv()).await.unwrap(),
+        ServerResponse::LobbyEntered
+    ));
+
+    within(alice.send(ClientMessage::StartSoloGame {
+        player_name: "alice".to_string(),
+        bots: 1,
+        strategy: None,
+        options: Default::default(),
+    }))
+    .await
+    .unwrap();
+    let (room_id, player_id) = match within(
+        alice.wait_for(|response| matches!(response, ServerResponse::RoomCreated { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::RoomCreated { room_id, player_id } => (room_id, player_id),
+        _ => unreachable!(),
+    };
+    match within(alice.wait_for(|response| matches!(response, ServerResponse::GameJoined { .. })))
+        .await
+        .unwrap()
+    {
+        ServerResponse::GameJoined { game_id, seat } => {
+            assert_eq!(game_id, room_id);
+            assert_eq!(seat, player_id);
+        }
+        _ => unreachable!(),
+    }
+}
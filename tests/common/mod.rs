@@ -0,0 +1,169 @@
+//! What the tests share: a server on a free port and clients connected to it, and headless
+//! games played straight on a `GameCoordinator`.
+
+// Each test crate uses only some of these
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use isaac_four_souls::config::TimerConfig;
+use isaac_four_souls::game::card_loader::BASE_SET;
+use isaac_four_souls::game::game_coordinator::GameCoordinator;
+use isaac_four_souls::game::rules::RuleSet;
+use isaac_four_souls::network::messenger::Messenger;
+use isaac_four_souls::network::server::WebsocketServerBuilder;
+use isaac_four_souls::{ConnectionCommand, TurnOrder, WebsocketServer};
+use isaac_four_souls_client::{Client, ClientResult};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// Start a server with the default config and return its url once it is listening
+pub async fn server() -> String {
+    server_with(|builder| builder).await
+}
+
+/// Start a server set up by `configure` on a free port, whatever address its config names
+pub async fn server_with(
+    configure: impl FnOnce(WebsocketServerBuilder) -> WebsocketServerBuilder,
+) -> String {
+    start(configure, std::future::pending()).await.0
+}
+
+/// A server that shuts down once `stop` is called, like on a restart
+pub struct StoppableServer {
+    pub url: String,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl StoppableServer {
+    /// Shut the server down and wait until it has, its games saved
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        tokio::time::timeout(Duration::from_secs(10), self.task)
+            .await
+            .expect("The server didn't shut down in time")
+            .unwrap();
+    }
+}
+
+pub async fn stoppable_server_with(
+    configure: impl FnOnce(WebsocketServerBuilder) -> WebsocketServerBuilder,
+) -> StoppableServer {
+    let (shutdown, stopped) = oneshot::channel::<()>();
+    let (url, task) = start(configure, async {
+        let _ = stopped.await;
+    })
+    .await;
+    StoppableServer {
+        url,
+        shutdown,
+        task,
+    }
+}
+
+async fn start(
+    configure: impl FnOnce(WebsocketServerBuilder) -> WebsocketServerBuilder,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> (String, JoinHandle<()>) {
+    let address = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+    let server = configure(WebsocketServer::builder())
+        .bind_address(&address)
+        .build();
+    let task = tokio::spawn(async move { server.run_until(shutdown).await.unwrap() });
+    for _ in 0..50 {
+        if tokio::net::TcpStream::connect(&address).await.is_ok() {
+            return (format!("ws://{}", address), task);
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("The server never started listening on {}", address);
+}
+
+/// Games start as soon as everyone is ready, without the countdown
+pub fn no_countdown(builder: WebsocketServerBuilder) -> WebsocketServerBuilder {
+    builder.timers(TimerConfig {
+        start_countdown_secs: 0,
+        ..TimerConfig::default()
+    })
+}
+
+pub async fn connect(url: &str) -> Client {
+    within(Client::connect(url)).await.unwrap()
+}
+
+pub async fn within<T>(step: impl Future<Output = ClientResult<T>>) -> ClientResult<T> {
+    tokio::time::timeout(Duration::from_secs(10), step)
+        .await
+        .expect("The server didn't answer in time")
+}
+
+/// A game between `players`, seated in that order and dealt from the base set with seed 7
+pub fn game<P: AsRef<str>>(players: &[P]) -> GameBuilder {
+    GameBuilder {
+        players: players
+            .iter()
+            .map(|player| player.as_ref().to_string())
+            .collect(),
+        seed: 7,
+        rules: RuleSet::default(),
+        setup: Vec::new(),
+    }
+}
+
+type Setup = Box<dyn FnOnce(&mut GameCoordinator)>;
+
+pub struct GameBuilder {
+    players: Vec<String>,
+    seed: u64,
+    rules: RuleSet,
+    setup: Vec<Setup>,
+}
+
+impl GameBuilder {
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Done to the coordinator before the game is initialized, such as `enable_undo`
+    pub fn setup(mut self, setup: impl FnOnce(&mut GameCoordinator) + 'static) -> Self {
+        self.setup.push(Box::new(setup));
+        self
+    }
+
+    /// The initialized game and what it sent. Connection ids are the player ids, so a
+    /// recipient is the player it was meant for.
+    pub async fn start(self) -> (GameCoordinator, mpsc::UnboundedReceiver<ConnectionCommand>) {
+        let (cmd_sender, cmd_receiver) = mpsc::unbounded_channel();
+        let connections: HashMap<String, String> = self
+            .players
+            .iter()
+            .map(|id| (id.clone(), id.clone()))
+            .collect();
+        let mut coordinator = GameCoordinator::with_seed(
+            connections,
+            TurnOrder::from_order(self.players),
+            Messenger::new(cmd_sender),
+            self.seed,
+            &[BASE_SET.to_string()],
+            &self.rules,
+        );
+        for setup in self.setup {
+            setup(&mut coordinator);
+        }
+        coordinator.initialize_game().await;
+        (coordinator, cmd_receiver)
+    }
+}
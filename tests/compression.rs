@@ -0,0 +1,105 @@
+//! Clients that connect with `compress=gzip` get large messages as gzipped binary frames,
+//! everyone else keeps getting plain text.
+
+mod common;
+
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use isaac_four_souls::network::compression::{gunzip, gzip, Compression};
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls::ServerConfig;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+
+#[test]
+fn only_clients_asking_for_gzip_get_it() {
+    assert_eq!(Compression::negotiate(None), Compression::None);
+    assert_eq!(
+        Compression::negotiate(Some("lang=it&compress=gzip")),
+        Compression::Gzip
+    );
+    assert_eq!(
+        Compression::negotiate(Some("compress=brotli")),
+        Compression::None
+    );
+}
+
+#[test]
+fn small_messages_stay_text() {
+    let small = r#"{"Pong":null}"#;
+    assert_eq!(
+        Compression::Gzip.frame(small, 64),
+        Message::Text(small.to_string())
+    );
+    let large = format!(r#"{{"Chat":"{}"}}"#, "a".repeat(1000));
+    let Message::Binary(bytes) = Compression::Gzip.frame(&large, 64) else {
+        panic!("A message over the threshold should be compressed");
+    };
+    assert!(bytes.len() < large.len());
+    assert_eq!(gunzip(&bytes).unwrap(), large);
+    // Never with a threshold of 0, nor for clients that didn't ask
+    assert!(matches!(
+        Compression::Gzip.frame(&large, 0),
+        Message::Text(_)
+    ));
+    assert!(matches!(
+        Compression::None.frame(&large, 64),
+        Message::Text(_)
+    ));
+    assert_eq!(gunzip(&gzip(small).unwrap()).unwrap(), small);
+}
+
+async fn server() -> String {
+    let mut config = ServerConfig::default();
+    config.outbound.compress_above_bytes = 256;
+    common::server_with(|builder| builder.config(config)).await
+}
+
+/// Ask for the protocol info, a large message, and return the frame it came in
+async fn protocol_info_frame(url: &str) -> Message {
+    let (mut socket, _) = connect_async(url).await.unwrap();
+    socket
+        .send(Message::Text(r#"{"GetProtocolInfo":null}"#.to_string()))
+        .await
+        .unwrap();
+    loop {
+        let frame = tokio::time::timeout(Duration::from_secs(10), socket.next())
+            .await
+            .expect("Timed out waiting for the server")
+            .unwrap()
+            .unwrap();
+        let text = match &frame {
+            Message::Text(text) => text.clone(),
+            Message::Binary(bytes) => gunzip(bytes).unwrap(),
+            _ => continue,
+        };
+        if text.contains("ProtocolInfo") {
+            return frame;
+        }
+    }
+}
+
+#[tokio::test]
+async fn large_messages_are_gzipped_for_clients_that_asked() {
+    let url = server().await;
+    assert!(matches!(
+        protocol_info_frame(&format!("{}/?compress=gzip", url)).await,
+        Message::Binary(_)
+    ));
+    assert!(matches!(protocol_info_frame(&url).await, Message::Text(_)));
+}
+
+#[tokio::test]
+async fn the_client_reads_compressed_frames() {
+    let url = server().await;
+    let mut client = common::connect(&format!("{}/?compress=gzip", url)).await;
+    client.send(ClientMessage::GetProtocolInfo).await.unwrap();
+    let info = tokio::time::timeout(
+        Duration::from_secs(10),
+        client.wait_for(|response| matches!(response, ServerResponse::ProtocolInfo { .. })),
+    )
+    .await
+    .unwrap();
+    assert!(info.is_ok());
+}
@@ -0,0 +1,156 @@
+//! Conceding takes a player out for good and the game plays on without them until one player
+//! is left; a draw ends the game with no winner once everyone still playing agrees, and is
+//! off if whoever offered it takes it back or the turn ends first.
+
+mod common;
+
+use isaac_four_souls::game::game_coordinator::{GameCoordinator, GameEvent};
+use isaac_four_souls::AppError;
+
+async fn game(players: &[&str]) -> GameCoordinator {
+    common::game(players).start().await.0
+}
+
+async fn concede(coordinator: &mut GameCoordinator, player_id: &str) {
+    coordinator
+        .handle_event(GameEvent::Concede {
+            player_id: player_id.to_string(),
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn the_active_player_conceding_hands_the_turn_on() {
+    let mut coordinator = game(&["alice", "bob", "carol"]).await;
+    let before = coordinator.game_state().clone();
+    assert_eq!(before.turn_order.active_player_id, "alice");
+    let hand = before.board.players_hands["alice"].clone();
+    assert!(!hand.is_empty());
+
+    concede(&mut coordinator, "alice").await;
+
+    let state = coordinator.game_state();
+    assert_eq!(state.turn_order.order, ["bob", "carol"]);
+    assert_eq!(state.conceded, ["alice"]);
+    assert_eq!(state.turn_order.active_player_id, "bob");
+    assert_eq!(state.current_priority_player, "bob");
+    assert_eq!(
+        state.turn_order.get_turn_counter(),
+        before.turn_order.get_turn_counter() + 1
+    );
+    assert!(!state.board.players.contains_key("alice"));
+    assert!(!state.board.players_hands.contains_key("alice"));
+    for card in &hand {
+        assert!(state
+            .board
+            .loot_discard
+            .iter()
+            .any(|discarded| discarded.entity_id == card.entity_id));
+    }
+    assert!(coordinator.is_running());
+}
+
+#[tokio::test]
+async fn conceding_out_of_turn_keeps_the_turn() {
+    let mut coordinator = game(&["alice", "bob", "carol"]).await;
+    let turn = coordinator.game_state().turn_order.get_turn_counter();
+
+    concede(&mut coordinator, "bob").await;
+
+    let state = coordinator.game_state();
+    assert_eq!(state.turn_order.active_player_id, "alice");
+    assert_eq!(state.turn_order.get_turn_counter(), turn);
+    let conceded_again = coordinator
+        .handle_event(GameEvent::Concede {
+            player_id: "bob".to_string(),
+        })
+        .await;
+    assert!(matches!(conceded_again, Err(AppError::PlayerNotFound)));
+}
+
+#[tokio::test]
+async fn the_last_player_standing_wins() {
+    let mut coordinator = game(&["alice", "bob"]).await;
+    concede(&mut coordinator, "bob").await;
+
+    assert!(!coordinator.is_running());
+    assert_eq!(coordinator.winner(), Some("alice"));
+}
+
+#[tokio::test]
+async fn a_draw_needs_everyone_else_to_agree() {
+    let mut coordinator = game(&["alice", "bob", "carol"]).await;
+    coordinator.offer_draw("alice").await.unwrap();
+    assert!(matches!(
+        coordinator.offer_draw("bob").await,
+        Err(AppError::DrawNotAllowed { .. })
+    ));
+    assert!(matches!(
+        coordinator.respond_to_draw("alice", true).await,
+        Err(AppError::DrawOfferNotFound)
+    ));
+
+    coordinator.respond_to_draw("bob", true).await.unwrap();
+    assert!(coordinator.is_running());
+    coordinator.respond_to_draw("carol", true).await.unwrap();
+    assert!(!coordinator.is_running());
+    assert_eq!(coordinator.winner(), None);
+}
+
+#[tokio::test]
+async fn one_refusal_or_a_concession_settles_the_draw() {
+    let mut coordinator = game(&["alice", "bob", "carol"]).await;
+    coordinator.offer_draw("alice").await.unwrap();
+    coordinator.respond_to_draw("bob", false).await.unwrap();
+    assert!(matches!(
+        coordinator.respond_to_draw("carol", true).await,
+        Err(AppError::DrawOfferNotFound)
+    ));
+
+    // Nobody waits on a player who left
+    coordinator.offer_draw("alice").await.unwrap();
+    coordinator.respond_to_draw("bob", true).await.unwrap();
+    concede(&mut coordinator, "carol").await;
+    assert!(!coordinator.is_running());
+    assert_eq!(coordinator.winner(), None);
+}
+
+#[tokio::test]
+async fn only_whoever_offered_a_draw_can_withdraw_it() {
+    let mut coordinator = game(&["alice", "bob", "carol"]).await;
+    assert!(matches!(
+        coordinator.withdraw_draw_offer("alice").await,
+        Err(AppError::DrawOfferNotFound)
+    ));
+    coordinator.offer_draw("alice").await.unwrap();
+    assert!(matches!(
+        coordinator.withdraw_draw_offer("bob").await,
+        Err(AppError::DrawOfferNotFound)
+    ));
+
+    coordinator.withdraw_draw_offer("alice").await.unwrap();
+    assert!(matches!(
+        coordinator.respond_to_draw("bob", true).await,
+        Err(AppError::DrawOfferNotFound)
+    ));
+    coordinator.offer_draw("bob").await.unwrap();
+}
+
+#[tokio::test]
+async fn a_draw_offer_lapses_when_the_turn_ends() {
+    let mut coordinator = game(&["alice", "bob"]).await;
+    coordinator.offer_draw("bob").await.unwrap();
+    coordinator
+        .handle_event(GameEvent::TurnPass {
+            player_id: "alice".to_string(),
+        })
+        .await
+        .unwrap();
+    assert!(matches!(
+        coordinator.respond_to_draw("alice", true).await,
+        Err(AppError::DrawOfferNotFound)
+    ));
+    assert!(coordinator.is_running());
+    coordinator.offer_draw("alice").await.unwrap();
+}
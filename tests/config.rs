@@ -0,0 +1,73 @@
+//! Every field of the config file can also be set from the environment and the command line,
+//! and settings the server can't run with are refused.
+
+use isaac_four_souls::config::{TlsConfig, SETTINGS};
+use isaac_four_souls::ServerConfig;
+use serde_json::Value;
+
+/// Dotted names of the leaves of a serialized config, as they are spelled in `SETTINGS`
+fn keys(prefix: &str, value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                let key = match prefix {
+                    "" => name.clone(),
+                    prefix => format!("{}.{}", prefix, name),
+                };
+                keys(&key, field, out);
+            }
+        }
+        _ => out.push(prefix.to_string()),
+    }
+}
+
+#[test]
+fn every_config_field_is_a_setting() {
+    // Optional sections only show their fields when they are there
+    let config = ServerConfig {
+        tls: Some(TlsConfig {
+            cert_path: "cert.pem".into(),
+            key_path: "key.pem".into(),
+        }),
+        ..ServerConfig::default()
+    };
+    let mut found = Vec::new();
+    keys("", &serde_json::to_value(&config).unwrap(), &mut found);
+
+    let missing: Vec<_> = found
+        .iter()
+        .filter(|key| !SETTINGS.contains(&key.as_str()))
+        .collect();
+    assert!(missing.is_empty(), "not in SETTINGS: {:?}", missing);
+}
+
+#[test]
+fn every_setting_can_be_set() {
+    let mut config = ServerConfig::default();
+    for key in SETTINGS {
+        // The value may not parse, but the key has to be known
+        if let Err(error) = config.set(key, "1") {
+            assert!(!error.starts_with("unknown setting"), "{}: {}", key, error);
+        }
+    }
+    assert_eq!(config.set("outbound.compress_above_bytes", "512"), Ok(()));
+    assert_eq!(config.outbound.compress_above_bytes, 512);
+}
+
+#[test]
+fn deployment_paths_and_chat_words_come_from_the_command_line() {
+    let args = ["--replay_dir", "replays", "--chat.banned_words=foo, bar"];
+    let config = ServerConfig::load_with_args(args.map(str::to_string)).unwrap();
+    assert_eq!(config.replay_dir, Some("replays".into()));
+    assert_eq!(config.dump_dir, None);
+    assert_eq!(config.chat.banned_words, ["foo", "bar"]);
+}
+
+#[test]
+fn empty_mailboxes_are_refused() {
+    for mailbox in ["lobby", "game", "connection"] {
+        let args = [format!("--mailboxes.{}", mailbox), "0".to_string()];
+        let refused = ServerConfig::load_with_args(args).unwrap_err();
+        assert!(refused.to_string().contains(mailbox), "{}", refused);
+    }
+}
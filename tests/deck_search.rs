@@ -0,0 +1,174 @@
+//! Effects can search a deck for a card: the copy found is taken the way that deck's top card
+//! would be, the deck is shuffled whether or not one was found, and only the searching player
+//! is told what turned up. Discard piles are face up, anyone at the table can look through them.
+
+use std::collections::HashMap;
+
+use isaac_four_souls::game::board::{Board, DeckKind};
+use isaac_four_souls::game::card_loader::BASE_SET;
+use isaac_four_souls::game::deadlines::WindowClock;
+use isaac_four_souls::game::game_state::GameState;
+use isaac_four_souls::game::rules::RuleSet;
+use isaac_four_souls::game::state_broadcaster::StateBroadcaster;
+use isaac_four_souls::game::triggers::{StackEntry, TriggerEffect};
+use isaac_four_souls::network::messenger::Messenger;
+use isaac_four_souls::{ConnectionCommand, TurnOrder};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+fn board() -> Board {
+    Board::new(
+        vec!["alice".to_string(), "bob".to_string()],
+        7,
+        &[BASE_SET.to_string()],
+        &RuleSet::default(),
+    )
+}
+
+fn deck_ids(board: &Board, deck: DeckKind) -> Vec<String> {
+    board
+        .deck_cards(deck)
+        .map(|card| card.entity_id.clone())
+        .collect()
+}
+
+#[test]
+fn a_found_card_is_taken_and_the_deck_shuffled() {
+    let mut board = board();
+    // Deep enough in the deck that a draw wouldn't have found it
+    let wanted = board.deck_cards(DeckKind::Loot).nth(5).unwrap().clone();
+    let before = deck_ids(&board, DeckKind::Loot);
+
+    let search = board
+        .search_deck(DeckKind::Loot, "alice", &wanted.template_id)
+        .unwrap();
+    let found = search.found.expect("The deck had a copy");
+    assert_eq!(found.template_id, wanted.template_id);
+    assert!(board.players_hands["alice"]
+        .iter()
+        .any(|card| card.entity_id == found.entity_id));
+
+    let after = deck_ids(&board, DeckKind::Loot);
+    assert_eq!(after.len(), before.len() - 1);
+    let mut expected: Vec<&String> = before.iter().filter(|id| **id != found.entity_id).collect();
+    assert_ne!(after.iter().collect::<Vec<_>>(), expected, "Not shuffled");
+    let mut shuffled: Vec<&String> = after.iter().collect();
+    expected.sort();
+    shuffled.sort();
+    assert_eq!(shuffled, expected);
+}
+
+#[test]
+fn a_treasure_found_goes_into_play() {
+    let mut board = board();
+    let wanted = board.deck_cards(DeckKind::Treasure).last().unwrap().clone();
+    let search = board
+        .search_deck(DeckKind::Treasure, "bob", &wanted.template_id)
+        .unwrap();
+    let found = search.found.unwrap();
+    assert!(board.players_items["bob"]
+        .iter()
+        .any(|item| item.entity_id == found.entity_id));
+}
+
+#[test]
+fn a_search_that_finds_nothing_still_shuffles() {
+    let mut board = board();
+    let before = deck_ids(&board, DeckKind::Loot);
+    let hand = board.players_hands["alice"].len();
+
+    let search = board
+        .search_deck(DeckKind::Loot, "alice", "no_such_card")
+        .unwrap();
+    assert!(search.found.is_none());
+    assert_eq!(board.players_hands["alice"].len(), hand);
+    assert_ne!(deck_ids(&board, DeckKind::Loot), before);
+    assert!(board.deck_searches["alice"].found.is_none());
+}
+
+#[test]
+fn discard_piles_read_newest_first() {
+    let mut board = board();
+    let first = board.players_hands["alice"][0].entity_id.clone();
+    let second = board.players_hands["alice"][1].entity_id.clone();
+    board.discard_from_hand("alice", &first).unwrap();
+    board.discard_from_hand("alice", &second).unwrap();
+
+    let pile: Vec<&str> = board
+        .discard_cards(DeckKind::Loot)
+        .iter()
+        .map(|card| card.entity_id.as_str())
+        .collect();
+    assert_eq!(pile, vec![second.as_str(), first.as_str()]);
+    assert!(board.discard_cards(DeckKind::Treasure).is_empty());
+
+    let curse = board.attach_curse("bob").unwrap();
+    board.remove_curse("bob", &curse.entity_id).unwrap();
+    assert_eq!(board.discard_cards(DeckKind::Curse).len(), 1);
+    assert!(board.discard_cards(DeckKind::Event).is_empty());
+}
+
+/// Each player's last PrivateBoardState
+async fn private_states(state: &GameState) -> HashMap<String, Value> {
+    let (cmd_sender, mut commands) = mpsc::unbounded_channel();
+    let connections: HashMap<String, String> = ["alice", "bob"]
+        .iter()
+        .map(|id| (id.to_string(), id.to_string()))
+        .collect();
+    let broadcaster = StateBroadcaster::new(connections, Messenger::new(cmd_sender));
+    for player_id in ["alice", "bob"] {
+        broadcaster
+            .send_full_state_to_player(state, &WindowClock::default(), player_id)
+            .await;
+    }
+
+    let mut private = HashMap::new();
+    while let Ok(command) = commands.try_recv() {
+        if let ConnectionCommand::SendToPlayer {
+            connection_id,
+            message,
+        } = command
+        {
+            let value: Value = serde_json::from_str(&message).unwrap();
+            if let Some(state) = value.get("PrivateBoardState") {
+                private.insert(connection_id, state.clone());
+            }
+        }
+    }
+    private
+}
+
+#[tokio::test]
+async fn only_the_searching_player_learns_what_was_found() {
+    let players = vec!["alice".to_string(), "bob".to_string()];
+    let mut state = GameState::new(
+        players.clone(),
+        TurnOrder::from_order(players),
+        7,
+        &[BASE_SET.to_string()],
+        &RuleSet::default(),
+    );
+    let wanted = state
+        .board
+        .deck_cards(DeckKind::Loot)
+        .nth(3)
+        .unwrap()
+        .clone();
+    let search = StackEntry {
+        source_id: "item-1".to_string(),
+        source_name: "Crystal Ball".to_string(),
+        owner_id: "alice".to_string(),
+        effect: TriggerEffect::SearchDeck {
+            deck: DeckKind::Loot,
+            template_id: wanted.template_id.clone(),
+        },
+    };
+    assert_eq!(search.effect.to_string(), "searched the loot deck");
+    search.resolve(&mut state.board, &mut state.turn_order);
+
+    let private = private_states(&state).await;
+    let last_search = &private["alice"]["last_search"];
+    assert_eq!(last_search["deck"], "Loot");
+    assert_eq!(last_search["found"]["template_id"], wanted.template_id);
+    assert_eq!(private["bob"]["last_search"], Value::Null);
+}
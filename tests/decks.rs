@@ -0,0 +1,83 @@
+//! Decks keep one order from the moment they're built: drawing takes the top card without
+//! touching the rest, cards can go back on the bottom, and only a reshuffle of the discard
+//! pile changes the order again. A saved deck is still the plain list of its cards.
+
+use isaac_four_souls::game::board::{Board, DeckKind};
+use isaac_four_souls::game::card_loader::BASE_SET;
+use isaac_four_souls::game::deck::Deck;
+use isaac_four_souls::game::rules::RuleSet;
+
+fn board() -> Board {
+    Board::new(
+        vec!["alice".to_string(), "bob".to_string()],
+        7,
+        &[BASE_SET.to_string()],
+        &RuleSet::default(),
+    )
+}
+
+fn deck_ids(board: &Board, deck: DeckKind) -> Vec<String> {
+    board
+        .deck_cards(deck)
+        .map(|card| card.entity_id.clone())
+        .collect()
+}
+
+#[test]
+fn draws_come_off_the_top_and_leave_the_rest_in_order() {
+    let mut deck = Deck::new(vec![1, 2, 3, 4]);
+    assert_eq!(deck.peek(2).copied().collect::<Vec<_>>(), vec![4, 3]);
+    assert_eq!(deck.draw(), Some(4));
+    assert_eq!(deck.draw_up_to(2), vec![3, 2]);
+    deck.put_on_bottom(5);
+    deck.put_on_top(6);
+    assert_eq!(deck.iter().copied().collect::<Vec<_>>(), vec![5, 1, 6]);
+    assert_eq!(deck.draw_up_to(10), vec![6, 1, 5]);
+    assert!(deck.is_empty());
+    assert_eq!(deck.draw(), None);
+}
+
+#[test]
+fn drawing_loot_keeps_the_deck_order() {
+    let mut board = board();
+    let before = deck_ids(&board, DeckKind::Loot);
+    board.draw_loot_for_player("alice").unwrap();
+    board.draw_loot_for_player("bob").unwrap();
+    assert_eq!(deck_ids(&board, DeckKind::Loot), before[2..]);
+}
+
+#[test]
+fn the_top_card_can_go_to_the_bottom() {
+    let mut board = board();
+    let before = deck_ids(&board, DeckKind::Treasure);
+    let moved = board.put_top_on_bottom(DeckKind::Treasure).unwrap();
+    assert_eq!(moved.entity_id, before[0]);
+
+    let after = deck_ids(&board, DeckKind::Treasure);
+    assert_eq!(after[..after.len() - 1], before[1..]);
+    assert_eq!(after.last(), Some(&moved.entity_id));
+}
+
+#[test]
+fn reordering_only_accepts_the_top_cards() {
+    let mut board = board();
+    let mut top = deck_ids(&board, DeckKind::Curse);
+    top.truncate(2);
+    let bottom = deck_ids(&board, DeckKind::Curse).last().unwrap().clone();
+    assert!(board
+        .reorder_top(DeckKind::Curse, &[top[0].clone(), bottom])
+        .is_err());
+
+    top.reverse();
+    board.reorder_top(DeckKind::Curse, &top).unwrap();
+    assert_eq!(deck_ids(&board, DeckKind::Curse)[..2], top);
+}
+
+#[test]
+fn a_saved_deck_is_a_plain_list() {
+    let deck = Deck::new(vec![1, 2, 3]);
+    let saved = serde_json::to_string(&deck).unwrap();
+    assert_eq!(saved, "[1,2,3]");
+    let loaded: Deck<i32> = serde_json::from_str(&saved).unwrap();
+    assert_eq!(loaded.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+}
@@ -0,0 +1,49 @@
+//! A name already seated in a room is suffixed or refused, depending on the room's options.
+
+use isaac_four_souls::network::room::{DuplicateNames, Room, RoomOptions};
+use isaac_four_souls::AppError;
+
+fn room(duplicate_names: DuplicateNames) -> Room {
+    Room::new(
+        "Basement".to_string(),
+        RoomOptions {
+            duplicate_names,
+            ..RoomOptions::default()
+        },
+    )
+}
+
+#[test]
+fn duplicates_are_suffixed_by_default() {
+    let mut room = room(DuplicateNames::default());
+    let names: Vec<String> = (0..3)
+        .map(|_| room.add_player("Alice".to_string()).unwrap().1)
+        .collect();
+    assert_eq!(names, ["Alice", "Alice (2)", "Alice (3)"]);
+}
+
+#[test]
+fn a_freed_suffix_is_reused() {
+    let mut room = room(DuplicateNames::Suffix);
+    room.add_player("Alice".to_string()).unwrap();
+    let (second, _) = room.add_player("Alice".to_string()).unwrap();
+    room.add_player("Alice".to_string()).unwrap();
+    room.remove_player(&second).unwrap();
+
+    let (_, name) = room.add_player("Alice".to_string()).unwrap();
+    assert_eq!(name, "Alice (2)");
+}
+
+#[test]
+fn rejecting_rooms_refuse_a_taken_name() {
+    let mut room = room(DuplicateNames::Reject);
+    room.add_player("Alice".to_string()).unwrap();
+
+    let refused = room.add_player("Alice".to_string());
+    assert!(matches!(
+        refused,
+        Err(AppError::PlayerNameTaken { player_name }) if player_name == "Alice"
+    ));
+    assert_eq!(room.player_count(), 1);
+    assert_eq!(room.add_player("Bob".to_string()).unwrap().1, "Bob");
+}
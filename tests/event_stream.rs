@@ -0,0 +1,114 @@
+//! Every change to a game goes through the domain events in its stream: an accepted action
+//! adds what happened, a rejected one adds nothing, an undo takes its events back out, and
+//! the stream on its own always rebuilds the live game.
+
+mod common;
+
+use isaac_four_souls::game::domain_events::DomainEvent;
+use isaac_four_souls::game::game_coordinator::{GameCoordinator, GameEvent};
+use isaac_four_souls::game::game_state::GameState;
+use isaac_four_souls::game::trades::TradeOffer;
+
+async fn game(undo: bool) -> GameCoordinator {
+    let mut game = common::game(&["alice", "bob"]);
+    if undo {
+        game = game.setup(GameCoordinator::enable_undo);
+    }
+    game.start().await.0
+}
+
+// Sets serialize in no particular order, so they're sorted before comparing
+fn comparable(state: &GameState) -> serde_json::Value {
+    let mut value = serde_json::to_value(state).unwrap();
+    for set in ["pending_mulligans", "players_passed_priority"] {
+        if let Some(serde_json::Value::Array(items)) = value.get_mut(set) {
+            items.sort_by_key(|item| item.to_string());
+        }
+    }
+    value
+}
+
+fn assert_rebuilds(coordinator: &GameCoordinator) {
+    assert_eq!(
+        comparable(&coordinator.rebuild_state().unwrap()),
+        comparable(coordinator.game_state())
+    );
+}
+
+#[tokio::test]
+async fn accepted_actions_add_what_happened() {
+    let mut coordinator = game(false).await;
+    // Opening the first turn is recorded too
+    assert!(!coordinator.events().is_empty());
+    assert_rebuilds(&coordinator);
+
+    let seq = coordinator.events().last_seq();
+    let rejected = coordinator
+        .handle_event(GameEvent::TurnPass {
+            player_id: "bob".to_string(),
+        })
+        .await;
+    assert!(rejected.is_err());
+    assert_eq!(coordinator.events().last_seq(), seq);
+
+    coordinator
+        .handle_event(GameEvent::TurnPass {
+            player_id: "alice".to_string(),
+        })
+        .await
+        .unwrap();
+    let delta = coordinator.events().since(seq);
+    assert_eq!(delta.len(), 1);
+    assert_eq!(delta[0].seq, seq + 1);
+    assert!(
+        matches!(&delta[0].event, DomainEvent::TurnPassed { player_id } if player_id == "alice")
+    );
+    assert_eq!(coordinator.game_state().turn_order.active_player_id, "bob");
+    assert_rebuilds(&coordinator);
+}
+
+#[tokio::test]
+async fn an_undo_takes_its_events_back_out() {
+    let mut coordinator = game(true).await;
+    let before = coordinator.events().len();
+    let card_id = coordinator.game_state().board.players_hands["alice"][0]
+        .entity_id
+        .clone();
+    coordinator
+        .handle_event(GameEvent::ProposeTrade {
+            player_id: "alice".to_string(),
+            to_player: "bob".to_string(),
+            offer: TradeOffer {
+                coins: 0,
+                loot: vec![card_id],
+            },
+            request: TradeOffer::default(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(coordinator.events().len(), before + 1);
+
+    coordinator.request_undo("alice").await.unwrap();
+    coordinator.respond_to_undo("bob", true).await.unwrap();
+    assert_eq!(coordinator.events().len(), before);
+    assert!(coordinator.game_state().pending_trades.is_empty());
+    assert_rebuilds(&coordinator);
+}
+
+#[tokio::test]
+async fn a_death_whose_penalty_is_refused_takes_no_damage() {
+    let coordinator = game(false).await;
+    let mut state = coordinator.game_state().clone();
+    // Without a hand the death penalty can't be worked out
+    state.board.players_hands.remove("bob");
+    let health = state.board.players["bob"].current_health;
+    let untouched = comparable(&state);
+
+    let refused = state.apply(&DomainEvent::PlayerDamaged {
+        target_id: "bob".to_string(),
+        amount: health,
+    });
+    assert!(refused.is_err());
+    assert!(!state.board.players["bob"].dead);
+    assert_eq!(comparable(&state), untouched);
+}
@@ -0,0 +1,126 @@
+//! Adversarial client frames against the FrameGuard that sits in front of message parsing.
+
+use isaac_four_souls::config::InboundConfig;
+use isaac_four_souls::network::frame_guard::{exceeds_depth, FrameGuard};
+use isaac_four_souls::network::messages::deserialize_message;
+use isaac_four_souls::AppError;
+use proptest::prelude::*;
+use tokio_tungstenite::tungstenite::Message;
+
+fn guard() -> FrameGuard {
+    FrameGuard::new(InboundConfig {
+        max_frame_bytes: 1024,
+        max_json_depth: 8,
+        max_malformed_frames: 3,
+        max_batched_messages: 4,
+        max_reliable_window: 8,
+    })
+}
+
+fn nested(depth: usize) -> String {
+    format!("{}{}", "[".repeat(depth), "]".repeat(depth))
+}
+
+fn is_malformed(result: Result<Option<&str>, AppError>) -> bool {
+    matches!(result, Err(AppError::MalformedFrame { .. }))
+}
+
+#[test]
+fn well_formed_messages_pass() {
+    let guard = guard();
+    for text in [
+        r#""Ping""#,
+        r#"{"Chat":{"message":"hi"}}"#,
+        r#"{"ProposeTrade":{"to_player":"p2","offer":{"coins":2,"loot":["a","b"]}}}"#,
+    ] {
+        let message = Message::Text(text.to_string());
+        assert_eq!(guard.inspect(&message).unwrap(), Some(text));
+    }
+}
+
+#[test]
+fn control_frames_are_skipped() {
+    let guard = guard();
+    assert_eq!(guard.inspect(&Message::Ping(vec![1, 2, 3])).unwrap(), None);
+    assert_eq!(guard.inspect(&Message::Pong(Vec::new())).unwrap(), None);
+}
+
+#[test]
+fn oversized_frames_are_refused() {
+    let guard = guard();
+    let padding = "a".repeat(1024);
+    let message = Message::Text(format!(r#"{{"Chat":{{"message":"{}"}}}}"#, padding));
+    assert!(is_malformed(guard.inspect(&message)));
+    assert!(guard.check_text(&"a".repeat(1024)).is_ok());
+}
+
+#[test]
+fn binary_frames_are_refused() {
+    let guard = guard();
+    let message = Message::Binary(br#"{"Chat":{"message":"hi"}}"#.to_vec());
+    assert!(is_malformed(guard.inspect(&message)));
+}
+
+#[test]
+fn deep_nesting_is_refused_before_parsing() {
+    let guard = guard();
+    assert!(guard.check_text(&nested(8)).is_ok());
+    assert!(guard.check_text(&nested(9)).is_err());
+    // Unbalanced openers never close, the scan still stops at the limit
+    assert!(exceeds_depth(&"{".repeat(100_000), 8));
+    assert!(exceeds_depth(&format!(r#"{{"Chat":{}}}"#, nested(20)), 8));
+}
+
+#[test]
+fn brackets_inside_strings_do_not_count() {
+    let text = format!(r#"{{"Chat":{{"message":"{}"}}}}"#, "[{".repeat(50));
+    assert!(!exceeds_depth(&text, 8));
+    // An escaped quote doesn't end the string
+    let escaped = format!(r#"{{"Chat":{{"message":"\"{}"}}}}"#, "[".repeat(50));
+    assert!(!exceeds_depth(&escaped, 8));
+    // A trailing backslash escapes the backslash, not the closing quote
+    let backslash = format!(r#"{{"Chat":{{"message":"\\"}}, "x": {}}}"#, nested(10));
+    assert!(exceeds_depth(&backslash, 8));
+}
+
+#[test]
+fn stray_closers_do_not_hide_nesting() {
+    let text = format!("]]]]]]]]]]{}", nested(9));
+    assert!(exceeds_depth(&text, 8));
+}
+
+#[test]
+fn repeated_malformed_frames_trip_the_limit() {
+    let guard = guard();
+    assert!(!guard.record_malformed());
+    assert!(!guard.record_malformed());
+    assert!(guard.record_malformed());
+}
+
+proptest! {
+    // Whatever gets through the guard, parsing it fails cleanly or yields a message
+    #[test]
+    fn arbitrary_text_never_panics(text in "\\PC{0,300}") {
+        let guard = guard();
+        let message = Message::Text(text);
+        if let Ok(Some(text)) = guard.inspect(&message) {
+            let _ = deserialize_message(text);
+        }
+    }
+
+    #[test]
+    fn json_shaped_noise_never_panics(
+        text in proptest::collection::vec(
+            prop_oneof![
+                Just("{"), Just("}"), Just("["), Just("]"), Just("\""), Just("\\"),
+                Just(":"), Just(","), Just("\"Chat\""), Just("\"message\""), Just("1"),
+            ],
+            0..200,
+        ).prop_map(|parts| parts.concat())
+    ) {
+        let guard = guard();
+        if guard.check_text(&text).is_ok() {
+            let _ = deserialize_message(&text);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Every game sends through a channel of its own, fanned out to the connections on its own
+//! task, and what the lobby sent before a game opened its channel still arrives first.
+
+use std::time::Duration;
+
+use isaac_four_souls::network::connection_manager::ConnectionSender;
+use isaac_four_souls::network::messages::{serialize_response, ServerResponse, SharedMessage};
+use isaac_four_souls::network::messenger::Messenger;
+use isaac_four_souls::{CommandProcessor, ConnectionCommand, ConnectionManager};
+use tokio::sync::mpsc;
+
+/// A messenger backed by the server-wide command loop, and a local connection for `player`
+fn connected(player: &str) -> (Messenger, mpsc::UnboundedReceiver<SharedMessage>) {
+    let connection_manager = ConnectionManager::default();
+    let (cmd_sender, mut cmd_receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(command) = cmd_receiver.recv().await {
+            let _ = CommandProcessor::process_command(command, &connection_manager).await;
+        }
+    });
+    let (sender, received) = mpsc::unbounded_channel();
+    cmd_sender
+        .send(ConnectionCommand::AddConnection {
+            id: player.to_string(),
+            sender: ConnectionSender::Local(sender),
+        })
+        .unwrap();
+    let messenger = Messenger::new(cmd_sender);
+    messenger.bind_player(player, player);
+    (messenger, received)
+}
+
+fn chat(text: &str) -> ServerResponse {
+    ServerResponse::LobbyChatMessage {
+        sender_name: "server".to_string(),
+        message: text.to_string(),
+    }
+}
+
+async fn next(received: &mut mpsc::UnboundedReceiver<SharedMessage>) -> String {
+    tokio::time::timeout(Duration::from_secs(5), received.recv())
+        .await
+        .expect("Timed out waiting for a message")
+        .expect("The connection was dropped")
+        .to_string()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn games_never_overtake_the_lobby() {
+    let (lobby, mut received) = connected("alice");
+    for round in 0..20 {
+        lobby
+            .send_to_player_id("alice", chat(&format!("lobby {}", round)))
+            .unwrap();
+        let game = lobby.for_game(&format!("game-{}", round));
+        game.send_to_player_id("alice", chat(&format!("game {}", round)))
+            .unwrap();
+
+        assert!(next(&mut received)
+            .await
+            .contains(&format!("lobby {}", round)));
+        assert!(next(&mut received)
+            .await
+            .contains(&format!("game {}", round)));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn each_game_keeps_its_own_order() {
+    let (lobby, mut received) = connected("alice");
+    let game = lobby.for_game("game");
+    for turn in 0..100 {
+        game.send_to_player_id("alice", chat(&format!("turn {}", turn)))
+            .unwrap();
+    }
+    for turn in 0..100 {
+        let expected = serialize_response(chat(&format!("turn {}", turn)));
+        assert_eq!(next(&mut received).await, expected.to_string());
+    }
+}
@@ -0,0 +1,535 @@
+//! Every client message the connection treats as a game message reaches the game in its game
+//! form, carrying the connection it arrived on, also inside the `Game` wrapper that names one of
+//! several games, a connection seated in two live games reaches the one it names, and priority
+//! passes are played by the rules. Keys and tokens in messages never show when they are logged.
+
+mod common;
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use isaac_four_souls::actors::actor_registry::ActorRegistry;
+use isaac_four_souls::actors::connection_actor::{ConnectionActor, ConnectionMessage};
+use isaac_four_souls::actors::game_actor::{GameMessage, GameServices};
+use isaac_four_souls::actors::lobby_actor::LobbyMessage;
+use isaac_four_souls::actors::lobby_shards::{LobbyDirectory, LobbyShards};
+use isaac_four_souls::actors::tournament_actor::TournamentMessage;
+use isaac_four_souls::config::InboundConfig;
+use isaac_four_souls::game::board::DeckKind;
+use isaac_four_souls::game::game_coordinator::{GameCoordinator, GameEvent};
+use isaac_four_souls::game::game_state::TurnPhases;
+use isaac_four_souls::game::persistence::GameSnapshots;
+use isaac_four_souls::game::ratings::{MemoryRatingStore, Ratings};
+use isaac_four_souls::game::stats::GameSummaries;
+use isaac_four_souls::game::trades::TradeOffer;
+use isaac_four_souls::network::auth::AuthContext;
+use isaac_four_souls::network::chat_filter::ChatFilter;
+use isaac_four_souls::network::frame_guard::MalformedFrames;
+use isaac_four_souls::network::locale::{ConnectionLocales, Locale};
+use isaac_four_souls::network::messages::{
+    AnnouncementSeverity, BatchedClientMessage, ChatChannel, ClientMessage, ClientMessageCategory,
+};
+use isaac_four_souls::network::messenger::Messenger;
+use isaac_four_souls::network::metrics::ServerMetrics;
+use isaac_four_souls::network::permissions::Role;
+use isaac_four_souls::network::room::RoomOptions;
+use isaac_four_souls::network::webhooks::Webhooks;
+use isaac_four_souls::{AppError, ConnectionCommand, ServerConfig};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+const CONNECTION: &str = "connection-1";
+const PLAYERS: [&str; 3] = ["alice", "bob", "carol"];
+
+/// One of each client message
+fn every_client_message() -> Vec<ClientMessage> {
+    let text = || "x".to_string();
+    vec![
+        ClientMessage::Ping,
+        ClientMessage::Chat { message: text() },
+        ClientMessage::CreateRoom {
+            room_name: text(),
+            first_player_name: text(),
+            options: RoomOptions::default(),
+        },
+        ClientMessage::DestroyRoom { room_id: text() },
+        ClientMessage::JoinRoom {
+            player_name: text(),
+            room_id: text(),
+        },
+        ClientMessage::LeaveRoom,
+        ClientMessage::PlayerReady,
+        ClientMessage::PlayerUnready,
+        ClientMessage::MulliganDecision { keep: true },
+        ClientMessage::ActivateItem { item_id: text() },
+        ClientMessage::BuyItem { shop_index: 1 },
+        ClientMessage::PlayLootCard {
+            card_id: text(),
+            tap_character: true,
+        },
+        ClientMessage::ChoiceResponse {
+            choice_id: text(),
+            option_id: text(),
+        },
+        ClientMessage::ProposeTrade {
+            to_player: text(),
+            offer: TradeOffer::default(),
+            request: TradeOffer::default(),
+        },
+        ClientMessage::AcceptTrade { trade_id: text() },
+        ClientMessage::DeclineTrade { trade_id: text() },
+        ClientMessage::RequestUndo,
+        ClientMessage::AcceptUndo,
+        ClientMessage::DeclineUndo,
+        ClientMessage::Concede,
+        ClientMessage::OfferDraw,
+        ClientMessage::AcceptDraw,
+        ClientMessage::DeclineDraw,
+        ClientMessage::WithdrawDraw,
+        ClientMessage::TurnPass,
+        ClientMessage::PriorityPass,
+        ClientMessage::GetSimpleState,
+        ClientMessage::GetLegalActions,
+        ClientMessage::GetTimeRemaining,
+        ClientMessage::ViewDiscardPile {
+            deck_type: DeckKind::Loot,
+        },
+        ClientMessage::GetEventLog { since: Some(3) },
+        ClientMessage::GetPrivateLog { since: None },
+        ClientMessage::ReloadCards {
+            admin_key: text().into(),
+        },
+        ClientMessage::GetProtocolInfo,
+        ClientMessage::GetCardCatalog {
+            known_version: None,
+        },
+        ClientMessage::GetGlossary,
+        ClientMessage::AddBot { strategy: None },
+        ClientMessage::StartSoloGame {
+            player_name: text(),
+            bots: 1,
+            strategy: None,
+            options: RoomOptions::default(),
+        },
+        ClientMessage::LobbyChat { message: text() },
+        ClientMessage::SetChatMuted {
+            channel: ChatChannel::Room,
+            muted: true,
+        },
+        ClientMessage::MutePlayer { player_id: text() },
+        ClientMessage::UnmutePlayer { player_id: text() },
+        ClientMessage::KickPlayer { player_id: text() },
+        ClientMessage::BanPlayer { player_id: text() },
+        ClientMessage::SetRoomWebhook { url: Some(text()) },
+        ClientMessage::RegisterProfile {
+            display_name: text(),
+            avatar_id: None,
+        },
+        ClientMessage::RestoreProfile {
+            token: text().into(),
+        },
+        ClientMessage::AddFriend { profile_id: text() },
+        ClientMessage::RemoveFriend { profile_id: text() },
+        ClientMessage::GetFriends,
+        ClientMessage::InviteToRoom { profile_id: text() },
+        ClientMessage::ResumeSession {
+            session_token: text().into(),
+        },
+        ClientMessage::GetLeaderboard { limit: None },
+        ClientMessage::GetGameSummary { game_id: text() },
+        ClientMessage::DumpGameState {
+            game_id: text(),
+            admin_key: text().into(),
+        },
+        ClientMessage::Announce {
+            admin_key: text().into(),
+            text: text(),
+            severity: AnnouncementSeverity::Warning,
+            send_at: Some(0),
+        },
+        ClientMessage::CancelAnnouncement {
+            admin_key: text().into(),
+            announcement_id: text(),
+        },
+        ClientMessage::PauseGame,
+        ClientMessage::ResumeGame,
+        ClientMessage::ImBack,
+        ClientMessage::SpectateGame { room_id: text() },
+        ClientMessage::StopSpectating { room_id: text() },
+        ClientMessage::CreateTournament {
+            name: text(),
+            options: RoomOptions::default(),
+        },
+        ClientMessage::RegisterForTournament {
+            tournament_id: text(),
+            player_name: text(),
+        },
+        ClientMessage::WithdrawFromTournament {
+            tournament_id: text(),
+        },
+        ClientMessage::StartTournament {
+            tournament_id: text(),
+        },
+        ClientMessage::GetTournament {
+            tournament_id: text(),
+        },
+        ClientMessage::Game {
+            game_id: text(),
+            message: Box::new(ClientMessage::TurnPass),
+        },
+        ClientMessage::BatchedClientMessages {
+            messages: vec![BatchedClientMessage {
+                request_id: text(),
+                message: ClientMessage::PriorityPass,
+            }],
+        },
+    ]
+}
+
+fn variant_name(message: &ClientMessage) -> String {
+    match serde_json::to_value(message).unwrap() {
+        serde_json::Value::String(name) => name,
+        serde_json::Value::Object(fields) => fields.keys().next().unwrap().clone(),
+        other => panic!("Unexpected encoding {}", other),
+    }
+}
+
+fn route(message: ClientMessage) -> Result<GameMessage, AppError> {
+    GameMessage::from_client_message(CONNECTION.to_string(), message)
+}
+
+#[test]
+fn the_samples_cover_every_client_message() {
+    let sampled: BTreeSet<String> = every_client_message().iter().map(variant_name).collect();
+    let advertised: BTreeSet<String> = ClientMessage::VARIANT_NAMES
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    assert_eq!(sampled, advertised);
+}
+
+#[test]
+fn game_messages_reach_the_game_from_their_connection() {
+    for message in every_client_message() {
+        let name = variant_name(&message);
+        let category = message.category();
+        let routed = route(message);
+        match category {
+            ClientMessageCategory::GameMessage => {
+                let game_message =
+                    routed.unwrap_or_else(|error| panic!("{} was refused: {}", name, error));
+                assert_eq!(game_message.connection_id(), Some(CONNECTION), "{}", name);
+            }
+            ClientMessageCategory::LobbyMessage => {
+                assert!(routed.is_err(), "{} is a lobby message", name);
+            }
+            ClientMessageCategory::TournamentMessage => {
+                assert!(routed.is_err(), "{} is a tournament message", name);
+            }
+        }
+    }
+}
+
+#[test]
+fn game_messages_keep_their_fields() {
+    let played = route(ClientMessage::PlayLootCard {
+        card_id: "card".into(),
+        tap_character: true,
+    });
+    assert!(matches!(
+        played,
+        Ok(GameMessage::PlayLootCard { card_id, tap_character: true, .. }) if card_id == "card"
+    ));
+    assert!(matches!(
+        route(ClientMessage::BuyItem { shop_index: 1 }),
+        Ok(GameMessage::BuyItem { shop_index: 1, .. })
+    ));
+    let chosen = route(ClientMessage::ChoiceResponse {
+        choice_id: "choice".into(),
+        option_id: "option".into(),
+    });
+    assert!(matches!(
+        chosen,
+        Ok(GameMessage::ChoiceResponse { choice_id, option_id, .. })
+            if choice_id == "choice" && option_id == "option"
+    ));
+    assert!(matches!(
+        route(ClientMessage::GetEventLog { since: Some(3) }),
+        Ok(GameMessage::GetEventLog { since: Some(3), .. })
+    ));
+    assert!(matches!(
+        route(ClientMessage::AcceptTrade {
+            trade_id: "trade".into()
+        }),
+        Ok(GameMessage::RespondToTrade { accept: true, .. })
+    ));
+    assert!(matches!(
+        route(ClientMessage::DeclineTrade {
+            trade_id: "trade".into()
+        }),
+        Ok(GameMessage::RespondToTrade { accept: false, .. })
+    ));
+    assert!(matches!(
+        route(ClientMessage::AcceptUndo),
+        Ok(GameMessage::RespondToUndo { accept: true, .. })
+    ));
+    assert!(matches!(
+        route(ClientMessage::DeclineUndo),
+        Ok(GameMessage::RespondToUndo { accept: false, .. })
+    ));
+    assert!(matches!(
+        route(ClientMessage::PriorityPass),
+        Ok(GameMessage::PriorityPass { .. })
+    ));
+}
+
+#[test]
+fn credentials_stay_out_of_the_logs() {
+    let message = ClientMessage::Announce {
+        admin_key: "hunter2".into(),
+        text: "Restarting".into(),
+        severity: AnnouncementSeverity::Info,
+        send_at: None,
+    };
+    assert!(!format!("{:?}", message).contains("hunter2"));
+    let resume = ClientMessage::ResumeSession {
+        session_token: "session-secret".into(),
+    };
+    assert!(!format!("{:?}", resume).contains("session-secret"));
+
+    // On the wire they're still plain strings
+    let sent: Value = serde_json::to_value(&message).unwrap();
+    assert_eq!(sent["Announce"]["admin_key"], "hunter2");
+}
+
+#[test]
+fn the_game_wrapper_is_routed_as_its_message() {
+    let wrapped = |message| ClientMessage::Game {
+        game_id: "game".into(),
+        message: Box::new(message),
+    };
+    assert!(matches!(
+        route(wrapped(ClientMessage::GetEventLog { since: Some(3) })),
+        Ok(GameMessage::GetEventLog { since: Some(3), .. })
+    ));
+    assert!(route(wrapped(ClientMessage::LeaveRoom)).is_err());
+
+    // Roles look at the wrapped message too
+    let pause = wrapped(ClientMessage::PauseGame);
+    assert!(!Role::Player.allows(&pause));
+    assert!(Role::Host.allows(&pause));
+    assert!(Role::Player.allows(&wrapped(ClientMessage::TurnPass)));
+}
+
+async fn game() -> GameCoordinator {
+    let (mut coordinator, _) = common::game(&PLAYERS).start().await;
+    // Priority is only passed around once a turn has started
+    let active = coordinator.game_state().turn_order.active_player_id.clone();
+    coordinator
+        .handle_event(GameEvent::TurnPass { player_id: active })
+        .await
+        .unwrap();
+    coordinator
+}
+
+#[tokio::test]
+async fn priority_goes_around_the_table_then_the_phase_advances() {
+    let mut coordinator = game().await;
+    let active = coordinator.game_state().turn_order.active_player_id.clone();
+    let phase = coordinator.game_state().current_phase.clone();
+    assert_eq!(phase, TurnPhases::UntapStartStep);
+
+    let mut holders = Vec::new();
+    for _ in PLAYERS {
+        let holder = coordinator.game_state().current_priority_player.clone();
+        holders.push(holder.clone());
+        coordinator
+            .handle_event(GameEvent::PriorityPass { player_id: holder })
+            .await
+            .unwrap();
+    }
+
+    holders.sort();
+    assert_eq!(holders, PLAYERS);
+    let state = coordinator.game_state();
+    assert_eq!(state.current_phase, TurnPhases::LootStep);
+    assert_eq!(state.current_priority_player, active);
+    assert_eq!(state.turn_order.active_player_id, active);
+}
+
+#[tokio::test]
+async fn only_the_priority_holder_passes_priority() {
+    let mut coordinator = game().await;
+    let holder = coordinator.game_state().current_priority_player.clone();
+    let other = PLAYERS.iter().find(|player| **player != holder).unwrap();
+
+    let result = coordinator
+        .handle_event(GameEvent::PriorityPass {
+            player_id: other.to_string(),
+        })
+        .await;
+    assert!(matches!(result, Err(AppError::InvalidPriorityPass)));
+    assert_eq!(coordinator.game_state().current_priority_player, holder);
+}
+
+/// A registry with no lobby or tournament actor behind it, the mailboxes it would send them,
+/// and the channel everything sent to clients goes out on
+struct Registry {
+    registry: Arc<ActorRegistry>,
+    cmd_sender: mpsc::UnboundedSender<ConnectionCommand>,
+    commands: mpsc::UnboundedReceiver<ConnectionCommand>,
+    _lobby: mpsc::Receiver<LobbyMessage>,
+    _tournaments: mpsc::Receiver<TournamentMessage>,
+}
+
+fn registry() -> Registry {
+    let config = ServerConfig::default();
+    let (lobby_sender, lobby_receiver) = mpsc::channel(config.mailboxes.lobby);
+    let (tournament_sender, tournaments) = mpsc::channel(config.mailboxes.lobby);
+    let lobby = LobbyShards::new(
+        vec![lobby_sender],
+        Arc::new(LobbyDirectory::new(ChatFilter::new(
+            Vec::new(),
+            100,
+            Duration::from_secs(1),
+        ))),
+    );
+    let services = GameServices {
+        game_store: None,
+        snapshots: Arc::new(GameSnapshots::new()),
+        ratings: Arc::new(Ratings::new(Box::new(MemoryRatingStore::new()))),
+        summaries: Arc::new(GameSummaries::new()),
+        lobby: lobby.clone(),
+        game_config: config.game.clone(),
+        locales: Arc::new(ConnectionLocales::new()),
+        metrics: Arc::new(ServerMetrics::new()),
+        webhooks: Webhooks::start(config.webhooks.clone()),
+        replay_dir: None,
+        dump_dir: None,
+        replay_key: None,
+    };
+    let (cmd_sender, commands) = mpsc::unbounded_channel();
+    let registry = Arc::new(ActorRegistry::new(
+        lobby,
+        tournament_sender,
+        services,
+        config.mailboxes.clone(),
+        Messenger::new(cmd_sender.clone()),
+    ));
+    Registry {
+        registry,
+        cmd_sender,
+        commands,
+        _lobby: lobby_receiver,
+        _tournaments: tournaments,
+    }
+}
+
+/// The next response called `name` sent to the connection on `commands`
+async fn next_sent(commands: &mut mpsc::UnboundedReceiver<ConnectionCommand>, name: &str) -> Value {
+    let wait = async {
+        while let Some(command) = commands.recv().await {
+            if let ConnectionCommand::SendToPlayer {
+                connection_id,
+                message,
+            } = command
+            {
+                let value: Value = serde_json::from_str(&message).unwrap();
+                if connection_id == CONNECTION && value.get(name).is_some() {
+                    return value[name].clone();
+                }
+            }
+        }
+        panic!("Nothing called {} was sent", name);
+    };
+    tokio::time::timeout(Duration::from_secs(10), wait)
+        .await
+        .expect("Nothing was sent in time")
+}
+
+#[tokio::test]
+async fn a_connection_in_two_live_games_reaches_the_one_it_names() {
+    let Registry {
+        registry,
+        cmd_sender,
+        mut commands,
+        ..
+    } = registry();
+    let games = ["basement", "cellar"];
+    for game_id in games {
+        let players = HashMap::from([
+            (format!("{}-alice", game_id), CONNECTION.to_string()),
+            (format!("{}-bob", game_id), format!("{}-bob", game_id)),
+        ]);
+        registry
+            .start_game_actor(
+                game_id.to_string(),
+                players,
+                HashMap::new(),
+                RoomOptions::default(),
+                HashMap::new(),
+            )
+            .unwrap();
+    }
+    // Each game sends on a channel of its own
+    let mut game_commands = HashMap::new();
+    while game_commands.len() < games.len() {
+        if let Some(ConnectionCommand::OpenGameChannel { game_id, commands }) =
+            commands.recv().await
+        {
+            game_commands.insert(game_id, commands);
+        }
+    }
+
+    let (sender, receiver) = mpsc::channel(16);
+    registry.register_connection_actor(CONNECTION.to_string(), sender.clone());
+    let mut connection = ConnectionActor::new(
+        CONNECTION.to_string(),
+        AuthContext::anonymous(),
+        Locale::default(),
+        &InboundConfig::default(),
+        MalformedFrames::new(10),
+        registry.clone(),
+        cmd_sender,
+    );
+    tokio::spawn(async move { connection.run(receiver).await });
+    for game_id in games {
+        registry
+            .notify_connection_game_start(
+                CONNECTION,
+                game_id.to_string(),
+                format!("{}-alice", game_id),
+            )
+            .unwrap();
+    }
+    let send = |message| {
+        sender
+            .try_send(ConnectionMessage::ClientMessage { message })
+            .unwrap()
+    };
+
+    send(ClientMessage::GetSimpleState);
+    let refused = next_sent(&mut commands, "Error").await;
+    assert_eq!(refused["error_type"], "GameIdRequired");
+
+    send(ClientMessage::Game {
+        game_id: "cellar".to_string(),
+        message: Box::new(ClientMessage::GetSimpleState),
+    });
+    let cellar = game_commands.get_mut("cellar").unwrap();
+    next_sent(cellar, "SimpleState").await;
+    let basement = game_commands.get_mut("basement").unwrap();
+    while let Ok(command) = basement.try_recv() {
+        if let ConnectionCommand::SendToPlayer { message, .. } = command {
+            assert!(!message.contains("SimpleState"), "The basement answered");
+        }
+    }
+
+    send(ClientMessage::Game {
+        game_id: "attic".to_string(),
+        message: Box::new(ClientMessage::GetSimpleState),
+    });
+    let refused = next_sent(&mut commands, "Error").await;
+    assert_eq!(refused["error_type"], "GameNotFound");
+}
@@ -0,0 +1,100 @@
+//! A room's speed sets its turn, priority and choice timers together, and players see them
+//! before the game starts.
+
+mod common;
+
+use std::time::Duration;
+
+use isaac_four_souls::config::GameConfig;
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls::network::room::{GameSpeed, RoomOptions, SpeedTimers};
+
+#[test]
+fn blitz_is_faster_than_relaxed_on_every_timer() {
+    let blitz = GameSpeed::Blitz.preset().unwrap();
+    let relaxed = GameSpeed::Relaxed.preset().unwrap();
+    assert!(blitz.turn_secs < relaxed.turn_secs);
+    assert!(blitz.priority_secs < relaxed.priority_secs);
+    assert!(blitz.choice_secs < relaxed.choice_secs);
+}
+
+#[test]
+fn normal_speed_is_the_configured_pace() {
+    let config = GameConfig {
+        turn_timeout_secs: 0,
+        priority_timeout_secs: 12,
+        choice_timeout_secs: 20,
+        ..GameConfig::default()
+    };
+    let timers = config.speed_timers(GameSpeed::Normal);
+    assert_eq!(
+        timers,
+        SpeedTimers {
+            turn_secs: 0,
+            priority_secs: 12,
+            choice_secs: 20,
+        }
+    );
+    assert_eq!(timers.turn_timeout(), None);
+    assert_eq!(timers.priority_timeout(), Some(Duration::from_secs(12)));
+    // Presets don't depend on the server
+    assert_eq!(
+        config.speed_timers(GameSpeed::Blitz),
+        GameSpeed::Blitz.preset().unwrap()
+    );
+}
+
+#[test]
+fn rooms_default_to_normal_speed() {
+    let options: RoomOptions = serde_json::from_str("{}").unwrap();
+    assert_eq!(options.speed, GameSpeed::Normal);
+    let options: RoomOptions = serde_json::from_str(r#"{"speed": "blitz"}"#).unwrap();
+    assert_eq!(options.speed, GameSpeed::Blitz);
+}
+
+#[tokio::test]
+async fn joining_players_see_the_room_speed() {
+    let url = common::server_with(common::no_countdown).await;
+    let mut alice = common::connect(&url).await;
+    let mut bob = common::connect(&url).await;
+
+    let options = RoomOptions {
+        speed: GameSpeed::Relaxed,
+        ..RoomOptions::default()
+    };
+    let seat = alice
+        .create_room("Basement", "alice", options)
+        .await
+        .unwrap();
+    let announced = tokio::time::timeout(
+        Duration::from_secs(10),
+        bob.wait_for(|response| matches!(response, ServerResponse::RoomCreatedBroadcast { .. })),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(matches!(
+        announced,
+        ServerResponse::RoomCreatedBroadcast { speed: GameSpeed::Relaxed, timers, .. }
+            if Some(timers) == GameSpeed::Relaxed.preset()
+    ));
+
+    bob.send(ClientMessage::JoinRoom {
+        player_name: "bob".to_string(),
+        room_id: seat.room_id,
+    })
+    .await
+    .unwrap();
+    let joined = tokio::time::timeout(
+        Duration::from_secs(10),
+        bob.wait_for(|response| matches!(response, ServerResponse::SelfJoined { .. })),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(matches!(
+        joined,
+        ServerResponse::SelfJoined { speed: GameSpeed::Relaxed, timers, .. }
+            if Some(timers) == GameSpeed::Relaxed.preset()
+    ));
+}
@@ -0,0 +1,219 @@
+//! Nothing a player holds in hand reaches anyone else: the public board carries hand counts,
+//! the cards themselves only go to their owner, and so does the log of what each player drew.
+
+mod common;
+
+use isaac_four_souls::game::game_coordinator::{GameCoordinator, GameEvent};
+use isaac_four_souls::ConnectionCommand;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+const PLAYERS: [&str; 3] = ["alice", "bob", "carol"];
+
+async fn game() -> (GameCoordinator, mpsc::UnboundedReceiver<ConnectionCommand>) {
+    common::game(&PLAYERS).start().await
+}
+
+/// Every message sent so far, as (recipient, text)
+fn delivered(commands: &mut mpsc::UnboundedReceiver<ConnectionCommand>) -> Vec<(String, String)> {
+    let mut delivered = Vec::new();
+    while let Ok(command) = commands.try_recv() {
+        match command {
+            ConnectionCommand::SendToPlayer {
+                connection_id,
+                message,
+            } => delivered.push((connection_id, message.to_string())),
+            ConnectionCommand::SendToPlayers {
+                connections_id,
+                message,
+            } => {
+                for connection_id in connections_id {
+                    delivered.push((connection_id, message.to_string()));
+                }
+            }
+            ConnectionCommand::SendToAll { message } => {
+                for player in PLAYERS {
+                    delivered.push((player.to_string(), message.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+    delivered
+}
+
+async fn play_a_round(coordinator: &mut GameCoordinator) {
+    for _ in PLAYERS {
+        let active = coordinator.game_state().turn_order.active_player_id.clone();
+        coordinator
+            .handle_event(GameEvent::TurnPass { player_id: active })
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn hands_only_reach_their_owner() {
+    let (mut coordinator, mut commands) = game().await;
+    play_a_round(&mut coordinator).await;
+    let messages = delivered(&mut commands);
+    assert!(!messages.is_empty());
+
+    // Cards only leave the deck into a hand here, so no other player ever saw these ids
+    let hands = &coordinator.game_state().board.players_hands;
+    for (recipient, text) in &messages {
+        for (owner, hand) in hands {
+            if owner == recipient {
+                continue;
+            }
+            for card in hand {
+                assert!(
+                    !text.contains(&card.entity_id),
+                    "{}'s {} was sent to {}: {}",
+                    owner,
+                    card.name,
+                    recipient,
+                    text
+                );
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn the_public_board_carries_counts_not_cards() {
+    let (mut coordinator, mut commands) = game().await;
+    play_a_round(&mut coordinator).await;
+    let boards: Vec<Value> = delivered(&mut commands)
+        .into_iter()
+        .filter_map(|(_, text)| {
+            let mut response: Value = serde_json::from_str(&text).ok()?;
+            response.get_mut("PublicBoardState").map(Value::take)
+        })
+        .collect();
+    assert!(!boards.is_empty());
+
+    for board in &boards {
+        for (_, player) in board["players"].as_object().unwrap() {
+            let fields: Vec<&str> = player
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(String::as_str)
+                .collect();
+            assert!(fields.contains(&"hand_count"));
+            assert!(!fields
+                .iter()
+                .any(|field| field.contains("hand") && *field != "hand_count"));
+        }
+    }
+
+    let latest = boards.last().unwrap();
+    for (player_id, hand) in &coordinator.game_state().board.players_hands {
+        assert_eq!(
+            latest["players"][player_id]["hand_count"].as_u64(),
+            Some(hand.len() as u64)
+        );
+    }
+}
+
+#[tokio::test]
+async fn private_boards_go_to_one_player_each() {
+    let (mut coordinator, mut commands) = game().await;
+    play_a_round(&mut coordinator).await;
+    let hands = &coordinator.game_state().board.players_hands;
+
+    let mut private_boards = 0;
+    for (recipient, text) in delivered(&mut commands) {
+        let response: Value = serde_json::from_str(&text).unwrap();
+        let Some(private) = response.get("PrivateBoardState") else {
+            continue;
+        };
+        private_boards += 1;
+        let ids: Vec<&str> = private["hand"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|card| card["entity_id"].as_str())
+            .collect();
+        // Whatever they held then, none of it belongs to anyone else now
+        for (owner, hand) in hands {
+            if *owner != recipient {
+                assert!(hand
+                    .iter()
+                    .all(|card| !ids.contains(&card.entity_id.as_str())));
+            }
+        }
+    }
+    assert!(private_boards >= PLAYERS.len());
+}
+
+fn private_logs(messages: &[(String, String)]) -> Vec<(String, Vec<String>)> {
+    messages
+        .iter()
+        .filter_map(|(recipient, text)| {
+            let response: Value = serde_json::from_str(text).ok()?;
+            let entries = response.get("PrivateLog")?["entries"].as_array()?.clone();
+            let texts = entries
+                .iter()
+                .map(|entry| entry["text"].as_str().unwrap().to_string())
+                .collect();
+            Some((recipient.clone(), texts))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn draws_are_logged_for_the_drawing_player_only() {
+    let (mut coordinator, mut commands) = game().await;
+    play_a_round(&mut coordinator).await;
+    let sent = private_logs(&delivered(&mut commands));
+
+    for (player_id, hand) in &coordinator.game_state().board.players_hands {
+        // Nobody played a card, so every card in hand was drawn and logged once
+        let logged: Vec<String> = coordinator
+            .event_log()
+            .private_since(player_id, None)
+            .into_iter()
+            .map(|entry| entry.text)
+            .collect();
+        let mut drawn: Vec<String> = hand
+            .iter()
+            .map(|card| format!("You drew {}", card.name))
+            .collect();
+        let mut sorted = logged.clone();
+        sorted.sort();
+        drawn.sort();
+        assert_eq!(sorted, drawn);
+
+        // and each entry went out once, to them
+        let delivered: Vec<String> = sent
+            .iter()
+            .filter(|(recipient, _)| recipient == player_id)
+            .flat_map(|(_, texts)| texts.clone())
+            .collect();
+        assert_eq!(delivered, logged);
+    }
+    assert!(sent
+        .iter()
+        .all(|(recipient, _)| PLAYERS.contains(&recipient.as_str())));
+}
+
+#[tokio::test]
+async fn a_resumed_seat_gets_its_private_log_back() {
+    let (mut coordinator, mut commands) = game().await;
+    play_a_round(&mut coordinator).await;
+    delivered(&mut commands);
+
+    coordinator
+        .reconnect_player("alice", "alice-again".to_string())
+        .await;
+    let resent = private_logs(&delivered(&mut commands));
+    let logged: Vec<String> = coordinator
+        .event_log()
+        .private_since("alice", None)
+        .into_iter()
+        .map(|entry| entry.text)
+        .collect();
+    assert_eq!(resent, vec![("alice-again".to_string(), logged)]);
+}
@@ -0,0 +1,198 @@
+//! The legal actions a player is told about are exactly the ones the game accepts from them.
+
+mod common;
+
+use isaac_four_souls::game::domain_events::DomainEvent;
+use isaac_four_souls::game::game_coordinator::{GameCoordinator, GameEvent};
+use isaac_four_souls::game::game_state::{GameState, TurnPhases};
+use isaac_four_souls::game::rules::RuleSet;
+use isaac_four_souls::AppError;
+
+const PLAYERS: [&str; 3] = ["alice", "bob", "carol"];
+
+async fn game(mulligan: bool) -> GameCoordinator {
+    game_with_rules(mulligan, &RuleSet::default()).await
+}
+
+async fn game_with_rules(mulligan: bool, rules: &RuleSet) -> GameCoordinator {
+    let mut game = common::game(&PLAYERS).rules(rules.clone());
+    if mulligan {
+        game = game.setup(|coordinator| coordinator.enable_mulligan(30));
+    }
+    game.start().await.0
+}
+
+/// Whether the game accepts `event`, tried on a copy of its own so `state` is left as it was
+fn accepts(state: &GameState, event: DomainEvent) -> bool {
+    state.clone().apply(&event).is_ok()
+}
+
+fn loot_play(player_id: &str, card_id: &str, tap_character: bool) -> DomainEvent {
+    DomainEvent::LootPlayed {
+        player_id: player_id.to_string(),
+        card_id: card_id.to_string(),
+        tap_character,
+    }
+}
+
+/// Every loot card in hand is listed exactly when one of the two ways of playing it works
+fn assert_loot_plays_match(state: &GameState, player_id: &str) {
+    let actions = state.legal_actions(player_id).unwrap();
+    for card in &state.board.players_hands[player_id] {
+        let listed = actions
+            .loot_cards
+            .iter()
+            .find(|play| play.card_id == card.entity_id);
+        let untapped = accepts(state, loot_play(player_id, &card.entity_id, false));
+        let tapped = accepts(state, loot_play(player_id, &card.entity_id, true));
+        match listed {
+            Some(play) if play.tap_character => assert!(!untapped && tapped),
+            Some(_) => assert!(untapped),
+            None => assert!(!untapped && !tapped),
+        }
+    }
+}
+
+#[tokio::test]
+async fn only_the_active_player_may_pass_the_turn() {
+    let coordinator = game(false).await;
+    let state = coordinator.game_state();
+    let alice = state.legal_actions("alice").unwrap();
+    let bob = state.legal_actions("bob").unwrap();
+
+    assert!(alice.pass_turn);
+    assert!(!bob.pass_turn);
+    assert_eq!(
+        alice.pass_priority,
+        state.current_priority_player == "alice" && state.waiting_for_priority
+    );
+    assert_eq!(alice.trade_partners, ["bob", "carol"]);
+    assert!(alice.concede && bob.concede);
+    for player_id in PLAYERS {
+        assert_loot_plays_match(state, player_id);
+    }
+}
+
+#[tokio::test]
+async fn an_open_mulligan_holds_everything_else_up() {
+    let mut coordinator = game(true).await;
+    let actions = coordinator.game_state().legal_actions("bob").unwrap();
+    assert!(actions.mulligan);
+    assert!(!actions.pass_turn && actions.loot_cards.is_empty() && !actions.concede);
+
+    coordinator
+        .handle_event(GameEvent::MulliganDecision {
+            player_id: "bob".to_string(),
+            keep: true,
+        })
+        .await
+        .unwrap();
+    let actions = coordinator.game_state().legal_actions("bob").unwrap();
+    assert!(!actions.mulligan);
+}
+
+#[tokio::test]
+async fn strangers_have_no_actions() {
+    let coordinator = game(false).await;
+    assert!(matches!(
+        coordinator.game_state().legal_actions("dave"),
+        Err(AppError::PlayerNotFound)
+    ));
+}
+
+#[tokio::test]
+async fn listed_items_are_the_ones_that_activate() {
+    let mut coordinator = game(false).await;
+    for _ in 0..2 {
+        let state = coordinator.game_state();
+        for player_id in PLAYERS {
+            let listed = state.legal_actions(player_id).unwrap().items;
+            let activatable: Vec<String> = state.board.players_items[player_id]
+                .iter()
+                .filter(|item| {
+                    accepts(
+                        state,
+                        DomainEvent::ItemActivated {
+                            player_id: player_id.to_string(),
+                            item_id: item.entity_id.clone(),
+                        },
+                    )
+                })
+                .map(|item| item.entity_id.clone())
+                .collect();
+            assert_eq!(listed, activatable);
+        }
+
+        // Again once an item was paid for: tapped, or with a charge less
+        let Some(item_id) = state.legal_actions("alice").unwrap().items.first().cloned() else {
+            break;
+        };
+        coordinator
+            .handle_event(GameEvent::ActivateItem {
+                player_id: "alice".to_string(),
+                item_id,
+            })
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn the_shop_sells_to_the_active_player_in_their_action_step() {
+    let rules = RuleSet {
+        starting_coins: 10,
+        ..RuleSet::default()
+    };
+    let mut coordinator = game_with_rules(false, &rules).await;
+    let active = coordinator.game_state().turn_order.active_player_id.clone();
+    coordinator
+        .handle_event(GameEvent::TurnPass { player_id: active })
+        .await
+        .unwrap();
+    let active = coordinator.game_state().turn_order.active_player_id.clone();
+    let buy = |player_id: &str| GameEvent::BuyItem {
+        player_id: player_id.to_string(),
+        shop_index: 0,
+    };
+
+    assert!(coordinator
+        .game_state()
+        .legal_actions(&active)
+        .unwrap()
+        .shop
+        .is_empty());
+    assert!(matches!(
+        coordinator.handle_event(buy(&active)).await,
+        Err(AppError::PurchaseNotAllowed { .. })
+    ));
+
+    while coordinator.game_state().current_phase != TurnPhases::ActionStep {
+        let holder = coordinator.game_state().current_priority_player.clone();
+        coordinator
+            .handle_event(GameEvent::PriorityPass { player_id: holder })
+            .await
+            .unwrap();
+    }
+    let state = coordinator.game_state();
+    let for_sale = state.board.shop[0].entity_id.clone();
+    assert_eq!(state.legal_actions(&active).unwrap().shop, [0, 1]);
+    let other = PLAYERS.iter().find(|player| **player != active).unwrap();
+    assert!(state.legal_actions(other).unwrap().shop.is_empty());
+    assert!(matches!(
+        coordinator.handle_event(buy(other)).await,
+        Err(AppError::NotPlayerTurn)
+    ));
+
+    coordinator.handle_event(buy(&active)).await.unwrap();
+    let state = coordinator.game_state();
+    assert_eq!(state.board.players[&active].coins, 0);
+    let bought = state.board.players_items[&active].last().unwrap();
+    assert_eq!(bought.entity_id, for_sale);
+    assert_eq!(bought.card.owner_id, active);
+    assert_eq!(state.board.shop.len(), rules.shop_slots);
+    assert!(state.legal_actions(&active).unwrap().shop.is_empty());
+    assert!(matches!(
+        coordinator.handle_event(buy(&active)).await,
+        Err(AppError::PurchaseNotAllowed { .. })
+    ));
+}
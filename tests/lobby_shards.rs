@@ -0,0 +1,211 @@
+//! A lobby spread over several shards behaves like a single one: rooms are found wherever they
+//! live, rooms on different shards run their games side by side, what a player sends right
+//! after joining follows them to the room's shard, and profiles and presence are shared by
+//! every shard.
+
+mod common;
+
+use isaac_four_souls::actors::lobby_shards::hashed_shard;
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls::network::profiles::PlayerProfile;
+use isaac_four_souls::ServerConfig;
+use isaac_four_souls_client::{Client, Seat};
+
+use common::{connect, within};
+
+const SHARDS: usize = 4;
+
+async fn server() -> String {
+    common::server_with(|builder| {
+        let mut config = ServerConfig {
+            lobby_shards: SHARDS,
+            ..ServerConfig::default()
+        };
+        config.timers.start_countdown_secs = 0;
+        builder.config(config)
+    })
+    .await
+}
+
+async fn register(client: &mut Client, display_name: &str) -> PlayerProfile {
+    client
+        .send(ClientMessage::RegisterProfile {
+            display_name: display_name.to_string(),
+            avatar_id: None,
+        })
+        .await
+        .unwrap();
+    match within(
+        client.wait_for(|response| matches!(response, ServerResponse::ProfileRegistered { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::ProfileRegistered { profile, .. } => profile,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn the_lobby_needs_a_shard() {
+    let mut config = ServerConfig::default();
+    assert_eq!(config.lobby_shards, 1);
+    config.set("lobby_shards", "0").unwrap();
+    assert!(config.validate().is_err());
+}
+
+#[tokio::test]
+async fn messages_sent_right_after_joining_reach_the_room() {
+    let url = server().await;
+    let mut alice = connect(&url).await;
+    let host = within(alice.create_room("Basement", "alice", Default::default()))
+        .await
+        .unwrap();
+
+    // Joining and chatting without waiting in between, whichever shard bob hashes to
+    let mut bob = connect(&url).await;
+    bob.send(ClientMessage::JoinRoom {
+        player_name: "bob".to_string(),
+        room_id: host.room_id.clone(),
+    })
+    .await
+    .unwrap();
+    bob.send(ClientMessage::Chat {
+        message: "hi".to_string(),
+    })
+    .await
+    .unwrap();
+
+    let chat = within(alice.wait_for(|response| {
+        matches!(response, ServerResponse::ChatMessage { player_name, .. } if player_name == "bob")
+    }))
+    .await
+    .unwrap();
+    assert!(matches!(chat, ServerResponse::ChatMessage { message, .. } if message == "hi"));
+}
+
+/// Seat a guest in the host's room, ready both and keep the opening hands; returns the two
+/// players with the one whose turn it is first
+async fn start_game(url: &str, host: Client, seat: &Seat) -> [Client; 2] {
+    let mut host = host;
+    let mut guest = connect(url).await;
+    within(guest.join(&seat.room_id, "guest")).await.unwrap();
+    within(host.ready()).await.unwrap();
+    within(guest.ready()).await.unwrap();
+
+    let turn_order = match within(
+        host.wait_for(|response| matches!(response, ServerResponse::RoomGameStart { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::RoomGameStart { turn_order, .. } => turn_order,
+        _ => unreachable!(),
+    };
+    let mut players = if turn_order[0] == seat.player_id {
+        [host, guest]
+    } else {
+        [guest, host]
+    };
+    for client in &mut players {
+        within(client.send(ClientMessage::MulliganDecision { keep: true }))
+            .await
+            .unwrap();
+    }
+    players
+}
+
+async fn play_first_card(active: &mut Client) {
+    let hand = match within(
+        active.wait_for(|response| matches!(response, ServerResponse::PrivateBoardState { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::PrivateBoardState { hand, .. } => hand,
+        _ => unreachable!(),
+    };
+    let card_id = &hand[0].card.entity_id;
+    match within(active.play_card(card_id)).await.unwrap() {
+        ServerResponse::LootPlayed { card, .. } => assert_eq!(&card.card.entity_id, card_id),
+        other => panic!("Expected LootPlayed, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn rooms_on_different_shards_play_side_by_side() {
+    let url = server().await;
+    let mut alice = connect(&url).await;
+    // Rooms are created on their host's shard
+    let mut bob = loop {
+        let client = connect(&url).await;
+        if hashed_shard(client.connection_id(), SHARDS)
+            != hashed_shard(alice.connection_id(), SHARDS)
+        {
+            break client;
+        }
+    };
+    let basement = within(alice.create_room("Basement", "alice", Default::default()))
+        .await
+        .unwrap();
+    let cellar = within(bob.create_room("Cellar", "bob", Default::default()))
+        .await
+        .unwrap();
+    assert_ne!(basement.room_id, cellar.room_id);
+
+    let [mut basement_active, _basement_other] = start_game(&url, alice, &basement).await;
+    let [mut cellar_active, _cellar_other] = start_game(&url, bob, &cellar).await;
+    play_first_card(&mut basement_active).await;
+    play_first_card(&mut cellar_active).await;
+}
+
+#[tokio::test]
+async fn profiles_and_presence_are_shared_by_every_shard() {
+    let url = server().await;
+    let mut alice = connect(&url).await;
+    let mut carol = connect(&url).await;
+    register(&mut alice, "alice").await;
+    let carol_profile = register(&mut carol, "carol").await;
+
+    alice
+        .send(ClientMessage::AddFriend {
+            profile_id: carol_profile.profile_id.clone(),
+        })
+        .await
+        .unwrap();
+    within(alice.wait_for(|response| matches!(response, ServerResponse::FriendsPresence { .. })))
+        .await
+        .unwrap();
+    let room = within(carol.create_room("Womb", "carol", Default::default()))
+        .await
+        .unwrap();
+
+    alice.send(ClientMessage::GetFriends).await.unwrap();
+    let ServerResponse::FriendsPresence { friends } = within(
+        alice.wait_for(|response| matches!(response, ServerResponse::FriendsPresence { .. })),
+    )
+    .await
+    .unwrap() else {
+        unreachable!()
+    };
+    assert_eq!(friends.len(), 1);
+    assert_eq!(friends[0].profile.profile_id, carol_profile.profile_id);
+    assert!(friends[0].online);
+    assert_eq!(friends[0].room_id.as_deref(), Some(room.room_id.as_str()));
+
+    alice
+        .send(ClientMessage::LobbyChat {
+            message: "anyone?".to_string(),
+        })
+        .await
+        .unwrap();
+    let chat = within(
+        carol.wait_for(|response| matches!(response, ServerResponse::LobbyChatMessage { .. })),
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        chat,
+        ServerResponse::LobbyChatMessage { sender_name, message } if sender_name == "alice" && message == "anyone?"
+    ));
+}
@@ -0,0 +1,44 @@
+//! A full mailbox sheds requests, while the messages an actor must see wait behind it and
+//! arrive in the order they were delivered, ahead of anything sent after them.
+
+use isaac_four_souls::actors::mailbox::Mailbox;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+#[tokio::test]
+async fn delivered_messages_arrive_in_order_past_a_full_mailbox() {
+    let (sender, mut receiver) = mpsc::channel(2);
+    let mailbox = Mailbox::new(sender);
+    for message in 0..10 {
+        assert!(mailbox.deliver(message));
+    }
+    // Until they're all in, requests would get ahead of them
+    assert!(matches!(
+        mailbox.try_send(100),
+        Err(TrySendError::Full(100))
+    ));
+
+    let mut received = Vec::new();
+    while received.len() < 10 {
+        received.push(receiver.recv().await.unwrap());
+        // More arrive while the overflow is still being fed in
+        if received.len() == 5 {
+            assert!(mailbox.deliver(10));
+        }
+    }
+    assert_eq!(received, (0..10).collect::<Vec<_>>());
+    assert_eq!(receiver.recv().await, Some(10));
+
+    // Once it has room again requests go straight in
+    mailbox.try_send(11).unwrap();
+    assert_eq!(receiver.recv().await, Some(11));
+}
+
+#[tokio::test]
+async fn nothing_is_delivered_to_an_actor_that_is_gone() {
+    let (sender, receiver) = mpsc::channel(1);
+    let mailbox = Mailbox::new(sender);
+    drop(receiver);
+    assert!(!mailbox.deliver(1));
+    assert!(matches!(mailbox.try_send(2), Err(TrySendError::Closed(2))));
+}
@@ -0,0 +1,296 @@
+//! Finished games move the Elo ratings of players with a profile: the winner beats every other
+//! seat, and a drawn game is a draw between the seats still in it, who beat whoever conceded.
+//! Ratings are kept in memory or in a JSON file that survives a restart.
+
+mod common;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use isaac_four_souls::game::ratings::{
+    FileRatingStore, MemoryRatingStore, PlayerRating, RatingStore, Ratings, DEFAULT_RATING,
+};
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls::network::profiles::PlayerProfile;
+use isaac_four_souls::network::room::RoomOptions;
+use isaac_four_souls_client::{Client, ClientError};
+
+use common::{connect, no_countdown, server_with, within};
+
+fn ids(players: &[&str]) -> Vec<String> {
+    players.iter().map(|id| id.to_string()).collect()
+}
+
+/// A profile for each of `players`, named after them
+fn profiles(players: &[&str]) -> HashMap<String, PlayerProfile> {
+    players
+        .iter()
+        .map(|id| {
+            let profile = PlayerProfile {
+                profile_id: format!("profile-{}", id),
+                display_name: id.to_string(),
+                avatar_id: None,
+            };
+            (id.to_string(), profile)
+        })
+        .collect()
+}
+
+fn rating(ratings: &Ratings, player: &str) -> PlayerRating {
+    ratings
+        .leaderboard(100)
+        .unwrap()
+        .into_iter()
+        .find(|rating| rating.display_name == player)
+        .unwrap_or_else(|| panic!("{} isn't rated", player))
+}
+
+fn assert_near(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "{} is not {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn the_winner_beats_every_other_seat() {
+    let ratings = Ratings::new(Box::new(MemoryRatingStore::new()));
+    ratings
+        .record_game(
+            Some("alice"),
+            &ids(&["alice", "bob", "carol"]),
+            &[],
+            &profiles(&["alice", "bob", "carol"]),
+        )
+        .unwrap();
+
+    // Evenly rated, each loss is worth half the K factor
+    let alice = rating(&ratings, "alice");
+    assert_near(alice.rating, DEFAULT_RATING + 32.0);
+    assert_eq!((alice.games_played, alice.wins), (1, 1));
+    let bob = rating(&ratings, "bob");
+    assert_near(bob.rating, DEFAULT_RATING - 16.0);
+    assert_eq!((bob.games_played, bob.wins), (1, 0));
+    assert_near(rating(&ratings, "carol").rating, DEFAULT_RATING - 16.0);
+}
+
+#[test]
+fn a_draw_moves_ratings_toward_each_other() {
+    let ratings = Ratings::new(Box::new(MemoryRatingStore::new()));
+    let players = profiles(&["alice", "bob"]);
+    ratings
+        .record_game(None, &ids(&["alice", "bob"]), &[], &players)
+        .unwrap();
+    // Between equals a draw changes nothing but counts as a game
+    let alice = rating(&ratings, "alice");
+    assert_near(alice.rating, DEFAULT_RATING);
+    assert_eq!((alice.games_played, alice.wins), (1, 0));
+
+    ratings
+        .record_game(Some("alice"), &ids(&["alice", "bob"]), &[], &players)
+        .unwrap();
+    ratings
+        .record_game(None, &ids(&["alice", "bob"]), &[], &players)
+        .unwrap();
+    let (alice, bob) = (rating(&ratings, "alice"), rating(&ratings, "bob"));
+    assert!(alice.rating < DEFAULT_RATING + 16.0);
+    assert!(bob.rating > DEFAULT_RATING - 16.0);
+    assert_near(alice.rating + bob.rating, 2.0 * DEFAULT_RATING);
+    assert_eq!(alice.games_played, 3);
+}
+
+#[test]
+fn whoever_conceded_loses_a_drawn_game() {
+    let ratings = Ratings::new(Box::new(MemoryRatingStore::new()));
+    ratings
+        .record_game(
+            None,
+            &ids(&["alice", "bob"]),
+            &ids(&["carol"]),
+            &profiles(&["alice", "bob", "carol"]),
+        )
+        .unwrap();
+    assert_near(rating(&ratings, "alice").rating, DEFAULT_RATING + 16.0);
+    assert_near(rating(&ratings, "bob").rating, DEFAULT_RATING + 16.0);
+    assert_near(rating(&ratings, "carol").rating, DEFAULT_RATING - 32.0);
+}
+
+#[test]
+fn guests_are_opponents_but_not_ranked() {
+    let ratings = Ratings::new(Box::new(MemoryRatingStore::new()));
+    ratings
+        .record_game(
+            Some("guest"),
+            &ids(&["alice", "guest"]),
+            &[],
+            &profiles(&["alice"]),
+        )
+        .unwrap();
+    let leaderboard = ratings.leaderboard(10).unwrap();
+    assert_eq!(leaderboard.len(), 1);
+    assert_near(leaderboard[0].rating, DEFAULT_RATING - 16.0);
+
+    // A game without profiles isn't recorded at all
+    ratings
+        .record_game(None, &ids(&["guest", "bot"]), &[], &HashMap::new())
+        .unwrap();
+    assert_eq!(ratings.leaderboard(10).unwrap().len(), 1);
+}
+
+#[test]
+fn the_leaderboard_is_highest_first() {
+    let store = MemoryRatingStore::new();
+    let ratings: Vec<PlayerRating> = [1400.0, 1600.0, 1500.0]
+        .iter()
+        .enumerate()
+        .map(|(i, rating)| PlayerRating {
+            profile_id: format!("profile-{}", i),
+            display_name: format!("player {}", i),
+            rating: *rating,
+            games_played: 1,
+            wins: 0,
+        })
+        .collect();
+    store.save(&ratings).unwrap();
+    let top: Vec<f64> = store
+        .top(2)
+        .unwrap()
+        .iter()
+        .map(|rating| rating.rating)
+        .collect();
+    assert_eq!(top, [1600.0, 1500.0]);
+    assert!(store.get("profile-0").unwrap().is_some());
+    assert!(store.get("profile-9").unwrap().is_none());
+}
+
+#[test]
+fn a_file_store_keeps_ratings_across_restarts() {
+    let dir = std::env::temp_dir().join(format!("ifs-ratings-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("ratings.json");
+
+    let ratings = Ratings::new(Box::new(FileRatingStore::new(&path).unwrap()));
+    let players = profiles(&["alice", "bob"]);
+    ratings
+        .record_game(Some("bob"), &ids(&["alice", "bob"]), &[], &players)
+        .unwrap();
+
+    let reopened = FileRatingStore::new(&path).unwrap();
+    let bob = reopened.get("profile-bob").unwrap().unwrap();
+    assert_near(bob.rating, DEFAULT_RATING + 16.0);
+    assert_eq!(bob.wins, 1);
+    assert_eq!(reopened.top(10).unwrap().len(), 2);
+
+    std::fs::write(&path, "{ not json").unwrap();
+    assert!(FileRatingStore::new(&path).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// Register a profile and take a seat; returns the game id once the game starts
+async fn seat(client: &mut Client, name: &str, room_id: Option<&str>) -> String {
+    within(client.send(ClientMessage::RegisterProfile {
+        display_name: name.to_string(),
+        avatar_id: None,
+    }))
+    .await
+    .unwrap();
+    within(
+        client.wait_for(|response| matches!(response, ServerResponse::ProfileRegistered { .. })),
+    )
+    .await
+    .unwrap();
+    match room_id {
+        Some(room_id) => within(client.join(room_id, name)).await.unwrap().room_id,
+        None => {
+            within(client.create_room("Cellar", name, RoomOptions::default()))
+                .await
+                .unwrap()
+                .room_id
+        }
+    }
+}
+
+#[tokio::test]
+async fn a_drawn_game_is_summarized_and_rated() {
+    let url = server_with(no_countdown).await;
+    let mut alice = connect(&url).await;
+    let mut bob = connect(&url).await;
+    let room_id = seat(&mut alice, "alice", None).await;
+    seat(&mut bob, "bob", Some(&room_id)).await;
+    within(alice.ready()).await.unwrap();
+    within(bob.ready()).await.unwrap();
+    let game_id = match within(
+        alice.wait_for(|response| matches!(response, ServerResponse::GameJoined { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::GameJoined { game_id, .. } => game_id,
+        other => panic!("Expected to join the game, got {:?}", other),
+    };
+    for client in [&mut alice, &mut bob] {
+        within(client.send(ClientMessage::MulliganDecision { keep: true }))
+            .await
+            .unwrap();
+    }
+
+    within(alice.send(ClientMessage::OfferDraw)).await.unwrap();
+    within(bob.wait_for(|response| matches!(response, ServerResponse::DrawOffered { .. })))
+        .await
+        .unwrap();
+    within(bob.send(ClientMessage::AcceptDraw)).await.unwrap();
+    match within(alice.wait_for(|response| matches!(response, ServerResponse::GameEnded { .. })))
+        .await
+        .unwrap()
+    {
+        ServerResponse::GameEnded { winner_id, .. } => assert_eq!(winner_id, None),
+        other => panic!("Expected the game to end, got {:?}", other),
+    }
+
+    // The summary is written once the game actor has stopped
+    let summary = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            alice
+                .send(ClientMessage::GetGameSummary {
+                    game_id: game_id.clone(),
+                })
+                .await
+                .unwrap();
+            match alice
+                .wait_for(|response| matches!(response, ServerResponse::GameSummary { .. }))
+                .await
+            {
+                Ok(ServerResponse::GameSummary { summary }) => return summary,
+                Err(ClientError::Server { error_type, .. })
+                    if error_type == "GameSummaryNotFound" =>
+                {
+                    tokio::time::sleep(Duration::from_millis(20)).await
+                }
+                other => panic!("Expected the game's summary, got {:?}", other),
+            }
+        }
+    })
+    .await
+    .expect("The drawn game was never summarized");
+    assert_eq!(summary.winner_id, None);
+
+    within(alice.send(ClientMessage::GetLeaderboard { limit: None }))
+        .await
+        .unwrap();
+    match within(alice.wait_for(|response| matches!(response, ServerResponse::Leaderboard { .. })))
+        .await
+        .unwrap()
+    {
+        ServerResponse::Leaderboard { entries } => {
+            assert_eq!(entries.len(), 2);
+            for entry in entries {
+                assert_eq!((entry.games_played, entry.wins), (1, 0));
+                assert_near(entry.rating, DEFAULT_RATING);
+            }
+        }
+        other => panic!("Expected the leaderboard, got {:?}", other),
+    }
+}
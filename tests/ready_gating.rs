@@ -0,0 +1,138 @@
+//! A room starts its game only once everyone seated is ready and there are enough of them.
+//! Every ready-up reaches the whole room as the new ready set, so clients can show who is
+//! still missing, and joining a room never starts anything on its own.
+
+mod common;
+
+use std::collections::HashSet;
+
+use isaac_four_souls::network::messages::ServerResponse;
+use isaac_four_souls_client::Client;
+
+use common::{connect, no_countdown, server_with, within};
+
+/// A room with `size` seated players, the host first; returns the room id too
+async fn room(url: &str, size: usize) -> (String, Vec<Client>, Vec<String>) {
+    let mut clients = vec![connect(url).await];
+    let host = within(clients[0].create_room("Basement", "player0", Default::default()))
+        .await
+        .unwrap();
+    let mut player_ids = vec![host.player_id];
+    for seat in 1..size {
+        let mut client = connect(url).await;
+        let joined = within(client.join(&host.room_id, &format!("player{}", seat)))
+            .await
+            .unwrap();
+        player_ids.push(joined.player_id);
+        clients.push(client);
+    }
+    (host.room_id, clients, player_ids)
+}
+
+/// Whatever comes first of a new ready set and the game starting
+async fn next_ready_or_start(client: &mut Client) -> ServerResponse {
+    within(client.wait_for(|response| {
+        matches!(
+            response,
+            ServerResponse::PlayersReady { .. } | ServerResponse::RoomGameStart { .. }
+        )
+    }))
+    .await
+    .unwrap()
+}
+
+async fn ready_one_by_one(size: usize) {
+    let (_, mut clients, player_ids) = room(&server_with(no_countdown).await, size).await;
+
+    for readied in 0..size {
+        within(clients[readied].ready()).await.unwrap();
+        let expected: HashSet<String> = player_ids[..=readied].iter().cloned().collect();
+        for client in clients.iter_mut() {
+            match next_ready_or_start(client).await {
+                ServerResponse::PlayersReady { players_ready } => {
+                    assert_eq!(players_ready, expected)
+                }
+                other => panic!(
+                    "Game started with {} of {} ready: {:?}",
+                    readied + 1,
+                    size,
+                    other
+                ),
+            }
+        }
+    }
+
+    for client in clients.iter_mut() {
+        match next_ready_or_start(client).await {
+            ServerResponse::RoomGameStart { turn_order, .. } => {
+                let seated: HashSet<&String> = player_ids.iter().collect();
+                assert_eq!(turn_order.iter().collect::<HashSet<_>>(), seated);
+            }
+            other => panic!("Expected the game to start, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn two_players_start_once_both_are_ready() {
+    ready_one_by_one(2).await;
+}
+
+#[tokio::test]
+async fn three_players_start_once_all_are_ready() {
+    ready_one_by_one(3).await;
+}
+
+#[tokio::test]
+async fn four_players_start_once_all_are_ready() {
+    ready_one_by_one(4).await;
+}
+
+#[tokio::test]
+async fn a_lone_ready_player_waits_for_company() {
+    let url = server_with(no_countdown).await;
+    let (room_id, mut clients, player_ids) = room(&url, 1).await;
+    within(clients[0].ready()).await.unwrap();
+    match next_ready_or_start(&mut clients[0]).await {
+        ServerResponse::PlayersReady { players_ready } => {
+            assert_eq!(players_ready, HashSet::from([player_ids[0].clone()]))
+        }
+        other => panic!("A single player started a game: {:?}", other),
+    }
+
+    // Had the game started, it would have been announced before the next player arrived
+    let mut bob = connect(&url).await;
+    within(bob.join(&room_id, "bob")).await.unwrap();
+    let joined = within(clients[0].wait_for(|response| {
+        matches!(
+            response,
+            ServerResponse::PlayerJoined { .. } | ServerResponse::RoomGameStart { .. }
+        )
+    }))
+    .await
+    .unwrap();
+    assert!(matches!(joined, ServerResponse::PlayerJoined { .. }));
+
+    within(bob.ready()).await.unwrap();
+    assert!(matches!(
+        next_ready_or_start(&mut clients[0]).await,
+        ServerResponse::PlayersReady { players_ready } if players_ready.len() == 2
+    ));
+    assert!(matches!(
+        next_ready_or_start(&mut clients[0]).await,
+        ServerResponse::RoomGameStart { .. }
+    ));
+}
+
+#[tokio::test]
+async fn joining_does_not_start_the_game() {
+    let (_, mut clients, _) = room(&server_with(no_countdown).await, 2).await;
+    // Readying up is the first thing anyone hears about the ready set or the game
+    within(clients[1].ready()).await.unwrap();
+    for client in clients.iter_mut() {
+        assert!(matches!(
+            next_ready_or_start(client).await,
+            ServerResponse::PlayersReady { players_ready } if players_ready.len() == 1
+        ));
+    }
+}
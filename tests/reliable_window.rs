@@ -0,0 +1,146 @@
+//! The server buffers only so many reliable messages past one that hasn't arrived. Anything
+//! further ahead is refused as a malformed frame, unacked, and a client that keeps sending
+//! them is cut off like any other sending garbage.
+
+mod common;
+
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use isaac_four_souls::network::reliable_messaging::{MessageReceiver, ReliableMessage};
+use isaac_four_souls::{AppError, ServerConfig};
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+fn message(sequence: u64) -> ReliableMessage {
+    carrying(sequence, r#""Ping""#)
+}
+
+fn carrying(sequence: u64, payload: &str) -> ReliableMessage {
+    ReliableMessage {
+        id: format!("message-{}", sequence),
+        sequence,
+        payload: payload.to_string(),
+        timestamp: 0,
+    }
+}
+
+async fn send(socket: &mut Socket, sequence: u64) {
+    send_message(socket, message(sequence)).await;
+}
+
+async fn send_message(socket: &mut Socket, message: ReliableMessage) {
+    let frame = json!({ "Reliable": message }).to_string();
+    socket.send(Message::Text(frame)).await.unwrap();
+}
+
+async fn closed_by_policy(socket: &mut Socket) {
+    let closed = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            match socket.next().await {
+                Some(Ok(Message::Close(frame))) => return frame,
+                Some(Ok(_)) => continue,
+                _ => return None,
+            }
+        }
+    })
+    .await
+    .expect("The server didn't close the connection");
+    assert_eq!(closed.map(|frame| frame.code), Some(CloseCode::Policy));
+}
+
+/// The next text frame `pick` accepts, skipping the others
+async fn next(socket: &mut Socket, pick: impl Fn(&Value) -> bool) -> Value {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let frame = socket.next().await.expect("The server hung up").unwrap();
+            if let Message::Text(text) = frame {
+                let value: Value = serde_json::from_str(&text).unwrap();
+                if pick(&value) {
+                    return value;
+                }
+            }
+        }
+    })
+    .await
+    .expect("The server didn't answer in time")
+}
+
+fn is_refused(value: &Value) -> bool {
+    value["Error"]["error_type"] == "MalformedFrame"
+}
+
+#[test]
+fn messages_past_the_window_are_refused() {
+    let mut receiver = MessageReceiver::with_window(4);
+    assert!(matches!(
+        receiver.receive_message(message(6)),
+        Err(AppError::MalformedFrame { .. })
+    ));
+    let (ack, released) = receiver.receive_message(message(5)).unwrap();
+    assert_eq!(ack.message_id, "message-5");
+    assert!(released.is_empty());
+
+    // The window moves along with the messages that come in order
+    for sequence in 1..=4 {
+        receiver.receive_message(message(sequence)).unwrap();
+    }
+    let (_, released) = receiver.receive_message(message(10)).unwrap();
+    assert!(released.is_empty());
+    assert!(receiver.receive_message(message(11)).is_err());
+
+    // Without a window everything is buffered
+    let mut unbounded = MessageReceiver::new();
+    assert!(unbounded.receive_message(message(1_000)).is_ok());
+}
+
+#[tokio::test]
+async fn a_client_that_keeps_running_ahead_is_cut_off() {
+    let mut config = ServerConfig::default();
+    config.inbound.max_reliable_window = 4;
+    config.inbound.max_malformed_frames = 3;
+    let url = common::server_with(|builder| builder.config(config)).await;
+    let (mut socket, _) = connect_async(&url).await.unwrap();
+
+    send(&mut socket, 5).await;
+    let ack = next(&mut socket, |value| value.get("Ack").is_some()).await;
+    assert_eq!(ack["Ack"]["message_id"], "message-5");
+
+    send(&mut socket, 6).await;
+    next(&mut socket, is_refused).await;
+    // A malformed frame the socket loop refuses counts towards the same limit
+    socket
+        .send(Message::Binary(b"\"Ping\"".to_vec()))
+        .await
+        .unwrap();
+    next(&mut socket, is_refused).await;
+    send(&mut socket, 100).await;
+    next(&mut socket, is_refused).await;
+
+    closed_by_policy(&mut socket).await;
+}
+
+#[tokio::test]
+async fn reliable_payloads_get_the_frame_checks_too() {
+    let mut config = ServerConfig::default();
+    config.inbound.max_json_depth = 8;
+    config.inbound.max_malformed_frames = 3;
+    let url = common::server_with(|builder| builder.config(config)).await;
+    let (mut socket, _) = connect_async(&url).await.unwrap();
+
+    // Nesting hidden inside the payload string is still too deep
+    let nested = format!("{}{}", "[".repeat(64), "]".repeat(64));
+    send_message(&mut socket, carrying(1, &nested)).await;
+    next(&mut socket, is_refused).await;
+
+    // Payloads that don't parse count towards the disconnect like bare frames that don't
+    for sequence in 2..=3 {
+        send_message(&mut socket, carrying(sequence, "not a message")).await;
+        next(&mut socket, |value| value.get("Error").is_some()).await;
+    }
+    closed_by_policy(&mut socket).await;
+}
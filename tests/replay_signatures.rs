@@ -0,0 +1,176 @@
+//! Signed replays verify as exported and stop verifying once anything in them is changed;
+//! a restored game goes on signing only a chain that still verifies, and only with the key it
+//! is given.
+
+mod common;
+
+use isaac_four_souls::game::card_loader::BASE_SET;
+use isaac_four_souls::game::game_coordinator::{GameCoordinator, GameEvent};
+use isaac_four_souls::game::replay::{Replay, ReplayKey};
+use isaac_four_souls::network::messenger::Messenger;
+use isaac_four_souls::{AppError, ConnectionCommand};
+use tokio::sync::mpsc;
+
+const SECRET: &str = "tournament-secret";
+
+/// A game recorded with the server's replay key, a turn and a round of priority passes long
+async fn signed_game() -> GameCoordinator {
+    let (mut coordinator, _) = common::game(&["alice", "bob"])
+        .setup(|coordinator| coordinator.enable_replay_signing(key()))
+        .start()
+        .await;
+    let active = coordinator.game_state().turn_order.active_player_id.clone();
+    coordinator
+        .handle_event(GameEvent::TurnPass { player_id: active })
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        let holder = coordinator.game_state().current_priority_player.clone();
+        coordinator
+            .handle_event(GameEvent::PriorityPass { player_id: holder })
+            .await
+            .unwrap();
+    }
+    coordinator
+}
+
+async fn signed_replay() -> Replay {
+    signed_game().await.replay().clone()
+}
+
+fn key() -> ReplayKey {
+    ReplayKey::new(SECRET.as_bytes())
+}
+
+fn assert_tampered(replay: &Replay) {
+    assert!(matches!(
+        replay.verify(&key()),
+        Err(AppError::ReplayTampered { .. })
+    ));
+}
+
+#[tokio::test]
+async fn an_exported_replay_verifies() {
+    let replay = signed_replay().await;
+    assert_eq!(replay.events.len(), 3);
+    assert_eq!(replay.signatures.len(), replay.events.len() + 1);
+    replay.verify(&key()).unwrap();
+
+    let exported = serde_json::to_string_pretty(&replay).unwrap();
+    let imported: Replay = serde_json::from_str(&exported).unwrap();
+    imported.verify(&key()).unwrap();
+    assert!(matches!(
+        imported.verify(&ReplayKey::new(b"someone else")),
+        Err(AppError::ReplayTampered { .. })
+    ));
+}
+
+#[tokio::test]
+async fn any_change_breaks_the_chain() {
+    let replay = signed_replay().await;
+
+    let mut edited = replay.clone();
+    edited.events[1] = GameEvent::TurnPass {
+        player_id: "bob".to_string(),
+    };
+    assert_tampered(&edited);
+
+    let mut reordered = replay.clone();
+    reordered.events.swap(1, 2);
+    reordered.signatures.swap(2, 3);
+    assert_tampered(&reordered);
+
+    let mut shortened = replay.clone();
+    shortened.events.pop();
+    assert_tampered(&shortened);
+
+    let mut reseeded = replay.clone();
+    reseeded.seed += 1;
+    assert_tampered(&reseeded);
+
+    let mut unsigned = replay.clone();
+    unsigned.signatures.clear();
+    assert_tampered(&unsigned);
+}
+
+#[tokio::test]
+async fn taking_events_back_keeps_the_chain_whole() {
+    let mut replay = signed_replay().await;
+    replay.truncate(1);
+    replay.verify(&key()).unwrap();
+
+    // Re-signing covers whatever was recorded before the key was given
+    let mut resigned = replay.clone();
+    resigned.sign_with(ReplayKey::new(b"new key"));
+    resigned.verify(&ReplayKey::new(b"new key")).unwrap();
+}
+
+// The game as saved by `coordinator`, with `replay` in place of its own
+fn restore(coordinator: &GameCoordinator, replay: Replay) -> Result<GameCoordinator, AppError> {
+    let (cmd_sender, _) = mpsc::unbounded_channel::<ConnectionCommand>();
+    GameCoordinator::restore(
+        coordinator.game_state().clone(),
+        replay,
+        coordinator.stats().clone(),
+        coordinator.event_log().clone(),
+        coordinator.events().clone(),
+        Messenger::new(cmd_sender),
+        Some(key()),
+    )
+}
+
+#[tokio::test]
+async fn a_restored_game_extends_the_chain_it_was_saved_with() {
+    let saved = signed_game().await;
+    let saved_signatures = saved.replay().signatures.clone();
+    let mut coordinator = restore(&saved, saved.replay().clone()).unwrap();
+    let holder = coordinator.game_state().current_priority_player.clone();
+    coordinator
+        .handle_event(GameEvent::PriorityPass { player_id: holder })
+        .await
+        .unwrap();
+
+    let replay = coordinator.replay();
+    assert_eq!(replay.events.len(), 4);
+    assert_eq!(
+        replay.signatures[..saved_signatures.len()],
+        saved_signatures
+    );
+    replay.verify(&key()).unwrap();
+}
+
+#[tokio::test]
+async fn a_tampered_save_is_not_restored() {
+    let saved = signed_game().await;
+    let mut replay = saved.replay().clone();
+    replay.events[1] = GameEvent::TurnPass {
+        player_id: "bob".to_string(),
+    };
+    assert!(matches!(
+        restore(&saved, replay),
+        Err(AppError::ReplayTampered { .. })
+    ));
+}
+
+#[tokio::test]
+async fn replays_are_unsigned_without_a_key() {
+    let replay = Replay::new(7, vec!["alice".to_string()], vec![BASE_SET.to_string()]);
+    assert!(!replay.is_signing());
+
+    let saved = signed_game().await;
+    let (cmd_sender, _) = mpsc::unbounded_channel::<ConnectionCommand>();
+    // As read back from the store, which never holds the key
+    let stored: Replay =
+        serde_json::from_value(serde_json::to_value(saved.replay()).unwrap()).unwrap();
+    let coordinator = GameCoordinator::restore(
+        saved.game_state().clone(),
+        stored,
+        saved.stats().clone(),
+        saved.event_log().clone(),
+        saved.events().clone(),
+        Messenger::new(cmd_sender),
+        None,
+    )
+    .unwrap();
+    assert!(!coordinator.replay().is_signing());
+}
@@ -0,0 +1,169 @@
+//! Rooms move through Lobby, Starting, InGame and Finished and nowhere else: a running game
+//! turns newcomers away, a starting room keeps its ready set, a room can't start twice, and
+//! every move reaches the room's members as `RoomStateChanged`.
+
+mod common;
+
+use isaac_four_souls::config::TimerConfig;
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls::network::room::{Room, RoomOptions, RoomState};
+use isaac_four_souls::AppError;
+use isaac_four_souls_client::{Client, ClientError};
+
+use common::{connect, within};
+
+fn ready_room() -> (Room, Vec<String>) {
+    let mut room = Room::new("Basement".to_string(), RoomOptions::default());
+    let players: Vec<String> = ["alice", "bob"]
+        .iter()
+        .map(|name| room.add_player(name.to_string()).unwrap().0)
+        .collect();
+    for player_id in &players {
+        room.add_player_ready(player_id).unwrap();
+    }
+    (room, players)
+}
+
+#[test]
+fn a_room_goes_around_its_lifecycle() {
+    let (mut room, players) = ready_room();
+    assert!(room.can_start_game());
+    for state in [
+        RoomState::Starting,
+        RoomState::InGame,
+        RoomState::Finished,
+        RoomState::Lobby,
+    ] {
+        room.transition(state.clone()).unwrap();
+        assert_eq!(*room.state(), state);
+    }
+    // Everyone readies up again for the next game
+    assert_eq!(room.player_ready_count(), 0);
+    assert!(room.add_player_ready(&players[0]).is_ok());
+}
+
+#[test]
+fn a_room_cannot_skip_ahead_or_start_twice() {
+    let (mut room, _) = ready_room();
+    assert!(matches!(
+        room.transition(RoomState::InGame),
+        Err(AppError::InvalidRoomTransition { from, to, .. }) if from == "Lobby" && to == "InGame"
+    ));
+
+    room.transition(RoomState::Starting).unwrap();
+    assert!(room.transition(RoomState::Starting).is_err());
+    room.transition(RoomState::InGame).unwrap();
+    assert!(room.transition(RoomState::InGame).is_err());
+    assert!(room.transition(RoomState::Starting).is_err());
+    assert_eq!(*room.state(), RoomState::InGame);
+}
+
+#[test]
+fn a_starting_room_keeps_its_ready_set() {
+    let (mut room, players) = ready_room();
+    room.transition(RoomState::Starting).unwrap();
+    assert!(matches!(
+        room.remove_player_ready(&players[0]),
+        Err(AppError::RoomStarting { .. })
+    ));
+    assert!(matches!(
+        room.add_player_ready(&players[1]),
+        Err(AppError::RoomStarting { .. })
+    ));
+    assert_eq!(room.player_ready_count(), 2);
+
+    // Calling the start off opens the ready set again
+    room.transition(RoomState::Lobby).unwrap();
+    assert!(room.remove_player_ready(&players[0]).is_ok());
+}
+
+#[test]
+fn a_running_game_turns_newcomers_away() {
+    let (mut room, players) = ready_room();
+    room.transition(RoomState::Starting).unwrap();
+    // Joining a countdown is still allowed, the lobby calls the start off
+    let (carol, _) = room.add_player("carol".to_string()).unwrap();
+    room.remove_player(&carol).unwrap();
+
+    room.transition(RoomState::InGame).unwrap();
+    assert!(matches!(
+        room.add_player("dave".to_string()),
+        Err(AppError::RoomInGame { .. })
+    ));
+    assert!(matches!(
+        room.remove_player(&players[0]),
+        Err(AppError::RoomInGame { .. })
+    ));
+    assert!(matches!(
+        room.add_player_ready(&players[0]),
+        Err(AppError::RoomInGame { .. })
+    ));
+}
+
+async fn server(start_countdown_secs: u64) -> String {
+    common::server_with(|builder| {
+        builder.timers(TimerConfig {
+            start_countdown_secs,
+            ..TimerConfig::default()
+        })
+    })
+    .await
+}
+
+async fn next_state(client: &mut Client) -> RoomState {
+    match within(
+        client.wait_for(|response| matches!(response, ServerResponse::RoomStateChanged { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::RoomStateChanged { state, .. } => state,
+        _ => unreachable!(),
+    }
+}
+
+async fn two_seated(url: &str) -> (Client, Client) {
+    let mut alice = connect(url).await;
+    let mut bob = connect(url).await;
+    let seat = within(alice.create_room("Basement", "alice", Default::default()))
+        .await
+        .unwrap();
+    within(bob.join(&seat.room_id, "bob")).await.unwrap();
+    (alice, bob)
+}
+
+#[tokio::test]
+async fn members_hear_every_state_change() {
+    let url = server(0).await;
+    let (mut alice, mut bob) = two_seated(&url).await;
+    within(alice.ready()).await.unwrap();
+    within(bob.ready()).await.unwrap();
+
+    for client in [&mut alice, &mut bob] {
+        assert_eq!(next_state(client).await, RoomState::Starting);
+        assert_eq!(next_state(client).await, RoomState::InGame);
+    }
+}
+
+#[tokio::test]
+async fn a_countdown_locks_the_ready_set_until_someone_leaves() {
+    let url = server(30).await;
+    let (mut alice, mut bob) = two_seated(&url).await;
+    within(alice.ready()).await.unwrap();
+    within(bob.ready()).await.unwrap();
+    assert_eq!(next_state(&mut alice).await, RoomState::Starting);
+
+    within(bob.send(ClientMessage::PlayerUnready))
+        .await
+        .unwrap();
+    let refused =
+        within(bob.wait_for(|response| matches!(response, ServerResponse::RoomGameStart { .. })))
+            .await;
+    assert!(matches!(
+        refused,
+        Err(ClientError::Server { error_type, .. }) if error_type == "RoomStarting"
+    ));
+
+    within(bob.send(ClientMessage::LeaveRoom)).await.unwrap();
+    assert_eq!(next_state(&mut alice).await, RoomState::Lobby);
+}
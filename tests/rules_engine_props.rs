@@ -0,0 +1,401 @@
+//! Property tests for the rules engine: random streams of legal and illegal events are fed
+//! through a headless GameCoordinator and the board invariants are checked after each one.
+//! At the end the game's event stream has to rebuild the very same state.
+
+mod common;
+
+use std::collections::HashSet;
+
+use isaac_four_souls::game::cards_types::ItemActivation;
+use isaac_four_souls::game::game_coordinator::GameEvent;
+use isaac_four_souls::game::game_state::{GameState, TurnPhases};
+use isaac_four_souls::game::trades::TradeOffer;
+use proptest::prelude::*;
+
+const COIN_CAP: u32 = 25;
+
+/// An event described by indexes, resolved against the state at the time it is applied so
+/// most generated events refer to real players, cards, items, choices and trades
+#[derive(Debug, Clone)]
+enum Action {
+    TurnPass {
+        player: Option<usize>,
+    },
+    Mulligan {
+        player: usize,
+        keep: bool,
+    },
+    ActivateItem {
+        player: Option<usize>,
+        item: usize,
+    },
+    BuyItem {
+        player: Option<usize>,
+        slot: usize,
+    },
+    DealDamage {
+        source: Option<usize>,
+        target: usize,
+        amount: u32,
+    },
+    ChoiceResponse {
+        player: usize,
+        option: usize,
+        bogus: bool,
+    },
+    ProposeTrade {
+        player: usize,
+        to: usize,
+        coins: u32,
+        loot: Option<usize>,
+        ask_coins: u32,
+    },
+    RespondToTrade {
+        player: Option<usize>,
+        trade: usize,
+        accept: bool,
+    },
+}
+
+/// None stands for the player the event is meant for (the active player, the receiver of a
+/// trade), so those events are accepted often enough to move the game along
+fn seat() -> impl Strategy<Value = Option<usize>> {
+    proptest::option::weighted(0.3, 0..4usize)
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        3 => seat().prop_map(|player| Action::TurnPass { player }),
+        1 => (0..4usize, any::<bool>()).prop_map(|(player, keep)| Action::Mulligan { player, keep }),
+        2 => (seat(), 0..3usize).prop_map(|(player, item)| Action::ActivateItem { player, item }),
+        1 => (seat(), 0..3usize).prop_map(|(player, slot)| Action::BuyItem { player, slot }),
+        3 => (proptest::option::of(0..4usize), 0..4usize, 0..4u32)
+            .prop_map(|(source, target, amount)| Action::DealDamage { source, target, amount }),
+        3 => (0..4usize, 0..5usize, proptest::bool::weighted(0.1))
+            .prop_map(|(player, option, bogus)| Action::ChoiceResponse { player, option, bogus }),
+        2 => (0..4usize, 0..4usize, 0..6u32, proptest::option::of(0..5usize), 0..4u32).prop_map(
+            |(player, to, coins, loot, ask_coins)| Action::ProposeTrade {
+                player,
+                to,
+                coins,
+                loot,
+                ask_coins,
+            }
+        ),
+        2 => (seat(), 0..3usize, any::<bool>())
+            .prop_map(|(player, trade, accept)| Action::RespondToTrade { player, trade, accept }),
+    ]
+}
+
+/// Ids 4 and up don't belong to anyone, so some events always target unknown players
+fn player_id(players: &[String], index: usize) -> String {
+    players
+        .get(index)
+        .cloned()
+        .unwrap_or_else(|| format!("ghost-{}", index))
+}
+
+fn resolve(action: &Action, state: &GameState, players: &[String]) -> GameEvent {
+    match action {
+        Action::TurnPass { player } => GameEvent::TurnPass {
+            player_id: player.map_or_else(
+                || state.turn_order.active_player_id.clone(),
+                |player| player_id(players, player),
+            ),
+        },
+        Action::Mulligan { player, keep } => GameEvent::MulliganDecision {
+            player_id: player_id(players, *player),
+            keep: *keep,
+        },
+        Action::ActivateItem { player, item } => {
+            let player_id = player.map_or_else(
+                || state.turn_order.active_player_id.clone(),
+                |player| player_id(players, player),
+            );
+            let item_id = state
+                .board
+                .players_items
+                .get(&player_id)
+                .and_then(|items| items.get(*item))
+                .map_or_else(|| "missing-item".to_string(), |item| item.entity_id.clone());
+            GameEvent::ActivateItem { player_id, item_id }
+        }
+        Action::BuyItem { player, slot } => GameEvent::BuyItem {
+            player_id: player.map_or_else(
+                || state.turn_order.active_player_id.clone(),
+                |player| player_id(players, player),
+            ),
+            shop_index: *slot,
+        },
+        Action::DealDamage {
+            source,
+            target,
+            amount,
+        } => GameEvent::DealDamage {
+            source_id: source.map(|source| player_id(players, source)),
+            target_id: player_id(players, *target),
+            amount: *amount,
+        },
+        Action::ChoiceResponse {
+            player,
+            option,
+            bogus,
+        } => match state.pending_choice() {
+            Some(choice) if !bogus => GameEvent::ChoiceResponse {
+                player_id: choice.player_id.clone(),
+                choice_id: choice.choice_id.clone(),
+                option_id: choice
+                    .options
+                    .get(option % choice.options.len().max(1))
+                    .map_or_else(String::new, |option| option.option_id.clone()),
+            },
+            _ => GameEvent::ChoiceResponse {
+                player_id: player_id(players, *player),
+                choice_id: "missing-choice".to_string(),
+                option_id: "missing-option".to_string(),
+            },
+        },
+        Action::ProposeTrade {
+            player,
+            to,
+            coins,
+            loot,
+            ask_coins,
+        } => {
+            let player_id = player_id(players, *player);
+            let loot = loot
+                .and_then(|index| {
+                    state
+                        .board
+                        .players_hands
+                        .get(&player_id)
+                        .and_then(|hand| hand.get(index))
+                        .map(|card| card.entity_id.clone())
+                })
+                .into_iter()
+                .collect();
+            GameEvent::ProposeTrade {
+                player_id,
+                to_player: self::player_id(players, *to),
+                offer: TradeOffer {
+                    coins: *coins,
+                    loot,
+                },
+                request: TradeOffer {
+                    coins: *ask_coins,
+                    loot: Vec::new(),
+                },
+            }
+        }
+        Action::RespondToTrade {
+            player,
+            trade,
+            accept,
+        } => {
+            let trade = state.pending_trades.get(*trade);
+            GameEvent::RespondToTrade {
+                player_id: match (player, trade) {
+                    (None, Some(trade)) => trade.to_player.clone(),
+                    (player, _) => player_id(players, player.unwrap_or(0)),
+                },
+                trade_id: trade.map_or_else(
+                    || "missing-trade".to_string(),
+                    |trade| trade.trade_id.clone(),
+                ),
+                accept: *accept,
+            }
+        }
+    }
+}
+
+struct Totals {
+    loot: usize,
+    treasure: usize,
+}
+
+fn totals(state: &GameState) -> Totals {
+    let board = &state.board;
+    Totals {
+        loot: board.loot_deck.len()
+            + board.loot_discard.len()
+            + board.players_hands.values().map(Vec::len).sum::<usize>(),
+        treasure: board.treasure_deck.len()
+            + board.treasure_discard.len()
+            + board.shop.len()
+            + board.players_items.values().map(Vec::len).sum::<usize>(),
+    }
+}
+
+fn check_invariants(
+    state: &GameState,
+    previous: &GameState,
+    initial: &Totals,
+    players: &[String],
+    coin_cap: Option<u32>,
+) -> Result<(), TestCaseError> {
+    let board = &state.board;
+
+    // Cards are only ever moved between zones, never created or lost
+    let now = totals(state);
+    prop_assert_eq!(now.loot, initial.loot, "loot cards not conserved");
+    prop_assert_eq!(
+        now.treasure,
+        initial.treasure,
+        "treasure cards not conserved"
+    );
+
+    let mut entity_ids = HashSet::new();
+    let loot = board
+        .loot_deck
+        .iter()
+        .chain(&board.loot_discard)
+        .chain(board.players_hands.values().flatten())
+        .map(|card| &card.entity_id);
+    let treasure = board
+        .treasure_deck
+        .iter()
+        .chain(&board.treasure_discard)
+        .chain(&board.shop)
+        .chain(board.players_items.values().flatten())
+        .map(|item| &item.entity_id);
+    for entity_id in loot.chain(treasure) {
+        prop_assert!(
+            entity_ids.insert(entity_id),
+            "card {} is in two places",
+            entity_id
+        );
+    }
+
+    // Health and coins stay within bounds, and a player is dead exactly when out of health
+    for (player_id, player) in &board.players {
+        prop_assert!(
+            player.current_health <= player.max_health,
+            "{} over max health",
+            player_id
+        );
+        prop_assert_eq!(
+            player.dead,
+            player.current_health == 0,
+            "{} dead flag out of sync",
+            player_id
+        );
+        if let Some(coin_cap) = coin_cap {
+            prop_assert!(player.coins <= coin_cap, "{} over the coin cap", player_id);
+        }
+    }
+    for item in board.players_items.values().flatten() {
+        if let ItemActivation::Charge { charges } = item.activation {
+            prop_assert!(item.charges <= charges, "{} over its charges", item.name);
+        }
+    }
+
+    // The seating never changes and the turn only moves forward
+    prop_assert_eq!(&state.turn_order.order, &players.to_vec());
+    prop_assert!(players.contains(&state.turn_order.active_player_id));
+    prop_assert!(players.contains(&state.current_priority_player));
+    let turns = state.turn_order.get_turn_counter() - previous.turn_order.get_turn_counter();
+    prop_assert!(turns <= 1, "{} turns passed on one event", turns);
+    let expected_active = &players[state.turn_order.get_turn_counter() as usize % players.len()];
+    prop_assert_eq!(&state.turn_order.active_player_id, expected_active);
+    if turns == 1 {
+        prop_assert_eq!(&state.current_phase, &TurnPhases::UntapStartStep);
+    }
+
+    // Only players in the game have hands, items or open choices and trades
+    for player_id in board.players_hands.keys().chain(board.players_items.keys()) {
+        prop_assert!(players.contains(player_id));
+    }
+    for choice in &state.pending_choices {
+        prop_assert!(players.contains(&choice.player_id));
+        prop_assert!(!choice.options.is_empty(), "choice without options");
+    }
+    for trade in &state.pending_trades {
+        prop_assert!(players.contains(&trade.from_player) && players.contains(&trade.to_player));
+        prop_assert_ne!(&trade.from_player, &trade.to_player);
+    }
+    Ok(())
+}
+
+// The state as JSON with its sets sorted, since two equal HashSets can list their items in
+// different orders
+fn comparable(state: &GameState) -> serde_json::Value {
+    let mut value = serde_json::to_value(state).unwrap();
+    for set in ["pending_mulligans", "players_passed_priority"] {
+        if let Some(serde_json::Value::Array(items)) = value.get_mut(set) {
+            items.sort_by_key(|item| item.to_string());
+        }
+    }
+    value
+}
+
+fn run_game(
+    seed: u64,
+    player_count: usize,
+    mulligan: bool,
+    coin_cap: bool,
+    actions: Vec<Action>,
+) -> Result<(), TestCaseError> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let players: Vec<String> = (0..player_count).map(|i| format!("player-{}", i)).collect();
+        let mut game = common::game(&players).seed(seed);
+        if mulligan {
+            game = game.setup(|coordinator| coordinator.enable_mulligan(0));
+        }
+        let coin_cap = coin_cap.then_some(COIN_CAP);
+        if let Some(coin_cap) = coin_cap {
+            game = game.setup(move |coordinator| coordinator.enable_coin_cap(coin_cap));
+        }
+        let (mut coordinator, _cmd_receiver) = game.start().await;
+
+        let initial = totals(coordinator.game_state());
+        check_invariants(
+            coordinator.game_state(),
+            coordinator.game_state(),
+            &initial,
+            &players,
+            coin_cap,
+        )?;
+
+        for action in &actions {
+            let previous = coordinator.game_state().clone();
+            let event = resolve(action, &previous, &players);
+            let result = coordinator.handle_event(event.clone()).await;
+            let state = coordinator.game_state();
+            if let Err(e) = result {
+                // A rejected event leaves the game exactly as it was
+                prop_assert_eq!(
+                    serde_json::to_value(state).unwrap(),
+                    serde_json::to_value(&previous).unwrap(),
+                    "{:?} was rejected ({}) but changed the state",
+                    event,
+                    e
+                );
+            }
+            check_invariants(state, &previous, &initial, &players, coin_cap)?;
+        }
+
+        // The event stream alone rebuilds the same game
+        let rebuilt = coordinator.rebuild_state().unwrap();
+        prop_assert_eq!(comparable(&rebuilt), comparable(coordinator.game_state()));
+        Ok(())
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn random_events_keep_the_board_consistent(
+        seed in any::<u64>(),
+        player_count in 2..=4usize,
+        mulligan in any::<bool>(),
+        coin_cap in any::<bool>(),
+        actions in proptest::collection::vec(action(), 1..80),
+    ) {
+        run_game(seed, player_count, mulligan, coin_cap, actions)?;
+    }
+}
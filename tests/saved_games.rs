@@ -0,0 +1,329 @@
+//! Running games are written out whole at checkpoints only; in between, each action appends
+//! what it added. Loading a game catches its checkpoint up with that progress and the game
+//! comes back rebuilt from its events, also on a server started after the one it was played on.
+
+mod common;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use isaac_four_souls::game::event_log::LogMark;
+use isaac_four_souls::game::game_coordinator::{GameCoordinator, GameEvent};
+use isaac_four_souls::game::game_state::GameState;
+use isaac_four_souls::game::persistence::{
+    FileGameStore, GameProgress, GameStore, MemoryGameStore, SavedGame,
+};
+use isaac_four_souls::game::rules::GameSpeed;
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls::network::messenger::Messenger;
+use isaac_four_souls::network::room::RoomOptions;
+use isaac_four_souls::network::server::WebsocketServerBuilder;
+use isaac_four_souls::{AppResult, ConnectionCommand, ServerConfig};
+use isaac_four_souls_client::Client;
+use tokio::sync::mpsc;
+
+use common::{connect, no_countdown, server_with, stoppable_server_with, within};
+
+async fn game() -> GameCoordinator {
+    common::game(&["alice", "bob"]).start().await.0
+}
+
+fn checkpoint(coordinator: &GameCoordinator, checkpoint: u64) -> SavedGame {
+    SavedGame {
+        game_id: "cellar".to_string(),
+        game_state: coordinator.game_state().clone(),
+        replay: coordinator.replay().clone(),
+        sessions: HashMap::new(),
+        rated_players: HashMap::new(),
+        stats: coordinator.stats().clone(),
+        event_log: coordinator.event_log().clone(),
+        events: coordinator.events().clone(),
+        speed: GameSpeed::default(),
+        spectator_delay_secs: 0,
+        checkpoint,
+    }
+}
+
+/// What the coordinator added since `saved`, the way the game actor appends it
+fn progress(coordinator: &GameCoordinator, saved: &SavedGame, log: &LogMark) -> GameProgress {
+    let replay = coordinator.replay();
+    GameProgress {
+        checkpoint: saved.checkpoint,
+        events: coordinator.events().since(saved.events.last_seq()).to_vec(),
+        replay_from: saved.replay.events.len(),
+        replay_events: replay.events[saved.replay.events.len()..].to_vec(),
+        signatures_from: saved.replay.signatures.len(),
+        signatures: replay.signatures[saved.replay.signatures.len()..].to_vec(),
+        stats: coordinator.stats().clone(),
+        log: coordinator.event_log().progress_since(log),
+    }
+}
+
+async fn pass_turn(coordinator: &mut GameCoordinator) {
+    let player_id = coordinator.game_state().turn_order.active_player_id.clone();
+    coordinator
+        .handle_event(GameEvent::TurnPass { player_id })
+        .await
+        .unwrap();
+}
+
+fn restored(saved_game: SavedGame) -> GameState {
+    let (cmd_sender, _) = mpsc::unbounded_channel::<ConnectionCommand>();
+    GameCoordinator::restore(
+        saved_game.game_state,
+        saved_game.replay,
+        saved_game.stats,
+        saved_game.event_log,
+        saved_game.events,
+        Messenger::new(cmd_sender),
+        None,
+    )
+    .unwrap()
+    .game_state()
+    .clone()
+}
+
+// Sets serialize in no particular order, so they're sorted before comparing
+fn comparable(state: &GameState) -> serde_json::Value {
+    let mut value = serde_json::to_value(state).unwrap();
+    for set in ["pending_mulligans", "players_passed_priority"] {
+        if let Some(serde_json::Value::Array(items)) = value.get_mut(set) {
+            items.sort_by_key(|item| item.to_string());
+        }
+    }
+    value
+}
+
+fn assert_same_game(restored: &GameState, live: &GameState) {
+    assert_eq!(comparable(restored), comparable(live));
+}
+
+fn store_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ifs-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test]
+async fn a_checkpoint_is_caught_up_with_appended_progress() {
+    let mut coordinator = game().await;
+    let store = MemoryGameStore::new();
+    let mut saved = checkpoint(&coordinator, 1);
+    store.save(&saved).unwrap();
+
+    for _ in 0..3 {
+        let log = coordinator.event_log().mark();
+        pass_turn(&mut coordinator).await;
+        let progress = progress(&coordinator, &saved, &log);
+        store.append("cellar", &progress).unwrap();
+        saved.catch_up(progress);
+    }
+
+    let loaded = store.load_all().unwrap().remove(0);
+    assert_eq!(loaded.events.len(), coordinator.events().len());
+    assert_eq!(
+        loaded.replay.events.len(),
+        coordinator.replay().events.len()
+    );
+    assert_eq!(
+        loaded.event_log.since(None).len(),
+        coordinator.event_log().since(None).len()
+    );
+    // The checkpoint's own state is three turns behind, the restored game isn't
+    assert_ne!(
+        loaded.game_state.turn_order.active_player_id,
+        coordinator.game_state().turn_order.active_player_id
+    );
+    assert_same_game(&restored(loaded), coordinator.game_state());
+}
+
+#[tokio::test]
+async fn progress_from_an_older_checkpoint_is_skipped() {
+    let mut coordinator = game().await;
+    let saved = checkpoint(&coordinator, 1);
+    let log = coordinator.event_log().mark();
+    pass_turn(&mut coordinator).await;
+    let stale = progress(&coordinator, &saved, &log);
+
+    // A newer checkpoint already holds that turn
+    let mut newer = checkpoint(&coordinator, 2);
+    newer.catch_up(stale);
+    assert_eq!(newer.events.len(), coordinator.events().len());
+    assert_eq!(newer.replay.events.len(), coordinator.replay().events.len());
+    assert_same_game(&restored(newer), coordinator.game_state());
+}
+
+#[tokio::test]
+async fn a_file_store_survives_a_cut_off_append() {
+    let dir = store_dir("saved-games");
+    let mut coordinator = game().await;
+    let saved = checkpoint(&coordinator, 1);
+    let log = coordinator.event_log().mark();
+    {
+        let store = FileGameStore::new(&dir).unwrap();
+        store.save(&saved).unwrap();
+        pass_turn(&mut coordinator).await;
+        store
+            .append("cellar", &progress(&coordinator, &saved, &log))
+            .unwrap();
+    }
+    // The server went down halfway through writing the next line
+    let journal = dir.join("cellar.journal");
+    let mut contents = std::fs::read_to_string(&journal).unwrap();
+    contents.push_str("{\"checkpoint\":1,\"ev");
+    std::fs::write(&journal, contents).unwrap();
+
+    let store = FileGameStore::new(&dir).unwrap();
+    let mut games = store.load_all().unwrap();
+    assert_eq!(games.len(), 1);
+    assert_same_game(&restored(games.remove(0)), coordinator.game_state());
+
+    // A new checkpoint starts a new journal
+    store.save(&checkpoint(&coordinator, 2)).unwrap();
+    assert!(!journal.exists());
+    store.remove("cellar").unwrap();
+    assert!(store.load_all().unwrap().is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// Counts how a game is written out
+#[derive(Default)]
+struct CountingStore {
+    games: MemoryGameStore,
+    saves: AtomicUsize,
+    appends: AtomicUsize,
+}
+
+impl GameStore for CountingStore {
+    fn save(&self, game: &SavedGame) -> AppResult<()> {
+        self.saves.fetch_add(1, Ordering::SeqCst);
+        self.games.save(game)
+    }
+
+    fn append(&self, game_id: &str, progress: &GameProgress) -> AppResult<()> {
+        self.appends.fetch_add(1, Ordering::SeqCst);
+        self.games.append(game_id, progress)
+    }
+
+    fn remove(&self, game_id: &str) -> AppResult<()> {
+        self.games.remove(game_id)
+    }
+
+    fn load_all(&self) -> AppResult<Vec<SavedGame>> {
+        self.games.load_all()
+    }
+}
+
+#[tokio::test]
+async fn actions_append_instead_of_saving_the_whole_game() {
+    let store = Arc::new(CountingStore::default());
+    let game_store = store.clone();
+    let url = server_with(|builder| no_countdown(builder).game_store(game_store)).await;
+    let mut alice = connect(&url).await;
+    let mut bob = connect(&url).await;
+    let seat = within(alice.create_room("Cellar", "alice", RoomOptions::default()))
+        .await
+        .unwrap();
+    within(bob.join(&seat.room_id, "bob")).await.unwrap();
+    within(alice.ready()).await.unwrap();
+    within(bob.ready()).await.unwrap();
+    for client in [&mut alice, &mut bob] {
+        within(client.send(ClientMessage::MulliganDecision { keep: true }))
+            .await
+            .unwrap();
+    }
+
+    within(async {
+        while store.appends.load(Ordering::SeqCst) < 2 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        Ok(())
+    })
+    .await
+    .unwrap();
+    // Only the game's start was written out in full
+    assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+    let saved = store.load_all().unwrap().remove(0);
+    assert!(restored(saved).pending_mulligans.is_empty());
+}
+
+/// The player's view of the game, comparable across servers
+async fn simple_state(client: &mut Client) -> serde_json::Value {
+    within(client.send(ClientMessage::GetSimpleState))
+        .await
+        .unwrap();
+    match within(client.wait_for(|response| matches!(response, ServerResponse::SimpleState { .. })))
+        .await
+        .unwrap()
+    {
+        ServerResponse::SimpleState { state } => serde_json::to_value(state).unwrap(),
+        other => panic!("Expected the game's state, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn a_game_carries_on_on_the_next_server() {
+    let dir = store_dir("restart");
+    let configure = |builder: WebsocketServerBuilder| {
+        let config = ServerConfig {
+            game_store_dir: Some(dir.clone()),
+            ..ServerConfig::default()
+        };
+        no_countdown(builder.config(config))
+    };
+
+    let first = stoppable_server_with(configure).await;
+    let mut alice = connect(&first.url).await;
+    let mut bob = connect(&first.url).await;
+    let seat = within(alice.create_room("Cellar", "alice", RoomOptions::default()))
+        .await
+        .unwrap();
+    within(bob.join(&seat.room_id, "bob")).await.unwrap();
+    within(alice.ready()).await.unwrap();
+    within(bob.ready()).await.unwrap();
+    let session_token = match within(
+        alice.wait_for(|response| matches!(response, ServerResponse::GameSession { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::GameSession { session_token, .. } => session_token,
+        other => panic!("Expected a session, got {:?}", other),
+    };
+    for client in [&mut alice, &mut bob] {
+        within(client.send(ClientMessage::MulliganDecision { keep: true }))
+            .await
+            .unwrap();
+    }
+    // Played on past the game's first checkpoint
+    let mut before = simple_state(&mut alice).await;
+    let active = if before["your_turn"] == true {
+        &mut alice
+    } else {
+        &mut bob
+    };
+    within(active.send(ClientMessage::TurnPass)).await.unwrap();
+    while before["turn"] == 1 {
+        before = simple_state(&mut alice).await;
+    }
+    drop((alice, bob));
+    first.stop().await;
+
+    let second = stoppable_server_with(configure).await;
+    let mut alice = connect(&second.url).await;
+    within(alice.send(ClientMessage::ResumeSession {
+        session_token: session_token.into(),
+    }))
+    .await
+    .unwrap();
+    within(alice.wait_for(|response| matches!(response, ServerResponse::SessionResumed { .. })))
+        .await
+        .unwrap();
+    assert_eq!(simple_state(&mut alice).await, before);
+
+    drop(alice);
+    second.stop().await;
+    let _ = std::fs::remove_dir_all(&dir);
+}
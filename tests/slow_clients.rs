@@ -0,0 +1,42 @@
+//! A client that stops reading fills its send queue and is cut off, socket and all, even
+//! while a write to it is stuck on the full TCP window.
+
+mod common;
+
+use std::time::Duration;
+
+use futures_util::SinkExt;
+use isaac_four_souls::config::OverflowPolicy;
+use isaac_four_souls::ServerConfig;
+use tokio::net::TcpSocket;
+use tokio_tungstenite::client_async;
+use tokio_tungstenite::tungstenite::Message;
+
+#[tokio::test]
+async fn a_client_that_never_reads_is_disconnected() {
+    let mut config = ServerConfig::default();
+    config.outbound.queue_size = 8;
+    config.outbound.on_overflow = OverflowPolicy::Disconnect;
+    let url = common::server_with(|builder| builder.config(config)).await;
+
+    // A small receive window so the server's writes back up quickly
+    let socket = TcpSocket::new_v4().unwrap();
+    socket.set_recv_buffer_size(4096).unwrap();
+    let address = url.trim_start_matches("ws://").parse().unwrap();
+    let stream = socket.connect(address).await.unwrap();
+    let (mut socket, _) = client_async(&url, stream).await.unwrap();
+
+    // Ask for the card catalog over and over without ever reading an answer. Once the
+    // server gives up on the client it closes the socket and the requests stop going through.
+    let request = Message::Text(r#"{"GetCardCatalog":{"known_version":null}}"#.to_string());
+    let cut_off = tokio::time::timeout(Duration::from_secs(30), async {
+        loop {
+            if socket.send(request.clone()).await.is_err() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    })
+    .await;
+    assert!(cut_off.is_ok(), "The server kept the socket open");
+}
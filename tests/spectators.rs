@@ -0,0 +1,145 @@
+//! Spectators see the public side of a game, and in rooms with a spectator delay they see it
+//! that much later than the players, who still get everything as it happens.
+
+mod common;
+
+use std::time::Duration;
+
+use isaac_four_souls::game::game_coordinator::{GameCoordinator, GameEvent};
+use isaac_four_souls::network::room::RoomOptions;
+use isaac_four_souls::{AppError, ConnectionCommand};
+use tokio::sync::mpsc;
+
+const PLAYERS: [&str; 2] = ["alice", "bob"];
+const SPECTATOR: &str = "watcher";
+
+async fn game(delay: Duration) -> (GameCoordinator, mpsc::UnboundedReceiver<ConnectionCommand>) {
+    common::game(&PLAYERS)
+        .setup(move |coordinator| coordinator.set_spectator_delay(delay))
+        .start()
+        .await
+}
+
+/// Every message sent so far, as (recipient, text)
+fn delivered(commands: &mut mpsc::UnboundedReceiver<ConnectionCommand>) -> Vec<(String, String)> {
+    let mut delivered = Vec::new();
+    while let Ok(command) = commands.try_recv() {
+        match command {
+            ConnectionCommand::SendToPlayer {
+                connection_id,
+                message,
+            } => delivered.push((connection_id, message.to_string())),
+            ConnectionCommand::SendToPlayers {
+                connections_id,
+                message,
+            } => {
+                for connection_id in connections_id {
+                    delivered.push((connection_id, message.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+    delivered
+}
+
+fn to(recipient: &str, messages: &[(String, String)]) -> Vec<String> {
+    messages
+        .iter()
+        .filter(|(to, _)| to == recipient)
+        .map(|(_, text)| text.clone())
+        .collect()
+}
+
+/// What went to every player, in the order they got it
+fn broadcast(messages: &[(String, String)]) -> Vec<String> {
+    let alice = to("alice", messages);
+    to("bob", messages)
+        .into_iter()
+        .filter(|text| alice.contains(text))
+        .collect()
+}
+
+async fn pass_turn(coordinator: &mut GameCoordinator) {
+    let active = coordinator.game_state().turn_order.active_player_id.clone();
+    coordinator
+        .handle_event(GameEvent::TurnPass { player_id: active })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn spectators_see_the_public_board_but_no_hands() {
+    let (mut coordinator, mut commands) = game(Duration::ZERO).await;
+    delivered(&mut commands);
+
+    coordinator.add_spectator(SPECTATOR.to_string());
+    pass_turn(&mut coordinator).await;
+
+    let messages = delivered(&mut commands);
+    let watched = to(SPECTATOR, &messages);
+    assert!(watched[0].contains("PublicBoardState"));
+    assert!(watched
+        .iter()
+        .all(|text| !text.contains("PrivateBoardState")));
+    // Whatever every player got, the spectator got too
+    for text in broadcast(&messages) {
+        assert!(
+            watched.contains(&text),
+            "{} never reached the spectator",
+            text
+        );
+    }
+    for hand in coordinator.game_state().board.players_hands.values() {
+        for card in hand {
+            assert!(watched.iter().all(|text| !text.contains(&card.entity_id)));
+        }
+    }
+}
+
+#[tokio::test]
+async fn a_spectator_delay_holds_back_only_what_spectators_get() {
+    let delay = Duration::from_millis(300);
+    let (mut coordinator, mut commands) = game(delay).await;
+    delivered(&mut commands);
+
+    coordinator.add_spectator(SPECTATOR.to_string());
+    pass_turn(&mut coordinator).await;
+    let now = delivered(&mut commands);
+    assert!(!to("bob", &now).is_empty());
+    assert!(to(SPECTATOR, &now).is_empty());
+
+    tokio::time::sleep(delay * 2).await;
+    let later = delivered(&mut commands);
+    let watched = to(SPECTATOR, &later);
+    assert!(watched[0].contains("PublicBoardState"));
+    // Held back in the order it happened
+    assert!(watched.ends_with(&broadcast(&now)));
+    assert!(to("bob", &later).is_empty());
+}
+
+#[tokio::test]
+async fn spectators_can_stop_watching() {
+    let (mut coordinator, mut commands) = game(Duration::ZERO).await;
+    coordinator.add_spectator(SPECTATOR.to_string());
+    assert!(coordinator.remove_spectator(SPECTATOR));
+    assert!(!coordinator.remove_spectator(SPECTATOR));
+    delivered(&mut commands);
+
+    pass_turn(&mut coordinator).await;
+    assert!(to(SPECTATOR, &delivered(&mut commands)).is_empty());
+}
+
+#[test]
+fn spectator_delays_are_capped() {
+    let options: RoomOptions = serde_json::from_str("{}").unwrap();
+    assert_eq!(options.spectator_delay_secs, 0);
+    let options = RoomOptions {
+        spectator_delay_secs: RoomOptions::MAX_SPECTATOR_DELAY_SECS + 1,
+        ..RoomOptions::default()
+    };
+    assert!(matches!(
+        options.validate(),
+        Err(AppError::InvalidRuleSet { .. })
+    ));
+}
@@ -0,0 +1,234 @@
+//! A tournament seats each pairing in a room of its own, hands out byes, advances whoever won
+//! and keeps everyone up to date with the bracket until there is a champion.
+
+mod common;
+
+use std::time::Duration;
+
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls::network::room::RoomOptions;
+use isaac_four_souls::network::tournament::{TournamentBracket, TournamentState};
+use isaac_four_souls::ServerConfig;
+use isaac_four_souls_client::{Client, ClientError};
+
+use common::connect;
+
+async fn server() -> String {
+    // Conceding is only refused while opening hands are being decided
+    let mut config = ServerConfig::default();
+    config.game.mulligan = false;
+    common::server_with(|builder| builder.config(config)).await
+}
+
+async fn next(
+    client: &mut Client,
+    pick: impl FnMut(&ServerResponse) -> bool,
+) -> Result<ServerResponse, ClientError> {
+    tokio::time::timeout(Duration::from_secs(10), client.wait_for(pick))
+        .await
+        .expect("Timed out waiting for the server")
+}
+
+/// The first update matching `done`, skipping the ones sent on the way there
+async fn bracket(
+    client: &mut Client,
+    done: impl Fn(&TournamentBracket) -> bool,
+) -> TournamentBracket {
+    let response = next(client, |response| {
+        matches!(response, ServerResponse::TournamentUpdate { bracket } if done(bracket))
+    })
+    .await
+    .unwrap();
+    match response {
+        ServerResponse::TournamentUpdate { bracket } => bracket,
+        _ => unreachable!(),
+    }
+}
+
+async fn concede_once_seated(client: &mut Client) {
+    next(client, |response| {
+        matches!(response, ServerResponse::GameJoined { .. })
+    })
+    .await
+    .unwrap();
+    client.send(ClientMessage::Concede).await.unwrap();
+}
+
+/// Create a tournament and register each of `entrants` under their name
+async fn open_tournament(organizer: &mut Client, entrants: &mut [(Client, &str)]) -> String {
+    organizer
+        .send(ClientMessage::CreateTournament {
+            name: "Basement Cup".to_string(),
+            options: RoomOptions::default(),
+        })
+        .await
+        .unwrap();
+    let ServerResponse::TournamentCreated { tournament_id } = next(organizer, |response| {
+        matches!(response, ServerResponse::TournamentCreated { .. })
+    })
+    .await
+    .unwrap() else {
+        unreachable!()
+    };
+    for (client, name) in entrants.iter_mut() {
+        client
+            .send(ClientMessage::RegisterForTournament {
+                tournament_id: tournament_id.clone(),
+                player_name: name.to_string(),
+            })
+            .await
+            .unwrap();
+    }
+    let count = entrants.len();
+    bracket(organizer, |bracket| bracket.entrants.len() == count).await;
+    tournament_id
+}
+
+#[tokio::test]
+async fn a_bracket_is_played_out_to_a_champion() {
+    let url = server().await;
+    let mut organizer = connect(&url).await;
+    let mut entrants = Vec::new();
+    for name in ["alice", "bob", "carol"] {
+        entrants.push((connect(&url).await, name));
+    }
+    let tournament_id = open_tournament(&mut organizer, &mut entrants).await;
+    let [(mut alice, _), _bob, (mut carol, _)]: [(Client, &str); 3] =
+        entrants.try_into().ok().unwrap();
+
+    // Only the organizer starts it
+    alice
+        .send(ClientMessage::StartTournament {
+            tournament_id: tournament_id.clone(),
+        })
+        .await
+        .unwrap();
+    let refused = next(&mut alice, |response| {
+        matches!(response, ServerResponse::Pong)
+    })
+    .await;
+    assert!(matches!(
+        refused,
+        Err(ClientError::Server { error_type, .. }) if error_type == "PermissionDenied"
+    ));
+
+    organizer
+        .send(ClientMessage::StartTournament {
+            tournament_id: tournament_id.clone(),
+        })
+        .await
+        .unwrap();
+    let first_round = bracket(&mut organizer, |bracket| {
+        bracket
+            .rounds
+            .first()
+            .is_some_and(|round| round[0].room_id.is_some())
+    })
+    .await;
+    assert_eq!(first_round.state, TournamentState::Running);
+    let round = &first_round.rounds[0];
+    assert_eq!(round.len(), 2);
+    assert_eq!(round[0].players, ["alice", "bob"]);
+    assert_eq!(round[1].players, ["carol"]);
+    assert_eq!(round[1].winner.as_deref(), Some("carol"));
+
+    // Too late to join
+    let mut dave = connect(&url).await;
+    dave.send(ClientMessage::RegisterForTournament {
+        tournament_id: tournament_id.clone(),
+        player_name: "dave".to_string(),
+    })
+    .await
+    .unwrap();
+    let refused = next(&mut dave, |response| {
+        matches!(response, ServerResponse::Pong)
+    })
+    .await;
+    assert!(matches!(
+        refused,
+        Err(ClientError::Server { error_type, .. }) if error_type == "InvalidTournament"
+    ));
+
+    concede_once_seated(&mut alice).await;
+    let second_round = bracket(&mut organizer, |bracket| {
+        bracket
+            .rounds
+            .get(1)
+            .is_some_and(|round| round[0].room_id.is_some())
+    })
+    .await;
+    assert_eq!(second_round.rounds[0][0].winner.as_deref(), Some("bob"));
+    assert_eq!(second_round.rounds[1][0].players, ["bob", "carol"]);
+
+    concede_once_seated(&mut carol).await;
+    let finished = bracket(&mut organizer, |bracket| {
+        bracket.state == TournamentState::Finished
+    })
+    .await;
+    assert_eq!(finished.champion.as_deref(), Some("bob"));
+    assert_eq!(finished.rounds.len(), 2);
+
+    // The bracket can still be looked up once it's over
+    bracket(&mut dave, |bracket| {
+        bracket.state == TournamentState::Finished
+    })
+    .await;
+    dave.send(ClientMessage::GetTournament { tournament_id })
+        .await
+        .unwrap();
+    let looked_up = bracket(&mut dave, |bracket| {
+        bracket.state == TournamentState::Finished
+    })
+    .await;
+    assert_eq!(looked_up.champion.as_deref(), Some("bob"));
+}
+
+#[tokio::test]
+async fn first_round_matches_run_side_by_side() {
+    let url = server().await;
+    let mut organizer = connect(&url).await;
+    let mut entrants = Vec::new();
+    for name in ["alice", "bob", "carol", "dave"] {
+        entrants.push((connect(&url).await, name));
+    }
+    let tournament_id = open_tournament(&mut organizer, &mut entrants).await;
+
+    organizer
+        .send(ClientMessage::StartTournament { tournament_id })
+        .await
+        .unwrap();
+    let first_round = bracket(&mut organizer, |bracket| {
+        bracket
+            .rounds
+            .first()
+            .is_some_and(|round| round.iter().all(|pairing| pairing.room_id.is_some()))
+    })
+    .await;
+    let round = &first_round.rounds[0];
+    assert_eq!(round[0].players, ["alice", "bob"]);
+    assert_eq!(round[1].players, ["carol", "dave"]);
+    assert_ne!(round[0].room_id, round[1].room_id);
+
+    // Both matches are seated before either ends
+    let [(mut alice, _), (mut bob, _), _carol, (mut dave, _)]: [(Client, &str); 4] =
+        entrants.try_into().ok().unwrap();
+    concede_once_seated(&mut alice).await;
+    concede_once_seated(&mut dave).await;
+    let final_round = bracket(&mut organizer, |bracket| {
+        bracket
+            .rounds
+            .get(1)
+            .is_some_and(|round| round[0].room_id.is_some())
+    })
+    .await;
+    assert_eq!(final_round.rounds[0][0].winner.as_deref(), Some("bob"));
+    assert_eq!(final_round.rounds[0][1].winner.as_deref(), Some("carol"));
+    assert_eq!(final_round.rounds[1][0].players, ["bob", "carol"]);
+
+    concede_once_seated(&mut bob).await;
+    let finished = bracket(&mut organizer, |bracket| {
+        bracket.state == TournamentState::Finished
+    })
+    .await;
+    assert_eq!(finished.champion.as_deref(), Some("carol"));
+}
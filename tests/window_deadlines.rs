@@ -0,0 +1,219 @@
+//! Turn, priority and choice windows go out with the moment they run out, and the game holds
+//! players to that same moment: a client that missed the announcement asks for what's left
+//! with GetTimeRemaining and gets the deadline it would have been sent. A pause pushes them all
+//! back by as long as it lasted.
+
+mod common;
+
+use isaac_four_souls::game::deadlines::unix_millis;
+use isaac_four_souls::game::game_coordinator::{GameCoordinator, GameEvent};
+use isaac_four_souls::game::rules::{GameSpeed, RoomOptions, RuleSet, SpeedTimers};
+use isaac_four_souls::network::messages::{ClientMessage, ServerResponse};
+use isaac_four_souls::ConnectionCommand;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use common::{connect, no_countdown, server_with, within};
+
+const TIMERS: SpeedTimers = SpeedTimers {
+    turn_secs: 60,
+    priority_secs: 20,
+    choice_secs: 30,
+};
+
+async fn game(
+    rules: RuleSet,
+    timers: SpeedTimers,
+) -> (GameCoordinator, mpsc::UnboundedReceiver<ConnectionCommand>) {
+    common::game(&["alice", "bob"])
+        .rules(rules)
+        .setup(move |coordinator| coordinator.set_timers(timers))
+        .start()
+        .await
+}
+
+/// Every response sent so far with the given name, oldest first
+fn sent(commands: &mut mpsc::UnboundedReceiver<ConnectionCommand>, name: &str) -> Vec<Value> {
+    let mut sent = Vec::new();
+    while let Ok(command) = commands.try_recv() {
+        let message = match command {
+            ConnectionCommand::SendToPlayer { message, .. }
+            | ConnectionCommand::SendToPlayers { message, .. } => message,
+            _ => continue,
+        };
+        let value: Value = serde_json::from_str(&message).unwrap();
+        if let Some(response) = value.get(name) {
+            sent.push(response.clone());
+        }
+    }
+    sent
+}
+
+// Deadlines are stamped while the test runs, so they're compared with some slack
+fn assert_about(deadline: &Value, expected: u64) {
+    let deadline = deadline.as_u64().expect("A deadline in unix millis");
+    assert!(
+        deadline.abs_diff(expected) < 2_000,
+        "Deadline {} is not about {}",
+        deadline,
+        expected
+    );
+}
+
+#[tokio::test]
+async fn turns_and_priority_go_out_with_their_deadlines() {
+    let (mut coordinator, mut commands) = game(RuleSet::default(), TIMERS).await;
+    let phase_changes = sent(&mut commands, "TurnPhaseChange");
+    let opening = phase_changes.last().expect("The first turn was announced");
+    assert_about(&opening["turn_deadline"], unix_millis() + 60_000);
+    assert_about(&opening["priority_deadline"], unix_millis() + 20_000);
+    let turn_deadline = opening["turn_deadline"].clone();
+
+    // Priority moving on is a new window, the turn keeps running
+    coordinator
+        .handle_event(GameEvent::PriorityPass {
+            player_id: "alice".to_string(),
+        })
+        .await
+        .unwrap();
+    let passed = sent(&mut commands, "TurnPhaseChange");
+    let passed = passed.last().unwrap();
+    assert_eq!(passed["turn_deadline"], turn_deadline);
+    assert_about(&passed["priority_deadline"], unix_millis() + 20_000);
+    assert_eq!(
+        coordinator.windows().turn_deadline(),
+        turn_deadline.as_u64()
+    );
+}
+
+#[tokio::test]
+async fn a_pause_pushes_the_open_windows_back() {
+    let (mut coordinator, mut commands) = game(RuleSet::default(), TIMERS).await;
+    let opening = sent(&mut commands, "TurnPhaseChange");
+    let turn_deadline = opening.last().unwrap()["turn_deadline"].as_u64().unwrap();
+    let priority_deadline = coordinator.windows().priority_deadline().unwrap();
+
+    coordinator.shift_windows(90_000);
+    assert_eq!(
+        coordinator.windows().turn_deadline(),
+        Some(turn_deadline + 90_000)
+    );
+    assert_eq!(
+        coordinator.windows().priority_deadline(),
+        Some(priority_deadline + 90_000)
+    );
+
+    // The turn announced again after the pause runs out when the game actor expects it to
+    coordinator
+        .handle_event(GameEvent::PriorityPass {
+            player_id: "alice".to_string(),
+        })
+        .await
+        .unwrap();
+    let passed = sent(&mut commands, "TurnPhaseChange");
+    assert_eq!(
+        passed.last().unwrap()["turn_deadline"].as_u64(),
+        Some(turn_deadline + 90_000)
+    );
+}
+
+#[tokio::test]
+async fn untimed_windows_have_no_deadline() {
+    let untimed = SpeedTimers {
+        turn_secs: 0,
+        priority_secs: 0,
+        choice_secs: 30,
+    };
+    let (mut coordinator, mut commands) = game(RuleSet::default(), untimed).await;
+    let active = coordinator.game_state().turn_order.active_player_id.clone();
+    sent(&mut commands, "TurnPhaseChange");
+    coordinator
+        .handle_event(GameEvent::TurnPass { player_id: active })
+        .await
+        .unwrap();
+    let next_turn = sent(&mut commands, "TurnPhaseChange");
+    let next_turn = next_turn.last().unwrap();
+    assert_eq!(next_turn["turn_deadline"], Value::Null);
+    assert_eq!(next_turn["priority_deadline"], Value::Null);
+}
+
+#[tokio::test]
+async fn choices_go_out_with_their_deadline() {
+    let rules = RuleSet {
+        hand_size_limit: Some(0),
+        ..RuleSet::default()
+    };
+    let (mut coordinator, mut commands) = game(rules, TIMERS).await;
+    let active = coordinator.game_state().turn_order.active_player_id.clone();
+    coordinator
+        .handle_event(GameEvent::TurnPass { player_id: active })
+        .await
+        .unwrap();
+    let choices = sent(&mut commands, "ChoiceRequired");
+    let choice = choices.first().expect("Discarding down to the hand limit");
+    assert_eq!(choice["timeout_secs"], 30);
+    assert_about(&choice["deadline"], unix_millis() + 30_000);
+    assert_eq!(
+        coordinator.windows().choice_deadline(),
+        choice["deadline"].as_u64()
+    );
+}
+
+#[tokio::test]
+async fn a_late_client_gets_the_deadline_it_was_sent() {
+    let url = server_with(no_countdown).await;
+    let mut alice = connect(&url).await;
+    let mut bob = connect(&url).await;
+    let options = RoomOptions {
+        speed: GameSpeed::Relaxed,
+        ..RoomOptions::default()
+    };
+    let seat = within(alice.create_room("Basement", "alice", options))
+        .await
+        .unwrap();
+    within(bob.join(&seat.room_id, "bob")).await.unwrap();
+    within(alice.ready()).await.unwrap();
+    within(bob.ready()).await.unwrap();
+
+    // The first turn opens once the opening hands are kept
+    for client in [&mut alice, &mut bob] {
+        within(client.send(ClientMessage::MulliganDecision { keep: true }))
+            .await
+            .unwrap();
+    }
+    let announced = within(
+        alice.wait_for(|response| matches!(response, ServerResponse::TurnPhaseChange { .. })),
+    )
+    .await
+    .unwrap();
+    let ServerResponse::TurnPhaseChange {
+        turn_deadline: Some(turn_deadline),
+        priority_deadline: Some(priority_deadline),
+        ..
+    } = announced
+    else {
+        panic!("Relaxed games time turns and priority: {:?}", announced);
+    };
+
+    within(alice.send(ClientMessage::GetTimeRemaining))
+        .await
+        .unwrap();
+    match within(
+        alice.wait_for(|response| matches!(response, ServerResponse::TimeRemaining { .. })),
+    )
+    .await
+    .unwrap()
+    {
+        ServerResponse::TimeRemaining {
+            turn: Some(turn),
+            priority: Some(priority),
+            choice: None,
+            paused: false,
+        } => {
+            assert!(turn.deadline.abs_diff(turn_deadline) < 50);
+            assert!(priority.deadline.abs_diff(priority_deadline) < 50);
+            assert!(priority.remaining_ms > 50_000 && priority.remaining_ms <= 60_000);
+        }
+        other => panic!("Expected the turn's and priority's time, got {:?}", other),
+    }
+}